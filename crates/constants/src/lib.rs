@@ -17,6 +17,127 @@ pub const COMBINED_NETWORK_MIN_PRIMARY_FAILURES: u64 = 5;
 /// the number of messages to send over the secondary network before re-attempting the (presumed down) primary network
 pub const COMBINED_NETWORK_PRIMARY_CHECK_INTERVAL: u64 = 5;
 
+/// the number of times a direct message is attempted before giving up on an acknowledgement
+pub const DIRECT_MESSAGE_MAX_RETRIES: u8 = 4;
+
+/// the number of seconds to wait for a direct message to be acknowledged before giving up on it
+pub const DIRECT_MESSAGE_ACK_TIMEOUT_SEC: u64 = 30;
+
+/// the maximum number of unacknowledged direct messages to a single peer
+pub const DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER: usize = 32;
+
+/// the number of shards the mempool is split into, keyed by the first byte of the transaction commitment
+pub const MEMPOOL_SHARDS: usize = 16;
+
+/// the number of decided block payloads kept around to serve block fetch requests
+pub const BLOCK_FETCH_CACHE_SIZE: usize = 64;
+
+/// the maximum number of payload bytes served to block fetch requests in a single view
+pub const BLOCK_FETCH_MAX_BYTES_PER_VIEW: usize = 16 * 1024 * 1024;
+
+/// the number of views to wait for a block fetch response before asking another DA member
+pub const BLOCK_FETCH_RETRY_VIEWS: u64 = 2;
+
+/// the number of DA members asked for a block before giving up on fetching it
+pub const BLOCK_FETCH_MAX_ATTEMPTS: usize = 3;
+
+/// the number of views to wait for the VID shares sampled for a payload before raising an alarm
+pub const AVAILABILITY_SAMPLING_TIMEOUT_VIEWS: u64 = 2;
+
+/// the number of views DA members keep the VID dispersals they received, to serve sampled shares
+pub const AVAILABILITY_SAMPLING_RETENTION_VIEWS: u64 = 10;
+
+/// the default maximum size of a single libp2p wire message, above which messages are chunked
+pub const DEFAULT_MAX_WIRE_MESSAGE_SIZE: usize = 256 * 1024;
+
+/// the maximum number of peers a libp2p peer store keeps, the least reliable are dropped first
+pub const PEER_STORE_MAX_PEERS: usize = 1000;
+
+/// the minimum interval, in seconds, between two saves of a libp2p peer store which changed
+pub const PEER_STORE_SAVE_INTERVAL_SECS: u64 = 30;
+
+/// the default number of transactions of each block reserved for system transactions
+pub const DEFAULT_SYSTEM_TRANSACTIONS_PER_BLOCK: usize = 16;
+
+/// the maximum number of transactions the system lane of a mempool holds; more are refused until
+/// some are decided or expire
+pub const MAX_SYSTEM_LANE_TRANSACTIONS: usize = 1024;
+
+/// the default maximum serialized size of a transaction accepted by a node
+pub const DEFAULT_MAX_TRANSACTION_SIZE: usize = 1024 * 1024;
+
+/// the maximum size of the application-defined extensions of a block header, in bytes
+pub const MAX_HEADER_EXTENSIONS_SIZE: usize = 4096;
+
+/// the maximum number of consensus messages held until the node reaches their view
+pub const FUTURE_VIEW_BUFFER_CAPACITY: usize = 1024;
+
+/// the default number of views before the current view whose consensus messages are still handled
+pub const DEFAULT_STALE_VIEW_GRACE_VIEWS: u64 = 2;
+
+/// the default number of views DA proposals are disseminated to the whole quorum for, once the DA
+/// committee failed to certify them
+pub const DEFAULT_DA_FALLBACK_VIEWS: u64 = 10;
+
+/// the number of views ahead of the current view for which consensus messages are held
+pub const FUTURE_VIEW_BUFFER_HORIZON: u64 = 3;
+
+/// the number of views before the last decided view for which certificates are checked for
+/// conflicts
+pub const SAFETY_MONITOR_HISTORY_VIEWS: u64 = 100;
+
+/// the number of views without a decide after which a timing out node suspects a partition
+pub const PARTITION_SUSPECTED_VIEWS: u64 = 10;
+
+/// the default number of seconds during which repeated log messages of a kind are coalesced
+pub const LOG_THROTTLE_DEFAULT_WINDOW_SEC: u64 = 10;
+
+/// the number of views between announcements of the transactions in a node's mempool
+pub const MEMPOOL_SYNC_INTERVAL_VIEWS: u64 = 5;
+
+/// the maximum number of transactions announced or requested in a single mempool sync message
+pub const MEMPOOL_SYNC_MAX_COMMITMENTS: usize = 1024;
+
+/// the number of views decided transactions are remembered for, so that a peer still holding
+/// them does not bring them back into the mempool
+pub const MEMPOOL_SYNC_DECIDED_RETENTION_VIEWS: u64 = 100;
+
+/// the number of views a DA leader believes the DA committee holds the transactions another node
+/// announced for, leaving them out of its compact DA proposals
+pub const COMPACT_DA_ANNOUNCEMENT_RETENTION_VIEWS: u64 = 2 * MEMPOOL_SYNC_INTERVAL_VIEWS;
+
+/// the number of views a node remembers the transactions it saw decided or dropped for, to report
+/// their status
+pub const TX_STATUS_RETENTION_VIEWS: u64 = 1000;
+
+/// the default payload size in bytes from which block payloads are written to disk instead of
+/// being held in memory
+pub const PAYLOAD_SPILL_THRESHOLD: usize = 4 * 1024 * 1024;
+
+/// the default maximum number of block payload bytes held in memory
+pub const PAYLOAD_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// the latest anchor is announced each time it passes a multiple of this many views
+pub const ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS: u64 = 10;
+
+/// the number of seconds to wait for the remaining chunks of a message before dropping it
+pub const CHUNK_REASSEMBLY_TIMEOUT_SEC: u64 = 30;
+
+/// the maximum number of bytes of messages still missing chunks a node holds, over all peers
+pub const CHUNK_REASSEMBLY_MAX_BYTES: usize = 512 * 1024 * 1024;
+
+/// the maximum number of messages still missing chunks a node holds for a single peer
+pub const CHUNK_REASSEMBLY_MAX_PENDING_PER_PEER: usize = 8;
+
+/// the default number of milliseconds an RPC request waits for its response
+pub const RPC_TIMEOUT_MS: u64 = 5000;
+
+/// the default maximum number of RPC requests of a node waiting for their responses at once
+pub const RPC_MAX_OUTGOING_REQUESTS: usize = 64;
+
+/// the default maximum number of RPC requests of peers a node handles at once
+pub const RPC_MAX_INCOMING_REQUESTS: usize = 64;
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Hash, Eq)]
 /// Type for protocol version number
 pub struct Version {
@@ -31,3 +152,6 @@ pub const VERSION_0_1: Version = Version { major: 0, minor: 1 };
 
 /// Default Channel Size for consensus event sharing
 pub const EVENT_CHANNEL_SIZE: usize = 100_000;
+
+/// The number of events a subscriber of the event bus can fall behind before missing the oldest
+pub const EVENT_BUS_CAPACITY: usize = 1024;
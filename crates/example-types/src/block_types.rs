@@ -6,8 +6,13 @@ use std::{
 use commit::{Commitment, Committable, RawCommitmentBuilder};
 use hotshot_types::{
     data::{BlockError, VidCommitment, VidScheme, VidSchemeTrait},
+    governance::GovernanceTransaction,
+    inclusion_proof::TransactionsRoot,
     traits::{
-        block_contents::{vid_commitment, BlockHeader, TestableBlock, Transaction},
+        block_contents::{
+            vid_commitment, BenchmarkableTransaction, BlockHeader, TestableBlock, Transaction,
+            TransactionLane, ViewWindow,
+        },
         BlockPayload, ValidatedState,
     },
     utils::BuilderCommitment,
@@ -19,9 +24,73 @@ use crate::state_types::TestValidatedState;
 
 /// The transaction in a [`TestBlockPayload`].
 #[derive(Default, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Debug)]
-pub struct TestTransaction(pub Vec<u8>);
+pub struct TestTransaction {
+    /// The bytes of the transaction
+    pub bytes: Vec<u8>,
+    /// The views whose blocks may include the transaction
+    pub window: ViewWindow,
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for TestTransaction {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            bytes: u.arbitrary()?,
+            window: ViewWindow {
+                earliest: u.arbitrary()?,
+                expiry: u.arbitrary()?,
+            },
+        })
+    }
+}
+
+/// Append `view` to `encoded`, as a flag byte followed by the view if there is one
+fn encode_view(encoded: &mut Vec<u8>, view: Option<u64>) {
+    match view {
+        Some(view) => {
+            encoded.push(1);
+            encoded.extend(view.to_le_bytes());
+        }
+        None => encoded.push(0),
+    }
+}
+
+/// Decode a view encoded by [`encode_view`] at `index`, returning it with the index after it
+fn decode_view(encoded: &[u8], index: usize) -> (Option<u64>, usize) {
+    if encoded[index] == 0 {
+        return (None, index + 1);
+    }
+    let mut view_bytes = [0; size_of::<u64>()];
+    view_bytes.copy_from_slice(&encoded[index + 1..index + 1 + size_of::<u64>()]);
+    (
+        Some(u64::from_le_bytes(view_bytes)),
+        index + 1 + size_of::<u64>(),
+    )
+}
 
 impl TestTransaction {
+    /// A transaction of `bytes`, which the block of any view may include
+    #[must_use]
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            window: ViewWindow::default(),
+        }
+    }
+
+    /// The transaction of the encoding of `governance`, which the block of any view may include
+    #[must_use]
+    pub fn from_governance(governance: &GovernanceTransaction) -> Self {
+        Self::new(governance.encode())
+    }
+
+    /// The transaction, only for the blocks of the views of `window`
+    #[must_use]
+    pub fn with_window(mut self, window: ViewWindow) -> Self {
+        self.window = window;
+        self
+    }
+
     /// Encode a list of transactions into bytes.
     ///
     /// # Errors
@@ -32,16 +101,19 @@ impl TestTransaction {
         for txn in transactions {
             // The transaction length is converted from `usize` to `u32` to ensure consistent
             // number of bytes on different platforms.
-            let txn_size = match u32::try_from(txn.0.len()) {
+            let txn_size = match u32::try_from(txn.bytes.len()) {
                 Ok(len) => len.to_le_bytes(),
                 Err(_) => {
                     return Err(BlockError::InvalidTransactionLength);
                 }
             };
 
-            // Concatenate the bytes of the transaction size and the transaction itself.
+            // Concatenate the bytes of the transaction size, the transaction itself and the views
+            // which may include it.
             encoded.extend(txn_size);
-            encoded.extend(txn.0);
+            encoded.extend(txn.bytes);
+            encode_view(&mut encoded, txn.window.earliest);
+            encode_view(&mut encoded, txn.window.expiry);
         }
 
         Ok(encoded)
@@ -52,9 +124,13 @@ impl Committable for TestTransaction {
     fn commit(&self) -> Commitment<Self> {
         let builder = commit::RawCommitmentBuilder::new("Txn Comm");
         let mut hasher = Keccak256::new();
-        hasher.update(&self.0);
+        hasher.update(&self.bytes);
         let generic_array = hasher.finalize();
-        builder.generic_byte_array(&generic_array).finalize()
+        builder
+            .generic_byte_array(&generic_array)
+            .u64_field("earliest view", self.window.earliest.unwrap_or(0))
+            .u64_field("expiry view", self.window.expiry.unwrap_or(u64::MAX))
+            .finalize()
     }
 
     fn tag() -> String {
@@ -62,7 +138,35 @@ impl Committable for TestTransaction {
     }
 }
 
-impl Transaction for TestTransaction {}
+impl Transaction for TestTransaction {
+    fn view_window(&self) -> ViewWindow {
+        self.window
+    }
+
+    fn lane(&self) -> TransactionLane {
+        if self.governance().is_some() {
+            TransactionLane::System
+        } else {
+            TransactionLane::User
+        }
+    }
+
+    fn governance(&self) -> Option<GovernanceTransaction> {
+        GovernanceTransaction::decode(&self.bytes)
+    }
+}
+
+impl BenchmarkableTransaction for TestTransaction {
+    fn create_random_transaction(
+        rng: &mut dyn rand::RngCore,
+        size: usize,
+        window: ViewWindow,
+    ) -> Self {
+        let mut bytes = vec![0; size];
+        rng.fill_bytes(&mut bytes);
+        TestTransaction::new(bytes).with_window(window)
+    }
+}
 
 /// A [`BlockPayload`] that contains a list of `TestTransaction`.
 #[derive(PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Debug)]
@@ -132,11 +236,15 @@ impl BlockPayload for TestBlockPayload {
             txn_len_bytes.copy_from_slice(&encoded_vec[current_index..txn_start_index]);
             let txn_len: usize = u32::from_le_bytes(txn_len_bytes) as usize;
 
-            // Get the transaction.
-            let next_index = txn_start_index + txn_len;
-            transactions.push(TestTransaction(
-                encoded_vec[txn_start_index..next_index].to_vec(),
-            ));
+            // Get the transaction and the views which may include it.
+            let txn_end_index = txn_start_index + txn_len;
+            let bytes = encoded_vec[txn_start_index..txn_end_index].to_vec();
+            let (earliest, expiry_index) = decode_view(&encoded_vec, txn_end_index);
+            let (expiry, next_index) = decode_view(&encoded_vec, expiry_index);
+            transactions.push(TestTransaction {
+                bytes,
+                window: ViewWindow { earliest, expiry },
+            });
             current_index = next_index;
         }
 
@@ -164,10 +272,21 @@ impl BlockPayload for TestBlockPayload {
     fn builder_commitment(&self, _metadata: &Self::Metadata) -> BuilderCommitment {
         let mut digest = sha2::Sha256::new();
         for txn in &self.transactions {
-            digest.update(&txn.0);
+            digest.update(&txn.bytes);
         }
         BuilderCommitment::from_raw_digest(digest.finalize())
     }
+
+    fn transactions(&self, _metadata: &Self::Metadata) -> Option<Vec<Self::Transaction>> {
+        Some(self.transactions.clone())
+    }
+
+    fn governance_transactions(&self, _metadata: &Self::Metadata) -> Vec<GovernanceTransaction> {
+        self.transactions
+            .iter()
+            .filter_map(Transaction::governance)
+            .collect()
+    }
 }
 
 /// Computes the (empty) genesis VID commitment
@@ -177,7 +296,13 @@ impl BlockPayload for TestBlockPayload {
 /// In that case, the payloads may mismatch and cause problems.
 #[must_use]
 pub fn genesis_vid_commitment() -> <VidScheme as VidSchemeTrait>::Commit {
-    vid_commitment(&vec![], 8)
+    vid_commitment(&[], 8)
+}
+
+/// Computes the root of the transactions of the (empty) genesis payload
+#[must_use]
+pub fn genesis_transactions_root() -> TransactionsRoot {
+    TransactionsRoot::from_commitments::<[u8; 32]>(&[])
 }
 
 /// A [`BlockHeader`] that commits to [`TestBlockPayload`].
@@ -187,6 +312,29 @@ pub struct TestBlockHeader {
     pub block_number: u64,
     /// VID commitment to the payload.
     pub payload_commitment: VidCommitment,
+    /// Root of the transactions of the payload.
+    pub transactions_root: TransactionsRoot,
+    /// Commitment to the header of the parent block, none for the genesis header.
+    pub parent_commitment: Option<Commitment<TestBlockHeader>>,
+    /// Application-defined extensions.
+    pub extensions: Vec<u8>,
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for TestBlockHeader {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            block_number: u.arbitrary()?,
+            payload_commitment: hotshot_types::fuzz::arbitrary_vid_commitment(u)?,
+            transactions_root: TransactionsRoot::from_commitments(&[u.arbitrary::<[u8; 32]>()?]),
+            parent_commitment: if u.arbitrary()? {
+                Some(hotshot_types::fuzz::arbitrary_commitment(u)?)
+            } else {
+                None
+            },
+            extensions: u.arbitrary()?,
+        })
+    }
 }
 
 impl BlockHeader for TestBlockHeader {
@@ -198,11 +346,16 @@ impl BlockHeader for TestBlockHeader {
         _instance_state: &<Self::State as ValidatedState>::Instance,
         parent_header: &Self,
         payload_commitment: VidCommitment,
+        transactions_root: TransactionsRoot,
         _metadata: <Self::Payload as BlockPayload>::Metadata,
+        extensions: Vec<u8>,
     ) -> Self {
         Self {
             block_number: parent_header.block_number + 1,
             payload_commitment,
+            transactions_root,
+            parent_commitment: Some(parent_header.commit()),
+            extensions,
         }
     }
 
@@ -218,6 +371,9 @@ impl BlockHeader for TestBlockHeader {
             Self {
                 block_number: 0,
                 payload_commitment: genesis_vid_commitment(),
+                transactions_root: payload.transactions_root(&metadata),
+                parent_commitment: None,
+                extensions: Vec::new(),
             },
             payload,
             metadata,
@@ -228,22 +384,48 @@ impl BlockHeader for TestBlockHeader {
         self.block_number
     }
 
+    fn parent_commitment(&self) -> Option<Commitment<Self>> {
+        self.parent_commitment
+    }
+
     fn payload_commitment(&self) -> VidCommitment {
         self.payload_commitment
     }
 
+    fn transactions_root(&self) -> TransactionsRoot {
+        self.transactions_root
+    }
+
     fn metadata(&self) -> &<Self::Payload as BlockPayload>::Metadata {
         &()
     }
+
+    fn extensions(&self) -> &[u8] {
+        &self.extensions
+    }
 }
 
 impl Committable for TestBlockHeader {
     fn commit(&self) -> Commitment<Self> {
-        RawCommitmentBuilder::new("Header Comm")
+        let builder = RawCommitmentBuilder::new("Header Comm")
             .u64_field("block number", self.block_number())
             .constant_str("payload commitment")
             .fixed_size_bytes(self.payload_commitment().as_ref().as_ref())
-            .finalize()
+            .constant_str("transactions root")
+            .fixed_size_bytes(self.transactions_root.as_ref());
+        let builder = match self.parent_commitment {
+            Some(parent) => builder.field("parent commitment", parent),
+            None => builder.constant_str("genesis"),
+        };
+        // headers without extensions keep the commitments they had before headers carried any
+        if self.extensions.is_empty() {
+            builder.finalize()
+        } else {
+            builder
+                .constant_str("extensions")
+                .var_size_bytes(&self.extensions)
+                .finalize()
+        }
     }
 
     fn tag() -> String {
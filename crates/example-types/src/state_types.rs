@@ -94,7 +94,7 @@ impl TestableState for TestValidatedState {
     ) -> <Self::BlockPayload as BlockPayload>::Transaction {
         /// clippy appeasement for `RANDOM_TX_BASE_SIZE`
         const RANDOM_TX_BASE_SIZE: usize = 8;
-        TestTransaction(vec![
+        TestTransaction::new(vec![
             0;
             RANDOM_TX_BASE_SIZE + usize::try_from(padding).unwrap()
         ])
@@ -1,5 +1,5 @@
 use crate::infra::CombinedDARun;
-use hotshot::traits::implementations::{CombinedNetworks, MemoryStorage};
+use hotshot::traits::implementations::{CombinedNetworks, MemoryStorage, RecordingNetwork};
 use hotshot_example_types::state_types::TestTypes;
 use hotshot_types::traits::node_implementation::NodeImplementation;
 use serde::{Deserialize, Serialize};
@@ -10,13 +10,13 @@ use std::fmt::Debug;
 pub struct NodeImpl {}
 
 /// convenience type alias
-pub type DANetwork = CombinedNetworks<TestTypes>;
+pub type DANetwork = RecordingNetwork<CombinedNetworks<TestTypes>>;
 /// convenience type alias
-pub type VIDNetwork = CombinedNetworks<TestTypes>;
+pub type VIDNetwork = RecordingNetwork<CombinedNetworks<TestTypes>>;
 /// convenience type alias
-pub type QuorumNetwork = CombinedNetworks<TestTypes>;
+pub type QuorumNetwork = RecordingNetwork<CombinedNetworks<TestTypes>>;
 /// convenience type alias
-pub type ViewSyncNetwork = CombinedNetworks<TestTypes>;
+pub type ViewSyncNetwork = RecordingNetwork<CombinedNetworks<TestTypes>>;
 
 impl NodeImplementation<TestTypes> for NodeImpl {
     type Storage = MemoryStorage<TestTypes>;
@@ -0,0 +1,71 @@
+//! Generates the `genesis.json` of a network from its run config
+//!
+//! The stake table holds the keys derived from the config's seed or mnemonic, with the stake
+//! given by its `stake_weights`, so it matches the keys the validators generate for themselves.
+#![allow(clippy::panic)]
+
+use std::fs;
+
+use clap::Parser;
+use hotshot_example_types::state_types::{TestInstanceState, TestTypes};
+use hotshot_orchestrator::config::NetworkConfigFile;
+use hotshot_types::{
+    genesis::GenesisFile,
+    traits::{node_implementation::NodeType, signature_key::SignatureKey, states::ValidatedState},
+};
+
+/// The signature key of the generated network
+type Key = <TestTypes as NodeType>::SignatureKey;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "hotshot-genesis",
+    about = "Generates the genesis file of a network"
+)]
+/// Arguments of the genesis generator
+struct GenesisArgs {
+    /// The run config the network is started with
+    config_file: String,
+    /// The chain id of the network
+    #[arg(long)]
+    chain_id: u64,
+    /// Where to write the genesis file
+    #[arg(long, default_value = "genesis.json")]
+    output: String,
+}
+
+fn main() {
+    let args = GenesisArgs::parse();
+
+    let contents = fs::read_to_string(&args.config_file)
+        .unwrap_or_else(|e| panic!("Could not read config file {}: {e}", args.config_file));
+    let config: NetworkConfigFile<Key> =
+        toml::from_str(&contents).expect("Unable to convert config file to TOML");
+    let seeds = config
+        .seeds()
+        .unwrap_or_else(|e| panic!("Could not derive the keys of the nodes: {e}"));
+
+    let stake_table = (0..config.config.total_nodes.get() as u64)
+        .map(|node_index| {
+            let stake = usize::try_from(node_index)
+                .ok()
+                .and_then(|index| config.stake_weights.get(index))
+                .copied()
+                .unwrap_or(1);
+            Key::generated_from_seed_indexed(seeds.consensus, node_index)
+                .0
+                .get_stake_table_entry(stake)
+        })
+        .collect();
+    let genesis_state = <TestTypes as NodeType>::ValidatedState::genesis(&TestInstanceState {});
+
+    let genesis = GenesisFile::<Key>::new(args.chain_id, stake_table, &config, &genesis_state)
+        .expect("Could not hash the config or genesis state");
+    genesis
+        .to_file(&args.output)
+        .unwrap_or_else(|e| panic!("Could not write genesis file {}: {e}", args.output));
+    println!(
+        "Wrote genesis of chain {} with config hash {} to {}",
+        genesis.chain_id, genesis.config_hash, args.output
+    );
+}
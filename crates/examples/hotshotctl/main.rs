@@ -0,0 +1,201 @@
+//! Command line client of the control endpoint of a validator node
+//!
+//! The node serves the endpoint when started with `--control-url` and a `--control-token`, which
+//! every request carries.
+#![allow(clippy::panic)]
+
+use clap::{Parser, Subcommand};
+use hotshot_orchestrator::control::{
+    LogFilterRequest, LogFilterStatus, NodeStatus, CONTROL_TOKEN_ENV,
+};
+use hotshot_types::leaf_dag::LeafDag;
+use surf_disco::{error::ClientError, Client, Url};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "hotshotctl",
+    about = "Inspects and controls a running HotShot node"
+)]
+/// Arguments of `hotshotctl`
+struct CtlArgs {
+    /// The control endpoint of the node
+    #[arg(long, default_value = "http://localhost:9100")]
+    url: Url,
+    /// The token of the control endpoint
+    #[arg(long, env = CONTROL_TOKEN_ENV, hide_env_values = true)]
+    token: String,
+    /// What to do
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// The commands of `hotshotctl`
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show the current view, the last decided view and the peers of the node
+    Status,
+    /// Dump the configuration the node runs with
+    Config,
+    /// Have the node write a snapshot of its status, leaf DAG and storage to disk
+    Snapshot,
+    /// Dump the leaves the node holds in memory, with the forks and orphaned proposals among them
+    LeafDag {
+        /// Dump them in graphviz DOT format rather than as JSON
+        #[arg(long)]
+        dot: bool,
+    },
+    /// Stop the node from submitting transactions
+    PauseTransactions,
+    /// Have the node submit transactions again
+    ResumeTransactions,
+    /// Have the node end its run and shut down gracefully
+    Shutdown,
+    /// Show the filter of the node's logs, and the open debug capture window, if any
+    LogFilter,
+    /// Filter the node's logs with different directives, in the syntax of `RUST_LOG`
+    SetLogFilter {
+        /// The directives, as in `info,hotshot_task_impls::consensus=debug`
+        directives: String,
+    },
+    /// Filter the node's logs with different directives for a while, then revert
+    CaptureLogs {
+        /// The directives, as in `debug` or `hotshot_task_impls::view_sync=trace`
+        directives: String,
+        /// How long to capture for before the filter reverts
+        #[arg(long, default_value_t = 60)]
+        seconds: u64,
+    },
+}
+
+/// The value of the `Authorization` header carrying `token`
+fn authorization(token: &str) -> String {
+    format!("Bearer {token}")
+}
+
+/// Post `request` to the log filter route
+async fn set_log_filter(client: &Client<ClientError>, token: &str, request: &LogFilterRequest) {
+    let result: Result<(), ClientError> = match client
+        .post("control/set_log_filter")
+        .header("Authorization", authorization(token))
+        .body_json(request)
+    {
+        Ok(post) => post.send().await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => println!("ok"),
+        Err(e) => panic!("Request to the node failed: {e}"),
+    }
+}
+
+/// Post to the control route `route`
+async fn post(client: &Client<ClientError>, token: &str, route: &str) {
+    let result: Result<(), ClientError> = client
+        .post(&format!("control/{route}"))
+        .header("Authorization", authorization(token))
+        .send()
+        .await;
+    match result {
+        Ok(()) => println!("ok"),
+        Err(e) => panic!("Request to the node failed: {e}"),
+    }
+}
+
+#[cfg_attr(async_executor_impl = "tokio", tokio::main)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::main)]
+async fn main() {
+    let args = CtlArgs::parse();
+    let client = Client::<ClientError>::new(args.url);
+
+    match args.command {
+        Command::Status => {
+            let status: NodeStatus = client
+                .get("control/status")
+                .header("Authorization", authorization(&args.token))
+                .send()
+                .await
+                .unwrap_or_else(|e| panic!("Could not get the node's status: {e}"));
+            println!("node index:        {}", status.node_index);
+            println!("current view:      {}", status.current_view);
+            println!("last decided view: {}", status.last_decided_view);
+            println!("errors:            {}", status.error_count);
+            println!("transactions sent: {}", status.transactions_sent);
+            let intake = if status.transactions_paused {
+                "paused"
+            } else {
+                "running"
+            };
+            println!("transactions:      {intake}");
+            println!("peers ({}):", status.peers.len());
+            for peer in status.peers {
+                println!("  {peer}");
+            }
+        }
+        Command::Config => {
+            let config: serde_json::Value = client
+                .get("control/config")
+                .header("Authorization", authorization(&args.token))
+                .send()
+                .await
+                .unwrap_or_else(|e| panic!("Could not get the node's config: {e}"));
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&config).expect("JSON values always serialize")
+            );
+        }
+        Command::Snapshot => post(&client, &args.token, "snapshot").await,
+        Command::LeafDag { dot } => {
+            let leaf_dag: LeafDag = client
+                .get("control/leaf_dag")
+                .header("Authorization", authorization(&args.token))
+                .send()
+                .await
+                .unwrap_or_else(|e| panic!("Could not get the node's leaf DAG: {e}"));
+            if dot {
+                print!("{}", leaf_dag.to_dot());
+            } else {
+                println!(
+                    "{}",
+                    leaf_dag
+                        .to_json()
+                        .unwrap_or_else(|e| panic!("Could not render the leaf DAG: {e}"))
+                );
+            }
+        }
+        Command::PauseTransactions => post(&client, &args.token, "pause_transactions").await,
+        Command::ResumeTransactions => post(&client, &args.token, "resume_transactions").await,
+        Command::Shutdown => post(&client, &args.token, "shutdown").await,
+        Command::LogFilter => {
+            let filter: LogFilterStatus = client
+                .get("control/log_filter")
+                .header("Authorization", authorization(&args.token))
+                .send()
+                .await
+                .unwrap_or_else(|e| panic!("Could not get the node's log filter: {e}"));
+            println!("log filter: {}", filter.directives);
+            if let Some(capture) = filter.capture {
+                println!(
+                    "capturing:  reverts to {} in {}s",
+                    capture.reverts_to, capture.remaining_seconds
+                );
+            }
+        }
+        Command::SetLogFilter { directives } => {
+            let request = LogFilterRequest {
+                directives,
+                revert_after_seconds: None,
+            };
+            set_log_filter(&client, &args.token, &request).await;
+        }
+        Command::CaptureLogs {
+            directives,
+            seconds,
+        } => {
+            let request = LogFilterRequest {
+                directives,
+                revert_after_seconds: Some(seconds),
+            };
+            set_log_filter(&client, &args.token, &request).await;
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Errors of the example infrastructure, which validators embedded in a larger service handle
+//! rather than crash on
+
+use hotshot_orchestrator::{
+    config::{NetworkConfigError, NetworkType},
+    preflight::PreflightError,
+};
+use hotshot_types::{genesis::GenesisError, traits::network::NetworkError};
+use libp2p_identity::DecodingError;
+use libp2p_networking::network::NetworkNodeConfigBuilderError;
+use snafu::Snafu;
+use surf_disco::error::ClientError;
+
+/// Why a validator could not join or finish a run
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum InfraError {
+    /// The run configuration file could not be loaded
+    #[snafu(display("Could not load config file {config_file}: {source}"))]
+    LoadConfig {
+        /// The configuration file
+        config_file: String,
+        /// The loading error
+        source: NetworkConfigError,
+    },
+    /// The campaign file could not be loaded
+    #[snafu(display("Could not load campaign file {campaign_file}: {source}"))]
+    LoadCampaign {
+        /// The campaign file
+        campaign_file: String,
+        /// The loading error
+        source: NetworkConfigError,
+    },
+    /// The orchestrator stopped serving
+    #[snafu(display("The orchestrator failed: {source}"))]
+    Orchestrator {
+        /// The serving error
+        source: std::io::Error,
+    },
+    /// A request to the orchestrator failed
+    #[snafu(display("Request to the orchestrator failed: {source}"))]
+    OrchestratorRequest {
+        /// The client error
+        source: ClientError,
+    },
+    /// The run configuration lacks a section the networks of this validator need
+    #[snafu(display("Configuration has no {section}"))]
+    MissingConfig {
+        /// The missing section
+        section: &'static str,
+    },
+    /// The run configuration does not match its schema
+    #[snafu(display("{config_file} is not a valid run configuration: {violations} error(s)"))]
+    InvalidConfig {
+        /// The configuration file
+        config_file: String,
+        /// The number of fields which do not match the schema
+        violations: usize,
+    },
+    /// The run has too few nodes for the libp2p replication factor
+    #[snafu(display("A libp2p network needs more than 2 nodes, not {total_nodes}"))]
+    TooFewNodes {
+        /// The number of nodes of the run
+        total_nodes: usize,
+    },
+    /// The keypair of a bootstrap node could not be decoded
+    #[snafu(display("Invalid bootstrap node keypair: {source}"))]
+    BootstrapKeypair {
+        /// The decoding error
+        source: DecodingError,
+    },
+    /// The libp2p node configuration is invalid
+    #[snafu(display("Invalid libp2p node configuration: {source}"))]
+    Libp2pConfig {
+        /// The builder error
+        source: NetworkNodeConfigBuilderError,
+    },
+    /// A network could not be created
+    #[snafu(display("Could not create the network: {source}"))]
+    Network {
+        /// The network error
+        source: NetworkError,
+    },
+    /// The run configuration asks for a channel to run over another network than this validator
+    #[snafu(display(
+        "The config runs the {channel} channel over {configured:?}, \
+         but this validator runs it over {actual:?}"
+    ))]
+    NetworkTypeMismatch {
+        /// The channel, quorum or DA
+        channel: &'static str,
+        /// The network the configuration asks for
+        configured: NetworkType,
+        /// The network of this validator
+        actual: NetworkType,
+    },
+    /// Both channels of a mixed run are over the same network
+    #[snafu(display("Both channels run over {network:?}, which the run of that network is for"))]
+    SameNetwork {
+        /// The network of both channels
+        network: NetworkType,
+    },
+    /// The genesis file could not be loaded
+    #[snafu(display("Could not load genesis file {genesis_file}: {source}"))]
+    Genesis {
+        /// The genesis file
+        genesis_file: String,
+        /// The loading error
+        source: GenesisError,
+    },
+    /// `HotShot` failed. Its error is generic over the node types, so only its message is kept.
+    #[snafu(display("Could not {action}: {reason}"))]
+    HotShot {
+        /// What failed
+        action: &'static str,
+        /// The message of the `HotShot` error
+        reason: String,
+    },
+    /// The events of consensus stopped before the run ended
+    #[snafu(display("Event stream completed before consensus ended"))]
+    EventStreamEnded,
+    /// No address to advertise to the other nodes was given or found
+    #[snafu(display("Could not find a local IP address"))]
+    NoLocalIp,
+    /// The preflight checks failed, and the validator was not forced to join anyway, or they
+    /// could not be run
+    #[snafu(display("{source}, pass --force to join the run anyway"))]
+    Preflight {
+        /// Why the checks did not pass
+        source: PreflightError,
+    },
+    /// A soak run found resources leaking
+    #[snafu(display("Resources leaked during the soak run"))]
+    ResourceLeaks,
+}
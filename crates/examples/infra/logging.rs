@@ -0,0 +1,50 @@
+//! Logging of validator nodes, with a filter the control endpoint changes while they run
+
+use std::sync::{Arc, OnceLock};
+
+use hotshot_orchestrator::control::{LogControl, LogFilter};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry,
+};
+
+/// A filter of the logs, reloaded in place
+#[derive(Debug)]
+struct ReloadableFilter(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilter for ReloadableFilter {
+    fn directives(&self) -> String {
+        self.0.with_current(ToString::to_string).unwrap_or_default()
+    }
+
+    fn set_directives(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.0.reload(filter).map_err(|e| e.to_string())
+    }
+}
+
+/// The log filter of this process, once [`setup_logging`] set logging up
+static LOG_CONTROL: OnceLock<Arc<LogControl>> = OnceLock::new();
+
+/// Set up logging filtered by `RUST_LOG` and formatted as `RUST_LOG_FORMAT` says, `full`,
+/// `compact` or `json`, like `async_compatibility_layer::logging::setup_logging`, but with a
+/// filter which [`log_control`] changes later. Does nothing if logging is already set up.
+pub fn setup_logging() {
+    let (filter, handle) = reload::Layer::new(EnvFilter::from_default_env());
+    let registry = tracing_subscriber::registry().with(filter);
+    let format = std::env::var("RUST_LOG_FORMAT").unwrap_or_default();
+    let installed = match format.trim().to_lowercase().as_str() {
+        "json" => registry.with(fmt::layer().json()).try_init(),
+        "compact" => registry.with(fmt::layer().compact()).try_init(),
+        _ => registry.with(fmt::layer()).try_init(),
+    };
+    if installed.is_ok() {
+        let _ = LOG_CONTROL.set(Arc::new(LogControl::new(ReloadableFilter(handle))));
+    }
+}
+
+/// The log filter of this process, shared by all of its nodes, if [`setup_logging`] set logging
+/// up
+#[must_use]
+pub fn log_control() -> Option<Arc<LogControl>> {
+    LOG_CONTROL.get().cloned()
+}
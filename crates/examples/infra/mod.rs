@@ -1,28 +1,47 @@
 #![allow(clippy::panic)]
-use async_compatibility_layer::art::async_sleep;
-use async_compatibility_layer::logging::{setup_backtrace, setup_logging};
+use async_compatibility_layer::art::{async_sleep, async_spawn};
+use async_compatibility_layer::logging::setup_backtrace;
 use async_lock::RwLock;
 use async_trait::async_trait;
+use bincode::Options;
 use clap::Parser;
-use futures::StreamExt;
+use commit::Committable;
+use futures::{
+    future::{self, BoxFuture, Either},
+    FutureExt, StreamExt,
+};
 use hotshot::traits::implementations::{CombinedNetworks, UnderlyingCombinedNetworks};
 use hotshot::{
     traits::{
-        implementations::{Libp2pNetwork, MemoryStorage, NetworkingMetricsValue, WebServerNetwork},
+        implementations::{
+            Libp2pNetwork, MemoryStorage, NetworkingMetricsValue, RecordingNetwork,
+            WebServerNetwork, DA_TOPIC,
+        },
         NodeImplementation,
     },
     types::{SignatureKey, SystemContextHandle},
     Memberships, Networks, SystemContext,
 };
-use hotshot_example_types::{
-    block_types::{TestBlockHeader, TestBlockPayload, TestTransaction},
-    state_types::TestInstanceState,
-};
+use hotshot_example_types::state_types::TestInstanceState;
 use hotshot_orchestrator::config::NetworkConfigSource;
 use hotshot_orchestrator::{
     self,
+    campaign::{CampaignConfig, PartitionOutcome, RunResults, ThroughputSample},
     client::{OrchestratorClient, ValidatorArgs},
-    config::{NetworkConfig, NetworkConfigFile, WebServerConfig},
+    config::{
+        prefer_family, unix_time_ms, IpFamily, LogThrottleConfig, NetworkConfig, NetworkType,
+        ScheduledFailure, WebServerConfig,
+    },
+    control::{
+        run_control_server, LeafDagSource, NodeControlHandle, PayloadValidationRequest,
+        PayloadValidator, PayloadVerdict,
+    },
+    load::{LoadController, LoadObservation},
+    preflight::{self, PreflightError},
+    schema::validate_config_file,
+    soak::{ProcessUsage, ResourceSample, SoakMonitor},
+    systemd::SystemdNotifier,
+    webhooks::{DecideNotification, WebhookNotifier},
 };
 use hotshot_types::message::Message;
 use hotshot_types::traits::network::ConnectedNetwork;
@@ -31,34 +50,68 @@ use hotshot_types::{
     consensus::ConsensusMetricsValue,
     data::{Leaf, TestableLeaf},
     event::{Event, EventType},
+    genesis::GenesisFile,
+    leaf_dag::LeafDag,
+    statsd::StatsdMetrics,
+    storage_sync::recover_storage,
     traits::{
-        block_contents::TestableBlock,
-        election::Membership,
+        block_contents::{BenchmarkableTransaction, TestableBlock, ViewWindow},
+        election::{ElectionConfig, Membership},
+        metrics::{Metrics, NoMetrics},
         node_implementation::{ConsensusTime, NodeType},
         states::TestableState,
+        storage::TestableStorage,
+        BlockPayload,
     },
     HotShotConfig,
 };
+use hotshot_utils::{
+    bincode::bincode_opts,
+    log_throttle::{self, LogThrottle},
+};
 use libp2p_identity::{
     ed25519::{self, SecretKey},
     Keypair,
 };
 use libp2p_networking::{
-    network::{MeshParams, NetworkNodeConfigBuilder, NetworkNodeType},
+    network::{MeshParams, MeshTuning, NetworkNodeConfigBuilder, NetworkNodeType, QuicParams},
     reexport::Multiaddr,
 };
 use rand::rngs::StdRng;
 use rand::SeedableRng;
+use snafu::{OptionExt, ResultExt};
 use std::marker::PhantomData;
 use std::time::Duration;
-use std::{collections::BTreeSet, sync::Arc};
-use std::{num::NonZeroUsize, str::FromStr};
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use std::{net::IpAddr, num::NonZeroUsize, str::FromStr};
 use surf_disco::Url;
 
 use libp2p_identity::PeerId;
 use std::fmt::Debug;
-use std::{fs, time::Instant};
-use tracing::{error, info, warn};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Instant, SystemTime},
+};
+use tracing::{debug, error, info, warn};
+
+use self::error::{
+    BootstrapKeypairSnafu, GenesisSnafu, InfraError, Libp2pConfigSnafu, LoadCampaignSnafu,
+    LoadConfigSnafu, MissingConfigSnafu, NetworkSnafu, OrchestratorRequestSnafu, OrchestratorSnafu,
+    TooFewNodesSnafu,
+};
+
+/// Errors of validators, which the binaries report at their top level
+pub mod error;
+
+/// Logging of validators, with a filter which can be changed while they run
+pub mod logging;
 
 #[derive(Parser, Debug, Clone)]
 #[command(
@@ -71,6 +124,14 @@ pub struct OrchestratorArgs {
     pub url: Url,
     /// The configuration file to be used for this run
     pub config_file: String,
+    /// An optional campaign file: a matrix of parameters applied on top of `config_file`, whose
+    /// combinations are run one after another
+    #[arg(long)]
+    pub campaign_file: Option<String>,
+    /// Only check `config_file` against the schema of run configurations, print every field
+    /// which does not match it, and exit
+    #[arg(long)]
+    pub validate_config: bool,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -85,8 +146,8 @@ pub struct ConfigArgs {
 }
 
 /// Reads a network configuration from a given filepath
-/// # Panics
-/// if unable to convert the config file into toml
+/// # Errors
+/// if unable to read the config file or convert it from toml, or if its mnemonic is invalid
 /// # Note
 /// This derived config is used for initialization of orchestrator,
 /// therefore `known_nodes_with_stake` will be an initialized
@@ -94,51 +155,108 @@ pub struct ConfigArgs {
 /// `my_own_validator_config` will be generated from seed here
 /// for loading config from orchestrator,
 /// or else it will be loaded from file.
-#[must_use]
 pub fn load_config_from_file<TYPES: NodeType>(
     config_file: &str,
-) -> NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType> {
-    let config_file_as_string: String = fs::read_to_string(config_file)
-        .unwrap_or_else(|_| panic!("Could not read config file located at {config_file}"));
-    let config_toml: NetworkConfigFile<TYPES::SignatureKey> =
-        toml::from_str::<NetworkConfigFile<TYPES::SignatureKey>>(&config_file_as_string)
-            .expect("Unable to convert config file to TOML");
-
-    let mut config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType> =
-        config_toml.into();
+) -> Result<NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>, InfraError> {
+    let mut config =
+        NetworkConfig::<TYPES::SignatureKey, TYPES::ElectionConfigType>::from_run_config_file(
+            config_file,
+        )
+        .context(LoadConfigSnafu { config_file })?;
 
     // my_own_validator_config would be best to load from file,
     // but its type is too complex to load so we'll generate it from seed now
-    config.config.my_own_validator_config =
-        ValidatorConfig::generated_from_seed_indexed(config.seed, config.node_index, 1);
+    config.config.my_own_validator_config = ValidatorConfig::generated_from_seed_indexed(
+        config.seed,
+        config.node_index,
+        config.stake_of(config.node_index),
+    );
     let my_own_validator_config_with_stake = config
         .config
         .my_own_validator_config
         .public_key
-        .get_stake_table_entry(1u64);
+        .get_stake_table_entry(config.config.my_own_validator_config.stake_value);
     // initialize it with size for better assignment of other peers' config
     config.config.known_nodes_with_stake =
         vec![my_own_validator_config_with_stake; config.config.total_nodes.get() as usize];
 
-    config
+    Ok(config)
+}
+
+/// Report `error`, which a binary cannot recover from, and exit with a failure status
+pub fn exit_with(error: &InfraError) -> ! {
+    error!("{error}");
+    std::process::exit(1);
 }
 
 /// Runs the orchestrator
+/// # Errors
+/// If the run configuration or the campaign cannot be loaded, or the orchestrator fails
 pub async fn run_orchestrator<
     TYPES: NodeType,
     DACHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey> + Debug,
     QUORUMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey> + Debug,
     NODE: NodeImplementation<TYPES, Storage = MemoryStorage<TYPES>>,
 >(
-    OrchestratorArgs { url, config_file }: OrchestratorArgs,
-) {
+    OrchestratorArgs {
+        url,
+        config_file,
+        campaign_file,
+        validate_config,
+    }: OrchestratorArgs,
+) -> Result<(), InfraError> {
+    if validate_config {
+        validate_config_and_exit::<TYPES>(&config_file);
+    }
     error!("Starting orchestrator",);
-    let run_config = load_config_from_file::<TYPES>(&config_file);
-    let _result = hotshot_orchestrator::run_orchestrator::<
-        TYPES::SignatureKey,
-        TYPES::ElectionConfigType,
-    >(run_config, url)
-    .await;
+    let run_config = load_config_from_file::<TYPES>(&config_file)?;
+    let configs = match campaign_file {
+        Some(campaign_file) => CampaignConfig::from_file(&campaign_file)
+            .context(LoadCampaignSnafu {
+                campaign_file: campaign_file.clone(),
+            })?
+            .expand(&run_config),
+        None => vec![run_config],
+    };
+    hotshot_orchestrator::run_campaign::<TYPES::SignatureKey, TYPES::ElectionConfigType>(
+        configs, url,
+    )
+    .await
+    .context(OrchestratorSnafu)
+}
+
+/// Check the run configuration file `config_file` against its schema, and print every field
+/// which does not match it
+/// # Errors
+/// If any does not
+fn validate_config<TYPES: NodeType>(config_file: &str) -> Result<(), InfraError> {
+    match validate_config_file::<TYPES::SignatureKey>(config_file) {
+        Ok(()) => {
+            println!("{config_file} is a valid run configuration");
+            Ok(())
+        }
+        Err(violations) => {
+            for violation in &violations {
+                eprintln!("{config_file}: {violation}");
+            }
+            Err(InfraError::InvalidConfig {
+                config_file: config_file.to_string(),
+                violations: violations.len(),
+            })
+        }
+    }
+}
+
+/// Check the run configuration file `config_file` against its schema, print every field which
+/// does not match it, and exit, with a failure status if any does not
+fn validate_config_and_exit<TYPES: NodeType>(config_file: &str) -> ! {
+    match validate_config::<TYPES>(config_file) {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
 }
 
 /// Helper function to calculate the nuymber of transactions to send per node per round
@@ -156,82 +274,123 @@ fn calculate_num_tx_per_round(
 }
 
 /// create a web server network from a config file + public key
-/// # Panics
-/// Panics if the web server config doesn't exist in `config`
+/// # Errors
+/// If the web server config doesn't exist in `config`
 fn webserver_network_from_config<TYPES: NodeType>(
     config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
     pub_key: TYPES::SignatureKey,
-) -> WebServerNetwork<TYPES> {
+) -> Result<WebServerNetwork<TYPES>, InfraError> {
     // Get the configuration for the web server
-    let WebServerConfig {
-        url,
-        wait_between_polls,
-    }: WebServerConfig = config.web_server_config.unwrap();
+    let web_server_config: WebServerConfig =
+        config.web_server_config.context(MissingConfigSnafu {
+            section: "web server",
+        })?;
+
+    Ok(WebServerNetwork::create_sharded(
+        web_server_config.urls(),
+        web_server_config.wait_between_polls,
+        pub_key,
+        false,
+        config.config.chain_id,
+    ))
+}
 
-    WebServerNetwork::create(url, wait_between_polls, pub_key, false)
+/// The configuration of the web server of the DA channel in `config`
+/// # Errors
+/// If there is none
+fn da_web_server_config<KEY: SignatureKey, ELECTION: ElectionConfig>(
+    config: &NetworkConfig<KEY, ELECTION>,
+) -> Result<WebServerConfig, InfraError> {
+    config
+        .da_web_server_config
+        .clone()
+        .context(MissingConfigSnafu {
+            section: "DA web server",
+        })
+}
+
+/// The election configurations of the quorum and of the DA committee of the run of `config`
+fn election_configs<TYPES: NodeType>(
+    config: &NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+) -> (TYPES::ElectionConfigType, TYPES::ElectionConfigType) {
+    // Since we do not currently pass the election config type in the NetworkConfig, this will always be the default election config
+    let mut quorum_election_config = config.config.election_config.clone().unwrap_or_else(|| {
+        TYPES::Membership::default_election_config(config.config.total_nodes.get() as u64)
+    });
+    if let Some(domains) = config.da_failure_domains() {
+        quorum_election_config = quorum_election_config.with_da_failure_domains(domains);
+    }
+    if let Some(rules) = config.leader_reputation {
+        quorum_election_config = quorum_election_config.with_leader_reputation(rules);
+    }
+    let committee_election_config =
+        quorum_election_config.da_config(config.config.da_committee_size as u64);
+    (quorum_election_config, committee_election_config)
 }
 
 #[allow(clippy::cast_possible_truncation)]
 #[allow(clippy::cast_lossless)]
 #[allow(clippy::too_many_lines)]
 /// Create a libp2p network from a config file and public key
-/// # Panics
-/// If unable to create bootstrap nodes multiaddres or the libp2p config is invalid
+/// # Errors
+/// If `config` is not for a libp2p network, a bootstrap node keypair cannot be decoded, the
+/// run has too few nodes, the libp2p config is invalid or the network cannot be created
 async fn libp2p_network_from_config<TYPES: NodeType>(
     config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
     pub_key: TYPES::SignatureKey,
-) -> Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey> {
+) -> Result<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>, InfraError> {
     let mut config = config;
     let libp2p_config = config
         .libp2p_config
         .take()
-        .expect("Configuration is not for a Libp2p network");
+        .context(MissingConfigSnafu { section: "libp2p" })?;
     let bs_len = libp2p_config.bootstrap_nodes.len();
-    let bootstrap_nodes: Vec<(PeerId, Multiaddr)> = libp2p_config
-        .bootstrap_nodes
-        .iter()
-        .map(|(addr, pair)| {
-            let kp = Keypair::from_protobuf_encoding(pair).unwrap();
-            let peer_id = PeerId::from_public_key(&kp.public());
-            let multiaddr =
-                Multiaddr::from_str(&format!("/ip4/{}/udp/{}/quic-v1", addr.ip(), addr.port()))
-                    .unwrap();
-            (peer_id, multiaddr)
-        })
-        .collect();
-    let identity = libp2p_generate_indexed_identity(config.seed, config.node_index);
+    // every address of every bootstrap node, those of the preferred family first
+    let mut bootstrap_nodes: Vec<(PeerId, Multiaddr)> = Vec::new();
+    for (addrs, pair) in &libp2p_config.bootstrap_nodes {
+        let kp = Keypair::from_protobuf_encoding(pair).context(BootstrapKeypairSnafu)?;
+        let peer_id = PeerId::from_public_key(&kp.public());
+        let mut addrs = addrs.clone();
+        prefer_family(&mut addrs, libp2p_config.preferred_ip_family);
+        bootstrap_nodes.extend(
+            addrs
+                .into_iter()
+                .map(|addr| (peer_id, quic_multiaddr(addr.ip(), addr.port()))),
+        );
+    }
+    let identity = libp2p_generate_indexed_identity(config.libp2p_seed, config.node_index);
     let node_type = if (config.node_index as usize) < bs_len {
         NetworkNodeType::Bootstrap
     } else {
         NetworkNodeType::Regular
     };
     let node_index = config.node_index;
-    let port_index = if libp2p_config.index_ports {
-        node_index
-    } else {
-        0
-    };
-    let bound_addr: Multiaddr = format!(
-        "/{}/{}/udp/{}/quic-v1",
-        if libp2p_config.public_ip.is_ipv4() {
-            "ip4"
-        } else {
-            "ip6"
-        },
-        libp2p_config.public_ip,
-        libp2p_config.base_port as u64 + port_index
-    )
-    .parse()
-    .unwrap();
+    let port = libp2p_config.listen_port(node_index);
+    let bind_ips = libp2p_config.listen_ips();
+    let bound_addr = quic_multiaddr(bind_ips[0], port);
+    let additional_bound_addrs = bind_ips[1..]
+        .iter()
+        .map(|ip| quic_multiaddr(*ip, port))
+        .collect();
+    let advertised_addrs = libp2p_config
+        .advertised_ips
+        .iter()
+        .map(|ip| quic_multiaddr(*ip, port))
+        .collect();
 
     // generate network
     let mut config_builder = NetworkNodeConfigBuilder::default();
-    assert!(config.config.total_nodes.get() > 2);
-    let replicated_nodes = NonZeroUsize::new(config.config.total_nodes.get() - 2).unwrap();
+    let total_nodes = config.config.total_nodes.get();
+    let replicated_nodes = total_nodes
+        .checked_sub(2)
+        .and_then(NonZeroUsize::new)
+        .context(TooFewNodesSnafu { total_nodes })?;
     config_builder.replication_factor(replicated_nodes);
     config_builder.identity(identity.clone());
 
     config_builder.bound_addr(Some(bound_addr.clone()));
+    config_builder.additional_bound_addrs(additional_bound_addrs);
+    config_builder.advertised_addrs(advertised_addrs);
 
     let to_connect_addrs = bootstrap_nodes
         .iter()
@@ -240,6 +399,12 @@ async fn libp2p_network_from_config<TYPES: NodeType>(
 
     config_builder.to_connect_addrs(to_connect_addrs);
 
+    if libp2p_config.mesh_auto_tune {
+        config_builder.mesh_tuning(Some(
+            MeshTuning::new(total_nodes, node_type)
+                .with_committee_topic(DA_TOPIC, config.config.da_committee_size),
+        ));
+    }
     let mesh_params =
         // NOTE I'm arbitrarily choosing these.
         match node_type {
@@ -259,23 +424,43 @@ async fn libp2p_network_from_config<TYPES: NodeType>(
         };
     config_builder.mesh_params(Some(mesh_params));
 
-    let mut all_keys = BTreeSet::new();
-    let mut da_keys = BTreeSet::new();
-    for i in 0..config.config.total_nodes.get() as u64 {
-        let privkey = TYPES::SignatureKey::generated_from_seed_indexed([0u8; 32], i).1;
-        let pub_key = TYPES::SignatureKey::from_private(&privkey);
-        if i < config.config.da_committee_size as u64 {
-            da_keys.insert(pub_key.clone());
-        }
-        all_keys.insert(pub_key);
+    let quic = &libp2p_config.quic;
+    config_builder.quic_params(QuicParams {
+        handshake_timeout: quic.handshake_timeout,
+        max_idle_timeout: quic.max_idle_timeout,
+        keep_alive_interval: quic.keep_alive_interval,
+        max_concurrent_stream_limit: quic.max_concurrent_stream_limit,
+        max_stream_data: quic.max_stream_data,
+        max_connection_data: quic.max_connection_data,
+    });
+    config_builder.peer_allowlist(libp2p_config.peer_allowlist);
+    if let Some(peer_store_dir) = &libp2p_config.peer_store_dir {
+        config_builder.peer_store_path(Some(
+            PathBuf::from(peer_store_dir).join(format!("peers-{node_index}.json")),
+        ));
     }
-    let node_config = config_builder.build().unwrap();
+
+    let all_keys: BTreeSet<_> = config
+        .config
+        .known_nodes_with_stake
+        .iter()
+        .map(TYPES::SignatureKey::get_public_key)
+        .collect();
+    // the DA committee is the one consensus elects, which may be sampled under failure domains
+    let (_, da_election_config) = election_configs::<TYPES>(&config);
+    let da_keys = <TYPES as NodeType>::Membership::create_election(
+        config.config.known_nodes_with_stake.clone(),
+        da_election_config,
+    )
+    .get_committee(TYPES::Time::genesis());
+    let node_config = config_builder.build().context(Libp2pConfigSnafu)?;
 
     #[allow(clippy::cast_possible_truncation)]
     Libp2pNetwork::new(
-        NetworkingMetricsValue::default(),
+        NetworkingMetricsValue::new(&*metrics_from_config(&config).subgroup("networking".into())),
         node_config,
         pub_key.clone(),
+        &config.config.my_own_validator_config.private_key,
         Arc::new(RwLock::new(
             bootstrap_nodes
                 .iter()
@@ -284,15 +469,75 @@ async fn libp2p_network_from_config<TYPES: NodeType>(
         )),
         bs_len,
         config.node_index as usize,
-        // NOTE: this introduces an invariant that the keys are assigned using this indexed
-        // function
         all_keys,
         None,
         da_keys.clone(),
         da_keys.contains(&pub_key),
     )
     .await
-    .unwrap()
+    .context(NetworkSnafu)
+}
+
+/// The checks of the replica of a running node, which the control endpoint runs on the payloads
+/// block builders post
+struct ReplicaPayloadValidator<TYPES: NodeType, NODE: NodeImplementation<TYPES>>(
+    SystemContextHandle<TYPES, NODE>,
+);
+
+impl<TYPES: NodeType, NODE: NodeImplementation<TYPES>> Debug
+    for ReplicaPayloadValidator<TYPES, NODE>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ReplicaPayloadValidator")
+    }
+}
+
+impl<TYPES: NodeType, NODE: NodeImplementation<TYPES>> PayloadValidator
+    for ReplicaPayloadValidator<TYPES, NODE>
+{
+    fn validate_payload(
+        &self,
+        request: PayloadValidationRequest,
+    ) -> BoxFuture<'_, Result<PayloadVerdict, String>> {
+        async move {
+            let metadata: <TYPES::BlockPayload as BlockPayload>::Metadata =
+                serde_json::from_value(request.metadata)
+                    .map_err(|e| format!("Invalid payload metadata: {e}"))?;
+            let payload = TYPES::BlockPayload::from_bytes(
+                request.encoded_transactions.into_iter(),
+                &metadata,
+            );
+            let view = TYPES::Time::new(request.view);
+            Ok(
+                match self.0.validate_payload(payload, metadata, view).await {
+                    Ok(acceptance) => PayloadVerdict::Accepted(
+                        serde_json::to_value(&acceptance).map_err(|e| e.to_string())?,
+                    ),
+                    Err(rejection) => PayloadVerdict::Rejected(rejection.to_string()),
+                },
+            )
+        }
+        .boxed()
+    }
+}
+
+/// The leaf DAG of the consensus of a running node, which the control endpoint reports
+struct ReplicaLeafDag<TYPES: NodeType, NODE: NodeImplementation<TYPES>>(
+    SystemContextHandle<TYPES, NODE>,
+);
+
+impl<TYPES: NodeType, NODE: NodeImplementation<TYPES>> Debug for ReplicaLeafDag<TYPES, NODE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ReplicaLeafDag")
+    }
+}
+
+impl<TYPES: NodeType, NODE: NodeImplementation<TYPES>> LeafDagSource
+    for ReplicaLeafDag<TYPES, NODE>
+{
+    fn leaf_dag(&self) -> BoxFuture<'_, LeafDag> {
+        self.0.get_leaf_dag().boxed()
+    }
 }
 
 /// Defines the behavior of a "run" of the network with a given configuration
@@ -310,24 +555,89 @@ pub trait RunDA<
 > where
     <TYPES as NodeType>::ValidatedState: TestableState,
     <TYPES as NodeType>::BlockPayload: TestableBlock,
-    TYPES: NodeType<Transaction = TestTransaction>,
+    TYPES::Transaction: BenchmarkableTransaction,
     Leaf<TYPES>: TestableLeaf,
-    Self: Sync,
+    Self: Sized + Send + Sync,
 {
     /// Initializes networking, returns self
+    /// # Errors
+    /// If the networks of the run cannot be created from `config`
     async fn initialize_networking(
         config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
-    ) -> Self;
+    ) -> Result<Self, InfraError>;
 
     /// Initializes the genesis state and HotShot instance; does not start HotShot consensus
-    /// # Panics if it cannot generate a genesis block, fails to initialize HotShot, or cannot
-    /// get the anchored view
+    /// # Errors
+    /// If it cannot load the genesis file or generate a genesis block, or fails to initialize
+    /// HotShot
     /// Note: sequencing leaf does not have state, so does not return state
-    async fn initialize_state_and_hotshot(&self) -> SystemContextHandle<TYPES, NODE> {
-        let initializer = hotshot::HotShotInitializer::<TYPES>::from_genesis(&TestInstanceState {})
-            .expect("Couldn't generate genesis block");
+    async fn initialize_state_and_hotshot(
+        &self,
+    ) -> Result<SystemContextHandle<TYPES, NODE>, InfraError> {
+        let genesis = load_genesis_file(&self.get_config())?;
+        let initializer = match &genesis {
+            Some(genesis) => hotshot::HotShotInitializer::<TYPES>::from_genesis_file(
+                &TestInstanceState {},
+                genesis,
+            )
+            .map_err(|e| InfraError::HotShot {
+                action: "generate the genesis block of the genesis file",
+                reason: e.to_string(),
+            })?,
+            None => hotshot::HotShotInitializer::<TYPES>::from_genesis(&TestInstanceState {})
+                .map_err(|e| InfraError::HotShot {
+                    action: "generate the genesis block",
+                    reason: e.to_string(),
+                })?,
+        };
+        self.initialize_hotshot(initializer, MemoryStorage::empty(), genesis)
+            .await
+    }
 
+    /// Initializes a HotShot instance resuming from the anchored view of `storage`, as a node
+    /// restarting after a crash does; does not start HotShot consensus
+    /// # Errors
+    /// If `storage` holds no anchored view, the genesis file cannot be loaded, or it fails to
+    /// initialize HotShot
+    async fn restart_hotshot(
+        &self,
+        storage: MemoryStorage<TYPES>,
+    ) -> Result<SystemContextHandle<TYPES, NODE>, InfraError> {
+        // the leaves a crash left uncommitted go back to storage before the anchor is read
         let config = self.get_config();
+        recover_storage::<TYPES>(&storage, &config.config.storage_sync, config.node_index)
+            .await
+            .map_err(|e| InfraError::HotShot {
+                action: "recover the write-ahead log",
+                reason: e.to_string(),
+            })?;
+        let initializer =
+            hotshot::HotShotInitializer::<TYPES>::from_storage(&storage, TestInstanceState {})
+                .await
+                .map_err(|e| InfraError::HotShot {
+                    action: "reload from storage",
+                    reason: e.to_string(),
+                })?;
+        let genesis = load_genesis_file(&config)?;
+        self.initialize_hotshot(initializer, storage, genesis).await
+    }
+
+    /// Initializes a HotShot instance from `initializer` and `storage`; does not start HotShot
+    /// consensus
+    /// # Errors
+    /// If it fails to initialize HotShot
+    async fn initialize_hotshot(
+        &self,
+        initializer: hotshot::HotShotInitializer<TYPES>,
+        storage: MemoryStorage<TYPES>,
+        genesis: Option<GenesisFile<TYPES::SignatureKey>>,
+    ) -> Result<SystemContextHandle<TYPES, NODE>, InfraError> {
+        let mut config = self.get_config();
+        let initializer = initializer.with_runtime(config.runtime);
+        if let Some(genesis) = genesis {
+            config.config.chain_id = genesis.chain_id;
+            config.config.known_nodes_with_stake = genesis.stake_table;
+        }
 
         // Get KeyPair for certificate Aggregation
         let pk = config.config.my_own_validator_config.public_key.clone();
@@ -337,14 +647,8 @@ pub trait RunDA<
         let da_network = self.get_da_channel();
         let quorum_network = self.get_quorum_channel();
 
-        // Since we do not currently pass the election config type in the NetworkConfig, this will always be the default election config
-        let quorum_election_config = config.config.election_config.clone().unwrap_or_else(|| {
-            TYPES::Membership::default_election_config(config.config.total_nodes.get() as u64)
-        });
-
-        let committee_election_config = TYPES::Membership::default_election_config(
-            config.config.da_committee_size.try_into().unwrap(),
-        );
+        let (quorum_election_config, committee_election_config) =
+            election_configs::<TYPES>(&config);
         let networks_bundle = Networks {
             quorum_network: quorum_network.clone().into(),
             da_network: da_network.clone().into(),
@@ -370,60 +674,190 @@ pub trait RunDA<
             ),
         };
 
+        let metrics =
+            ConsensusMetricsValue::new(&*metrics_from_config(&config).subgroup("consensus".into()));
         SystemContext::init(
             pk,
             sk,
             config.node_index,
             config.config,
-            MemoryStorage::empty(),
+            storage,
             memberships,
             networks_bundle,
             initializer,
-            ConsensusMetricsValue::default(),
+            metrics,
         )
         .await
-        .expect("Could not init hotshot")
-        .0
+        .map(|(handle, _)| handle)
+        .map_err(|e| InfraError::HotShot {
+            action: "init hotshot",
+            reason: e.to_string(),
+        })
     }
 
-    /// Starts HotShot consensus, returns when consensus has finished
+    /// Starts HotShot consensus at `genesis`, or after `start_delay_seconds` if there is none.
+    /// Tells `systemd` the node is ready once consensus started. Returns the results of the run
+    /// once consensus has finished, or once the run duration is up, whether consensus still
+    /// decides or not. Transactions are generated as they are sent, for the view after the
+    /// last decided one, so a run never runs out of them
+    /// # Errors
+    /// If the events of consensus stop before the run ends, or a transaction cannot be submitted
     async fn run_hotshot(
         &self,
-        context: SystemContextHandle<TYPES, NODE>,
-        transactions: &mut Vec<TestTransaction>,
+        mut context: SystemContextHandle<TYPES, NODE>,
         transactions_to_send_per_round: u64,
-    ) {
+        genesis: Option<SystemTime>,
+        control: &NodeControlHandle,
+        systemd: &SystemdNotifier,
+    ) -> Result<RunResults, InfraError> {
         let NetworkConfig {
             rounds,
             node_index,
             start_delay_seconds,
+            run_duration_seconds,
+            warmup_seconds,
+            sample_interval_seconds,
+            leaf_dag_export,
+            webhooks,
+            failure_schedule,
+            partition_schedule,
+            heartbeat_interval_seconds,
+            transaction_size,
+            load_generator,
+            soak,
+            config:
+                HotShotConfig {
+                    my_own_validator_config,
+                    known_nodes_with_stake,
+                    ..
+                },
             ..
         } = self.get_config();
+        let webhooks = webhooks.map(|webhooks| {
+            WebhookNotifier::new(
+                webhooks,
+                my_own_validator_config.public_key,
+                my_own_validator_config.private_key,
+            )
+        });
+
+        let mut failures: Vec<ScheduledFailure> = failure_schedule
+            .into_iter()
+            .filter(|failure| failure.node_index == node_index)
+            .collect();
+        // the next failure is popped off the end
+        failures.sort_by_key(|failure| std::cmp::Reverse(failure.view));
+        // the partition the orchestrator ordered the node into which is applied, by its index in
+        // the schedule
+        let mut applied_partition: Option<usize> = None;
+        if !partition_schedule.is_empty() && heartbeat_interval_seconds == 0 {
+            error!("No partition will be applied: the orchestrator orders them through heartbeats");
+        }
+        let peers: Vec<TYPES::SignatureKey> = known_nodes_with_stake
+            .iter()
+            .map(TYPES::SignatureKey::get_public_key)
+            .collect();
+        let mut partition_outcomes: Vec<PartitionOutcome> = Vec::new();
+        // when the last partition heals, until consensus decides again
+        let mut healing_at: Option<Instant> = None;
 
         let mut total_transactions_committed = 0;
         let mut total_transactions_sent = 0;
-
-        error!("Sleeping for {start_delay_seconds} seconds before starting hotshot!");
-        async_sleep(Duration::from_secs(start_delay_seconds)).await;
+        // in closed-loop mode, the rate of transactions is adjusted to what consensus sustains
+        let mut load = load_generator.map(LoadController::new);
+        let mut txn_rng = StdRng::seed_from_u64(node_index);
+
+        let start_delay = match genesis {
+            Some(genesis) => genesis
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+            None => Duration::from_secs(start_delay_seconds),
+        };
+        error!("Sleeping for {start_delay:?} before starting hotshot!");
+        async_sleep(start_delay).await;
 
         error!("Starting HotShot example!");
         let start = Instant::now();
 
+        // in soak mode, resource usage is sampled through the run to find leaks. The samples
+        // are taken from a timer of their own, so they stay evenly spaced however rarely
+        // consensus emits events, and stop once the run drops the monitor.
+        let soak = soak.map(|config| {
+            let monitor = SoakMonitor::new(config);
+            let interval = monitor.sample_interval();
+            let monitor = Arc::new(RwLock::new(monitor));
+            let sampler = Arc::downgrade(&monitor);
+            let context = context.clone();
+            async_spawn(async move {
+                loop {
+                    async_sleep(interval).await;
+                    let Some(monitor) = sampler.upgrade() else {
+                        break;
+                    };
+                    let usage = ProcessUsage::current();
+                    monitor.write().await.record(ResourceSample {
+                        elapsed: start.elapsed(),
+                        rss_bytes: usage.rss_bytes,
+                        open_fds: usage.open_fds,
+                        threads: usage.threads,
+                        tasks: Some(context.task_count() as u64),
+                        channel_depth: Some(context.queued_events() as u64),
+                    });
+                }
+            });
+            monitor
+        });
+
+        let warmup = Duration::from_secs(warmup_seconds);
+        let run_duration = run_duration_seconds.map(Duration::from_secs);
+        let sample_interval = Duration::from_secs(sample_interval_seconds);
+        // statistics are only recorded once the warm-up is over
+        let mut measurement_start = warmup.is_zero().then_some(start);
+        let mut last_sample = start;
+        let mut interval_transactions_committed = 0;
+        let mut interval_commits = 0;
+        let mut throughput_samples = Vec::new();
+
         let mut event_stream = context.get_event_stream();
         let mut anchor_view: TYPES::Time = <TYPES::Time as ConsensusTime>::genesis();
+        let mut num_views_decided = 0;
         let mut num_successful_commits = 0;
 
         context.hotshot.start_consensus().await;
+        systemd.notify_ready();
+        let mut last_decide = Instant::now();
 
         loop {
-            match event_stream.next().await {
+            // a run of a set duration ends on time even if consensus stalls, which nothing but
+            // a timer would tell; until the warm-up is over, it ends as late as it would if
+            // consensus were to decide right at the end of the warm-up
+            let event = match run_duration {
+                Some(run_duration) => {
+                    let deadline = measurement_start.unwrap_or(start + warmup) + run_duration;
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    match future::select(event_stream.next(), Box::pin(async_sleep(remaining)))
+                        .await
+                    {
+                        Either::Left((event, _)) => event,
+                        Either::Right(((), _)) => {
+                            error!("Run duration of {run_duration:?} is up, ending the run");
+                            break;
+                        }
+                    }
+                }
+                None => event_stream.next().await,
+            };
+            match event {
                 None => {
-                    panic!("Error! Event stream completed before consensus ended.");
+                    return Err(InfraError::EventStreamEnded);
                 }
-                Some(Event { event, .. }) => {
+                Some(Event { view_number, event }) => {
+                    control.write().await.status.current_view = *view_number;
                     match event {
                         EventType::Error { error } => {
-                            error!("Error in consensus: {:?}", error);
+                            let kind = error.kind();
+                            error!("Error in consensus [{kind:?}/{}]: {error}", kind.code());
+                            control.write().await.status.error_count += 1;
                             // TODO what to do here
                         }
                         EventType::Decide {
@@ -439,22 +873,124 @@ pub trait RunDA<
                                 if new_anchor >= anchor_view {
                                     anchor_view = leaf.view_number;
                                 }
-
-                                // send transactions
-                                for _ in 0..transactions_to_send_per_round {
-                                    let tx = transactions.remove(0);
-
-                                    () = context.submit_transaction(tx).await.unwrap();
+                                if let Some(webhooks) = &webhooks {
+                                    webhooks.notify(DecideNotification {
+                                        view: *leaf.view_number,
+                                        leaf_commitment: format!("{:?}", leaf.commit()),
+                                        block_size,
+                                        timestamp: unix_time_ms(),
+                                    });
+                                }
+                                let closed_loop_rate = match &mut load {
+                                    Some(load) => {
+                                        let observation = LoadObservation {
+                                            mempool_depth: context.mempool_depth().await,
+                                            decide_latency: context
+                                                .estimated_finality_latency()
+                                                .await,
+                                            transactions_committed: block_size.unwrap_or_default(),
+                                            elapsed: last_decide.elapsed(),
+                                        };
+                                        Some(load.observe(&observation))
+                                    }
+                                    None => None,
+                                };
+                                last_decide = Instant::now();
+
+                                let mut node_control = control.write().await;
+                                node_control.status.last_decided_view = *anchor_view;
+                                node_control.status.bandwidth =
+                                    context.hotshot.get_metrics().bandwidth.usage();
+                                node_control.status.leader_scores = context
+                                    .hotshot
+                                    .get_consensus()
+                                    .read()
+                                    .await
+                                    .leader_scores
+                                    .report();
+                                let transactions_to_send =
+                                    if node_control.status.transactions_paused {
+                                        0
+                                    } else {
+                                        closed_loop_rate.unwrap_or(transactions_to_send_per_round)
+                                    };
+                                drop(node_control);
+
+                                // send transactions, destined for a few views after the
+                                // decided one, unless the load generator sends them
+                                let window = if load.is_some() {
+                                    ViewWindow::default()
+                                } else {
+                                    ViewWindow::starting_at(*anchor_view + 4)
+                                };
+                                for _ in 0..transactions_to_send {
+                                    let tx = TYPES::Transaction::create_random_transaction(
+                                        &mut txn_rng,
+                                        transaction_size,
+                                        window,
+                                    );
+
+                                    context.submit_transaction(tx).await.map_err(|e| {
+                                        InfraError::HotShot {
+                                            action: "submit a transaction",
+                                            reason: e.to_string(),
+                                        }
+                                    })?;
                                     total_transactions_sent += 1;
                                 }
+                                control.write().await.status.transactions_sent =
+                                    total_transactions_sent;
+                            }
+
+                            if let Some(healed) =
+                                healing_at.filter(|healed| *healed <= Instant::now())
+                            {
+                                healing_at = None;
+                                error!(
+                                    "Consensus resumed {:?} after the partition healed",
+                                    healed.elapsed()
+                                );
+                                if let Some(outcome) = partition_outcomes.last_mut() {
+                                    outcome.recovered_after = Some(healed.elapsed());
+                                }
+                            }
+
+                            if measurement_start.is_none() && start.elapsed() >= warmup {
+                                info!("Warm-up finished after {:?}", start.elapsed());
+                                let now = Instant::now();
+                                measurement_start = Some(now);
+                                last_sample = now;
                             }
 
-                            if let Some(size) = block_size {
+                            if let Some(measurement_start) = measurement_start {
+                                let size = block_size.unwrap_or_default();
                                 total_transactions_committed += size;
+                                num_successful_commits += leaf_chain.len();
+                                interval_transactions_committed += size;
+                                interval_commits += leaf_chain.len();
+
+                                if !sample_interval.is_zero()
+                                    && last_sample.elapsed() >= sample_interval
+                                {
+                                    throughput_samples.push(ThroughputSample {
+                                        elapsed: measurement_start.elapsed(),
+                                        interval: last_sample.elapsed(),
+                                        transactions_committed: interval_transactions_committed,
+                                        commits: interval_commits,
+                                    });
+                                    interval_transactions_committed = 0;
+                                    interval_commits = 0;
+                                    last_sample = Instant::now();
+                                }
                             }
 
-                            num_successful_commits += leaf_chain.len();
-                            if num_successful_commits >= rounds {
+                            num_views_decided += leaf_chain.len();
+                            let finished = match run_duration {
+                                Some(run_duration) => measurement_start
+                                    .is_some_and(|started| started.elapsed() >= run_duration),
+                                None => num_views_decided >= rounds,
+                            };
+                            if finished {
                                 break;
                             }
 
@@ -464,20 +1000,245 @@ pub trait RunDA<
                             // when we make progress, submit new events
                         }
                         EventType::ReplicaViewTimeout { view_number } => {
-                            warn!("Timed out as a replicas in view {:?}", view_number);
+                            if let Some(summary) = log_throttle::check("replica_view_timeout") {
+                                warn!("Timed out as a replicas in view {view_number:?}{summary}");
+                            }
+                        }
+                        EventType::SafetyViolation { evidence } => {
+                            error!("Safety violation, this node has halted: {evidence:?}");
                         }
                         EventType::NextLeaderViewTimeout { view_number } => {
-                            warn!("Timed out as the next leader in view {:?}", view_number);
+                            if let Some(summary) = log_throttle::check("next_leader_view_timeout") {
+                                warn!(
+                                    "Timed out as the next leader in view {view_number:?}{summary}"
+                                );
+                            }
+                        }
+                        EventType::ProposalMissing { view, leader } => {
+                            if let Some(summary) = log_throttle::check("proposal_missing") {
+                                warn!(
+                                    "No proposal from leader {leader:?} in view {view:?}{summary}"
+                                );
+                            }
+                        }
+                        EventType::AvailabilityAlarm {
+                            view,
+                            samples,
+                            failed_samples,
+                            ..
+                        } => {
+                            error!(
+                                "{failed_samples} of {samples} VID shares sampled for the \
+                                 payload of view {view:?} are unavailable"
+                            );
+                        }
+                        EventType::DAFallback {
+                            failed_views,
+                            until_view,
+                        } => {
+                            warn!(
+                                "No DA certificate for {failed_views} views, DA falls back to \
+                                 the whole quorum until view {until_view:?}"
+                            );
+                        }
+                        EventType::EpochChange { certificate } => {
+                            info!(
+                                "Epoch {} certified, starting at view {:?}",
+                                certificate.data.epoch, certificate.data.first_view
+                            );
+                        }
+                        EventType::ParameterChangeScheduled { epoch, parameters } => {
+                            info!("Consensus parameters change at epoch {epoch}: {parameters:?}");
+                        }
+                        EventType::VoteProgress {
+                            view,
+                            kind,
+                            collected_weight,
+                            threshold,
+                            signers,
+                            missing,
+                        } => {
+                            debug!(
+                                "{kind:?} votes of view {view:?} weigh {collected_weight} of the \
+                                 {threshold} needed, from {} voters, missing {missing:?}",
+                                signers.len()
+                            );
+                        }
+                        EventType::Stall { diagnostics } => {
+                            error!(
+                                "Stalled in view {:?} for {:?}: {} of {} peers heard from, \
+                                 votes {:?}, timers {:?}, next leader {:?}, last proposal \
+                                 {:?}, silent peers {:?}",
+                                diagnostics.view,
+                                diagnostics.stalled_for,
+                                diagnostics.peers.heard_from,
+                                diagnostics.peers.committee_size,
+                                diagnostics.votes,
+                                diagnostics.timers,
+                                diagnostics.leader,
+                                diagnostics.last_proposal,
+                                diagnostics.peers.silent
+                            );
                         }
                         _ => {}
                     }
+
+                    let mut node_control = control.write().await;
+                    if node_control.take_snapshot_request() {
+                        let prefix = format!("snapshot-{node_index}-view-{}", *view_number);
+                        match serde_json::to_string_pretty(&node_control.status) {
+                            Ok(status) => {
+                                if let Err(e) = fs::write(format!("{prefix}-status.json"), status) {
+                                    error!("Failed to write snapshot: {e}");
+                                }
+                            }
+                            Err(e) => error!("Failed to serialize node status: {e}"),
+                        }
+                        write_leaf_dag(&context.get_leaf_dag().await, &prefix);
+                        // readable offline with `hotshot-storage`
+                        match bincode_opts().serialize(&context.storage().get_full_state().await) {
+                            Ok(storage) => {
+                                if let Err(e) = fs::write(format!("{prefix}-storage.bin"), storage)
+                                {
+                                    error!("Failed to write snapshot: {e}");
+                                }
+                            }
+                            Err(e) => error!("Failed to serialize storage: {e}"),
+                        }
+                        info!("Wrote snapshot {prefix}");
+                    }
+                    if node_control.shutdown_requested() {
+                        error!("Shutting down as requested through the control endpoint");
+                        break;
+                    }
+
+                    // the orchestrator orders every node into and out of the partitions of the
+                    // schedule together, in its replies to their heartbeats
+                    let order = node_control.partition_order().cloned();
+                    drop(node_control);
+                    if order.as_ref().map(|order| order.index) != applied_partition {
+                        match &order {
+                            Some(order) => error!(
+                                "Partitioned from every node but {:?} in view {}, as ordered",
+                                order.reachable, *view_number
+                            ),
+                            None => error!("Healing the partition, as ordered"),
+                        }
+                        let reachable = order.as_ref().map(|order| {
+                            order
+                                .reachable
+                                .iter()
+                                .filter_map(|node| usize::try_from(*node).ok())
+                                .filter_map(|node| peers.get(node).cloned())
+                                .collect()
+                        });
+                        if let Err(e) = context.set_network_partition(reachable).await {
+                            error!("Failed to apply the partition: {e}");
+                        }
+                        applied_partition = order.map(|order| order.index);
+                        match applied_partition.and_then(|index| partition_schedule.get(index)) {
+                            Some(partition) => {
+                                healing_at = None;
+                                partition_outcomes.push(PartitionOutcome {
+                                    view: partition.view,
+                                    duration: Duration::from_secs(partition.duration_seconds),
+                                    recovered_after: None,
+                                });
+                            }
+                            None => healing_at = Some(Instant::now()),
+                        }
+                    }
+
+                    if let Some(failure) = failures
+                        .last()
+                        .copied()
+                        .filter(|failure| *view_number >= failure.view)
+                    {
+                        failures.pop();
+                        error!(
+                            "Crashing in view {} for {}s, as scheduled",
+                            *view_number, failure.down_seconds
+                        );
+                        let storage = context.storage().clone();
+                        context.shut_down().await;
+                        async_sleep(Duration::from_secs(failure.down_seconds)).await;
+
+                        error!("Restarting from storage");
+                        let restarted = Self::initialize_networking(self.get_config()).await;
+                        context = restarted.restart_hotshot(storage).await;
+                        event_stream = context.get_event_stream();
+                        context.hotshot.start_consensus().await;
+                    }
                 }
             }
         }
 
         // Output run results
-        let total_time_elapsed = start.elapsed();
-        error!("[{node_index}]: {rounds} rounds completed in {total_time_elapsed:?} - Total transactions sent: {total_transactions_sent} - Total transactions committed: {total_transactions_committed} - Total commitments: {num_successful_commits}");
+        let total_time_elapsed =
+            measurement_start.map_or(Duration::ZERO, |started| started.elapsed());
+        error!("[{node_index}]: {num_views_decided} rounds completed in {:?} ({warmup:?} warm-up) - Total transactions sent: {total_transactions_sent} - Total transactions committed: {total_transactions_committed} - Total commitments: {num_successful_commits}", start.elapsed());
+        if healing_at.is_some() {
+            error!("[{node_index}]: consensus did not resume after the last partition healed");
+        }
+        let sustainable_tps = load.as_ref().and_then(LoadController::sustainable_tps);
+        match (&load, sustainable_tps) {
+            (Some(load), Some(tps)) => error!(
+                "[{node_index}]: sustainable throughput of {tps:.2} transactions/s, \
+                 submitting {} transactions per decide",
+                load.rate()
+            ),
+            (Some(load), None) => error!(
+                "[{node_index}]: consensus kept up with every rate, up to {} transactions per \
+                 decide; raise `max_rate` to find the saturation point",
+                load.rate()
+            ),
+            (None, _) => {}
+        }
+        for sample in &throughput_samples {
+            error!(
+                "[{node_index}]: at {:?}: {} transactions, {} commits in {:?}",
+                sample.elapsed, sample.transactions_committed, sample.commits, sample.interval
+            );
+        }
+
+        let soak = match soak {
+            Some(monitor) => Some(monitor.read().await.clone()),
+            None => None,
+        };
+        let resource_leaks = soak.as_ref().map(SoakMonitor::leaks).unwrap_or_default();
+        for leak in &resource_leaks {
+            error!("[{node_index}]: leak detected, {leak}");
+        }
+        if let Some(soak) = &soak {
+            if resource_leaks.is_empty() {
+                info!(
+                    "[{node_index}]: no leak detected over {} resource samples",
+                    soak.samples().len()
+                );
+            }
+        }
+
+        if let Some(prefix) = leaf_dag_export {
+            write_leaf_dag(
+                &context.get_leaf_dag().await,
+                &format!("{prefix}-{node_index}"),
+            );
+        }
+
+        context.shut_down().await;
+
+        Ok(RunResults {
+            node_index,
+            num_successful_commits,
+            total_transactions_sent,
+            total_transactions_committed,
+            total_time_elapsed,
+            throughput_samples,
+            sustainable_tps,
+            partitions: partition_outcomes,
+            resource_samples: soak.map(|soak| soak.samples().to_vec()).unwrap_or_default(),
+            resource_leaks,
+        })
     }
 
     /// Returns the da network for this run
@@ -490,6 +1251,21 @@ pub trait RunDA<
     fn get_config(&self) -> NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>;
 }
 
+/// Write `leaf_dag` to `{prefix}.dot` and `{prefix}.json`
+fn write_leaf_dag(leaf_dag: &LeafDag, prefix: &str) {
+    if let Err(e) = fs::write(format!("{prefix}.dot"), leaf_dag.to_dot()) {
+        error!("Failed to write leaf DAG: {e}");
+    }
+    match leaf_dag.to_json() {
+        Ok(json) => {
+            if let Err(e) = fs::write(format!("{prefix}.json"), json) {
+                error!("Failed to write leaf DAG: {e}");
+            }
+        }
+        Err(e) => error!("Failed to serialize leaf DAG: {e}"),
+    }
+}
+
 // WEB SERVER
 
 /// Represents a web server-based run
@@ -497,65 +1273,69 @@ pub struct WebServerDARun<TYPES: NodeType> {
     /// the network configuration
     config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
     /// quorum channel
-    quorum_channel: WebServerNetwork<TYPES>,
+    quorum_channel: RecordingNetwork<WebServerNetwork<TYPES>>,
     /// data availability channel
-    da_channel: WebServerNetwork<TYPES>,
+    da_channel: RecordingNetwork<WebServerNetwork<TYPES>>,
 }
 
 #[async_trait]
 impl<
-        TYPES: NodeType<
-            Transaction = TestTransaction,
-            BlockPayload = TestBlockPayload,
-            BlockHeader = TestBlockHeader,
-            InstanceState = TestInstanceState,
-        >,
+        TYPES: NodeType<InstanceState = TestInstanceState>,
         NODE: NodeImplementation<
             TYPES,
-            QuorumNetwork = WebServerNetwork<TYPES>,
-            CommitteeNetwork = WebServerNetwork<TYPES>,
+            QuorumNetwork = RecordingNetwork<WebServerNetwork<TYPES>>,
+            CommitteeNetwork = RecordingNetwork<WebServerNetwork<TYPES>>,
             Storage = MemoryStorage<TYPES>,
         >,
-    > RunDA<TYPES, WebServerNetwork<TYPES>, WebServerNetwork<TYPES>, NODE> for WebServerDARun<TYPES>
+    >
+    RunDA<
+        TYPES,
+        RecordingNetwork<WebServerNetwork<TYPES>>,
+        RecordingNetwork<WebServerNetwork<TYPES>>,
+        NODE,
+    > for WebServerDARun<TYPES>
 where
     <TYPES as NodeType>::ValidatedState: TestableState,
     <TYPES as NodeType>::BlockPayload: TestableBlock,
+    TYPES::Transaction: BenchmarkableTransaction,
     Leaf<TYPES>: TestableLeaf,
     Self: Sync,
 {
     async fn initialize_networking(
         config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
-    ) -> WebServerDARun<TYPES> {
+    ) -> Result<WebServerDARun<TYPES>, InfraError> {
         // Get our own key
         let pub_key = config.config.my_own_validator_config.public_key.clone();
 
         // extract values from config (for DA network)
-        let WebServerConfig {
-            url,
-            wait_between_polls,
-        }: WebServerConfig = config.clone().da_web_server_config.unwrap();
+        let da_web_server_config = da_web_server_config(&config)?;
 
         // create and wait for underlying network
         let underlying_quorum_network =
-            webserver_network_from_config::<TYPES>(config.clone(), pub_key.clone());
+            webserver_network_from_config::<TYPES>(config.clone(), pub_key.clone())?;
 
         underlying_quorum_network.wait_for_ready().await;
 
-        let da_channel: WebServerNetwork<TYPES> =
-            WebServerNetwork::create(url.clone(), wait_between_polls, pub_key.clone(), true);
+        let da_channel = WebServerNetwork::create_sharded(
+            da_web_server_config.urls(),
+            da_web_server_config.wait_between_polls,
+            pub_key.clone(),
+            true,
+            config.config.chain_id,
+        );
 
-        WebServerDARun {
+        Ok(WebServerDARun {
+            quorum_channel: record_network(&config, "quorum", underlying_quorum_network)?,
+            da_channel: record_network(&config, "da", da_channel)?,
             config,
-            quorum_channel: underlying_quorum_network,
-            da_channel,
-        }
+        })
     }
 
-    fn get_da_channel(&self) -> WebServerNetwork<TYPES> {
+    fn get_da_channel(&self) -> RecordingNetwork<WebServerNetwork<TYPES>> {
         self.da_channel.clone()
     }
 
-    fn get_quorum_channel(&self) -> WebServerNetwork<TYPES> {
+    fn get_quorum_channel(&self) -> RecordingNetwork<WebServerNetwork<TYPES>> {
         self.quorum_channel.clone()
     }
 
@@ -571,61 +1351,64 @@ pub struct Libp2pDARun<TYPES: NodeType> {
     /// the network configuration
     config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
     /// quorum channel
-    quorum_channel: Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>,
+    quorum_channel: RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>>,
     /// data availability channel
-    da_channel: Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>,
+    da_channel: RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>>,
 }
 
 #[async_trait]
 impl<
-        TYPES: NodeType<
-            Transaction = TestTransaction,
-            BlockPayload = TestBlockPayload,
-            BlockHeader = TestBlockHeader,
-            InstanceState = TestInstanceState,
-        >,
+        TYPES: NodeType<InstanceState = TestInstanceState>,
         NODE: NodeImplementation<
             TYPES,
-            QuorumNetwork = Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>,
-            CommitteeNetwork = Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>,
+            QuorumNetwork = RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>>,
+            CommitteeNetwork = RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>>,
             Storage = MemoryStorage<TYPES>,
         >,
     >
     RunDA<
         TYPES,
-        Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>,
-        Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>,
+        RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>>,
+        RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>>,
         NODE,
     > for Libp2pDARun<TYPES>
 where
     <TYPES as NodeType>::ValidatedState: TestableState,
     <TYPES as NodeType>::BlockPayload: TestableBlock,
+    TYPES::Transaction: BenchmarkableTransaction,
     Leaf<TYPES>: TestableLeaf,
     Self: Sync,
 {
     async fn initialize_networking(
         config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
-    ) -> Libp2pDARun<TYPES> {
+    ) -> Result<Libp2pDARun<TYPES>, InfraError> {
         let pub_key = config.config.my_own_validator_config.public_key.clone();
 
         // create and wait for underlying network
-        let quorum_channel = libp2p_network_from_config::<TYPES>(config.clone(), pub_key).await;
+        let quorum_channel = libp2p_network_from_config::<TYPES>(config.clone(), pub_key).await?;
 
-        let da_channel = quorum_channel.clone();
         quorum_channel.wait_for_ready().await;
 
-        Libp2pDARun {
+        // both channels go through the same network, so they are recorded to a single trace
+        let quorum_channel = record_network(&config, "all", quorum_channel)?;
+        let da_channel = quorum_channel.clone();
+
+        Ok(Libp2pDARun {
             config,
             quorum_channel,
             da_channel,
-        }
+        })
     }
 
-    fn get_da_channel(&self) -> Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey> {
+    fn get_da_channel(
+        &self,
+    ) -> RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>> {
         self.da_channel.clone()
     }
 
-    fn get_quorum_channel(&self) -> Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey> {
+    fn get_quorum_channel(
+        &self,
+    ) -> RecordingNetwork<Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey>> {
         self.quorum_channel.clone()
     }
 
@@ -641,35 +1424,37 @@ pub struct CombinedDARun<TYPES: NodeType> {
     /// the network configuration
     config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
     /// quorum channel
-    quorum_channel: CombinedNetworks<TYPES>,
+    quorum_channel: RecordingNetwork<CombinedNetworks<TYPES>>,
     /// data availability channel
-    da_channel: CombinedNetworks<TYPES>,
+    da_channel: RecordingNetwork<CombinedNetworks<TYPES>>,
 }
 
 #[async_trait]
 impl<
-        TYPES: NodeType<
-            Transaction = TestTransaction,
-            BlockPayload = TestBlockPayload,
-            BlockHeader = TestBlockHeader,
-            InstanceState = TestInstanceState,
-        >,
+        TYPES: NodeType<InstanceState = TestInstanceState>,
         NODE: NodeImplementation<
             TYPES,
             Storage = MemoryStorage<TYPES>,
-            QuorumNetwork = CombinedNetworks<TYPES>,
-            CommitteeNetwork = CombinedNetworks<TYPES>,
+            QuorumNetwork = RecordingNetwork<CombinedNetworks<TYPES>>,
+            CommitteeNetwork = RecordingNetwork<CombinedNetworks<TYPES>>,
         >,
-    > RunDA<TYPES, CombinedNetworks<TYPES>, CombinedNetworks<TYPES>, NODE> for CombinedDARun<TYPES>
+    >
+    RunDA<
+        TYPES,
+        RecordingNetwork<CombinedNetworks<TYPES>>,
+        RecordingNetwork<CombinedNetworks<TYPES>>,
+        NODE,
+    > for CombinedDARun<TYPES>
 where
     <TYPES as NodeType>::ValidatedState: TestableState,
     <TYPES as NodeType>::BlockPayload: TestableBlock,
+    TYPES::Transaction: BenchmarkableTransaction,
     Leaf<TYPES>: TestableLeaf,
     Self: Sync,
 {
     async fn initialize_networking(
         config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
-    ) -> CombinedDARun<TYPES> {
+    ) -> Result<CombinedDARun<TYPES>, InfraError> {
         // generate our own key
         let (pub_key, _privkey) =
             <<TYPES as NodeType>::SignatureKey as SignatureKey>::generated_from_seed_indexed(
@@ -679,21 +1464,24 @@ where
 
         // create and wait for libp2p network
         let libp2p_underlying_quorum_network =
-            libp2p_network_from_config::<TYPES>(config.clone(), pub_key.clone()).await;
+            libp2p_network_from_config::<TYPES>(config.clone(), pub_key.clone()).await?;
 
         libp2p_underlying_quorum_network.wait_for_ready().await;
 
         // extract values from config (for webserver DA network)
-        let WebServerConfig {
-            url,
-            wait_between_polls,
-        }: WebServerConfig = config.clone().da_web_server_config.unwrap();
+        let da_web_server_config = da_web_server_config(&config)?;
 
         // create and wait for underlying webserver network
         let web_quorum_network =
-            webserver_network_from_config::<TYPES>(config.clone(), pub_key.clone());
-
-        let web_da_network = WebServerNetwork::create(url, wait_between_polls, pub_key, true);
+            webserver_network_from_config::<TYPES>(config.clone(), pub_key.clone())?;
+
+        let web_da_network = WebServerNetwork::create_sharded(
+            da_web_server_config.urls(),
+            da_web_server_config.wait_between_polls,
+            pub_key,
+            true,
+            config.config.chain_id,
+        );
 
         web_quorum_network.wait_for_ready().await;
 
@@ -702,24 +1490,179 @@ where
         let da_channel = CombinedNetworks::new(Arc::new(UnderlyingCombinedNetworks(
             web_da_network.clone(),
             libp2p_underlying_quorum_network.clone(),
-        )));
+        )))
+        .with_view_change_relay(config.web_server_view_change_relay);
         let quorum_channel = CombinedNetworks::new(Arc::new(UnderlyingCombinedNetworks(
             web_quorum_network.clone(),
             libp2p_underlying_quorum_network.clone(),
-        )));
+        )))
+        .with_view_change_relay(config.web_server_view_change_relay);
 
-        CombinedDARun {
+        Ok(CombinedDARun {
+            quorum_channel: record_network(&config, "quorum", quorum_channel)?,
+            da_channel: record_network(&config, "da", da_channel)?,
             config,
-            quorum_channel,
-            da_channel,
+        })
+    }
+
+    fn get_da_channel(&self) -> RecordingNetwork<CombinedNetworks<TYPES>> {
+        self.da_channel.clone()
+    }
+
+    fn get_quorum_channel(&self) -> RecordingNetwork<CombinedNetworks<TYPES>> {
+        self.quorum_channel.clone()
+    }
+
+    fn get_config(&self) -> NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType> {
+        self.config.clone()
+    }
+}
+
+// Mixed networks
+
+/// A network a channel of [`MixedDARun`] can run over
+#[async_trait]
+pub trait ExampleNetwork<TYPES: NodeType>:
+    ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey> + Clone + Debug + Sized
+{
+    /// The network, as named in the config
+    const NETWORK_TYPE: NetworkType;
+
+    /// Create the network of the DA channel if `is_da`, of the quorum channel otherwise, and
+    /// wait for it to be ready
+    /// # Errors
+    /// If the network cannot be created from `config`
+    async fn from_config(
+        config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+        pub_key: TYPES::SignatureKey,
+        is_da: bool,
+    ) -> Result<Self, InfraError>;
+}
+
+#[async_trait]
+impl<TYPES: NodeType> ExampleNetwork<TYPES> for WebServerNetwork<TYPES> {
+    const NETWORK_TYPE: NetworkType = NetworkType::WebServer;
+
+    async fn from_config(
+        config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+        pub_key: TYPES::SignatureKey,
+        is_da: bool,
+    ) -> Result<Self, InfraError> {
+        let web_server_config = if is_da {
+            da_web_server_config(&config)?
+        } else {
+            config.web_server_config.context(MissingConfigSnafu {
+                section: "web server",
+            })?
+        };
+        let network = WebServerNetwork::create_sharded(
+            web_server_config.urls(),
+            web_server_config.wait_between_polls,
+            pub_key,
+            is_da,
+            config.config.chain_id,
+        );
+        network.wait_for_ready().await;
+        Ok(network)
+    }
+}
+
+#[async_trait]
+impl<TYPES: NodeType> ExampleNetwork<TYPES> for Libp2pNetwork<Message<TYPES>, TYPES::SignatureKey> {
+    const NETWORK_TYPE: NetworkType = NetworkType::Libp2p;
+
+    async fn from_config(
+        config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+        pub_key: TYPES::SignatureKey,
+        _is_da: bool,
+    ) -> Result<Self, InfraError> {
+        let network = libp2p_network_from_config::<TYPES>(config, pub_key).await?;
+        network.wait_for_ready().await;
+        Ok(network)
+    }
+}
+
+/// Represents a run with the quorum and DA channels over different networks, e.g. libp2p for
+/// votes and the web server for bulk data
+pub struct MixedDARun<TYPES: NodeType, QUORUMNET, DANET> {
+    /// the network configuration
+    config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+    /// quorum channel
+    quorum_channel: RecordingNetwork<QUORUMNET>,
+    /// data availability channel
+    da_channel: RecordingNetwork<DANET>,
+}
+
+/// Check the network `config` asks for a channel to run over is `actual`
+/// # Errors
+/// If it is another network
+fn check_network_type(
+    channel: &'static str,
+    configured: Option<NetworkType>,
+    actual: NetworkType,
+) -> Result<(), InfraError> {
+    match configured {
+        Some(configured) if configured != actual => Err(InfraError::NetworkTypeMismatch {
+            channel,
+            configured,
+            actual,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[async_trait]
+impl<
+        TYPES: NodeType<InstanceState = TestInstanceState>,
+        QUORUMNET: ExampleNetwork<TYPES>,
+        DANET: ExampleNetwork<TYPES>,
+        NODE: NodeImplementation<
+            TYPES,
+            QuorumNetwork = RecordingNetwork<QUORUMNET>,
+            CommitteeNetwork = RecordingNetwork<DANET>,
+            Storage = MemoryStorage<TYPES>,
+        >,
+    > RunDA<TYPES, RecordingNetwork<DANET>, RecordingNetwork<QUORUMNET>, NODE>
+    for MixedDARun<TYPES, QUORUMNET, DANET>
+where
+    <TYPES as NodeType>::ValidatedState: TestableState,
+    <TYPES as NodeType>::BlockPayload: TestableBlock,
+    TYPES::Transaction: BenchmarkableTransaction,
+    Leaf<TYPES>: TestableLeaf,
+    Self: Sync,
+{
+    async fn initialize_networking(
+        config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+    ) -> Result<MixedDARun<TYPES, QUORUMNET, DANET>, InfraError> {
+        check_network_type(
+            "quorum",
+            config.quorum_network_type,
+            QUORUMNET::NETWORK_TYPE,
+        )?;
+        check_network_type("DA", config.da_network_type, DANET::NETWORK_TYPE)?;
+        // one node cannot run two libp2p networks on the same port
+        if QUORUMNET::NETWORK_TYPE == DANET::NETWORK_TYPE {
+            return Err(InfraError::SameNetwork {
+                network: QUORUMNET::NETWORK_TYPE,
+            });
         }
+        let pub_key = config.config.my_own_validator_config.public_key.clone();
+
+        let quorum_channel = QUORUMNET::from_config(config.clone(), pub_key.clone(), false).await?;
+        let da_channel = DANET::from_config(config.clone(), pub_key, true).await?;
+
+        Ok(MixedDARun {
+            quorum_channel: record_network(&config, "quorum", quorum_channel)?,
+            da_channel: record_network(&config, "da", da_channel)?,
+            config,
+        })
     }
 
-    fn get_da_channel(&self) -> CombinedNetworks<TYPES> {
+    fn get_da_channel(&self) -> RecordingNetwork<DANET> {
         self.da_channel.clone()
     }
 
-    fn get_quorum_channel(&self) -> CombinedNetworks<TYPES> {
+    fn get_quorum_channel(&self) -> RecordingNetwork<QUORUMNET> {
         self.quorum_channel.clone()
     }
 
@@ -728,16 +1671,48 @@ where
     }
 }
 
+/// Wrap `network`, the `channel` channel of this node, to record its messages to a trace in the
+/// `network_trace_dir` of `config`, if set
+/// # Errors
+/// If the trace file cannot be created
+fn record_network<N, K: SignatureKey, E: ElectionConfig>(
+    config: &NetworkConfig<K, E>,
+    channel: &str,
+    network: N,
+) -> Result<RecordingNetwork<N>, InfraError> {
+    match &config.network_trace_dir {
+        Some(dir) => {
+            let trace =
+                PathBuf::from(dir).join(format!("node-{}-{channel}.trace", config.node_index));
+            RecordingNetwork::new(network, trace).context(NetworkSnafu)
+        }
+        None => Ok(RecordingNetwork::unrecorded(network)),
+    }
+}
+
+/// Load the genesis file `config` starts the network from, if any
+/// # Errors
+/// if the genesis file cannot be loaded
+fn load_genesis_file<K: SignatureKey, E: ElectionConfig>(
+    config: &NetworkConfig<K, E>,
+) -> Result<Option<GenesisFile<K>>, InfraError> {
+    config
+        .genesis_file
+        .as_ref()
+        .map(|genesis_file| {
+            GenesisFile::from_file(genesis_file).context(GenesisSnafu {
+                genesis_file: genesis_file.clone(),
+            })
+        })
+        .transpose()
+}
+
 /// Main entry point for validators
-/// # Panics
-/// if unable to get the local ip address
+/// # Errors
+/// if the config is invalid or its networks cannot be created, no local ip address is found,
+/// the preflight checks fail, HotShot fails, or a soak run finds resources leaking
 pub async fn main_entry_point<
-    TYPES: NodeType<
-        Transaction = TestTransaction,
-        BlockPayload = TestBlockPayload,
-        BlockHeader = TestBlockHeader,
-        InstanceState = TestInstanceState,
-    >,
+    TYPES: NodeType<InstanceState = TestInstanceState>,
     DACHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey> + Debug,
     QUORUMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey> + Debug,
     NODE: NodeImplementation<
@@ -749,98 +1724,365 @@ pub async fn main_entry_point<
     RUNDA: RunDA<TYPES, DACHANNEL, QUORUMCHANNEL, NODE>,
 >(
     args: ValidatorArgs,
-) where
+) -> Result<(), InfraError>
+where
     <TYPES as NodeType>::ValidatedState: TestableState,
     <TYPES as NodeType>::BlockPayload: TestableBlock,
+    TYPES::Transaction: BenchmarkableTransaction,
     Leaf<TYPES>: TestableLeaf,
 {
-    setup_logging();
+    logging::setup_logging();
     setup_backtrace();
 
+    if let Some(config_file) = &args.validate_config {
+        return validate_config::<TYPES>(config_file);
+    }
+
     error!("Starting validator");
 
-    // see what our public identity will be
-    let public_ip = match args.public_ip {
-        Some(ip) => ip,
-        None => local_ip_address::local_ip().unwrap(),
+    // see what our public identity will be: every address we are reachable on
+    let mut public_ips = match args.public_ip {
+        Some(ip) => vec![ip],
+        None => [local_ip_address::local_ip(), local_ip_address::local_ipv6()]
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect(),
     };
+    public_ips.extend(args.additional_ips.iter().copied());
+    if public_ips.is_empty() {
+        return Err(InfraError::NoLocalIp);
+    }
+    let identity = public_ips
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let orchestrator_client: OrchestratorClient = OrchestratorClient::new(args.clone(), identity);
 
-    let orchestrator_client: OrchestratorClient =
-        OrchestratorClient::new(args.clone(), public_ip.to_string());
+    let systemd = SystemdNotifier::from_env();
+    let control = NodeControlHandle::default();
+    if let Some(log) = logging::log_control() {
+        control.write().await.set_log_control(log);
+    }
+    match (args.control_url.clone(), args.control_token.clone()) {
+        (Some(control_url), Some(token)) => {
+            let control = control.clone();
+            async_spawn(async move {
+                if let Err(e) = run_control_server(control, control_url, token).await {
+                    error!("Control endpoint failed: {e}");
+                }
+            });
+        }
+        (Some(_), None) => {
+            error!("Not serving the control endpoint: it needs a token, see --control-token")
+        }
+        (None, _) => {}
+    }
 
-    // conditionally save/load config from file or orchestrator
-    let (mut run_config, source) =
-        NetworkConfig::<TYPES::SignatureKey, TYPES::ElectionConfigType>::from_file_or_orchestrator(
-            &orchestrator_client,
-            args.clone().network_config_file,
+    // whether a soak run found a leak, which fails the validator
+    let mut leaked = false;
+    loop {
+        // conditionally save/load config from file or orchestrator
+        let (mut run_config, source) = NetworkConfig::<
+            TYPES::SignatureKey,
+            TYPES::ElectionConfigType,
+        >::from_file_or_orchestrator(
+            &orchestrator_client, args.clone().network_config_file
         )
         .await;
 
-    let node_index = run_config.node_index;
-    error!("Retrieved config; our node index is {node_index}");
+        let node_index = run_config.node_index;
+        error!("Retrieved config; our node index is {node_index}");
+        log_throttle::configure(log_throttle_from_config(&run_config.log_throttle));
+        // where to listen is up to each node, the orchestrator tells the others our public_ips
+        if let Some(libp2p_config) = run_config.libp2p_config.as_mut() {
+            libp2p_config.bind_ips = args.bind_ips.clone();
+            libp2p_config.advertised_ips = public_ips.clone();
+        }
 
-    // one more round of orchestrator here to get peer's public key/config
-    let updated_config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType> =
-        orchestrator_client
-            .post_and_wait_all_public_keys::<TYPES::SignatureKey, TYPES::ElectionConfigType>(
-                run_config.node_index,
-                run_config.config.my_own_validator_config.public_key.clone(),
-            )
-            .await;
-    run_config.config.known_nodes_with_stake = updated_config.config.known_nodes_with_stake;
-
-    error!("Initializing networking");
-    let run = RUNDA::initialize_networking(run_config.clone()).await;
-    let hotshot = run.initialize_state_and_hotshot().await;
-
-    // pre-generate transactions
-    let NetworkConfig {
-        transaction_size,
-        rounds,
-        transactions_per_round,
-        node_index,
-        config: HotShotConfig { total_nodes, .. },
-        ..
-    } = run_config;
-
-    let mut txn_rng = StdRng::seed_from_u64(node_index);
-    let transactions_to_send_per_round =
-        calculate_num_tx_per_round(node_index, total_nodes.get(), transactions_per_round);
-    let mut transactions = Vec::new();
-
-    for round in 0..rounds {
-        for _ in 0..transactions_to_send_per_round {
-            let mut txn = <TYPES::ValidatedState>::create_random_transaction(
-                None,
-                &mut txn_rng,
-                transaction_size as u64,
+        let run_index = match source {
+            NetworkConfigSource::Orchestrator => orchestrator_client.get_run_index().await,
+            NetworkConfigSource::File => None,
+        };
+
+        // a node joining a run which already started takes the stake table of the running
+        // nodes from a snapshot, rather than waiting for every node to post its key
+        let joins_late = matches!(source, NetworkConfigSource::Orchestrator)
+            && orchestrator_client.run_started().await;
+        // one more round of orchestrator here to get peer's public key/config
+        let public_key = run_config.config.my_own_validator_config.public_key.clone();
+        let updated_config: NetworkConfig<TYPES::SignatureKey, TYPES::ElectionConfigType> =
+            if joins_late {
+                error!("The run already started, joining it late");
+                orchestrator_client
+                    .post_public_key(node_index, &public_key)
+                    .await
+                    .context(OrchestratorRequestSnafu)?;
+                orchestrator_client
+                    .get_config_snapshot()
+                    .await
+                    .context(OrchestratorRequestSnafu)?
+            } else {
+                orchestrator_client
+                    .post_and_wait_all_public_keys(node_index, public_key)
+                    .await
+                    .context(OrchestratorRequestSnafu)?
+            };
+        run_config.config.known_nodes_with_stake = updated_config.config.known_nodes_with_stake;
+        run_config.ramp_up_start_ms = updated_config.ramp_up_start_ms;
+        run_config.genesis_timestamp_ms = updated_config.genesis_timestamp_ms;
+
+        {
+            let mut control = control.write().await;
+            control.status.node_index = node_index;
+            control.status.peers = run_config
+                .config
+                .known_nodes_with_stake
+                .iter()
+                .map(|entry| format!("{:?}", TYPES::SignatureKey::get_public_key(entry)))
+                .collect();
+            if let Err(e) = control.set_config(&run_config) {
+                error!("Failed to serialize the config for the control endpoint: {e}");
+            }
+        }
+
+        let clock_offset_ms = orchestrator_client.get_clock_offset_ms().await;
+        match preflight::check(run_config.clone(), clock_offset_ms).await {
+            Ok(report) => error!("{report}"),
+            Err(source) => {
+                if let PreflightError::Failed { report } = &source {
+                    error!("{report}");
+                }
+                if !args.force {
+                    return Err(InfraError::Preflight { source });
+                }
+                warn!("{source}, joining the run anyway as forced to");
+            }
+        }
+
+        if let (Some(schedule), Some(ramp_up_start_ms)) =
+            (run_config.ramp_up, run_config.ramp_up_start_ms)
+        {
+            error!(
+                "Waiting for wave {} of the ramp-up",
+                schedule.wave_of(run_config.node_index)
             );
+            orchestrator_client
+                .wait_for_wave(&schedule, ramp_up_start_ms, run_config.node_index)
+                .await;
+        }
+
+        error!("Initializing networking");
+        let run = RUNDA::initialize_networking(run_config.clone()).await?;
+        let hotshot = run.initialize_state_and_hotshot().await?;
+        {
+            let mut control = control.write().await;
+            control.set_payload_validator(Arc::new(ReplicaPayloadValidator(hotshot.clone())));
+            control.set_leaf_dag_source(Arc::new(ReplicaLeafDag(hotshot.clone())));
+        }
+
+        let NetworkConfig {
+            transactions_per_round,
+            node_transactions_per_round,
+            node_index,
+            config: HotShotConfig { total_nodes, .. },
+            ..
+        } = run_config;
+
+        let transactions_to_send_per_round = node_transactions_per_round.unwrap_or_else(|| {
+            calculate_num_tx_per_round(node_index, total_nodes.get(), transactions_per_round)
+        });
+
+        let genesis = if let NetworkConfigSource::Orchestrator = source {
+            // the nodes of a run which started are not waiting for us to be ready
+            if !joins_late {
+                error!("Waiting for the start command from orchestrator");
+                orchestrator_client
+                    .wait_for_all_nodes_ready(run_config.clone().node_index)
+                    .await;
+            }
+            Some(orchestrator_client.wait_for_genesis().await)
+        } else {
+            None
+        };
+
+        // keep the orchestrator informed of our progress while consensus runs
+        let heartbeats_running = Arc::new(AtomicBool::new(true));
+        if matches!(source, NetworkConfigSource::Orchestrator)
+            && run_config.heartbeat_interval_seconds > 0
+        {
+            let orchestrator_client = orchestrator_client.clone();
+            let control = control.clone();
+            let interval = Duration::from_secs(run_config.heartbeat_interval_seconds);
+            let running = heartbeats_running.clone();
+            async_spawn(async move {
+                orchestrator_client
+                    .send_heartbeats(control, interval, running)
+                    .await;
+            });
+        }
+
+        // the nodes which join the run later take part once the networks admit them
+        let newcomers_running = Arc::new(AtomicBool::new(true));
+        if matches!(source, NetworkConfigSource::Orchestrator) {
+            let orchestrator_client = orchestrator_client.clone();
+            let control = control.clone();
+            let hotshot = hotshot.clone();
+            let interval = Duration::from_secs(run_config.heartbeat_interval_seconds.max(1));
+            let running = newcomers_running.clone();
+            async_spawn(async move {
+                orchestrator_client
+                    .watch_newcomers::<TYPES::SignatureKey, _, _>(
+                        interval,
+                        running,
+                        |newcomers, stake_table| {
+                            let control = control.clone();
+                            let hotshot = hotshot.clone();
+                            async move {
+                                error!("Nodes {newcomers:?} joined the run");
+                                let keys: BTreeSet<_> = stake_table
+                                    .iter()
+                                    .map(TYPES::SignatureKey::get_public_key)
+                                    .collect();
+                                control.write().await.status.peers =
+                                    keys.iter().map(|key| format!("{key:?}")).collect();
+                                hotshot.update_stake_table(keys).await;
+                            }
+                        },
+                    )
+                    .await;
+            });
+        }
 
-            // prepend destined view number to transaction
-            let view_execute_number: u64 = round as u64 + 4;
-            txn.0[0..8].copy_from_slice(&view_execute_number.to_be_bytes());
+        // a node whose views stop advancing is stuck, and restarted by systemd. The watchdog is
+        // pinged from a timer of its own, so it keeps going while the loop handling the events
+        // of the run is busy, and stops once consensus itself stops advancing.
+        let watchdog_running = Arc::new(AtomicBool::new(true));
+        if let Some(watchdog) = systemd.watchdog() {
+            let consensus = hotshot.get_consensus();
+            let running = watchdog_running.clone();
+            async_spawn(async move {
+                let mut watched_view = consensus.read().await.cur_view;
+                while running.load(Ordering::Relaxed) {
+                    async_sleep(watchdog.interval()).await;
+                    let view = consensus.read().await.cur_view;
+                    if view > watched_view {
+                        watched_view = view;
+                        watchdog.ping();
+                    }
+                }
+            });
+        }
 
-            transactions.push(txn);
+        error!("Starting HotShot");
+        let results = run
+            .run_hotshot(
+                hotshot,
+                transactions_to_send_per_round as u64,
+                genesis,
+                &control,
+                &systemd,
+            )
+            .await;
+        heartbeats_running.store(false, Ordering::Relaxed);
+        watchdog_running.store(false, Ordering::Relaxed);
+        newcomers_running.store(false, Ordering::Relaxed);
+        let results = match results {
+            Ok(results) => results,
+            Err(e) => {
+                leave_run(&orchestrator_client, &source, node_index).await;
+                return Err(e);
+            }
+        };
+        let shutdown_requested = control.read().await.shutdown_requested();
+        leaked |= !results.resource_leaks.is_empty();
+
+        // in a campaign, re-register with the orchestrator for the next run
+        let Some(run_index) = run_index else {
+            leave_run(&orchestrator_client, &source, node_index).await;
+            break;
+        };
+        if let Err(e) = orchestrator_client.post_run_results(results).await {
+            error!("Failed to post the results of the run: {e}");
         }
+        if shutdown_requested {
+            leave_run(&orchestrator_client, &source, node_index).await;
+            break;
+        }
+        // outside of a campaign, and after its last run, there is no next run to wait for
+        if orchestrator_client.get_runs_after(run_index).await == 0
+            || !orchestrator_client.wait_for_next_run(run_index).await
+        {
+            break;
+        }
+        error!("Run {run_index} finished, registering for the next run");
+    }
+    systemd.notify_stopping();
+    if leaked {
+        return Err(InfraError::ResourceLeaks);
     }
+    Ok(())
+}
 
+/// Tell the orchestrator, if the run came from it, that the node of `node_index` leaves it, so
+/// that the index is handed out to a node joining later
+async fn leave_run(
+    orchestrator_client: &OrchestratorClient,
+    source: &NetworkConfigSource,
+    node_index: u64,
+) {
     if let NetworkConfigSource::Orchestrator = source {
-        error!("Waiting for the start command from orchestrator");
-        orchestrator_client
-            .wait_for_all_nodes_ready(run_config.clone().node_index)
-            .await;
+        if let Err(e) = orchestrator_client.post_leave(node_index).await {
+            debug!("The orchestrator did not take our leave: {e}");
+        }
     }
+}
+
+/// The QUIC multiaddr of `ip` and `port`, in the family of `ip`
+/// # Panics
+/// Never: the address is always well formed
+#[must_use]
+pub fn quic_multiaddr(ip: IpAddr, port: u16) -> Multiaddr {
+    let family = match IpFamily::of(&ip) {
+        IpFamily::Ipv4 => "ip4",
+        IpFamily::Ipv6 => "ip6",
+    };
+    Multiaddr::from_str(&format!("/{family}/{ip}/udp/{port}/quic-v1")).unwrap()
+}
 
-    error!("Starting HotShot");
-    run.run_hotshot(
-        hotshot,
-        &mut transactions,
-        transactions_to_send_per_round as u64,
+/// The throttle of repeated log messages described by `config`
+#[must_use]
+pub fn log_throttle_from_config(config: &LogThrottleConfig) -> LogThrottle {
+    config.targets.iter().fold(
+        LogThrottle::new(Duration::from_secs(config.window_seconds)),
+        |throttle, (target, window_seconds)| {
+            throttle.with_window(target, Duration::from_secs(*window_seconds))
+        },
     )
-    .await;
+}
+
+/// The metrics of this node: pushed to the configured statsd sink, if any, and dropped otherwise
+#[must_use]
+pub fn metrics_from_config<KEY: SignatureKey, ELECTION: ElectionConfig>(
+    config: &NetworkConfig<KEY, ELECTION>,
+) -> Box<dyn Metrics> {
+    let Some(statsd) = &config.statsd else {
+        return NoMetrics::boxed();
+    };
+    match StatsdMetrics::boxed(statsd) {
+        Ok(metrics) => metrics,
+        Err(e) => {
+            error!("Could not connect to statsd at {}: {e}", statsd.address);
+            NoMetrics::boxed()
+        }
+    }
 }
 
 /// generate a libp2p identity based on a seed and idx
+///
+/// `seed` is either a raw seed or the libp2p seed derived from a mnemonic, as held by
+/// [`hotshot_types::key_derivation::DerivedSeeds`]
 /// # Panics
 /// if unable to create a secret key out of bytes
 #[must_use]
@@ -5,7 +5,7 @@ pub mod types;
 use crate::infra::load_config_from_file;
 use crate::types::ThisRun;
 use async_compatibility_layer::art::async_spawn;
-use async_compatibility_layer::logging::{setup_backtrace, setup_logging};
+use async_compatibility_layer::logging::setup_backtrace;
 use clap::Parser;
 use hotshot_example_types::state_types::TestTypes;
 use hotshot_orchestrator::client::ValidatorArgs;
@@ -32,7 +32,7 @@ pub mod infra;
 #[cfg_attr(async_executor_impl = "async-std", async_std::main)]
 #[instrument]
 async fn main() {
-    setup_logging();
+    infra::logging::setup_logging();
     setup_backtrace();
 
     // use configfile args
@@ -40,21 +40,24 @@ async fn main() {
     let orchestrator_url = Url::parse("http://localhost:4444").unwrap();
 
     // orchestrator
-    async_spawn(run_orchestrator::<
-        TestTypes,
-        DANetwork,
-        QuorumNetwork,
-        NodeImpl,
-    >(OrchestratorArgs {
+    let orchestrator_args = OrchestratorArgs {
         url: orchestrator_url.clone(),
         config_file: args.config_file.clone(),
-    }));
+        campaign_file: None,
+        validate_config: false,
+    };
+    async_spawn(async move {
+        run_orchestrator::<TestTypes, DANetwork, QuorumNetwork, NodeImpl>(orchestrator_args)
+            .await
+            .unwrap_or_else(|e| infra::exit_with(&e));
+    });
 
     // nodes
     let config: NetworkConfig<
         <TestTypes as NodeType>::SignatureKey,
         <TestTypes as NodeType>::ElectionConfigType,
-    > = load_config_from_file::<TestTypes>(&args.config_file);
+    > = load_config_from_file::<TestTypes>(&args.config_file)
+        .unwrap_or_else(|e| infra::exit_with(&e));
     let mut nodes = Vec::new();
     for _ in 0..config.config.total_nodes.into() {
         let orchestrator_url = orchestrator_url.clone();
@@ -63,10 +66,17 @@ async fn main() {
                 ValidatorArgs {
                     url: orchestrator_url,
                     public_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                    additional_ips: Vec::new(),
+                    bind_ips: Vec::new(),
                     network_config_file: None,
+                    control_url: None,
+                    control_token: None,
+                    validate_config: None,
+                    force: false,
                 },
             )
-            .await;
+            .await
+            .unwrap_or_else(|e| infra::exit_with(&e));
         });
         nodes.push(node);
     }
@@ -1,8 +1,5 @@
 //! A multi-validator using libp2p
-use async_compatibility_layer::{
-    art::async_spawn,
-    logging::{setup_backtrace, setup_logging},
-};
+use async_compatibility_layer::{art::async_spawn, logging::setup_backtrace};
 use clap::Parser;
 use hotshot_example_types::state_types::TestTypes;
 use hotshot_orchestrator::client::{MultiValidatorArgs, ValidatorArgs};
@@ -24,7 +21,7 @@ pub mod infra;
 #[cfg_attr(async_executor_impl = "async-std", async_std::main)]
 #[instrument]
 async fn main() {
-    setup_logging();
+    infra::logging::setup_logging();
     setup_backtrace();
     let args = MultiValidatorArgs::parse();
     tracing::error!("connecting to orchestrator at {:?}", args.url);
@@ -36,7 +33,8 @@ async fn main() {
             infra::main_entry_point::<TestTypes, DANetwork, QuorumNetwork, NodeImpl, ThisRun>(
                 ValidatorArgs::from_multi_args(args, node_index),
             )
-            .await;
+            .await
+            .unwrap_or_else(|e| infra::exit_with(&e));
         });
         nodes.push(node);
     }
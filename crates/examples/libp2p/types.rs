@@ -1,5 +1,5 @@
 use crate::infra::Libp2pDARun;
-use hotshot::traits::implementations::{Libp2pNetwork, MemoryStorage};
+use hotshot::traits::implementations::{Libp2pNetwork, MemoryStorage, RecordingNetwork};
 use hotshot_example_types::state_types::TestTypes;
 use hotshot_types::{
     message::Message,
@@ -13,9 +13,11 @@ use std::fmt::Debug;
 pub struct NodeImpl {}
 
 /// convenience type alias
-pub type DANetwork = Libp2pNetwork<Message<TestTypes>, <TestTypes as NodeType>::SignatureKey>;
+pub type DANetwork =
+    RecordingNetwork<Libp2pNetwork<Message<TestTypes>, <TestTypes as NodeType>::SignatureKey>>;
 /// convenience type alias
-pub type QuorumNetwork = Libp2pNetwork<Message<TestTypes>, <TestTypes as NodeType>::SignatureKey>;
+pub type QuorumNetwork =
+    RecordingNetwork<Libp2pNetwork<Message<TestTypes>, <TestTypes as NodeType>::SignatureKey>>;
 
 impl NodeImplementation<TestTypes> for NodeImpl {
     type Storage = MemoryStorage<TestTypes>;
@@ -1,5 +1,5 @@
 //! A validator using libp2p
-use async_compatibility_layer::logging::{setup_backtrace, setup_logging};
+use async_compatibility_layer::logging::setup_backtrace;
 use clap::Parser;
 use hotshot_example_types::state_types::TestTypes;
 use tracing::{info, instrument};
@@ -22,9 +22,11 @@ pub mod infra;
 #[cfg_attr(async_executor_impl = "async-std", async_std::main)]
 #[instrument]
 async fn main() {
-    setup_logging();
+    infra::logging::setup_logging();
     setup_backtrace();
     let args = ValidatorArgs::parse();
     info!("connecting to orchestrator at {:?}", args.url);
-    infra::main_entry_point::<TestTypes, DANetwork, QuorumNetwork, NodeImpl, ThisRun>(args).await;
+    infra::main_entry_point::<TestTypes, DANetwork, QuorumNetwork, NodeImpl, ThisRun>(args)
+        .await
+        .unwrap_or_else(|e| infra::exit_with(&e));
 }
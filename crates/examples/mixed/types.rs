@@ -0,0 +1,32 @@
+use crate::infra::MixedDARun;
+use hotshot::traits::implementations::{
+    Libp2pNetwork, MemoryStorage, RecordingNetwork, WebServerNetwork,
+};
+use hotshot_example_types::state_types::TestTypes;
+use hotshot_types::{
+    message::Message,
+    traits::node_implementation::{NodeImplementation, NodeType},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// dummy struct so we can choose types
+#[derive(Clone, Debug, Deserialize, Serialize, Hash, PartialEq, Eq)]
+pub struct NodeImpl {}
+
+/// the network DA messages go through
+pub type DAChannel = WebServerNetwork<TestTypes>;
+/// the network quorum messages go through
+pub type QuorumChannel = Libp2pNetwork<Message<TestTypes>, <TestTypes as NodeType>::SignatureKey>;
+/// convenience type alias
+pub type DANetwork = RecordingNetwork<DAChannel>;
+/// convenience type alias
+pub type QuorumNetwork = RecordingNetwork<QuorumChannel>;
+
+impl NodeImplementation<TestTypes> for NodeImpl {
+    type Storage = MemoryStorage<TestTypes>;
+    type QuorumNetwork = QuorumNetwork;
+    type CommitteeNetwork = DANetwork;
+}
+/// convenience type alias
+pub type ThisRun = MixedDARun<TestTypes, QuorumChannel, DAChannel>;
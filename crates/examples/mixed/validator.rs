@@ -0,0 +1,32 @@
+//! A validator running votes over libp2p and bulk data over the web server
+use async_compatibility_layer::logging::setup_backtrace;
+use clap::Parser;
+use hotshot_example_types::state_types::TestTypes;
+use tracing::{info, instrument};
+
+use crate::types::{DANetwork, NodeImpl, QuorumNetwork, ThisRun};
+
+use hotshot_orchestrator::client::ValidatorArgs;
+
+/// types used for this example
+pub mod types;
+
+/// general infra used for this example
+#[path = "../infra/mod.rs"]
+pub mod infra;
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::main(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::main)]
+#[instrument]
+async fn main() {
+    infra::logging::setup_logging();
+    setup_backtrace();
+    let args = ValidatorArgs::parse();
+    info!("connecting to orchestrator at {:?}", args.url);
+    infra::main_entry_point::<TestTypes, DANetwork, QuorumNetwork, NodeImpl, ThisRun>(args)
+        .await
+        .unwrap_or_else(|e| infra::exit_with(&e));
+}
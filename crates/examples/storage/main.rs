@@ -0,0 +1,92 @@
+//! Offline maintenance of the storage of a validator node
+//!
+//! Works on the `<prefix>-storage.bin` files nodes write when asked for a snapshot through
+//! `hotshotctl snapshot`.
+#![allow(clippy::panic)]
+
+use std::fs;
+
+use bincode::Options;
+use clap::{Parser, Subcommand};
+use hotshot::traits::implementations::MemoryStorage;
+use hotshot_example_types::state_types::TestTypes;
+use hotshot_types::traits::storage::{Storage, StorageState, TestableStorage};
+use hotshot_utils::bincode::bincode_opts;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "hotshot-storage",
+    about = "Validates and compacts the storage of a HotShot node"
+)]
+/// Arguments of `hotshot-storage`
+struct StorageArgs {
+    /// What to do
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// The commands of `hotshot-storage`
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check that the stored views link up, recomputing their commitments
+    Verify {
+        /// The storage file
+        path: String,
+    },
+    /// Drop everything before the anchored view
+    Compact {
+        /// The storage file
+        path: String,
+        /// Where to write the compacted storage, instead of replacing `path`
+        #[arg(long)]
+        output: Option<String>,
+    },
+}
+
+/// Load the storage file at `path`
+fn load(path: &str) -> MemoryStorage<TestTypes> {
+    let bytes = fs::read(path).unwrap_or_else(|e| panic!("Could not read {path}: {e}"));
+    let state: StorageState<TestTypes> = bincode_opts()
+        .deserialize(&bytes)
+        .unwrap_or_else(|e| panic!("{path} is not a storage file: {e}"));
+    MemoryStorage::from_state(state)
+}
+
+#[cfg_attr(async_executor_impl = "tokio", tokio::main)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::main)]
+async fn main() {
+    let args = StorageArgs::parse();
+
+    match args.command {
+        Command::Verify { path } => {
+            let report = load(&path)
+                .verify_integrity()
+                .await
+                .unwrap_or_else(|e| panic!("Could not verify {path}: {e}"));
+            for violation in &report.violations {
+                println!("{violation}");
+            }
+            println!(
+                "{} views checked, {} problems found",
+                report.views_checked,
+                report.violations.len()
+            );
+            if !report.is_ok() {
+                std::process::exit(1);
+            }
+        }
+        Command::Compact { path, output } => {
+            let storage = load(&path);
+            let dropped = storage
+                .compact()
+                .await
+                .unwrap_or_else(|e| panic!("Could not compact {path}: {e}"));
+            let bytes = bincode_opts()
+                .serialize(&storage.get_full_state().await)
+                .expect("storage always serializes");
+            let output = output.unwrap_or(path);
+            fs::write(&output, bytes).unwrap_or_else(|e| panic!("Could not write {output}: {e}"));
+            println!("Dropped {dropped} entries, wrote {output}");
+        }
+    }
+}
@@ -28,8 +28,8 @@ use tracing::error;
 #[cfg_attr(async_executor_impl = "tokio", tokio::main)]
 #[cfg_attr(async_executor_impl = "async-std", async_std::main)]
 async fn main() {
-    use async_compatibility_layer::logging::{setup_backtrace, setup_logging};
-    setup_logging();
+    use async_compatibility_layer::logging::setup_backtrace;
+    infra::logging::setup_logging();
     setup_backtrace();
 
     // use configfile args
@@ -68,21 +68,24 @@ async fn main() {
     let orchestrator_url = Url::parse("http://localhost:4444").unwrap();
 
     // web server orchestrator
-    async_spawn(run_orchestrator::<
-        TestTypes,
-        DANetwork,
-        QuorumNetwork,
-        NodeImpl,
-    >(OrchestratorArgs {
+    let orchestrator_args = OrchestratorArgs {
         url: orchestrator_url.clone(),
         config_file: args.config_file.clone(),
-    }));
+        campaign_file: None,
+        validate_config: false,
+    };
+    async_spawn(async move {
+        run_orchestrator::<TestTypes, DANetwork, QuorumNetwork, NodeImpl>(orchestrator_args)
+            .await
+            .unwrap_or_else(|e| infra::exit_with(&e));
+    });
 
     // multi validator run
     let config: NetworkConfig<
         <TestTypes as NodeType>::SignatureKey,
         <TestTypes as NodeType>::ElectionConfigType,
-    > = load_config_from_file::<TestTypes>(&args.config_file);
+    > = load_config_from_file::<TestTypes>(&args.config_file)
+        .unwrap_or_else(|e| infra::exit_with(&e));
     let mut nodes = Vec::new();
     for _ in 0..(config.config.total_nodes.get()) {
         let orchestrator_url = orchestrator_url.clone();
@@ -91,10 +94,17 @@ async fn main() {
                 ValidatorArgs {
                     url: orchestrator_url,
                     public_ip: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                    additional_ips: Vec::new(),
+                    bind_ips: Vec::new(),
                     network_config_file: None,
+                    control_url: None,
+                    control_token: None,
+                    validate_config: None,
+                    force: false,
                 },
             )
-            .await;
+            .await
+            .unwrap_or_else(|e| infra::exit_with(&e));
         });
         nodes.push(node);
     }
@@ -27,5 +27,7 @@ async fn main() {
     setup_backtrace();
     let args = OrchestratorArgs::parse();
 
-    run_orchestrator::<TestTypes, DANetwork, QuorumNetwork, NodeImpl>(args).await;
+    run_orchestrator::<TestTypes, DANetwork, QuorumNetwork, NodeImpl>(args)
+        .await
+        .unwrap_or_else(|e| infra::exit_with(&e));
 }
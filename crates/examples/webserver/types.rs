@@ -1,5 +1,5 @@
 use crate::infra::WebServerDARun;
-use hotshot::traits::implementations::{MemoryStorage, WebServerNetwork};
+use hotshot::traits::implementations::{MemoryStorage, RecordingNetwork, WebServerNetwork};
 use hotshot_example_types::state_types::TestTypes;
 use hotshot_types::traits::node_implementation::NodeImplementation;
 use serde::{Deserialize, Serialize};
@@ -10,13 +10,13 @@ use std::fmt::Debug;
 pub struct NodeImpl {}
 
 /// convenience type alias
-pub type DANetwork = WebServerNetwork<TestTypes>;
+pub type DANetwork = RecordingNetwork<WebServerNetwork<TestTypes>>;
 /// convenience type alias
-pub type VIDNetwork = WebServerNetwork<TestTypes>;
+pub type VIDNetwork = RecordingNetwork<WebServerNetwork<TestTypes>>;
 /// convenience type alias
-pub type QuorumNetwork = WebServerNetwork<TestTypes>;
+pub type QuorumNetwork = RecordingNetwork<WebServerNetwork<TestTypes>>;
 /// convenience type alias
-pub type ViewSyncNetwork = WebServerNetwork<TestTypes>;
+pub type ViewSyncNetwork = RecordingNetwork<WebServerNetwork<TestTypes>>;
 
 impl NodeImplementation<TestTypes> for NodeImpl {
     type Storage = MemoryStorage<TestTypes>;
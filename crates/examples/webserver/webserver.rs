@@ -14,6 +14,9 @@ use clap::Parser;
 struct WebServerArgs {
     /// url to run on
     url: Url,
+    /// options of the web server
+    #[command(flatten)]
+    options: hotshot_web_server::Options,
 }
 
 #[cfg_attr(async_executor_impl = "tokio", tokio::main)]
@@ -24,8 +27,8 @@ async fn main() {
     let args = WebServerArgs::parse();
     let (server_shutdown_sender, server_shutdown) = oneshot();
     let _sender = Arc::new(server_shutdown_sender);
-    let _result = hotshot_web_server::run_web_server::<
+    let _result = hotshot_web_server::run_web_server_with_options::<
         <TestTypes as hotshot_types::traits::node_implementation::NodeType>::SignatureKey,
-    >(Some(server_shutdown), args.url)
+    >(Some(server_shutdown), args.url, args.options)
     .await;
 }
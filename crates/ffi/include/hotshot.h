@@ -0,0 +1,91 @@
+/*
+ * C ABI for embedding HotShot in sequencers which are not written in Rust.
+ *
+ * Link against the `hotshot_ffi` static or shared library, which embeds the test types, or against
+ * a library exporting this interface for the types of the sequencer with `hotshot_ffi::export!`.
+ * Every call returns a hotshot_status_t, which hotshot_status_message describes. A node handle
+ * must not be used from two threads at once, nor after it is shut down.
+ */
+
+#ifndef HOTSHOT_FFI_H
+#define HOTSHOT_FFI_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The outcome of a call */
+typedef enum {
+    HOTSHOT_OK = 0,
+    /* a pointer argument was null */
+    HOTSHOT_NULL_ARGUMENT = 1,
+    /* the path of the configuration file is not valid UTF-8 */
+    HOTSHOT_INVALID_PATH = 2,
+    /* the configuration file could not be read, or does not configure a web server network */
+    HOTSHOT_INVALID_CONFIG = 3,
+    /* the node could not be started */
+    HOTSHOT_START_FAILED = 4,
+    /* the transaction was rejected by the transaction validator */
+    HOTSHOT_TRANSACTION_REJECTED = 5,
+    /* no decided block is waiting to be polled */
+    HOTSHOT_NO_DECIDE = 6,
+    /* the buffer is too small for the transactions of the decided block, which stays queued */
+    HOTSHOT_BUFFER_TOO_SMALL = 7,
+    /* the node panicked while handling the call */
+    HOTSHOT_PANICKED = 8,
+} hotshot_status_t;
+
+/* A running node, only ever handled through a pointer */
+typedef struct HotShotNode hotshot_node_t;
+
+/* A block decided by a node */
+typedef struct {
+    /* the view the block was proposed in */
+    uint64_t view;
+    /* the height of the block */
+    uint64_t block_height;
+    /* the number of transactions in the block */
+    uint64_t num_transactions;
+    /* the bytes the transactions take in the buffer, each prefixed with its length as a
+     * little-endian uint64_t */
+    size_t transactions_len;
+} hotshot_decide_t;
+
+/*
+ * Create a node from the JSON network configuration a validator saved at `config_path`, and
+ * start consensus. The node is written to `node`, and must be shut down with
+ * hotshot_node_shutdown.
+ */
+hotshot_status_t hotshot_node_create(const char *config_path, hotshot_node_t **node);
+
+/* Submit the `len` bytes at `transaction` as a transaction, to be included in a block */
+hotshot_status_t hotshot_node_submit_transaction(hotshot_node_t *node,
+                                                 const uint8_t *transaction, size_t len);
+
+/*
+ * Take the oldest decided block which was not polled yet, describing it in `decide` and writing
+ * its transactions to the `capacity` bytes at `buffer`. Returns HOTSHOT_NO_DECIDE if no block is
+ * waiting, and HOTSHOT_BUFFER_TOO_SMALL if `capacity` is less than `decide->transactions_len`, in
+ * which case the block stays queued for a call with a larger buffer. `buffer` may be NULL if
+ * `capacity` is 0.
+ *
+ * Poll regularly: once its event buffer is full, the node waits for the embedder to poll before
+ * it handles further views.
+ */
+hotshot_status_t hotshot_node_poll_decide(hotshot_node_t *node, hotshot_decide_t *decide,
+                                          uint8_t *buffer, size_t capacity);
+
+/* Shut `node` down and free it */
+hotshot_status_t hotshot_node_shutdown(hotshot_node_t *node);
+
+/* A description of `status`, as a static string */
+const char *hotshot_status_message(hotshot_status_t status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HOTSHOT_FFI_H */
@@ -0,0 +1,618 @@
+//! C ABI for embedding `HotShot` in sequencers which are not written in Rust
+//!
+//! A node is created from the JSON network configuration a validator saves once the orchestrator
+//! has assigned it a node index, see [`NetworkConfig::to_file`], and runs over the web servers of
+//! that configuration until it is shut down. The embedder only ever holds an opaque pointer to a
+//! [`HotShotNode`], through which it submits transactions and polls the blocks the node decides.
+//! Every call returns a [`HotShotStatus`], which [`hotshot_status_message`] describes.
+//!
+//! `include/hotshot.h` declares this interface for C and C++, and Go sequencers link it with cgo.
+//! A node handle must not be used from two threads at once.
+//!
+//! The node types, its storage and the way transactions are encoded are those of an
+//! [`Embedding`]. A sequencer builds its own library exporting the interface for its types with
+//! [`export!`], depending on this crate without its default `test-embedding` feature, which
+//! exports it for the test types of `hotshot-example-types`.
+
+use std::{
+    collections::VecDeque,
+    ffi::{c_char, CStr},
+    future::Future,
+    marker::PhantomData,
+    panic::{catch_unwind, AssertUnwindSafe},
+    slice,
+};
+
+use async_broadcast::{Receiver, TryRecvError};
+use hotshot::{
+    traits::{implementations::WebServerNetwork, NodeImplementation},
+    types::SystemContextHandle,
+    HotShotInitializer, Memberships, Networks, SystemContext,
+};
+use hotshot_orchestrator::config::NetworkConfig;
+use hotshot_types::{
+    consensus::ConsensusMetricsValue,
+    event::{Event, EventType},
+    traits::{
+        block_contents::BlockHeader,
+        election::{ElectionConfig, Membership},
+        network::ConnectedNetwork,
+        node_implementation::NodeType,
+    },
+};
+use tracing::error;
+
+/// The network configuration of a node of `E`
+type EmbeddedConfig<E> = NetworkConfig<
+    <<E as Embedding>::Types as NodeType>::SignatureKey,
+    <<E as Embedding>::Types as NodeType>::ElectionConfigType,
+>;
+
+/// The types of the nodes embedded through this interface, and how the bytes the embedder hands
+/// over map to them
+pub trait Embedding: 'static {
+    /// the types of the consensus the nodes run
+    type Types: NodeType;
+    /// the implementation of the nodes, over the web servers of their configuration
+    type Node: NodeImplementation<
+        Self::Types,
+        QuorumNetwork = WebServerNetwork<Self::Types>,
+        CommitteeNetwork = WebServerNetwork<Self::Types>,
+    >;
+
+    /// The instance state the genesis of a node is built from
+    fn instance_state() -> <Self::Types as NodeType>::InstanceState;
+
+    /// The storage a node starts with
+    fn storage() -> <Self::Node as NodeImplementation<Self::Types>>::Storage;
+
+    /// The transaction the embedder submits as `bytes`
+    fn transaction(bytes: Vec<u8>) -> <Self::Types as NodeType>::Transaction;
+
+    /// The transactions of a decided `payload`, as handed to the embedder
+    fn transactions(payload: <Self::Types as NodeType>::BlockPayload) -> Vec<Vec<u8>>;
+}
+
+#[cfg(feature = "test-embedding")]
+pub use test_embedding::TestEmbedding;
+
+/// The embedding of the test types of `hotshot-example-types`
+#[cfg(feature = "test-embedding")]
+mod test_embedding {
+    use hotshot::traits::implementations::MemoryStorage;
+    use hotshot_example_types::{
+        block_types::{TestBlockPayload, TestTransaction},
+        node_types::{TestTypes, WebImpl},
+        state_types::TestInstanceState,
+    };
+
+    use super::Embedding;
+
+    /// The test types of `hotshot-example-types`, with transactions of raw bytes
+    pub struct TestEmbedding;
+
+    impl Embedding for TestEmbedding {
+        type Types = TestTypes;
+        type Node = WebImpl;
+
+        fn instance_state() -> TestInstanceState {
+            TestInstanceState {}
+        }
+
+        fn storage() -> MemoryStorage<TestTypes> {
+            MemoryStorage::empty()
+        }
+
+        fn transaction(bytes: Vec<u8>) -> TestTransaction {
+            TestTransaction::new(bytes)
+        }
+
+        fn transactions(payload: TestBlockPayload) -> Vec<Vec<u8>> {
+            payload
+                .transactions
+                .into_iter()
+                .map(|transaction| transaction.bytes)
+                .collect()
+        }
+    }
+}
+
+/// The outcome of a call
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotShotStatus {
+    /// the call succeeded
+    Ok = 0,
+    /// a pointer argument was null
+    NullArgument = 1,
+    /// the path of the configuration file is not valid UTF-8
+    InvalidPath = 2,
+    /// the configuration file could not be read, or does not configure a web server network
+    InvalidConfig = 3,
+    /// the node could not be started
+    StartFailed = 4,
+    /// the transaction was rejected by the transaction validator
+    TransactionRejected = 5,
+    /// no decided block is waiting to be polled
+    NoDecide = 6,
+    /// the buffer is too small for the transactions of the decided block, which stays queued
+    BufferTooSmall = 7,
+    /// the node panicked while handling the call
+    Panicked = 8,
+}
+
+/// A block decided by a node, as [`hotshot_node_poll_decide`] describes it
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HotShotDecide {
+    /// the view the block was proposed in
+    pub view: u64,
+    /// the height of the block
+    pub block_height: u64,
+    /// the number of transactions in the block
+    pub num_transactions: u64,
+    /// the bytes the transactions take in the buffer, each prefixed with its length as a
+    /// little-endian `u64`
+    pub transactions_len: usize,
+}
+
+/// A decided block waiting to be polled
+struct DecidedBlock {
+    /// the view the block was proposed in
+    view: u64,
+    /// the height of the block
+    block_height: u64,
+    /// the transactions of the block, empty if the node does not hold its payload
+    transactions: Vec<Vec<u8>>,
+}
+
+impl DecidedBlock {
+    /// The bytes the transactions take in the buffer of [`hotshot_node_poll_decide`]
+    fn encoded_len(&self) -> usize {
+        self.transactions
+            .iter()
+            .map(|transaction| std::mem::size_of::<u64>() + transaction.len())
+            .sum()
+    }
+
+    /// The description of the block handed to the embedder
+    fn summary(&self) -> HotShotDecide {
+        HotShotDecide {
+            view: self.view,
+            block_height: self.block_height,
+            num_transactions: self.transactions.len() as u64,
+            transactions_len: self.encoded_len(),
+        }
+    }
+
+    /// Write the transactions to the start of `buffer`, which holds at least
+    /// [`DecidedBlock::encoded_len`] bytes
+    fn write(&self, buffer: &mut [u8]) {
+        let mut offset = 0;
+        for transaction in &self.transactions {
+            let len = (transaction.len() as u64).to_le_bytes();
+            buffer[offset..offset + len.len()].copy_from_slice(&len);
+            offset += len.len();
+            buffer[offset..offset + transaction.len()].copy_from_slice(transaction);
+            offset += transaction.len();
+        }
+    }
+}
+
+/// A node embedded through this interface, which the embedder only holds a pointer to
+pub struct HotShotNode<E: Embedding> {
+    /// the handle of the running node
+    handle: SystemContextHandle<E::Types, E::Node>,
+    /// the events of the node, read as the embedder polls
+    events: Receiver<Event<E::Types>>,
+    /// the blocks decided but not polled yet, oldest first
+    decided: VecDeque<DecidedBlock>,
+}
+
+impl<E: Embedding> HotShotNode<E> {
+    /// Queue the blocks of the decide events received since the last poll
+    fn drain_events(&mut self) {
+        loop {
+            match self.events.try_recv() {
+                Ok(Event {
+                    event: EventType::Decide { leaf_chain, .. },
+                    ..
+                }) => {
+                    // the chain lists the newest leaf first
+                    for (leaf, _) in leaf_chain.iter().rev() {
+                        self.decided.push_back(DecidedBlock {
+                            view: *leaf.view_number,
+                            block_height: leaf.block_header.block_number(),
+                            transactions: leaf
+                                .get_block_payload()
+                                .map(E::transactions)
+                                .unwrap_or_default(),
+                        });
+                    }
+                }
+                Ok(_) | Err(TryRecvError::Overflowed(_)) => {}
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+/// Run `future` to completion on the executor the nodes of the process run on
+#[cfg(async_executor_impl = "tokio")]
+fn block_on<F: Future>(future: F) -> F::Output {
+    use std::sync::OnceLock;
+    /// the runtime every node of the process is spawned on
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to start the tokio runtime")
+        })
+        .block_on(future)
+}
+
+/// Run `future` to completion on the executor the nodes of the process run on
+#[cfg(async_executor_impl = "async-std")]
+fn block_on<F: Future>(future: F) -> F::Output {
+    async_std::task::block_on(future)
+}
+
+/// Run `call`, reporting a panic as [`HotShotStatus::Panicked`] rather than unwinding into the
+/// embedder
+fn guard(call: impl FnOnce() -> HotShotStatus) -> HotShotStatus {
+    catch_unwind(AssertUnwindSafe(call)).unwrap_or(HotShotStatus::Panicked)
+}
+
+/// Start a node over the web servers of `config`, with the stake table and election of `config`
+async fn start<E: Embedding>(config: EmbeddedConfig<E>) -> Result<HotShotNode<E>, HotShotStatus> {
+    let (Some(quorum_server), Some(da_server)) = (
+        config.web_server_config.clone(),
+        config.da_web_server_config.clone(),
+    ) else {
+        return Err(HotShotStatus::InvalidConfig);
+    };
+    let public_key = config.config.my_own_validator_config.public_key;
+    let private_key = config.config.my_own_validator_config.private_key.clone();
+    let quorum_network = WebServerNetwork::create_sharded(
+        quorum_server.urls(),
+        quorum_server.wait_between_polls,
+        public_key,
+        false,
+        config.config.chain_id,
+    );
+    let da_network = WebServerNetwork::create_sharded(
+        da_server.urls(),
+        da_server.wait_between_polls,
+        public_key,
+        true,
+        config.config.chain_id,
+    );
+    quorum_network.wait_for_ready().await;
+    da_network.wait_for_ready().await;
+
+    let mut quorum_election_config = config.config.election_config.clone().unwrap_or_else(|| {
+        <E::Types as NodeType>::Membership::default_election_config(
+            config.config.total_nodes.get() as u64
+        )
+    });
+    if let Some(domains) = config.da_failure_domains() {
+        quorum_election_config = quorum_election_config.with_da_failure_domains(domains);
+    }
+    if let Some(rules) = config.leader_reputation {
+        quorum_election_config = quorum_election_config.with_leader_reputation(rules);
+    }
+    let da_committee_size =
+        u64::try_from(config.config.da_committee_size).map_err(|_| HotShotStatus::InvalidConfig)?;
+    let da_election_config = quorum_election_config.da_config(da_committee_size);
+    let stake_table = config.config.known_nodes_with_stake.clone();
+    let election = |election_config| {
+        <E::Types as NodeType>::Membership::create_election(stake_table.clone(), election_config)
+    };
+    let memberships = Memberships {
+        quorum_membership: election(quorum_election_config.clone()),
+        da_membership: election(da_election_config),
+        vid_membership: election(quorum_election_config.clone()),
+        view_sync_membership: election(quorum_election_config),
+    };
+    let networks = Networks {
+        quorum_network: quorum_network.into(),
+        da_network: da_network.into(),
+        _pd: PhantomData,
+    };
+
+    let initializer = HotShotInitializer::<E::Types>::from_genesis(&E::instance_state())
+        .map_err(|e| {
+            error!("Failed to build the genesis of the node: {e:?}");
+            HotShotStatus::StartFailed
+        })?
+        .with_runtime(config.runtime);
+    let (handle, ..) = SystemContext::<E::Types, E::Node>::init(
+        public_key,
+        private_key,
+        config.node_index,
+        config.config,
+        E::storage(),
+        memberships,
+        networks,
+        initializer,
+        ConsensusMetricsValue::default(),
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to start the node: {e:?}");
+        HotShotStatus::StartFailed
+    })?;
+    let events = handle.get_event_stream_known_impl();
+    handle.hotshot.start_consensus().await;
+    Ok(HotShotNode {
+        handle,
+        events,
+        decided: VecDeque::new(),
+    })
+}
+
+/// Create a node from the network configuration at `config_path` and start consensus, writing
+/// the node to `node`. The node must be shut down with [`node_shutdown`]. Exported as
+/// `hotshot_node_create` by [`export!`].
+///
+/// # Safety
+/// `config_path` must be a nul-terminated string, and `node` valid for writing a pointer
+pub unsafe fn node_create<E: Embedding>(
+    config_path: *const c_char,
+    node: *mut *mut HotShotNode<E>,
+) -> HotShotStatus {
+    guard(|| {
+        if config_path.is_null() || node.is_null() {
+            return HotShotStatus::NullArgument;
+        }
+        let Ok(path) = CStr::from_ptr(config_path).to_str() else {
+            return HotShotStatus::InvalidPath;
+        };
+        let config = match EmbeddedConfig::<E>::from_file(path.to_string()) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("Failed to load the configuration at {path}: {e:?}");
+                return HotShotStatus::InvalidConfig;
+            }
+        };
+        match block_on(start(config)) {
+            Ok(started) => {
+                *node = Box::into_raw(Box::new(started));
+                HotShotStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+/// Submit the `len` bytes at `transaction` as a transaction, to be included in a block.
+/// Exported as `hotshot_node_submit_transaction` by [`export!`].
+///
+/// # Safety
+/// `node` must have been created by [`node_create`] and not shut down, and `transaction` must
+/// be valid for reading `len` bytes
+pub unsafe fn node_submit_transaction<E: Embedding>(
+    node: *mut HotShotNode<E>,
+    transaction: *const u8,
+    len: usize,
+) -> HotShotStatus {
+    guard(|| {
+        let Some(node) = node.as_mut() else {
+            return HotShotStatus::NullArgument;
+        };
+        if transaction.is_null() {
+            return HotShotStatus::NullArgument;
+        }
+        let bytes = slice::from_raw_parts(transaction, len).to_vec();
+        match block_on(node.handle.submit_transaction(E::transaction(bytes))) {
+            Ok(_) => HotShotStatus::Ok,
+            Err(e) => {
+                error!("Failed to submit a transaction: {e:?}");
+                HotShotStatus::TransactionRejected
+            }
+        }
+    })
+}
+
+/// Take the oldest decided block which was not polled yet, describing it in `decide` and writing
+/// its transactions to the `capacity` bytes at `buffer`, each prefixed with its length as a
+/// little-endian `u64`. Returns [`HotShotStatus::NoDecide`] if no block is waiting, and
+/// [`HotShotStatus::BufferTooSmall`] if its transactions need more than `capacity` bytes, in which
+/// case `decide` tells how many and the block stays queued. Exported as
+/// `hotshot_node_poll_decide` by [`export!`].
+///
+/// Poll regularly: once its event buffer is full, the node waits for the embedder to poll
+/// before it handles further views.
+///
+/// # Safety
+/// `node` must have been created by [`node_create`] and not shut down, `decide` must be valid
+/// for writing, and `buffer` valid for writing `capacity` bytes. `buffer` may be null if
+/// `capacity` is 0.
+pub unsafe fn node_poll_decide<E: Embedding>(
+    node: *mut HotShotNode<E>,
+    decide: *mut HotShotDecide,
+    buffer: *mut u8,
+    capacity: usize,
+) -> HotShotStatus {
+    guard(|| {
+        let (Some(node), Some(decide)) = (node.as_mut(), decide.as_mut()) else {
+            return HotShotStatus::NullArgument;
+        };
+        node.drain_events();
+        let Some(block) = node.decided.front() else {
+            return HotShotStatus::NoDecide;
+        };
+        *decide = block.summary();
+        let len = block.encoded_len();
+        if len > capacity {
+            return HotShotStatus::BufferTooSmall;
+        }
+        if len > 0 {
+            if buffer.is_null() {
+                return HotShotStatus::NullArgument;
+            }
+            block.write(slice::from_raw_parts_mut(buffer, capacity));
+        }
+        node.decided.pop_front();
+        HotShotStatus::Ok
+    })
+}
+
+/// Shut `node` down and free it. `node` must not be used afterwards. Exported as
+/// `hotshot_node_shutdown` by [`export!`].
+///
+/// # Safety
+/// `node` must have been created by [`node_create`] and not shut down yet
+pub unsafe fn node_shutdown<E: Embedding>(node: *mut HotShotNode<E>) -> HotShotStatus {
+    guard(|| {
+        if node.is_null() {
+            return HotShotStatus::NullArgument;
+        }
+        let mut node = Box::from_raw(node);
+        block_on(node.handle.shut_down());
+        HotShotStatus::Ok
+    })
+}
+
+/// Export the C interface of `include/hotshot.h` for the nodes of `$embedding`, an
+/// [`Embedding`]. A library invokes it once, as the functions it defines are not mangled.
+#[macro_export]
+macro_rules! export {
+    ($embedding:ty) => {
+        /// See [`node_create`]($crate::node_create)
+        ///
+        /// # Safety
+        /// As for [`node_create`]($crate::node_create)
+        #[no_mangle]
+        pub unsafe extern "C" fn hotshot_node_create(
+            config_path: *const ::std::ffi::c_char,
+            node: *mut *mut $crate::HotShotNode<$embedding>,
+        ) -> $crate::HotShotStatus {
+            $crate::node_create::<$embedding>(config_path, node)
+        }
+
+        /// See [`node_submit_transaction`]($crate::node_submit_transaction)
+        ///
+        /// # Safety
+        /// As for [`node_submit_transaction`]($crate::node_submit_transaction)
+        #[no_mangle]
+        pub unsafe extern "C" fn hotshot_node_submit_transaction(
+            node: *mut $crate::HotShotNode<$embedding>,
+            transaction: *const u8,
+            len: usize,
+        ) -> $crate::HotShotStatus {
+            $crate::node_submit_transaction::<$embedding>(node, transaction, len)
+        }
+
+        /// See [`node_poll_decide`]($crate::node_poll_decide)
+        ///
+        /// # Safety
+        /// As for [`node_poll_decide`]($crate::node_poll_decide)
+        #[no_mangle]
+        pub unsafe extern "C" fn hotshot_node_poll_decide(
+            node: *mut $crate::HotShotNode<$embedding>,
+            decide: *mut $crate::HotShotDecide,
+            buffer: *mut u8,
+            capacity: usize,
+        ) -> $crate::HotShotStatus {
+            $crate::node_poll_decide::<$embedding>(node, decide, buffer, capacity)
+        }
+
+        /// See [`node_shutdown`]($crate::node_shutdown)
+        ///
+        /// # Safety
+        /// As for [`node_shutdown`]($crate::node_shutdown)
+        #[no_mangle]
+        pub unsafe extern "C" fn hotshot_node_shutdown(
+            node: *mut $crate::HotShotNode<$embedding>,
+        ) -> $crate::HotShotStatus {
+            $crate::node_shutdown::<$embedding>(node)
+        }
+    };
+}
+
+#[cfg(feature = "test-embedding")]
+export!(TestEmbedding);
+
+/// A description of `status`, as a static nul-terminated string
+#[no_mangle]
+pub extern "C" fn hotshot_status_message(status: HotShotStatus) -> *const c_char {
+    let message: &'static [u8] = match status {
+        HotShotStatus::Ok => b"ok\0",
+        HotShotStatus::NullArgument => b"a pointer argument was null\0",
+        HotShotStatus::InvalidPath => b"the configuration path is not valid UTF-8\0",
+        HotShotStatus::InvalidConfig => {
+            b"the configuration could not be read or has no web server network\0"
+        }
+        HotShotStatus::StartFailed => b"the node could not be started\0",
+        HotShotStatus::TransactionRejected => b"the transaction was rejected\0",
+        HotShotStatus::NoDecide => b"no decided block is waiting to be polled\0",
+        HotShotStatus::BufferTooSmall => b"the buffer is too small for the decided block\0",
+        HotShotStatus::Panicked => b"the node panicked\0",
+    };
+    message.as_ptr().cast()
+}
+
+#[cfg(all(test, feature = "test-embedding"))]
+mod test {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn null_arguments_are_reported() {
+        let mut node = ptr::null_mut();
+        let mut decide = HotShotDecide::default();
+        unsafe {
+            assert_eq!(
+                hotshot_node_create(ptr::null(), &mut node),
+                HotShotStatus::NullArgument
+            );
+            assert_eq!(
+                hotshot_node_submit_transaction(ptr::null_mut(), ptr::null(), 0),
+                HotShotStatus::NullArgument
+            );
+            assert_eq!(
+                hotshot_node_poll_decide(ptr::null_mut(), &mut decide, ptr::null_mut(), 0),
+                HotShotStatus::NullArgument
+            );
+            assert_eq!(
+                hotshot_node_shutdown(ptr::null_mut()),
+                HotShotStatus::NullArgument
+            );
+        }
+        assert!(node.is_null());
+    }
+
+    #[test]
+    fn missing_configurations_are_invalid() {
+        let mut node = ptr::null_mut();
+        let path = b"/nonexistent/hotshot/config.json\0";
+        let status = unsafe { hotshot_node_create(path.as_ptr().cast(), &mut node) };
+        assert_eq!(status, HotShotStatus::InvalidConfig);
+        assert!(node.is_null());
+        let message = unsafe { CStr::from_ptr(hotshot_status_message(status)) };
+        assert!(message.to_str().unwrap().contains("configuration"));
+    }
+
+    #[test]
+    fn decided_blocks_are_length_prefixed() {
+        let block = DecidedBlock {
+            view: 3,
+            block_height: 2,
+            transactions: vec![vec![1, 2], vec![], vec![3]],
+        };
+        let summary = block.summary();
+        assert_eq!(summary.num_transactions, 3);
+        assert_eq!(summary.transactions_len, 3 * 8 + 3);
+
+        let mut buffer = vec![0xff; summary.transactions_len + 4];
+        block.write(&mut buffer);
+        assert_eq!(&buffer[..10], &[2, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        assert_eq!(&buffer[10..18], &[0; 8]);
+        assert_eq!(&buffer[18..27], &[1, 0, 0, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(&buffer[27..], &[0xff; 4]);
+    }
+}
@@ -0,0 +1,197 @@
+//! Step by step construction of a [`SystemContext`]
+//!
+//! [`SystemContext::init`] takes every part of a node at once. A [`SystemContextBuilder`] takes
+//! them one setter at a time: the keys, the id and the configuration of the node up front, the
+//! storage, memberships, networks and initializer through required setters, and the metrics, the
+//! runtime tuning and observer mode through optional ones. [`SystemContextBuilder::build`] checks
+//! that every required part was given before starting the node.
+
+use async_broadcast::{Receiver, Sender};
+use hotshot_task_impls::events::HotShotEvent;
+use hotshot_types::{
+    consensus::ConsensusMetricsValue, error::HotShotError, runtime::RuntimeConfig,
+    traits::node_implementation::NodeType, traits::signature_key::SignatureKey, HotShotConfig,
+};
+
+use crate::{
+    traits::NodeImplementation, types::SystemContextHandle, HotShotInitializer, Memberships,
+    Networks, SystemContext,
+};
+
+/// Builds a [`SystemContext`], see the [module documentation](self)
+pub struct SystemContextBuilder<TYPES: NodeType, I: NodeImplementation<TYPES>> {
+    /// The public key of the node
+    public_key: TYPES::SignatureKey,
+    /// The private key of the node
+    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// The id of the node, for instrumentation
+    node_id: u64,
+    /// The configuration of the node
+    config: HotShotConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+    /// The storage of the node, required
+    storage: Option<I::Storage>,
+    /// The memberships of the node, required
+    memberships: Option<Memberships<TYPES>>,
+    /// The networks of the node, required
+    networks: Option<Networks<TYPES, I>>,
+    /// Where the node starts from, required
+    initializer: Option<HotShotInitializer<TYPES>>,
+    /// The metrics consensus reports to, none by default
+    metrics: Option<ConsensusMetricsValue>,
+    /// The runtime tuning, overriding the one of the initializer if set
+    runtime: Option<RuntimeConfig>,
+    /// Whether the node only observes consensus, overriding the initializer if set
+    observer: Option<bool>,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContextBuilder<TYPES, I> {
+    /// Start building the node with the keys `public_key` and `private_key`, the id `node_id`, and
+    /// the configuration `config`
+    #[must_use]
+    pub fn new(
+        public_key: TYPES::SignatureKey,
+        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        node_id: u64,
+        config: HotShotConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+    ) -> Self {
+        Self {
+            public_key,
+            private_key,
+            node_id,
+            config,
+            storage: None,
+            memberships: None,
+            networks: None,
+            initializer: None,
+            metrics: None,
+            runtime: None,
+            observer: None,
+        }
+    }
+
+    /// The storage of the node. Required.
+    #[must_use]
+    pub fn storage(mut self, storage: I::Storage) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// The memberships of the node. Required.
+    #[must_use]
+    pub fn memberships(mut self, memberships: Memberships<TYPES>) -> Self {
+        self.memberships = Some(memberships);
+        self
+    }
+
+    /// The networks of the node, assumed to be primed already. Required.
+    #[must_use]
+    pub fn networks(mut self, networks: Networks<TYPES, I>) -> Self {
+        self.networks = Some(networks);
+        self
+    }
+
+    /// Where the node starts from, genesis or a reloaded state. Required.
+    #[must_use]
+    pub fn initializer(mut self, initializer: HotShotInitializer<TYPES>) -> Self {
+        self.initializer = Some(initializer);
+        self
+    }
+
+    /// The metrics consensus reports to. Metrics are dropped if none are given.
+    #[must_use]
+    pub fn metrics(mut self, metrics: ConsensusMetricsValue) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// How the work of the node is spread over threads, see
+    /// [`HotShotInitializer::with_runtime`]. Overrides the tuning of the initializer.
+    #[must_use]
+    pub fn runtime(mut self, runtime: RuntimeConfig) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Whether the node only follows consensus, see [`HotShotInitializer::with_observer`].
+    /// Overrides the initializer.
+    #[must_use]
+    pub fn observer(mut self, observer: bool) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Check that every required part was given, and build the [`SystemContext`] without
+    /// spawning its tasks, as [`SystemContext::new`] does
+    /// # Errors
+    /// If a required part is missing, or if [`SystemContext::new`] fails
+    pub async fn build_context(self) -> Result<SystemContext<TYPES, I>, HotShotError<TYPES>> {
+        let mut missing = Vec::new();
+        if self.storage.is_none() {
+            missing.push("storage");
+        }
+        if self.memberships.is_none() {
+            missing.push("memberships");
+        }
+        if self.networks.is_none() {
+            missing.push("networks");
+        }
+        if self.initializer.is_none() {
+            missing.push("initializer");
+        }
+        let (Some(storage), Some(memberships), Some(networks), Some(mut initializer)) = (
+            self.storage,
+            self.memberships,
+            self.networks,
+            self.initializer,
+        ) else {
+            return Err(HotShotError::InvalidConfig {
+                context: format!(
+                    "node {} is missing its {}",
+                    self.node_id,
+                    missing.join(", ")
+                ),
+            });
+        };
+        if let Some(runtime) = self.runtime {
+            initializer = initializer.with_runtime(runtime);
+        }
+        if let Some(observer) = self.observer {
+            initializer = initializer.with_observer(observer);
+        }
+
+        SystemContext::new(
+            self.public_key,
+            self.private_key,
+            self.node_id,
+            self.config,
+            storage,
+            memberships,
+            networks,
+            initializer,
+            self.metrics.unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Check that every required part was given, build the [`SystemContext`] and spawn its
+    /// tasks, as [`SystemContext::init`] does. Returns a handle to the node, and the sender and
+    /// receiver of its internal event stream.
+    /// # Errors
+    /// If a required part is missing, or if [`SystemContext::new`] fails
+    pub async fn build(
+        self,
+    ) -> Result<
+        (
+            SystemContextHandle<TYPES, I>,
+            Sender<HotShotEvent<TYPES>>,
+            Receiver<HotShotEvent<TYPES>>,
+        ),
+        HotShotError<TYPES>,
+    > {
+        let hotshot = self.build_context().await?;
+        let handle = hotshot.clone().run_tasks().await;
+        let (tx, rx) = hotshot.inner.internal_event_stream.clone();
+
+        Ok((handle, tx, rx.activate()))
+    }
+}
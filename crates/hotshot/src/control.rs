@@ -0,0 +1,152 @@
+//! Deterministic control of a node, for tests
+//!
+//! Regression tests of edge cases, such as a node receiving the certificate of a view far ahead
+//! of it, would otherwise need elaborate network choreography. With the `hotshot-testing` feature,
+//! [`SystemContext::test_control`] returns a [`TestControl`] which forces the view of a node,
+//! injects certificates into it as if they had been formed or received, and pauses and resumes
+//! its tasks one by one. Every task of a node waits at a `PauseGate` of its own between events,
+//! which stays open unless a test pauses it. Without the feature the gates are left out, and
+//! tasks never wait.
+
+#[cfg(feature = "hotshot-testing")]
+use std::collections::HashMap;
+
+#[cfg(feature = "hotshot-testing")]
+use hotshot_task::pause::PauseGate;
+#[cfg(feature = "hotshot-testing")]
+use hotshot_task_impls::{events::HotShotEvent, helpers::broadcast_event};
+#[cfg(feature = "hotshot-testing")]
+use hotshot_types::{
+    simple_certificate::{DACertificate, QuorumCertificate, TimeoutCertificate},
+    traits::node_implementation::NodeType,
+};
+
+#[cfg(feature = "hotshot-testing")]
+use crate::{traits::NodeImplementation, SystemContext};
+
+/// The tasks of a node which can be paused
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ControlledTask {
+    /// the consensus task
+    Consensus,
+    /// the DA task
+    DA,
+    /// the VID task
+    Vid,
+    /// the upgrade task
+    Upgrade,
+    /// the epoch task
+    Epoch,
+    /// the view sync task
+    ViewSync,
+    /// the transaction task
+    Transactions,
+    /// the block fetch task
+    BlockFetch,
+    /// the availability sampling task
+    AvailabilitySampling,
+}
+
+impl ControlledTask {
+    /// Every task which can be paused
+    pub const ALL: [Self; 9] = [
+        Self::Consensus,
+        Self::DA,
+        Self::Vid,
+        Self::Upgrade,
+        Self::Epoch,
+        Self::ViewSync,
+        Self::Transactions,
+        Self::BlockFetch,
+        Self::AvailabilitySampling,
+    ];
+}
+
+/// The pause gate of each task of a node
+#[cfg(feature = "hotshot-testing")]
+#[derive(Clone, Debug)]
+pub struct PauseGates(HashMap<ControlledTask, PauseGate>);
+
+#[cfg(feature = "hotshot-testing")]
+impl Default for PauseGates {
+    fn default() -> Self {
+        Self(
+            ControlledTask::ALL
+                .into_iter()
+                .map(|task| (task, PauseGate::default()))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(feature = "hotshot-testing")]
+impl PauseGates {
+    /// The gate of `task`
+    #[must_use]
+    pub fn gate(&self, task: ControlledTask) -> PauseGate {
+        self.0.get(&task).cloned().unwrap_or_default()
+    }
+}
+
+/// Controls a node deterministically, see the [module documentation](self)
+#[cfg(feature = "hotshot-testing")]
+pub struct TestControl<TYPES: NodeType, I: NodeImplementation<TYPES>> {
+    /// the node controlled
+    hotshot: SystemContext<TYPES, I>,
+}
+
+#[cfg(feature = "hotshot-testing")]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
+    /// A handle to control this node deterministically from a test
+    #[must_use]
+    pub fn test_control(&self) -> TestControl<TYPES, I> {
+        TestControl {
+            hotshot: self.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "hotshot-testing")]
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>> TestControl<TYPES, I> {
+    /// Move the tasks of the node to `view`, as if it had just entered it
+    pub async fn force_view(&self, view: TYPES::Time) {
+        self.inject(HotShotEvent::ViewChange(view)).await;
+    }
+
+    /// Hand `qc` to the tasks of the node, as if the node had just formed it
+    pub async fn inject_quorum_certificate(&self, qc: QuorumCertificate<TYPES>) {
+        self.inject(HotShotEvent::QCFormed(either::Left(qc))).await;
+    }
+
+    /// Hand `tc` to the tasks of the node, as if the node had just formed it
+    pub async fn inject_timeout_certificate(&self, tc: TimeoutCertificate<TYPES>) {
+        self.inject(HotShotEvent::QCFormed(either::Right(tc))).await;
+    }
+
+    /// Hand `dac` to the tasks of the node, as if the node had just received it
+    pub async fn inject_da_certificate(&self, dac: DACertificate<TYPES>) {
+        self.inject(HotShotEvent::DACRecv(dac)).await;
+    }
+
+    /// Hold `task` back before the next event it handles, until it is resumed. The events it is
+    /// sent meanwhile are handled in order once it is.
+    pub fn pause(&self, task: ControlledTask) {
+        self.hotshot.inner.pause_gates.gate(task).pause();
+    }
+
+    /// Let `task` handle its events again
+    pub fn resume(&self, task: ControlledTask) {
+        self.hotshot.inner.pause_gates.gate(task).resume();
+    }
+
+    /// Whether `task` is paused
+    #[must_use]
+    pub fn is_paused(&self, task: ControlledTask) -> bool {
+        self.hotshot.inner.pause_gates.gate(task).is_paused()
+    }
+
+    /// Broadcast `event` on the internal event stream of the node
+    async fn inject(&self, event: HotShotEvent<TYPES>) {
+        broadcast_event(event, &self.hotshot.inner.internal_event_stream.0).await;
+    }
+}
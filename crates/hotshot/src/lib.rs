@@ -5,6 +5,12 @@
 #[cfg(feature = "docs")]
 pub mod documentation;
 
+/// Step by step construction of a [`SystemContext`]
+pub mod builder;
+/// Deterministic control of a node, for tests
+pub mod control;
+/// Custom tasks embedders attach to a running node
+pub mod plugin;
 /// Contains traits consumed by [`SystemContext`]
 pub mod traits;
 /// Contains types used by the crate
@@ -12,56 +18,84 @@ pub mod types;
 
 pub mod tasks;
 
+#[cfg(feature = "hotshot-testing")]
+use crate::control::PauseGates;
 use crate::{
+    builder::SystemContextBuilder,
     tasks::{
-        add_consensus_task, add_da_task, add_network_event_task, add_network_message_task,
-        add_transaction_task, add_upgrade_task, add_view_sync_task,
+        add_availability_sampling_task, add_block_fetch_task, add_consensus_task, add_da_task,
+        add_epoch_task, add_network_event_task, add_network_message_task, add_transaction_task,
+        add_upgrade_task, add_view_sync_task, add_watchdog_task,
     },
     traits::{NodeImplementation, Storage},
     types::{Event, SystemContextHandle},
 };
 use async_broadcast::{broadcast, InactiveReceiver, Receiver, Sender};
 use async_compatibility_layer::art::async_spawn;
-use async_lock::RwLock;
+use async_lock::{Mutex, RwLock};
+#[cfg(async_executor_impl = "async-std")]
+use async_std::task::spawn_blocking;
 use async_trait::async_trait;
+use bytes::Bytes;
 use commit::Committable;
 use custom_debug::Debug;
 use futures::join;
-use hotshot_constants::{EVENT_CHANNEL_SIZE, VERSION_0_1};
+use hotshot_constants::{EVENT_CHANNEL_SIZE, TX_STATUS_RETENTION_VIEWS, VERSION_0_1};
+use hotshot_task_impls::bus::EventBus;
 use hotshot_task_impls::events::HotShotEvent;
 use hotshot_task_impls::helpers::broadcast_event;
-use hotshot_task_impls::network;
+use hotshot_task_impls::mempool::ShardedMempool;
+use hotshot_task_impls::network::{self, StaleViewFilter, VoteBatcher};
 
-use hotshot_task::task::TaskRegistry;
+use hotshot_task::{pool::TaskPool, task::TaskRegistry};
 use hotshot_types::{
     consensus::{Consensus, ConsensusMetricsValue, View, ViewInner},
-    data::Leaf,
-    error::StorageSnafu,
+    data::{Leaf, VidCommitment},
+    epoch::EpochSchedule,
+    error::{InvalidElectionConfigSnafu, StorageSnafu, TransactionRejectedSnafu},
     event::EventType,
+    genesis::GenesisFile,
+    governance::{ConsensusParameters, GovernancePolicy, NoGovernance, ParameterSchedule},
     message::{DataMessage, Message, MessageKind},
+    payload_store::{PayloadStore, StoredPayload},
+    qc_chain::StakeTable,
+    receipt::{TransactionIndex, TxReceipt},
+    reputation::LeaderScores,
+    rpc::NetworkRpc,
+    runtime::{self, RuntimeConfig},
+    safety::SafetyEvidence,
+    signing::{self, SigningContext},
     simple_certificate::QuorumCertificate,
+    storage_sync::{encode_wal_record, CommitAction, GroupCommit, WriteAheadLog},
     traits::{
+        clock::{Clock, SystemClock},
         consensus_api::ConsensusApi,
-        election::Membership,
-        network::ConnectedNetwork,
+        election::{ElectionConfig, Membership},
+        header_extension::{HeaderExtender, NoExtensions},
+        network::{ConnectedNetwork, NetworkError},
         node_implementation::{ConsensusTime, NodeType},
         signature_key::SignatureKey,
         states::ValidatedState,
-        storage::StoredView,
+        storage::{StorageError, StoredView, ViewArtifacts},
+        transaction_validator::{MaxSizeValidator, TransactionValidator},
         BlockPayload,
     },
+    view_latency::ViewLatencyEstimator,
     HotShotConfig,
 };
 use snafu::ResultExt;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io,
     marker::PhantomData,
     num::NonZeroUsize,
     sync::Arc,
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 use tasks::add_vid_task;
-use tracing::{debug, instrument, trace};
+#[cfg(async_executor_impl = "tokio")]
+use tokio::task::spawn_blocking;
+use tracing::{debug, error, instrument, trace};
 
 // -- Rexports
 // External
@@ -100,6 +134,17 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> Networks<TYPES, I> {
         self.quorum_network.shut_down().await;
         self.da_network.shut_down().await;
     }
+
+    /// partition both networks, see [`ConnectedNetwork::set_partition`]
+    /// # Errors
+    /// If either network cannot be partitioned
+    pub async fn set_partition(
+        &self,
+        reachable: Option<BTreeSet<TYPES::SignatureKey>>,
+    ) -> Result<(), NetworkError> {
+        self.quorum_network.set_partition(reachable.clone()).await?;
+        self.da_network.set_partition(reachable).await
+    }
 }
 
 /// Bundle of all the memberships a consensus instance uses
@@ -114,6 +159,41 @@ pub struct Memberships<TYPES: NodeType> {
     pub view_sync_membership: TYPES::Membership,
 }
 
+/// The pools the tasks of a consensus instance are spawned on, see [`RuntimeConfig`]
+#[derive(Clone, Default)]
+pub struct TaskPools {
+    /// Tasks receiving messages
+    pub inbound: TaskPool,
+    /// Tasks sending messages
+    pub outbound: TaskPool,
+    /// The consensus task
+    pub consensus: TaskPool,
+}
+
+impl TaskPools {
+    /// Start the threads `config` dedicates to tasks
+    /// # Errors
+    /// If the threads cannot be started
+    pub fn new(config: &RuntimeConfig) -> io::Result<Self> {
+        let pool = |name: &str, threads: usize| {
+            if threads == 0 {
+                Ok(TaskPool::shared())
+            } else {
+                TaskPool::dedicated(name, threads, None)
+            }
+        };
+        let consensus = match config.consensus_core {
+            Some(core) => TaskPool::dedicated("hotshot-consensus", 1, Some(core))?,
+            None => TaskPool::shared(),
+        };
+        Ok(Self {
+            inbound: pool("hotshot-inbound", config.inbound_threads)?,
+            outbound: pool("hotshot-outbound", config.outbound_threads)?,
+            consensus,
+        })
+    }
+}
+
 /// Holds the state needed to participate in `HotShot` consensus
 pub struct SystemContextInner<TYPES: NodeType, I: NodeImplementation<TYPES>> {
     /// The public key of this node
@@ -135,7 +215,25 @@ pub struct SystemContextInner<TYPES: NodeType, I: NodeImplementation<TYPES>> {
     pub memberships: Arc<Memberships<TYPES>>,
 
     /// the metrics that the implementor is using.
-    _metrics: Arc<ConsensusMetricsValue>,
+    metrics: Arc<ConsensusMetricsValue>,
+
+    /// Checks transactions before they reach the mempool, whether submitted or received
+    transaction_validator: Arc<dyn TransactionValidator<TYPES>>,
+
+    /// Fills in the extensions of the headers the node proposes, and checks those it is proposed
+    header_extender: Arc<dyn HeaderExtender<TYPES>>,
+
+    /// Decides which decided governance transactions change the consensus parameters
+    governance_policy: Arc<dyn GovernancePolicy>,
+
+    /// The time source of the timeouts of consensus and network tasks
+    clock: Arc<dyn Clock>,
+
+    /// The pools tasks are spawned on
+    pools: TaskPools,
+
+    /// Whether the node only follows consensus, without proposing or voting
+    observer: bool,
 
     /// The hotstuff implementation
     consensus: Arc<RwLock<Consensus<TYPES>>>,
@@ -150,10 +248,119 @@ pub struct SystemContextInner<TYPES: NodeType, I: NodeImplementation<TYPES>> {
         InactiveReceiver<HotShotEvent<TYPES>>,
     ),
 
+    /// The bus tasks publish lifecycle events on, for plugins
+    event_bus: EventBus<TYPES>,
+
+    /// The gates tasks wait at between events, which tests can pause
+    #[cfg(feature = "hotshot-testing")]
+    pause_gates: PauseGates,
+
+    /// The undecided transactions, held by the transaction task
+    mempool: Arc<ShardedMempool<TYPES>>,
+
+    /// What became of the transactions the node saw proposed, decided or dropped, kept by the
+    /// DA and transaction tasks
+    tx_index: Arc<RwLock<TransactionIndex<TYPES>>>,
+
+    /// Request/response calls to other nodes, over the quorum network
+    rpc: NetworkRpc<TYPES, I::QuorumNetwork>,
+
+    /// The writes to storage yet to be committed
+    storage_sync: Mutex<StorageSync>,
+
     /// uid for instrumentation
     pub id: u64,
 }
 
+/// The writes to storage since its last commit, and the write-ahead log of their leaves
+struct StorageSync {
+    /// the writes since the last commit
+    batch: GroupCommit,
+    /// the write-ahead log of the leaves written since the last commit, if there is one
+    wal: Option<WriteAheadLog>,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContextInner<TYPES, I> {
+    /// Record the writes to storage of `views` more views, made while holding `sync`, and
+    /// commit them if their batch is due, or arm a timer to commit their batch if they start it
+    async fn record_storage_writes(
+        self: &Arc<Self>,
+        sync: &mut StorageSync,
+        views: u64,
+    ) -> std::result::Result<(), StorageError> {
+        let action = sync.batch.record(views, self.clock.now());
+        self.apply_commit_action(sync, action).await
+    }
+
+    /// Commit the writes recorded in `sync` now, or later, as `action` says
+    async fn apply_commit_action(
+        self: &Arc<Self>,
+        sync: &mut StorageSync,
+        action: CommitAction,
+    ) -> std::result::Result<(), StorageError> {
+        match action {
+            CommitAction::Commit => self.commit_storage(sync).await,
+            CommitAction::CommitAfter(delay) => {
+                let batch = sync.batch.batch();
+                let timeout = self.clock.sleep(delay);
+                let inner = self.clone();
+                async_spawn(async move {
+                    timeout.await;
+                    let mut sync = inner.storage_sync.lock().await;
+                    // the batch may have been committed for its views meanwhile
+                    if sync.batch.batch() == batch && sync.batch.is_pending() {
+                        if let Err(e) = inner.commit_storage(&mut sync).await {
+                            error!("Failed to commit storage: {e:?}");
+                        }
+                    }
+                });
+                Ok(())
+            }
+            CommitAction::Wait => Ok(()),
+        }
+    }
+
+    /// Commit the pending writes to storage, and empty the write-ahead log of their leaves
+    async fn commit_storage(
+        &self,
+        sync: &mut StorageSync,
+    ) -> std::result::Result<(), StorageError> {
+        self.storage.commit().await?;
+        sync.batch.committed();
+        if let Some(wal) = sync.wal.clone() {
+            on_disk(move || wal.clear()).await?;
+        }
+        Ok(())
+    }
+
+    /// Commit whatever writes to storage are pending, e.g. before shutting down
+    async fn flush_storage(&self) -> std::result::Result<(), StorageError> {
+        let mut sync = self.storage_sync.lock().await;
+        if sync.batch.is_pending() {
+            self.commit_storage(&mut sync).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Run `op` on the write-ahead log on a blocking thread, so that syncing it to disk does not
+/// hold up the executor
+async fn on_disk(
+    op: impl FnOnce() -> std::io::Result<()> + Send + 'static,
+) -> std::result::Result<(), StorageError> {
+    let result = spawn_blocking(op).await;
+    #[cfg(async_executor_impl = "tokio")]
+    let result = result.unwrap_or_else(|e| {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            e.to_string(),
+        ))
+    });
+    result.map_err(|e| StorageError::WriteAheadLog {
+        reason: e.to_string(),
+    })
+}
+
 /// Thread safe, shared view of a `HotShot`
 // TODO Perhaps we can delete SystemContext since we only consume it in run_tasks()
 #[derive(Clone)]
@@ -183,15 +390,85 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
     ) -> Result<Self, HotShotError<TYPES>> {
         debug!("Creating a new hotshot");
 
+        if let Some(election_config) = &config.election_config {
+            election_config
+                .validate()
+                .context(InvalidElectionConfigSnafu)?;
+        }
+
+        // every node of the process signs for the same network
+        signing::configure(SigningContext {
+            chain_id: config.chain_id,
+            legacy: config.legacy_signing,
+        });
+
         let consensus_metrics = Arc::new(metrics);
         let anchored_leaf = initializer.inner;
         let instance_state = initializer.instance_state;
+        let transaction_validator = initializer.transaction_validator;
+        // the mempool checks every transaction as it enters, wherever it comes from
+        let mempool = Arc::new(
+            ShardedMempool::new(&consensus_metrics).with_validator(transaction_validator.clone()),
+        );
+        let header_extender = initializer.header_extender;
+        let governance_policy = initializer.governance_policy;
+        let clock = initializer.clock;
+        let observer = initializer.observer;
+        let runtime_error = |e: io::Error| HotShotError::Misc {
+            context: format!("Failed to start the threads of the runtime configuration: {e}"),
+        };
+        runtime::configure(&initializer.runtime).map_err(runtime_error)?;
+        let pools = TaskPools::new(&initializer.runtime).map_err(runtime_error)?;
+
+        let wal = match config.storage_sync.wal_path(nonce) {
+            Some(path) => {
+                let wal_error = |e: std::io::Error| HotShotError::Misc {
+                    context: format!("Failed to open the write-ahead log {}: {e}", path.display()),
+                };
+                // the initializer was read from storage without the leaves of the log, so it
+                // would resume from an older anchor than the node had
+                let uncommitted = WriteAheadLog::read(&path).map_err(wal_error)?.len();
+                if uncommitted > 0 {
+                    return Err(HotShotError::Misc {
+                        context: format!(
+                            "The write-ahead log {} holds {uncommitted} uncommitted leaves: \
+                             recover them with recover_storage before reading the initializer \
+                             from storage",
+                            path.display()
+                        ),
+                    });
+                }
+                Some(WriteAheadLog::open(&path).map_err(wal_error)?)
+            }
+            None => None,
+        };
+        let storage_sync = Mutex::new(StorageSync {
+            batch: GroupCommit::new(&config.storage_sync),
+            wal,
+        });
 
         // insert to storage
         storage
             .append(vec![anchored_leaf.clone().into()])
             .await
             .context(StorageSnafu)?;
+        // the stake table of the committee is in effect from the anchor on, unless a previous run
+        // already persisted it in effect at the anchor; one the committee changed since is
+        // superseded at the anchor, so that no view after it is taken to have the stale one
+        let anchor_view = anchored_leaf.get_view_number();
+        let stake_table = memberships.quorum_membership.get_committee_qc_stake_table();
+        if storage
+            .get_stake_table_at(anchor_view)
+            .await
+            .context(StorageSnafu)?
+            .as_ref()
+            != Some(&stake_table)
+        {
+            storage
+                .append_stake_table(anchor_view, stake_table)
+                .await
+                .context(StorageSnafu)?;
+        }
 
         // insert genesis (or latest block) to state map
         let mut validated_state_map = BTreeMap::default();
@@ -206,24 +483,49 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             },
         );
 
+        let payload_store = PayloadStore::new(config.payload_spill.clone());
         let mut saved_leaves = HashMap::new();
         let mut saved_payloads = BTreeMap::new();
         saved_leaves.insert(anchored_leaf.commit(), anchored_leaf.clone());
         if let Some(payload) = anchored_leaf.get_block_payload() {
-            let encoded_txns: Vec<u8> = match payload.encode() {
-                // TODO (Keyao) [VALIDATED_STATE] - Avoid collect/copy on the encoded transaction bytes.
-                // <https://github.com/EspressoSystems/HotShot/issues/2115>
+            let encoded_txns: Bytes = match payload.encode() {
                 Ok(encoded) => encoded.into_iter().collect(),
                 Err(e) => {
                     return Err(HotShotError::BlockError { source: e });
                 }
             };
+            let encoded_txns =
+                payload_store.intern(anchored_leaf.get_payload_commitment(), encoded_txns);
             saved_payloads.insert(anchored_leaf.get_view_number(), encoded_txns.clone());
             saved_payloads.insert(TYPES::Time::new(1), encoded_txns);
         }
 
         let start_view = anchored_leaf.get_view_number();
 
+        // a restarted node runs with the parameter changes decided and certified before it
+        // stopped, the DA committee included
+        let parameter_schedule = match storage
+            .get_parameter_schedule()
+            .await
+            .context(StorageSnafu)?
+        {
+            Some(schedule) => {
+                memberships
+                    .da_membership
+                    .resize_committee(schedule.parameters_at_view(start_view).da_committee_size);
+                schedule
+            }
+            None => ParameterSchedule::new(
+                ConsensusParameters {
+                    next_view_timeout: config.next_view_timeout,
+                    max_transactions: config.max_transactions,
+                    da_committee_size: config.da_committee_size as u64,
+                },
+                config.epoch_length.map(EpochSchedule::new),
+                config.total_nodes.get() as u64,
+            ),
+        };
+
         let consensus = Consensus {
             instance_state,
             validated_state_map,
@@ -231,12 +533,16 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             last_decided_view: anchored_leaf.get_view_number(),
             saved_leaves,
             saved_payloads,
+            payload_store,
             saved_da_certs: HashMap::new(),
             // TODO this is incorrect
             // https://github.com/EspressoSystems/HotShot/issues/560
             locked_view: anchored_leaf.get_view_number(),
             high_qc: anchored_leaf.get_justify_qc(),
             metrics: consensus_metrics.clone(),
+            view_latency: ViewLatencyEstimator::default(),
+            leader_scores: LeaderScores::default(),
+            parameter_schedule,
         };
         let consensus = Arc::new(RwLock::new(consensus));
 
@@ -247,6 +553,13 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
         // Our own copy of the receiver is inactive so it doesn't count.
         external_tx.set_await_active(false);
 
+        let rpc = NetworkRpc::new(
+            networks.quorum_network.clone(),
+            config.chain_id,
+            public_key.clone(),
+            private_key.clone(),
+            config.rpc,
+        );
         let inner: Arc<SystemContextInner<TYPES, I>> = Arc::new(SystemContextInner {
             id: nonce,
             consensus,
@@ -256,9 +569,24 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             storage,
             networks: Arc::new(networks),
             memberships: Arc::new(memberships),
-            _metrics: consensus_metrics.clone(),
+            metrics: consensus_metrics.clone(),
+            transaction_validator,
+            header_extender,
+            governance_policy,
+            storage_sync,
+            clock,
+            pools,
+            observer,
             internal_event_stream: (internal_tx, internal_rx.deactivate()),
             output_event_stream: (external_tx, external_rx.deactivate()),
+            event_bus: EventBus::default(),
+            #[cfg(feature = "hotshot-testing")]
+            pause_gates: PauseGates::default(),
+            mempool,
+            tx_index: Arc::new(RwLock::new(TransactionIndex::new(
+                TX_STATUS_RETENTION_VIEWS,
+            ))),
+            rpc,
         });
 
         Ok(Self { inner })
@@ -288,16 +616,27 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
         broadcast_event(event, &self.inner.output_event_stream.0).await;
     }
 
-    /// Publishes a transaction asynchronously to the network
+    /// Publishes a transaction asynchronously to the network, returning its receipt
     ///
     /// # Errors
     ///
-    /// Always returns Ok; does not return an error if the transaction couldn't be published to the network
+    /// Returns [`HotShotError::TransactionRejected`] if the transaction does not pass the
+    /// transaction validator; does not return an error if the transaction couldn't be published
+    /// to the network
     #[instrument(skip(self), err)]
     pub async fn publish_transaction_async(
         &self,
         transaction: TYPES::Transaction,
-    ) -> Result<(), HotShotError<TYPES>> {
+    ) -> Result<TxReceipt<TYPES>, HotShotError<TYPES>> {
+        if let Err(e) = self.inner.transaction_validator.validate(&transaction) {
+            self.inner.metrics.transactions_rejected.add(1);
+            return Err(e).context(TransactionRejectedSnafu);
+        }
+        let receipt = TxReceipt {
+            commitment: transaction.commit(),
+            submitted_at: SystemTime::now(),
+            node_view: self.inner.consensus.read().await.cur_view,
+        };
         trace!("Adding transaction to our own queue");
         // Wrap up a message
         // TODO place a view number here that makes sense
@@ -319,6 +658,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
                     .broadcast_message(
                         Message {
                             version: VERSION_0_1,
+                            chain_id: api.inner.config.chain_id,
                             sender: api.inner.public_key.clone(),
                             kind: MessageKind::from(message),
                         },
@@ -333,7 +673,13 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
                     }),
             }
         });
-        Ok(())
+        Ok(receipt)
+    }
+
+    /// Returns the time source of the timeouts of this instance
+    #[must_use]
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.inner.clock.clone()
     }
 
     /// Returns a copy of the consensus struct
@@ -342,6 +688,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
         self.inner.consensus.clone()
     }
 
+    /// Returns the consensus metrics of this instance
+    #[must_use]
+    pub fn get_metrics(&self) -> Arc<ConsensusMetricsValue> {
+        self.inner.metrics.clone()
+    }
+
     /// Returns a copy of the last decided leaf
     /// # Panics
     /// Panics if internal leaf for consensus is inconsistent
@@ -395,7 +747,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
     /// Upon encountering an unrecoverable error, such as a failure to send to a broadcast channel,
     /// the `HotShot` instance will log the error and shut down.
     ///
-    /// To construct a [`SystemContext`] without setting up tasks, use `fn new` instead.
+    /// To construct a [`SystemContext`] without setting up tasks, use `fn new` instead. To set
+    /// the parts of the node one at a time, use [`SystemContext::builder`].
     ///
     /// # Errors
     ///
@@ -419,24 +772,28 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
         ),
         HotShotError<TYPES>,
     > {
-        // Save a clone of the storage for the handle
-        let hotshot = Self::new(
-            public_key,
-            private_key,
-            node_id,
-            config,
-            storage,
-            memberships,
-            networks,
-            initializer,
-            metrics,
-        )
-        .await?;
-        let handle = hotshot.clone().run_tasks().await;
-        let (tx, rx) = hotshot.inner.internal_event_stream.clone();
+        Self::builder(public_key, private_key, node_id, config)
+            .storage(storage)
+            .memberships(memberships)
+            .networks(networks)
+            .initializer(initializer)
+            .metrics(metrics)
+            .build()
+            .await
+    }
 
-        Ok((handle, tx, rx.activate()))
+    /// Start building a [`SystemContext`] with the keys `public_key` and `private_key`, the id
+    /// `node_id` and the configuration `config`, see [`SystemContextBuilder`]
+    #[must_use]
+    pub fn builder(
+        public_key: TYPES::SignatureKey,
+        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        node_id: u64,
+        config: HotShotConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>,
+    ) -> SystemContextBuilder<TYPES, I> {
+        SystemContextBuilder::new(public_key, private_key, node_id, config)
     }
+
     /// return the timeout for a view for `self`
     #[must_use]
     pub fn get_next_view_timeout(&self) -> u64 {
@@ -454,6 +811,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
     /// Spawn all tasks that operate on [`SystemContextHandle`].
     ///
     /// For a list of which tasks are being spawned, see this module's documentation.
+    /// An observer spawns only the tasks following consensus and serving what the node has.
     #[allow(clippy::too_many_lines)]
     pub async fn run_tasks(self) -> SystemContextHandle<TYPES, I> {
         // ED Need to set first first number to 1, or properly trigger the change upon start
@@ -479,25 +837,110 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             storage: self.inner.storage.clone(),
         };
 
-        add_network_message_task(registry.clone(), event_tx.clone(), quorum_network.clone()).await;
-        add_network_message_task(registry.clone(), event_tx.clone(), da_network.clone()).await;
+        let chain_id = self.inner.config.chain_id;
+        let stale_view_filter = StaleViewFilter::new(self.inner.config.stale_view_grace);
+        // only the quorum network event task sends quorum and timeout votes
+        let vote_batcher = (self.inner.config.vote_batch_delay_ms > 0).then(|| {
+            VoteBatcher::new(Duration::from_millis(self.inner.config.vote_batch_delay_ms))
+        });
+        add_network_message_task(
+            registry.clone(),
+            event_tx.clone(),
+            quorum_network.clone(),
+            chain_id,
+            self.inner.metrics.clone(),
+            self.inner.transaction_validator.clone(),
+            self.inner.clock.clone(),
+            stale_view_filter.clone(),
+            self.inner.event_bus.clone(),
+            Some(Arc::new(self.inner.rpc.clone())),
+            Arc::new(quorum_membership.clone()),
+            &self.inner.pools.inbound,
+        )
+        .await;
+        add_network_message_task(
+            registry.clone(),
+            event_tx.clone(),
+            da_network.clone(),
+            chain_id,
+            self.inner.metrics.clone(),
+            self.inner.transaction_validator.clone(),
+            self.inner.clock.clone(),
+            stale_view_filter.clone(),
+            self.inner.event_bus.clone(),
+            None,
+            Arc::new(quorum_membership.clone()),
+            &self.inner.pools.inbound,
+        )
+        .await;
 
+        // an observer only sends what it serves to others
+        let (quorum_filter, vote_batcher): (fn(&HotShotEvent<TYPES>) -> bool, _) =
+            if self.inner.observer {
+                (network::observer_filter, None)
+            } else {
+                (network::quorum_filter, vote_batcher)
+            };
         add_network_event_task(
             registry.clone(),
             event_tx.clone(),
             event_rx.activate_cloned(),
             quorum_network.clone(),
+            chain_id,
             quorum_membership,
-            network::quorum_filter,
+            quorum_filter,
+            stale_view_filter.clone(),
+            self.inner.metrics.clone(),
+            vote_batcher,
+            self.inner.event_bus.clone(),
+            &self.inner.pools.outbound,
+        )
+        .await;
+        add_consensus_task(
+            registry.clone(),
+            event_tx.clone(),
+            event_rx.activate_cloned(),
+            &handle,
+        )
+        .await;
+        add_block_fetch_task(
+            registry.clone(),
+            event_tx.clone(),
+            event_rx.activate_cloned(),
+            &handle,
+        )
+        .await;
+        add_availability_sampling_task(
+            registry.clone(),
+            event_tx.clone(),
+            event_rx.activate_cloned(),
+            &handle,
+        )
+        .await;
+        add_view_sync_task(
+            registry.clone(),
+            event_tx.clone(),
+            event_rx.activate_cloned(),
+            &handle,
         )
         .await;
+        if self.inner.observer {
+            return handle;
+        }
+
         add_network_event_task(
             registry.clone(),
             event_tx.clone(),
             event_rx.activate_cloned(),
             da_network.clone(),
+            chain_id,
             da_membership,
             network::committee_filter,
+            stale_view_filter.clone(),
+            self.inner.metrics.clone(),
+            None,
+            self.inner.event_bus.clone(),
+            &self.inner.pools.outbound,
         )
         .await;
         add_network_event_task(
@@ -505,8 +948,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             event_tx.clone(),
             event_rx.activate_cloned(),
             quorum_network.clone(),
+            chain_id,
             view_sync_membership,
             network::view_sync_filter,
+            stale_view_filter.clone(),
+            self.inner.metrics.clone(),
+            None,
+            self.inner.event_bus.clone(),
+            &self.inner.pools.outbound,
         )
         .await;
         add_network_event_task(
@@ -514,15 +963,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             event_tx.clone(),
             event_rx.activate_cloned(),
             quorum_network.clone(),
+            chain_id,
             vid_membership,
             network::vid_filter,
-        )
-        .await;
-        add_consensus_task(
-            registry.clone(),
-            event_tx.clone(),
-            event_rx.activate_cloned(),
-            &handle,
+            stale_view_filter.clone(),
+            self.inner.metrics.clone(),
+            None,
+            self.inner.event_bus.clone(),
+            &self.inner.pools.outbound,
         )
         .await;
         add_da_task(
@@ -546,13 +994,6 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             &handle,
         )
         .await;
-        add_view_sync_task(
-            registry.clone(),
-            event_tx.clone(),
-            event_rx.activate_cloned(),
-            &handle,
-        )
-        .await;
         add_upgrade_task(
             registry.clone(),
             event_tx.clone(),
@@ -560,6 +1001,26 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> SystemContext<TYPES, I> {
             &handle,
         )
         .await;
+        if let Some(epoch_length) = self.inner.config.epoch_length {
+            add_epoch_task(
+                registry.clone(),
+                event_tx.clone(),
+                event_rx.activate_cloned(),
+                &handle,
+                epoch_length,
+            )
+            .await;
+        }
+        if let Some(stall_timeout) = self.inner.config.stall_timeout {
+            add_watchdog_task(
+                registry.clone(),
+                event_tx.clone(),
+                event_rx.activate_cloned(),
+                &handle,
+                stall_timeout,
+            )
+            .await;
+        }
         handle
     }
 }
@@ -595,6 +1056,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> ConsensusApi<TYPES, I>
         self.inner.config.min_transactions
     }
 
+    fn system_transactions_per_block(&self) -> usize {
+        self.inner.config.system_transactions_per_block
+    }
+
+    fn vote_progress_steps(&self) -> Option<u64> {
+        self.inner.config.vote_progress_steps
+    }
+
     async fn send_event(&self, event: Event<TYPES>) {
         debug!(?event, "send_event");
         broadcast_event(event, &self.inner.output_event_stream.0).await;
@@ -608,6 +1077,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> ConsensusApi<TYPES, I>
         &self.inner.private_key
     }
 
+    fn metrics(&self) -> &ConsensusMetricsValue {
+        &self.inner.metrics
+    }
+
     async fn store_leaf(
         &self,
         old_anchor_view: TYPES::Time,
@@ -615,10 +1088,104 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>> ConsensusApi<TYPES, I>
     ) -> std::result::Result<(), hotshot_types::traits::storage::StorageError> {
         let view_to_insert = StoredView::from(leaf);
         let storage = &self.inner.storage;
+        let mut sync = self.inner.storage_sync.lock().await;
+        let action = sync.batch.record(1, self.inner.clock.now());
+        // a leaf committed along with its write needs no log, which saves a sync
+        if action != CommitAction::Commit {
+            if let Some(wal) = sync.wal.clone() {
+                let record = encode_wal_record(&view_to_insert)?;
+                on_disk(move || wal.append(&[record])).await?;
+            }
+        }
         storage.append_single_view(view_to_insert).await?;
         storage.cleanup_storage_up_to_view(old_anchor_view).await?;
-        storage.commit().await?;
-        Ok(())
+        match storage.cleanup_votes_before_view(old_anchor_view).await {
+            Ok(_) | Err(StorageError::Unsupported { .. }) => {}
+            Err(e) => return Err(e),
+        }
+        if let Some(retention) = self.inner.config.stake_table_retention_views {
+            let horizon = TYPES::Time::new((*old_anchor_view).saturating_sub(retention));
+            storage.cleanup_stake_tables_before_view(horizon).await?;
+        }
+        if let Some(retention) = self.inner.config.view_artifact_retention_views {
+            let horizon = TYPES::Time::new((*old_anchor_view).saturating_sub(retention));
+            match storage.cleanup_view_artifacts_before_view(horizon).await {
+                Ok(_) | Err(StorageError::Unsupported { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.inner.apply_commit_action(&mut sync, action).await
+    }
+
+    async fn store_view_artifacts(
+        &self,
+        artifacts: Vec<ViewArtifacts<TYPES>>,
+    ) -> std::result::Result<(), hotshot_types::traits::storage::StorageError> {
+        let mut sync = self.inner.storage_sync.lock().await;
+        for view_artifacts in artifacts {
+            self.inner
+                .storage
+                .append_view_artifacts(view_artifacts)
+                .await?;
+        }
+        // part of the views of the leaves they were produced for
+        self.inner.record_storage_writes(&mut sync, 0).await
+    }
+
+    async fn store_payloads(
+        &self,
+        payloads: Vec<(TYPES::Time, VidCommitment, StoredPayload)>,
+    ) -> std::result::Result<(), hotshot_types::traits::storage::StorageError> {
+        let mut sync = self.inner.storage_sync.lock().await;
+        for (view, payload_commitment, payload) in payloads {
+            self.inner
+                .storage
+                .append_payload(view, payload_commitment, payload)
+                .await?;
+        }
+        self.inner.record_storage_writes(&mut sync, 0).await
+    }
+
+    async fn store_safety_evidence(
+        &self,
+        evidence: SafetyEvidence<TYPES>,
+    ) -> std::result::Result<(), hotshot_types::traits::storage::StorageError> {
+        self.inner.storage.append_safety_evidence(evidence).await
+    }
+
+    async fn store_stake_table(
+        &self,
+        view: TYPES::Time,
+        stake_table: StakeTable<TYPES>,
+    ) -> std::result::Result<(), hotshot_types::traits::storage::StorageError> {
+        self.inner
+            .storage
+            .append_stake_table(view, stake_table)
+            .await
+    }
+
+    async fn store_parameter_schedule(
+        &self,
+        schedule: ParameterSchedule,
+    ) -> std::result::Result<(), hotshot_types::traits::storage::StorageError> {
+        self.inner.storage.append_parameter_schedule(schedule).await
+    }
+
+    async fn store_vote(
+        &self,
+        view: TYPES::Time,
+        commitment: Vec<u8>,
+        vote: Vec<u8>,
+    ) -> std::result::Result<(), hotshot_types::traits::storage::StorageError> {
+        self.inner.storage.append_vote(view, commitment, vote).await
+    }
+
+    async fn get_stored_votes(
+        &self,
+        view: TYPES::Time,
+        commitment: &[u8],
+    ) -> std::result::Result<Vec<Vec<u8>>, hotshot_types::traits::storage::StorageError> {
+        self.inner.storage.get_votes(view, commitment).await
     }
 }
 
@@ -629,6 +1196,24 @@ pub struct HotShotInitializer<TYPES: NodeType> {
 
     /// Instance-level state.
     instance_state: TYPES::InstanceState,
+
+    /// Checks transactions before they reach the mempool
+    transaction_validator: Arc<dyn TransactionValidator<TYPES>>,
+
+    /// Fills in and checks the extensions of block headers
+    header_extender: Arc<dyn HeaderExtender<TYPES>>,
+
+    /// Decides which governance transactions change the consensus parameters
+    governance_policy: Arc<dyn GovernancePolicy>,
+
+    /// The time source of timeouts
+    clock: Arc<dyn Clock>,
+
+    /// How the work of the node is spread over threads
+    runtime: RuntimeConfig,
+
+    /// Whether the node only follows consensus, without proposing or voting
+    observer: bool,
 }
 
 impl<TYPES: NodeType> HotShotInitializer<TYPES> {
@@ -641,14 +1226,105 @@ impl<TYPES: NodeType> HotShotInitializer<TYPES> {
         Ok(Self {
             inner: Leaf::genesis(instance_state),
             instance_state: instance_state.clone(),
+            transaction_validator: Arc::new(MaxSizeValidator::default()),
+            header_extender: Arc::new(NoExtensions),
+            governance_policy: Arc::new(NoGovernance),
+            clock: Arc::new(SystemClock),
+            runtime: RuntimeConfig::default(),
+            observer: false,
         })
     }
 
+    /// initialize from genesis, checking the genesis state against the commitment in `genesis`
+    /// # Errors
+    /// If the genesis state does not match `genesis`
+    pub fn from_genesis_file(
+        instance_state: &TYPES::InstanceState,
+        genesis: &GenesisFile<TYPES::SignatureKey>,
+    ) -> Result<Self, HotShotError<TYPES>> {
+        genesis
+            .verify_state(&TYPES::ValidatedState::genesis(instance_state))
+            .map_err(|e| HotShotError::InvalidState {
+                context: e.to_string(),
+            })?;
+        Self::from_genesis(instance_state)
+    }
+
     /// reload previous state based on most recent leaf and the instance-level state.
     pub fn from_reload(anchor_leaf: Leaf<TYPES>, instance_state: TYPES::InstanceState) -> Self {
         Self {
             inner: anchor_leaf,
             instance_state,
+            transaction_validator: Arc::new(MaxSizeValidator::default()),
+            header_extender: Arc::new(NoExtensions),
+            governance_policy: Arc::new(NoGovernance),
+            clock: Arc::new(SystemClock),
+            runtime: RuntimeConfig::default(),
+            observer: false,
         }
     }
+
+    /// reload previous state from the anchored view of `storage`, as a node restarting with the
+    /// storage it ran with before does. A node with a write-ahead log first recovers its leaves
+    /// into `storage` with [`recover_storage`](hotshot_types::storage_sync::recover_storage).
+    /// # Errors
+    /// If `storage` holds no anchored view
+    pub async fn from_storage(
+        storage: &impl Storage<TYPES>,
+        instance_state: TYPES::InstanceState,
+    ) -> Result<Self, HotShotError<TYPES>> {
+        let anchored_view = storage.get_anchored_view().await.context(StorageSnafu)?;
+        Ok(Self::from_reload(anchored_view.into(), instance_state))
+    }
+
+    /// check transactions with `validator` instead of only limiting their size to
+    /// [`hotshot_constants::DEFAULT_MAX_TRANSACTION_SIZE`]
+    #[must_use]
+    pub fn with_transaction_validator(
+        mut self,
+        validator: impl TransactionValidator<TYPES>,
+    ) -> Self {
+        self.transaction_validator = Arc::new(validator);
+        self
+    }
+
+    /// fill in and check the extensions of block headers with `extender`, instead of building
+    /// headers without any
+    #[must_use]
+    pub fn with_header_extender(mut self, extender: impl HeaderExtender<TYPES>) -> Self {
+        self.header_extender = Arc::new(extender);
+        self
+    }
+
+    /// change the consensus parameters with the governance transactions `policy` authorizes,
+    /// instead of ignoring all of them
+    #[must_use]
+    pub fn with_governance_policy(mut self, policy: impl GovernancePolicy) -> Self {
+        self.governance_policy = Arc::new(policy);
+        self
+    }
+
+    /// time out views and poll networks on `clock` instead of the wall clock, e.g. a
+    /// [`MockClock`](hotshot_types::traits::clock::MockClock) in tests
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// spread the work of the node over the threads `runtime` asks for, instead of running it
+    /// all on the runtime of the caller
+    #[must_use]
+    pub fn with_runtime(mut self, runtime: RuntimeConfig) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    /// only follow consensus, and serve the blocks and samples the node has, without proposing,
+    /// voting, nor gossiping transactions
+    #[must_use]
+    pub fn with_observer(mut self, observer: bool) -> Self {
+        self.observer = observer;
+        self
+    }
 }
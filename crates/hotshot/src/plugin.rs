@@ -0,0 +1,59 @@
+//! Plugins: custom tasks embedders attach to a running node
+//!
+//! A [`HotShotPlugin`] is registered on the [`SystemContextHandle`] of a node, with
+//! [`SystemContextHandle::register_plugin`], and runs as a task of its own next to the tasks of
+//! the node. It follows what the node does through the [`BusEvent`]s the consensus, DA, network
+//! and view sync tasks publish, rather than through the internal event stream, which changes
+//! with the tasks, and reaches the rest of the node through its handle. It is stopped with the
+//! node, when the handle is shut down.
+
+use async_broadcast::Receiver;
+use async_compatibility_layer::art::async_spawn;
+use async_trait::async_trait;
+use hotshot_types::traits::node_implementation::NodeType;
+use tracing::info;
+
+use crate::{traits::NodeImplementation, types::SystemContextHandle};
+
+pub use hotshot_task_impls::bus::BusEvent;
+
+/// What a plugin is handed when it starts
+pub struct PluginContext<TYPES: NodeType, I: NodeImplementation<TYPES>> {
+    /// The lifecycle events the tasks of the node publish from the start of the plugin on
+    pub events: Receiver<BusEvent<TYPES>>,
+    /// The handle of the node the plugin was registered on
+    pub handle: SystemContextHandle<TYPES, I>,
+}
+
+/// A custom task attached to a node, see the [module documentation](self)
+#[async_trait]
+pub trait HotShotPlugin<TYPES: NodeType, I: NodeImplementation<TYPES>>: Send + 'static {
+    /// The name of the plugin, for logging
+    fn name(&self) -> String;
+
+    /// Run the plugin until it is done, or until the node shuts down
+    async fn run(self: Box<Self>, context: PluginContext<TYPES, I>);
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static> SystemContextHandle<TYPES, I> {
+    /// Start `plugin` as a task of its own, which is stopped when the node is shut down
+    pub async fn register_plugin(&self, plugin: Box<dyn HotShotPlugin<TYPES, I>>) {
+        let name = plugin.name();
+        info!("Registering plugin {name}");
+        let context = PluginContext {
+            events: self.subscribe_event_bus(),
+            handle: self.clone(),
+        };
+        let handle = async_spawn(async move {
+            plugin.run(context).await;
+            info!("Plugin {name} is done");
+        });
+        self.registry.register(handle).await;
+    }
+
+    /// Receive the lifecycle events the tasks of the node publish from now on
+    #[must_use]
+    pub fn subscribe_event_bus(&self) -> Receiver<BusEvent<TYPES>> {
+        self.hotshot.inner.event_bus.subscribe()
+    }
+}
@@ -1,35 +1,59 @@
 //! Provides a number of tasks that run continuously
 
-use crate::{types::SystemContextHandle, HotShotConsensusApi};
+use crate::{control::ControlledTask, types::SystemContextHandle, HotShotConsensusApi};
 use async_broadcast::{Receiver, Sender};
-use async_compatibility_layer::art::{async_sleep, async_spawn};
-
-use hotshot_constants::VERSION_0_1;
-use hotshot_task::task::{Task, TaskRegistry};
+use hotshot_constants::{
+    COMPACT_DA_ANNOUNCEMENT_RETENTION_VIEWS, FUTURE_VIEW_BUFFER_CAPACITY,
+    FUTURE_VIEW_BUFFER_HORIZON, MEMPOOL_SYNC_DECIDED_RETENTION_VIEWS, MEMPOOL_SYNC_INTERVAL_VIEWS,
+    MEMPOOL_SYNC_MAX_COMMITMENTS, SAFETY_MONITOR_HISTORY_VIEWS, VERSION_0_1,
+};
+use hotshot_task::{
+    pool::TaskPool,
+    task::{Task, TaskRegistry, TaskState},
+};
 use hotshot_task_impls::{
+    availability_sampling::AvailabilitySamplingTaskState,
+    block_fetch::BlockFetchTaskState,
+    bus::EventBus,
+    compact_block::AnnouncedTransactions,
     consensus::{CommitmentAndMetadata, ConsensusTaskState},
-    da::DATaskState,
+    da::{DAFallbackState, DATaskState},
+    epoch::EpochTaskState,
     events::HotShotEvent,
-    network::{NetworkEventTaskState, NetworkMessageTaskState},
+    future_view_buffer::FutureViewBuffer,
+    mempool_sync::MempoolSync,
+    network::{NetworkEventTaskState, NetworkMessageTaskState, StaleViewFilter, VoteBatcher},
+    state_pipeline::StatePipeline,
     transactions::TransactionTaskState,
     upgrade::UpgradeTaskState,
     vid::VIDTaskState,
     view_sync::ViewSyncTaskState,
+    watchdog::WatchdogTaskState,
 };
 use hotshot_types::{
+    consensus::ConsensusMetricsValue,
+    epoch::{EpochParameters, EpochSchedule},
     event::Event,
     message::Messages,
+    rpc::RpcEndpoint,
+    safety::SafetyMonitor,
+    stall::StallTracker,
     traits::{
         block_contents::vid_commitment,
+        clock::Clock,
         consensus_api::ConsensusApi,
         network::{ConsensusIntentEvent, TransmitType},
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
+        transaction_validator::TransactionValidator,
         BlockPayload,
     },
 };
 use hotshot_types::{
     message::Message,
-    traits::{election::Membership, network::ConnectedNetwork},
+    traits::{
+        election::Membership,
+        network::{prioritize, ConnectedNetwork, NetworkMsg},
+    },
 };
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
@@ -48,7 +72,49 @@ pub enum GlobalEvent {
     Dummy,
 }
 
+/// Let other tasks run before continuing
+async fn yield_now() {
+    #[cfg(async_executor_impl = "tokio")]
+    tokio::task::yield_now().await;
+    #[cfg(async_executor_impl = "async-std")]
+    async_std::task::yield_now().await;
+}
+
+/// Maximum number of received batches of messages waiting for the handler of the network
+/// message task, before the receive loops wait for it
+const RECEIVED_BATCHES_CAPACITY: usize = 64;
+
+/// Handle `pending` and whatever else is received meanwhile, one priority class at a time. Before
+/// each class, the batches received since are merged in and the whole backlog is sorted, so an
+/// urgent message which arrived behind a batch of transactions, on either receive loop, is handled
+/// before the rest of them. We yield between classes to let the receive loops run.
+async fn handle_by_priority<TYPES: NodeType>(
+    state: &mut NetworkMessageTaskState<TYPES>,
+    received: &mut Receiver<Vec<Message<TYPES>>>,
+    mut pending: Vec<Message<TYPES>>,
+) {
+    while !pending.is_empty() {
+        while let Ok(batch) = received.try_recv() {
+            pending.extend(batch);
+        }
+        prioritize(&mut pending);
+        let priority = pending[0].priority();
+        let class_len = pending
+            .iter()
+            .position(|message| message.priority() != priority)
+            .unwrap_or(pending.len());
+        let rest = pending.split_off(class_len);
+        state
+            .handle_messages(std::mem::replace(&mut pending, rest))
+            .await;
+        if !pending.is_empty() {
+            yield_now().await;
+        }
+    }
+}
+
 /// Add the network task to handle messages and publish events.
+#[allow(clippy::too_many_arguments)]
 pub async fn add_network_message_task<
     TYPES: NodeType,
     NET: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>,
@@ -56,62 +122,73 @@ pub async fn add_network_message_task<
     task_reg: Arc<TaskRegistry>,
     event_stream: Sender<HotShotEvent<TYPES>>,
     channel: Arc<NET>,
+    chain_id: u64,
+    metrics: Arc<ConsensusMetricsValue>,
+    transaction_validator: Arc<dyn TransactionValidator<TYPES>>,
+    clock: Arc<dyn Clock>,
+    stale_view_filter: StaleViewFilter,
+    event_bus: EventBus<TYPES>,
+    rpc: Option<Arc<dyn RpcEndpoint<TYPES::SignatureKey>>>,
+    quorum_membership: Arc<TYPES::Membership>,
+    pool: &TaskPool,
 ) {
     let net = channel.clone();
-    let network_state: NetworkMessageTaskState<_> = NetworkMessageTaskState {
+    let mut state: NetworkMessageTaskState<_> = NetworkMessageTaskState {
         event_stream: event_stream.clone(),
+        chain_id,
+        metrics,
+        transaction_validator,
+        stale_view_filter,
+        event_bus,
+        rpc,
+        quorum_membership,
     };
 
+    // both receive loops hand their batches to a single handler, which orders everything received
+    // so far as a whole
+    let (batch_sender, mut batch_receiver) = async_broadcast::broadcast(RECEIVED_BATCHES_CAPACITY);
+    let handler_handle = pool.spawn(async move {
+        while let Ok(batch) = batch_receiver.recv().await {
+            handle_by_priority(&mut state, &mut batch_receiver, batch).await;
+        }
+    });
+
     // TODO we don't need two async tasks for this, we should combine the
     // by getting rid of `TransmitType`
     // https://github.com/EspressoSystems/HotShot/issues/2377
-    let network = net.clone();
-    let mut state = network_state.clone();
-    let broadcast_handle = async_spawn(async move {
-        loop {
-            let msgs = match network.recv_msgs(TransmitType::Broadcast).await {
-                Ok(msgs) => Messages(msgs),
-                Err(err) => {
-                    error!("failed to receive broadcast messages: {err}");
-
-                    // return zero messages so we sleep and try again
-                    Messages(vec![])
-                }
-            };
-            if msgs.0.is_empty() {
-                // TODO: Stop sleeping here: https://github.com/EspressoSystems/HotShot/issues/2558
-                async_sleep(Duration::from_millis(100)).await;
-            } else {
-                state.handle_messages(msgs.0).await;
-            }
-        }
-    });
-    let network = net.clone();
-    let mut state = network_state.clone();
-    let direct_handle = async_spawn(async move {
-        loop {
-            let msgs = match network.recv_msgs(TransmitType::Direct).await {
-                Ok(msgs) => Messages(msgs),
-                Err(err) => {
-                    error!("failed to receive direct messages: {err}");
-
-                    // return zero messages so we sleep and try again
-                    Messages(vec![])
+    let mut receive_handles = Vec::new();
+    for transmit_type in [TransmitType::Broadcast, TransmitType::Direct] {
+        let network = net.clone();
+        let clock = clock.clone();
+        let batch_sender = batch_sender.clone();
+        receive_handles.push(pool.spawn(async move {
+            loop {
+                let msgs = match network.recv_msgs(transmit_type).await {
+                    Ok(msgs) => Messages(msgs),
+                    Err(err) => {
+                        error!("failed to receive {transmit_type:?} messages: {err}");
+
+                        // return zero messages so we sleep and try again
+                        Messages(vec![])
+                    }
+                };
+                if msgs.0.is_empty() {
+                    // TODO: Stop sleeping here: https://github.com/EspressoSystems/HotShot/issues/2558
+                    clock.sleep(Duration::from_millis(100)).await;
+                } else if batch_sender.broadcast(msgs.0).await.is_err() {
+                    break;
                 }
-            };
-            if msgs.0.is_empty() {
-                // TODO: Stop sleeping here: https://github.com/EspressoSystems/HotShot/issues/2558
-                async_sleep(Duration::from_millis(100)).await;
-            } else {
-                state.handle_messages(msgs.0).await;
             }
-        }
-    });
-    task_reg.register(direct_handle).await;
-    task_reg.register(broadcast_handle).await;
+        }));
+    }
+    for handle in receive_handles {
+        task_reg.register(handle).await;
+    }
+    task_reg.register(handler_handle).await;
 }
 
 /// Add the network task to handle events and send messages.
+#[allow(clippy::too_many_arguments)]
 pub async fn add_network_event_task<
     TYPES: NodeType,
     NET: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>,
@@ -120,17 +197,28 @@ pub async fn add_network_event_task<
     tx: Sender<HotShotEvent<TYPES>>,
     rx: Receiver<HotShotEvent<TYPES>>,
     channel: Arc<NET>,
+    chain_id: u64,
     membership: TYPES::Membership,
     filter: fn(&HotShotEvent<TYPES>) -> bool,
+    stale_view_filter: StaleViewFilter,
+    metrics: Arc<ConsensusMetricsValue>,
+    vote_batcher: Option<VoteBatcher<TYPES>>,
+    event_bus: EventBus<TYPES>,
+    pool: &TaskPool,
 ) {
     let network_state: NetworkEventTaskState<_, _> = NetworkEventTaskState {
         channel,
         view: TYPES::Time::genesis(),
+        chain_id,
         membership,
         filter,
+        stale_view_filter,
+        metrics,
+        vote_batcher,
+        event_bus,
     };
     let task = Task::new(tx, rx, task_reg.clone(), network_state);
-    task_reg.run_task(task).await;
+    task_reg.run_task_on(task, pool).await;
 }
 
 /// Create the consensus task state
@@ -148,7 +236,7 @@ pub async fn create_consensus_state<TYPES: NodeType, I: NodeImplementation<TYPES
     let (payload, metadata) = <TYPES::BlockPayload as BlockPayload>::genesis();
     // Impossible for `unwrap` to fail on the genesis payload.
     let payload_commitment = vid_commitment(
-        &payload.encode().unwrap().collect(),
+        &payload.encode().unwrap().collect::<Vec<u8>>(),
         handle
             .hotshot
             .inner
@@ -159,10 +247,11 @@ pub async fn create_consensus_state<TYPES: NodeType, I: NodeImplementation<TYPES
     // build the consensus task
     let consensus_state = ConsensusTaskState {
         consensus,
-        timeout: handle.hotshot.inner.config.next_view_timeout,
+        proposal_timeout: handle.hotshot.inner.config.proposal_timeout,
         cur_view: TYPES::Time::new(0),
         payload_commitment_and_metadata: Some(CommitmentAndMetadata {
             commitment: payload_commitment,
+            transactions_root: payload.transactions_root(&metadata),
             metadata,
             is_genesis: true,
         }),
@@ -171,6 +260,7 @@ pub async fn create_consensus_state<TYPES: NodeType, I: NodeImplementation<TYPES
         vote_collector: None.into(),
         timeout_vote_collector: None.into(),
         timeout_task: None,
+        proposal_timeout_task: None,
         timeout_cert: None,
         upgrade_cert: None,
         decided_upgrade_cert: None,
@@ -178,6 +268,20 @@ pub async fn create_consensus_state<TYPES: NodeType, I: NodeImplementation<TYPES
         output_event_stream: output_stream,
         vid_shares: BTreeMap::new(),
         current_proposal: None,
+        proposals: BTreeMap::new(),
+        future_events: FutureViewBuffer::new(
+            FUTURE_VIEW_BUFFER_CAPACITY,
+            FUTURE_VIEW_BUFFER_HORIZON,
+        ),
+        safety_monitor: SafetyMonitor::new(SAFETY_MONITOR_HISTORY_VIEWS),
+        halted: false,
+        header_extender: handle.hotshot.inner.header_extender.clone(),
+        governance_policy: handle.hotshot.inner.governance_policy.clone(),
+        parallel_state_application: handle.hotshot.inner.config.parallel_state_application,
+        state_pipeline: StatePipeline::default(),
+        clock: handle.hotshot.inner.clock.clone(),
+        view_start: None,
+        event_bus: handle.hotshot.inner.event_bus.clone(),
         id: handle.hotshot.inner.id,
         public_key: c_api.public_key().clone(),
         private_key: c_api.private_key().clone(),
@@ -217,6 +321,26 @@ pub async fn create_consensus_state<TYPES: NodeType, I: NodeImplementation<TYPES
     consensus_state
 }
 
+/// `task`, waiting at the pause gate of `controlled` so that tests can hold it back
+#[cfg(feature = "hotshot-testing")]
+fn pausable<TYPES: NodeType, I: NodeImplementation<TYPES>, S: TaskState + Send + 'static>(
+    task: Task<S>,
+    handle: &SystemContextHandle<TYPES, I>,
+    controlled: ControlledTask,
+) -> Task<S> {
+    task.with_pause_gate(handle.hotshot.inner.pause_gates.gate(controlled))
+}
+
+/// `task` as it is, tasks only being paused in tests
+#[cfg(not(feature = "hotshot-testing"))]
+fn pausable<TYPES: NodeType, I: NodeImplementation<TYPES>, S: TaskState + Send + 'static>(
+    task: Task<S>,
+    _handle: &SystemContextHandle<TYPES, I>,
+    _controlled: ControlledTask,
+) -> Task<S> {
+    task
+}
+
 /// add the consensus task
 pub async fn add_consensus_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
     task_reg: Arc<TaskRegistry>,
@@ -226,8 +350,14 @@ pub async fn add_consensus_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
 ) {
     let state =
         create_consensus_state(handle.hotshot.inner.output_event_stream.0.clone(), handle).await;
-    let task = Task::new(tx, rx, task_reg.clone(), state);
-    task_reg.run_task(task).await;
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), state),
+        handle,
+        ControlledTask::Consensus,
+    );
+    task_reg
+        .run_task_on(task, &handle.hotshot.inner.pools.consensus)
+        .await;
 }
 
 /// add the VID task
@@ -253,7 +383,11 @@ pub async fn add_vid_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
         id: handle.hotshot.inner.id,
     };
 
-    let task = Task::new(tx, rx, task_reg.clone(), vid_state);
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), vid_state),
+        handle,
+        ControlledTask::Vid,
+    );
     task_reg.run_task(task).await;
 }
 
@@ -282,7 +416,82 @@ pub async fn add_upgrade_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
         private_key: c_api.private_key().clone(),
         id: handle.hotshot.inner.id,
     };
-    let task = Task::new(tx, rx, task_reg.clone(), upgrade_state);
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), upgrade_state),
+        handle,
+        ControlledTask::Upgrade,
+    );
+    task_reg.run_task(task).await;
+}
+
+/// add the epoch task, which certifies the stake table and parameters of every epoch of
+/// `epoch_length` views at its boundary
+pub async fn add_epoch_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
+    task_reg: Arc<TaskRegistry>,
+    tx: Sender<HotShotEvent<TYPES>>,
+    rx: Receiver<HotShotEvent<TYPES>>,
+    handle: &SystemContextHandle<TYPES, I>,
+    epoch_length: u64,
+) {
+    let c_api: HotShotConsensusApi<TYPES, I> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let epoch_state = EpochTaskState {
+        api: c_api.clone(),
+        cur_view: TYPES::Time::new(0),
+        schedule: EpochSchedule::new(epoch_length),
+        parameters: EpochParameters {
+            epoch_length,
+            da_committee_size: c_api.inner.config.da_committee_size as u64,
+        },
+        consensus: handle.hotshot.get_consensus(),
+        quorum_membership: c_api.inner.memberships.quorum_membership.clone().into(),
+        da_membership: c_api.inner.memberships.da_membership.clone().into(),
+        quorum_network: c_api.inner.networks.quorum_network.clone(),
+        vote_collector: None.into(),
+        voted_epoch: None,
+        certified_epoch: None,
+        public_key: c_api.public_key().clone(),
+        private_key: c_api.private_key().clone(),
+        id: handle.hotshot.inner.id,
+    };
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), epoch_state),
+        handle,
+        ControlledTask::Epoch,
+    );
+    task_reg.run_task(task).await;
+}
+
+/// add the watchdog task, which reports the node stalled when its view does not change for
+/// `stall_timeout` milliseconds. It is not paused with the other tasks, so that a node whose
+/// consensus is paused is reported stalled too.
+pub async fn add_watchdog_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
+    task_reg: Arc<TaskRegistry>,
+    tx: Sender<HotShotEvent<TYPES>>,
+    rx: Receiver<HotShotEvent<TYPES>>,
+    handle: &SystemContextHandle<TYPES, I>,
+    stall_timeout: u64,
+) {
+    let c_api: HotShotConsensusApi<TYPES, I> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let clock = c_api.inner.clock.clone();
+    let mut watchdog_state = WatchdogTaskState {
+        api: c_api.clone(),
+        tracker: StallTracker::new(TYPES::Time::genesis(), clock.now()),
+        clock,
+        stall_timeout: Duration::from_millis(stall_timeout),
+        quorum_membership: c_api.inner.memberships.quorum_membership.clone().into(),
+        da_membership: c_api.inner.memberships.da_membership.clone().into(),
+        timer_task: None,
+        public_key: c_api.public_key().clone(),
+        id: handle.hotshot.inner.id,
+        _pd: PhantomData,
+    };
+    // a node which never leaves the genesis view is stalled too
+    watchdog_state.arm_timer(&tx).await;
+    let task = Task::new(tx, rx, task_reg.clone(), watchdog_state);
     task_reg.run_task(task).await;
 }
 
@@ -307,10 +516,81 @@ pub async fn add_da_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
         vote_collector: None.into(),
         public_key: c_api.public_key().clone(),
         private_key: c_api.private_key().clone(),
+        unicast_payload: c_api.inner.config.da_unicast_payload,
+        compact_proposals: c_api.inner.config.da_compact_proposals,
+        announced: AnnouncedTransactions::new(COMPACT_DA_ANNOUNCEMENT_RETENTION_VIEWS),
+        rebuilds: BTreeMap::new(),
+        mempool: handle.hotshot.inner.mempool.clone(),
+        event_bus: handle.hotshot.inner.event_bus.clone(),
+        fallback: DAFallbackState::new(
+            c_api.inner.config.da_fallback_after_failures,
+            c_api.inner.config.da_fallback_views,
+        ),
+        tx_index: handle.hotshot.inner.tx_index.clone(),
         id: handle.hotshot.inner.id,
     };
 
-    let task = Task::new(tx, rx, task_reg.clone(), da_state);
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), da_state),
+        handle,
+        ControlledTask::DA,
+    );
+    task_reg.run_task(task).await;
+}
+
+/// add the block fetch task
+pub async fn add_block_fetch_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
+    task_reg: Arc<TaskRegistry>,
+    tx: Sender<HotShotEvent<TYPES>>,
+    rx: Receiver<HotShotEvent<TYPES>>,
+    handle: &SystemContextHandle<TYPES, I>,
+) {
+    let c_api: HotShotConsensusApi<TYPES, I> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let block_fetch_state = BlockFetchTaskState::new(
+        handle.hotshot.get_consensus(),
+        c_api.inner.memberships.da_membership.clone().into(),
+        c_api.inner.memberships.quorum_membership.clone().into(),
+        c_api.public_key().clone(),
+        Some(Arc::new(handle.hotshot.inner.rpc.clone())),
+        handle.hotshot.inner.id,
+    );
+
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), block_fetch_state),
+        handle,
+        ControlledTask::BlockFetch,
+    );
+    task_reg.run_task(task).await;
+}
+
+/// add the availability sampling task
+pub async fn add_availability_sampling_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
+    task_reg: Arc<TaskRegistry>,
+    tx: Sender<HotShotEvent<TYPES>>,
+    rx: Receiver<HotShotEvent<TYPES>>,
+    handle: &SystemContextHandle<TYPES, I>,
+) {
+    let c_api: HotShotConsensusApi<TYPES, I> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let availability_sampling_state = AvailabilitySamplingTaskState::new(
+        handle.hotshot.get_consensus(),
+        c_api.inner.memberships.da_membership.clone().into(),
+        c_api.inner.memberships.quorum_membership.clone().into(),
+        c_api.public_key().clone(),
+        c_api.private_key().clone(),
+        c_api.inner.config.availability_samples,
+        handle.hotshot.inner.output_event_stream.0.clone(),
+        handle.hotshot.inner.id,
+    );
+
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), availability_sampling_state),
+        handle,
+        ControlledTask::AvailabilitySampling,
+    );
     task_reg.run_task(task).await;
 }
 
@@ -328,8 +608,14 @@ pub async fn add_transaction_task<TYPES: NodeType, I: NodeImplementation<TYPES>>
     let transactions_state = TransactionTaskState {
         api: c_api.clone(),
         consensus: handle.hotshot.get_consensus(),
-        transactions: Arc::default(),
+        transactions: handle.hotshot.inner.mempool.clone(),
+        tx_index: handle.hotshot.inner.tx_index.clone(),
         seen_transactions: HashSet::new(),
+        mempool_sync: MempoolSync::new(
+            MEMPOOL_SYNC_INTERVAL_VIEWS,
+            MEMPOOL_SYNC_MAX_COMMITMENTS,
+            MEMPOOL_SYNC_DECIDED_RETENTION_VIEWS,
+        ),
         cur_view: TYPES::Time::new(0),
         network: c_api.inner.networks.quorum_network.clone(),
         membership: c_api.inner.memberships.quorum_membership.clone().into(),
@@ -338,7 +624,11 @@ pub async fn add_transaction_task<TYPES: NodeType, I: NodeImplementation<TYPES>>
         id: handle.hotshot.inner.id,
     };
 
-    let task = Task::new(tx, rx, task_reg.clone(), transactions_state);
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), transactions_state),
+        handle,
+        ControlledTask::Transactions,
+    );
     task_reg.run_task(task).await;
 }
 /// add the view sync task
@@ -359,6 +649,7 @@ pub async fn add_view_sync_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
         membership: api.inner.memberships.view_sync_membership.clone().into(),
         public_key: api.public_key().clone(),
         private_key: api.private_key().clone(),
+        clock: api.inner.clock.clone(),
         api,
         num_timeouts_tracked: 0,
         replica_task_map: HashMap::default().into(),
@@ -366,10 +657,15 @@ pub async fn add_view_sync_task<TYPES: NodeType, I: NodeImplementation<TYPES>>(
         commit_relay_map: HashMap::default().into(),
         finalize_relay_map: HashMap::default().into(),
         view_sync_timeout: Duration::new(10, 0),
+        event_bus: handle.hotshot.inner.event_bus.clone(),
         id: handle.hotshot.inner.id,
         last_garbage_collected_view: TYPES::Time::new(0),
     };
 
-    let task = Task::new(tx, rx, task_reg.clone(), view_sync_state);
+    let task = pausable(
+        Task::new(tx, rx, task_reg.clone(), view_sync_state),
+        handle,
+        ControlledTask::ViewSync,
+    );
     task_reg.run_task(task).await;
 }
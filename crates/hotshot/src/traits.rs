@@ -16,9 +16,12 @@ pub mod implementations {
             combined_network::{
                 calculate_hash_of, Cache, CombinedNetworks, UnderlyingCombinedNetworks,
             },
-            libp2p_network::{Libp2pNetwork, PeerInfoVec},
+            libp2p_network::{Libp2pNetwork, PeerInfoVec, DA_TOPIC},
             memory_network::{MasterMap, MemoryNetwork},
-            web_server_network::WebServerNetwork,
+            trace_network::{
+                read_trace, PlaybackNetwork, RecordingNetwork, TraceEntry, TraceEvent,
+            },
+            web_server_network::{ShardRing, WebServerNetwork},
             NetworkingMetricsValue,
         },
         storage::memory_storage::MemoryStorage, // atomic_storage::AtomicStorage,
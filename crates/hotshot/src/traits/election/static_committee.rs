@@ -1,53 +1,268 @@
 // use ark_bls12_381::Parameters as Param381;
+use hotshot_types::reputation::{LeaderReputation, ReputationRules};
 use hotshot_types::signature_key::BLSPubKey;
+use hotshot_types::stake_table_cache::{stake_table_epoch, StakeTableCache, StakeTableCacheStats};
 use hotshot_types::traits::{
-    election::{ElectionConfig, Membership},
+    election::{ElectionConfig, ElectionError, FailureDomains, Membership, QuorumRules},
     node_implementation::NodeType,
-    signature_key::SignatureKey,
+    signature_key::{SignatureKey, StakeTableEntryType},
 };
 #[allow(deprecated)]
 use serde::{Deserialize, Serialize};
-use std::{marker::PhantomData, num::NonZeroU64};
-use tracing::debug;
+use std::{
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    num::NonZeroU64,
+    sync::{Arc, RwLock},
+};
+use tracing::{debug, warn};
 
 #[cfg(feature = "randomized-leader-election")]
 use rand::{rngs::StdRng, Rng};
 
+/// The nodes on a committee, of the size it was asked for
+struct CommitteeMembers<PUBKEY: SignatureKey> {
+    /// the size the committee was created or last resized with
+    size: usize,
+    /// the nodes on the committee and their stake
+    nodes_with_stake: Vec<PUBKEY::StakeTableEntry>,
+    /// the epoch of the committee stake table, which only changes as the committee is resized
+    stake_table_epoch: u64,
+}
+
+/// The members of a committee, shared by the clones of its membership so that they are all
+/// resized together
+struct SharedCommittee<PUBKEY: SignatureKey> {
+    /// the current members
+    state: Arc<RwLock<CommitteeMembers<PUBKEY>>>,
+}
+
+impl<PUBKEY: SignatureKey> SharedCommittee<PUBKEY> {
+    /// A committee of `nodes_with_stake`, created with `size`
+    fn new(size: usize, nodes_with_stake: Vec<PUBKEY::StakeTableEntry>) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(CommitteeMembers {
+                size,
+                stake_table_epoch: stake_table_epoch::<PUBKEY>(&nodes_with_stake),
+                nodes_with_stake,
+            })),
+        }
+    }
+
+    /// `f` of the current members and the epoch of their stake table
+    /// # Panics
+    /// If the lock on the members is poisoned
+    fn read<R>(&self, f: impl FnOnce(&[PUBKEY::StakeTableEntry], u64) -> R) -> R {
+        let members = self.state.read().unwrap();
+        f(&members.nodes_with_stake, members.stake_table_epoch)
+    }
+}
+
+impl<PUBKEY: SignatureKey> Clone for SharedCommittee<PUBKEY> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<PUBKEY: SignatureKey> Debug for SharedCommittee<PUBKEY> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.read(|members, _| f.debug_list().entries(members).finish())
+    }
+}
+
+impl<PUBKEY: SignatureKey> PartialEq for SharedCommittee<PUBKEY> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+            || self.read(|members, _| other.read(|others, _| members == others))
+    }
+}
+
+impl<PUBKEY: SignatureKey> Eq for SharedCommittee<PUBKEY> {}
+
+impl<PUBKEY: SignatureKey> Hash for SharedCommittee<PUBKEY> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.read(|members, _| members.hash(state));
+    }
+}
+
 /// Dummy implementation of [`Membership`]
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct GeneralStaticCommittee<T, PUBKEY: SignatureKey> {
     /// All the nodes participating and their stake
     nodes_with_stake: Vec<PUBKEY::StakeTableEntry>,
-    /// The nodes on the static committee and their stake
-    committee_nodes_with_stake: Vec<PUBKEY::StakeTableEntry>,
+    /// The nodes on the static committee and their stake, shared with the clones of the
+    /// committee
+    committee: SharedCommittee<PUBKEY>,
+    /// If set, the committee is sampled so that no failure domain holds too many of its members
+    failure_domains: Option<FailureDomains>,
+    /// The stake each kind of certificate needs from the committee
+    quorum_rules: QuorumRules,
+    /// If set, how leaders are down-weighted in the rotation, and their reputation so far
+    reputation: Option<(ReputationRules, LeaderReputation)>,
+    /// The indices and certificate parameters derived from the committee stake table
+    stake_table_cache: StakeTableCache<PUBKEY>,
     /// Node type phantom
     _type_phantom: PhantomData<T>,
 }
 
+/// The first `size` of `nodes_with_stake`, sampled under `failure_domains` if set
+fn sample_committee<PUBKEY: SignatureKey>(
+    nodes_with_stake: &[PUBKEY::StakeTableEntry],
+    failure_domains: Option<&FailureDomains>,
+    size: usize,
+) -> Vec<PUBKEY::StakeTableEntry> {
+    match failure_domains {
+        Some(domains) => {
+            let committee: Vec<_> = domains
+                .sample_committee(nodes_with_stake.len(), size)
+                .into_iter()
+                .map(|index| nodes_with_stake[index].clone())
+                .collect();
+            if committee.len() < size {
+                warn!(
+                    "Failure domains only allow a committee of {} out of {size} nodes",
+                    committee.len()
+                );
+            }
+            committee
+        }
+        None => nodes_with_stake.iter().take(size).cloned().collect(),
+    }
+}
+
 /// static committee using a vrf kp
 pub type StaticCommittee<T> = GeneralStaticCommittee<T, BLSPubKey>;
 
 impl<T, PUBKEY: SignatureKey> GeneralStaticCommittee<T, PUBKEY> {
+    /// The total stake of the committee, which thresholds are fractions of
+    fn total_stake(&self) -> u64 {
+        self.committee.read(|members, _| {
+            members
+                .iter()
+                .map(|entry| u64::try_from(entry.get_stake()).unwrap_or(u64::MAX))
+                .fold(0, u64::saturating_add)
+        })
+    }
+
     /// Creates a new dummy elector
     #[must_use]
     pub fn new(_nodes: &[PUBKEY], nodes_with_stake: Vec<PUBKEY::StakeTableEntry>) -> Self {
         Self {
-            nodes_with_stake: nodes_with_stake.clone(),
-            committee_nodes_with_stake: nodes_with_stake,
+            committee: SharedCommittee::new(nodes_with_stake.len(), nodes_with_stake.clone()),
+            nodes_with_stake,
+            failure_domains: None,
+            quorum_rules: QuorumRules::default(),
+            reputation: None,
+            stake_table_cache: StakeTableCache::default(),
             _type_phantom: PhantomData,
         }
     }
+
+    #[cfg(not(feature = "randomized-leader-election"))]
+    /// Index the vector of public keys with the current view number
+    fn rotation_index(&self, view_number: u64) -> usize {
+        usize::try_from(view_number % self.nodes_with_stake.len() as u64).unwrap()
+    }
+
+    #[cfg(feature = "randomized-leader-election")]
+    /// Index the vector of public keys with a random number generated using the current view number as a seed
+    fn rotation_index(&self, view_number: u64) -> usize {
+        let mut rng: StdRng = rand::SeedableRng::seed_from_u64(view_number);
+        let randomized_view_number: u64 = rng.gen();
+        (randomized_view_number % self.nodes_with_stake.len() as u64) as usize
+    }
 }
 
-/// configuration for static committee. stub for now
+/// configuration for static committee
 #[derive(Default, Clone, Serialize, Deserialize, core::fmt::Debug)]
 pub struct StaticElectionConfig {
     /// Number of nodes on the committee
     num_nodes: u64,
+    /// Certificate thresholds of this committee
+    #[serde(default)]
+    quorum_rules: QuorumRules,
+    /// Certificate thresholds of the DA committee, if this is the quorum's configuration
+    #[serde(default)]
+    da_rules: QuorumRules,
+    /// If set, the committee is sampled so that no failure domain holds too many of its members
+    #[serde(default)]
+    failure_domains: Option<FailureDomains>,
+    /// Failure domains the DA committee is sampled under, if this is the quorum's configuration
+    #[serde(default)]
+    da_failure_domains: Option<FailureDomains>,
+    /// If set, leaders which fail to get their proposals decided are down-weighted in the rotation
+    #[serde(default)]
+    leader_reputation: Option<ReputationRules>,
+    /// The reputation of the leaders, shared by the committees created from clones of this
+    /// configuration
+    #[serde(skip)]
+    reputation: LeaderReputation,
+}
+
+impl StaticElectionConfig {
+    /// Use `quorum_rules` for the certificates of this committee
+    #[must_use]
+    pub fn with_quorum_rules(mut self, quorum_rules: QuorumRules) -> Self {
+        self.quorum_rules = quorum_rules;
+        self
+    }
+
+    /// Use `da_rules` for the certificates of the DA committee derived from this configuration
+    #[must_use]
+    pub fn with_da_rules(mut self, da_rules: QuorumRules) -> Self {
+        self.da_rules = da_rules;
+        self
+    }
 }
 
-impl ElectionConfig for StaticElectionConfig {}
+impl ElectionConfig for StaticElectionConfig {
+    fn validate(&self) -> Result<(), ElectionError> {
+        if self.quorum_rules.honest_majority {
+            return Err(ElectionError::UnsafeQuorumRules {
+                reason: "honest majority mode is only allowed for the DA committee".to_string(),
+            });
+        }
+        self.quorum_rules.validate()?;
+        self.da_rules.validate()?;
+        for domains in [&self.failure_domains, &self.da_failure_domains]
+            .into_iter()
+            .flatten()
+        {
+            domains.validate()?;
+        }
+        if let Some(rules) = &self.leader_reputation {
+            rules.validate()?;
+        }
+        Ok(())
+    }
+
+    fn da_config(&self, num_nodes: u64) -> Self {
+        Self {
+            num_nodes,
+            quorum_rules: self.da_rules,
+            da_rules: self.da_rules,
+            failure_domains: self.da_failure_domains.clone(),
+            da_failure_domains: self.da_failure_domains.clone(),
+            // the DA committee rotates through the same leaders
+            leader_reputation: self.leader_reputation,
+            reputation: self.reputation.clone(),
+        }
+    }
+
+    fn with_da_failure_domains(mut self, domains: FailureDomains) -> Self {
+        self.da_failure_domains = Some(domains);
+        self
+    }
+
+    fn with_leader_reputation(mut self, rules: ReputationRules) -> Self {
+        self.leader_reputation = Some(rules);
+        self
+    }
+}
 
 impl<TYPES, PUBKEY: SignatureKey + 'static> Membership<TYPES>
     for GeneralStaticCommittee<TYPES, PUBKEY>
@@ -56,89 +271,134 @@ where
 {
     /// Clone the public key and corresponding stake table for current elected committee
     fn get_committee_qc_stake_table(&self) -> Vec<PUBKEY::StakeTableEntry> {
-        self.committee_nodes_with_stake.clone()
+        self.committee.read(|members, _| members.to_vec())
     }
 
-    #[cfg(not(feature = "randomized-leader-election"))]
-    /// Index the vector of public keys with the current view number
     fn get_leader(&self, view_number: TYPES::Time) -> PUBKEY {
-        let index = usize::try_from(*view_number % self.nodes_with_stake.len() as u64).unwrap();
-        let res = self.nodes_with_stake[index].clone();
-        TYPES::SignatureKey::get_public_key(&res)
-    }
-
-    #[cfg(feature = "randomized-leader-election")]
-    /// Index the vector of public keys with a random number generated using the current view number as a seed
-    fn get_leader(&self, view_number: TYPES::Time) -> PUBKEY {
-        let mut rng: StdRng = rand::SeedableRng::seed_from_u64(*view_number as u64);
-        let randomized_view_number: u64 = rng.gen();
-        let index = (randomized_view_number % self.nodes_with_stake.len() as u64) as usize;
+        let mut index = self.rotation_index(*view_number);
+        if let Some((rules, reputation)) = &self.reputation {
+            index = reputation.leader(rules, *view_number, index, self.nodes_with_stake.len());
+        }
         let res = self.nodes_with_stake[index].clone();
         TYPES::SignatureKey::get_public_key(&res)
     }
 
     fn has_stake(&self, pub_key: &PUBKEY) -> bool {
-        let entry = pub_key.get_stake_table_entry(1u64);
-        self.committee_nodes_with_stake.contains(&entry)
+        self.get_stake(pub_key).is_some()
     }
 
     fn get_stake(
         &self,
         pub_key: &<TYPES as NodeType>::SignatureKey,
     ) -> Option<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry> {
-        let entry = pub_key.get_stake_table_entry(1u64);
-        if self.committee_nodes_with_stake.contains(&entry) {
-            Some(entry)
-        } else {
-            None
-        }
+        self.committee.read(|members, epoch| {
+            let index = self.stake_table_cache.index_of(epoch, members, pub_key)?;
+            Some(members[index].clone())
+        })
+    }
+
+    fn get_committee_index(&self, pub_key: &PUBKEY) -> Option<usize> {
+        self.committee
+            .read(|members, epoch| self.stake_table_cache.index_of(epoch, members, pub_key))
+    }
+
+    fn get_committee_qc_params(&self, threshold: u64) -> PUBKEY::QCParams {
+        self.committee
+            .read(|members, epoch| self.stake_table_cache.params(epoch, members, threshold))
+    }
+
+    /// The stake table of a static committee is the same at every view, until it is resized
+    fn get_stake_table_at(&self, _view: TYPES::Time) -> Option<Vec<PUBKEY::StakeTableEntry>> {
+        Some(self.get_committee_qc_stake_table())
+    }
+
+    fn stake_table_cache_stats(&self) -> Option<StakeTableCacheStats> {
+        Some(self.stake_table_cache.stats())
     }
 
     fn default_election_config(num_nodes: u64) -> TYPES::ElectionConfigType {
-        StaticElectionConfig { num_nodes }
+        StaticElectionConfig {
+            num_nodes,
+            ..StaticElectionConfig::default()
+        }
     }
 
     fn create_election(
         keys_qc: Vec<PUBKEY::StakeTableEntry>,
         config: TYPES::ElectionConfigType,
     ) -> Self {
-        let mut committee_nodes_with_stake = keys_qc.clone();
         debug!("Election Membership Size: {}", config.num_nodes);
-        committee_nodes_with_stake.truncate(config.num_nodes.try_into().unwrap());
+        let num_nodes = config.num_nodes.try_into().unwrap();
+        let committee =
+            sample_committee::<PUBKEY>(&keys_qc, config.failure_domains.as_ref(), num_nodes);
         Self {
             nodes_with_stake: keys_qc,
-            committee_nodes_with_stake,
+            stake_table_cache: StakeTableCache::default(),
+            committee: SharedCommittee::new(num_nodes, committee),
+            failure_domains: config.failure_domains,
+            quorum_rules: config.quorum_rules,
+            reputation: config
+                .leader_reputation
+                .map(|rules| (rules, config.reputation.clone())),
             _type_phantom: PhantomData,
         }
     }
 
     fn total_nodes(&self) -> usize {
-        self.committee_nodes_with_stake.len()
+        self.committee.read(|members, _| members.len())
     }
 
     fn success_threshold(&self) -> NonZeroU64 {
-        NonZeroU64::new(((self.committee_nodes_with_stake.len() as u64 * 2) / 3) + 1).unwrap()
+        self.quorum_rules.success.threshold(self.total_stake())
     }
 
     fn failure_threshold(&self) -> NonZeroU64 {
-        NonZeroU64::new(((self.committee_nodes_with_stake.len() as u64) / 3) + 1).unwrap()
+        self.quorum_rules.failure.threshold(self.total_stake())
     }
 
     fn upgrade_threshold(&self) -> NonZeroU64 {
-        NonZeroU64::new(((self.committee_nodes_with_stake.len() as u64 * 9) / 10) + 1).unwrap()
+        self.quorum_rules.upgrade.threshold(self.total_stake())
+    }
+
+    fn record_decided_views(&self, views: &[TYPES::Time]) {
+        if let Some((rules, reputation)) = &self.reputation {
+            let views: Vec<_> = views.iter().map(|view| **view).collect();
+            reputation.record_decided(rules, &views, self.nodes_with_stake.len(), |view| {
+                self.rotation_index(view)
+            });
+        }
+    }
+
+    /// The committee is sampled again from all the nodes, as it was created
+    fn resize_committee(&self, size: u64) {
+        let size = usize::try_from(size).unwrap_or(usize::MAX);
+        let mut members = self.committee.state.write().unwrap();
+        if members.size == size {
+            return;
+        }
+        debug!(
+            "Resizing the committee from {} to {size} nodes",
+            members.size
+        );
+        let nodes_with_stake =
+            sample_committee::<PUBKEY>(&self.nodes_with_stake, self.failure_domains.as_ref(), size);
+        *members = CommitteeMembers {
+            size,
+            stake_table_epoch: stake_table_epoch::<PUBKEY>(&nodes_with_stake),
+            nodes_with_stake,
+        };
     }
 
     fn get_committee(
         &self,
         _view_number: <TYPES as NodeType>::Time,
     ) -> std::collections::BTreeSet<<TYPES as NodeType>::SignatureKey> {
-        // Transfer from committee_nodes_with_stake to pure committee_nodes
-        (0..self.committee_nodes_with_stake.len())
-            .map(|node_id| {
-                <TYPES as NodeType>::SignatureKey::get_public_key(
-                    &self.committee_nodes_with_stake[node_id],
-                )
-            })
-            .collect()
+        // Transfer from the committee members with stake to pure committee nodes
+        self.committee.read(|members, _| {
+            members
+                .iter()
+                .map(<TYPES as NodeType>::SignatureKey::get_public_key)
+                .collect()
+        })
     }
 }
@@ -4,10 +4,14 @@
 //! trait. Currently this includes
 //! - [`MemoryNetwork`](memory_network::MemoryNetwork), an in memory testing-only implementation
 //! - [`Libp2pNetwork`](libp2p_network::Libp2pNetwork), a production-ready networking implementation built on top of libp2p-rs.
+//! - [`RecordingNetwork`](trace_network::RecordingNetwork) and
+//!   [`PlaybackNetwork`](trace_network::PlaybackNetwork), which record the messages of a node and
+//!   replay them into a node under test.
 
 pub mod combined_network;
 pub mod libp2p_network;
 pub mod memory_network;
+pub mod trace_network;
 pub mod web_server_network;
 use std::{
     collections::HashMap,
@@ -1,5 +1,10 @@
 //! Networking Implementation that has a primary and a fallback newtork.  If the primary
 //! Errors we will use the backup to send or receive
+//!
+//! Messages nodes change views with, timeout votes and view sync messages, are still sent over
+//! the primary while it is presumed down, unless the relay is turned off with
+//! [`CombinedNetworks::with_view_change_relay`]: consensus cannot recover from a partition of the
+//! secondary without them.
 use super::NetworkError;
 use crate::traits::implementations::{Libp2pNetwork, WebServerNetwork};
 use async_lock::RwLock;
@@ -22,11 +27,12 @@ use async_compatibility_layer::channel::UnboundedSendError;
 #[cfg(feature = "hotshot-testing")]
 use hotshot_types::traits::network::{NetworkReliability, TestableNetworkingImplementation};
 use hotshot_types::{
+    anchor::AnchorHint,
     boxed_sync,
     data::ViewNumber,
     message::Message,
     traits::{
-        network::{ConnectedNetwork, ConsensusIntentEvent, TransmitType},
+        network::{prioritize, ConnectedNetwork, ConsensusIntentEvent, TransmitType},
         node_implementation::NodeType,
     },
     BoxSyncFuture,
@@ -115,6 +121,9 @@ pub struct CombinedNetworks<TYPES: NodeType> {
 
     /// If the primary network is down (0) or not, and for how many messages
     primary_down: Arc<AtomicU64>,
+
+    /// Whether view change messages are sent over the primary network even while it is down
+    relay_view_change: bool,
 }
 
 impl<TYPES: NodeType> CombinedNetworks<TYPES> {
@@ -125,9 +134,27 @@ impl<TYPES: NodeType> CombinedNetworks<TYPES> {
             networks,
             message_cache: Arc::new(RwLock::new(Cache::new(COMBINED_NETWORK_CACHE_SIZE))),
             primary_down: Arc::new(AtomicU64::new(0)),
+            relay_view_change: true,
         }
     }
 
+    /// Send view change messages over the primary network even while it is presumed down, if
+    /// `relay`, which is the default, or like any other message if not
+    #[must_use]
+    pub fn with_view_change_relay(mut self, relay: bool) -> Self {
+        self.relay_view_change = relay;
+        self
+    }
+
+    /// Whether to send `message` over the primary network, which is skipped while it is presumed
+    /// down but for view change messages
+    fn use_primary(&self, message: &Message<TYPES>) -> bool {
+        use_primary(
+            self.primary_down.load(Ordering::Relaxed),
+            self.relay_view_change && message.kind.is_view_change(),
+        )
+    }
+
     /// Get a ref to the primary network
     #[must_use]
     pub fn primary(&self) -> &WebServerNetwork<TYPES> {
@@ -141,6 +168,16 @@ impl<TYPES: NodeType> CombinedNetworks<TYPES> {
     }
 }
 
+/// Whether a message is sent over the primary network after `primary_down` failed sends in a
+/// row: always while it has failed less than [`COMBINED_NETWORK_MIN_PRIMARY_FAILURES`] times,
+/// then once every [`COMBINED_NETWORK_PRIMARY_CHECK_INTERVAL`] messages to see if it is back,
+/// unless the message is `relayed` over it regardless
+fn use_primary(primary_down: u64, relayed: bool) -> bool {
+    primary_down < COMBINED_NETWORK_MIN_PRIMARY_FAILURES
+        || primary_down % COMBINED_NETWORK_PRIMARY_CHECK_INTERVAL == 0
+        || relayed
+}
+
 /// Wrapper for the tuple of `WebServerNetwork` and `Libp2pNetwork`
 /// We need this so we can impl `TestableNetworkingImplementation`
 /// on the tuple
@@ -197,11 +234,13 @@ impl<TYPES: NodeType> TestableNetworkingImplementation<TYPES> for CombinedNetwor
                 networks: Arc::new(quorum_networks),
                 message_cache: Arc::new(RwLock::new(Cache::new(COMBINED_NETWORK_CACHE_SIZE))),
                 primary_down: Arc::new(AtomicU64::new(0)),
+                relay_view_change: true,
             };
             let da_net = Self {
                 networks: Arc::new(da_networks),
                 message_cache: Arc::new(RwLock::new(Cache::new(COMBINED_NETWORK_CACHE_SIZE))),
                 primary_down: Arc::new(AtomicU64::new(0)),
+                relay_view_change: true,
             };
             (quorum_net.into(), da_net.into())
         })
@@ -255,11 +294,9 @@ impl<TYPES: NodeType> ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>
         recipients: BTreeSet<TYPES::SignatureKey>,
     ) -> Result<(), NetworkError> {
         // broadcast optimistically on both networks, but if the primary network is down, skip it
-        let primary_down = self.primary_down.load(Ordering::Relaxed);
-        if primary_down < COMBINED_NETWORK_MIN_PRIMARY_FAILURES
-            || primary_down % COMBINED_NETWORK_PRIMARY_CHECK_INTERVAL == 0
-        {
-            // broadcast on the primary network as it is not down, or we are checking if it is back up
+        if self.use_primary(&message) {
+            // broadcast on the primary network as it is not down, we are checking if it is back up
+            // or the message is needed to change views
             match self
                 .primary()
                 .broadcast_message(message.clone(), recipients.clone())
@@ -286,11 +323,9 @@ impl<TYPES: NodeType> ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>
         recipient: TYPES::SignatureKey,
     ) -> Result<(), NetworkError> {
         // DM optimistically on both networks, but if the primary network is down, skip it
-        let primary_down = self.primary_down.load(Ordering::Relaxed);
-        if primary_down < COMBINED_NETWORK_MIN_PRIMARY_FAILURES
-            || primary_down % COMBINED_NETWORK_PRIMARY_CHECK_INTERVAL == 0
-        {
-            // message on the primary network as it is not down, or we are checking if it is back up
+        if self.use_primary(&message) {
+            // message on the primary network as it is not down, we are checking if it is back up
+            // or the message is needed to change views
             match self
                 .primary()
                 .direct_message(message.clone(), recipient.clone())
@@ -342,6 +377,7 @@ impl<TYPES: NodeType> ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>
                 }
             }
 
+            prioritize(&mut filtered_msgs);
             Ok(filtered_msgs)
         };
 
@@ -366,6 +402,35 @@ impl<TYPES: NodeType> ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>
         <Libp2pNetwork<_, _> as ConnectedNetwork<Message<TYPES>,TYPES::SignatureKey>>::
             inject_consensus_info(self.secondary(), event).await;
     }
+
+    // only the libp2p network tells its peers apart to admit them
+    async fn update_stake_table(&self, keys: BTreeSet<TYPES::SignatureKey>) {
+        <Libp2pNetwork<_, _> as ConnectedNetwork<Message<TYPES>,TYPES::SignatureKey>>::
+            update_stake_table(self.secondary(), keys).await;
+    }
+
+    // the web server does not carry anchor announcements, only the libp2p network does
+    fn latest_anchor_hint(&self) -> Option<AnchorHint> {
+        <Libp2pNetwork<_, _> as ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>>::
+            latest_anchor_hint(self.secondary())
+    }
+
+    fn record_anchor_hint(&self, hint: AnchorHint) {
+        <Libp2pNetwork<_, _> as ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>>::
+            record_anchor_hint(self.secondary(), hint);
+    }
+
+    // the web server relays messages between every node, so only the libp2p network is
+    // partitioned, and the error of the web server reports that the partition does not hold
+    async fn set_partition(
+        &self,
+        reachable: Option<BTreeSet<TYPES::SignatureKey>>,
+    ) -> Result<(), NetworkError> {
+        <Libp2pNetwork<_, _> as ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>>::
+            set_partition(self.secondary(), reachable.clone()).await?;
+        <WebServerNetwork<_> as ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>>::
+            set_partition(self.primary(), reachable).await
+    }
 }
 
 #[cfg(test)]
@@ -397,4 +462,24 @@ mod test {
         assert!(cache.hashes.contains(&3));
         assert!(cache.hashes.contains(&4));
     }
+
+    /// the primary network is skipped once it keeps failing, but for periodic checks and relayed
+    /// messages, and used again as soon as a send over it succeeds
+    #[test]
+    fn test_primary_switching() {
+        for primary_down in 0..COMBINED_NETWORK_MIN_PRIMARY_FAILURES {
+            assert!(use_primary(primary_down, false));
+        }
+        for primary_down in COMBINED_NETWORK_MIN_PRIMARY_FAILURES..100 {
+            // the primary network is tried again every check interval
+            assert_eq!(
+                use_primary(primary_down, false),
+                primary_down % COMBINED_NETWORK_PRIMARY_CHECK_INTERVAL == 0
+            );
+            // view change messages go over it regardless
+            assert!(use_primary(primary_down, true));
+        }
+        // a successful send resets the failure count
+        assert!(use_primary(0, false));
+    }
 }
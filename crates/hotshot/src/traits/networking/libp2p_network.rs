@@ -12,17 +12,21 @@ use async_lock::RwLock;
 use async_trait::async_trait;
 use bimap::BiHashMap;
 use bincode::Options;
-use hotshot_constants::{Version, LOOK_AHEAD, VERSION_0_1};
+#[cfg(feature = "hotshot-testing")]
+use bytes::Bytes;
+use hotshot_constants::{Version, DIRECT_MESSAGE_ACK_TIMEOUT_SEC, LOOK_AHEAD, VERSION_0_1};
 #[cfg(feature = "hotshot-testing")]
 use hotshot_types::traits::network::{NetworkReliability, TestableNetworkingImplementation};
 use hotshot_types::{
+    anchor::{AnchorHint, LatestAnchor},
     boxed_sync,
     data::ViewNumber,
     message::{Message, MessageKind},
+    signing::{self, SigningDomain},
     traits::{
         network::{
-            ConnectedNetwork, ConsensusIntentEvent, FailedToSerializeSnafu, NetworkError,
-            NetworkMsg, TransmitType, ViewMessage,
+            prioritize, ConnectedNetwork, ConsensusIntentEvent, FailedToSerializeSnafu,
+            NetworkError, NetworkMsg, TransmitType, ViewMessage,
         },
         node_implementation::{ConsensusTime, NodeType},
         signature_key::SignatureKey,
@@ -36,27 +40,28 @@ use libp2p_networking::network::{MeshParams, NetworkNodeConfigBuilder};
 
 use libp2p_networking::{
     network::{
+        is_routable,
         NetworkEvent::{self, DirectRequest, DirectResponse, GossipMsg},
         NetworkNodeConfig, NetworkNodeHandle, NetworkNodeHandleError, NetworkNodeType,
     },
     reexport::Multiaddr,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 #[cfg(feature = "hotshot-testing")]
-use std::{collections::HashSet, num::NonZeroUsize, str::FromStr};
+use std::{num::NonZeroUsize, str::FromStr};
 
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet, HashSet},
     fmt::Debug,
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tracing::{error, info, instrument, warn};
+use tracing::{debug, error, info, instrument, warn};
 
 /// convienence alias for the type for bootstrap addresses
 /// concurrency primitives are needed for having tests
@@ -65,6 +70,9 @@ pub type BootstrapAddrs = Arc<RwLock<Vec<(Option<PeerId>, Multiaddr)>>>;
 /// hardcoded topic of QC used
 pub const QC_TOPIC: &str = "global";
 
+/// hardcoded topic of the DA committee
+pub const DA_TOPIC: &str = "DA";
+
 /// Stubbed out Ack
 ///
 /// Note: as part of versioning for upgradability,
@@ -79,6 +87,117 @@ pub struct Empty {
     version: Version,
 }
 
+/// DHT key prefix under which nodes publish their [`SignedContactRecord`]
+const CONTACT_RECORD_KEY: &str = "contact";
+
+/// A node's libp2p contact information, signed with its consensus key and published to the
+/// kademlia DHT so peers can find it without relying on the configured address book.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound(deserialize = ""))]
+pub struct SignedContactRecord<K: SignatureKey> {
+    /// the public key this record belongs to
+    pub pk: K,
+    /// the libp2p peer id of the node
+    pub peer_id: PeerId,
+    /// the addresses the node can be reached at
+    pub addrs: Vec<Multiaddr>,
+    /// signature over `(pk, peer_id, addrs)` by `pk`
+    pub signature: K::PureAssembledSignatureType,
+}
+
+impl<K: SignatureKey> SignedContactRecord<K> {
+    /// the bytes which are signed
+    fn signed_bytes(
+        pk: &K,
+        peer_id: &PeerId,
+        addrs: &[Multiaddr],
+    ) -> Result<Vec<u8>, NetworkError> {
+        bincode_opts()
+            .serialize(&(pk, peer_id, addrs))
+            .context(FailedToSerializeSnafu)
+    }
+
+    /// Sign a new contact record
+    /// # Errors
+    /// If the record cannot be serialized or signed
+    pub fn new(
+        pk: K,
+        private_key: &K::PrivateKey,
+        peer_id: PeerId,
+        addrs: Vec<Multiaddr>,
+    ) -> Result<Self, NetworkError> {
+        let bytes = Self::signed_bytes(&pk, &peer_id, &addrs)?;
+        let signature = signing::sign::<K>(private_key, SigningDomain::ContactRecord, &bytes)
+            .map_err(|e| {
+                error!("Failed to sign contact record: {e}");
+                NetworkError::FailedToSign
+            })?;
+        Ok(Self {
+            pk,
+            peer_id,
+            addrs,
+            signature,
+        })
+    }
+
+    /// Whether the record was signed by the key it claims to belong to
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        Self::signed_bytes(&self.pk, &self.peer_id, &self.addrs).is_ok_and(|bytes| {
+            signing::validate(
+                &self.pk,
+                &self.signature,
+                SigningDomain::ContactRecord,
+                &bytes,
+            )
+        })
+    }
+}
+
+/// How long the peer id of a verified contact record is used before the record is looked up in
+/// the DHT again, to pick up nodes which moved
+pub const CONTACT_RECORD_TTL: Duration = Duration::from_secs(60);
+
+/// The peer ids of the contact records verified recently, so that messaging a key does not look
+/// its record up in the DHT and verify it every time
+#[derive(Debug)]
+pub struct ContactCache<K: SignatureKey> {
+    /// how long a verified record is used for
+    ttl: Duration,
+    /// the peer id of each key whose record was verified, and when it was
+    peers: BTreeMap<K, (PeerId, Instant)>,
+}
+
+impl<K: SignatureKey> ContactCache<K> {
+    /// An empty cache, using each verified record for `ttl`
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            peers: BTreeMap::new(),
+        }
+    }
+
+    /// The peer id of `pk`, if its record was verified less than the TTL before `now`
+    #[must_use]
+    pub fn get(&self, pk: &K, now: Instant) -> Option<PeerId> {
+        self.peers
+            .get(pk)
+            .filter(|(_, verified)| now.saturating_duration_since(*verified) < self.ttl)
+            .map(|(peer_id, _)| *peer_id)
+    }
+
+    /// Verify `record` as the contact record of `pk` at `now`, and cache its peer id if it is
+    /// signed by `pk` for that peer id. Returns whether it is.
+    pub fn verify(&mut self, pk: &K, record: &SignedContactRecord<K>, now: Instant) -> bool {
+        if record.pk != *pk || !record.is_valid() {
+            return false;
+        }
+        self.peers.insert(pk.clone(), (record.peer_id, now));
+        true
+    }
+}
+
 impl<M: NetworkMsg, K: SignatureKey + 'static> Debug for Libp2pNetwork<M, K> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Libp2p").field("inner", &"inner").finish()
@@ -105,6 +224,8 @@ struct Libp2pNetworkInner<M: NetworkMsg, K: SignatureKey + 'static> {
     direct_recv: UnboundedReceiver<M>,
     /// Sender for node lookup (relevant view number, key of node) (None for shutdown)
     node_lookup_send: UnboundedSender<Option<(ViewNumber, K)>>,
+    /// Sender of the keys of new stake tables to the allowlist task, in allowlist mode
+    allowlist_send: Option<UnboundedSender<BTreeSet<K>>>,
     /// this is really cheating to enable local tests
     /// hashset of (bootstrap_addr, peer_id)
     bootstrap_addrs: PeerInfoVec,
@@ -114,6 +235,12 @@ struct Libp2pNetworkInner<M: NetworkMsg, K: SignatureKey + 'static> {
     is_ready: Arc<AtomicBool>,
     /// max time before dropping message due to DHT error
     dht_timeout: Duration,
+    /// our own contact record, published to the DHT once connected
+    contact_record: SignedContactRecord<K>,
+    /// the contact records of other keys verified recently
+    contact_cache: Arc<RwLock<ContactCache<K>>>,
+    /// max time to wait for a direct message to be acknowledged, across all retries
+    direct_message_timeout: Duration,
     /// whether or not we've bootstrapped into the DHT yet
     is_bootstrapped: Arc<AtomicBool>,
     /// The networking metrics we're keeping track of
@@ -131,6 +258,8 @@ struct Libp2pNetworkInner<M: NetworkMsg, K: SignatureKey + 'static> {
     reliability_config: Option<Box<dyn NetworkReliability>>,
     /// if we're a member of the DA committee or not
     is_da: bool,
+    /// the latest anchor announced to this node
+    latest_anchor: LatestAnchor,
 }
 
 /// Networking implementation that uses libp2p
@@ -255,6 +384,7 @@ where
                         NetworkingMetricsValue::default(),
                         config,
                         pubkey.clone(),
+                        &privkey,
                         bootstrap_addrs_ref,
                         num_bootstrap,
                         usize::try_from(node_id).unwrap(),
@@ -298,10 +428,11 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
     /// One must call `connect` in order to connect.
     /// * `config`: the configuration of the node
     /// * `pk`: public key associated with the node
+    /// * `private_key`: private key of the node, used to sign its DHT contact record
     /// * `bootstrap_addrs`: rwlock containing the bootstrap addrs
     /// # Errors
     /// Returns error in the event that the underlying libp2p network
-    /// is unable to create a network.
+    /// is unable to create a network, or the contact record cannot be signed.
     ///
     /// # Panics
     ///
@@ -311,6 +442,7 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
         metrics: NetworkingMetricsValue,
         config: NetworkNodeConfig,
         pk: K,
+        private_key: &K::PrivateKey,
         bootstrap_addrs: BootstrapAddrs,
         bootstrap_addrs_len: usize,
         id: usize,
@@ -332,7 +464,11 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
             network_handle.config().node_type,
             NetworkNodeType::Bootstrap
         ) {
-            let addr = network_handle.listen_addr();
+            let addr = network_handle
+                .advertised_addrs()
+                .first()
+                .cloned()
+                .unwrap_or_else(|| network_handle.listen_addr());
             let pid = network_handle.peer_id();
             let mut bs_cp = bootstrap_addrs.write().await;
             bs_cp.push((Some(pid), addr));
@@ -342,9 +478,21 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
         let mut pubkey_pid_map = BiHashMap::new();
         pubkey_pid_map.insert(pk.clone(), network_handle.peer_id());
 
+        let contact_record = SignedContactRecord::new(
+            pk.clone(),
+            private_key,
+            network_handle.peer_id(),
+            network_handle.advertised_addrs().to_vec(),
+        )?;
+
+        let allowlist = network_handle.config().peer_allowlist.then(|| {
+            let (allowlist_send, allowlist_recv) = unbounded();
+            (committee_pks.clone(), allowlist_send, allowlist_recv)
+        });
+
         let mut topic_map = BiHashMap::new();
         topic_map.insert(committee_pks, QC_TOPIC.to_string());
-        topic_map.insert(da_pks, "DA".to_string());
+        topic_map.insert(da_pks, DA_TOPIC.to_string());
 
         let topic_map = RwLock::new(topic_map);
 
@@ -368,10 +516,14 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
                 // This is optimal for 10-30 nodes. TODO: parameterize this for both tests and examples
                 // https://github.com/EspressoSystems/HotShot/issues/2088
                 dht_timeout: Duration::from_secs(120),
+                contact_record,
+                contact_cache: Arc::new(RwLock::new(ContactCache::new(CONTACT_RECORD_TTL))),
+                direct_message_timeout: Duration::from_secs(DIRECT_MESSAGE_ACK_TIMEOUT_SEC),
                 is_bootstrapped: Arc::new(AtomicBool::new(false)),
                 metrics,
                 topic_map,
                 node_lookup_send,
+                allowlist_send: allowlist.as_ref().map(|(_, send, _)| send.clone()),
                 // Start the latest view from 0. "Latest" refers to "most recent view we are polling for
                 // proposals on". We need this because to have consensus info injected we need a working
                 // network already. In the worst case, we send a few lookups we don't need.
@@ -379,20 +531,82 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
                 #[cfg(feature = "hotshot-testing")]
                 reliability_config,
                 is_da,
+                latest_anchor: LatestAnchor::default(),
             }),
         };
 
         result.handle_event_generator(direct_send, broadcast_send);
         result.spawn_node_lookup(node_lookup_recv);
         result.spawn_connect(id);
+        if let Some((committee_pks, _, allowlist_recv)) = allowlist {
+            result.spawn_allowlist(committee_pks, allowlist_recv);
+        }
 
         Ok(result)
     }
 
+    /// Resolve `pk` to a peer id through its signed contact record in the DHT, so that its
+    /// current addresses become known to the swarm, unless `cache` verified its record recently.
+    /// `None` if the record found is not validly signed by `pk`.
+    /// # Errors
+    /// If no contact record can be found
+    async fn lookup_signed_contact(
+        handle: &NetworkNodeHandle<()>,
+        cache: &RwLock<ContactCache<K>>,
+        pk: &K,
+        dht_timeout: Duration,
+    ) -> Result<Option<PeerId>, NetworkNodeHandleError> {
+        if let Some(peer_id) = cache.read().await.get(pk, Instant::now()) {
+            return Ok(Some(peer_id));
+        }
+        let record = handle
+            .get_record_timeout::<SignedContactRecord<K>>(&(CONTACT_RECORD_KEY, pk), dht_timeout)
+            .await?;
+        if !cache.write().await.verify(pk, &record, Instant::now()) {
+            warn!(
+                "Ignoring contact record for {:?} with an invalid signature",
+                pk
+            );
+            return Ok(None);
+        }
+        let peer_id = record.peer_id;
+        handle
+            .add_known_peers(
+                record
+                    .addrs
+                    .into_iter()
+                    .filter(is_routable)
+                    .map(|addr| (Some(peer_id), addr))
+                    .collect(),
+            )
+            .await?;
+        Ok(Some(peer_id))
+    }
+
+    /// Resolve `pk` to a peer id, preferring its signed contact record in the DHT so that its
+    /// current addresses become known to the swarm. Falls back to the plain
+    /// public key to peer id mapping if no valid contact record is found.
+    /// # Errors
+    /// If neither record can be found
+    async fn lookup_contact(
+        handle: &NetworkNodeHandle<()>,
+        cache: &RwLock<ContactCache<K>>,
+        pk: K,
+        dht_timeout: Duration,
+    ) -> Result<PeerId, NetworkNodeHandleError> {
+        match Self::lookup_signed_contact(handle, cache, &pk, dht_timeout).await {
+            Ok(Some(peer_id)) => return Ok(peer_id),
+            Ok(None) => {}
+            Err(err) => debug!("No contact record found for {:?}: {}", pk, err),
+        }
+        handle.lookup_node::<K>(pk, dht_timeout).await
+    }
+
     /// Spawns task for looking up nodes pre-emptively
     #[allow(clippy::cast_sign_loss, clippy::cast_precision_loss)]
     fn spawn_node_lookup(&self, node_lookup_recv: UnboundedReceiver<Option<(ViewNumber, K)>>) {
         let handle = self.inner.handle.clone();
+        let contact_cache = self.inner.contact_cache.clone();
         let dht_timeout = self.inner.dht_timeout;
         let latest_seen_view = self.inner.latest_seen_view.clone();
 
@@ -409,7 +623,9 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
                 // only run if we are not too close to the next view number
                 if latest_seen_view.load(Ordering::Relaxed) + THRESHOLD <= *view_number {
                     // look up
-                    if let Err(err) = handle.lookup_node::<K>(pk.clone(), dht_timeout).await {
+                    if let Err(err) =
+                        Self::lookup_contact(&handle, &contact_cache, pk.clone(), dht_timeout).await
+                    {
                         error!("Failed to perform lookup for key {:?}: {}", pk, err);
                     };
                 }
@@ -417,9 +633,95 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
         });
     }
 
+    /// Spawns task admitting connections only from the bootstrap nodes and the peers the keys of
+    /// the stake table resolve to through their signed contact records, starting with
+    /// `committee_pks` and following the stake tables received on `stake_tables`. The allowlist
+    /// is enforced once the node is ready, and grows as more keys resolve, so that a key which
+    /// never publishes its record does not keep the mesh open to anyone.
+    fn spawn_allowlist(
+        &self,
+        committee_pks: BTreeSet<K>,
+        stake_tables: UnboundedReceiver<BTreeSet<K>>,
+    ) {
+        let handle = self.inner.handle.clone();
+        let contact_cache = self.inner.contact_cache.clone();
+        let dht_timeout = self.inner.dht_timeout;
+        let is_ready = self.inner.is_ready.clone();
+        let own_pk = self.inner.pk.clone();
+        let others = move |pks: BTreeSet<K>| -> BTreeSet<K> {
+            pks.into_iter().filter(|pk| *pk != own_pk).collect()
+        };
+        async_spawn(async move {
+            while !is_ready.load(Ordering::Relaxed) {
+                if handle.is_killed() {
+                    return;
+                }
+                async_sleep(Duration::from_secs(1)).await;
+            }
+            let mut committee = others(committee_pks);
+            let mut resolved: BTreeMap<K, PeerId> = BTreeMap::new();
+            let mut enforced: Option<HashSet<PeerId>> = None;
+            loop {
+                let unresolved: Vec<K> = committee
+                    .iter()
+                    .filter(|pk| !resolved.contains_key(*pk))
+                    .cloned()
+                    .collect();
+                for pk in unresolved {
+                    match Self::lookup_signed_contact(&handle, &contact_cache, &pk, dht_timeout)
+                        .await
+                    {
+                        Ok(Some(peer_id)) => {
+                            resolved.insert(pk, peer_id);
+                        }
+                        Ok(None) => {}
+                        Err(err) => {
+                            debug!("Key {:?} not resolved for the allowlist: {}", pk, err);
+                        }
+                    }
+                }
+                let allowed: HashSet<PeerId> = resolved.values().copied().collect();
+                if enforced.as_ref() != Some(&allowed) {
+                    info!(
+                        "Admitting the peers of {} of the {} other keys of the stake table",
+                        resolved.len(),
+                        committee.len()
+                    );
+                    if let Err(err) = handle
+                        .enforce_allowlist(allowed.iter().copied().collect())
+                        .await
+                    {
+                        error!("Failed to enforce the allowlist: {}", err);
+                        return;
+                    }
+                    enforced = Some(allowed);
+                }
+                // retry the keys yet to resolve, until the stake table changes
+                let stake_table = if resolved.len() == committee.len() {
+                    match stake_tables.recv().await {
+                        Ok(stake_table) => Some(stake_table),
+                        Err(_) => return,
+                    }
+                } else {
+                    async_sleep(Duration::from_secs(1)).await;
+                    stake_tables.try_recv().ok()
+                };
+                if let Some(stake_table) = stake_table {
+                    committee = others(stake_table);
+                    // the peers of keys which left the stake table are no longer admitted
+                    resolved.retain(|pk, _| committee.contains(pk));
+                }
+                if handle.is_killed() {
+                    return;
+                }
+            }
+        });
+    }
+
     /// Initiates connection to the outside world
     fn spawn_connect(&mut self, id: usize) {
         let pk = self.inner.pk.clone();
+        let contact_record = self.inner.contact_record.clone();
         let bootstrap_ref = self.inner.bootstrap_addrs.clone();
         let num_bootstrap = self.inner.bootstrap_addrs_len;
         let handle = self.inner.handle.clone();
@@ -498,6 +800,14 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> Libp2pNetwork<M, K> {
                     async_sleep(Duration::from_secs(1)).await;
                 }
 
+                while handle
+                    .put_record(&(CONTACT_RECORD_KEY, &pk), &contact_record)
+                    .await
+                    .is_err()
+                {
+                    async_sleep(Duration::from_secs(1)).await;
+                }
+
                 info!(
                     "node {:?} is barring bootstrap, type: {:?}",
                     handle.peer_id(),
@@ -701,12 +1011,13 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Libp2p
             if let Some(ref config) = &self.inner.reliability_config {
                 let handle = self.inner.handle.clone();
 
-                let serialized_msg = bincode_opts()
+                let serialized_msg: Bytes = bincode_opts()
                     .serialize(&message)
-                    .context(FailedToSerializeSnafu)?;
+                    .context(FailedToSerializeSnafu)?
+                    .into();
                 let fut = config.clone().chaos_send_msg(
                     serialized_msg,
-                    Arc::new(move |msg: Vec<u8>| {
+                    Arc::new(move |msg: Bytes| {
                         let topic_2 = topic.clone();
                         let handle_2 = handle.clone();
                         let metrics_2 = metrics.clone();
@@ -759,11 +1070,13 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Libp2p
 
         self.wait_for_ready().await;
 
-        let pid = match self
-            .inner
-            .handle
-            .lookup_node::<K>(recipient.clone(), self.inner.dht_timeout)
-            .await
+        let pid = match Self::lookup_contact(
+            &self.inner.handle,
+            &self.inner.contact_cache,
+            recipient.clone(),
+            self.inner.dht_timeout,
+        )
+        .await
         {
             Ok(pid) => pid,
             Err(err) => {
@@ -781,17 +1094,26 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Libp2p
             let metrics = self.inner.metrics.clone();
             if let Some(ref config) = &self.inner.reliability_config {
                 let handle = self.inner.handle.clone();
+                let direct_message_timeout = self.inner.direct_message_timeout;
 
-                let serialized_msg = bincode_opts()
+                let serialized_msg: Bytes = bincode_opts()
                     .serialize(&message)
-                    .context(FailedToSerializeSnafu)?;
+                    .context(FailedToSerializeSnafu)?
+                    .into();
                 let fut = config.clone().chaos_send_msg(
                     serialized_msg,
-                    Arc::new(move |msg: Vec<u8>| {
+                    Arc::new(move |msg: Bytes| {
                         let handle_2 = handle.clone();
                         let metrics_2 = metrics.clone();
                         boxed_sync(async move {
-                            match handle_2.direct_request_no_serialize(pid, msg).await {
+                            match handle_2
+                                .direct_request_with_ack_no_serialize(
+                                    pid,
+                                    msg,
+                                    direct_message_timeout,
+                                )
+                                .await
+                            {
                                 Err(e) => {
                                     metrics_2.message_failed_to_send.add(1);
                                     warn!("Failed to broadcast to libp2p: {:?}", e);
@@ -808,9 +1130,20 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Libp2p
             }
         }
 
-        match self.inner.handle.direct_request(pid, &message).await {
-            Ok(()) => Ok(()),
-            Err(e) => Err(e.into()),
+        match self
+            .inner
+            .handle
+            .direct_request_with_ack(pid, &message, self.inner.direct_message_timeout)
+            .await
+        {
+            Ok(()) => {
+                self.inner.metrics.outgoing_direct_message_count.add(1);
+                Ok(())
+            }
+            Err(e) => {
+                self.inner.metrics.message_failed_to_send.add(1);
+                Err(e.into())
+            }
         }
     }
 
@@ -829,12 +1162,13 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Libp2p
             } else {
                 match transmit_type {
                     TransmitType::Direct => {
-                        let result = self
+                        let mut result = self
                             .inner
                             .direct_recv
                             .drain_at_least_one()
                             .await
                             .map_err(|_x| NetworkError::ShutDown)?;
+                        prioritize(&mut result);
                         self.inner
                             .metrics
                             .incoming_direct_message_count
@@ -842,12 +1176,13 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Libp2p
                         Ok(result)
                     }
                     TransmitType::Broadcast => {
-                        let result = self
+                        let mut result = self
                             .inner
                             .broadcast_recv
                             .drain_at_least_one()
                             .await
                             .map_err(|_x| NetworkError::ShutDown)?;
+                        prioritize(&mut result);
                         self.inner
                             .metrics
                             .incoming_direct_message_count
@@ -892,4 +1227,136 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Libp2p
             _ => {}
         }
     }
+
+    async fn update_stake_table(&self, keys: BTreeSet<K>) {
+        if let Some(allowlist_send) = &self.inner.allowlist_send {
+            if allowlist_send.send(keys).await.is_err() {
+                warn!("The allowlist task ended, the stake table update is lost");
+            }
+        }
+    }
+
+    fn latest_anchor_hint(&self) -> Option<AnchorHint> {
+        self.inner.latest_anchor.get()
+    }
+
+    fn record_anchor_hint(&self, hint: AnchorHint) {
+        self.inner.latest_anchor.record(hint);
+    }
+
+    async fn set_partition(&self, reachable: Option<BTreeSet<K>>) -> Result<(), NetworkError> {
+        let Some(reachable) = reachable else {
+            self.inner
+                .handle
+                .partition(None)
+                .await
+                .map_err(|err| NetworkError::Libp2p { source: err })?;
+            // reconnect across the healed partition through the bootstrap nodes
+            let bootstrap_addrs = self.inner.bootstrap_addrs.read().await.clone();
+            self.inner
+                .handle
+                .add_known_peers(bootstrap_addrs)
+                .await
+                .map_err(|err| NetworkError::Libp2p { source: err })?;
+            return self
+                .inner
+                .handle
+                .begin_bootstrap()
+                .await
+                .map_err(|err| NetworkError::Libp2p { source: err });
+        };
+        // connections are denied by the peer id they authenticate as, which every key of the
+        // partition has to resolve to first
+        let mut peers = Vec::new();
+        for pk in reachable.into_iter().filter(|pk| *pk != self.inner.pk) {
+            let peer_id = Self::lookup_contact(
+                &self.inner.handle,
+                &self.inner.contact_cache,
+                pk,
+                self.inner.dht_timeout,
+            )
+            .await
+            .map_err(|err| NetworkError::Libp2p { source: err })?;
+            peers.push(peer_id);
+        }
+        self.inner
+            .handle
+            .partition(Some(peers))
+            .await
+            .map_err(|err| NetworkError::Libp2p { source: err })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hotshot_types::signature_key::BLSPubKey;
+
+    #[test]
+    fn contact_records_sign_the_advertised_addrs() {
+        let (pk, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+        let addr: Multiaddr = "/ip4/203.0.113.7/udp/9000/quic-v1".parse().unwrap();
+        let record =
+            SignedContactRecord::new(pk, &private_key, PeerId::random(), vec![addr]).unwrap();
+        assert!(record.is_valid());
+
+        // a record whose addresses were swapped for others no longer verifies
+        let mut redirected = record.clone();
+        redirected.addrs = vec!["/ip4/198.51.100.1/udp/9000/quic-v1".parse().unwrap()];
+        assert!(!redirected.is_valid());
+        let mut other_peer = record;
+        other_peer.peer_id = PeerId::random();
+        assert!(!other_peer.is_valid());
+    }
+
+    #[test]
+    fn contact_cache_rejects_records_not_signed_for_their_key_and_peer() {
+        let (pk, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+        let (other_pk, other_private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1);
+        let addr: Multiaddr = "/ip4/203.0.113.7/udp/9000/quic-v1".parse().unwrap();
+        let record =
+            SignedContactRecord::new(pk, &private_key, PeerId::random(), vec![addr]).unwrap();
+        let mut cache = ContactCache::new(CONTACT_RECORD_TTL);
+        let now = Instant::now();
+
+        // a record signed by another key
+        let mut forged = record.clone();
+        forged.signature =
+            SignedContactRecord::new(pk, &other_private_key, record.peer_id, record.addrs.clone())
+                .unwrap()
+                .signature;
+        assert!(!cache.verify(&pk, &forged, now));
+        // a record whose peer id is not the one signed
+        let mut other_peer = record.clone();
+        other_peer.peer_id = PeerId::random();
+        assert!(!cache.verify(&pk, &other_peer, now));
+        // a valid record of another key
+        assert!(!cache.verify(&other_pk, &record, now));
+        assert_eq!(cache.get(&pk, now), None);
+        assert_eq!(cache.get(&other_pk, now), None);
+
+        assert!(cache.verify(&pk, &record, now));
+        assert_eq!(cache.get(&pk, now), Some(record.peer_id));
+    }
+
+    #[test]
+    fn contact_cache_entries_expire() {
+        let (pk, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+        let record = SignedContactRecord::new(pk, &private_key, PeerId::random(), vec![]).unwrap();
+        let mut cache = ContactCache::new(CONTACT_RECORD_TTL);
+        let now = Instant::now();
+        assert!(cache.verify(&pk, &record, now));
+        assert_eq!(
+            cache.get(&pk, now + CONTACT_RECORD_TTL - Duration::from_millis(1)),
+            Some(record.peer_id)
+        );
+        assert_eq!(cache.get(&pk, now + CONTACT_RECORD_TTL), None);
+
+        // verifying the record again renews it
+        assert!(cache.verify(&pk, &record, now + CONTACT_RECORD_TTL));
+        assert_eq!(
+            cache.get(&pk, now + CONTACT_RECORD_TTL),
+            Some(record.peer_id)
+        );
+    }
 }
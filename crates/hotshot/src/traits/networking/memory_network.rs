@@ -11,13 +11,18 @@ use async_compatibility_layer::{
 use async_lock::{Mutex, RwLock};
 use async_trait::async_trait;
 use bincode::Options;
+use bytes::Bytes;
 use dashmap::DashMap;
 use futures::StreamExt;
 use hotshot_types::{
+    anchor::{AnchorHint, LatestAnchor},
     boxed_sync,
     message::Message,
     traits::{
-        network::{ConnectedNetwork, NetworkMsg, TestableNetworkingImplementation, TransmitType},
+        network::{
+            prioritize, ConnectedNetwork, NetworkMsg, NetworkPartition,
+            TestableNetworkingImplementation, TransmitType,
+        },
         node_implementation::NodeType,
         signature_key::SignatureKey,
     },
@@ -45,6 +50,8 @@ pub struct MasterMap<M: NetworkMsg, K: SignatureKey> {
     /// The list of `MemoryNetwork`s
     #[debug(skip)]
     map: DashMap<K, MemoryNetwork<M, K>>,
+    /// The index of each node, for networks whose links differ between nodes
+    node_indices: DashMap<K, u64>,
     /// The id of this `MemoryNetwork` cluster
     id: u64,
 }
@@ -55,6 +62,7 @@ impl<M: NetworkMsg, K: SignatureKey> MasterMap<M, K> {
     pub fn new() -> Arc<MasterMap<M, K>> {
         Arc::new(MasterMap {
             map: DashMap::new(),
+            node_indices: DashMap::new(),
             id: rand::thread_rng().gen(),
         })
     }
@@ -71,10 +79,12 @@ enum Combo<T> {
 /// Internal state for a `MemoryNetwork` instance
 #[derive(Debug)]
 struct MemoryNetworkInner<M: NetworkMsg, K: SignatureKey> {
+    /// The public key of this node
+    pub_key: K,
     /// Input for broadcast messages
-    broadcast_input: RwLock<Option<Sender<Vec<u8>>>>,
+    broadcast_input: RwLock<Option<Sender<Bytes>>>,
     /// Input for direct messages
-    direct_input: RwLock<Option<Sender<Vec<u8>>>>,
+    direct_input: RwLock<Option<Sender<Bytes>>>,
     /// Output for broadcast messages
     broadcast_output: Mutex<Receiver<M>>,
     /// Output for direct messages
@@ -90,6 +100,12 @@ struct MemoryNetworkInner<M: NetworkMsg, K: SignatureKey> {
 
     /// config to introduce unreliability to the network
     reliability_config: Option<Box<dyn NetworkReliability>>,
+
+    /// The latest anchor announced to this node
+    latest_anchor: LatestAnchor,
+
+    /// The peers this node exchanges messages with, while it is partitioned
+    partition: NetworkPartition<K>,
 }
 
 /// In memory only network simulator.
@@ -134,11 +150,11 @@ impl<M: NetworkMsg, K: SignatureKey> MemoryNetwork<M, K> {
             async move {
                 debug!("Starting background task");
                 // direct input is right stream
-                let direct = direct_task_recv.into_stream().map(Combo::<Vec<u8>>::Direct);
+                let direct = direct_task_recv.into_stream().map(Combo::<Bytes>::Direct);
                 // broadcast input is left stream
                 let broadcast = broadcast_task_recv
                     .into_stream()
-                    .map(Combo::<Vec<u8>>::Broadcast);
+                    .map(Combo::<Bytes>::Broadcast);
                 // Combine the streams
                 let mut combined = futures::stream::select(direct, broadcast);
                 trace!("Entering processing loop");
@@ -192,6 +208,7 @@ impl<M: NetworkMsg, K: SignatureKey> MemoryNetwork<M, K> {
         trace!("Task spawned, creating MemoryNetwork");
         let mn = MemoryNetwork {
             inner: Arc::new(MemoryNetworkInner {
+                pub_key: pub_key.clone(),
                 broadcast_input: RwLock::new(Some(broadcast_input)),
                 direct_input: RwLock::new(Some(direct_input)),
                 broadcast_output: Mutex::new(broadcast_output),
@@ -200,6 +217,8 @@ impl<M: NetworkMsg, K: SignatureKey> MemoryNetwork<M, K> {
                 in_flight_message_count,
                 metrics,
                 reliability_config,
+                latest_anchor: LatestAnchor::default(),
+                partition: NetworkPartition::default(),
             }),
         };
         master_map.map.insert(pub_key, mn.clone());
@@ -208,8 +227,8 @@ impl<M: NetworkMsg, K: SignatureKey> MemoryNetwork<M, K> {
         mn
     }
 
-    /// Send a [`Vec<u8>`] message to the inner `broadcast_input`
-    async fn broadcast_input(&self, message: Vec<u8>) -> Result<(), SendError<Vec<u8>>> {
+    /// Send a [`Bytes`] message to the inner `broadcast_input`
+    async fn broadcast_input(&self, message: Bytes) -> Result<(), SendError<Bytes>> {
         self.inner
             .in_flight_message_count
             .fetch_add(1, Ordering::Relaxed);
@@ -222,8 +241,8 @@ impl<M: NetworkMsg, K: SignatureKey> MemoryNetwork<M, K> {
         }
     }
 
-    /// Send a [`Vec<u8>`] message to the inner `direct_input`
-    async fn direct_input(&self, message: Vec<u8>) -> Result<(), SendError<Vec<u8>>> {
+    /// Send a [`Bytes`] message to the inner `direct_input`
+    async fn direct_input(&self, message: Bytes) -> Result<(), SendError<Bytes>> {
         self.inner
             .in_flight_message_count
             .fetch_add(1, Ordering::Relaxed);
@@ -235,6 +254,31 @@ impl<M: NetworkMsg, K: SignatureKey> MemoryNetwork<M, K> {
             Err(SendError(message))
         }
     }
+
+    /// Whether messages flow between this node and `peer`: neither has cut itself off from the
+    /// other, so that a partition holds both ways even before every node has applied it
+    fn is_linked_to(&self, peer: &Self) -> bool {
+        self.inner.partition.is_reachable(&peer.inner.pub_key)
+            && peer.inner.partition.is_reachable(&self.inner.pub_key)
+    }
+
+    /// Apply `config` to a message bound for `recipient`, using the link between the two nodes
+    /// if both of their indices are known
+    fn chaos_send(
+        &self,
+        config: &dyn NetworkReliability,
+        msg: Bytes,
+        recipient: &K,
+        send_fn: Arc<dyn Send + Sync + 'static + Fn(Bytes) -> BoxSyncFuture<'static, ()>>,
+    ) -> BoxSyncFuture<'static, ()> {
+        let indices = &self.inner.master_map.node_indices;
+        match (indices.get(&self.inner.pub_key), indices.get(recipient)) {
+            (Some(sender), Some(recipient)) => {
+                config.chaos_send_link_msg(msg, *sender, *recipient, send_fn)
+            }
+            _ => config.chaos_send_msg(msg, send_fn),
+        }
+    }
 }
 
 impl<TYPES: NodeType> TestableNetworkingImplementation<TYPES>
@@ -254,11 +298,12 @@ impl<TYPES: NodeType> TestableNetworkingImplementation<TYPES>
             let privkey = TYPES::SignatureKey::generated_from_seed_indexed([0u8; 32], node_id).1;
             let pubkey = TYPES::SignatureKey::from_private(&privkey);
             let net = MemoryNetwork::new(
-                pubkey,
+                pubkey.clone(),
                 NetworkingMetricsValue::default(),
                 master.clone(),
                 reliability_config.clone(),
             );
+            master.node_indices.insert(pubkey, node_id);
             (net.clone().into(), net.into())
         })
     }
@@ -307,10 +352,11 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
         recipients: BTreeSet<K>,
     ) -> Result<(), NetworkError> {
         trace!(?message, "Broadcasting message");
-        // Bincode the message
-        let vec = bincode_opts()
+        // Bincode the message once; every recipient shares the same buffer
+        let vec: Bytes = bincode_opts()
             .serialize(&message)
-            .context(FailedToSerializeSnafu)?;
+            .context(FailedToSerializeSnafu)?
+            .into();
         trace!("Message bincoded, sending");
         for node in &self.inner.master_map.map {
             // TODO delay/drop etc here
@@ -318,13 +364,19 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
             if !recipients.contains(key) {
                 continue;
             }
+            if !self.is_linked_to(node) {
+                trace!(?key, "Not sending message to node across the partition");
+                continue;
+            }
             trace!(?key, "Sending message to node");
             if let Some(ref config) = &self.inner.reliability_config {
                 {
                     let node2 = node.clone();
-                    let fut = config.chaos_send_msg(
+                    let fut = self.chaos_send(
+                        config.as_ref(),
                         vec.clone(),
-                        Arc::new(move |msg: Vec<u8>| {
+                        key,
+                        Arc::new(move |msg: Bytes| {
                             let node3 = (node2).clone();
                             boxed_sync(async move {
                                 let _res = node3.broadcast_input(msg).await;
@@ -356,17 +408,28 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
     async fn direct_message(&self, message: M, recipient: K) -> Result<(), NetworkError> {
         // debug!(?message, ?recipient, "Sending direct message");
         // Bincode the message
-        let vec = bincode_opts()
+        let vec: Bytes = bincode_opts()
             .serialize(&message)
-            .context(FailedToSerializeSnafu)?;
+            .context(FailedToSerializeSnafu)?
+            .into();
         trace!("Message bincoded, finding recipient");
         if let Some(node) = self.inner.master_map.map.get(&recipient) {
             let node = node.value().clone();
+            if !self.is_linked_to(&node) {
+                // dropped, as it would be on a real link that is cut
+                trace!(
+                    ?recipient,
+                    "Not sending message to node across the partition"
+                );
+                return Ok(());
+            }
             if let Some(ref config) = &self.inner.reliability_config {
                 {
-                    let fut = config.chaos_send_msg(
-                        vec.clone(),
-                        Arc::new(move |msg: Vec<u8>| {
+                    let fut = self.chaos_send(
+                        config.as_ref(),
+                        vec,
+                        &recipient,
+                        Arc::new(move |msg: Bytes| {
                             let node2 = node.clone();
                             boxed_sync(async move {
                                 let _res = node2.direct_input(msg).await;
@@ -415,7 +478,7 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
         let closure = async move {
             match transmit_type {
                 TransmitType::Direct => {
-                    let ret = self
+                    let mut ret = self
                         .inner
                         .direct_output
                         .lock()
@@ -423,6 +486,7 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
                         .drain_at_least_one()
                         .await
                         .map_err(|_x| NetworkError::ShutDown)?;
+                    prioritize(&mut ret);
                     self.inner
                         .in_flight_message_count
                         .fetch_sub(ret.len(), Ordering::Relaxed);
@@ -433,7 +497,7 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
                     Ok(ret)
                 }
                 TransmitType::Broadcast => {
-                    let ret = self
+                    let mut ret = self
                         .inner
                         .broadcast_output
                         .lock()
@@ -441,6 +505,7 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
                         .drain_at_least_one()
                         .await
                         .map_err(|_x| NetworkError::ShutDown)?;
+                    prioritize(&mut ret);
                     self.inner
                         .in_flight_message_count
                         .fetch_sub(ret.len(), Ordering::Relaxed);
@@ -454,4 +519,20 @@ impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for Memory
         };
         boxed_sync(closure)
     }
+
+    fn latest_anchor_hint(&self) -> Option<AnchorHint> {
+        self.inner.latest_anchor.get()
+    }
+
+    fn record_anchor_hint(&self, hint: AnchorHint) {
+        self.inner.latest_anchor.record(hint);
+    }
+
+    async fn set_partition(&self, reachable: Option<BTreeSet<K>>) -> Result<(), NetworkError> {
+        match reachable {
+            Some(reachable) => self.inner.partition.isolate(reachable),
+            None => self.inner.partition.heal(),
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,512 @@
+//! Networking implementations to reproduce runs
+//!
+//! A [`RecordingNetwork`] wraps another network and writes every message the node sends and
+//! receives to a trace file. A [`PlaybackNetwork`] feeds the messages received in such a trace to
+//! a single node under test, at the times they were received and in the order they were
+//! received, and keeps what the node sends instead, to be compared with what the recorded node
+//! sent.
+//!
+//! A trace is a sequence of [`TraceEntry`]s, each encoded with bincode and prefixed with its
+//! length as a little-endian `u64`. Entries are flushed as they are written, so a trace survives
+//! the node crashing.
+
+use super::NetworkError;
+use async_compatibility_layer::{art::async_sleep, channel::UnboundedSendError};
+use async_trait::async_trait;
+use bincode::Options;
+use hotshot_types::{
+    anchor::{AnchorHint, LatestAnchor},
+    boxed_sync,
+    data::ViewNumber,
+    traits::{
+        network::{
+            ConnectedNetwork, ConsensusIntentEvent, FailedToDeserializeSnafu, NetworkMsg,
+            TraceSnafu, TransmitType,
+        },
+        signature_key::SignatureKey,
+    },
+    BoxSyncFuture,
+};
+use hotshot_utils::bincode::bincode_opts;
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt::Debug,
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// How long a receiver of a [`PlaybackNetwork`] waits for the receiver of the other transmit
+/// type to take the message due before its own
+const HANDOVER_INTERVAL: Duration = Duration::from_millis(5);
+
+/// What a node did on the network
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum TraceEvent<M, K> {
+    /// the node received `message`
+    Received {
+        /// how the message was received
+        transmit_type: TransmitType,
+        /// the message
+        message: M,
+    },
+    /// the node broadcast `message` to `recipients`
+    Broadcast {
+        /// the message
+        message: M,
+        /// the nodes it was broadcast to
+        recipients: BTreeSet<K>,
+    },
+    /// the node sent `message` to `recipient`
+    Direct {
+        /// the message
+        message: M,
+        /// the node it was sent to
+        recipient: K,
+    },
+}
+
+/// An event of a trace, with when it happened
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceEntry<M, K> {
+    /// time since the trace started
+    pub elapsed: Duration,
+    /// what happened
+    pub event: TraceEvent<M, K>,
+}
+
+/// Read all the entries of the trace at `path`
+/// # Errors
+/// If the trace cannot be read, or one of its entries cannot be decoded
+pub fn read_trace<M: NetworkMsg, K: SignatureKey>(
+    path: impl AsRef<Path>,
+) -> Result<Vec<TraceEntry<M, K>>, NetworkError> {
+    let mut reader = BufReader::new(File::open(path).context(TraceSnafu)?);
+    let mut entries = Vec::new();
+    loop {
+        let mut len = [0u8; 8];
+        match reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(entries),
+            Err(e) => return Err(NetworkError::Trace { source: e }),
+        }
+        let len = usize::try_from(u64::from_le_bytes(len)).map_err(|_| NetworkError::Trace {
+            source: ErrorKind::InvalidData.into(),
+        })?;
+        let mut bytes = vec![0u8; len];
+        reader.read_exact(&mut bytes).context(TraceSnafu)?;
+        entries.push(
+            bincode_opts()
+                .deserialize(&bytes)
+                .context(FailedToDeserializeSnafu)?,
+        );
+    }
+}
+
+/// Appends entries to a trace file
+#[derive(Debug)]
+struct TraceWriter {
+    /// when the trace started
+    start: Instant,
+    /// the trace file
+    file: Mutex<BufWriter<File>>,
+}
+
+impl TraceWriter {
+    /// Append `event`, logging rather than failing if it cannot be written, so that recording
+    /// never disrupts the node
+    fn record<M: NetworkMsg, K: SignatureKey>(&self, event: TraceEvent<M, K>) {
+        // entries are timed under the lock, so that they are written in the order they happened
+        let mut file = self.file.lock().unwrap();
+        let entry = TraceEntry {
+            elapsed: self.start.elapsed(),
+            event,
+        };
+        let bytes = match bincode_opts().serialize(&entry) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Failed to encode a network trace entry: {e}");
+                return;
+            }
+        };
+        let written = file
+            .write_all(&(bytes.len() as u64).to_le_bytes())
+            .and_then(|()| file.write_all(&bytes))
+            .and_then(|()| file.flush());
+        if let Err(e) = written {
+            warn!("Failed to write a network trace entry: {e}");
+        }
+    }
+}
+
+/// A network which records every message sent and received through `N` to a trace file
+#[derive(Clone, Debug)]
+pub struct RecordingNetwork<N> {
+    /// the network messages actually go through
+    inner: N,
+    /// the trace messages are recorded to, if recording
+    trace: Option<Arc<TraceWriter>>,
+}
+
+impl<N> RecordingNetwork<N> {
+    /// Record the messages of `inner` to a new trace file at `path`
+    /// # Errors
+    /// If the trace file cannot be created
+    pub fn new(inner: N, path: impl AsRef<Path>) -> Result<Self, NetworkError> {
+        let file = File::create(path).context(TraceSnafu)?;
+        Ok(Self {
+            inner,
+            trace: Some(Arc::new(TraceWriter {
+                start: Instant::now(),
+                file: Mutex::new(BufWriter::new(file)),
+            })),
+        })
+    }
+
+    /// Pass the messages through to `inner` without recording them, for runs which are not
+    /// traced
+    #[must_use]
+    pub fn unrecorded(inner: N) -> Self {
+        Self { inner, trace: None }
+    }
+
+    /// Whether messages are recorded
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Get a ref to the network messages go through
+    #[must_use]
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    /// Append `event` to the trace, if recording
+    fn record<M: NetworkMsg, K: SignatureKey>(&self, event: TraceEvent<M, K>) {
+        if let Some(trace) = &self.trace {
+            trace.record(event);
+        }
+    }
+}
+
+#[async_trait]
+impl<M: NetworkMsg, K: SignatureKey + 'static, N: ConnectedNetwork<M, K>> ConnectedNetwork<M, K>
+    for RecordingNetwork<N>
+{
+    fn pause(&self) {
+        self.inner.pause();
+    }
+
+    fn resume(&self) {
+        self.inner.resume();
+    }
+
+    async fn wait_for_ready(&self) {
+        self.inner.wait_for_ready().await;
+    }
+
+    async fn is_ready(&self) -> bool {
+        self.inner.is_ready().await
+    }
+
+    fn shut_down<'a, 'b>(&'a self) -> BoxSyncFuture<'b, ()>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        self.inner.shut_down()
+    }
+
+    async fn broadcast_message(
+        &self,
+        message: M,
+        recipients: BTreeSet<K>,
+    ) -> Result<(), NetworkError> {
+        self.record(TraceEvent::Broadcast {
+            message: message.clone(),
+            recipients: recipients.clone(),
+        });
+        self.inner.broadcast_message(message, recipients).await
+    }
+
+    async fn direct_message(&self, message: M, recipient: K) -> Result<(), NetworkError> {
+        self.record(TraceEvent::Direct {
+            message: message.clone(),
+            recipient: recipient.clone(),
+        });
+        self.inner.direct_message(message, recipient).await
+    }
+
+    fn recv_msgs<'a, 'b>(
+        &'a self,
+        transmit_type: TransmitType,
+    ) -> BoxSyncFuture<'b, Result<Vec<M>, NetworkError>>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        let closure = async move {
+            let msgs = self.inner.recv_msgs(transmit_type).await?;
+            for message in &msgs {
+                self.record::<M, K>(TraceEvent::Received {
+                    transmit_type,
+                    message: message.clone(),
+                });
+            }
+            Ok(msgs)
+        };
+        boxed_sync(closure)
+    }
+
+    async fn queue_node_lookup(
+        &self,
+        view_number: ViewNumber,
+        pk: K,
+    ) -> Result<(), UnboundedSendError<Option<(ViewNumber, K)>>> {
+        self.inner.queue_node_lookup(view_number, pk).await
+    }
+
+    async fn inject_consensus_info(&self, event: ConsensusIntentEvent<K>) {
+        self.inner.inject_consensus_info(event).await;
+    }
+
+    async fn update_stake_table(&self, keys: BTreeSet<K>) {
+        self.inner.update_stake_table(keys).await;
+    }
+
+    fn latest_anchor_hint(&self) -> Option<AnchorHint> {
+        self.inner.latest_anchor_hint()
+    }
+
+    fn record_anchor_hint(&self, hint: AnchorHint) {
+        self.inner.record_anchor_hint(hint);
+    }
+
+    async fn set_partition(&self, reachable: Option<BTreeSet<K>>) -> Result<(), NetworkError> {
+        self.inner.set_partition(reachable).await
+    }
+}
+
+/// State of a [`PlaybackNetwork`], shared by its clones
+#[derive(Debug)]
+struct PlaybackInner<M, K> {
+    /// whether messages are delivered at the times they were received, rather than at once
+    recorded_timing: bool,
+    /// messages received which were not delivered yet, in the order they were received, with
+    /// when and how they were received
+    received: Mutex<VecDeque<(Duration, TransmitType, M)>>,
+    /// the messages the recorded node sent
+    recorded_sends: Vec<TraceEntry<M, K>>,
+    /// the messages the node under test sent
+    sent: Mutex<Vec<TraceEntry<M, K>>>,
+    /// when the node under test first asked for messages, which the playback is timed from
+    start: OnceLock<Instant>,
+    /// whether delivery is paused
+    paused: AtomicBool,
+    /// whether the network was shut down
+    shut_down: AtomicBool,
+    /// the latest anchor announced in the replayed messages
+    latest_anchor: LatestAnchor,
+}
+
+/// A network which replays the messages received in a trace to a single node, and keeps what
+/// the node sends rather than delivering it
+#[derive(Clone, Debug)]
+pub struct PlaybackNetwork<M, K> {
+    /// the state of the playback
+    inner: Arc<PlaybackInner<M, K>>,
+}
+
+impl<M: NetworkMsg, K: SignatureKey> PlaybackNetwork<M, K> {
+    /// Replay the messages received in `entries`, at the times they were received if
+    /// `recorded_timing` is set, or as soon as they are asked for otherwise
+    #[must_use]
+    pub fn new(entries: Vec<TraceEntry<M, K>>, recorded_timing: bool) -> Self {
+        let mut received = VecDeque::new();
+        let mut recorded_sends = Vec::new();
+        for entry in entries {
+            match entry.event {
+                TraceEvent::Received {
+                    transmit_type,
+                    message,
+                } => received.push_back((entry.elapsed, transmit_type, message)),
+                TraceEvent::Broadcast { .. } | TraceEvent::Direct { .. } => {
+                    recorded_sends.push(entry);
+                }
+            }
+        }
+        Self {
+            inner: Arc::new(PlaybackInner {
+                recorded_timing,
+                received: Mutex::new(received),
+                recorded_sends,
+                sent: Mutex::new(Vec::new()),
+                start: OnceLock::new(),
+                paused: AtomicBool::new(false),
+                shut_down: AtomicBool::new(false),
+                latest_anchor: LatestAnchor::default(),
+            }),
+        }
+    }
+
+    /// Replay the trace at `path`, see [`PlaybackNetwork::new`]
+    /// # Errors
+    /// If the trace cannot be read
+    pub fn from_file(path: impl AsRef<Path>, recorded_timing: bool) -> Result<Self, NetworkError> {
+        Ok(Self::new(read_trace(path)?, recorded_timing))
+    }
+
+    /// The messages the recorded node sent
+    #[must_use]
+    pub fn recorded_sends(&self) -> &[TraceEntry<M, K>] {
+        &self.inner.recorded_sends
+    }
+
+    /// The messages the node under test sent so far
+    /// # Panics
+    /// If the lock on the sent messages is poisoned
+    #[must_use]
+    pub fn sent(&self) -> Vec<TraceEntry<M, K>> {
+        self.inner.sent.lock().unwrap().clone()
+    }
+
+    /// The number of messages of the trace not delivered yet
+    /// # Panics
+    /// If the lock on the messages is poisoned
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.inner.received.lock().unwrap().len()
+    }
+
+    /// Time since the playback started, starting it if it did not
+    fn elapsed(&self) -> Duration {
+        self.inner.start.get_or_init(Instant::now).elapsed()
+    }
+
+    /// Keep a message the node under test sent
+    fn record_send(&self, event: TraceEvent<M, K>) {
+        let elapsed = self.elapsed();
+        self.inner
+            .sent
+            .lock()
+            .unwrap()
+            .push(TraceEntry { elapsed, event });
+    }
+}
+
+#[async_trait]
+impl<M: NetworkMsg, K: SignatureKey + 'static> ConnectedNetwork<M, K> for PlaybackNetwork<M, K> {
+    fn pause(&self) {
+        self.inner.paused.store(true, Ordering::Relaxed);
+    }
+
+    fn resume(&self) {
+        self.inner.paused.store(false, Ordering::Relaxed);
+    }
+
+    async fn wait_for_ready(&self) {}
+
+    async fn is_ready(&self) -> bool {
+        true
+    }
+
+    fn shut_down<'a, 'b>(&'a self) -> BoxSyncFuture<'b, ()>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        let closure = async move {
+            self.inner.shut_down.store(true, Ordering::Relaxed);
+        };
+        boxed_sync(closure)
+    }
+
+    async fn broadcast_message(
+        &self,
+        message: M,
+        recipients: BTreeSet<K>,
+    ) -> Result<(), NetworkError> {
+        if self.inner.shut_down.load(Ordering::Relaxed) {
+            return Err(NetworkError::ShutDown);
+        }
+        self.record_send(TraceEvent::Broadcast {
+            message,
+            recipients,
+        });
+        Ok(())
+    }
+
+    async fn direct_message(&self, message: M, recipient: K) -> Result<(), NetworkError> {
+        if self.inner.shut_down.load(Ordering::Relaxed) {
+            return Err(NetworkError::ShutDown);
+        }
+        self.record_send(TraceEvent::Direct { message, recipient });
+        Ok(())
+    }
+
+    fn recv_msgs<'a, 'b>(
+        &'a self,
+        transmit_type: TransmitType,
+    ) -> BoxSyncFuture<'b, Result<Vec<M>, NetworkError>>
+    where
+        'a: 'b,
+        Self: 'b,
+    {
+        let closure = async move {
+            if self.inner.shut_down.load(Ordering::Relaxed) {
+                return Err(NetworkError::ShutDown);
+            }
+            if self.inner.paused.load(Ordering::Relaxed) {
+                return Ok(Vec::new());
+            }
+            // wait for the next message to be due, so that messages are delivered in time
+            let next = self
+                .inner
+                .received
+                .lock()
+                .unwrap()
+                .front()
+                .map(|(elapsed, received_as, _)| (*elapsed, *received_as));
+            match next {
+                Some((next, received_as)) if received_as == transmit_type => {
+                    let elapsed = self.elapsed();
+                    if self.inner.recorded_timing && next > elapsed {
+                        async_sleep(next - elapsed).await;
+                    }
+                }
+                // the next message is delivered to the receiver of the other transmit type
+                // first, so that the node gets the messages in the order they were received
+                Some(_) => async_sleep(HANDOVER_INTERVAL).await,
+                None => {}
+            }
+            let elapsed = self.elapsed();
+            let mut queue = self.inner.received.lock().unwrap();
+            let due = queue
+                .iter()
+                .take_while(|(received, received_as, _)| {
+                    *received_as == transmit_type
+                        && (!self.inner.recorded_timing || *received <= elapsed)
+                })
+                .count();
+            Ok(queue.drain(..due).map(|(_, _, msg)| msg).collect())
+        };
+        boxed_sync(closure)
+    }
+
+    fn latest_anchor_hint(&self) -> Option<AnchorHint> {
+        self.inner.latest_anchor.get()
+    }
+
+    fn record_anchor_hint(&self, hint: AnchorHint) {
+        self.inner.latest_anchor.record(hint);
+    }
+}
@@ -12,6 +12,7 @@ use async_compatibility_layer::{
 use async_lock::RwLock;
 use async_trait::async_trait;
 use derive_more::{Deref, DerefMut};
+use futures::future::join_all;
 use hotshot_constants::VERSION_0_1;
 use hotshot_types::{
     boxed_sync,
@@ -35,7 +36,7 @@ use std::hash::{Hash, Hasher};
 use std::num::NonZeroUsize;
 use surf_disco::Url;
 
-use hotshot_types::traits::network::{NetworkReliability, ViewMessage};
+use hotshot_types::traits::network::{prioritize, NetworkReliability, ViewMessage};
 use std::collections::BTreeMap;
 use std::{
     collections::{btree_map::Entry, BTreeSet},
@@ -60,6 +61,83 @@ fn hash<T: Hash>(t: &T) -> u64 {
     s.finish()
 }
 
+/// Number of points each shard places on the [`ShardRing`]
+const SHARD_RING_REPLICAS: u64 = 64;
+
+/// Assigns nodes to the shards of a sharded web server deployment by consistent hashing of their
+/// public keys, so that adding a shard only moves the nodes that land on it.
+///
+/// The ring is derived from the number of shards alone, so every node computes the same
+/// assignment.
+#[derive(Clone, Debug)]
+pub struct ShardRing {
+    /// The points of the ring, mapped to the shard owning the arc ending at them
+    points: BTreeMap<u64, usize>,
+    /// Number of shards in the deployment
+    num_shards: usize,
+}
+
+impl ShardRing {
+    /// A ring over `num_shards` shards. A single shard is used if `num_shards` is 0.
+    #[must_use]
+    pub fn new(num_shards: usize) -> Self {
+        let num_shards = num_shards.max(1);
+        let points = (0..num_shards)
+            .flat_map(|shard| {
+                (0..SHARD_RING_REPLICAS).map(move |replica| {
+                    (ring_point(format!("{shard}-{replica}").as_bytes()), shard)
+                })
+            })
+            .collect();
+        Self { points, num_shards }
+    }
+
+    /// Number of shards in the deployment
+    #[must_use]
+    pub fn num_shards(&self) -> usize {
+        self.num_shards
+    }
+
+    /// The shard serving the node with public key `key`: the shard direct messages to the node
+    /// are posted to
+    #[must_use]
+    pub fn shard_of<K: SignatureKey>(&self, key: &K) -> usize {
+        self.shard_at(ring_point(&key.to_bytes()))
+    }
+
+    /// The shard a message broadcast for `purpose` in `view` is posted to, and polled from:
+    /// [`HOME_SHARD`] for transactions, which each shard indexes on its own, and the shard of
+    /// the view otherwise, so each view's broadcasts are served by a single shard
+    #[must_use]
+    pub fn shard_of_broadcast(&self, purpose: MessagePurpose, view: u64) -> usize {
+        match purpose {
+            MessagePurpose::Data => HOME_SHARD,
+            _ => self.shard_at(ring_point(format!("view-{view}").as_bytes())),
+        }
+    }
+
+    /// The shard owning the arc of the ring `point` is on
+    fn shard_at(&self, point: u64) -> usize {
+        self.points
+            .range(point..)
+            .chain(self.points.iter())
+            .next()
+            .map_or(0, |(_, shard)| *shard)
+    }
+}
+
+/// The shard transactions are posted to and polled from
+pub const HOME_SHARD: usize = 0;
+
+/// Position on the [`ShardRing`] of `bytes`. Uses blake3 rather than `DefaultHasher`, as every
+/// node must agree on it whatever it was built with.
+fn ring_point(bytes: &[u8]) -> u64 {
+    let hash = blake3::hash(bytes);
+    let mut point = [0u8; 8];
+    point.copy_from_slice(&hash.as_bytes()[..8]);
+    u64::from_le_bytes(point)
+}
+
 /// The web server network state
 #[derive(Clone, Debug)]
 pub struct WebServerNetwork<TYPES: NodeType> {
@@ -70,16 +148,16 @@ pub struct WebServerNetwork<TYPES: NodeType> {
 }
 
 impl<TYPES: NodeType> WebServerNetwork<TYPES> {
-    /// Post a message to the web server and return the result
+    /// Post a message to the web server shard `shard` and return the result
     async fn post_message_to_web_server(
         &self,
-        message: SendMsg<Message<TYPES>>,
+        message: &SendMsg<Message<TYPES>>,
+        shard: usize,
     ) -> Result<(), NetworkError> {
-        let result: Result<(), ClientError> = self
-            .inner
-            .client
+        let result: Result<(), ClientError> = self.inner.shard_clients[shard]
             .post(&message.get_endpoint())
-            .body_binary(&message.get_message())
+            // the message is serialized in place rather than cloned first
+            .body_binary(&message.message)
             .unwrap()
             .send()
             .await;
@@ -169,6 +247,8 @@ impl<K: SignatureKey> TaskMap<K> {
 struct Inner<TYPES: NodeType> {
     /// Our own key
     _own_key: TYPES::SignatureKey,
+    /// The chain whose messages we post and poll, on web servers shared by several chains
+    chain_id: u64,
     /// Queue for broadcasted messages
     broadcast_poll_queue_0_1: Arc<RwLock<Vec<RecvMsg<Message<TYPES>>>>>,
     /// Queue for direct messages
@@ -177,8 +257,12 @@ struct Inner<TYPES: NodeType> {
     running: AtomicBool,
     /// The web server connection is ready
     connected: AtomicBool,
-    /// The connectioni to the web server
-    client: surf_disco::Client<ClientError>,
+    /// The shard the direct messages to us are posted to
+    own_shard: usize,
+    /// The connections to every shard of the web server, in shard order
+    shard_clients: Vec<surf_disco::Client<ClientError>>,
+    /// Which shard serves which node
+    shard_ring: ShardRing,
     /// The duration to wait between poll attempts
     wait_between_polls: Duration,
     /// Whether we are connecting to a DA server
@@ -214,7 +298,7 @@ impl<TYPES: NodeType> Inner<TYPES> {
     ///
     /// * `first_tx_index` - the index of the first transaction received from the server in the latest batch.
     /// * `tx_index` - the last transaction index we saw from the web server.
-    async fn handle_tx_0_1(&self, tx: Vec<u8>, first_tx_index: u64, tx_index: &mut u64) {
+    async fn handle_tx_0_1(&self, tx: &[u8], first_tx_index: u64, tx_index: &mut u64) {
         let broadcast_poll_queue = &self.broadcast_poll_queue_0_1;
         if first_tx_index > *tx_index + 1 {
             debug!(
@@ -227,14 +311,14 @@ impl<TYPES: NodeType> Inner<TYPES> {
 
         *tx_index += 1;
 
-        if let Ok(deserialized_message_inner) = bincode::deserialize::<Message<TYPES>>(&tx) {
+        if let Ok(deserialized_message_inner) = bincode::deserialize::<Message<TYPES>>(tx) {
             let deserialized_message = RecvMsg {
                 message: Some(deserialized_message_inner),
             };
             broadcast_poll_queue
                 .write()
                 .await
-                .push(deserialized_message.clone());
+                .push(deserialized_message);
         } else {
             async_sleep(self.wait_between_polls).await;
         }
@@ -250,7 +334,7 @@ impl<TYPES: NodeType> Inner<TYPES> {
     #[allow(clippy::too_many_arguments)]
     async fn handle_message_0_1(
         &self,
-        message: Vec<u8>,
+        message: &[u8],
         view_number: u64,
         message_purpose: MessagePurpose,
         vote_index: &mut u64,
@@ -259,7 +343,7 @@ impl<TYPES: NodeType> Inner<TYPES> {
     ) -> bool {
         let broadcast_poll_queue = &self.broadcast_poll_queue_0_1;
         let direct_poll_queue = &self.direct_poll_queue_0_1;
-        if let Ok(deserialized_message_inner) = bincode::deserialize::<Message<TYPES>>(&message) {
+        if let Ok(deserialized_message_inner) = bincode::deserialize::<Message<TYPES>>(message) {
             let deserialized_message = RecvMsg {
                 message: Some(deserialized_message_inner),
             };
@@ -342,6 +426,36 @@ impl<TYPES: NodeType> Inner<TYPES> {
                     return false;
                 }
 
+                MessagePurpose::MempoolSync => {
+                    error!("Received mempool sync message in web server network");
+
+                    return false;
+                }
+
+                MessagePurpose::AvailabilitySampling => {
+                    error!("Received availability sampling message in web server network");
+
+                    return false;
+                }
+
+                MessagePurpose::AnchorAnnouncement => {
+                    error!("Received anchor announcement message in web server network");
+
+                    return false;
+                }
+
+                MessagePurpose::EpochChange => {
+                    error!("Received epoch change message in web server network");
+
+                    return false;
+                }
+
+                MessagePurpose::Rpc => {
+                    error!("Received RPC message in web server network");
+
+                    return false;
+                }
+
                 MessagePurpose::Upgrade => {
                     broadcast_poll_queue
                         .write()
@@ -356,6 +470,57 @@ impl<TYPES: NodeType> Inner<TYPES> {
         false
     }
 
+    /// The shards to poll for messages of `message_purpose` in `view_number`: our own for the
+    /// direct messages to us, the shard of the view for broadcasts, and every shard for the latest
+    /// proposal and view sync certificate, as each shard only knows of those posted to it
+    fn polled_shards(&self, message_purpose: MessagePurpose, view_number: u64) -> Vec<usize> {
+        match message_purpose {
+            MessagePurpose::Vote | MessagePurpose::ViewSyncVote => {
+                vec![self.own_shard]
+            }
+            MessagePurpose::LatestProposal | MessagePurpose::LatestViewSyncCertificate => {
+                (0..self.shard_ring.num_shards()).collect()
+            }
+            _ => vec![self
+                .shard_ring
+                .shard_of_broadcast(message_purpose, view_number)],
+        }
+    }
+
+    /// Get `endpoint` from each of `shards`, with the messages of all of them. Fails only if
+    /// every shard does.
+    async fn get_from_shards(
+        &self,
+        endpoint: &str,
+        shards: &[usize],
+    ) -> Result<Option<Vec<Vec<u8>>>, ClientError> {
+        let responses: Vec<Result<Option<Vec<Vec<u8>>>, ClientError>> = join_all(
+            shards
+                .iter()
+                .map(|shard| self.shard_clients[*shard].get(endpoint).send()),
+        )
+        .await;
+        let mut messages: Option<Vec<Vec<u8>>> = None;
+        let mut failures = 0;
+        let mut last_error = None;
+        for response in responses {
+            match response {
+                Ok(Some(shard_messages)) => {
+                    messages.get_or_insert_with(Vec::new).extend(shard_messages);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    failures += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if failures == shards.len() => Err(e),
+            _ => Ok(messages),
+        }
+    }
+
     /// Pull a web server.
     async fn poll_web_server(
         &self,
@@ -385,7 +550,13 @@ impl<TYPES: NodeType> Inner<TYPES> {
                 }
                 MessagePurpose::Vote => config::get_vote_route(view_number, vote_index),
                 MessagePurpose::Data => config::get_transactions_route(tx_index),
-                MessagePurpose::Internal => unimplemented!(),
+                // the web server has no routes for these
+                MessagePurpose::Internal
+                | MessagePurpose::MempoolSync
+                | MessagePurpose::AvailabilitySampling
+                | MessagePurpose::AnchorAnnouncement
+                | MessagePurpose::EpochChange
+                | MessagePurpose::Rpc => return Err(NetworkError::UnimplementedFeature),
                 MessagePurpose::ViewSyncCertificate => {
                     config::get_view_sync_certificate_route(view_number, vote_index)
                 }
@@ -396,9 +567,11 @@ impl<TYPES: NodeType> Inner<TYPES> {
                 MessagePurpose::VidDisperse => config::get_vid_disperse_route(view_number), // like `Proposal`
                 MessagePurpose::Upgrade => config::get_upgrade_route(view_number),
             };
+            let endpoint = config::chain_route(self.chain_id, &endpoint);
 
             if let MessagePurpose::Data = message_purpose {
-                let possible_message: TxnResult = self.client.get(&endpoint).send().await;
+                let possible_message: TxnResult =
+                    self.shard_clients[HOME_SHARD].get(&endpoint).send().await;
                 // Deserialize and process transactions from the server.
                 // If something goes wrong at any point, we sleep for wait_between_polls
                 // then try again next time.
@@ -422,8 +595,9 @@ impl<TYPES: NodeType> Inner<TYPES> {
                                 continue;
                             }
                             Some(1) => {
-                                let tx = tx_raw[1..].to_vec();
-                                let tx_version = read_version(&tx);
+                                // the message is borrowed from the response, not copied out of it
+                                let tx = &tx_raw[1..];
+                                let tx_version = read_version(tx);
 
                                 match tx_version {
                                     Some(VERSION_0_1) => {
@@ -453,8 +627,9 @@ impl<TYPES: NodeType> Inner<TYPES> {
                     async_sleep(self.wait_between_polls + additional_wait).await;
                 }
             } else {
-                let possible_message: Result<Option<Vec<Vec<u8>>>, ClientError> =
-                    self.client.get(&endpoint).send().await;
+                let possible_message = self
+                    .get_from_shards(&endpoint, &self.polled_shards(message_purpose, view_number))
+                    .await;
                 if let Ok(Some(messages)) = possible_message {
                     for message_raw in messages {
                         // This is very hacky.
@@ -475,8 +650,9 @@ impl<TYPES: NodeType> Inner<TYPES> {
                                 continue;
                             }
                             Some(1) => {
-                                let message = message_raw[1..].to_vec();
-                                let message_version = read_version(&message);
+                                // the message is borrowed from the response, not copied out of it
+                                let message = &message_raw[1..];
+                                let message_version = read_version(message);
 
                                 let should_return;
 
@@ -621,7 +797,8 @@ impl<M: NetworkMsg> NetworkMsg for SendMsg<M> {}
 impl<M: NetworkMsg> NetworkMsg for RecvMsg<M> {}
 
 impl<TYPES: NodeType + 'static> WebServerNetwork<TYPES> {
-    /// Creates a new instance of the `WebServerNetwork`
+    /// Creates a new instance of the `WebServerNetwork`, for the messages of the chain
+    /// `chain_id` on the web server
     /// # Panics
     /// if the web server url is malformed
     pub fn create(
@@ -629,20 +806,54 @@ impl<TYPES: NodeType + 'static> WebServerNetwork<TYPES> {
         wait_between_polls: Duration,
         key: TYPES::SignatureKey,
         is_da_server: bool,
+        chain_id: u64,
+    ) -> Self {
+        Self::create_sharded(vec![url], wait_between_polls, key, is_da_server, chain_id)
+    }
+
+    /// Creates a `WebServerNetwork` over a sharded web server deployment, with one url per
+    /// shard. Direct messages are posted to the shard `key` of their recipient is assigned to by
+    /// the [`ShardRing`], and polled from there by it; broadcasts are posted once, to the shard
+    /// of their view, which every node polls for that view.
+    /// # Panics
+    /// if `urls` is empty
+    pub fn create_sharded(
+        urls: Vec<Url>,
+        wait_between_polls: Duration,
+        key: TYPES::SignatureKey,
+        is_da_server: bool,
+        chain_id: u64,
     ) -> Self {
-        info!("Connecting to web server at {url:?} is da: {is_da_server}");
+        assert!(
+            !urls.is_empty(),
+            "A web server network needs at least one shard"
+        );
+        let shard_ring = ShardRing::new(urls.len());
+        let own_shard = shard_ring.shard_of(&key);
+        info!(
+            "Connecting to web server shard {own_shard} of {} at {:?} for chain {chain_id} is da: \
+             {is_da_server}",
+            urls.len(),
+            urls[own_shard]
+        );
 
         // TODO ED Wait for healthcheck
-        let client = surf_disco::Client::<ClientError>::new(url);
+        let shard_clients: Vec<_> = urls
+            .into_iter()
+            .map(surf_disco::Client::<ClientError>::new)
+            .collect();
 
         let inner = Arc::new(Inner {
             broadcast_poll_queue_0_1: Arc::default(),
             direct_poll_queue_0_1: Arc::default(),
             running: AtomicBool::new(true),
             connected: AtomicBool::new(false),
-            client,
+            own_shard,
+            shard_clients,
+            shard_ring,
             wait_between_polls,
             _own_key: key,
+            chain_id,
             is_da: is_da_server,
             tx_index: Arc::default(),
             proposal_task_map: Arc::default(),
@@ -664,10 +875,11 @@ impl<TYPES: NodeType + 'static> WebServerNetwork<TYPES> {
         }
     }
 
-    /// Parses a message to find the appropriate endpoint
+    /// Parses a message to find the appropriate endpoint on the chain `chain_id`
     /// Returns a `SendMsg` containing the endpoint
     fn parse_post_message(
         message: Message<TYPES>,
+        chain_id: u64,
     ) -> Result<SendMsg<Message<TYPES>>, WebServerNetworkError> {
         let view_number: TYPES::Time = message.get_view_number();
 
@@ -676,6 +888,11 @@ impl<TYPES: NodeType + 'static> WebServerNetwork<TYPES> {
             MessagePurpose::Vote => config::post_vote_route(*view_number),
             MessagePurpose::Data => config::post_transactions_route(),
             MessagePurpose::Internal
+            | MessagePurpose::MempoolSync
+            | MessagePurpose::AvailabilitySampling
+            | MessagePurpose::AnchorAnnouncement
+            | MessagePurpose::EpochChange
+            | MessagePurpose::Rpc
             | MessagePurpose::LatestProposal
             | MessagePurpose::LatestViewSyncCertificate => {
                 return Err(WebServerNetworkError::EndpointError)
@@ -692,7 +909,7 @@ impl<TYPES: NodeType + 'static> WebServerNetwork<TYPES> {
 
         let network_msg: SendMsg<Message<TYPES>> = SendMsg {
             message: Some(message),
-            endpoint,
+            endpoint: config::chain_route(chain_id, &endpoint),
         };
         Ok(network_msg)
     }
@@ -745,6 +962,7 @@ impl<TYPES: NodeType + 'static> WebServerNetwork<TYPES> {
                 Duration::from_millis(100),
                 known_nodes[usize::try_from(id).unwrap()].clone(),
                 is_da,
+                0,
             );
             network.server_shutdown_signal = Some(sender);
             network
@@ -816,9 +1034,13 @@ impl<TYPES: NodeType + 'static> ConnectedNetwork<Message<TYPES>, TYPES::Signatur
             return Err(NetworkError::ShutDown);
         }
 
-        let network_msg = Self::parse_post_message(message);
+        let shard = self
+            .inner
+            .shard_ring
+            .shard_of_broadcast(message.purpose(), *message.get_view_number());
+        let network_msg = Self::parse_post_message(message, self.inner.chain_id);
         match network_msg {
-            Ok(network_msg) => self.post_message_to_web_server(network_msg).await,
+            Ok(network_msg) => self.post_message_to_web_server(&network_msg, shard).await,
             Err(network_msg) => Err(NetworkError::WebServer {
                 source: network_msg,
             }),
@@ -830,19 +1052,20 @@ impl<TYPES: NodeType + 'static> ConnectedNetwork<Message<TYPES>, TYPES::Signatur
     async fn direct_message(
         &self,
         message: Message<TYPES>,
-        _recipient: TYPES::SignatureKey,
+        recipient: TYPES::SignatureKey,
     ) -> Result<(), NetworkError> {
         // short circuit if we are shut down
         #[cfg(feature = "hotshot-testing")]
         if !self.inner.running.load(Ordering::Relaxed) {
             return Err(NetworkError::ShutDown);
         }
-        let network_msg = Self::parse_post_message(message);
+        let network_msg = Self::parse_post_message(message, self.inner.chain_id);
         match network_msg {
             Ok(network_msg) => {
                 // error!("network msg is {:?}", network_msg.clone());
 
-                self.post_message_to_web_server(network_msg).await
+                let shard = self.inner.shard_ring.shard_of(&recipient);
+                self.post_message_to_web_server(&network_msg, shard).await
             }
             Err(network_msg) => Err(NetworkError::WebServer {
                 source: network_msg,
@@ -866,21 +1089,25 @@ impl<TYPES: NodeType + 'static> ConnectedNetwork<Message<TYPES>, TYPES::Signatur
             match transmit_type {
                 TransmitType::Direct => {
                     let mut queue = self.inner.direct_poll_queue_0_1.write().await;
-                    Ok(queue
+                    let mut messages: Vec<_> = queue
                         .drain(..)
                         .collect::<Vec<_>>()
                         .iter()
                         .map(|x| x.get_message().unwrap())
-                        .collect())
+                        .collect();
+                    prioritize(&mut messages);
+                    Ok(messages)
                 }
                 TransmitType::Broadcast => {
                     let mut queue = self.inner.broadcast_poll_queue_0_1.write().await;
-                    Ok(queue
+                    let mut messages: Vec<_> = queue
                         .drain(..)
                         .collect::<Vec<_>>()
                         .iter()
                         .map(|x| x.get_message().unwrap())
-                        .collect())
+                        .collect();
+                    prioritize(&mut messages);
+                    Ok(messages)
                 }
             }
         };
@@ -3,14 +3,25 @@
 //! This module provides a non-persisting, dummy adapter for the [`Storage`] trait
 use async_lock::RwLock;
 use async_trait::async_trait;
-use hotshot_types::traits::{
-    node_implementation::NodeType,
-    storage::{
-        Result, Storage, StorageError, StorageState, StoredView, TestableStorage, ViewEntry,
+use hotshot_types::{
+    data::VidCommitment,
+    governance::ParameterSchedule,
+    payload_store::StoredPayload,
+    qc_chain::StakeTable,
+    safety::SafetyEvidence,
+    simple_certificate::QuorumCertificate,
+    traits::{
+        node_implementation::NodeType,
+        storage::{
+            verify_stored_views, IntegrityReport, Result, Storage, StorageError, StorageState,
+            StoredView, StoredViews, TestableStorage, ViewArtifacts, ViewEntry,
+        },
     },
+    vote::HasViewNumber,
 };
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::{Bound, RangeBounds},
     sync::Arc,
 };
 
@@ -20,6 +31,20 @@ struct MemoryStorageInternal<TYPES: NodeType> {
     stored: BTreeMap<TYPES::Time, StoredView<TYPES>>,
     /// The views that have failed
     failed: BTreeSet<TYPES::Time>,
+    /// Votes of in-progress certificates, by view and commitment
+    votes: BTreeMap<TYPES::Time, HashMap<Vec<u8>, Vec<Vec<u8>>>>,
+    /// Artifacts of decided views, kept for as long as the storage lives
+    artifacts: BTreeMap<TYPES::Time, ViewArtifacts<TYPES>>,
+    /// Payloads of decided views, by commitment
+    payloads: HashMap<VidCommitment, StoredPayload>,
+    /// Commitments of the payloads of decided views
+    payload_views: BTreeMap<TYPES::Time, VidCommitment>,
+    /// Stake tables, by the view from which each is in effect
+    stake_tables: BTreeMap<TYPES::Time, StakeTable<TYPES>>,
+    /// Evidence of safety violations, oldest first
+    safety_evidence: Vec<SafetyEvidence<TYPES>>,
+    /// The parameter schedule persisted last
+    parameter_schedule: Option<ParameterSchedule>,
 }
 
 /// In memory, ephemeral, storage for a [`SystemContext`](crate::SystemContext) instance
@@ -36,6 +61,33 @@ impl<TYPES: NodeType> MemoryStorage<TYPES> {
         let inner = MemoryStorageInternal {
             stored: BTreeMap::new(),
             failed: BTreeSet::new(),
+            votes: BTreeMap::new(),
+            artifacts: BTreeMap::new(),
+            payloads: HashMap::new(),
+            payload_views: BTreeMap::new(),
+            stake_tables: BTreeMap::new(),
+            safety_evidence: Vec::new(),
+            parameter_schedule: None,
+        };
+        Self {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+
+    /// Create a storage holding the views of `state`, as taken with
+    /// [`TestableStorage::get_full_state`], e.g. to inspect a node's storage offline
+    #[must_use]
+    pub fn from_state(state: StorageState<TYPES>) -> Self {
+        let inner = MemoryStorageInternal {
+            stored: state.stored,
+            failed: state.failed,
+            votes: BTreeMap::new(),
+            artifacts: BTreeMap::new(),
+            payloads: HashMap::new(),
+            payload_views: BTreeMap::new(),
+            stake_tables: BTreeMap::new(),
+            safety_evidence: Vec::new(),
+            parameter_schedule: None,
         };
         Self {
             inner: Arc::new(RwLock::new(inner)),
@@ -87,6 +139,13 @@ impl<TYPES: NodeType> Storage<TYPES> for MemoryStorage<TYPES> {
         let failed_after = inner.failed.split_off(&view);
         let old_failed = std::mem::replace(&mut inner.failed, failed_after);
 
+        // payloads go with their views, unless a later view has the same payload
+        inner.payload_views = inner.payload_views.split_off(&view);
+        let kept: HashSet<_> = inner.payload_views.values().copied().collect();
+        inner
+            .payloads
+            .retain(|commitment, _| kept.contains(commitment));
+
         Ok(old_stored.len() + old_failed.len())
     }
 
@@ -103,4 +162,170 @@ impl<TYPES: NodeType> Storage<TYPES> for MemoryStorage<TYPES> {
     async fn commit(&self) -> Result {
         Ok(()) // do nothing
     }
+
+    async fn append_vote(&self, view: TYPES::Time, commitment: Vec<u8>, vote: Vec<u8>) -> Result {
+        let mut inner = self.inner.write().await;
+        inner
+            .votes
+            .entry(view)
+            .or_default()
+            .entry(commitment)
+            .or_default()
+            .push(vote);
+        Ok(())
+    }
+
+    async fn get_votes(&self, view: TYPES::Time, commitment: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .votes
+            .get(&view)
+            .and_then(|votes| votes.get(commitment))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn cleanup_votes_before_view(&self, view: TYPES::Time) -> Result<usize> {
+        let mut inner = self.inner.write().await;
+        let votes_after = inner.votes.split_off(&view);
+        let old_votes = std::mem::replace(&mut inner.votes, votes_after);
+        Ok(old_votes
+            .values()
+            .flat_map(HashMap::values)
+            .map(Vec::len)
+            .sum())
+    }
+
+    async fn append_view_artifacts(&self, artifacts: ViewArtifacts<TYPES>) -> Result {
+        let mut inner = self.inner.write().await;
+        inner.artifacts.insert(artifacts.view_number, artifacts);
+        Ok(())
+    }
+
+    async fn get_view_artifacts(&self, view: TYPES::Time) -> Result<Option<ViewArtifacts<TYPES>>> {
+        let inner = self.inner.read().await;
+        Ok(inner.artifacts.get(&view).cloned())
+    }
+
+    async fn cleanup_view_artifacts_before_view(&self, view: TYPES::Time) -> Result<usize> {
+        let mut inner = self.inner.write().await;
+        let artifacts_after = inner.artifacts.split_off(&view);
+        let old_artifacts = std::mem::replace(&mut inner.artifacts, artifacts_after);
+        Ok(old_artifacts.len())
+    }
+
+    async fn append_payload(
+        &self,
+        view: TYPES::Time,
+        payload_commitment: VidCommitment,
+        payload: StoredPayload,
+    ) -> Result {
+        let mut inner = self.inner.write().await;
+        inner.payloads.entry(payload_commitment).or_insert(payload);
+        inner.payload_views.insert(view, payload_commitment);
+        Ok(())
+    }
+
+    async fn get_payload(
+        &self,
+        payload_commitment: &VidCommitment,
+    ) -> Result<Option<StoredPayload>> {
+        let inner = self.inner.read().await;
+        Ok(inner.payloads.get(payload_commitment).cloned())
+    }
+
+    async fn append_stake_table(
+        &self,
+        view: TYPES::Time,
+        stake_table: StakeTable<TYPES>,
+    ) -> Result {
+        let mut inner = self.inner.write().await;
+        inner.stake_tables.insert(view, stake_table);
+        Ok(())
+    }
+
+    async fn get_stake_table_at(&self, view: TYPES::Time) -> Result<Option<StakeTable<TYPES>>> {
+        let inner = self.inner.read().await;
+        Ok(inner
+            .stake_tables
+            .range(..=view)
+            .next_back()
+            .map(|(_, stake_table)| stake_table.clone()))
+    }
+
+    async fn cleanup_stake_tables_before_view(&self, view: TYPES::Time) -> Result<usize> {
+        let mut inner = self.inner.write().await;
+        let stake_tables_after = inner.stake_tables.split_off(&view);
+        let mut old_stake_tables = std::mem::replace(&mut inner.stake_tables, stake_tables_after);
+        // the last table before `view` is still in effect at it, unless one starts at `view`
+        if !inner.stake_tables.contains_key(&view) {
+            if let Some((start, stake_table)) = old_stake_tables.pop_last() {
+                inner.stake_tables.insert(start, stake_table);
+            }
+        }
+        Ok(old_stake_tables.len())
+    }
+
+    async fn append_parameter_schedule(&self, schedule: ParameterSchedule) -> Result {
+        self.inner.write().await.parameter_schedule = Some(schedule);
+        Ok(())
+    }
+
+    async fn get_parameter_schedule(&self) -> Result<Option<ParameterSchedule>> {
+        Ok(self.inner.read().await.parameter_schedule.clone())
+    }
+
+    async fn append_safety_evidence(&self, evidence: SafetyEvidence<TYPES>) -> Result {
+        self.inner.write().await.safety_evidence.push(evidence);
+        Ok(())
+    }
+
+    async fn get_safety_evidence(&self) -> Result<Vec<SafetyEvidence<TYPES>>> {
+        Ok(self.inner.read().await.safety_evidence.clone())
+    }
+
+    async fn verify_integrity(&self) -> Result<IntegrityReport<TYPES>> {
+        let inner = self.inner.read().await;
+        Ok(verify_stored_views(&inner.stored))
+    }
+
+    async fn compact(&self) -> Result<usize> {
+        let Some(anchor) = self.inner.read().await.stored.keys().next_back().copied() else {
+            return Ok(0);
+        };
+        let dropped_views = self.cleanup_storage_up_to_view(anchor).await?;
+        let dropped_votes = self.cleanup_votes_before_view(anchor).await?;
+        Ok(dropped_views + dropped_votes)
+    }
+
+    async fn iter_views<R>(&self, range: R) -> Result<StoredViews<TYPES>>
+    where
+        R: RangeBounds<TYPES::Time> + Send,
+    {
+        let inner = self.inner.read().await;
+        let views: Vec<_> = inner
+            .stored
+            .range(range)
+            .map(|(_, view)| view.clone())
+            .collect();
+        Ok(Box::new(views.into_iter()))
+    }
+
+    async fn qc_for_view(&self, view: TYPES::Time) -> Result<Option<QuorumCertificate<TYPES>>> {
+        let inner = self.inner.read().await;
+        let from_child = inner
+            .stored
+            .range((Bound::Excluded(view), Bound::Unbounded))
+            .map(|(_, child)| &child.justify_qc)
+            .find(|qc| qc.get_view_number() == view);
+        // the QC of a view whose child was cleaned up may still be among its artifacts
+        Ok(from_child
+            .or_else(|| {
+                inner
+                    .artifacts
+                    .get(&view)
+                    .and_then(|artifacts| artifacts.quorum_certificate.as_ref())
+            })
+            .cloned())
+    }
 }
@@ -1,21 +1,47 @@
 //! Provides an event-streaming handle for a [`SystemContext`] running in the background
 
-use crate::{traits::NodeImplementation, types::Event, SystemContext};
+use crate::{
+    traits::{NodeImplementation, Storage},
+    types::Event,
+    SystemContext,
+};
 use async_broadcast::{InactiveReceiver, Receiver, Sender};
 
+use async_compatibility_layer::art::async_timeout;
 use async_lock::RwLock;
-use futures::Stream;
+#[cfg(async_executor_impl = "async-std")]
+use async_std::task::spawn_blocking;
+use bytes::Bytes;
+use commit::Commitment;
+use futures::{Stream, StreamExt};
 
 use hotshot_task_impls::events::HotShotEvent;
-#[cfg(feature = "hotshot-testing")]
-use hotshot_types::traits::election::Membership;
 
 use hotshot_task::task::TaskRegistry;
-use hotshot_types::{boxed_sync, BoxSyncFuture};
 use hotshot_types::{
-    consensus::Consensus, data::Leaf, error::HotShotError, traits::node_implementation::NodeType,
+    anchor::AnchorHint,
+    consensus::Consensus,
+    data::{Leaf, VidCommitment},
+    error::{HotShotError, StorageSnafu},
+    leaf_dag::LeafDag,
+    payload_validation::{PayloadAcceptance, PayloadDryRun, PayloadRejection},
+    qc_chain::StakeTable,
+    receipt::{TxReceipt, TxStatus},
+    rpc::NetworkRpc,
+    traits::{
+        election::Membership,
+        network::{ConnectedNetwork, NetworkError},
+        node_implementation::NodeType,
+        storage::ViewArtifacts,
+        BlockPayload,
+    },
 };
-use std::sync::Arc;
+use hotshot_types::{boxed_sync, BoxSyncFuture};
+use snafu::ResultExt;
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+#[cfg(async_executor_impl = "tokio")]
+use tokio::task::spawn_blocking;
+use tracing::error;
 
 /// Event streaming handle for a [`SystemContext`] instance running in the background
 ///
@@ -102,7 +128,86 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static> SystemContextHandl
         self.hotshot.try_get_decided_leaf()
     }
 
-    /// Submits a transaction to the backing [`SystemContext`] instance.
+    /// Snapshot the leaves consensus currently holds in memory, with which branch was decided,
+    /// for exporting as DOT or JSON.
+    pub async fn get_leaf_dag(&self) -> LeafDag {
+        self.hotshot.get_consensus().read().await.leaf_dag()
+    }
+
+    /// Get the proposal, DA certificate, QC and QC signers of the decided view `view`, so it can be
+    /// re-verified offline. Returns [`None`] if the storage holds no artifacts for `view`.
+    ///
+    /// # Errors
+    /// If the storage fails to read the artifacts
+    pub async fn get_view_artifacts(
+        &self,
+        view: TYPES::Time,
+    ) -> Result<Option<ViewArtifacts<TYPES>>, HotShotError<TYPES>> {
+        self.storage
+            .get_view_artifacts(view)
+            .await
+            .context(StorageSnafu)
+    }
+
+    /// Get the stake table of the quorum in effect at `view`, as persisted in storage, or as the
+    /// quorum membership knows it if the storage holds none for `view`.
+    ///
+    /// # Errors
+    /// If the storage fails to read the stake table, or if neither knows the stake table of
+    /// `view`, e.g. as it is before the anchor of this node or was pruned
+    pub async fn get_stake_table_at(
+        &self,
+        view: TYPES::Time,
+    ) -> Result<StakeTable<TYPES>, HotShotError<TYPES>> {
+        if let Some(stake_table) = self
+            .storage
+            .get_stake_table_at(view)
+            .await
+            .context(StorageSnafu)?
+        {
+            return Ok(stake_table);
+        }
+        self.hotshot
+            .inner
+            .memberships
+            .quorum_membership
+            .get_stake_table_at(view)
+            .ok_or(HotShotError::StakeTableNotFound { view_number: view })
+    }
+
+    /// Get the encoded transactions of the block payload with `payload_commitment`, proposed in
+    /// `view`, fetching it from the DA committee if this node does not hold it.
+    ///
+    /// Returns [`None`] if the payload could not be fetched within `timeout`.
+    pub async fn fetch_block_payload(
+        &self,
+        payload_commitment: VidCommitment,
+        view: TYPES::Time,
+        timeout: Duration,
+    ) -> Option<Bytes> {
+        // subscribe before asking, so the response cannot be missed
+        let mut events = self.internal_event_stream.1.activate_cloned();
+        self.internal_event_stream
+            .0
+            .broadcast_direct(HotShotEvent::BlockFetchNeeded(payload_commitment, view))
+            .await
+            .ok()?;
+
+        let fetched = async move {
+            while let Some(event) = events.next().await {
+                if let HotShotEvent::BlockFetched(fetched_commitment, _, payload) = event {
+                    if fetched_commitment == payload_commitment {
+                        return Some(payload);
+                    }
+                }
+            }
+            None
+        };
+        async_timeout(timeout, fetched).await.ok().flatten()
+    }
+
+    /// Submits a transaction to the backing [`SystemContext`] instance, returning its receipt,
+    /// whose commitment [`tx_status`](Self::tx_status) reports what became of the transaction.
     ///
     /// The current node broadcasts the transaction to all nodes on the network.
     ///
@@ -113,10 +218,62 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static> SystemContextHandl
     pub async fn submit_transaction(
         &self,
         tx: TYPES::Transaction,
-    ) -> Result<(), HotShotError<TYPES>> {
+    ) -> Result<TxReceipt<TYPES>, HotShotError<TYPES>> {
         self.hotshot.publish_transaction_async(tx).await
     }
 
+    /// What became of the transaction `commitment`, as far as this node knows: whether it waits
+    /// in the mempool, is in the block of a proposal or decided leaf, or was dropped. Decided and
+    /// dropped transactions are forgotten after a while, and reported [`TxStatus::Unknown`], as
+    /// are transactions which never reached the node.
+    pub async fn tx_status(&self, commitment: &Commitment<TYPES::Transaction>) -> TxStatus<TYPES> {
+        // the mempool goes first: the tasks record what became of a transaction before they
+        // take it out of the mempool
+        let in_mempool = self.hotshot.inner.mempool.contains(commitment).await;
+        self.hotshot
+            .inner
+            .tx_index
+            .read()
+            .await
+            .status(commitment, in_mempool)
+    }
+
+    /// Whether the replicas would vote for a proposal of `payload`, with `metadata`, in `view`,
+    /// for block builders to check a payload before bidding it. Runs the checks this node runs
+    /// as a replica, with its header extensions, without voting, see
+    /// [`payload_validation`](hotshot_types::payload_validation). Only holds the consensus state
+    /// to take the leaf the proposal extends, and checks the payload on a blocking thread.
+    ///
+    /// # Errors
+    ///
+    /// Why the replicas would reject the proposal
+    pub async fn validate_payload(
+        &self,
+        payload: TYPES::BlockPayload,
+        metadata: <TYPES::BlockPayload as BlockPayload>::Metadata,
+        view: TYPES::Time,
+    ) -> Result<PayloadAcceptance<TYPES>, PayloadRejection<TYPES>> {
+        let total_nodes = self
+            .hotshot
+            .inner
+            .memberships
+            .quorum_membership
+            .total_nodes();
+        let dry_run = PayloadDryRun::prepare(&*self.hotshot.inner.consensus.read().await, view)?;
+        let extender = Arc::clone(&self.hotshot.inner.header_extender);
+        let result = spawn_blocking(move || {
+            dry_run.run(extender.as_ref(), total_nodes, &payload, &metadata)
+        })
+        .await;
+        #[cfg(async_executor_impl = "tokio")]
+        let result = result.unwrap_or_else(|e| {
+            Err(PayloadRejection::Aborted {
+                reason: e.to_string(),
+            })
+        });
+        result
+    }
+
     /// Provides a reference to the underlying storage for this [`SystemContext`], allowing access to
     /// historical data
     pub fn storage(&self) -> &I::Storage {
@@ -128,6 +285,65 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static> SystemContextHandl
         self.hotshot.get_consensus()
     }
 
+    /// How long a transaction submitted now is expected to take to be decided, from the moving
+    /// average of recent view durations. [`None`] until the node has completed a view.
+    pub async fn estimated_finality_latency(&self) -> Option<Duration> {
+        self.hotshot
+            .get_consensus()
+            .read()
+            .await
+            .view_latency
+            .estimated_finality_latency()
+    }
+
+    /// Cut this node off from every node but `reachable` in both of its networks, or reconnect
+    /// it if `reachable` is `None`, e.g. in partition scenarios
+    /// # Errors
+    /// If a network cannot be partitioned, as the web server network cannot
+    pub async fn set_network_partition(
+        &self,
+        reachable: Option<BTreeSet<TYPES::SignatureKey>>,
+    ) -> Result<(), NetworkError> {
+        self.hotshot.inner.networks.set_partition(reachable).await
+    }
+
+    /// Follow the stake table of `keys` in both networks, as networks admitting only the peers
+    /// of the stake table do, e.g. once nodes joined the running network
+    pub async fn update_stake_table(&self, keys: BTreeSet<TYPES::SignatureKey>) {
+        let networks = &self.hotshot.inner.networks;
+        networks
+            .quorum_network
+            .update_stake_table(keys.clone())
+            .await;
+        networks.da_network.update_stake_table(keys).await;
+    }
+
+    /// Request/response calls to other nodes over the quorum network, to ask them with
+    /// [`NetworkRpc::request`] and to answer them with the handlers registered with
+    /// [`NetworkRpc::register`]
+    #[must_use]
+    pub fn rpc(&self) -> &NetworkRpc<TYPES, I::QuorumNetwork> {
+        &self.hotshot.inner.rpc
+    }
+
+    /// The number of undecided transactions waiting in the mempool of this node
+    pub async fn mempool_depth(&self) -> usize {
+        self.hotshot.inner.mempool.len().await
+    }
+
+    /// The number of tasks of this node, counted as the receivers of its internal event
+    /// channel, which every task listens on
+    #[must_use]
+    pub fn task_count(&self) -> usize {
+        self.internal_event_stream.0.receiver_count()
+    }
+
+    /// The number of events queued in the internal and output event channels of this node
+    #[must_use]
+    pub fn queued_events(&self) -> usize {
+        self.internal_event_stream.0.len() + self.output_event_stream.0.len()
+    }
+
     /// Block the underlying quorum (and committee) networking interfaces until node is
     /// successfully initialized into the networks.
     pub async fn wait_for_networks_ready(&self) {
@@ -145,6 +361,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static> SystemContextHandl
         boxed_sync(async move {
             self.hotshot.inner.networks.shut_down_networks().await;
             self.registry.shutdown().await;
+            if let Err(e) = self.hotshot.inner.flush_storage().await {
+                error!("Failed to commit storage before shutting down: {e:?}");
+            }
         })
     }
 
@@ -166,6 +385,23 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES> + 'static> SystemContextHandl
             .get_leader(view_number)
     }
 
+    /// Whether this node only follows consensus, without proposing or voting
+    #[must_use]
+    pub fn is_observer(&self) -> bool {
+        self.hotshot.inner.observer
+    }
+
+    /// The announced anchor of the highest view this node heard of, if any. A node which joined a
+    /// running network can target its catchup at the view of the hint.
+    #[must_use]
+    pub fn latest_anchor_hint(&self) -> Option<AnchorHint> {
+        self.hotshot
+            .inner
+            .networks
+            .quorum_network
+            .latest_anchor_hint()
+    }
+
     /// Wrapper to get this node's public key
     #[cfg(feature = "hotshot-testing")]
     pub fn get_public_key(&self) -> TYPES::SignatureKey {
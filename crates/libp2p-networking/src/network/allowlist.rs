@@ -0,0 +1,56 @@
+//! The peers a node admits connections from, in allowlist mode
+//!
+//! Without an allowlist, anyone who knows the bootstrap nodes can join the gossip mesh. A
+//! [`PeerAllowlist`] admits every peer until it is enforced; from then on, the connection
+//! handler of the gossip behaviour denies connections from peers not on it, and the node closes
+//! the connections to them it already had. The bootstrap nodes are always on it. The client sets
+//! the other peers, e.g. to those the keys of the stake table resolve to through their signed
+//! contact records, and sets them again as more keys resolve or the stake table changes.
+
+use libp2p_identity::PeerId;
+use std::collections::HashSet;
+
+/// The peers a node admits connections from
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerAllowlist {
+    /// the peers always admitted, such as the bootstrap nodes
+    bootstrap: HashSet<PeerId>,
+    /// the other peers admitted once the list is enforced
+    allowed: HashSet<PeerId>,
+    /// whether connections from peers not on the list are denied
+    enforced: bool,
+}
+
+impl PeerAllowlist {
+    /// A list of the `bootstrap` peers, which are always admitted, not enforced yet
+    #[must_use]
+    pub fn new(bootstrap: impl IntoIterator<Item = PeerId>) -> Self {
+        Self {
+            bootstrap: bootstrap.into_iter().collect(),
+            allowed: HashSet::new(),
+            enforced: false,
+        }
+    }
+
+    /// Admit `peers` and the bootstrap peers, and no longer the peers allowed before
+    pub fn set_allowed(&mut self, peers: impl IntoIterator<Item = PeerId>) {
+        self.allowed = peers.into_iter().collect();
+    }
+
+    /// Start denying connections from peers not on the list
+    pub fn enforce(&mut self) {
+        self.enforced = true;
+    }
+
+    /// Whether connections from peers not on the list are denied
+    #[must_use]
+    pub fn is_enforced(&self) -> bool {
+        self.enforced
+    }
+
+    /// Whether a connection from `peer` is admitted
+    #[must_use]
+    pub fn admits(&self, peer: &PeerId) -> bool {
+        !self.enforced || self.bootstrap.contains(peer) || self.allowed.contains(peer)
+    }
+}
@@ -3,6 +3,8 @@ use std::{
     task::Poll,
 };
 
+use futures::channel::oneshot::Sender;
+use hotshot_constants::DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER;
 use libp2p::request_response::cbor::Behaviour;
 use libp2p::{
     request_response::{Event, Message, OutboundRequestId, ResponseChannel},
@@ -10,7 +12,7 @@ use libp2p::{
     Multiaddr,
 };
 use libp2p_identity::PeerId;
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 
 use super::exponential_backoff::ExponentialBackoff;
 
@@ -24,6 +26,9 @@ pub struct DMRequest {
     pub backoff: ExponentialBackoff,
     /// the number of remaining retries before giving up
     pub(crate) retry_count: u8,
+    /// notified once the recipient acknowledges the message. Dropped without being notified if
+    /// we run out of retries.
+    pub(crate) notify: Option<Sender<()>>,
 }
 
 /// Wrapper metadata around libp2p's request response
@@ -37,6 +42,12 @@ pub struct DMBehaviour {
     failed_rr: VecDeque<DMRequest>,
     /// lsit of out events for parent behaviour
     out_event_queue: Vec<DMEvent>,
+    /// number of requests awaiting acknowledgement by each peer
+    in_flight: HashMap<PeerId, usize>,
+    /// requests held back because their peer already has too many requests in flight
+    queued_rr: HashMap<PeerId, VecDeque<DMRequest>>,
+    /// the maximum number of unacknowledged requests to a single peer
+    max_in_flight_per_peer: usize,
 }
 
 /// Lilst of direct message output events
@@ -71,11 +82,17 @@ impl DMBehaviour {
                     "outbound failure to send message to {:?} with error {:?}",
                     peer, error
                 );
-                // RM TODO: make direct messages have n (and not infinite) retries
-                // issue: https://github.com/EspressoSystems/HotShot/issues/2003
                 if let Some(mut req) = self.in_progress_rr.remove(&request_id) {
-                    req.backoff.start_next(false);
-                    self.failed_rr.push_back(req);
+                    self.finish_request(&req.peer_id);
+                    if req.retry_count == 0 {
+                        warn!(
+                            "giving up on direct message to {:?}, out of retries",
+                            req.peer_id
+                        );
+                    } else {
+                        req.backoff.start_next(false);
+                        self.failed_rr.push_back(req);
+                    }
                 }
             }
             Event::Message { message, peer, .. } => match message {
@@ -95,8 +112,13 @@ impl DMBehaviour {
                     response: msg,
                 } => {
                     // success, finished.
-                    if let Some(req) = self.in_progress_rr.remove(&request_id) {
+                    if let Some(mut req) = self.in_progress_rr.remove(&request_id) {
                         info!("recv-ed DIRECT RESPONSE {:?}", msg);
+                        self.finish_request(&req.peer_id);
+                        if let Some(notify) = req.notify.take() {
+                            // the requester may have stopped waiting, which is fine
+                            let _ = notify.send(());
+                        }
                         self.out_event_queue
                             .push(DMEvent::DirectResponse(msg, req.peer_id));
                     } else {
@@ -134,7 +156,10 @@ impl NetworkBehaviour for DMBehaviour {
         &mut self,
         cx: &mut std::task::Context<'_>,
     ) -> Poll<ToSwarm<DMEvent, THandlerInEvent<Self>>> {
-        while let Some(req) = self.failed_rr.pop_front() {
+        for _ in 0..self.failed_rr.len() {
+            let Some(req) = self.failed_rr.pop_front() else {
+                break;
+            };
             if req.backoff.is_expired() {
                 self.add_direct_request(req);
             } else {
@@ -260,6 +285,9 @@ impl DMBehaviour {
             in_progress_rr: HashMap::default(),
             failed_rr: VecDeque::default(),
             out_event_queue: Vec::default(),
+            in_flight: HashMap::default(),
+            queued_rr: HashMap::default(),
+            max_in_flight_per_peer: DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER,
         }
     }
 
@@ -274,11 +302,28 @@ impl DMBehaviour {
     }
 
     /// Add a direct request for a given peer
+    ///
+    /// If the peer already has `max_in_flight_per_peer` unacknowledged requests, the request is
+    /// queued until one of them completes.
     pub fn add_direct_request(&mut self, mut req: DMRequest) {
         if req.retry_count == 0 {
             return;
         }
 
+        let in_flight = self.in_flight.entry(req.peer_id).or_default();
+        if *in_flight >= self.max_in_flight_per_peer {
+            debug!(
+                "{:?} has {} direct requests in flight, queueing",
+                req.peer_id, in_flight
+            );
+            self.queued_rr
+                .entry(req.peer_id)
+                .or_default()
+                .push_back(req);
+            return;
+        }
+        *in_flight += 1;
+
         req.retry_count -= 1;
 
         let request_id = self
@@ -289,6 +334,27 @@ impl DMBehaviour {
         self.in_progress_rr.insert(request_id, req);
     }
 
+    /// Mark a request to `peer_id` as no longer in flight, and send the next queued request to
+    /// that peer, if any.
+    fn finish_request(&mut self, peer_id: &PeerId) {
+        if let Some(in_flight) = self.in_flight.get_mut(peer_id) {
+            *in_flight = in_flight.saturating_sub(1);
+            if *in_flight == 0 {
+                self.in_flight.remove(peer_id);
+            }
+        }
+        let next = self
+            .queued_rr
+            .get_mut(peer_id)
+            .and_then(VecDeque::pop_front);
+        if self.queued_rr.get(peer_id).is_some_and(VecDeque::is_empty) {
+            self.queued_rr.remove(peer_id);
+        }
+        if let Some(req) = next {
+            self.add_direct_request(req);
+        }
+    }
+
     /// Add a direct response for a channel
     pub fn add_direct_response(&mut self, chan: ResponseChannel<Vec<u8>>, msg: Vec<u8>) {
         let res = self.request_response.send_response(chan, msg);
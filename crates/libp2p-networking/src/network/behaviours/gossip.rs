@@ -5,7 +5,7 @@ use std::{
 
 use libp2p::{
     gossipsub::{Behaviour, Event, IdentTopic, PublishError::Duplicate, TopicHash},
-    swarm::{NetworkBehaviour, THandlerInEvent, THandlerOutEvent, ToSwarm},
+    swarm::{ConnectionDenied, NetworkBehaviour, THandlerInEvent, THandlerOutEvent, ToSwarm},
     Multiaddr,
 };
 use libp2p_identity::PeerId;
@@ -13,6 +13,10 @@ use libp2p_identity::PeerId;
 use tracing::{debug, error, info, warn};
 
 use super::exponential_backoff::ExponentialBackoff;
+use crate::network::{
+    allowlist::PeerAllowlist,
+    error::{NotAllowlisted, Partitioned},
+};
 
 /// wrapper metadata around libp2p's gossip protocol
 pub struct GossipBehaviour {
@@ -26,25 +30,38 @@ pub struct GossipBehaviour {
     out_event_queue: Vec<GossipEvent>,
     /// Set of topics we are subscribed to
     subscribed_topics: HashSet<String>,
+    /// The peers connections are admitted from
+    allowlist: PeerAllowlist,
+    /// The only peers connections are admitted from while the node is partitioned
+    partition: Option<HashSet<PeerId>>,
+    /// The only topic this behaviour carries, if it is dedicated to one
+    dedicated_topic: Option<String>,
 }
 
 /// Output event
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum GossipEvent {
-    /// We received a gossip
-    GossipMsg(Vec<u8>, TopicHash),
+    /// We received a gossip, forwarded to us by the given peer
+    GossipMsg(Vec<u8>, TopicHash, PeerId),
 }
 
 impl GossipBehaviour {
     /// handle a gossip event
     fn gossip_handle_event(&mut self, event: Event) {
         match event {
-            Event::Message { message, .. } => {
+            Event::Message {
+                message,
+                propagation_source,
+                ..
+            } => {
                 // if we get an event from the gossipsub behaviour, push it
                 // onto the event queue (which will get popped during poll)
                 // and propagated back to the overall behaviour
-                self.out_event_queue
-                    .push(GossipEvent::GossipMsg(message.data, message.topic));
+                self.out_event_queue.push(GossipEvent::GossipMsg(
+                    message.data,
+                    message.topic,
+                    propagation_source,
+                ));
             }
             Event::Subscribed { topic, .. } => {
                 info!("subscribed to topic {}", topic);
@@ -159,6 +176,7 @@ impl NetworkBehaviour for GossipBehaviour {
         local_addr: &Multiaddr,
         remote_addr: &Multiaddr,
     ) -> Result<libp2p::swarm::THandler<Self>, libp2p::swarm::ConnectionDenied> {
+        self.check_allowlist(peer)?;
         self.gossipsub.handle_established_inbound_connection(
             connection_id,
             peer,
@@ -189,6 +207,7 @@ impl NetworkBehaviour for GossipBehaviour {
         addr: &Multiaddr,
         role_override: libp2p::core::Endpoint,
     ) -> Result<libp2p::swarm::THandler<Self>, libp2p::swarm::ConnectionDenied> {
+        self.check_allowlist(peer)?;
         self.gossipsub.handle_established_outbound_connection(
             connection_id,
             peer,
@@ -199,18 +218,79 @@ impl NetworkBehaviour for GossipBehaviour {
 }
 
 impl GossipBehaviour {
-    /// Create new gossip behavioru based on gossipsub
+    /// Create new gossip behavioru based on gossipsub, admitting connections from the peers
+    /// of `allowlist`
     #[must_use]
-    pub fn new(gossipsub: Behaviour) -> Self {
+    pub fn new(gossipsub: Behaviour, allowlist: PeerAllowlist) -> Self {
         Self {
             backoff: ExponentialBackoff::default(),
             in_progress_gossip: VecDeque::default(),
             gossipsub,
             out_event_queue: Vec::default(),
             subscribed_topics: HashSet::default(),
+            allowlist,
+            partition: None,
+            dedicated_topic: None,
         }
     }
 
+    /// Dedicate this behaviour to carrying `topic` only
+    #[must_use]
+    pub fn dedicated_to(mut self, topic: impl Into<String>) -> Self {
+        self.dedicated_topic = Some(topic.into());
+        self
+    }
+
+    /// Whether this behaviour carries `topic`
+    #[must_use]
+    pub fn carries(&self, topic: &str) -> bool {
+        self.dedicated_topic
+            .as_deref()
+            .map_or(true, |dedicated| dedicated == topic)
+    }
+
+    /// Deny the connection of `peer` if it is not on the allowlist or across the partition
+    fn check_allowlist(&self, peer: PeerId) -> Result<(), ConnectionDenied> {
+        if !self.allowlist.admits(&peer) {
+            debug!(
+                "denying connection from {} which is not on the allowlist",
+                peer
+            );
+            Err(ConnectionDenied::new(NotAllowlisted { peer }))
+        } else if self
+            .partition
+            .as_ref()
+            .is_some_and(|reachable| !reachable.contains(&peer))
+        {
+            debug!("denying connection from {} across the partition", peer);
+            Err(ConnectionDenied::new(Partitioned { peer }))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Admit connections only from `peers` and the bootstrap nodes
+    pub fn enforce_allowlist(&mut self, peers: Vec<PeerId>) {
+        self.allowlist.set_allowed(peers);
+        self.allowlist.enforce();
+    }
+
+    /// Admit connections only from `reachable` peers, or from any peer on the allowlist again
+    /// if `reachable` is `None`
+    pub fn set_partition(&mut self, reachable: Option<Vec<PeerId>>) {
+        self.partition = reachable.map(|peers| peers.into_iter().collect());
+    }
+
+    /// Whether a connection from `peer` is admitted
+    #[must_use]
+    pub fn admits(&self, peer: &PeerId) -> bool {
+        self.allowlist.admits(peer)
+            && self
+                .partition
+                .as_ref()
+                .map_or(true, |reachable| reachable.contains(peer))
+    }
+
     /// Publish a given gossip
     pub fn publish_gossip(&mut self, topic: IdentTopic, contents: Vec<u8>) {
         let res = self.gossipsub.publish(topic.clone(), contents.clone());
@@ -0,0 +1,330 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use hotshot_constants::{
+    CHUNK_REASSEMBLY_MAX_BYTES, CHUNK_REASSEMBLY_MAX_PENDING_PER_PEER, CHUNK_REASSEMBLY_TIMEOUT_SEC,
+};
+use libp2p_identity::PeerId;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::{deserialize_msg, node::MAX_GOSSIP_MSG_SIZE, serialize_msg};
+
+/// Bytes reserved on top of a chunk's data for the envelope and transport framing
+pub const CHUNK_FRAMING_OVERHEAD: usize = 4096;
+
+/// A blake3 digest
+type Digest = [u8; 32];
+
+/// What actually goes over the wire for every gossip message and direct request. The data is
+/// borrowed from the message when sending, and owned when receiving; both serialize the same.
+#[derive(Serialize, Deserialize, Debug)]
+enum Envelope<D = Vec<u8>> {
+    /// A message small enough to be sent as is
+    Whole(D),
+    /// One part of a message larger than the maximum wire message size
+    Chunk {
+        /// blake3 hash of the whole message, which also identifies it
+        id: Digest,
+        /// position of this chunk
+        index: u32,
+        /// number of chunks the message was split into
+        total: u32,
+        /// blake3 hash of `data`
+        checksum: Digest,
+        /// the data of this chunk
+        data: D,
+    },
+}
+
+/// Split `message` into wire messages whose data is at most `max_size` bytes each
+///
+/// # Errors
+/// If the envelope cannot be serialized
+pub fn split(message: &[u8], max_size: usize) -> Result<Vec<Vec<u8>>, Box<bincode::ErrorKind>> {
+    let max_size = max_size.max(1);
+    if message.len() <= max_size {
+        return Ok(vec![serialize_msg(&Envelope::Whole(message))?]);
+    }
+    let id = *blake3::hash(&message).as_bytes();
+    let chunks: Vec<_> = message.chunks(max_size).collect();
+    let total = u32::try_from(chunks.len()).unwrap_or(u32::MAX);
+    chunks
+        .into_iter()
+        .zip(0..total)
+        .map(|(data, index)| {
+            serialize_msg(&Envelope::Chunk {
+                id,
+                index,
+                total,
+                checksum: *blake3::hash(data).as_bytes(),
+                data,
+            })
+        })
+        .collect()
+}
+
+/// A message some of whose chunks have been received
+struct PartialMessage<A> {
+    /// the peer the first chunk came from, whose budget the message counts against
+    peer: PeerId,
+    /// number of chunks the message was split into
+    total: u32,
+    /// the chunks received so far, by index
+    chunks: BTreeMap<u32, Vec<u8>>,
+    /// number of bytes received so far
+    size: usize,
+    /// when the first chunk arrived
+    started: Instant,
+    /// the acknowledgement of the last chunk, which the sender waits on, held until the message
+    /// is complete
+    ack: Option<A>,
+}
+
+/// What became of a wire message handed to [`Reassembler::receive`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Received<A> {
+    /// The wire message completed a message
+    Complete {
+        /// the whole message
+        message: Vec<u8>,
+        /// the acknowledgement the sender waits on, that of the message's last chunk, to be
+        /// given once the message is handled
+        ack: A,
+        /// the acknowledgement of the chunk which completed the message, if it is not the last
+        /// chunk, to be given at once
+        chunk_ack: Option<A>,
+    },
+    /// The wire message is a chunk of a message still missing chunks, and was kept
+    Pending {
+        /// the acknowledgement of the chunk, to be given at once, unless it is that of the last
+        /// chunk, which is held until the message is complete
+        chunk_ack: Option<A>,
+    },
+    /// The wire message was malformed, corrupt, a duplicate, or over the limits, and was dropped
+    /// without an acknowledgement
+    Dropped,
+}
+
+/// Reassembles chunked messages, discarding those not completed within
+/// [`CHUNK_REASSEMBLY_TIMEOUT_SEC`], and bounding the messages held at once by
+/// [`CHUNK_REASSEMBLY_MAX_BYTES`] and [`CHUNK_REASSEMBLY_MAX_PENDING_PER_PEER`].
+///
+/// `A` is the acknowledgement of a wire message, e.g. the response channel of a direct request.
+/// The acknowledgement of a message's last chunk, the only one its sender waits on, is only
+/// handed back once the whole message has been reassembled; dropping it (with the message, on a
+/// timeout or an eviction) lets the sender know the message was not delivered.
+pub struct Reassembler<A = ()> {
+    /// messages still missing chunks, by id
+    partial: HashMap<Digest, PartialMessage<A>>,
+    /// how long a partial message is kept around
+    timeout: Duration,
+    /// maximum number of bytes of all partial messages together
+    max_bytes: usize,
+    /// maximum number of partial messages started by a single peer
+    max_pending_per_peer: usize,
+}
+
+impl<A> Default for Reassembler<A> {
+    fn default() -> Self {
+        Self::new(
+            Duration::from_secs(CHUNK_REASSEMBLY_TIMEOUT_SEC),
+            CHUNK_REASSEMBLY_MAX_BYTES,
+            CHUNK_REASSEMBLY_MAX_PENDING_PER_PEER,
+        )
+    }
+}
+
+impl<A> Reassembler<A> {
+    /// Create a reassembler with the given limits
+    #[must_use]
+    pub fn new(timeout: Duration, max_bytes: usize, max_pending_per_peer: usize) -> Self {
+        Self {
+            partial: HashMap::new(),
+            timeout,
+            max_bytes,
+            max_pending_per_peer: max_pending_per_peer.max(1),
+        }
+    }
+
+    /// Number of messages still missing chunks
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.partial.len()
+    }
+
+    /// Number of bytes held for messages still missing chunks
+    #[must_use]
+    pub fn pending_bytes(&self) -> usize {
+        self.partial.values().map(|partial| partial.size).sum()
+    }
+
+    /// Handle a wire message received from `peer`, whose acknowledgement is `ack`
+    pub fn receive(&mut self, peer: PeerId, wire: &[u8], ack: A) -> Received<A> {
+        let now = Instant::now();
+        let timeout = self.timeout;
+        self.partial.retain(|id, partial| {
+            let live = now.duration_since(partial.started) < timeout;
+            if !live {
+                warn!(
+                    "Timed out reassembling message {:?}, received {} of {} chunks",
+                    id,
+                    partial.chunks.len(),
+                    partial.total
+                );
+            }
+            live
+        });
+
+        let (id, index, total, checksum, data) = match deserialize_msg::<Envelope>(wire) {
+            Ok(Envelope::Whole(message)) => {
+                return Received::Complete {
+                    message,
+                    ack,
+                    chunk_ack: None,
+                }
+            }
+            Ok(Envelope::Chunk {
+                id,
+                index,
+                total,
+                checksum,
+                data,
+            }) => (id, index, total, checksum, data),
+            Err(e) => {
+                warn!("Failed to deserialize wire message: {:?}", e);
+                return Received::Dropped;
+            }
+        };
+        if index >= total || *blake3::hash(&data).as_bytes() != checksum {
+            warn!("Dropping corrupt chunk {} of message {:?}", index, id);
+            return Received::Dropped;
+        }
+
+        if !self.partial.contains_key(&id) {
+            self.make_room_for(peer);
+            self.partial.insert(
+                id,
+                PartialMessage {
+                    peer,
+                    total,
+                    chunks: BTreeMap::new(),
+                    size: 0,
+                    started: now,
+                    ack: None,
+                },
+            );
+        }
+        let Some(partial) = self.partial.get_mut(&id) else {
+            return Received::Dropped;
+        };
+        if partial.total != total || partial.chunks.contains_key(&index) {
+            debug!(
+                "Dropping duplicate or inconsistent chunk {} of {:?}",
+                index, id
+            );
+            return Received::Dropped;
+        }
+        partial.size += data.len();
+        if partial.size > MAX_GOSSIP_MSG_SIZE.min(self.max_bytes) {
+            warn!(
+                "Message {:?} exceeds the maximum message size, dropping it",
+                id
+            );
+            self.partial.remove(&id);
+            return Received::Dropped;
+        }
+        partial.chunks.insert(index, data);
+        let last = index + 1 == total;
+        let chunk_ack = if last {
+            partial.ack = Some(ack);
+            None
+        } else {
+            Some(ack)
+        };
+        if partial.chunks.len() < total as usize {
+            self.evict_over_budget();
+            return if self.partial.contains_key(&id) {
+                Received::Pending { chunk_ack }
+            } else {
+                Received::Dropped
+            };
+        }
+
+        let Some(partial) = self.partial.remove(&id) else {
+            return Received::Dropped;
+        };
+        let message: Vec<u8> = partial.chunks.into_values().flatten().collect();
+        match partial.ack {
+            Some(ack) if *blake3::hash(&message).as_bytes() == id => Received::Complete {
+                message,
+                ack,
+                chunk_ack,
+            },
+            _ => {
+                warn!("Reassembled message does not match its id {:?}", id);
+                Received::Dropped
+            }
+        }
+    }
+
+    /// Evict the oldest partial message of `peer` if it is at its limit of partial messages, to
+    /// make room for a new one
+    fn make_room_for(&mut self, peer: PeerId) {
+        let started_by_peer = self
+            .partial
+            .values()
+            .filter(|partial| partial.peer == peer)
+            .count();
+        if started_by_peer < self.max_pending_per_peer {
+            return;
+        }
+        if let Some(oldest) = self
+            .partial
+            .iter()
+            .filter(|(_, partial)| partial.peer == peer)
+            .min_by_key(|(_, partial)| partial.started)
+            .map(|(id, _)| *id)
+        {
+            warn!(
+                "Peer {:?} has too many partial messages, evicting {:?}",
+                peer, oldest
+            );
+            self.partial.remove(&oldest);
+        }
+    }
+
+    /// Evict partial messages while all of them together exceed the byte budget, the oldest of
+    /// the peer holding the most bytes first, so a peer flooding chunks only evicts its own
+    fn evict_over_budget(&mut self) {
+        while self.pending_bytes() > self.max_bytes {
+            let mut bytes_by_peer: HashMap<PeerId, usize> = HashMap::new();
+            for partial in self.partial.values() {
+                *bytes_by_peer.entry(partial.peer).or_default() += partial.size;
+            }
+            let Some(peer) = bytes_by_peer
+                .into_iter()
+                .max_by_key(|(_, bytes)| *bytes)
+                .map(|(peer, _)| peer)
+            else {
+                return;
+            };
+            let Some(oldest) = self
+                .partial
+                .iter()
+                .filter(|(_, partial)| partial.peer == peer)
+                .min_by_key(|(_, partial)| partial.started)
+                .map(|(id, _)| *id)
+            else {
+                return;
+            };
+            warn!(
+                "Partial messages exceed {} bytes, evicting {:?} of peer {:?}",
+                self.max_bytes, oldest, peer
+            );
+            self.partial.remove(&oldest);
+        }
+    }
+}
@@ -3,6 +3,7 @@ use libp2p::{
     gossipsub::IdentTopic as Topic,
     identify::{Behaviour as IdentifyBehaviour, Event as IdentifyEvent},
     request_response::ResponseChannel,
+    swarm::behaviour::toggle::Toggle,
     Multiaddr,
 };
 use libp2p_identity::PeerId;
@@ -36,6 +37,11 @@ pub struct NetworkDef {
     #[debug(skip)]
     gossipsub: GossipBehaviour,
 
+    /// purpose: broadcasting messages of the topic only a committee subscribes to, on a mesh
+    /// tuned to the committee, if that topic is carried apart from the others
+    #[debug(skip)]
+    committee_gossipsub: Toggle<GossipBehaviour>,
+
     /// purpose: peer routing
     /// purpose: storing pub key <-> peer id bijection
     #[debug(skip)]
@@ -55,12 +61,14 @@ impl NetworkDef {
     #[must_use]
     pub fn new(
         gossipsub: GossipBehaviour,
+        committee_gossipsub: Option<GossipBehaviour>,
         dht: DHTBehaviour,
         identify: IdentifyBehaviour,
         request_response: DMBehaviour,
     ) -> NetworkDef {
         Self {
             gossipsub,
+            committee_gossipsub: committee_gossipsub.into(),
             dht,
             identify,
             request_response,
@@ -84,19 +92,51 @@ impl NetworkDef {
 
 /// Gossip functions
 impl NetworkDef {
+    /// The gossip behaviour carrying topic `t`
+    fn gossip_carrying(&mut self, t: &str) -> &mut GossipBehaviour {
+        match self.committee_gossipsub.as_mut() {
+            Some(committee_gossipsub) if committee_gossipsub.carries(t) => committee_gossipsub,
+            _ => &mut self.gossipsub,
+        }
+    }
+
     /// Publish a given gossip
     pub fn publish_gossip(&mut self, topic: Topic, contents: Vec<u8>) {
-        self.gossipsub.publish_gossip(topic, contents);
+        self.gossip_carrying(&topic.to_string())
+            .publish_gossip(topic, contents);
     }
 
     /// Subscribe to a given topic
     pub fn subscribe_gossip(&mut self, t: &str) {
-        self.gossipsub.subscribe_gossip(t);
+        self.gossip_carrying(t).subscribe_gossip(t);
     }
 
     /// Unsubscribe from a given topic
     pub fn unsubscribe_gossip(&mut self, t: &str) {
-        self.gossipsub.unsubscribe_gossip(t);
+        self.gossip_carrying(t).unsubscribe_gossip(t);
+    }
+
+    /// Admit connections only from `peers` and the bootstrap nodes
+    pub fn enforce_allowlist(&mut self, peers: Vec<PeerId>) {
+        if let Some(committee_gossipsub) = self.committee_gossipsub.as_mut() {
+            committee_gossipsub.enforce_allowlist(peers.clone());
+        }
+        self.gossipsub.enforce_allowlist(peers);
+    }
+
+    /// Admit connections only from `reachable` peers, or from any peer on the allowlist again
+    /// if `reachable` is `None`
+    pub fn set_partition(&mut self, reachable: Option<Vec<PeerId>>) {
+        if let Some(committee_gossipsub) = self.committee_gossipsub.as_mut() {
+            committee_gossipsub.set_partition(reachable.clone());
+        }
+        self.gossipsub.set_partition(reachable);
+    }
+
+    /// Whether a connection from `peer` is admitted
+    #[must_use]
+    pub fn admits(&self, peer: &PeerId) -> bool {
+        self.gossipsub.admits(peer)
     }
 }
 
@@ -132,13 +172,20 @@ impl NetworkDef {
 
 /// Request/response functions
 impl NetworkDef {
-    /// Add a direct request for a given peer
-    pub fn add_direct_request(&mut self, peer_id: PeerId, data: Vec<u8>, retry_count: u8) {
+    /// Add a direct request for a given peer, notifying `notify` once it is acknowledged
+    pub fn add_direct_request(
+        &mut self,
+        peer_id: PeerId,
+        data: Vec<u8>,
+        retry_count: u8,
+        notify: Option<Sender<()>>,
+    ) {
         let request = DMRequest {
             peer_id,
             data,
             backoff: ExponentialBackoff::default(),
             retry_count,
+            notify,
         };
         self.request_response.add_direct_request(request);
     }
@@ -7,6 +7,7 @@ use libp2p::{
     swarm::DialError,
     TransportError,
 };
+use libp2p_identity::PeerId;
 use snafu::Snafu;
 use std::fmt::{Debug, Display};
 
@@ -52,6 +53,14 @@ pub enum NetworkError {
     NoKnownPeers,
 }
 
+/// Cause of a connection denied because the allowlist is enforced and the peer is not on it
+#[derive(Debug, Snafu)]
+#[snafu(display("peer {peer} is not on the allowlist"))]
+pub struct NotAllowlisted {
+    /// the peer the connection is from
+    pub peer: PeerId,
+}
+
 /// Error enum for querying store
 /// because for some reason, [`libp2p::kad::GetRecordError`]
 /// does not derive `Error`
@@ -103,3 +112,11 @@ impl Display for GetRecordWrapperError {
 }
 
 impl std::error::Error for GetRecordWrapperError {}
+
+/// Cause of a connection denied because the node is partitioned from the peer
+#[derive(Debug, Snafu)]
+#[snafu(display("peer {peer} is across the partition"))]
+pub struct Partitioned {
+    /// the peer the connection is from
+    pub peer: PeerId,
+}
@@ -1,24 +1,31 @@
+/// the peers a node admits connections from, in allowlist mode
+pub mod allowlist;
 /// networking behaviours wrapping libp2p's behaviours
 pub mod behaviours;
+/// splitting of messages larger than the maximum wire message size, and their reassembly
+pub mod chunking;
 /// defines the swarm and network definition (internal)
 mod def;
 /// libp2p network errors
 pub mod error;
 /// functionality of a libp2p network node
 mod node;
+/// peers a node learned about, persisted across restarts
+pub mod peer_store;
 
 pub use self::{
     def::NetworkDef,
     error::NetworkError,
     node::{
-        network_node_handle_error, MeshParams, NetworkNode, NetworkNodeConfig,
+        network_node_handle_error, MeshParams, MeshTuning, NetworkNode, NetworkNodeConfig,
         NetworkNodeConfigBuilder, NetworkNodeConfigBuilderError, NetworkNodeHandle,
-        NetworkNodeHandleError,
+        NetworkNodeHandleError, QuicParams, MESH_REPORT_INTERVAL,
     },
 };
 
 use self::behaviours::{dht::DHTEvent, direct_message::DMEvent, gossip::GossipEvent};
 use bincode::Options;
+use bytes::Bytes;
 use futures::channel::oneshot::Sender;
 use hotshot_utils::bincode::bincode_opts;
 use libp2p::{
@@ -27,6 +34,7 @@ use libp2p::{
     gossipsub::TopicHash,
     identify::Event as IdentifyEvent,
     identity::Keypair,
+    multiaddr::Protocol,
     quic,
     request_response::ResponseChannel,
     Multiaddr, Transport,
@@ -107,7 +115,7 @@ pub enum ClientRequest {
     /// kill the swarm
     Shutdown,
     /// broadcast a serialized message
-    GossipMsg(String, Vec<u8>),
+    GossipMsg(String, Bytes),
     /// subscribe to a topic
     Subscribe(String, Option<Sender<()>>),
     /// unsubscribe from a topic
@@ -117,9 +125,11 @@ pub enum ClientRequest {
         /// peer id
         pid: PeerId,
         /// msg contents
-        contents: Vec<u8>,
+        contents: Bytes,
         /// number of retries
         retry_count: u8,
+        /// notified when the recipient acknowledges the message
+        notify: Option<Sender<()>>,
     },
     /// client request to send a direct reply to a message
     DirectResponse(ResponseChannel<Vec<u8>>, Vec<u8>),
@@ -127,6 +137,12 @@ pub enum ClientRequest {
     Prune(PeerId),
     /// add vec of known peers or addresses
     AddKnownPeers(Vec<(Option<PeerId>, Multiaddr)>),
+    /// Admit connections only from these peers and the bootstrap nodes, closing the connections
+    /// to any other peer
+    EnforceAllowlist(Vec<PeerId>),
+    /// Admit connections only from these peers, closing the connections to any other peer, or
+    /// lift the partition if `None`
+    Partition(Option<Vec<PeerId>>),
     /// Ignore peers. Only here for debugging purposes.
     /// Allows us to have nodes that are never pruned
     IgnorePeers(Vec<PeerId>),
@@ -195,19 +211,53 @@ pub fn gen_multiaddr(port: u16) -> Multiaddr {
     build_multiaddr!(Ip4([0, 0, 0, 0]), Udp(port), QuicV1)
 }
 
+/// Whether other peers may dial `addr`, i.e. it has no unspecified IP address such as `0.0.0.0`
+#[must_use]
+pub fn is_routable(addr: &Multiaddr) -> bool {
+    !addr.iter().any(|protocol| match protocol {
+        Protocol::Ip4(ip) => ip.is_unspecified(),
+        Protocol::Ip6(ip) => ip.is_unspecified(),
+        _ => false,
+    })
+}
+
+/// The addresses a node bound to `bound` tells other peers to reach it at: the routable ones of
+/// `advertised`, or `bound` if none is and it is routable itself
+#[must_use]
+pub fn routable_addrs(advertised: &[Multiaddr], bound: &Multiaddr) -> Vec<Multiaddr> {
+    let mut addrs: Vec<_> = advertised
+        .iter()
+        .filter(|addr| is_routable(addr))
+        .cloned()
+        .collect();
+    if addrs.is_empty() && is_routable(bound) {
+        addrs.push(bound.clone());
+    }
+    addrs
+}
+
 /// `BoxedTransport` is a type alias for a boxed tuple containing a `PeerId` and a `StreamMuxerBox`.
 ///
 /// This type is used to represent a transport in the libp2p network framework. The `PeerId` is a unique identifier for each peer in the network, and the `StreamMuxerBox` is a type of multiplexer that can handle multiple substreams over a single connection.
 type BoxedTransport = Boxed<(PeerId, StreamMuxerBox)>;
 
-/// Generate authenticated transport
+/// Generate authenticated transport, tuned by `quic_params`
 /// # Errors
 /// could not sign the quic key with `identity`
 #[instrument(skip(identity))]
-pub async fn gen_transport(identity: Keypair) -> Result<BoxedTransport, NetworkError> {
+pub async fn gen_transport(
+    identity: Keypair,
+    quic_params: &QuicParams,
+) -> Result<BoxedTransport, NetworkError> {
     let quic_transport = {
         let mut config = quic::Config::new(&identity);
-        config.handshake_timeout = std::time::Duration::from_secs(20);
+        config.handshake_timeout = quic_params.handshake_timeout;
+        config.max_idle_timeout =
+            u32::try_from(quic_params.max_idle_timeout.as_millis()).unwrap_or(u32::MAX);
+        config.keep_alive_interval = quic_params.keep_alive_interval;
+        config.max_concurrent_stream_limit = quic_params.max_concurrent_stream_limit;
+        config.max_stream_data = quic_params.max_stream_data;
+        config.max_connection_data = quic_params.max_connection_data;
         QuicTransport::new(config)
     };
 
@@ -7,16 +7,20 @@ mod handle;
 
 pub use self::{
     config::{
-        MeshParams, NetworkNodeConfig, NetworkNodeConfigBuilder, NetworkNodeConfigBuilderError,
+        MeshParams, MeshTuning, NetworkNodeConfig, NetworkNodeConfigBuilder,
+        NetworkNodeConfigBuilderError, QuicParams, MESH_REPORT_INTERVAL,
     },
     handle::{network_node_handle_error, NetworkNodeHandle, NetworkNodeHandleError},
 };
 
 use super::{
+    allowlist::PeerAllowlist,
     behaviours::gossip::GossipBehaviour,
+    chunking::{self, Reassembler, Received, CHUNK_FRAMING_OVERHEAD},
     error::{GossipsubBuildSnafu, GossipsubConfigSnafu, NetworkError, TransportSnafu},
-    gen_transport, BoxedTransport, ClientRequest, NetworkDef, NetworkEvent, NetworkEventInternal,
-    NetworkNodeType,
+    gen_transport,
+    peer_store::PeerStore,
+    BoxedTransport, ClientRequest, NetworkDef, NetworkEvent, NetworkEventInternal, NetworkNodeType,
 };
 
 use crate::network::behaviours::{
@@ -30,7 +34,7 @@ use async_compatibility_layer::{
     channel::{unbounded, UnboundedReceiver, UnboundedRecvError, UnboundedSender},
 };
 use futures::{select, FutureExt, StreamExt};
-use hotshot_constants::KAD_DEFAULT_REPUB_INTERVAL_SEC;
+use hotshot_constants::{DEFAULT_MAX_WIRE_MESSAGE_SIZE, KAD_DEFAULT_REPUB_INTERVAL_SEC};
 use libp2p::{core::transport::ListenerId, StreamProtocol};
 use libp2p::{
     gossipsub::{
@@ -45,6 +49,7 @@ use libp2p::{
     kad::{store::MemoryStore, Behaviour, Config},
     request_response::{
         Behaviour as RequestResponse, Config as RequestResponseConfig, ProtocolSupport,
+        ResponseChannel,
     },
     swarm::SwarmEvent,
     Multiaddr, Swarm, SwarmBuilder,
@@ -56,11 +61,11 @@ use std::{
     collections::{HashMap, HashSet},
     iter,
     num::{NonZeroU32, NonZeroUsize},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tracing::{debug, error, info, info_span, instrument, trace, warn, Instrument};
 
-/// Maximum size of a message
+/// Maximum size of a message, once reassembled from its chunks
 pub const MAX_GOSSIP_MSG_SIZE: usize = 200_000_000;
 
 /// Wrapped num of connections
@@ -69,6 +74,63 @@ pub const ESTABLISHED_LIMIT: NonZeroU32 =
 /// Number of connections to a single peer before logging an error
 pub const ESTABLISHED_LIMIT_UNWR: u32 = 10;
 
+/// Prefix of the gossipsub protocol carrying the topic only a committee subscribes to
+const COMMITTEE_GOSSIPSUB_PROTOCOL_PREFIX: &str = "hotshot-committee-meshsub";
+
+/// Build a gossipsub behaviour with a mesh of `params`, speaking the protocol of
+/// `protocol_id_prefix`, or the default gossipsub protocol if `None`
+fn build_gossipsub(
+    identity: &Keypair,
+    params: &MeshParams,
+    max_message_size: usize,
+    protocol_id_prefix: Option<&'static str>,
+) -> Result<Gossipsub, NetworkError> {
+    // Use the hash of the message's contents as the ID
+    // Use blake3 for much paranoia at very high speeds
+    let message_id_fn = |message: &GossipsubMessage| {
+        let hash = blake3::hash(&message.data);
+        MessageId::from(hash.as_bytes().to_vec())
+    };
+
+    // Create a custom gossipsub
+    let mut gossipsub_config = GossipsubConfigBuilder::default();
+    gossipsub_config
+        .opportunistic_graft_ticks(3)
+        .heartbeat_interval(Duration::from_secs(1))
+        // Force all messages to have valid signatures
+        .validation_mode(ValidationMode::Strict)
+        .history_gossip(50)
+        .mesh_n_high(params.mesh_n_high)
+        .mesh_n_low(params.mesh_n_low)
+        .mesh_outbound_min(params.mesh_outbound_min)
+        .mesh_n(params.mesh_n)
+        .history_length(500)
+        // Larger messages are chunked before they reach gossipsub
+        .max_transmit_size(max_message_size + CHUNK_FRAMING_OVERHEAD)
+        // Use the (blake3) hash of a message as its ID
+        .message_id_fn(message_id_fn);
+    if let Some(protocol_id_prefix) = protocol_id_prefix {
+        gossipsub_config.protocol_id_prefix(protocol_id_prefix);
+    }
+    let gossipsub_config = gossipsub_config.build().map_err(|s| {
+        GossipsubConfigSnafu {
+            message: s.to_string(),
+        }
+        .build()
+    })?;
+
+    // - Build a gossipsub network behavior
+    Gossipsub::new(
+        // TODO do we even need this?
+        // <https://github.com/EspressoSystems/hotshot/issues/42>
+        // if messages are signed at the the consensus level AND the network
+        // level (noise), this feels redundant.
+        MessageAuthenticity::Signed(identity.clone()),
+        gossipsub_config,
+    )
+    .map_err(|s| GossipsubBuildSnafu { message: s }.build())
+}
+
 /// Network definition
 #[derive(custom_debug::Debug)]
 pub struct NetworkNode {
@@ -83,6 +145,20 @@ pub struct NetworkNode {
     config: NetworkNodeConfig,
     /// the listener id we are listening on, if it exists
     listener_id: Option<ListenerId>,
+    /// maximum size of a single wire message
+    max_message_size: usize,
+    /// chunks of gossip messages awaiting the rest of their message
+    #[debug(skip)]
+    gossip_chunks: Reassembler,
+    /// chunks of direct messages awaiting the rest of their message, with the response channel of
+    /// their last chunk
+    #[debug(skip)]
+    direct_chunks: Reassembler<ResponseChannel<Vec<u8>>>,
+    /// the peers this node learned about, saved across restarts
+    #[debug(skip)]
+    peer_store: Option<PeerStore>,
+    /// tuning of the gossipsub mesh to the size of the network, if enabled
+    mesh_tuning: Option<MeshTuning>,
 }
 
 impl NetworkNode {
@@ -96,6 +172,18 @@ impl NetworkNode {
         self.swarm.connected_peers().copied().collect()
     }
 
+    /// Close the connections to the peers no longer admitted
+    fn disconnect_unadmitted(&mut self) {
+        for pid in self.connected_pids() {
+            if !self.swarm.behaviour().admits(&pid) && self.swarm.disconnect_peer_id(pid).is_err() {
+                error!(
+                    "Peer {:?} could not disconnect from pid {:?}",
+                    self.peer_id, pid
+                );
+            }
+        }
+    }
+
     /// starts the swarm listening on `listen_addr`
     /// and optionally dials into peer `known_peer`
     /// returns the address the swarm is listening upon
@@ -114,6 +202,39 @@ impl NetworkNode {
         Ok(addr)
     }
 
+    /// Also listen on `listen_addr`, next to the address given to
+    /// [`NetworkNode::start_listen`]. Returns the address actually listened on.
+    /// # Errors
+    /// If the transport cannot listen on `listen_addr`
+    #[instrument(skip(self))]
+    pub async fn start_listen_additional(
+        &mut self,
+        listen_addr: Multiaddr,
+    ) -> Result<Multiaddr, NetworkError> {
+        let listener_id = self.swarm.listen_on(listen_addr).context(TransportSnafu)?;
+        let addr = loop {
+            if let Some(SwarmEvent::NewListenAddr {
+                listener_id: id,
+                address,
+            }) = self.swarm.next().await
+            {
+                if id == listener_id {
+                    break address;
+                }
+            }
+        };
+        info!("peerid {:?} also started on addr: {:?}", self.peer_id, addr);
+        Ok(addr)
+    }
+
+    /// Tell peers they can reach this node at `addr`, an address it is reachable at but not
+    /// bound to
+    #[instrument(skip(self))]
+    pub fn advertise(&mut self, addr: Multiaddr) {
+        info!("peerid {:?} advertises addr: {:?}", self.peer_id, addr);
+        self.swarm.add_external_address(addr);
+    }
+
     /// initialize the DHT with known peers
     /// add the peers to kademlia and then
     /// the `spawn_listeners` function
@@ -162,18 +283,18 @@ impl NetworkNode {
         };
         let peer_id = PeerId::from(identity.public());
         debug!(?peer_id);
-        let transport: BoxedTransport = gen_transport(identity.clone()).await?;
+        let transport: BoxedTransport =
+            gen_transport(identity.clone(), &config.quic_params).await?;
         trace!("Launched network transport");
+        let max_message_size = config
+            .max_message_size
+            .unwrap_or(DEFAULT_MAX_WIRE_MESSAGE_SIZE)
+            .max(1);
         // Generate the swarm
         let mut swarm: Swarm<NetworkDef> = {
-            // Use the hash of the message's contents as the ID
-            // Use blake3 for much paranoia at very high speeds
-            let message_id_fn = |message: &GossipsubMessage| {
-                let hash = blake3::hash(&message.data);
-                MessageId::from(hash.as_bytes().to_vec())
-            };
-
-            let params = if let Some(ref params) = config.mesh_params {
+            let params = if let Some(ref tuning) = config.mesh_tuning {
+                tuning.params().clone()
+            } else if let Some(ref params) = config.mesh_params {
                 params.clone()
             } else {
                 // NOTE this should most likely be a builder pattern
@@ -200,40 +321,24 @@ impl NetworkNode {
                     },
                 }
             };
-
-            // Create a custom gossipsub
-            let gossipsub_config = GossipsubConfigBuilder::default()
-                .opportunistic_graft_ticks(3)
-                .heartbeat_interval(Duration::from_secs(1))
-                // Force all messages to have valid signatures
-                .validation_mode(ValidationMode::Strict)
-                .history_gossip(50)
-                .mesh_n_high(params.mesh_n_high)
-                .mesh_n_low(params.mesh_n_low)
-                .mesh_outbound_min(params.mesh_outbound_min)
-                .mesh_n(params.mesh_n)
-                .history_length(500)
-                .max_transmit_size(MAX_GOSSIP_MSG_SIZE)
-                // Use the (blake3) hash of a message as its ID
-                .message_id_fn(message_id_fn)
-                .build()
-                .map_err(|s| {
-                    GossipsubConfigSnafu {
-                        message: s.to_string(),
-                    }
-                    .build()
-                })?;
-
-            // - Build a gossipsub network behavior
-            let gossipsub: Gossipsub = Gossipsub::new(
-                // TODO do we even need this?
-                // <https://github.com/EspressoSystems/hotshot/issues/42>
-                // if messages are signed at the the consensus level AND the network
-                // level (noise), this feels redundant.
-                MessageAuthenticity::Signed(identity.clone()),
-                gossipsub_config,
-            )
-            .map_err(|s| GossipsubBuildSnafu { message: s }.build())?;
+            let gossipsub = build_gossipsub(&identity, &params, max_message_size, None)?;
+
+            // the topic only a committee subscribes to gets a mesh of its own, tuned to the
+            // committee, on a protocol of its own so the two meshes do not mix
+            let committee_gossipsub = config
+                .mesh_tuning
+                .as_ref()
+                .and_then(MeshTuning::committee_topic)
+                .map(|(topic, params)| {
+                    build_gossipsub(
+                        &identity,
+                        params,
+                        max_message_size,
+                        Some(COMMITTEE_GOSSIPSUB_PROTOCOL_PREFIX),
+                    )
+                    .map(|gossipsub| (topic.to_string(), gossipsub))
+                })
+                .transpose()?;
 
             //   Build a identify network behavior needed for own
             //   node connection information
@@ -278,8 +383,14 @@ impl NetworkNode {
                     rrconfig,
                 );
 
+            // the bootstrap nodes are always admitted
+            let allowlist =
+                PeerAllowlist::new(config.to_connect_addrs.iter().filter_map(|(peer, _)| *peer));
             let network = NetworkDef::new(
-                GossipBehaviour::new(gossipsub),
+                GossipBehaviour::new(gossipsub, allowlist.clone()),
+                committee_gossipsub.map(|(topic, gossipsub)| {
+                    GossipBehaviour::new(gossipsub, allowlist).dedicated_to(topic)
+                }),
                 DHTBehaviour::new(
                     kadem,
                     peer_id,
@@ -314,12 +425,33 @@ impl NetworkNode {
             }
         }
 
+        // reconnect to the peers known before a restart, the most reliable first
+        let peer_store = config.peer_store_path.as_ref().map(PeerStore::load);
+        if let Some(peer_store) = &peer_store {
+            let mut bs_nodes = HashMap::<PeerId, HashSet<Multiaddr>>::new();
+            for (peer, known) in peer_store.best_peers() {
+                if peer == peer_id {
+                    continue;
+                }
+                for addr in &known.addrs {
+                    swarm.behaviour_mut().add_address(&peer, addr.clone());
+                }
+                bs_nodes.insert(peer, known.addrs.into_iter().collect());
+            }
+            swarm.behaviour_mut().dht.add_bootstrap_nodes(bs_nodes);
+        }
+
         Ok(Self {
             identity,
             peer_id,
             swarm,
+            mesh_tuning: config.mesh_tuning.clone(),
             config,
             listener_id: None,
+            max_message_size,
+            gossip_chunks: Reassembler::default(),
+            direct_chunks: Reassembler::default(),
+            peer_store,
         })
     }
 
@@ -384,6 +516,14 @@ impl NetworkNode {
                             retry_count,
                         );
                     }
+                    ClientRequest::EnforceAllowlist(peers) => {
+                        behaviour.enforce_allowlist(peers);
+                        self.disconnect_unadmitted();
+                    }
+                    ClientRequest::Partition(reachable) => {
+                        behaviour.set_partition(reachable);
+                        self.disconnect_unadmitted();
+                    }
                     ClientRequest::IgnorePeers(_peers) => {
                         // NOTE used by test with conductor only
                     }
@@ -391,11 +531,21 @@ impl NetworkNode {
                         if let Some(listener_id) = self.listener_id {
                             self.swarm.remove_listener(listener_id);
                         }
+                        if let Some(peer_store) = &mut self.peer_store {
+                            peer_store.save_or_warn();
+                        }
 
                         return Ok(true);
                     }
                     ClientRequest::GossipMsg(topic, contents) => {
-                        behaviour.publish_gossip(Topic::new(topic), contents);
+                        match chunking::split(&contents, self.max_message_size) {
+                            Ok(chunks) => {
+                                for chunk in chunks {
+                                    behaviour.publish_gossip(Topic::new(topic.clone()), chunk);
+                                }
+                            }
+                            Err(e) => error!("failed to chunk gossip message: {:?}", e),
+                        }
                     }
                     ClientRequest::Subscribe(t, chan) => {
                         behaviour.subscribe_gossip(&t);
@@ -417,9 +567,22 @@ impl NetworkNode {
                         pid,
                         contents,
                         retry_count,
+                        notify,
                     } => {
                         info!("pid {:?} adding direct request", self.peer_id);
-                        behaviour.add_direct_request(pid, contents, retry_count);
+                        match chunking::split(&contents, self.max_message_size) {
+                            Ok(mut chunks) => {
+                                // only the acknowledgement of the last chunk is reported
+                                let last = chunks.pop();
+                                for chunk in chunks {
+                                    behaviour.add_direct_request(pid, chunk, retry_count, None);
+                                }
+                                if let Some(last) = last {
+                                    behaviour.add_direct_request(pid, last, retry_count, notify);
+                                }
+                            }
+                            Err(e) => error!("failed to chunk direct message: {:?}", e),
+                        }
                     }
                     ClientRequest::DirectResponse(chan, msg) => {
                         behaviour.add_direct_response(chan, msg);
@@ -444,6 +607,32 @@ impl NetworkNode {
         Ok(false)
     }
 
+    /// Report the gossipsub mesh parameters suited to the peers connected now, if the mesh is
+    /// tuned to the network and they differ from those it was built with
+    fn retune_mesh(&mut self) {
+        let connected = self.num_connected();
+        let Some(tuning) = &mut self.mesh_tuning else {
+            return;
+        };
+        let Some(suited) = tuning.observe(connected, Instant::now()) else {
+            return;
+        };
+        if suited == *tuning.params() {
+            info!(
+                "gossipsub mesh suits the {} connected peers again",
+                connected
+            );
+        } else {
+            warn!(
+                "gossipsub mesh built with {:?} for the network expected, but {:?} would suit \
+                 the {} connected peers",
+                tuning.params(),
+                suited,
+                connected
+            );
+        }
+    }
+
     /// event handler for events emited from the swarm
     #[allow(clippy::type_complexity)]
     #[instrument(skip(self))]
@@ -473,6 +662,11 @@ impl NetworkNode {
                 } else {
                     info!("peerid {:?} connection is established to {:?} with endpoint {:?} with concurrent dial errors {:?}. {:?} connections left", self.peer_id, peer_id, endpoint, concurrent_dial_errors, num_established);
                 }
+                if let Some(peer_store) = &mut self.peer_store {
+                    peer_store.record_connected(&peer_id);
+                    peer_store.save_if_due();
+                }
+                self.retune_mesh();
             }
             SwarmEvent::ConnectionClosed {
                 connection_id: _,
@@ -489,6 +683,7 @@ impl NetworkNode {
                 } else {
                     info!("peerid {:?} connection is closed to {:?} with endpoint {:?}. {:?} connections left. Cause: {:?}", self.peer_id, peer_id, endpoint, num_established, cause);
                 }
+                self.retune_mesh();
             }
             SwarmEvent::Dialing {
                 peer_id,
@@ -550,22 +745,58 @@ impl NetworkNode {
                             for addr in listen_addrs.iter().collect::<HashSet<_>>() {
                                 behaviour.dht.add_address(&peer_id, addr.clone());
                             }
+                            if let Some(peer_store) = &mut self.peer_store {
+                                peer_store.record_addresses(peer_id, listen_addrs);
+                                peer_store.save_if_due();
+                            }
                         }
                         None
                     }
                     NetworkEventInternal::GossipEvent(e) => match e {
-                        GossipEvent::GossipMsg(data, topic) => {
-                            Some(NetworkEvent::GossipMsg(data, topic))
+                        GossipEvent::GossipMsg(data, topic, source) => {
+                            match self.gossip_chunks.receive(source, &data, ()) {
+                                Received::Complete { message, .. } => {
+                                    Some(NetworkEvent::GossipMsg(message, topic))
+                                }
+                                Received::Pending { .. } | Received::Dropped => None,
+                            }
                         }
                     },
-                    NetworkEventInternal::DMEvent(e) => Some(match e {
+                    NetworkEventInternal::DMEvent(e) => match e {
                         DMEvent::DirectRequest(data, pid, chan) => {
-                            NetworkEvent::DirectRequest(data, pid, chan)
+                            match self.direct_chunks.receive(pid, &data, chan) {
+                                // the client acknowledges the message by responding on the
+                                // channel of its last chunk, the one the sender waits on
+                                Received::Complete {
+                                    message,
+                                    ack,
+                                    chunk_ack,
+                                } => {
+                                    if let Some(chunk_ack) = chunk_ack {
+                                        self.swarm
+                                            .behaviour_mut()
+                                            .add_direct_response(chunk_ack, Vec::new());
+                                    }
+                                    Some(NetworkEvent::DirectRequest(message, pid, ack))
+                                }
+                                Received::Pending { chunk_ack } => {
+                                    if let Some(chunk_ack) = chunk_ack {
+                                        self.swarm
+                                            .behaviour_mut()
+                                            .add_direct_response(chunk_ack, Vec::new());
+                                    }
+                                    None
+                                }
+                                // the channel is dropped unanswered, failing the request
+                                Received::Dropped => None,
+                            }
                         }
+                        // empty responses acknowledge chunks, and are not meant for the client
+                        DMEvent::DirectResponse(data, _) if data.is_empty() => None,
                         DMEvent::DirectResponse(data, pid) => {
-                            NetworkEvent::DirectResponse(data, pid)
+                            Some(NetworkEvent::DirectResponse(data, pid))
                         }
-                    }),
+                    },
                 };
 
                 if let Some(event) = maybe_event {
@@ -578,10 +809,14 @@ impl NetworkNode {
             }
             SwarmEvent::OutgoingConnectionError {
                 connection_id: _,
-                peer_id: _,
+                peer_id,
                 error,
             } => {
                 info!(?error, "OUTGOING CONNECTION ERROR, {:?}", error);
+                if let (Some(peer_store), Some(peer_id)) = (&mut self.peer_store, peer_id) {
+                    peer_store.record_failed_dial(&peer_id);
+                    peer_store.save_if_due();
+                }
             }
             SwarmEvent::IncomingConnectionError {
                 connection_id: _,
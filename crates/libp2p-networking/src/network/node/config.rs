@@ -1,7 +1,12 @@
 use crate::network::NetworkNodeType;
 use libp2p::{identity::Keypair, Multiaddr};
 use libp2p_identity::PeerId;
-use std::{collections::HashSet, num::NonZeroUsize, time::Duration};
+use std::{
+    collections::HashSet,
+    num::NonZeroUsize,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 /// replication factor for kademlia
 pub const DEFAULT_REPLICATION_FACTOR: Option<NonZeroUsize> = NonZeroUsize::new(20);
@@ -19,6 +24,13 @@ pub struct NetworkNodeConfig {
     /// address to bind to
     #[builder(default)]
     pub bound_addr: Option<Multiaddr>,
+    /// further addresses to bind to, e.g. in the other address family of a dual-stack node
+    #[builder(default)]
+    pub additional_bound_addrs: Vec<Multiaddr>,
+    /// addresses other peers reach this node at, when they differ from the bound ones, e.g. the
+    /// public address of a node bound to the unspecified address behind a NAT
+    #[builder(default)]
+    pub advertised_addrs: Vec<Multiaddr>,
     /// replication factor for entries in the DHT
     /// default is [`libp2p::kad::K_VALUE`] which is 20
     #[builder(setter(into, strip_option), default = "DEFAULT_REPLICATION_FACTOR")]
@@ -31,6 +43,10 @@ pub struct NetworkNodeConfig {
     #[builder(default)]
     /// parameters for gossipsub mesh network
     pub mesh_params: Option<MeshParams>,
+    /// tuning of the gossipsub mesh to the size of the network, which takes precedence over
+    /// `mesh_params`. default is None, to use those
+    #[builder(default)]
+    pub mesh_tuning: Option<MeshTuning>,
 
     /// list of addresses to connect to at initialization
     pub to_connect_addrs: HashSet<(Option<PeerId>, Multiaddr)>,
@@ -40,12 +56,63 @@ pub struct NetworkNodeConfig {
     /// expiratiry for records in DHT
     #[builder(default)]
     pub ttl: Option<Duration>,
+    /// maximum size of a single wire message, larger messages are split into chunks of this size.
+    /// default is [`hotshot_constants::DEFAULT_MAX_WIRE_MESSAGE_SIZE`]
+    #[builder(setter(into, strip_option), default)]
+    pub max_message_size: Option<usize>,
+    /// tuning of the QUIC transport
+    #[builder(default)]
+    pub quic_params: QuicParams,
+    /// file the peers this node learns about are saved to, and reconnected to on restart.
+    /// default is None, to not save them
+    #[builder(default)]
+    pub peer_store_path: Option<PathBuf>,
+    /// whether to admit connections only from the bootstrap nodes and the peers the keys of the
+    /// stake table resolve to, as they resolve. default is false, to admit any peer
+    #[builder(default)]
+    pub peer_allowlist: bool,
+}
+
+/// Tuning of the QUIC transport. The defaults are those of `libp2p-quic`, except for the
+/// handshake timeout. Links with a high bandwidth-delay product, such as cross-region links
+/// carrying large blocks, want larger windows.
+///
+/// The congestion controller is not part of these: `libp2p-quic` always uses the default of
+/// `quinn`, which is CUBIC.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuicParams {
+    /// time allowed for the QUIC handshake of a new connection
+    pub handshake_timeout: Duration,
+    /// a connection with no activity for this long is closed
+    pub max_idle_timeout: Duration,
+    /// interval between keep-alive packets on an idle connection, which must be shorter than
+    /// `max_idle_timeout` to keep connections open
+    pub keep_alive_interval: Duration,
+    /// maximum number of concurrent incoming streams per connection
+    pub max_concurrent_stream_limit: u32,
+    /// flow control window of a single stream, in bytes
+    pub max_stream_data: u32,
+    /// flow control window of a whole connection, in bytes
+    pub max_connection_data: u32,
+}
+
+impl Default for QuicParams {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: Duration::from_secs(20),
+            max_idle_timeout: Duration::from_secs(10),
+            keep_alive_interval: Duration::from_secs(5),
+            max_concurrent_stream_limit: 256,
+            max_stream_data: 10_000_000,
+            max_connection_data: 15_000_000,
+        }
+    }
 }
 
 /// NOTE: `mesh_outbound_min <= mesh_n_low <= mesh_n <= mesh_n_high`
 /// NOTE: `mesh_outbound_min <= self.config.mesh_n / 2`
 /// parameters fed into gossipsub controlling the structure of the mesh
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MeshParams {
     /// mesh_n_high from gossipsub
     pub mesh_n_high: usize,
@@ -67,3 +134,128 @@ impl Default for MeshParams {
         }
     }
 }
+
+/// Smallest degree of a tuned mesh, below which a message crosses too many hops
+const MIN_TUNED_MESH_N: usize = 4;
+/// Largest degree of a tuned mesh, above which peers mostly receive messages they already have
+const MAX_TUNED_MESH_N: usize = 12;
+
+impl MeshParams {
+    /// Parameters tuned to a network of `total_nodes`, following the heuristics of the gossipsub
+    /// specification:
+    /// - a message reaches every node in about `log(total_nodes) / log(mesh_n)` hops, so the
+    ///   degree `mesh_n` grows with `log2(total_nodes)`, from 4 to 12, around the default of 6,
+    ///   and never exceeds the number of other nodes
+    /// - `mesh_n_low` is two thirds of `mesh_n`, and `mesh_n_high` twice `mesh_n`, so the mesh
+    ///   is not rebuilt on every peer joining or leaving. A bootstrap node keeps every peer in its
+    ///   mesh.
+    /// - `mesh_outbound_min` is a third of `mesh_n`, as the default 2 is of 6, so that peers a
+    ///   node dialed itself hold part of its mesh against eclipse attacks
+    ///
+    /// Gossipsub applies the same parameters to every topic it carries, so a topic only a committee
+    /// subscribes to, such as the DA topic, is carried by a gossipsub of its own, tuned to the
+    /// committee size, see [`MeshTuning::with_committee_topic`].
+    #[must_use]
+    pub fn tuned(total_nodes: usize, node_type: NetworkNodeType) -> Self {
+        let peers = total_nodes.saturating_sub(1).max(1);
+        let log2_total_nodes = total_nodes.max(1).next_power_of_two().trailing_zeros() as usize;
+        let mesh_n = (log2_total_nodes + 2)
+            .clamp(MIN_TUNED_MESH_N, MAX_TUNED_MESH_N)
+            .min(peers);
+        let mesh_n_low = (mesh_n * 2 / 3).max(1);
+        let mesh_n_high = match node_type {
+            NetworkNodeType::Bootstrap => peers,
+            NetworkNodeType::Regular | NetworkNodeType::Conductor => (mesh_n * 2).min(peers),
+        };
+        Self {
+            mesh_n_high,
+            mesh_n_low,
+            mesh_outbound_min: (mesh_n / 3).min(mesh_n_low),
+            mesh_n,
+        }
+    }
+}
+
+/// Shortest time between two reports of the mesh parameters suited to the peers connected
+pub const MESH_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Tuning of the gossipsub mesh to the size of the network, see [`MeshParams::tuned`].
+///
+/// The network may turn out larger or smaller than expected, e.g. when nodes are down or join
+/// later. Gossipsub fixes its parameters once built though, and rebuilding it drops the meshes
+/// and subscriptions of every connection, so they are not adjusted at runtime: the parameters
+/// suited to the peers connected are reported instead, at most once every
+/// [`MESH_REPORT_INTERVAL`], for operators to size the network expected by.
+#[derive(Clone, Debug)]
+pub struct MeshTuning {
+    /// the type of the node
+    node_type: NetworkNodeType,
+    /// the parameters gossipsub is built with
+    params: MeshParams,
+    /// the topic only a committee subscribes to, and the parameters of the gossipsub carrying it
+    committee_topic: Option<(String, MeshParams)>,
+    /// the parameters reported last
+    reported: MeshParams,
+    /// when they were reported
+    reported_at: Option<Instant>,
+}
+
+impl MeshTuning {
+    /// Tune the mesh of a node of `node_type` to a network expected to have `total_nodes`
+    #[must_use]
+    pub fn new(total_nodes: usize, node_type: NetworkNodeType) -> Self {
+        let params = MeshParams::tuned(total_nodes, node_type);
+        Self {
+            node_type,
+            reported: params.clone(),
+            reported_at: None,
+            committee_topic: None,
+            params,
+        }
+    }
+
+    /// Carry `topic`, which only the `committee_size` members of a committee subscribe to, on a
+    /// gossipsub of its own with a mesh tuned to the committee rather than the whole network.
+    ///
+    /// Every node of the network must carry the topic the same way, or messages published to it
+    /// do not reach the nodes carrying it differently.
+    #[must_use]
+    pub fn with_committee_topic(mut self, topic: impl Into<String>, committee_size: usize) -> Self {
+        self.committee_topic = Some((
+            topic.into(),
+            MeshParams::tuned(committee_size, self.node_type),
+        ));
+        self
+    }
+
+    /// The parameters gossipsub is built with
+    #[must_use]
+    pub fn params(&self) -> &MeshParams {
+        &self.params
+    }
+
+    /// The topic carried on a gossipsub of its own, and the parameters that gossipsub is built with
+    #[must_use]
+    pub fn committee_topic(&self) -> Option<(&str, &MeshParams)> {
+        self.committee_topic
+            .as_ref()
+            .map(|(topic, params)| (topic.as_str(), params))
+    }
+
+    /// Observe the network this node sees at `now`, itself and its `connected_peers`.
+    /// Returns the parameters to report: those suited to it, when they changed since the last
+    /// report, and that was at least [`MESH_REPORT_INTERVAL`] ago.
+    pub fn observe(&mut self, connected_peers: usize, now: Instant) -> Option<MeshParams> {
+        let suited = MeshParams::tuned(connected_peers + 1, self.node_type);
+        if suited == self.reported
+            || self
+                .reported_at
+                .is_some_and(|at| now.saturating_duration_since(at) < MESH_REPORT_INTERVAL)
+        {
+            return None;
+        }
+        self.reported = suited.clone();
+        self.reported_at = Some(now);
+        Some(suited)
+    }
+}
@@ -1,6 +1,6 @@
 use crate::network::{
-    error::DHTError, gen_multiaddr, ClientRequest, NetworkError, NetworkEvent, NetworkNode,
-    NetworkNodeConfig, NetworkNodeConfigBuilderError,
+    error::DHTError, gen_multiaddr, routable_addrs, ClientRequest, NetworkError, NetworkEvent,
+    NetworkNode, NetworkNodeConfig, NetworkNodeConfigBuilderError,
 };
 use async_compatibility_layer::{
     art::{async_sleep, async_spawn, async_timeout, future::to, stream},
@@ -12,7 +12,9 @@ use async_compatibility_layer::{
 };
 use async_lock::Mutex;
 use bincode::Options;
+use bytes::Bytes;
 use futures::{stream::FuturesOrdered, Future, FutureExt};
+use hotshot_constants::DIRECT_MESSAGE_MAX_RETRIES;
 use hotshot_utils::bincode::bincode_opts;
 use libp2p::{request_response::ResponseChannel, Multiaddr};
 use libp2p_identity::PeerId;
@@ -27,7 +29,7 @@ use std::{
     },
     time::{Duration, Instant},
 };
-use tracing::{debug, info, instrument};
+use tracing::{debug, info, instrument, warn};
 
 /// A handle containing:
 /// - A reference to the state
@@ -46,6 +48,9 @@ pub struct NetworkNodeHandle<S> {
     /// the local address we're listening on
     listen_addr: Multiaddr,
 
+    /// the addresses other peers reach us at, see [`routable_addrs`]
+    advertised_addrs: Vec<Multiaddr>,
+
     /// the peer id of the networkbehaviour
     peer_id: PeerId,
 
@@ -108,6 +113,20 @@ impl<S: Default + Debug> NetworkNodeHandle<S> {
             .await
             .context(NetworkSnafu)?;
         info!("LISTEN ADDRESS IS {:?}", listen_addr);
+        for addr in &config.additional_bound_addrs {
+            let addr = network
+                .start_listen_additional(addr.clone())
+                .await
+                .context(NetworkSnafu)?;
+            info!("ADDITIONAL LISTEN ADDRESS IS {:?}", addr);
+        }
+        let advertised_addrs = routable_addrs(&config.advertised_addrs, &listen_addr);
+        if advertised_addrs.is_empty() {
+            warn!("No routable address to advertise, peers cannot dial {listen_addr:?}");
+        }
+        for addr in &advertised_addrs {
+            network.advertise(addr.clone());
+        }
         // pin here to force the future onto the heap since it can be large
         // in the case of flume
         let (send_chan, recv_chan) = Box::pin(network.spawn_listeners())
@@ -122,6 +141,7 @@ impl<S: Default + Debug> NetworkNodeHandle<S> {
             state: std::sync::Arc::default(),
             send_network: send_chan,
             listen_addr,
+            advertised_addrs,
             peer_id,
             id,
             webui_listeners: Arc::default(),
@@ -265,6 +285,13 @@ impl<S: Default + Debug> NetworkNodeHandle<S> {
     pub fn listen_addr(&self) -> Multiaddr {
         self.listen_addr.clone()
     }
+
+    /// The routable addresses other peers reach this node at, which it publishes in its contact
+    /// record. Unlike the listen addr, never an unspecified address such as `0.0.0.0`.
+    #[must_use]
+    pub fn advertised_addrs(&self) -> &[Multiaddr] {
+        &self.advertised_addrs
+    }
 }
 
 impl<S> NetworkNodeHandle<S> {
@@ -447,6 +474,30 @@ impl<S> NetworkNodeHandle<S> {
         self.send_request(req).await
     }
 
+    /// Admit connections only from `peers` and the bootstrap nodes, closing the connections to
+    /// any other peer, including those of peers `peers` no longer has
+    /// # Errors
+    /// - Will return [`NetworkNodeHandleError::SendError`] when underlying `NetworkNode` has been killed
+    pub async fn enforce_allowlist(
+        &self,
+        peers: Vec<PeerId>,
+    ) -> Result<(), NetworkNodeHandleError> {
+        let req = ClientRequest::EnforceAllowlist(peers);
+        self.send_request(req).await
+    }
+
+    /// Admit connections only from `reachable` peers, closing the connections to any other peer,
+    /// or lift the partition if `reachable` is `None`
+    /// # Errors
+    /// - Will return [`NetworkNodeHandleError::SendError`] when underlying `NetworkNode` has been killed
+    pub async fn partition(
+        &self,
+        reachable: Option<Vec<PeerId>>,
+    ) -> Result<(), NetworkNodeHandleError> {
+        let req = ClientRequest::Partition(reachable);
+        self.send_request(req).await
+    }
+
     /// Make a direct request to `peer_id` containing `msg`
     /// # Errors
     /// - Will return [`NetworkNodeHandleError::SendError`] when underlying `NetworkNode` has been killed
@@ -457,7 +508,8 @@ impl<S> NetworkNodeHandle<S> {
         msg: &impl Serialize,
     ) -> Result<(), NetworkNodeHandleError> {
         let serialized_msg = bincode_opts().serialize(msg).context(SerializationSnafu)?;
-        self.direct_request_no_serialize(pid, serialized_msg).await
+        self.direct_request_no_serialize(pid, serialized_msg.into())
+            .await
     }
 
     /// Make a direct request to `peer_id` containing `msg` without serializing
@@ -467,16 +519,61 @@ impl<S> NetworkNodeHandle<S> {
     pub async fn direct_request_no_serialize(
         &self,
         pid: PeerId,
-        contents: Vec<u8>,
+        contents: Bytes,
     ) -> Result<(), NetworkNodeHandleError> {
         let req = ClientRequest::DirectRequest {
             pid,
             contents,
             retry_count: 1,
+            notify: None,
         };
         self.send_request(req).await
     }
 
+    /// Make a direct request to `peer_id` containing `msg`, retrying with backoff until the
+    /// recipient acknowledges it or `timeout` elapses
+    /// # Errors
+    /// - Will return [`NetworkNodeHandleError::SendError`] when underlying `NetworkNode` has been killed
+    /// - Will return [`NetworkNodeHandleError::SerializationError`] when unable to serialize `msg`
+    /// - Will return [`NetworkNodeHandleError::TimeoutError`] when not acknowledged in time
+    /// - Will return [`NetworkNodeHandleError::NotAcknowledged`] when we ran out of retries
+    pub async fn direct_request_with_ack(
+        &self,
+        pid: PeerId,
+        msg: &impl Serialize,
+        timeout: Duration,
+    ) -> Result<(), NetworkNodeHandleError> {
+        let serialized_msg = bincode_opts().serialize(msg).context(SerializationSnafu)?;
+        self.direct_request_with_ack_no_serialize(pid, serialized_msg.into(), timeout)
+            .await
+    }
+
+    /// Make a direct request to `peer_id` containing `contents` without serializing, retrying
+    /// with backoff until the recipient acknowledges it or `timeout` elapses
+    /// # Errors
+    /// - Will return [`NetworkNodeHandleError::SendError`] when underlying `NetworkNode` has been killed
+    /// - Will return [`NetworkNodeHandleError::TimeoutError`] when not acknowledged in time
+    /// - Will return [`NetworkNodeHandleError::NotAcknowledged`] when we ran out of retries
+    pub async fn direct_request_with_ack_no_serialize(
+        &self,
+        pid: PeerId,
+        contents: Bytes,
+        timeout: Duration,
+    ) -> Result<(), NetworkNodeHandleError> {
+        let (s, r) = futures::channel::oneshot::channel();
+        let req = ClientRequest::DirectRequest {
+            pid,
+            contents,
+            retry_count: DIRECT_MESSAGE_MAX_RETRIES,
+            notify: Some(s),
+        };
+        self.send_request(req).await?;
+        async_timeout(timeout, r)
+            .await
+            .context(TimeoutSnafu)?
+            .map_err(|_| NetworkNodeHandleError::NotAcknowledged)
+    }
+
     /// Reply with `msg` to a request over `chan`
     /// # Errors
     /// - Will return [`NetworkNodeHandleError::SendError`] when underlying `NetworkNode` has been killed
@@ -513,7 +610,7 @@ impl<S> NetworkNodeHandle<S> {
         msg: &impl Serialize,
     ) -> Result<(), NetworkNodeHandleError> {
         let serialized_msg = bincode_opts().serialize(msg).context(SerializationSnafu)?;
-        self.gossip_no_serialize(topic, serialized_msg).await
+        self.gossip_no_serialize(topic, serialized_msg.into()).await
     }
 
     /// Gossip a message to peers without serializing
@@ -523,7 +620,7 @@ impl<S> NetworkNodeHandle<S> {
     pub async fn gossip_no_serialize(
         &self,
         topic: String,
-        msg: Vec<u8>,
+        msg: Bytes,
     ) -> Result<(), NetworkNodeHandleError> {
         let req = ClientRequest::GossipMsg(topic, msg);
         self.send_request(req).await
@@ -720,6 +817,8 @@ pub enum NetworkNodeHandleError {
     },
     /// no known topic matches the hashset of keys
     NoSuchTopic,
+    /// A direct message was not acknowledged by the recipient after all retries
+    NotAcknowledged,
 }
 
 /// Re-exports of the snafu errors that [`NetworkNodeHandleError`] can throw
@@ -0,0 +1,249 @@
+//! Peers a node learned about, persisted across restarts
+//!
+//! A [`PeerStore`] keeps the addresses the peers of a node identified themselves with, and a
+//! score of how reliably they could be reached: established connections raise it, failed dials
+//! lower it, and peers whose score drops below [`MIN_PEER_SCORE`] are forgotten. The store is
+//! saved to disk at most every [`PEER_STORE_SAVE_INTERVAL_SECS`] seconds and when the node shuts
+//! down, and a restarted node seeds its DHT with the stored peers, so it reconnects to the peers
+//! it knew rather than bootstrapping from scratch.
+
+use hotshot_constants::{PEER_STORE_MAX_PEERS, PEER_STORE_SAVE_INTERVAL_SECS};
+use libp2p::Multiaddr;
+use libp2p_identity::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+/// the highest score of a peer
+pub const MAX_PEER_SCORE: i32 = 100;
+
+/// peers whose score drops below this are forgotten
+pub const MIN_PEER_SCORE: i32 = -10;
+
+/// A peer a node knows, with how reliably it could be reached
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KnownPeer {
+    /// the addresses the peer listens on
+    pub addrs: BTreeSet<Multiaddr>,
+    /// established connections to the peer, less the dials of it which failed, within
+    /// [`MIN_PEER_SCORE`] and [`MAX_PEER_SCORE`]
+    pub score: i32,
+    /// when a connection to the peer was last established, in milliseconds since the unix epoch
+    pub last_seen_ms: u64,
+}
+
+/// A peer as it is saved to disk
+#[derive(Serialize, Deserialize)]
+struct StoredPeer {
+    /// the peer id, in base 58
+    peer_id: String,
+    /// the addresses the peer listens on
+    addrs: Vec<String>,
+    /// the score of the peer
+    score: i32,
+    /// when a connection to the peer was last established
+    last_seen_ms: u64,
+}
+
+/// The peers a node knows, saved to a file
+#[derive(Debug)]
+pub struct PeerStore {
+    /// the file the store is saved to
+    path: PathBuf,
+    /// the peers, by id
+    peers: HashMap<PeerId, KnownPeer>,
+    /// whether the peers changed since the store was last saved
+    dirty: bool,
+    /// when the store was last saved
+    last_saved: Instant,
+}
+
+/// Milliseconds since the unix epoch on the local clock
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
+}
+
+impl PeerStore {
+    /// Load the store saved at `path`, or start an empty one if there is none or it cannot be
+    /// read
+    #[must_use]
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let peers = match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<Vec<StoredPeer>>(&bytes) {
+                Ok(stored) => stored
+                    .into_iter()
+                    .filter_map(|peer| {
+                        let peer_id = peer.peer_id.parse().ok()?;
+                        let addrs = peer
+                            .addrs
+                            .iter()
+                            .filter_map(|addr| addr.parse().ok())
+                            .collect();
+                        Some((
+                            peer_id,
+                            KnownPeer {
+                                addrs,
+                                score: peer.score.clamp(MIN_PEER_SCORE, MAX_PEER_SCORE),
+                                last_seen_ms: peer.last_seen_ms,
+                            },
+                        ))
+                    })
+                    .collect(),
+                Err(e) => {
+                    warn!("Ignoring the unreadable peer store at {path:?}: {e}");
+                    HashMap::new()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                warn!("Ignoring the peer store at {path:?}, which cannot be read: {e}");
+                HashMap::new()
+            }
+        };
+        info!(
+            "Loaded {} peers from the peer store at {path:?}",
+            peers.len()
+        );
+        Self {
+            path,
+            peers,
+            dirty: false,
+            last_saved: Instant::now(),
+        }
+    }
+
+    /// The number of peers in the store
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Whether the store has no peers
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// The peer with id `peer_id`, if it is known
+    #[must_use]
+    pub fn get(&self, peer_id: &PeerId) -> Option<&KnownPeer> {
+        self.peers.get(peer_id)
+    }
+
+    /// The peers with an address, most reliable first
+    #[must_use]
+    pub fn best_peers(&self) -> Vec<(PeerId, KnownPeer)> {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|(_, peer)| !peer.addrs.is_empty())
+            .map(|(peer_id, peer)| (*peer_id, peer.clone()))
+            .collect();
+        peers.sort_by(|(_, a), (_, b)| {
+            b.score
+                .cmp(&a.score)
+                .then(b.last_seen_ms.cmp(&a.last_seen_ms))
+        });
+        peers
+    }
+
+    /// Record the addresses `peer_id` listens on, replacing those known
+    pub fn record_addresses(
+        &mut self,
+        peer_id: PeerId,
+        addrs: impl IntoIterator<Item = Multiaddr>,
+    ) {
+        let addrs: BTreeSet<_> = addrs.into_iter().collect();
+        if addrs.is_empty() {
+            return;
+        }
+        let peer = self.peers.entry(peer_id).or_default();
+        if peer.addrs != addrs {
+            peer.addrs = addrs;
+            self.dirty = true;
+        }
+    }
+
+    /// Record that a connection to `peer_id` was established
+    pub fn record_connected(&mut self, peer_id: &PeerId) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.score = (peer.score + 1).min(MAX_PEER_SCORE);
+            peer.last_seen_ms = unix_time_ms();
+            self.dirty = true;
+        }
+    }
+
+    /// Record that dialing `peer_id` failed, forgetting it once it failed too often
+    pub fn record_failed_dial(&mut self, peer_id: &PeerId) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.score -= 1;
+            if peer.score < MIN_PEER_SCORE {
+                self.peers.remove(peer_id);
+            }
+            self.dirty = true;
+        }
+    }
+
+    /// Save the store, keeping the [`PEER_STORE_MAX_PEERS`] most reliable peers
+    /// # Errors
+    /// If the store cannot be written
+    pub fn save(&mut self) -> io::Result<()> {
+        let best = self.best_peers();
+        if best.len() > PEER_STORE_MAX_PEERS {
+            for (peer_id, _) in &best[PEER_STORE_MAX_PEERS..] {
+                self.peers.remove(peer_id);
+            }
+        }
+        let stored: Vec<_> = best
+            .into_iter()
+            .take(PEER_STORE_MAX_PEERS)
+            .map(|(peer_id, peer)| StoredPeer {
+                peer_id: peer_id.to_base58(),
+                addrs: peer.addrs.iter().map(ToString::to_string).collect(),
+                score: peer.score,
+                last_seen_ms: peer.last_seen_ms,
+            })
+            .collect();
+        let bytes = serde_json::to_vec_pretty(&stored)?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // write a temporary file first, so that a crash while saving keeps the previous store
+        let temp_path = self.path.with_extension("tmp");
+        fs::write(&temp_path, bytes)?;
+        fs::rename(&temp_path, &self.path)?;
+        self.dirty = false;
+        self.last_saved = Instant::now();
+        Ok(())
+    }
+
+    /// Save the store if it changed and was not saved recently, logging rather than failing if
+    /// it cannot be written
+    pub fn save_if_due(&mut self) {
+        if self.dirty
+            && self.last_saved.elapsed() >= Duration::from_secs(PEER_STORE_SAVE_INTERVAL_SECS)
+        {
+            self.save_or_warn();
+        }
+    }
+
+    /// Save the store if it changed, logging rather than failing if it cannot be written
+    pub fn save_or_warn(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        if let Err(e) = self.save() {
+            warn!("Failed to save the peer store to {:?}: {e}", self.path);
+        }
+    }
+}
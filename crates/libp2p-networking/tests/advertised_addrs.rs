@@ -0,0 +1,34 @@
+use libp2p::Multiaddr;
+use libp2p_networking::network::{is_routable, routable_addrs};
+
+/// The QUIC address of `ip` on port 9000
+fn addr(ip: &str) -> Multiaddr {
+    let family = if ip.contains(':') { "ip6" } else { "ip4" };
+    format!("/{family}/{ip}/udp/9000/quic-v1").parse().unwrap()
+}
+
+#[test]
+fn unspecified_addresses_are_not_routable() {
+    assert!(!is_routable(&addr("0.0.0.0")));
+    assert!(!is_routable(&addr("::")));
+    assert!(is_routable(&addr("203.0.113.7")));
+    assert!(is_routable(&addr("2001:db8::7")));
+    assert!(is_routable(
+        &"/dns4/node.example/udp/9000/quic-v1".parse().unwrap()
+    ));
+}
+
+#[test]
+fn nodes_advertise_routable_addresses_only() {
+    // a node bound to the unspecified address advertises its public addresses
+    assert_eq!(
+        routable_addrs(&[addr("0.0.0.0"), addr("203.0.113.7")], &addr("0.0.0.0")),
+        vec![addr("203.0.113.7")]
+    );
+    // and its bound address if it is routable and nothing else is advertised
+    assert_eq!(
+        routable_addrs(&[addr("::")], &addr("192.0.2.1")),
+        vec![addr("192.0.2.1")]
+    );
+    assert!(routable_addrs(&[], &addr("0.0.0.0")).is_empty());
+}
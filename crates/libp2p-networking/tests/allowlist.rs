@@ -0,0 +1,116 @@
+use std::{collections::HashSet, num::NonZeroUsize, str::FromStr, time::Duration};
+
+use async_compatibility_layer::art::{async_sleep, async_timeout};
+use libp2p::{identity::Keypair, Multiaddr};
+use libp2p_identity::PeerId;
+use libp2p_networking::network::{
+    allowlist::PeerAllowlist, NetworkNodeConfigBuilder, NetworkNodeHandle, NetworkNodeType,
+};
+
+/// A fresh peer id
+fn random_peer() -> PeerId {
+    PeerId::from(Keypair::generate_ed25519().public())
+}
+
+/// A regular node listening on a random local port
+async fn local_node(id: usize) -> NetworkNodeHandle<()> {
+    let config = NetworkNodeConfigBuilder::default()
+        .node_type(NetworkNodeType::Regular)
+        .replication_factor(NonZeroUsize::new(1).unwrap())
+        .bound_addr(Some(
+            Multiaddr::from_str("/ip4/127.0.0.1/udp/0/quic-v1").unwrap(),
+        ))
+        .to_connect_addrs(HashSet::default())
+        .build()
+        .unwrap();
+    NetworkNodeHandle::new(config, id).await.unwrap()
+}
+
+#[test]
+fn allowlist_admits_only_allowed_peers_once_enforced() {
+    let bootstrap = random_peer();
+    let member = random_peer();
+    let stranger = random_peer();
+
+    let mut allowlist = PeerAllowlist::new([bootstrap]);
+    // every peer is admitted until the list is enforced
+    assert!(!allowlist.is_enforced());
+    assert!(allowlist.admits(&stranger));
+
+    allowlist.set_allowed([member]);
+    allowlist.enforce();
+    assert!(allowlist.is_enforced());
+    assert!(allowlist.admits(&bootstrap));
+    assert!(allowlist.admits(&member));
+    assert!(!allowlist.admits(&stranger));
+}
+
+#[test]
+fn peers_leaving_the_stake_table_are_no_longer_admitted() {
+    let bootstrap = random_peer();
+    let member = random_peer();
+    let newcomer = random_peer();
+
+    let mut allowlist = PeerAllowlist::new([bootstrap]);
+    allowlist.set_allowed([member]);
+    allowlist.enforce();
+    allowlist.set_allowed([newcomer]);
+    assert!(!allowlist.admits(&member));
+    assert!(allowlist.admits(&newcomer));
+    // the bootstrap nodes stay admitted whatever the stake table
+    assert!(allowlist.admits(&bootstrap));
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn connections_from_non_members_are_denied() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let gate = local_node(0).await;
+    let member = local_node(1).await;
+    let stranger = local_node(2).await;
+    gate.enforce_allowlist(vec![member.peer_id()])
+        .await
+        .unwrap();
+
+    for dialer in [&member, &stranger] {
+        dialer
+            .add_known_peers(vec![(Some(gate.peer_id()), gate.listen_addr())])
+            .await
+            .unwrap();
+        dialer.begin_bootstrap().await.unwrap();
+    }
+    async_timeout(Duration::from_secs(30), async {
+        while !gate
+            .connected_pids()
+            .await
+            .unwrap()
+            .contains(&member.peer_id())
+        {
+            async_sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the member never connected");
+
+    // the stranger had as long to connect as the member, and more
+    async_sleep(Duration::from_secs(2)).await;
+    assert!(!gate
+        .connected_pids()
+        .await
+        .unwrap()
+        .contains(&stranger.peer_id()));
+    assert!(!stranger
+        .connected_pids()
+        .await
+        .unwrap()
+        .contains(&gate.peer_id()));
+
+    for node in [gate, member, stranger] {
+        node.shutdown().await.unwrap();
+    }
+}
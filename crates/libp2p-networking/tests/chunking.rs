@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use libp2p::identity::Keypair;
+use libp2p_identity::PeerId;
+use libp2p_networking::network::chunking::{split, Reassembler, Received};
+
+/// A fresh peer id
+fn random_peer() -> PeerId {
+    PeerId::from(Keypair::generate_ed25519().public())
+}
+
+/// A reassembler with the default timeout and the given limits
+fn reassembler(max_bytes: usize, max_pending_per_peer: usize) -> Reassembler<usize> {
+    Reassembler::new(Duration::from_secs(30), max_bytes, max_pending_per_peer)
+}
+
+#[test]
+fn chunked_messages_are_reassembled_in_any_order() {
+    let peer = random_peer();
+    let message: Vec<u8> = (0..100u8).collect();
+    let mut chunks = split(&message, 10).unwrap();
+    assert_eq!(chunks.len(), 10);
+    chunks.reverse();
+
+    let mut reassembler = reassembler(1024, 4);
+    let last = chunks.remove(0);
+    // the last chunk's acknowledgement is held until the message is complete
+    assert_eq!(
+        reassembler.receive(peer, &last, 9),
+        Received::Pending { chunk_ack: None }
+    );
+    let completing = chunks.pop().unwrap();
+    for (ack, chunk) in chunks.iter().enumerate() {
+        assert_eq!(
+            reassembler.receive(peer, chunk, ack),
+            Received::Pending {
+                chunk_ack: Some(ack)
+            }
+        );
+    }
+    assert_eq!(
+        reassembler.receive(peer, &completing, 0),
+        Received::Complete {
+            message,
+            ack: 9,
+            chunk_ack: Some(0),
+        }
+    );
+    assert_eq!(reassembler.pending(), 0);
+}
+
+#[test]
+fn whole_messages_are_handed_back_with_their_ack() {
+    let message = vec![1, 2, 3];
+    let wire = split(&message, 10).unwrap();
+    assert_eq!(wire.len(), 1);
+    assert_eq!(
+        reassembler(1024, 4).receive(random_peer(), &wire[0], 7),
+        Received::Complete {
+            message,
+            ack: 7,
+            chunk_ack: None,
+        }
+    );
+}
+
+#[test]
+fn incomplete_messages_are_not_acknowledged() {
+    let peer = random_peer();
+    let chunks = split(&(0..30u8).collect::<Vec<_>>(), 10).unwrap();
+    let mut reassembler = reassembler(1024, 4);
+
+    // the last chunk arriving while another is missing acknowledges nothing
+    assert_eq!(
+        reassembler.receive(peer, &chunks[0], 0),
+        Received::Pending { chunk_ack: Some(0) }
+    );
+    assert_eq!(
+        reassembler.receive(peer, &chunks[2], 2),
+        Received::Pending { chunk_ack: None }
+    );
+    // nor do duplicates or corrupt wire messages
+    assert_eq!(reassembler.receive(peer, &chunks[0], 3), Received::Dropped);
+    assert_eq!(reassembler.receive(peer, &[0xff; 8], 4), Received::Dropped);
+    assert_eq!(reassembler.pending(), 1);
+}
+
+#[test]
+fn partial_messages_of_a_peer_are_bounded() {
+    let flooder = random_peer();
+    let honest = random_peer();
+    let mut reassembler = reassembler(1024, 2);
+
+    let honest_chunks = split(&[7; 20], 10).unwrap();
+    assert!(matches!(
+        reassembler.receive(honest, &honest_chunks[0], 0),
+        Received::Pending { .. }
+    ));
+    for i in 0..5u8 {
+        let chunks = split(&[i; 20], 10).unwrap();
+        assert!(matches!(
+            reassembler.receive(flooder, &chunks[0], 0),
+            Received::Pending { .. }
+        ));
+    }
+    // the flooder only ever holds as many messages as it is allowed, evicting its own
+    assert_eq!(reassembler.pending(), 3);
+    assert!(matches!(
+        reassembler.receive(honest, &honest_chunks[1], 1),
+        Received::Complete { ack: 1, .. }
+    ));
+}
+
+#[test]
+fn partial_messages_are_bounded_in_bytes() {
+    let flooder = random_peer();
+    let honest = random_peer();
+    let mut reassembler = reassembler(50, 16);
+
+    let honest_chunks = split(&[7; 20], 10).unwrap();
+    assert!(matches!(
+        reassembler.receive(honest, &honest_chunks[0], 0),
+        Received::Pending { .. }
+    ));
+    for i in 0..8u8 {
+        let chunks = split(&[i; 20], 10).unwrap();
+        reassembler.receive(flooder, &chunks[0], 0);
+    }
+    // the peer holding the most bytes has its oldest messages evicted first
+    assert!(reassembler.pending_bytes() <= 50);
+    assert!(matches!(
+        reassembler.receive(honest, &honest_chunks[1], 1),
+        Received::Complete { ack: 1, .. }
+    ));
+}
+
+#[test]
+fn messages_over_the_byte_budget_are_dropped() {
+    let peer = random_peer();
+    let chunks = split(&[1; 100], 10).unwrap();
+    let mut reassembler = reassembler(50, 4);
+    let received: Vec<_> = chunks
+        .iter()
+        .enumerate()
+        .map(|(ack, chunk)| reassembler.receive(peer, chunk, ack))
+        .collect();
+    assert!(!received
+        .iter()
+        .any(|received| matches!(received, Received::Complete { .. })));
+    assert!(reassembler.pending_bytes() <= 50);
+}
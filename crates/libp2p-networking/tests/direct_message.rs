@@ -0,0 +1,162 @@
+#![allow(clippy::panic)]
+
+mod common;
+
+use async_compatibility_layer::art::async_sleep;
+use common::test_bed;
+use futures::future::{join_all, ready};
+use hotshot_constants::{DIRECT_MESSAGE_ACK_TIMEOUT_SEC, DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER};
+use libp2p::request_response::ResponseChannel;
+use libp2p_networking::network::{NetworkEvent, NetworkNodeHandle, NetworkNodeHandleError};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::instrument;
+
+const TOTAL_NUM_PEERS: usize = 5;
+const NUM_OF_BOOTSTRAP: usize = 2;
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// the requests beyond the in-flight limit, held back by the sender
+const QUEUED: usize = 4;
+
+/// how long requests are given to show up, well within the request-response timeout after which
+/// the sender would retry them
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// The direct requests received and not yet responded to, across all nodes
+type Held = Arc<Mutex<Vec<ResponseChannel<Vec<u8>>>>>;
+
+/// An event handler holding on to every direct request instead of responding to it
+fn hold_requests(
+    held: Held,
+) -> impl Fn(
+    NetworkEvent,
+    Arc<NetworkNodeHandle<()>>,
+) -> futures::future::Ready<Result<(), NetworkNodeHandleError>>
+       + Clone
+       + Send
+       + Sync
+       + 'static {
+    move |event, _handle| {
+        if let NetworkEvent::DirectRequest(_, _, chan) = event {
+            held.lock().unwrap().push(chan);
+        }
+        ready(Ok(()))
+    }
+}
+
+/// Wait for `held` to have `count` requests in it
+async fn wait_for_held(held: &Held, count: usize) {
+    let start = Instant::now();
+    while held.lock().unwrap().len() < count {
+        assert!(
+            start.elapsed() < TIMEOUT,
+            "only {} of {count} direct requests arrived",
+            held.lock().unwrap().len()
+        );
+        async_sleep(Duration::from_millis(20)).await;
+    }
+}
+
+/// Acknowledge one of the requests held by `receiver`
+async fn respond_to_one(held: &Held, receiver: &NetworkNodeHandle<()>) {
+    let chan = held.lock().unwrap().pop().expect("a request is held");
+    receiver.direct_response(chan, &()).await.unwrap();
+}
+
+/// requests beyond the in-flight limit of a peer are sent as the requests in flight are
+/// acknowledged, and are all acknowledged in the end
+async fn run_in_flight_limit(handles: Vec<Arc<NetworkNodeHandle<()>>>, held: Held) {
+    let sender = handles[0].clone();
+    let receiver = handles[1].clone();
+    let total = DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER + QUEUED;
+    let acks = join_all((0..total).map(|i| {
+        let sender = sender.clone();
+        let pid = receiver.peer_id();
+        async move { sender.direct_request_with_ack(pid, &i, TIMEOUT).await }
+    }));
+    let control = async {
+        // no more than the limit are in flight at once
+        wait_for_held(&held, DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER).await;
+        async_sleep(SETTLE).await;
+        assert_eq!(
+            held.lock().unwrap().len(),
+            DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER
+        );
+
+        // acknowledging one request lets exactly one queued request go
+        respond_to_one(&held, &receiver).await;
+        wait_for_held(&held, DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER).await;
+        async_sleep(SETTLE).await;
+        assert_eq!(
+            held.lock().unwrap().len(),
+            DIRECT_MESSAGE_MAX_IN_FLIGHT_PER_PEER
+        );
+
+        // the queue drains as the rest are acknowledged
+        for _ in 1..total {
+            wait_for_held(&held, 1).await;
+            respond_to_one(&held, &receiver).await;
+        }
+    };
+    let (acks, ()) = futures::join!(acks, control);
+    for ack in acks {
+        ack.unwrap();
+    }
+    assert!(held.lock().unwrap().is_empty());
+}
+
+/// a request that is never acknowledged fails once the ack timeout elapses
+async fn run_unacknowledged(handles: Vec<Arc<NetworkNodeHandle<()>>>, held: Held) {
+    let timeout = Duration::from_secs(DIRECT_MESSAGE_ACK_TIMEOUT_SEC);
+    let start = Instant::now();
+    let result = handles[0]
+        .direct_request_with_ack(handles[1].peer_id(), &(), timeout)
+        .await;
+    assert!(
+        matches!(result, Err(NetworkNodeHandleError::TimeoutError { .. })),
+        "{result:?}"
+    );
+    assert!(start.elapsed() >= timeout);
+    assert!(!held.lock().unwrap().is_empty());
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn test_direct_requests_beyond_the_in_flight_limit_are_queued() {
+    let held = Held::default();
+    let run_held = held.clone();
+    Box::pin(test_bed(
+        move |handles, _| run_in_flight_limit(handles, run_held),
+        hold_requests(held),
+        TOTAL_NUM_PEERS,
+        NUM_OF_BOOTSTRAP,
+        TIMEOUT,
+    ))
+    .await;
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn test_unacknowledged_direct_requests_time_out() {
+    let held = Held::default();
+    let run_held = held.clone();
+    Box::pin(test_bed(
+        move |handles, _| run_unacknowledged(handles, run_held),
+        hold_requests(held),
+        TOTAL_NUM_PEERS,
+        NUM_OF_BOOTSTRAP,
+        TIMEOUT,
+    ))
+    .await;
+}
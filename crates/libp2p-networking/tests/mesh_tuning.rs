@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use libp2p::gossipsub::ConfigBuilder;
+use libp2p_networking::network::{MeshParams, MeshTuning, NetworkNodeType, MESH_REPORT_INTERVAL};
+
+#[test]
+fn tuned_mesh_params_are_accepted_by_gossipsub() {
+    for total_nodes in (1..=64).chain([100, 500, 1000, 5000]) {
+        for node_type in [NetworkNodeType::Bootstrap, NetworkNodeType::Regular] {
+            let params = MeshParams::tuned(total_nodes, node_type);
+            assert!(
+                ConfigBuilder::default()
+                    .mesh_n_high(params.mesh_n_high)
+                    .mesh_n_low(params.mesh_n_low)
+                    .mesh_outbound_min(params.mesh_outbound_min)
+                    .mesh_n(params.mesh_n)
+                    .build()
+                    .is_ok(),
+                "{params:?} for {total_nodes} nodes"
+            );
+            assert!(params.mesh_n_high < total_nodes.max(2));
+        }
+    }
+}
+
+#[test]
+fn mesh_degree_grows_with_the_network() {
+    assert_eq!(
+        MeshParams::tuned(10, NetworkNodeType::Regular),
+        MeshParams {
+            mesh_n_high: 9,
+            mesh_n_low: 4,
+            mesh_outbound_min: 2,
+            mesh_n: 6,
+        }
+    );
+    assert_eq!(
+        MeshParams::tuned(1000, NetworkNodeType::Regular),
+        MeshParams {
+            mesh_n_high: 24,
+            mesh_n_low: 8,
+            mesh_outbound_min: 4,
+            mesh_n: 12,
+        }
+    );
+    // bootstrap nodes keep every peer in their mesh
+    assert_eq!(
+        MeshParams::tuned(1000, NetworkNodeType::Bootstrap).mesh_n_high,
+        999
+    );
+}
+
+#[test]
+fn mesh_params_suited_to_the_peers_connected_are_reported_at_intervals() {
+    let start = Instant::now();
+    let mut tuning = MeshTuning::new(100, NetworkNodeType::Regular);
+    assert_eq!(
+        *tuning.params(),
+        MeshParams::tuned(100, NetworkNodeType::Regular)
+    );
+    // the network expected
+    assert_eq!(tuning.observe(99, start), None);
+    // a network where most nodes are down is reported once
+    let suited = tuning.observe(20, start);
+    assert_eq!(
+        suited,
+        Some(MeshParams::tuned(21, NetworkNodeType::Regular))
+    );
+    assert_ne!(suited.as_ref(), Some(tuning.params()));
+    assert_eq!(tuning.observe(20, start + MESH_REPORT_INTERVAL), None);
+    // peers flapping are not reported on every connection
+    assert_eq!(tuning.observe(99, start + Duration::from_secs(1)), None);
+    assert_eq!(
+        tuning.observe(99, start + MESH_REPORT_INTERVAL),
+        Some(tuning.params().clone())
+    );
+    // reporting does not change the parameters gossipsub is built with
+    assert_eq!(
+        *tuning.params(),
+        MeshParams::tuned(100, NetworkNodeType::Regular)
+    );
+}
+
+#[test]
+fn committee_topic_mesh_is_tuned_to_the_committee() {
+    let tuning = MeshTuning::new(1000, NetworkNodeType::Regular);
+    assert_eq!(tuning.committee_topic(), None);
+
+    let tuning = tuning.with_committee_topic("DA", 10);
+    assert_eq!(
+        tuning.committee_topic(),
+        Some(("DA", &MeshParams::tuned(10, NetworkNodeType::Regular)))
+    );
+    // the other topics keep the mesh tuned to the whole network
+    assert_eq!(
+        *tuning.params(),
+        MeshParams::tuned(1000, NetworkNodeType::Regular)
+    );
+}
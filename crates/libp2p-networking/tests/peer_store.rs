@@ -0,0 +1,47 @@
+use libp2p::{identity::Keypair, Multiaddr};
+use libp2p_identity::PeerId;
+use libp2p_networking::network::peer_store::{PeerStore, MIN_PEER_SCORE};
+
+/// A fresh peer id
+fn random_peer() -> PeerId {
+    PeerId::from(Keypair::generate_ed25519().public())
+}
+
+#[test]
+fn peer_store_round_trip() {
+    let dir = std::env::temp_dir().join(format!("hotshot-peer-store-{}", random_peer()));
+    let path = dir.join("peers.json");
+
+    let reliable = random_peer();
+    let flaky = random_peer();
+    let unreachable = random_peer();
+    let addr: Multiaddr = "/ip4/127.0.0.1/udp/9000/quic-v1".parse().unwrap();
+
+    let mut store = PeerStore::load(&path);
+    assert!(store.is_empty());
+    for peer in [reliable, flaky, unreachable] {
+        store.record_addresses(peer, [addr.clone()]);
+    }
+    store.record_connected(&reliable);
+    store.record_connected(&reliable);
+    store.record_connected(&flaky);
+    store.record_failed_dial(&flaky);
+    // peers failing too often are forgotten
+    for _ in 0..=MIN_PEER_SCORE.unsigned_abs() {
+        store.record_failed_dial(&unreachable);
+    }
+    assert!(store.get(&unreachable).is_none());
+    store.save().unwrap();
+
+    let loaded = PeerStore::load(&path);
+    assert_eq!(loaded.len(), 2);
+    assert_eq!(loaded.get(&reliable), store.get(&reliable));
+    let order: Vec<_> = loaded
+        .best_peers()
+        .into_iter()
+        .map(|(peer, _)| peer)
+        .collect();
+    assert_eq!(order, vec![reliable, flaky]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,185 @@
+use std::{fs, num::NonZeroUsize, time::Duration};
+
+use hotshot_types::traits::{election::ElectionConfig, signature_key::SignatureKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{NetworkConfig, NetworkConfigError},
+    soak::{ResourceLeak, ResourceSample},
+};
+
+/// A matrix of benchmark parameters. Every combination is run in turn, each one on top of the
+/// same base [`NetworkConfig`]. An empty list keeps the value from the base config.
+///
+/// The network implementation is chosen by the binary the nodes run, so a campaign over
+/// several networks is one campaign per binary.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CampaignConfig {
+    /// total number of nodes
+    #[serde(default)]
+    pub total_nodes: Vec<NonZeroUsize>,
+    /// size of transactions
+    #[serde(default)]
+    pub transaction_sizes: Vec<usize>,
+    /// number of transactions per view
+    #[serde(default)]
+    pub transactions_per_round: Vec<usize>,
+}
+
+impl CampaignConfig {
+    /// Load a campaign from a toml file
+    /// # Errors
+    /// If the file cannot be read or is not a valid campaign
+    pub fn from_file(file: &str) -> Result<Self, NetworkConfigError> {
+        let data = fs::read_to_string(file).map_err(NetworkConfigError::ReadFromFileError)?;
+        toml::from_str(&data).map_err(NetworkConfigError::CampaignDeserializeError)
+    }
+
+    /// Expand the matrix into one config per combination, in order
+    #[must_use]
+    pub fn expand<K: SignatureKey, E: ElectionConfig>(
+        &self,
+        base: &NetworkConfig<K, E>,
+    ) -> Vec<NetworkConfig<K, E>> {
+        /// the values to use for one axis of the matrix
+        fn axis<T: Clone>(values: &[T], default: T) -> Vec<T> {
+            if values.is_empty() {
+                vec![default]
+            } else {
+                values.to_vec()
+            }
+        }
+
+        let mut configs = Vec::new();
+        for total_nodes in axis(&self.total_nodes, base.config.total_nodes) {
+            for transaction_size in axis(&self.transaction_sizes, base.transaction_size) {
+                for transactions_per_round in
+                    axis(&self.transactions_per_round, base.transactions_per_round)
+                {
+                    let mut config = base.clone();
+                    config.config.total_nodes = total_nodes;
+                    config.config.da_committee_size =
+                        config.config.da_committee_size.min(total_nodes.get());
+                    if let Some(entry) = config.config.known_nodes_with_stake.first().cloned() {
+                        config
+                            .config
+                            .known_nodes_with_stake
+                            .resize(total_nodes.get(), entry);
+                    }
+                    config.transaction_size = transaction_size;
+                    config.transactions_per_round = transactions_per_round;
+                    configs.push(config);
+                }
+            }
+        }
+        configs
+    }
+}
+
+/// Throughput observed by a node over one sampling interval
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ThroughputSample {
+    /// time since the end of the warm-up at which the sample was taken
+    pub elapsed: Duration,
+    /// length of the interval this sample covers
+    pub interval: Duration,
+    /// number of transactions committed during the interval
+    pub transactions_committed: u64,
+    /// number of views decided during the interval
+    pub commits: usize,
+}
+
+/// Whether consensus resumed after a partition, as observed by a node
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartitionOutcome {
+    /// the view at which the partition started
+    pub view: u64,
+    /// how long the partition lasted
+    pub duration: Duration,
+    /// time from the heal to the first decide after it, or `None` if the node decided nothing
+    /// more before the run ended
+    pub recovered_after: Option<Duration>,
+}
+
+/// The results of a single node's run, posted to the orchestrator once it finishes
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RunResults {
+    /// index of the node which ran
+    pub node_index: u64,
+    /// number of views decided after the warm-up
+    pub num_successful_commits: usize,
+    /// number of transactions this node submitted
+    pub total_transactions_sent: u64,
+    /// number of transactions committed after the warm-up, as seen by this node
+    pub total_transactions_committed: u64,
+    /// wall clock time the run took, excluding the warm-up
+    pub total_time_elapsed: Duration,
+    /// throughput over time, one sample per sampling interval
+    pub throughput_samples: Vec<ThroughputSample>,
+    /// committed transactions per second the load generator held consensus at, if it ran and
+    /// found the saturation point
+    #[serde(default)]
+    pub sustainable_tps: Option<f64>,
+    /// how the node came out of each partition of the run
+    #[serde(default)]
+    pub partitions: Vec<PartitionOutcome>,
+    /// the resource usage of the node through the run, if it was a soak run
+    #[serde(default)]
+    pub resource_samples: Vec<ResourceSample>,
+    /// the resources the node leaked during the run, which fail a soak run
+    #[serde(default)]
+    pub resource_leaks: Vec<ResourceLeak>,
+}
+
+/// The results of every node for one configuration of a campaign
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CampaignRunResults {
+    /// index of the run within the campaign
+    pub run_index: u64,
+    /// total number of nodes
+    pub total_nodes: usize,
+    /// size of transactions
+    pub transaction_size: usize,
+    /// number of transactions per view
+    pub transactions_per_round: usize,
+    /// the results posted by each node
+    pub node_results: Vec<RunResults>,
+}
+
+impl CampaignRunResults {
+    /// Mean committed transactions per second across the nodes of this run
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean_throughput(&self) -> f64 {
+        let throughputs: Vec<f64> = self
+            .node_results
+            .iter()
+            .filter(|results| !results.total_time_elapsed.is_zero())
+            .map(|results| {
+                results.total_transactions_committed as f64
+                    / results.total_time_elapsed.as_secs_f64()
+            })
+            .collect();
+        if throughputs.is_empty() {
+            return 0.0;
+        }
+        throughputs.iter().sum::<f64>() / throughputs.len() as f64
+    }
+
+    /// The nodes which saw consensus resume after every partition of this run, and the nodes
+    /// which went through at least one partition
+    #[must_use]
+    pub fn recovered_nodes(&self) -> (usize, usize) {
+        let partitioned = self
+            .node_results
+            .iter()
+            .filter(|results| !results.partitions.is_empty());
+        let recovered = partitioned.clone().filter(|results| {
+            results
+                .partitions
+                .iter()
+                .all(|outcome| outcome.recovered_after.is_some())
+        });
+        (recovered.count(), partitioned.count())
+    }
+}
@@ -1,6 +1,18 @@
-use std::{net::IpAddr, time::Duration};
+use std::{
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-use crate::config::NetworkConfig;
+use crate::{
+    campaign::{CampaignRunResults, RunResults},
+    config::{unix_time_ms, GenesisTimestamp, NetworkConfig, PartitionOrder, RampUpSchedule},
+    control::NodeControlHandle,
+    health::Heartbeat,
+};
 use async_compatibility_layer::art::async_sleep;
 use clap::Parser;
 use futures::{Future, FutureExt};
@@ -11,8 +23,14 @@ use hotshot_types::{
 };
 use surf_disco::{error::ClientError, Client};
 use tide_disco::Url;
+use tracing::{debug, warn};
+
+/// Clock offsets from the orchestrator larger than this are reported, as they suggest the
+/// node's clock is not synchronized over NTP
+const MAX_CLOCK_OFFSET_MS: i128 = 500;
 
 /// Holds the client connection to the orchestrator
+#[derive(Clone)]
 pub struct OrchestratorClient {
     /// the client
     client: surf_disco::Client<ClientError>,
@@ -31,13 +49,38 @@ pub struct OrchestratorClient {
 pub struct ValidatorArgs {
     /// The address the orchestrator runs on
     pub url: Url,
-    /// This node's public IP address, for libp2p
-    /// If no IP address is passed in, it will default to 127.0.0.1
+    /// This node's public IP address, for libp2p, advertised to the other nodes through the
+    /// orchestrator. It need not be an address the node listens on, see `bind_ips`.
+    /// If no IP address is passed in, it will default to the local IPv4 and IPv6 addresses
     pub public_ip: Option<IpAddr>,
+    /// Further public IP addresses of this node, e.g. its IPv6 address in a dual-stack
+    /// deployment
+    #[arg(long)]
+    pub additional_ips: Vec<IpAddr>,
+    /// The addresses libp2p listens on, e.g. those of the network interfaces to use on a host
+    /// with several. If none is passed in, it listens on the unspecified address, `0.0.0.0` or
+    /// `::`, of the families it uses.
+    #[arg(long)]
+    pub bind_ips: Vec<IpAddr>,
     /// An optional network config file to save to/load from
     /// Allows for rejoining the network on a complete state loss
     #[arg(short, long)]
     pub network_config_file: Option<String>,
+    /// Where to serve the control endpoint `hotshotctl` talks to. Not served if not given.
+    #[arg(long)]
+    pub control_url: Option<Url>,
+    /// The token requests to the control endpoint must carry. The endpoint is not served without
+    /// one.
+    #[arg(long, env = crate::control::CONTROL_TOKEN_ENV, hide_env_values = true)]
+    pub control_token: Option<String>,
+    /// Only check the run configuration file at this path against its schema, print every field
+    /// which does not match it, and exit without joining the run
+    #[arg(long)]
+    pub validate_config: Option<String>,
+    /// Join the run even if preflight checks fail, see
+    /// [`preflight`](crate::preflight)
+    #[arg(long)]
+    pub force: bool,
 }
 
 /// arguments to run multiple validators
@@ -85,9 +128,15 @@ impl ValidatorArgs {
         Self {
             url: multi_args.url,
             public_ip: multi_args.public_ip,
+            additional_ips: Vec::new(),
+            bind_ips: Vec::new(),
             network_config_file: multi_args
                 .network_config_file
                 .map(|s| format!("{s}-{node_index}")),
+            control_url: None,
+            control_token: None,
+            validate_config: None,
+            force: false,
         }
     }
 }
@@ -96,7 +145,13 @@ impl OrchestratorClient {
     /// Creates the client that will connect to the orchestrator
     #[must_use]
     pub fn new(args: ValidatorArgs, identity: String) -> Self {
-        let client = surf_disco::Client::<ClientError>::new(args.url);
+        Self::connect(args.url, identity)
+    }
+
+    /// Creates a client of the orchestrator at `url`, for tools which are not validators
+    #[must_use]
+    pub fn connect(url: Url, identity: String) -> Self {
+        let client = surf_disco::Client::<ClientError>::new(url);
         // TODO ED: Add healthcheck wait here
         OrchestratorClient { client, identity }
     }
@@ -104,9 +159,6 @@ impl OrchestratorClient {
     /// Sends an identify message to the orchestrator and attempts to get its config
     /// Returns both the `node_index` and the run configuration from the orchestrator
     /// Will block until both are returned
-    /// # Panics
-    /// if unable to convert the node index from usize into u64
-    /// (only applicable on 32 bit systems)
     #[allow(clippy::type_complexity)]
     pub async fn get_config<K: SignatureKey, E: ElectionConfig>(
         &self,
@@ -141,8 +193,11 @@ impl OrchestratorClient {
         let mut config = self.wait_for_fn_from_orchestrator(f).await;
         config.node_index = From::<u16>::from(node_index);
         // The orchestrator will generate keys for validator if it doesn't load keys from file
-        config.config.my_own_validator_config =
-            ValidatorConfig::<K>::generated_from_seed_indexed(config.seed, config.node_index, 1);
+        config.config.my_own_validator_config = ValidatorConfig::<K>::generated_from_seed_indexed(
+            config.seed,
+            config.node_index,
+            config.stake_of(config.node_index),
+        );
 
         config
     }
@@ -150,21 +205,14 @@ impl OrchestratorClient {
     /// Sends my public key to the orchestrator so that it can collect all public keys
     /// And get the updated config
     /// Blocks until the orchestrator collects all peer's public keys/configs
-    /// # Panics
-    /// if unable to post
+    /// # Errors
+    /// if unable to post the key, or to get the updated config once all keys are collected
     pub async fn post_and_wait_all_public_keys<K: SignatureKey, E: ElectionConfig>(
         &self,
         node_index: u64,
         my_pub_key: K,
-    ) -> NetworkConfig<K, E> {
-        // send my public key
-        let _send_pubkey_ready_f: Result<(), ClientError> = self
-            .client
-            .post(&format!("api/pubkey/{node_index}"))
-            .body_binary(&my_pub_key.to_bytes())
-            .unwrap()
-            .send()
-            .await;
+    ) -> Result<NetworkConfig<K, E>, ClientError> {
+        self.post_public_key(node_index, &my_pub_key).await?;
 
         // wait for all nodes' public keys
         let wait_for_all_nodes_pub_key = |client: Client<ClientError>| {
@@ -178,20 +226,41 @@ impl OrchestratorClient {
             .get("api/config_after_peer_collected")
             .send()
             .await
-            .expect("Unable to get the updated config")
+    }
+
+    /// Sends my public key to the orchestrator, without waiting for the other nodes, as nodes
+    /// joining after the run started do
+    /// # Errors
+    /// if unable to post the key
+    pub async fn post_public_key<K: SignatureKey>(
+        &self,
+        node_index: u64,
+        my_pub_key: &K,
+    ) -> Result<(), ClientError> {
+        self.client
+            .post::<()>(&format!("api/pubkey/{node_index}"))
+            .body_binary(&my_pub_key.to_bytes())?
+            .send()
+            .await
+    }
+
+    /// Whether the run has started, so that this node joins it late
+    pub async fn run_started(&self) -> bool {
+        self.client
+            .get::<bool>("api/start")
+            .send()
+            .await
+            .unwrap_or(false)
     }
 
     /// Tells the orchestrator this validator is ready to start
     /// Blocks until the orchestrator indicates all nodes are ready to start
-    /// # Panics
-    /// Panics if unable to post.
     pub async fn wait_for_all_nodes_ready(&self, node_index: u64) -> bool {
         let send_ready_f = |client: Client<ClientError>| {
             async move {
                 let result: Result<_, ClientError> = client
                     .post("api/ready")
-                    .body_json(&node_index)
-                    .unwrap()
+                    .body_json(&node_index)?
                     .send()
                     .await;
                 result
@@ -208,6 +277,230 @@ impl OrchestratorClient {
             .await
     }
 
+    /// Gets the offset of the orchestrator's clock from ours, in milliseconds, using the
+    /// midpoint of the request as our reading. `None` if the orchestrator does not respond.
+    pub async fn get_clock_offset_ms(&self) -> Option<i128> {
+        let sent_ms = unix_time_ms();
+        let result: Result<u64, ClientError> = self.client.get("api/time").send().await;
+        let received_ms = unix_time_ms();
+        let orchestrator_ms = result.ok()?;
+        let local_ms = (i128::from(sent_ms) + i128::from(received_ms)) / 2;
+        Some(i128::from(orchestrator_ms) - local_ms)
+    }
+
+    /// Waits for the wave of node `node_index` in the ramp-up `schedule`, whose first wave started
+    /// at `ramp_up_start_ms` on the orchestrator's clock. Returns at once if the wave has already
+    /// started, e.g. for a node joining late.
+    pub async fn wait_for_wave(
+        &self,
+        schedule: &RampUpSchedule,
+        ramp_up_start_ms: u64,
+        node_index: u64,
+    ) {
+        let offset_ms = self.get_clock_offset_ms().await.unwrap_or(0);
+        let wave_offset_ms =
+            i128::try_from(schedule.start_offset(node_index).as_millis()).unwrap_or(i128::MAX);
+        let wave_local_ms = i128::from(ramp_up_start_ms) - offset_ms + wave_offset_ms;
+        let wait_ms = wave_local_ms - i128::from(unix_time_ms());
+        if wait_ms > 0 {
+            debug!(
+                "Waiting {wait_ms}ms for wave {} of the ramp-up",
+                schedule.wave_of(node_index)
+            );
+            async_sleep(Duration::from_millis(
+                u64::try_from(wait_ms).unwrap_or(u64::MAX),
+            ))
+            .await;
+        }
+    }
+
+    /// Gets the instant at which the run begins, blocking until the run has started.
+    ///
+    /// The orchestrator's clock is compared against ours, using the midpoint of the request as
+    /// our reading, and the returned instant is corrected for the offset between them.
+    pub async fn wait_for_genesis(&self) -> SystemTime {
+        loop {
+            let sent_ms = unix_time_ms();
+            let result: Result<GenesisTimestamp, ClientError> =
+                self.client.get("api/genesis_timestamp").send().await;
+            let received_ms = unix_time_ms();
+            let Ok(genesis) = result else {
+                async_sleep(Duration::from_millis(250)).await;
+                continue;
+            };
+
+            let local_ms = (i128::from(sent_ms) + i128::from(received_ms)) / 2;
+            let offset_ms = i128::from(genesis.orchestrator_now_ms) - local_ms;
+            if offset_ms.abs() > MAX_CLOCK_OFFSET_MS {
+                warn!("Local clock is {offset_ms}ms off the orchestrator's, check that NTP is running");
+            }
+            let genesis_local_ms = (i128::from(genesis.genesis_ms) - offset_ms).max(0);
+            return UNIX_EPOCH
+                + Duration::from_millis(u64::try_from(genesis_local_ms).unwrap_or(u64::MAX));
+        }
+    }
+
+    /// Gets the index of the run currently accepting nodes, or `None` if the campaign has finished
+    /// Blocks until the orchestrator responds
+    pub async fn get_run_index(&self) -> Option<u64> {
+        let f = |client: Client<ClientError>| {
+            async move { client.get("api/run_index").send().await }.boxed()
+        };
+        self.wait_for_fn_from_orchestrator(f).await
+    }
+
+    /// Gets the number of runs of the campaign after run `run_index`, 0 outside of campaigns
+    /// Blocks until the orchestrator responds
+    pub async fn get_runs_after(&self, run_index: u64) -> u64 {
+        let f = |client: Client<ClientError>| {
+            async move {
+                client
+                    .get(&format!("api/runs_after/{run_index}"))
+                    .send()
+                    .await
+            }
+            .boxed()
+        };
+        self.wait_for_fn_from_orchestrator(f).await
+    }
+
+    /// Posts this node's results for the run to the orchestrator
+    /// # Errors
+    /// if unable to serialize the results or to reach the orchestrator
+    pub async fn post_run_results(&self, results: RunResults) -> Result<(), ClientError> {
+        self.client
+            .post("api/results")
+            .body_json(&results)?
+            .send()
+            .await
+    }
+
+    /// Gets the results of every finished run of the campaign
+    /// # Errors
+    /// if unable to reach the orchestrator
+    pub async fn get_campaign_results(&self) -> Result<Vec<CampaignRunResults>, ClientError> {
+        self.client.get("api/campaign_results").send().await
+    }
+
+    /// Posts a heartbeat of this running node to the orchestrator, without retrying, and returns
+    /// the partition the orchestrator orders the node into, if any
+    /// # Errors
+    /// If the orchestrator cannot be reached or does not accept the heartbeat
+    pub async fn post_heartbeat(
+        &self,
+        heartbeat: &Heartbeat,
+    ) -> Result<Option<PartitionOrder>, ClientError> {
+        self.client
+            .post("api/heartbeat")
+            .body_json(heartbeat)?
+            .send()
+            .await
+    }
+
+    /// Posts a heartbeat with the status held by `control` every `interval`, until `running` is
+    /// cleared, and hands the partition orders of the replies to `control`. A heartbeat which
+    /// fails leaves the order in place, so that a partition does not heal early.
+    pub async fn send_heartbeats(
+        &self,
+        control: NodeControlHandle,
+        interval: Duration,
+        running: Arc<AtomicBool>,
+    ) {
+        while running.load(Ordering::Relaxed) {
+            let heartbeat = Heartbeat::from(&control.read().await.status);
+            match self.post_heartbeat(&heartbeat).await {
+                Ok(order) => control.write().await.set_partition_order(order),
+                Err(e) => debug!("Orchestrator did not accept our heartbeat: {e}"),
+            }
+            async_sleep(interval).await;
+        }
+    }
+
+    /// Waits for the orchestrator to move past run `finished_run`.
+    /// Returns whether there is another run of the campaign to register for.
+    pub async fn wait_for_next_run(&self, finished_run: u64) -> bool {
+        loop {
+            match self.get_run_index().await {
+                None => return false,
+                Some(run_index) if run_index > finished_run => return true,
+                Some(_) => async_sleep(Duration::from_millis(250)).await,
+            }
+        }
+    }
+
+    /// Gets the current stake table from the orchestrator, including nodes which joined late
+    /// # Errors
+    /// if unable to reach the orchestrator
+    pub async fn get_stake_table<K: SignatureKey>(
+        &self,
+    ) -> Result<Vec<K::StakeTableEntry>, ClientError> {
+        self.client.get("api/stake_table").send().await
+    }
+
+    /// Gets a snapshot of the current network config, without waiting for all peers.
+    /// Used by nodes joining after the run has started.
+    /// # Errors
+    /// if unable to reach the orchestrator
+    pub async fn get_config_snapshot<K: SignatureKey, E: ElectionConfig>(
+        &self,
+    ) -> Result<NetworkConfig<K, E>, ClientError> {
+        self.client.get("api/config_snapshot").send().await
+    }
+
+    /// Gets the node indices of the nodes which joined after the run started, skipping the
+    /// first `from` of them
+    /// # Errors
+    /// if unable to reach the orchestrator
+    pub async fn get_newcomers(&self, from: usize) -> Result<Vec<u64>, ClientError> {
+        self.client
+            .get(&format!("api/newcomers/{from}"))
+            .send()
+            .await
+    }
+
+    /// Tells the orchestrator that the node of `node_index` leaves the run, so that its index is
+    /// handed out to a node joining later
+    /// # Errors
+    /// if unable to reach the orchestrator, or if the node is not part of the run
+    pub async fn post_leave(&self, node_index: u64) -> Result<(), ClientError> {
+        self.client
+            .post(&format!("api/leave/{node_index}"))
+            .send()
+            .await
+    }
+
+    /// Polls the orchestrator for the nodes which join the run every `interval`, until `running`
+    /// is cleared, and hands the stake table including them to `joined` whenever some did
+    pub async fn watch_newcomers<K: SignatureKey, F, Fut>(
+        &self,
+        interval: Duration,
+        running: Arc<AtomicBool>,
+        mut joined: F,
+    ) where
+        F: FnMut(Vec<u64>, Vec<K::StakeTableEntry>) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut seen = 0;
+        while running.load(Ordering::Relaxed) {
+            async_sleep(interval).await;
+            let newcomers = match self.get_newcomers(seen).await {
+                Ok(newcomers) if !newcomers.is_empty() => newcomers,
+                Ok(_) => continue,
+                Err(e) => {
+                    debug!("Failed to ask the orchestrator for newcomers: {e}");
+                    continue;
+                }
+            };
+            match self.get_stake_table::<K>().await {
+                Ok(stake_table) => {
+                    seen += newcomers.len();
+                    joined(newcomers, stake_table).await;
+                }
+                Err(e) => debug!("Failed to get the stake table from the orchestrator: {e}"),
+            }
+        }
+    }
+
     /// Generic function that waits for the orchestrator to return a non-error
     /// Returns whatever type the given function returns
     async fn wait_for_fn_from_orchestrator<F, Fut, GEN>(&self, f: F) -> GEN
@@ -1,32 +1,56 @@
 use hotshot_types::{
-    traits::{election::ElectionConfig, signature_key::SignatureKey},
+    key_derivation::{DerivedSeeds, KeyDerivationError},
+    payload_store::PayloadSpillConfig,
+    reputation::ReputationRules,
+    rpc::RpcConfig,
+    runtime::RuntimeConfig,
+    statsd::StatsdConfig,
+    storage_sync::StorageSyncConfig,
+    traits::{
+        election::{ElectionConfig, FailureDomains},
+        signature_key::SignatureKey,
+    },
     ExecutionType, HotShotConfig, ValidatorConfig,
 };
+use schemars::JsonSchema;
 use serde_inline_default::serde_inline_default;
 use std::{
+    collections::{BTreeMap, BTreeSet},
     env,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
     num::NonZeroUsize,
     path::PathBuf,
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use std::{fs, path::Path};
 use surf_disco::Url;
 use thiserror::Error;
 use toml;
-use tracing::error;
+use tracing::{error, info};
 
-use crate::client::OrchestratorClient;
+use crate::{client::OrchestratorClient, webhooks::WebhookConfig};
 
 /// Configuration describing a libp2p node
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct Libp2pConfig {
-    /// bootstrap nodes (socket, serialized public key)
-    pub bootstrap_nodes: Vec<(SocketAddr, Vec<u8>)>,
+    /// bootstrap nodes (sockets in every address family they listen on, serialized public key)
+    pub bootstrap_nodes: Vec<(Vec<SocketAddr>, Vec<u8>)>,
     /// number of bootstrap nodes
     pub num_bootstrap_nodes: usize,
-    /// public ip of this node
-    pub public_ip: IpAddr,
+    /// addresses to listen on, e.g. those of the network interfaces to use. When empty, the
+    /// unspecified address of the preferred family, and of the other one with dual stack.
+    #[serde(default)]
+    pub bind_ips: Vec<IpAddr>,
+    /// addresses other nodes reach this node at, when they differ from those it listens on, e.g.
+    /// the elastic IP of a cloud VM listening on `0.0.0.0`
+    #[serde(default)]
+    pub advertised_ips: Vec<IpAddr>,
+    /// the address family dialed first, and listened on first
+    #[serde(default)]
+    pub preferred_ip_family: IpFamily,
+    /// whether to also listen on the other address family
+    #[serde(default)]
+    pub dual_stack: bool,
     /// port to run libp2p on
     pub base_port: u16,
     /// global index of node (for testing purposes a uid)
@@ -49,6 +73,10 @@ pub struct Libp2pConfig {
     pub mesh_outbound_min: usize,
     /// corresponds to libp2p DHT parameter of the same name
     pub mesh_n: usize,
+    /// whether to derive the mesh parameters from the number of nodes instead, ignoring those
+    /// above, and to carry the DA topic on a mesh of its own derived from the DA committee size
+    #[serde(default)]
+    pub mesh_auto_tune: bool,
     /// timeout before starting the next view
     pub next_view_timeout: u64,
     /// minimum time to wait for a view
@@ -59,10 +87,79 @@ pub struct Libp2pConfig {
     pub online_time: u64,
     /// number of transactions per view
     pub num_txn_per_round: usize,
+    /// tuning of the QUIC transport
+    #[serde(default)]
+    pub quic: Libp2pQuicConfig,
+    /// directory each node saves the peers it learns about to, and reconnects to them from
+    /// after a restart
+    #[serde(default)]
+    pub peer_store_dir: Option<String>,
+    /// whether each node admits connections only from the bootstrap nodes and the peers the keys
+    /// of the current stake table resolve to through their signed contact records
+    #[serde(default)]
+    pub peer_allowlist: bool,
+}
+
+impl Libp2pConfig {
+    /// The port node `node_index` listens on, `base_port` offset by the index if `index_ports`
+    #[must_use]
+    pub fn listen_port(&self, node_index: u64) -> u16 {
+        if !self.index_ports {
+            return self.base_port;
+        }
+        self.base_port
+            .saturating_add(u16::try_from(node_index).unwrap_or(u16::MAX))
+    }
+
+    /// The addresses libp2p listens on: `bind_ips` if any, or else the unspecified address of
+    /// the preferred family, and with dual stack, of the other family too
+    #[must_use]
+    pub fn listen_ips(&self) -> Vec<IpAddr> {
+        if !self.bind_ips.is_empty() {
+            return self.bind_ips.clone();
+        }
+        let mut ips = vec![self.preferred_ip_family.unspecified()];
+        if self.dual_stack {
+            ips.push(self.preferred_ip_family.other().unspecified());
+        }
+        ips
+    }
+}
+
+/// Tuning of the QUIC transport of libp2p. Each field defaults to the `libp2p-quic` default,
+/// except the handshake timeout.
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Debug)]
+#[serde(default)]
+pub struct Libp2pQuicConfig {
+    /// time allowed for the QUIC handshake of a new connection
+    pub handshake_timeout: Duration,
+    /// a connection with no activity for this long is closed
+    pub max_idle_timeout: Duration,
+    /// interval between keep-alive packets on an idle connection
+    pub keep_alive_interval: Duration,
+    /// maximum number of concurrent incoming streams per connection
+    pub max_concurrent_stream_limit: u32,
+    /// flow control window of a single stream, in bytes
+    pub max_stream_data: u32,
+    /// flow control window of a whole connection, in bytes
+    pub max_connection_data: u32,
+}
+
+impl Default for Libp2pQuicConfig {
+    fn default() -> Self {
+        Self {
+            handshake_timeout: Duration::from_secs(20),
+            max_idle_timeout: Duration::from_secs(10),
+            keep_alive_interval: Duration::from_secs(5),
+            max_concurrent_stream_limit: 256,
+            max_stream_data: 10_000_000,
+            max_connection_data: 15_000_000,
+        }
+    }
 }
 
 /// configuration serialized into a file
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Debug)]
 pub struct Libp2pConfigFile {
     /// whether or not to index ports
     pub index_ports: bool,
@@ -82,19 +179,115 @@ pub struct Libp2pConfigFile {
     pub mesh_outbound_min: usize,
     /// corresponds to libp2p DHT parameter of the same name
     pub mesh_n: usize,
+    /// whether to derive the mesh parameters from the number of nodes instead, ignoring those
+    /// above
+    #[serde(default)]
+    pub mesh_auto_tune: bool,
     /// time node has been running
     pub online_time: u64,
     /// port to run libp2p on
     pub base_port: u16,
+    /// tuning of the QUIC transport
+    #[serde(default)]
+    pub quic: Libp2pQuicConfig,
+    /// the address family dialed first, and listened on first
+    #[serde(default)]
+    pub preferred_ip_family: IpFamily,
+    /// whether to also listen on the other address family
+    #[serde(default)]
+    pub dual_stack: bool,
+    /// directory each node saves the peers it learns about to, and reconnects to them from
+    /// after a restart
+    #[serde(default)]
+    pub peer_store_dir: Option<String>,
+    /// whether each node admits connections only from the bootstrap nodes and the peers the keys
+    /// of the current stake table resolve to through their signed contact records
+    #[serde(default)]
+    pub peer_allowlist: bool,
+}
+
+/// An IP address family
+#[derive(
+    serde::Serialize, serde::Deserialize, JsonSchema, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    /// IPv4
+    #[default]
+    Ipv4,
+    /// IPv6
+    Ipv6,
+}
+
+impl IpFamily {
+    /// The family of `ip`
+    #[must_use]
+    pub fn of(ip: &IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(_) => Self::Ipv4,
+            IpAddr::V6(_) => Self::Ipv6,
+        }
+    }
+
+    /// The other family
+    #[must_use]
+    pub fn other(self) -> Self {
+        match self {
+            Self::Ipv4 => Self::Ipv6,
+            Self::Ipv6 => Self::Ipv4,
+        }
+    }
+
+    /// The unspecified address of this family, to listen on every interface
+    #[must_use]
+    pub fn unspecified(self) -> IpAddr {
+        match self {
+            Self::Ipv4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            Self::Ipv6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+}
+
+/// Order `addrs` so that those of the `preferred` family come first, keeping the order within
+/// each family
+pub fn prefer_family(addrs: &mut [SocketAddr], preferred: IpFamily) {
+    addrs.sort_by_key(|addr| IpFamily::of(&addr.ip()) != preferred);
+}
+
+/// A network a channel of the example runs can run over
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkType {
+    /// the web server, and its shards
+    WebServer,
+    /// libp2p
+    Libp2p,
+    /// the web server, falling back to libp2p
+    Combined,
 }
 
 /// configuration for a web server
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Debug)]
 pub struct WebServerConfig {
     /// the url to run on
+    #[schemars(with = "String")]
     pub url: Url,
     /// the time to wait between polls
     pub wait_between_polls: Duration,
+    /// the urls of further web servers to shard messages across, alongside `url`
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub shard_urls: Vec<Url>,
+}
+
+impl WebServerConfig {
+    /// the urls of every shard of the web server, `url` first
+    #[must_use]
+    pub fn urls(&self) -> Vec<Url> {
+        std::iter::once(self.url.clone())
+            .chain(self.shard_urls.iter().cloned())
+            .collect()
+    }
 }
 
 /// a network configuration error
@@ -115,6 +308,15 @@ pub enum NetworkConfigError {
     /// Failed to recursively create path to NetworkConfig
     #[error("Failed to recursively create path to NetworkConfig")]
     FailedToCreatePath(std::io::Error),
+    /// Failed to deserialize a campaign file
+    #[error("Failed to deserialize campaign file")]
+    CampaignDeserializeError(toml::de::Error),
+    /// Failed to deserialize a run configuration file
+    #[error("Failed to deserialize run configuration file: {0}")]
+    RunConfigDeserializeError(toml::de::Error),
+    /// The keys of a run configuration cannot be derived
+    #[error("Invalid keys in run configuration: {0}")]
+    KeyDerivationError(KeyDerivationError),
 }
 
 /// a network configuration
@@ -137,10 +339,108 @@ pub struct NetworkConfig<KEY: SignatureKey, ELECTIONCONFIG: ElectionConfig> {
     pub node_index: u64,
     /// unique seed (for randomness? TODO)
     pub seed: [u8; 32],
+    /// seed the libp2p identities of the nodes are generated from
+    #[serde(default)]
+    pub libp2p_seed: [u8; 32],
     /// size of transactions
     pub transaction_size: usize,
     /// delay before beginning consensus
     pub start_delay_seconds: u64,
+    /// number of nodes which must be ready before the run starts, the rest may join late.
+    /// If `None`, every node must be ready.
+    #[serde(default)]
+    pub start_threshold: Option<usize>,
+    /// if set, the nodes bring up their networking in waves once every public key is posted,
+    /// instead of all at once
+    #[serde(default)]
+    pub ramp_up: Option<RampUpSchedule>,
+    /// wall-clock instant, in milliseconds since the unix epoch, at which the first wave of the
+    /// ramp-up starts. Set by the orchestrator once every public key is posted.
+    #[serde(default)]
+    pub ramp_up_start_ms: Option<u64>,
+    /// if set, run for this many seconds after the warm-up instead of for `rounds` views
+    #[serde(default)]
+    pub run_duration_seconds: Option<u64>,
+    /// time after starting consensus which is excluded from the run's statistics
+    #[serde(default)]
+    pub warmup_seconds: u64,
+    /// interval at which throughput samples are taken, 0 to disable sampling
+    #[serde(default = "default_sample_interval_seconds")]
+    pub sample_interval_seconds: u64,
+    /// if set, each node writes its leaf DAG to `<prefix>-<node index>.{dot,json}` at shutdown
+    #[serde(default)]
+    pub leaf_dag_export: Option<String>,
+    /// if set, the webhooks each node notifies of decided leaves
+    #[serde(default)]
+    pub webhooks: Option<WebhookConfig>,
+    /// if set, the statsd sink each node pushes its consensus and networking metrics to
+    #[serde(default)]
+    pub statsd: Option<StatsdConfig>,
+    /// interval at which nodes send heartbeats to the orchestrator during the run, 0 to disable
+    #[serde(default = "default_heartbeat_interval_seconds")]
+    pub heartbeat_interval_seconds: u64,
+    /// time without heartbeats, or without a new decide, after which the orchestrator reports a
+    /// node as crashed, or stalled
+    #[serde(default = "default_stall_timeout_seconds")]
+    pub stall_timeout_seconds: u64,
+    /// time after the first node of a run posts its results after which the run is over, even
+    /// if some nodes, which may have crashed, never post theirs
+    #[serde(default = "default_results_timeout_seconds")]
+    pub results_timeout_seconds: u64,
+    /// stake of each node, by node index. Nodes past the end of the list have a stake of 1.
+    #[serde(default)]
+    pub stake_weights: Vec<u64>,
+    /// failure domain of each node, e.g. its region or provider, by node index. Nodes with an
+    /// empty label or past the end of the list are not constrained.
+    #[serde(default)]
+    pub failure_domains: Vec<String>,
+    /// if set, the maximum number of DA committee members from a single failure domain
+    #[serde(default)]
+    pub max_da_nodes_per_domain: Option<usize>,
+    /// if set, leaders which fail to get their proposals decided are down-weighted in the
+    /// rotation under these rules
+    #[serde(default)]
+    pub leader_reputation: Option<ReputationRules>,
+    /// how the work of each node is spread over threads, by default all on its main runtime
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+    /// if set, the `genesis.json` the network is started from, which replaces the stake table
+    #[serde(default)]
+    pub genesis_file: Option<String>,
+    /// if set, the directory each node records the messages of its networks to, as traces to
+    /// replay with a `PlaybackNetwork`
+    #[serde(default)]
+    pub network_trace_dir: Option<String>,
+    /// crashes to inject during the run; each node restarts from its storage once it is back up
+    #[serde(default)]
+    pub failure_schedule: Vec<ScheduledFailure>,
+    /// partitions the orchestrator orders during the run, in its replies to the heartbeats of the
+    /// nodes, which must be enabled; each node checks consensus resumes once they heal
+    #[serde(default)]
+    pub partition_schedule: Vec<ScheduledPartition>,
+    /// settings which differ for some nodes, merged into the configuration of each node by the
+    /// orchestrator
+    #[serde(default)]
+    pub node_overrides: Vec<NodeOverride>,
+    /// if set, the transactions this node submits per round instead of its share of
+    /// `transactions_per_round`, from a node override
+    #[serde(default)]
+    pub node_transactions_per_round: Option<usize>,
+    /// if set, each node adjusts the transactions it submits to what consensus keeps up with,
+    /// instead of submitting `transactions_per_round`
+    #[serde(default)]
+    pub load_generator: Option<LoadGeneratorConfig>,
+    /// if set, each node samples its resource usage through the run and fails it if any of
+    /// them trends upward, see [`SoakMonitor`](crate::soak::SoakMonitor)
+    #[serde(default)]
+    pub soak: Option<SoakConfig>,
+    /// how repeated warnings, e.g. of timed out views, are coalesced in the logs of each node
+    #[serde(default)]
+    pub log_throttle: LogThrottleConfig,
+    /// wall-clock instant, in milliseconds since the unix epoch, at which every node begins
+    /// consensus. Set by the orchestrator once the run starts.
+    #[serde(default)]
+    pub genesis_timestamp_ms: Option<u64>,
     /// name of the key type (for debugging)
     pub key_type_name: String,
     /// election config type (for debugging)
@@ -153,6 +453,328 @@ pub struct NetworkConfig<KEY: SignatureKey, ELECTIONCONFIG: ElectionConfig> {
     pub web_server_config: Option<WebServerConfig>,
     /// the data availability web server config
     pub da_web_server_config: Option<WebServerConfig>,
+    /// the network the quorum channel runs over, checked by the validators which can mix
+    /// networks; if unset, that of the example run
+    #[serde(default)]
+    pub quorum_network_type: Option<NetworkType>,
+    /// the network the DA channel runs over, checked by the validators which can mix networks;
+    /// if unset, that of the example run
+    #[serde(default)]
+    pub da_network_type: Option<NetworkType>,
+    /// whether combined networks send timeout votes and view sync messages over the web server
+    /// even while it is presumed down, which other messages skip
+    #[serde(default = "default_web_server_view_change_relay")]
+    pub web_server_view_change_relay: bool,
+}
+
+/// A crash of one node, injected during a run to exercise recovery
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduledFailure {
+    /// index of the node which crashes
+    pub node_index: u64,
+    /// the node crashes once it reaches this view
+    pub view: u64,
+    /// how long the node stays down before restarting
+    pub down_seconds: u64,
+}
+
+/// A partition of the network, injected during a run to check consensus recovers once it heals
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledPartition {
+    /// the view at which each node cuts itself off from the nodes outside its group
+    pub view: u64,
+    /// how long the partition lasts; views may not advance meanwhile, so it heals on a timer
+    pub duration_seconds: u64,
+    /// the groups of nodes, by index, which can only reach each other; the nodes listed in no
+    /// group make up one more group
+    pub groups: Vec<Vec<u64>>,
+}
+
+impl ScheduledPartition {
+    /// The nodes, of `total_nodes`, which node `node_index` can reach during the partition
+    #[must_use]
+    pub fn reachable(&self, node_index: u64, total_nodes: u64) -> BTreeSet<u64> {
+        let listed = |node: &u64| self.groups.iter().flatten().any(|n| n == node);
+        match self.groups.iter().find(|group| group.contains(&node_index)) {
+            Some(group) => group
+                .iter()
+                .copied()
+                .filter(|node| *node < total_nodes)
+                .collect(),
+            None => (0..total_nodes).filter(|node| !listed(node)).collect(),
+        }
+    }
+}
+
+/// What the orchestrator orders a node to do about the partitions of the run, in reply to its
+/// heartbeats
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PartitionOrder {
+    /// the index of the partition in force, in the partition schedule
+    pub index: usize,
+    /// the nodes, by index, the node may still exchange messages with
+    pub reachable: BTreeSet<u64>,
+}
+
+/// Tracks which partitions of a schedule are in force, so that every node is ordered into the
+/// same partition at the same time, whatever view it is in
+#[derive(Clone, Debug, Default)]
+pub struct PartitionTracker {
+    /// the partitions of the run
+    schedule: Vec<ScheduledPartition>,
+    /// when each partition started, in milliseconds since the unix epoch
+    started_ms: Vec<Option<u64>>,
+    /// the number of nodes of the run
+    total_nodes: u64,
+}
+
+impl PartitionTracker {
+    /// Track the partitions of `schedule`, among `total_nodes` nodes
+    #[must_use]
+    pub fn new(schedule: Vec<ScheduledPartition>, total_nodes: u64) -> Self {
+        Self {
+            started_ms: vec![None; schedule.len()],
+            schedule,
+            total_nodes,
+        }
+    }
+
+    /// Start the partitions node `node_index`, in view `view`, is the first to reach at
+    /// `now_ms`, and return the order for the node: the latest partition still in force, if any
+    pub fn order(&mut self, node_index: u64, view: u64, now_ms: u64) -> Option<PartitionOrder> {
+        for (index, (partition, started)) in self
+            .schedule
+            .iter()
+            .zip(self.started_ms.iter_mut())
+            .enumerate()
+        {
+            if started.is_none() && view >= partition.view {
+                info!(
+                    "Node {node_index} reached view {view}, starting partition {index} for {}s",
+                    partition.duration_seconds
+                );
+                *started = Some(now_ms);
+            }
+        }
+        self.schedule
+            .iter()
+            .zip(&self.started_ms)
+            .enumerate()
+            .rev()
+            .find(|(_, (partition, started))| {
+                started.is_some_and(|started| {
+                    now_ms < started.saturating_add(partition.duration_seconds * 1000)
+                })
+            })
+            .map(|(index, (partition, _))| PartitionOrder {
+                index,
+                reachable: partition.reachable(node_index, self.total_nodes),
+            })
+    }
+}
+
+/// A staggered start of the nodes of a run: node `i` brings up its networking in wave
+/// `i / wave_size`, `wave_interval_seconds` after the wave before it, so that large runs do not
+/// all hit the bootstrap nodes and the web server at once. Consensus still begins for every node
+/// at the same instant, once the last wave is ready.
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RampUpSchedule {
+    /// nodes starting in each wave, taken in order of their index
+    pub wave_size: u64,
+    /// seconds between the starts of consecutive waves
+    pub wave_interval_seconds: u64,
+}
+
+impl RampUpSchedule {
+    /// The wave, counting from 0, node `node_index` starts in
+    #[must_use]
+    pub fn wave_of(&self, node_index: u64) -> u64 {
+        node_index / self.wave_size.max(1)
+    }
+
+    /// How long after the first wave node `node_index` starts
+    #[must_use]
+    pub fn start_offset(&self, node_index: u64) -> Duration {
+        Duration::from_secs(
+            self.wave_of(node_index)
+                .saturating_mul(self.wave_interval_seconds),
+        )
+    }
+}
+
+/// Closed-loop rate control of the transactions each node submits, instead of a fixed number per
+/// round: see [`LoadController`](crate::load::LoadController)
+#[serde_inline_default]
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadGeneratorConfig {
+    /// transactions submitted after the first decide
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_LOAD_INITIAL_RATE)]
+    pub initial_rate: u64,
+    /// the fewest transactions submitted after a decide
+    #[serde_inline_default(1)]
+    pub min_rate: u64,
+    /// the most transactions submitted after a decide
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_LOAD_MAX_RATE)]
+    pub max_rate: u64,
+    /// transactions added to the rate after each decide consensus keeps up with
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_LOAD_INITIAL_RATE)]
+    pub increase_step: u64,
+    /// percentage the rate is cut by after each decide consensus falls behind on
+    #[serde_inline_default(50)]
+    pub decrease_percent: u64,
+    /// mempool depth, in transactions, beyond which consensus is falling behind
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_LOAD_TARGET_MEMPOOL_DEPTH)]
+    pub target_mempool_depth: usize,
+    /// expected decide latency, in milliseconds, beyond which consensus is falling behind
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_LOAD_MAX_DECIDE_LATENCY_MS)]
+    pub max_decide_latency_ms: u64,
+}
+
+impl Default for LoadGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            initial_rate: ORCHESTRATOR_DEFAULT_LOAD_INITIAL_RATE,
+            min_rate: 1,
+            max_rate: ORCHESTRATOR_DEFAULT_LOAD_MAX_RATE,
+            increase_step: ORCHESTRATOR_DEFAULT_LOAD_INITIAL_RATE,
+            decrease_percent: 50,
+            target_mempool_depth: ORCHESTRATOR_DEFAULT_LOAD_TARGET_MEMPOOL_DEPTH,
+            max_decide_latency_ms: ORCHESTRATOR_DEFAULT_LOAD_MAX_DECIDE_LATENCY_MS,
+        }
+    }
+}
+
+/// Resource leak detection over a long run: see [`SoakMonitor`](crate::soak::SoakMonitor).
+/// Growth is the trend of a metric over the samples taken after the warm-up, not its peak.
+#[serde_inline_default]
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoakConfig {
+    /// seconds between samples of the resource usage of the node
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SOAK_SAMPLE_INTERVAL_SECONDS)]
+    pub sample_interval_seconds: u64,
+    /// samples taken before trends are measured, while the node builds up its working set
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SOAK_WARMUP_SAMPLES)]
+    pub warmup_samples: usize,
+    /// most the resident memory may grow, as a percentage of its size after the warm-up
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SOAK_MAX_RSS_GROWTH_PERCENT)]
+    pub max_rss_growth_percent: u64,
+    /// most open file descriptors may be added
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SOAK_MAX_FD_GROWTH)]
+    pub max_fd_growth: u64,
+    /// most threads and tasks, each, may be added
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SOAK_MAX_TASK_GROWTH)]
+    pub max_task_growth: u64,
+    /// most events the event channels of the node may queue up
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SOAK_MAX_CHANNEL_DEPTH_GROWTH)]
+    pub max_channel_depth_growth: u64,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            sample_interval_seconds: ORCHESTRATOR_DEFAULT_SOAK_SAMPLE_INTERVAL_SECONDS,
+            warmup_samples: ORCHESTRATOR_DEFAULT_SOAK_WARMUP_SAMPLES,
+            max_rss_growth_percent: ORCHESTRATOR_DEFAULT_SOAK_MAX_RSS_GROWTH_PERCENT,
+            max_fd_growth: ORCHESTRATOR_DEFAULT_SOAK_MAX_FD_GROWTH,
+            max_task_growth: ORCHESTRATOR_DEFAULT_SOAK_MAX_TASK_GROWTH,
+            max_channel_depth_growth: ORCHESTRATOR_DEFAULT_SOAK_MAX_CHANNEL_DEPTH_GROWTH,
+        }
+    }
+}
+
+/// The role of the nodes a [`NodeOverride`] applies to
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    /// the first `da_committee_size` nodes, which form the DA committee unless it is sampled
+    /// across failure domains
+    Da,
+    /// the other nodes
+    NonDa,
+}
+
+/// Settings which differ for some nodes of a run. An override applies to the nodes listed in
+/// `nodes` which have the role `role`, to any node if neither is set; later overrides take
+/// precedence over earlier ones.
+#[derive(
+    serde::Serialize, serde::Deserialize, JsonSchema, Clone, Debug, Default, PartialEq, Eq,
+)]
+pub struct NodeOverride {
+    /// indices of the nodes the override applies to, all nodes if empty
+    #[serde(default)]
+    pub nodes: Vec<u64>,
+    /// role of the nodes the override applies to, any role if unset
+    #[serde(default)]
+    pub role: Option<NodeRole>,
+    /// transactions each of the nodes submits per round, instead of its share of
+    /// `transactions_per_round`
+    #[serde(default)]
+    pub transactions_per_round: Option<usize>,
+    /// size of the transactions the nodes submit
+    #[serde(default)]
+    pub transaction_size: Option<usize>,
+    /// failure domain of the nodes, e.g. their region, replacing their entry of `failure_domains`
+    #[serde(default)]
+    pub failure_domain: Option<String>,
+    /// stake of the nodes, replacing their entry of `stake_weights`
+    #[serde(default)]
+    pub stake: Option<u64>,
+}
+
+impl NodeOverride {
+    /// Whether the override applies to the node with index `node_index`, in a run whose DA
+    /// committee has `da_committee_size` nodes
+    #[must_use]
+    pub fn applies_to(&self, node_index: u64, da_committee_size: usize) -> bool {
+        let is_da = usize::try_from(node_index).map_or(false, |index| index < da_committee_size);
+        (self.nodes.is_empty() || self.nodes.contains(&node_index))
+            && match self.role {
+                None => true,
+                Some(NodeRole::Da) => is_da,
+                Some(NodeRole::NonDa) => !is_da,
+            }
+    }
+}
+
+/// How repeated log messages are coalesced into periodic summaries
+#[serde_inline_default]
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct LogThrottleConfig {
+    /// seconds during which repeated messages of a target are logged once, 0 to log them all
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_LOG_THROTTLE_WINDOW_SECONDS)]
+    pub window_seconds: u64,
+    /// windows of specific targets, e.g. `view_timeout`, overriding `window_seconds`
+    #[serde(default)]
+    pub targets: BTreeMap<String, u64>,
+}
+
+impl Default for LogThrottleConfig {
+    fn default() -> Self {
+        Self {
+            window_seconds: ORCHESTRATOR_DEFAULT_LOG_THROTTLE_WINDOW_SECONDS,
+            targets: BTreeMap::new(),
+        }
+    }
+}
+
+/// The genesis timestamp of a run, together with the orchestrator's clock at the time of the
+/// response so that nodes can check their own clock against it
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
+pub struct GenesisTimestamp {
+    /// milliseconds since the unix epoch at which the run begins
+    pub genesis_ms: u64,
+    /// milliseconds since the unix epoch on the orchestrator's clock
+    pub orchestrator_now_ms: u64,
+}
+
+/// Milliseconds since the unix epoch on the local clock
+#[must_use]
+pub fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
 }
 
 /// the source of the network config
@@ -164,6 +786,80 @@ pub enum NetworkConfigSource {
 }
 
 impl<K: SignatureKey, E: ElectionConfig> NetworkConfig<K, E> {
+    /// The stake of the node with index `node_index`
+    #[must_use]
+    pub fn stake_of(&self, node_index: u64) -> u64 {
+        usize::try_from(node_index)
+            .ok()
+            .and_then(|index| self.stake_weights.get(index))
+            .copied()
+            .unwrap_or(1)
+    }
+
+    /// The overrides which apply to the node with index `node_index`, in order of precedence
+    fn overrides_of(&self, node_index: u64) -> impl Iterator<Item = &NodeOverride> {
+        let da_committee_size = self.config.da_committee_size;
+        self.node_overrides
+            .iter()
+            .filter(move |node_override| node_override.applies_to(node_index, da_committee_size))
+    }
+
+    /// Apply the stakes and failure domains of the node overrides to the stake table and
+    /// failure domains of the run, which every node needs. Run configurations read from a file
+    /// have them applied already.
+    pub fn apply_run_overrides(&mut self) {
+        for node_index in 0..self.config.total_nodes.get() {
+            let mut stake = None;
+            let mut failure_domain = None;
+            for node_override in self.overrides_of(node_index as u64) {
+                stake = node_override.stake.or(stake);
+                failure_domain = node_override.failure_domain.clone().or(failure_domain);
+            }
+            if let Some(stake) = stake {
+                if self.stake_weights.len() <= node_index {
+                    self.stake_weights.resize(node_index + 1, 1);
+                }
+                self.stake_weights[node_index] = stake;
+            }
+            if let Some(failure_domain) = failure_domain {
+                if self.failure_domains.len() <= node_index {
+                    self.failure_domains.resize(node_index + 1, String::new());
+                }
+                self.failure_domains[node_index] = failure_domain;
+            }
+        }
+    }
+
+    /// The configuration of the node with index `node_index`, with the settings of the node
+    /// overrides which apply to it
+    #[must_use]
+    pub fn for_node(&self, node_index: u64) -> Self {
+        let mut config = self.clone();
+        for node_override in self.overrides_of(node_index) {
+            if let Some(transactions_per_round) = node_override.transactions_per_round {
+                config.node_transactions_per_round = Some(transactions_per_round);
+            }
+            if let Some(transaction_size) = node_override.transaction_size {
+                config.transaction_size = transaction_size;
+            }
+        }
+        config
+    }
+
+    /// The failure domains the DA committee is sampled under, if a maximum per domain is set
+    #[must_use]
+    pub fn da_failure_domains(&self) -> Option<FailureDomains> {
+        self.max_da_nodes_per_domain
+            .map(|max_per_domain| FailureDomains {
+                labels: self
+                    .failure_domains
+                    .iter()
+                    .map(|label| (!label.is_empty()).then(|| label.clone()))
+                    .collect(),
+                max_per_domain,
+            })
+    }
+
     /// Asynchronously retrieves a `NetworkConfig` either from a file or from an orchestrator.
     ///
     /// This function takes an `OrchestratorClient`, an identity string, and an optional file path.
@@ -274,6 +970,21 @@ impl<K: SignatureKey, E: ElectionConfig> NetworkConfig<K, E> {
         }
     }
 
+    /// Loads the run configuration the orchestrator hands out from the TOML file `file`, of
+    /// [`NetworkConfigFile`]
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be read or deserialized, or its keys cannot be derived
+    pub fn from_run_config_file(file: &str) -> Result<Self, NetworkConfigError> {
+        let data = fs::read_to_string(file).map_err(NetworkConfigError::ReadFromFileError)?;
+        let config_file: NetworkConfigFile<K> =
+            toml::from_str(&data).map_err(NetworkConfigError::RunConfigDeserializeError)?;
+        config_file
+            .try_into()
+            .map_err(NetworkConfigError::KeyDerivationError)
+    }
+
     /// Serializes the `NetworkConfig` and writes it to a file.
     ///
     /// This function takes a file path as a string, serializes the `NetworkConfig` into JSON format using `serde_json` and then writes the serialized data to the file.
@@ -329,14 +1040,45 @@ impl<K: SignatureKey, E: ElectionConfig> Default for NetworkConfig<K, E> {
             transactions_per_round: ORCHESTRATOR_DEFAULT_TRANSACTIONS_PER_ROUND,
             node_index: 0,
             seed: [0u8; 32],
+            libp2p_seed: [0u8; 32],
             transaction_size: ORCHESTRATOR_DEFAULT_TRANSACTION_SIZE,
             libp2p_config: None,
             config: HotShotConfigFile::default().into(),
             start_delay_seconds: 60,
+            start_threshold: None,
+            ramp_up: None,
+            ramp_up_start_ms: None,
+            run_duration_seconds: None,
+            warmup_seconds: 0,
+            sample_interval_seconds: ORCHESTRATOR_DEFAULT_SAMPLE_INTERVAL_SECONDS,
+            leaf_dag_export: None,
+            webhooks: None,
+            statsd: None,
+            heartbeat_interval_seconds: ORCHESTRATOR_DEFAULT_HEARTBEAT_INTERVAL_SECONDS,
+            stall_timeout_seconds: ORCHESTRATOR_DEFAULT_STALL_TIMEOUT_SECONDS,
+            results_timeout_seconds: ORCHESTRATOR_DEFAULT_RESULTS_TIMEOUT_SECONDS,
+            stake_weights: Vec::new(),
+            failure_domains: Vec::new(),
+            max_da_nodes_per_domain: None,
+            leader_reputation: None,
+            runtime: RuntimeConfig::default(),
+            genesis_file: None,
+            network_trace_dir: None,
+            failure_schedule: Vec::new(),
+            partition_schedule: Vec::new(),
+            node_overrides: Vec::new(),
+            node_transactions_per_round: None,
+            load_generator: None,
+            soak: None,
+            log_throttle: LogThrottleConfig::default(),
+            genesis_timestamp_ms: None,
             key_type_name: std::any::type_name::<K>().to_string(),
             election_config_type_name: std::any::type_name::<E>().to_string(),
             web_server_config: None,
             da_web_server_config: None,
+            quorum_network_type: None,
+            da_network_type: None,
+            web_server_view_change_relay: true,
             next_view_timeout: 10,
             num_bootrap: 5,
             propose_min_round_time: Duration::from_secs(0),
@@ -347,8 +1089,9 @@ impl<K: SignatureKey, E: ElectionConfig> Default for NetworkConfig<K, E> {
 
 /// a network config stored in a file
 #[serde_inline_default]
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, JsonSchema, Debug, Clone)]
 #[serde(bound(deserialize = ""))]
+#[schemars(bound = "", rename = "NetworkConfigFile")]
 pub struct NetworkConfigFile<KEY: SignatureKey> {
     /// number of views to run
     #[serde_inline_default(ORCHESTRATOR_DEFAULT_NUM_ROUNDS)]
@@ -362,12 +1105,96 @@ pub struct NetworkConfigFile<KEY: SignatureKey> {
     /// unique seed (for randomness? TODO)
     #[serde(default)]
     pub seed: [u8; 32],
+    /// if set, the BIP-39 mnemonic the keys of the nodes are derived from, instead of `seed`
+    #[serde(default)]
+    pub mnemonic: Option<String>,
     /// size of transactions
     #[serde_inline_default(ORCHESTRATOR_DEFAULT_TRANSACTION_SIZE)]
     pub transaction_size: usize,
     /// delay before beginning consensus
     #[serde_inline_default(ORCHESTRATOR_DEFAULT_START_DELAY_SECONDS)]
     pub start_delay_seconds: u64,
+    /// number of nodes which must be ready before the run starts, the rest may join late
+    #[serde(default)]
+    pub start_threshold: Option<usize>,
+    /// bring up the networking of the nodes in waves of `wave_size` nodes, one every
+    /// `wave_interval_seconds`, instead of all at once
+    #[serde(default)]
+    pub ramp_up: Option<RampUpSchedule>,
+    /// if set, run for this many seconds after the warm-up instead of for `rounds` views
+    #[serde(default)]
+    pub run_duration_seconds: Option<u64>,
+    /// time after starting consensus which is excluded from the run's statistics
+    #[serde(default)]
+    pub warmup_seconds: u64,
+    /// interval at which throughput samples are taken, 0 to disable sampling
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SAMPLE_INTERVAL_SECONDS)]
+    pub sample_interval_seconds: u64,
+    /// if set, each node writes its leaf DAG to `<prefix>-<node index>.{dot,json}` at shutdown
+    #[serde(default)]
+    pub leaf_dag_export: Option<String>,
+    /// if set, the webhooks each node notifies of decided leaves
+    #[serde(default)]
+    pub webhooks: Option<WebhookConfig>,
+    /// if set, the statsd sink each node pushes its consensus and networking metrics to
+    #[serde(default)]
+    pub statsd: Option<StatsdConfig>,
+    /// interval at which nodes send heartbeats to the orchestrator during the run, 0 to disable
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_HEARTBEAT_INTERVAL_SECONDS)]
+    pub heartbeat_interval_seconds: u64,
+    /// time without heartbeats, or without a new decide, after which the orchestrator reports a
+    /// node as crashed, or stalled
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_STALL_TIMEOUT_SECONDS)]
+    pub stall_timeout_seconds: u64,
+    /// time after the first node of a run posts its results after which the run is over, even
+    /// if some nodes, which may have crashed, never post theirs
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_RESULTS_TIMEOUT_SECONDS)]
+    pub results_timeout_seconds: u64,
+    /// stake of each node, by node index. Nodes past the end of the list have a stake of 1.
+    #[serde(default)]
+    pub stake_weights: Vec<u64>,
+    /// failure domain of each node, e.g. its region or provider, by node index. Nodes with an
+    /// empty label or past the end of the list are not constrained.
+    #[serde(default)]
+    pub failure_domains: Vec<String>,
+    /// if set, the maximum number of DA committee members from a single failure domain
+    #[serde(default)]
+    pub max_da_nodes_per_domain: Option<usize>,
+    /// if set, leaders which fail to get their proposals decided are down-weighted in the
+    /// rotation under these rules
+    #[serde(default)]
+    pub leader_reputation: Option<ReputationRules>,
+    /// how the work of each node is spread over threads, by default all on its main runtime
+    #[serde(default)]
+    pub runtime: RuntimeConfig,
+    /// if set, the `genesis.json` the network is started from, which replaces the stake table
+    #[serde(default)]
+    pub genesis_file: Option<String>,
+    /// if set, the directory each node records the messages of its networks to, as traces to
+    /// replay with a `PlaybackNetwork`
+    #[serde(default)]
+    pub network_trace_dir: Option<String>,
+    /// crashes to inject during the run; each node restarts from its storage once it is back up
+    #[serde(default)]
+    pub failure_schedule: Vec<ScheduledFailure>,
+    /// partitions the orchestrator orders during the run, in its replies to the heartbeats of the
+    /// nodes, which must be enabled; each node checks consensus resumes once they heal
+    #[serde(default)]
+    pub partition_schedule: Vec<ScheduledPartition>,
+    /// settings which differ for some nodes, as `[[node_override]]` sections
+    #[serde(default, rename = "node_override")]
+    pub node_overrides: Vec<NodeOverride>,
+    /// if set, each node adjusts the transactions it submits to what consensus keeps up with,
+    /// instead of submitting `transactions_per_round`
+    #[serde(default)]
+    pub load_generator: Option<LoadGeneratorConfig>,
+    /// if set, each node samples its resource usage through the run and fails it if any of
+    /// them trends upward
+    #[serde(default)]
+    pub soak: Option<SoakConfig>,
+    /// how repeated warnings, e.g. of timed out views, are coalesced in the logs of each node
+    #[serde(default)]
+    pub log_throttle: LogThrottleConfig,
     /// the libp2p config
     #[serde(default)]
     pub libp2p_config: Option<Libp2pConfigFile>,
@@ -380,11 +1207,36 @@ pub struct NetworkConfigFile<KEY: SignatureKey> {
     /// the data availability web server config
     #[serde(default)]
     pub da_web_server_config: Option<WebServerConfig>,
+    /// the network the quorum channel runs over, e.g. libp2p for votes
+    #[serde(default)]
+    pub quorum_network_type: Option<NetworkType>,
+    /// the network the DA channel runs over, e.g. the web server for bulk data
+    #[serde(default)]
+    pub da_network_type: Option<NetworkType>,
+    /// whether combined networks send timeout votes and view sync messages over the web server
+    /// even while it is presumed down, so that views still change if libp2p is partitioned
+    #[serde(default = "default_web_server_view_change_relay")]
+    pub web_server_view_change_relay: bool,
 }
 
-impl<K: SignatureKey, E: ElectionConfig> From<NetworkConfigFile<K>> for NetworkConfig<K, E> {
-    fn from(val: NetworkConfigFile<K>) -> Self {
-        NetworkConfig {
+impl<K: SignatureKey> NetworkConfigFile<K> {
+    /// The seeds the keys of the nodes are generated from, derived from `mnemonic` if one is set
+    /// # Errors
+    /// If `mnemonic` is not a valid BIP-39 mnemonic
+    pub fn seeds(&self) -> Result<DerivedSeeds, KeyDerivationError> {
+        match &self.mnemonic {
+            Some(phrase) => DerivedSeeds::from_mnemonic(phrase),
+            None => Ok(DerivedSeeds::from_raw(self.seed)),
+        }
+    }
+}
+
+impl<K: SignatureKey, E: ElectionConfig> TryFrom<NetworkConfigFile<K>> for NetworkConfig<K, E> {
+    type Error = KeyDerivationError;
+
+    fn try_from(val: NetworkConfigFile<K>) -> Result<Self, Self::Error> {
+        let seeds = val.seeds()?;
+        let mut config = NetworkConfig {
             rounds: val.rounds,
             transactions_per_round: val.transactions_per_round,
             node_index: 0,
@@ -392,13 +1244,17 @@ impl<K: SignatureKey, E: ElectionConfig> From<NetworkConfigFile<K>> for NetworkC
             next_view_timeout: val.config.next_view_timeout,
             propose_max_round_time: val.config.propose_max_round_time,
             propose_min_round_time: val.config.propose_min_round_time,
-            seed: val.seed,
+            seed: seeds.consensus,
+            libp2p_seed: seeds.libp2p,
             transaction_size: val.transaction_size,
             libp2p_config: val.libp2p_config.map(|libp2p_config| Libp2pConfig {
                 num_bootstrap_nodes: val.config.num_bootstrap,
                 index_ports: libp2p_config.index_ports,
                 bootstrap_nodes: Vec::new(),
-                public_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                bind_ips: Vec::new(),
+                advertised_ips: Vec::new(),
+                preferred_ip_family: libp2p_config.preferred_ip_family,
+                dual_stack: libp2p_config.dual_stack,
                 base_port: libp2p_config.base_port,
                 node_index: 0,
                 bootstrap_mesh_n_high: libp2p_config.bootstrap_mesh_n_high,
@@ -409,26 +1265,67 @@ impl<K: SignatureKey, E: ElectionConfig> From<NetworkConfigFile<K>> for NetworkC
                 mesh_n_low: libp2p_config.mesh_n_low,
                 mesh_outbound_min: libp2p_config.mesh_outbound_min,
                 mesh_n: libp2p_config.mesh_n,
+                mesh_auto_tune: libp2p_config.mesh_auto_tune,
                 next_view_timeout: val.config.next_view_timeout,
                 propose_min_round_time: val.config.propose_min_round_time,
                 propose_max_round_time: val.config.propose_max_round_time,
                 online_time: libp2p_config.online_time,
                 num_txn_per_round: val.transactions_per_round,
+                quic: libp2p_config.quic,
+                peer_store_dir: libp2p_config.peer_store_dir,
+                peer_allowlist: libp2p_config.peer_allowlist,
             }),
             config: val.config.into(),
             key_type_name: std::any::type_name::<K>().to_string(),
             election_config_type_name: std::any::type_name::<E>().to_string(),
             start_delay_seconds: val.start_delay_seconds,
+            start_threshold: val.start_threshold,
+            ramp_up: val.ramp_up,
+            ramp_up_start_ms: None,
+            run_duration_seconds: val.run_duration_seconds,
+            warmup_seconds: val.warmup_seconds,
+            sample_interval_seconds: val.sample_interval_seconds,
+            leaf_dag_export: val.leaf_dag_export,
+            webhooks: val.webhooks,
+            statsd: val.statsd,
+            heartbeat_interval_seconds: val.heartbeat_interval_seconds,
+            stall_timeout_seconds: val.stall_timeout_seconds,
+            results_timeout_seconds: val.results_timeout_seconds,
+            stake_weights: val.stake_weights,
+            failure_domains: val.failure_domains,
+            max_da_nodes_per_domain: val.max_da_nodes_per_domain,
+            leader_reputation: val.leader_reputation,
+            runtime: val.runtime,
+            genesis_file: val.genesis_file,
+            network_trace_dir: val.network_trace_dir,
+            failure_schedule: val.failure_schedule,
+            partition_schedule: val.partition_schedule,
+            node_overrides: val.node_overrides,
+            node_transactions_per_round: None,
+            load_generator: val.load_generator,
+            soak: val.soak,
+            log_throttle: val.log_throttle,
+            genesis_timestamp_ms: None,
             web_server_config: val.web_server_config,
             da_web_server_config: val.da_web_server_config,
-        }
+            quorum_network_type: val.quorum_network_type,
+            da_network_type: val.da_network_type,
+            web_server_view_change_relay: val.web_server_view_change_relay,
+        };
+        config.apply_run_overrides();
+        Ok(config)
     }
 }
 
 /// Holds configuration for a `HotShot`
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde_inline_default]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, JsonSchema)]
 #[serde(bound(deserialize = ""))]
+#[schemars(bound = "", rename = "HotShotConfigFile")]
 pub struct HotShotConfigFile<KEY: SignatureKey> {
+    /// The chain id of the network. Replaced by the chain id of the genesis file, if one is given.
+    #[serde(default)]
+    pub chain_id: u64,
     /// Total number of nodes in the network
     pub total_nodes: NonZeroUsize,
     #[serde(skip)]
@@ -441,10 +1338,16 @@ pub struct HotShotConfigFile<KEY: SignatureKey> {
     pub committee_nodes: usize,
     /// Maximum transactions per block
     pub max_transactions: NonZeroUsize,
+    /// Transactions per block reserved for system transactions
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_SYSTEM_TRANSACTIONS_PER_BLOCK)]
+    pub system_transactions_per_block: usize,
     /// Minimum transactions per block
     pub min_transactions: usize,
     /// Base duration for next-view timeout, in milliseconds
     pub next_view_timeout: u64,
+    /// How long to wait for a proposal before reporting it missing, in milliseconds
+    #[serde(default = "default_proposal_timeout")]
+    pub proposal_timeout: u64,
     /// The exponential backoff ration for the next-view timeout
     pub timeout_ratio: (u64, u64),
     /// The delay a leader inserts before starting pre-commit, in milliseconds
@@ -457,6 +1360,59 @@ pub struct HotShotConfigFile<KEY: SignatureKey> {
     pub propose_min_round_time: Duration,
     /// The maximum amount of time a leader can wait to start a round
     pub propose_max_round_time: Duration,
+    /// When block payloads are written to disk rather than held in memory
+    #[serde(default)]
+    pub payload_spill: PayloadSpillConfig,
+    /// Sign messages without domain separation, as nodes predating it do
+    #[serde(default)]
+    pub legacy_signing: bool,
+    /// Number of views before the current view whose consensus messages are still handled
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_STALE_VIEW_GRACE_VIEWS)]
+    pub stale_view_grace: u64,
+    /// Milliseconds votes wait to be sent with the next votes for the same leader, 0 to disable
+    #[serde(default)]
+    pub vote_batch_delay_ms: u64,
+    /// Steps of the way to a certificate threshold at which leaders report their votes, if any
+    #[serde(default)]
+    pub vote_progress_steps: Option<u64>,
+    /// Apply block headers to the state on worker tasks rather than on the consensus task
+    #[serde(default)]
+    pub parallel_state_application: bool,
+    /// VID shares non-DA nodes sample for every certified payload, 0 to disable
+    #[serde(default)]
+    pub availability_samples: usize,
+    /// Unicast DA payloads to the DA committee, broadcasting only their commitment
+    #[serde(default)]
+    pub da_unicast_payload: bool,
+    /// Replace the transactions the DA committee holds by their commitments in DA proposals
+    #[serde(default)]
+    pub da_compact_proposals: bool,
+    /// Views without a DA certificate after which DA falls back to the whole quorum, unset to
+    /// never fall back
+    #[serde(default)]
+    pub da_fallback_after_failures: Option<u64>,
+    /// Views DA stays with the whole quorum for once fallen back
+    #[serde_inline_default(ORCHESTRATOR_DEFAULT_DA_FALLBACK_VIEWS)]
+    pub da_fallback_views: u64,
+    /// Views before the anchor whose stake tables are kept, unset to keep them all
+    #[serde(default)]
+    pub stake_table_retention_views: Option<u64>,
+    /// Views before the anchor whose artifacts are kept, unset to keep them all
+    #[serde(default)]
+    pub view_artifact_retention_views: Option<u64>,
+    /// Views of every epoch, unset to run without epochs
+    #[serde(default)]
+    pub epoch_length: Option<u64>,
+    /// Timeouts and limits of request/response calls between nodes
+    #[serde(default)]
+    pub rpc: RpcConfig,
+    /// Milliseconds without a view change before the node reports itself stalled, unset to run
+    /// no watchdog
+    #[serde(default)]
+    pub stall_timeout: Option<u64>,
+    /// When the writes to storage are committed, by default after every view
+    #[serde(default)]
+    pub storage_sync: StorageSyncConfig,
 }
 
 /// Holds configuration for a validator node
@@ -465,6 +1421,9 @@ pub struct HotShotConfigFile<KEY: SignatureKey> {
 pub struct ValidatorConfigFile {
     /// The validator's seed
     pub seed: [u8; 32],
+    /// If set, the BIP-39 mnemonic the validator's keys are derived from, instead of `seed`
+    #[serde(default)]
+    pub mnemonic: Option<String>,
     /// The validator's index, which can be treated as another input to the seed
     pub node_id: u64,
     // The validator's stake, commented for now
@@ -514,14 +1473,17 @@ impl ValidatorConfigFile {
 impl<KEY: SignatureKey, E: ElectionConfig> From<HotShotConfigFile<KEY>> for HotShotConfig<KEY, E> {
     fn from(val: HotShotConfigFile<KEY>) -> Self {
         HotShotConfig {
+            chain_id: val.chain_id,
             execution_type: ExecutionType::Continuous,
             total_nodes: val.total_nodes,
             max_transactions: val.max_transactions,
+            system_transactions_per_block: val.system_transactions_per_block,
             min_transactions: val.min_transactions,
             known_nodes_with_stake: val.known_nodes_with_stake,
             my_own_validator_config: val.my_own_validator_config,
             da_committee_size: val.committee_nodes,
             next_view_timeout: val.next_view_timeout,
+            proposal_timeout: val.proposal_timeout,
             timeout_ratio: val.timeout_ratio,
             round_start_delay: val.round_start_delay,
             start_delay: val.start_delay,
@@ -529,6 +1491,23 @@ impl<KEY: SignatureKey, E: ElectionConfig> From<HotShotConfigFile<KEY>> for HotS
             propose_min_round_time: val.propose_min_round_time,
             propose_max_round_time: val.propose_max_round_time,
             election_config: None,
+            payload_spill: val.payload_spill,
+            legacy_signing: val.legacy_signing,
+            stale_view_grace: val.stale_view_grace,
+            vote_batch_delay_ms: val.vote_batch_delay_ms,
+            vote_progress_steps: val.vote_progress_steps,
+            parallel_state_application: val.parallel_state_application,
+            availability_samples: val.availability_samples,
+            da_unicast_payload: val.da_unicast_payload,
+            da_compact_proposals: val.da_compact_proposals,
+            da_fallback_after_failures: val.da_fallback_after_failures,
+            da_fallback_views: val.da_fallback_views,
+            stake_table_retention_views: val.stake_table_retention_views,
+            view_artifact_retention_views: val.view_artifact_retention_views,
+            epoch_length: val.epoch_length,
+            rpc: val.rpc,
+            stall_timeout: val.stall_timeout,
+            storage_sync: val.storage_sync,
         }
     }
 }
@@ -540,11 +1519,84 @@ pub const ORCHESTRATOR_DEFAULT_TRANSACTIONS_PER_ROUND: usize = 10;
 pub const ORCHESTRATOR_DEFAULT_TRANSACTION_SIZE: usize = 100;
 /// default delay before beginning consensus
 pub const ORCHESTRATOR_DEFAULT_START_DELAY_SECONDS: u64 = 60;
+/// default interval between throughput samples
+pub const ORCHESTRATOR_DEFAULT_SAMPLE_INTERVAL_SECONDS: u64 = 10;
+/// default time to wait for a proposal before reporting it missing, in milliseconds
+pub const ORCHESTRATOR_DEFAULT_PROPOSAL_TIMEOUT_MS: u64 = 5000;
+/// default interval between heartbeats
+pub const ORCHESTRATOR_DEFAULT_HEARTBEAT_INTERVAL_SECONDS: u64 = 5;
+/// default time after which a silent or stuck node is reported
+pub const ORCHESTRATOR_DEFAULT_STALL_TIMEOUT_SECONDS: u64 = 60;
+/// default time the nodes of a run have to post their results once the first one did
+pub const ORCHESTRATOR_DEFAULT_RESULTS_TIMEOUT_SECONDS: u64 = 120;
+/// default number of seconds during which repeated log messages are coalesced
+pub const ORCHESTRATOR_DEFAULT_LOG_THROTTLE_WINDOW_SECONDS: u64 = 10;
+/// default number of transactions of each block reserved for system transactions
+pub const ORCHESTRATOR_DEFAULT_SYSTEM_TRANSACTIONS_PER_BLOCK: usize = 16;
+/// default number of views before the current view whose consensus messages are still handled
+pub const ORCHESTRATOR_DEFAULT_STALE_VIEW_GRACE_VIEWS: u64 = 2;
+/// default number of views DA stays with the whole quorum for once fallen back
+pub const ORCHESTRATOR_DEFAULT_DA_FALLBACK_VIEWS: u64 = 10;
+/// default number of transactions the load generator submits after the first decide, and adds
+/// after each decide consensus keeps up with
+pub const ORCHESTRATOR_DEFAULT_LOAD_INITIAL_RATE: u64 = 10;
+/// default most transactions the load generator submits after a decide
+pub const ORCHESTRATOR_DEFAULT_LOAD_MAX_RATE: u64 = 10_000;
+/// default mempool depth beyond which the load generator backs off
+pub const ORCHESTRATOR_DEFAULT_LOAD_TARGET_MEMPOOL_DEPTH: usize = 1000;
+/// default expected decide latency, in milliseconds, beyond which the load generator backs off
+pub const ORCHESTRATOR_DEFAULT_LOAD_MAX_DECIDE_LATENCY_MS: u64 = 5000;
+/// default interval between resource samples of a soak run
+pub const ORCHESTRATOR_DEFAULT_SOAK_SAMPLE_INTERVAL_SECONDS: u64 = 60;
+/// default number of resource samples of a soak run taken before trends are measured
+pub const ORCHESTRATOR_DEFAULT_SOAK_WARMUP_SAMPLES: usize = 10;
+/// default most the resident memory of a node may grow over a soak run, in percent
+pub const ORCHESTRATOR_DEFAULT_SOAK_MAX_RSS_GROWTH_PERCENT: u64 = 25;
+/// default most file descriptors a node may open over a soak run
+pub const ORCHESTRATOR_DEFAULT_SOAK_MAX_FD_GROWTH: u64 = 64;
+/// default most threads or tasks a node may add over a soak run
+pub const ORCHESTRATOR_DEFAULT_SOAK_MAX_TASK_GROWTH: u64 = 8;
+/// default most events the channels of a node may queue up over a soak run
+pub const ORCHESTRATOR_DEFAULT_SOAK_MAX_CHANNEL_DEPTH_GROWTH: u64 = 1000;
+
+/// default interval between throughput samples, for serde
+fn default_sample_interval_seconds() -> u64 {
+    ORCHESTRATOR_DEFAULT_SAMPLE_INTERVAL_SECONDS
+}
+
+/// view change messages are relayed over the web server by default, for serde
+fn default_web_server_view_change_relay() -> bool {
+    true
+}
+
+/// default interval between heartbeats, for serde
+fn default_heartbeat_interval_seconds() -> u64 {
+    ORCHESTRATOR_DEFAULT_HEARTBEAT_INTERVAL_SECONDS
+}
+
+/// default time after which a silent or stuck node is reported, for serde
+fn default_stall_timeout_seconds() -> u64 {
+    ORCHESTRATOR_DEFAULT_STALL_TIMEOUT_SECONDS
+}
+
+/// default time the nodes of a run have to post their results, for serde
+fn default_results_timeout_seconds() -> u64 {
+    ORCHESTRATOR_DEFAULT_RESULTS_TIMEOUT_SECONDS
+}
+
+/// default time to wait for a proposal, for serde
+fn default_proposal_timeout() -> u64 {
+    ORCHESTRATOR_DEFAULT_PROPOSAL_TIMEOUT_MS
+}
 
 impl<K: SignatureKey> From<ValidatorConfigFile> for ValidatorConfig<K> {
     fn from(val: ValidatorConfigFile) -> Self {
         // here stake_value is set to 1, since we don't input stake_value from ValidatorConfigFile for now
-        ValidatorConfig::generated_from_seed_indexed(val.seed, val.node_id, 1)
+        match val.mnemonic {
+            Some(phrase) => ValidatorConfig::from_mnemonic(&phrase, val.node_id, 1)
+                .unwrap_or_else(|e| panic!("Could not derive the validator's keys: {e}")),
+            None => ValidatorConfig::generated_from_seed_indexed(val.seed, val.node_id, 1),
+        }
     }
 }
 impl<KEY: SignatureKey, E: ElectionConfig> From<ValidatorConfigFile> for HotShotConfig<KEY, E> {
@@ -568,19 +1620,39 @@ impl<KEY: SignatureKey> Default for HotShotConfigFile<KEY> {
             })
             .collect();
         Self {
+            chain_id: 0,
             total_nodes: NonZeroUsize::new(10).unwrap(),
             my_own_validator_config: ValidatorConfig::default(),
             known_nodes_with_stake: gen_known_nodes_with_stake,
             committee_nodes: 5,
             max_transactions: NonZeroUsize::new(100).unwrap(),
+            system_transactions_per_block: ORCHESTRATOR_DEFAULT_SYSTEM_TRANSACTIONS_PER_BLOCK,
             min_transactions: 1,
             next_view_timeout: 10000,
+            proposal_timeout: ORCHESTRATOR_DEFAULT_PROPOSAL_TIMEOUT_MS,
             timeout_ratio: (11, 10),
             round_start_delay: 1,
             start_delay: 1,
             propose_min_round_time: Duration::from_secs(0),
             propose_max_round_time: Duration::from_secs(10),
             num_bootstrap: 5,
+            payload_spill: PayloadSpillConfig::default(),
+            legacy_signing: false,
+            stale_view_grace: ORCHESTRATOR_DEFAULT_STALE_VIEW_GRACE_VIEWS,
+            vote_batch_delay_ms: 0,
+            vote_progress_steps: None,
+            parallel_state_application: false,
+            availability_samples: 0,
+            da_unicast_payload: false,
+            da_compact_proposals: false,
+            da_fallback_after_failures: None,
+            da_fallback_views: ORCHESTRATOR_DEFAULT_DA_FALLBACK_VIEWS,
+            stake_table_retention_views: None,
+            view_artifact_retention_views: None,
+            epoch_length: None,
+            rpc: RpcConfig::default(),
+            stall_timeout: None,
+            storage_sync: StorageSyncConfig::default(),
         }
     }
 }
@@ -0,0 +1,622 @@
+//! The control endpoint of a validator node, which operators talk to through `hotshotctl`
+//!
+//! Every route requires the endpoint's token, as `Authorization: Bearer <token>`, and the
+//! configuration it reports has its key material redacted.
+//!
+//! The endpoint only records what was requested; the node's run loop carries requests out as it
+//! handles consensus events, and keeps the reported status up to date. Changes of the log filter
+//! are the exception: the endpoint applies them itself, so they take effect on a stuck node too,
+//! as are dry runs of payloads, which the node's [`PayloadValidator`] answers as they come, and
+//! snapshots of the leaf DAG, which the node's [`LeafDagSource`] takes as they are asked for.
+
+use std::{
+    fmt::Debug,
+    io,
+    io::ErrorKind,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use async_compatibility_layer::art::{async_sleep, async_spawn};
+use async_lock::RwLock;
+use futures::{future::BoxFuture, FutureExt};
+use hotshot_types::{bandwidth::BandwidthUsage, leaf_dag::LeafDag, reputation::LeaderPerformance};
+use serde::{Deserialize, Serialize};
+use surf_disco::Url;
+use tide_disco::{
+    api::ApiError,
+    error::ServerError,
+    method::{ReadState, WriteState},
+    Api, App, RequestParams, StatusCode,
+};
+
+use crate::config::PartitionOrder;
+
+/// What a node reports about itself
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeStatus {
+    /// index of the node in the network
+    pub node_index: u64,
+    /// the view the node is in
+    pub current_view: u64,
+    /// the view of the last leaf the node decided
+    pub last_decided_view: u64,
+    /// number of errors consensus reported so far
+    pub error_count: u64,
+    /// number of transactions the node submitted so far
+    pub transactions_sent: usize,
+    /// whether the node stopped submitting transactions
+    pub transactions_paused: bool,
+    /// public keys of the other nodes of the network, from the stake table
+    pub peers: Vec<String>,
+    /// bytes exchanged with each peer so far, by message class
+    pub bandwidth: Vec<BandwidthUsage>,
+    /// how each leader performed, as the node observed it
+    pub leader_scores: Vec<LeaderPerformance>,
+}
+
+/// A change of a node's log filter, posted to the control endpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LogFilterRequest {
+    /// the directives to filter the logs with, in the syntax of `RUST_LOG`
+    pub directives: String,
+    /// if given, the change opens a debug capture window: the filter reverts to the one before
+    /// after this many seconds
+    pub revert_after_seconds: Option<u64>,
+}
+
+/// An open debug capture window, as reported by the control endpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LogCaptureStatus {
+    /// the directives the filter reverts to when the window closes
+    pub reverts_to: String,
+    /// seconds until the window closes
+    pub remaining_seconds: u64,
+}
+
+/// The log filter of a node, as reported by the control endpoint
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LogFilterStatus {
+    /// the directives the logs are filtered with, in the syntax of `RUST_LOG`
+    pub directives: String,
+    /// the open debug capture window, if any
+    pub capture: Option<LogCaptureStatus>,
+}
+
+/// The filter of a node's logs, which can be changed while the node runs
+pub trait LogFilter: Send + Sync + Debug {
+    /// The directives the logs are filtered with, in the syntax of `RUST_LOG`
+    fn directives(&self) -> String;
+
+    /// Filter the logs with `directives`, in the syntax of `RUST_LOG`
+    /// # Errors
+    /// If `directives` are invalid, with why
+    fn set_directives(&self, directives: &str) -> Result<(), String>;
+}
+
+/// A debug capture window, after which the log filter reverts
+#[derive(Clone, Debug)]
+struct LogCapture {
+    /// the directives to revert to
+    reverts_to: String,
+    /// when the window closes
+    until: Instant,
+}
+
+/// The log filter of a node, as operators change it through the control endpoint: for good, or
+/// for a debug capture window which reverts on its own
+#[derive(Debug)]
+pub struct LogControl {
+    /// the filter of the node's logs
+    filter: Box<dyn LogFilter>,
+    /// the open debug capture window, if any
+    capture: Mutex<Option<LogCapture>>,
+}
+
+impl LogControl {
+    /// Control of `filter`, without a capture window open
+    #[must_use]
+    pub fn new(filter: impl LogFilter + 'static) -> Self {
+        Self {
+            filter: Box::new(filter),
+            capture: Mutex::new(None),
+        }
+    }
+
+    /// The open capture window, if any
+    fn capture(&self) -> MutexGuard<'_, Option<LogCapture>> {
+        self.capture.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The filter at `now`
+    #[must_use]
+    pub fn status_at(&self, now: Instant) -> LogFilterStatus {
+        let capture = self.capture();
+        LogFilterStatus {
+            directives: self.filter.directives(),
+            capture: capture.as_ref().map(|open| LogCaptureStatus {
+                reverts_to: open.reverts_to.clone(),
+                remaining_seconds: open.until.saturating_duration_since(now).as_secs(),
+            }),
+        }
+    }
+
+    /// Filter the logs with `directives` for good. An open capture window is closed without
+    /// reverting.
+    /// # Errors
+    /// If `directives` are invalid, in which case the filter is left as it was
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let mut capture = self.capture();
+        self.filter.set_directives(directives)?;
+        *capture = None;
+        Ok(())
+    }
+
+    /// Filter the logs with `directives` for `window` from `now`, then revert. A window opened
+    /// while another is open replaces it, but still reverts to the filter before the first.
+    /// # Errors
+    /// If `directives` are invalid, in which case the filter and any open window are left as
+    /// they were
+    pub fn capture_at(
+        &self,
+        directives: &str,
+        window: Duration,
+        now: Instant,
+    ) -> Result<(), String> {
+        let mut capture = self.capture();
+        let until = now
+            .checked_add(window)
+            .ok_or_else(|| format!("A capture window of {window:?} is too long"))?;
+        let reverts_to = match capture.as_ref() {
+            Some(open) => open.reverts_to.clone(),
+            None => self.filter.directives(),
+        };
+        self.filter.set_directives(directives)?;
+        *capture = Some(LogCapture { reverts_to, until });
+        Ok(())
+    }
+
+    /// Revert the filter if the open capture window closed by `now`. Returns the directives the
+    /// filter reverted to, if it did.
+    pub fn close_expired_at(&self, now: Instant) -> Option<String> {
+        let mut capture = self.capture();
+        if !capture.as_ref().is_some_and(|open| open.until <= now) {
+            return None;
+        }
+        let closed = capture.take()?;
+        if let Err(e) = self.filter.set_directives(&closed.reverts_to) {
+            tracing::error!(
+                "Failed to revert the log filter to {}: {e}",
+                closed.reverts_to
+            );
+        }
+        Some(closed.reverts_to)
+    }
+}
+
+/// A block payload to check as the node's replica would check a proposal of it, posted to the
+/// control endpoint by a block builder
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PayloadValidationRequest {
+    /// the view the payload would be proposed in
+    pub view: u64,
+    /// the encoded transactions of the payload
+    pub encoded_transactions: Vec<u8>,
+    /// the metadata of the payload, as JSON
+    pub metadata: serde_json::Value,
+}
+
+/// Whether the node's replica would vote for a proposal of a payload
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum PayloadVerdict {
+    /// It would, with what it would accept the proposal as, as JSON
+    Accepted(serde_json::Value),
+    /// It would not, for this reason
+    Rejected(String),
+}
+
+/// The largest body of a payload validation request the control endpoint checks, in bytes; the
+/// JSON of the encoded transactions takes up to four bytes per byte
+pub const MAX_PAYLOAD_VALIDATION_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Runs the checks of the node's replica on payloads posted to the control endpoint
+pub trait PayloadValidator: Send + Sync + Debug {
+    /// Check the payload of `request`
+    /// # Errors
+    /// If `request` does not hold a payload of the node's block type
+    fn validate_payload(
+        &self,
+        request: PayloadValidationRequest,
+    ) -> BoxFuture<'_, Result<PayloadVerdict, String>>;
+}
+
+/// Takes snapshots of the leaf DAG of the node's consensus for the control endpoint
+pub trait LeafDagSource: Send + Sync + Debug {
+    /// The leaves consensus holds in memory, as of now
+    fn leaf_dag(&self) -> BoxFuture<'_, LeafDag>;
+}
+
+/// The fields of the configuration reported by the control endpoint which are redacted, as they
+/// hold key material
+pub const REDACTED_CONFIG_FIELDS: &[&str] = &[
+    "private_key",
+    "state_key_pair",
+    "seed",
+    "libp2p_seed",
+    "mnemonic",
+];
+
+/// What redacted fields of the configuration are replaced with
+pub const REDACTED: &str = "<redacted>";
+
+/// The environment variable the token of the control endpoint is taken from by `hotshotctl`, and
+/// by the node if not given on its command line
+pub const CONTROL_TOKEN_ENV: &str = "HOTSHOT_CONTROL_TOKEN";
+
+/// Replace the values of the fields of `value` holding key material, at any depth, with
+/// [`REDACTED`]
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if REDACTED_CONFIG_FIELDS.contains(&name.as_str()) || name.ends_with("_private_key")
+                {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Whether `a` and `b` are equal, taking as long whichever byte they differ at
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The state behind the control endpoint, shared with the node's run loop
+#[derive(Debug, Default)]
+pub struct NodeControl {
+    /// the status reported to operators
+    pub status: NodeStatus,
+    /// the token requests to the endpoint must carry; without one, every request is refused
+    token: Option<String>,
+    /// the configuration of the run, as JSON, with its key material redacted
+    config: serde_json::Value,
+    /// whether a snapshot was requested and not taken yet
+    snapshot_requested: bool,
+    /// whether the node was asked to shut down
+    shutdown_requested: bool,
+    /// the log filter of the node, if it can be changed
+    log: Option<Arc<LogControl>>,
+    /// the checks of the node's replica, once it runs
+    payload_validator: Option<Arc<dyn PayloadValidator>>,
+    /// the leaf DAG of the node's consensus, once it runs
+    leaf_dag: Option<Arc<dyn LeafDagSource>>,
+    /// the partition the orchestrator last ordered the node into, if any
+    partition_order: Option<PartitionOrder>,
+}
+
+/// A [`NodeControl`] shared between the control endpoint and the node's run loop
+pub type NodeControlHandle = Arc<RwLock<NodeControl>>;
+
+impl NodeControl {
+    /// Record `config` as the configuration reported by the endpoint, with the fields in
+    /// [`REDACTED_CONFIG_FIELDS`] redacted
+    /// # Errors
+    /// If `config` cannot be serialized to JSON
+    pub fn set_config(&mut self, config: &impl Serialize) -> Result<(), serde_json::Error> {
+        let mut config = serde_json::to_value(config)?;
+        redact(&mut config);
+        self.config = config;
+        Ok(())
+    }
+
+    /// The configuration reported by the endpoint
+    #[must_use]
+    pub fn config(&self) -> &serde_json::Value {
+        &self.config
+    }
+
+    /// Require `token` of every request to the endpoint
+    pub fn set_token(&mut self, token: String) {
+        self.token = Some(token);
+    }
+
+    /// Whether a request with the `Authorization` header `authorization` may use the endpoint
+    #[must_use]
+    pub fn authorizes(&self, authorization: Option<&str>) -> bool {
+        match (
+            &self.token,
+            authorization.and_then(|a| a.strip_prefix("Bearer ")),
+        ) {
+            (Some(token), Some(presented)) => {
+                !token.is_empty() && constant_time_eq(token.as_bytes(), presented.as_bytes())
+            }
+            _ => false,
+        }
+    }
+
+    /// Refuse `req` unless it carries the endpoint's token
+    /// # Errors
+    /// If it does not
+    fn authorize(&self, req: &RequestParams) -> Result<(), ServerError> {
+        self.authorize_header(authorization(req))
+    }
+
+    /// Refuse a request with the `Authorization` header `authorization` unless it carries the
+    /// endpoint's token
+    /// # Errors
+    /// If it does not
+    fn authorize_header(&self, authorization: Option<&str>) -> Result<(), ServerError> {
+        if self.authorizes(authorization) {
+            Ok(())
+        } else {
+            Err(ServerError {
+                status: StatusCode::Unauthorized,
+                message: "A valid control token is required".to_string(),
+            })
+        }
+    }
+
+    /// Whether a snapshot was requested since the last call
+    pub fn take_snapshot_request(&mut self) -> bool {
+        std::mem::take(&mut self.snapshot_requested)
+    }
+
+    /// Whether the node was asked to shut down
+    #[must_use]
+    pub fn shutdown_requested(&self) -> bool {
+        self.shutdown_requested
+    }
+
+    /// Record the partition the orchestrator orders the node into, `None` once it heals
+    pub fn set_partition_order(&mut self, order: Option<PartitionOrder>) {
+        self.partition_order = order;
+    }
+
+    /// The partition the orchestrator last ordered the node into, if any
+    #[must_use]
+    pub fn partition_order(&self) -> Option<&PartitionOrder> {
+        self.partition_order.as_ref()
+    }
+
+    /// Let the endpoint change the log filter through `log`
+    pub fn set_log_control(&mut self, log: Arc<LogControl>) {
+        self.log = Some(log);
+    }
+
+    /// The log filter of the node
+    /// # Errors
+    /// If the node's log filter cannot be changed
+    fn log_control(&self) -> Result<Arc<LogControl>, ServerError> {
+        self.log.clone().ok_or_else(|| ServerError {
+            status: StatusCode::ServiceUnavailable,
+            message: "The node's log filter cannot be changed while it runs".to_string(),
+        })
+    }
+
+    /// Let the endpoint check payloads with `validator`
+    pub fn set_payload_validator(&mut self, validator: Arc<dyn PayloadValidator>) {
+        self.payload_validator = Some(validator);
+    }
+
+    /// The checks of the node's replica
+    /// # Errors
+    /// If the node does not run consensus yet
+    fn payload_validator(&self) -> Result<Arc<dyn PayloadValidator>, ServerError> {
+        self.payload_validator.clone().ok_or_else(|| ServerError {
+            status: StatusCode::ServiceUnavailable,
+            message: "The node does not run consensus yet".to_string(),
+        })
+    }
+
+    /// Let the endpoint report the leaf DAG snapshots `source` takes
+    pub fn set_leaf_dag_source(&mut self, source: Arc<dyn LeafDagSource>) {
+        self.leaf_dag = Some(source);
+    }
+
+    /// The leaf DAG of the node's consensus
+    /// # Errors
+    /// If the node does not run consensus yet
+    fn leaf_dag_source(&self) -> Result<Arc<dyn LeafDagSource>, ServerError> {
+        self.leaf_dag.clone().ok_or_else(|| ServerError {
+            status: StatusCode::ServiceUnavailable,
+            message: "The node does not run consensus yet".to_string(),
+        })
+    }
+}
+
+/// The `Authorization` header of `req`
+fn authorization(req: &RequestParams) -> Option<&str> {
+    req.headers()
+        .get("Authorization")
+        .map(|values| values.last().as_str())
+}
+
+/// Carry out `request` on `log`, closing the capture window it opens, if any, once it is over
+/// # Errors
+/// If the directives of `request` are invalid
+fn change_log_filter(log: Arc<LogControl>, request: LogFilterRequest) -> Result<(), ServerError> {
+    let result = match request.revert_after_seconds {
+        None => log.set(&request.directives),
+        Some(seconds) => {
+            let window = Duration::from_secs(seconds);
+            let opened = log.capture_at(&request.directives, window, Instant::now());
+            if opened.is_ok() {
+                async_spawn(async move {
+                    async_sleep(window).await;
+                    if let Some(reverted) = log.close_expired_at(Instant::now()) {
+                        tracing::info!("Capture window closed, log filter reverted to {reverted}");
+                    }
+                });
+            }
+            opened
+        }
+    };
+    result.map_err(|message| ServerError {
+        status: StatusCode::BadRequest,
+        message,
+    })
+}
+
+/// Sets up the routes of the control endpoint
+fn define_api<State>() -> Result<Api<State, ServerError>, ApiError>
+where
+    State: 'static + Send + Sync + ReadState<State = NodeControl> + WriteState,
+{
+    let api_toml = toml::from_str::<toml::Value>(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/control-api.toml"
+    )))
+    .expect("API file is not valid toml");
+    let mut api = Api::<State, ServerError>::new(api_toml)?;
+    api.get("status", |req, state| {
+        async move {
+            state.authorize(&req)?;
+            Ok(state.status.clone())
+        }
+        .boxed()
+    })?
+    .get("config", |req, state| {
+        async move {
+            state.authorize(&req)?;
+            Ok(state.config.clone())
+        }
+        .boxed()
+    })?
+    .post("snapshot", |req, state: &mut NodeControl| {
+        async move {
+            state.authorize(&req)?;
+            state.snapshot_requested = true;
+            Ok(())
+        }
+        .boxed()
+    })?
+    .post("pause_transactions", |req, state: &mut NodeControl| {
+        async move {
+            state.authorize(&req)?;
+            state.status.transactions_paused = true;
+            Ok(())
+        }
+        .boxed()
+    })?
+    .post("resume_transactions", |req, state: &mut NodeControl| {
+        async move {
+            state.authorize(&req)?;
+            state.status.transactions_paused = false;
+            Ok(())
+        }
+        .boxed()
+    })?
+    .post("shutdown", |req, state: &mut NodeControl| {
+        async move {
+            state.authorize(&req)?;
+            state.shutdown_requested = true;
+            Ok(())
+        }
+        .boxed()
+    })?
+    .get("log_filter", |req, state| {
+        async move {
+            state.authorize(&req)?;
+            Ok(state.log_control()?.status_at(Instant::now()))
+        }
+        .boxed()
+    })?
+    .post("set_log_filter", |req, state: &mut NodeControl| {
+        async move {
+            state.authorize(&req)?;
+            let request = req.body_json()?;
+            change_log_filter(state.log_control()?, request)
+        }
+        .boxed()
+    })?
+    // as with dry runs below, the state is only locked to take the source, so that waiting on
+    // consensus does not hold up the node's run loop updating its status
+    .at("leaf_dag", |req, state: &State| {
+        async move {
+            let authorization = authorization(&req).map(ToString::to_string);
+            let source = state
+                .read(|control| {
+                    async move {
+                        control.authorize_header(authorization.as_deref())?;
+                        control.leaf_dag_source()
+                    }
+                    .boxed()
+                })
+                .await?;
+            Ok(source.leaf_dag().await)
+        }
+        .boxed()
+    })?
+    // the state is only locked to take the validator, so that a long dry run does not hold up
+    // the other routes, nor the node's run loop updating its status
+    .at("validate_payload", |req, state: &State| {
+        async move {
+            let body = req.body_bytes();
+            let authorization = authorization(&req).map(ToString::to_string);
+            let validator = state
+                .read(|control| {
+                    async move {
+                        control.authorize_header(authorization.as_deref())?;
+                        control.payload_validator()
+                    }
+                    .boxed()
+                })
+                .await?;
+            if body.len() > MAX_PAYLOAD_VALIDATION_BODY_BYTES {
+                return Err(ServerError {
+                    status: StatusCode::PayloadTooLarge,
+                    message: format!(
+                        "The request has {} bytes, more than the maximum of {}",
+                        body.len(),
+                        MAX_PAYLOAD_VALIDATION_BODY_BYTES
+                    ),
+                });
+            }
+            let request = serde_json::from_slice(&body).map_err(|e| ServerError {
+                status: StatusCode::BadRequest,
+                message: format!("Invalid payload validation request: {e}"),
+            })?;
+            validator
+                .validate_payload(request)
+                .await
+                .map_err(|message| ServerError {
+                    status: StatusCode::BadRequest,
+                    message,
+                })
+        }
+        .boxed()
+    })?;
+    Ok(api)
+}
+
+/// Serves the control endpoint of a node on `url`, to requests carrying `token`
+/// # Errors
+/// If `token` is empty, or if tide disco runs into an issue during serving
+/// # Panics
+/// This panics if unable to register the api with tide disco
+pub async fn run_control_server(
+    control: NodeControlHandle,
+    url: Url,
+    token: String,
+) -> io::Result<()> {
+    if token.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The control endpoint needs a non-empty token",
+        ));
+    }
+    control.write().await.set_token(token);
+    let api =
+        define_api().map_err(|_e| io::Error::new(ErrorKind::Other, "Failed to define api"))?;
+    let mut app = App::<NodeControlHandle, ServerError>::with_state(control);
+    app.register_module("control", api)
+        .expect("Error registering api");
+    tracing::info!("control endpoint listening on {:?}", url);
+    app.serve(url).await
+}
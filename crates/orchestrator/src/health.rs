@@ -0,0 +1,172 @@
+//! Liveness of the nodes of a run, as seen by the orchestrator
+//!
+//! While consensus runs, every node periodically posts a [`Heartbeat`] with its progress. A node
+//! which stops sending heartbeats is reported as crashed, and one which keeps sending them without
+//! deciding anything new is reported as stalled.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::control::NodeStatus;
+
+/// What a node periodically reports to the orchestrator during a run
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Heartbeat {
+    /// index of the node in the network
+    pub node_index: u64,
+    /// the view the node is in
+    pub current_view: u64,
+    /// the view of the last leaf the node decided
+    pub last_decided_view: u64,
+    /// number of errors consensus reported so far
+    pub error_count: u64,
+}
+
+impl From<&NodeStatus> for Heartbeat {
+    fn from(status: &NodeStatus) -> Self {
+        Self {
+            node_index: status.node_index,
+            current_view: status.current_view,
+            last_decided_view: status.last_decided_view,
+            error_count: status.error_count,
+        }
+    }
+}
+
+/// The health of a node, judged from its heartbeats
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeHealth {
+    /// the node sends heartbeats and decides new views
+    Healthy,
+    /// the node sends heartbeats, but has not decided a new view for a while
+    Stalled,
+    /// the node has not sent a heartbeat for a while
+    Crashed,
+}
+
+/// What the orchestrator knows about the health of a node
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NodeHealthReport {
+    /// the health of the node
+    pub health: NodeHealth,
+    /// the last heartbeat the node sent
+    pub last_heartbeat: Heartbeat,
+    /// milliseconds since the node's last heartbeat
+    pub ms_since_heartbeat: u64,
+    /// milliseconds since the node last decided a new view
+    pub ms_since_progress: u64,
+}
+
+/// What the orchestrator remembers of a node's heartbeats
+#[derive(Clone, Debug)]
+struct NodeRecord {
+    /// the last heartbeat the node sent
+    heartbeat: Heartbeat,
+    /// when the last heartbeat arrived, in milliseconds since the unix epoch
+    received_ms: u64,
+    /// when the node last decided a new view, in milliseconds since the unix epoch
+    progress_ms: u64,
+    /// the health last reported for the node
+    health: NodeHealth,
+}
+
+/// Tracks the heartbeats of the nodes of a run and detects stalled and crashed nodes
+#[derive(Clone, Debug, Default)]
+pub struct HealthTracker {
+    /// the heartbeats seen so far, by node index
+    nodes: BTreeMap<u64, NodeRecord>,
+    /// how long a node may go without heartbeats, or without deciding, before it is reported
+    stall_timeout_ms: u64,
+}
+
+impl HealthTracker {
+    /// Create a tracker reporting nodes after `stall_timeout_ms` without heartbeats or progress
+    #[must_use]
+    pub fn new(stall_timeout_ms: u64) -> Self {
+        Self {
+            nodes: BTreeMap::new(),
+            stall_timeout_ms,
+        }
+    }
+
+    /// Record a heartbeat which arrived at `now_ms`
+    pub fn record(&mut self, heartbeat: Heartbeat, now_ms: u64) {
+        match self.nodes.get_mut(&heartbeat.node_index) {
+            Some(record) => {
+                if heartbeat.last_decided_view > record.heartbeat.last_decided_view {
+                    record.progress_ms = now_ms;
+                }
+                if heartbeat.error_count > record.heartbeat.error_count {
+                    warn!(
+                        "Node {} reported {} new errors",
+                        heartbeat.node_index,
+                        heartbeat.error_count - record.heartbeat.error_count
+                    );
+                }
+                record.heartbeat = heartbeat;
+                record.received_ms = now_ms;
+            }
+            None => {
+                self.nodes.insert(
+                    heartbeat.node_index,
+                    NodeRecord {
+                        heartbeat,
+                        received_ms: now_ms,
+                        progress_ms: now_ms,
+                        health: NodeHealth::Healthy,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Stop tracking the node of `node_index`, which left the run
+    pub fn forget(&mut self, node_index: u64) {
+        self.nodes.remove(&node_index);
+    }
+
+    /// Judge the health of every node as of `now_ms`, logging the nodes whose health changed
+    /// since the last check
+    pub fn check(&mut self, now_ms: u64) -> BTreeMap<u64, NodeHealthReport> {
+        let mut reports = BTreeMap::new();
+        for (node_index, record) in &mut self.nodes {
+            let ms_since_heartbeat = now_ms.saturating_sub(record.received_ms);
+            let ms_since_progress = now_ms.saturating_sub(record.progress_ms);
+            let health = if ms_since_heartbeat > self.stall_timeout_ms {
+                NodeHealth::Crashed
+            } else if ms_since_progress > self.stall_timeout_ms {
+                NodeHealth::Stalled
+            } else {
+                NodeHealth::Healthy
+            };
+            if health != record.health {
+                match health {
+                    NodeHealth::Healthy => info!("Node {node_index} recovered"),
+                    NodeHealth::Stalled => warn!(
+                        "Node {node_index} is stalled: nothing decided for {ms_since_progress}ms, \
+                         last decided view {}",
+                        record.heartbeat.last_decided_view
+                    ),
+                    NodeHealth::Crashed => error!(
+                        "Node {node_index} may have crashed: no heartbeat for \
+                         {ms_since_heartbeat}ms, last seen in view {}",
+                        record.heartbeat.current_view
+                    ),
+                }
+                record.health = health;
+            }
+            reports.insert(
+                *node_index,
+                NodeHealthReport {
+                    health,
+                    last_heartbeat: record.heartbeat.clone(),
+                    ms_since_heartbeat,
+                    ms_since_progress,
+                },
+            );
+        }
+        reports
+    }
+}
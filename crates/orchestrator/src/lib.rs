@@ -1,17 +1,38 @@
 //! Orchestrator for manipulating nodes and recording results during a run of `HotShot` tests
 
+/// Benchmark campaigns: running a matrix of configurations back to back
+pub mod campaign;
 /// The orchestrator's clients
 pub mod client;
 /// Configuration for the orchestrator
 pub mod config;
+/// The control endpoint of validator nodes
+pub mod control;
+/// Detection of stalled and crashed nodes from their heartbeats
+pub mod health;
+/// Closed-loop rate control of the transactions nodes submit
+pub mod load;
+/// Checks of a node's environment before it joins a run
+pub mod preflight;
+/// The JSON Schema of run configuration files, and their validation against it
+pub mod schema;
+/// Resource leak detection over long soak runs
+pub mod soak;
+/// Readiness and watchdog notifications to systemd
+pub mod systemd;
+/// Webhooks notified of decided leaves
+pub mod webhooks;
 
+use async_compatibility_layer::art::{async_sleep, async_spawn};
 use async_lock::RwLock;
 use hotshot_types::traits::{election::ElectionConfig, signature_key::SignatureKey};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
     io,
     io::ErrorKind,
     net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
 };
 use tide_disco::{Api, App};
 
@@ -24,13 +45,23 @@ use tide_disco::{
 
 use futures::FutureExt;
 
-use crate::config::NetworkConfig;
+use crate::{
+    campaign::{CampaignRunResults, RunResults},
+    config::{
+        prefer_family, unix_time_ms, GenesisTimestamp, NetworkConfig, PartitionOrder,
+        PartitionTracker,
+    },
+    health::{HealthTracker, Heartbeat, NodeHealthReport},
+};
 
 use libp2p::identity::{
     ed25519::{Keypair as EdKeypair, SecretKey},
     Keypair,
 };
 /// Generate an keypair based on a `seed` and an `index`
+///
+/// `seed` is either a raw seed or the libp2p seed derived from a mnemonic, as held by
+/// [`hotshot_types::key_derivation::DerivedSeeds`]
 /// # Panics
 /// This panics if libp2p is unable to generate a secret key from the seed
 #[must_use]
@@ -43,11 +74,14 @@ pub fn libp2p_generate_indexed_identity(seed: [u8; 32], index: u64) -> Keypair {
     <EdKeypair as From<SecretKey>>::from(sk_bytes).into()
 }
 
+/// How often the orchestrator checks whether the current run timed out
+const RUN_EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// The state of the orchestrator
 #[derive(Default, Clone)]
-struct OrchestratorState<KEY: SignatureKey, ELECTION: ElectionConfig> {
-    /// Tracks the latest node index we have generated a configuration for
-    latest_index: u16,
+pub struct OrchestratorState<KEY: SignatureKey, ELECTION: ElectionConfig> {
+    /// The node indices which have not yet been handed out
+    available_indices: BTreeSet<u16>,
     /// The network configuration
     config: NetworkConfig<KEY, ELECTION>,
     /// The total nodes that have posted their public keys
@@ -61,31 +95,161 @@ struct OrchestratorState<KEY: SignatureKey, ELECTION: ElectionConfig> {
     start: bool,
     /// The total nodes that have posted they are ready to start
     pub nodes_connected: u64,
+    /// Indices of the nodes that posted their public keys after the run started, in the order
+    /// they joined
+    newcomers: Vec<u64>,
+    /// Index of the current run within the campaign
+    run_index: u64,
+    /// Configurations for the runs of the campaign still to come
+    pending_configs: VecDeque<NetworkConfig<KEY, ELECTION>>,
+    /// Results posted by the nodes for the current run
+    run_results: Vec<RunResults>,
+    /// When the first node posted its results for the current run, in milliseconds since the
+    /// unix epoch
+    first_result_ms: Option<u64>,
+    /// Aggregated results of every finished run of the campaign
+    campaign_results: Vec<CampaignRunResults>,
+    /// Whether every run of the campaign has finished
+    campaign_finished: bool,
+    /// The heartbeats of the nodes of the current run
+    health: HealthTracker,
+    /// The partitions of the current run in force, ordered in the replies to heartbeats
+    partitions: PartitionTracker,
 }
 
 impl<KEY: SignatureKey + 'static, ELECTION: ElectionConfig + 'static>
     OrchestratorState<KEY, ELECTION>
 {
     /// create a new [`OrchestratorState`]
+    #[must_use]
     pub fn new(network_config: NetworkConfig<KEY, ELECTION>) -> Self {
+        let total_nodes =
+            u16::try_from(network_config.config.total_nodes.get()).unwrap_or(u16::MAX);
+        let health = HealthTracker::new(network_config.stall_timeout_seconds * 1000);
+        let partitions = PartitionTracker::new(
+            network_config.partition_schedule.clone(),
+            network_config.config.total_nodes.get() as u64,
+        );
         OrchestratorState {
-            latest_index: 0,
+            available_indices: (0..total_nodes).collect(),
             config: network_config,
             nodes_with_pubkey: 0,
             peer_pub_ready: false,
             pub_posted: HashSet::new(),
             nodes_connected: 0,
             start: false,
+            newcomers: Vec::new(),
+            run_index: 0,
+            pending_configs: VecDeque::new(),
+            run_results: Vec::new(),
+            first_result_ms: None,
+            campaign_results: Vec::new(),
+            campaign_finished: false,
+            health,
+            partitions,
+        }
+    }
+
+    /// create a new [`OrchestratorState`] which runs each of `configs` in turn
+    /// # Panics
+    /// if `configs` is empty
+    #[must_use]
+    pub fn new_campaign(configs: Vec<NetworkConfig<KEY, ELECTION>>) -> Self {
+        let mut configs: VecDeque<_> = configs.into();
+        let first = configs
+            .pop_front()
+            .expect("A campaign needs at least one configuration");
+        let mut state = Self::new(first);
+        state.pending_configs = configs;
+        state
+    }
+
+    /// Record the results of the current run and move on to the next configuration of the
+    /// campaign, resetting registration so nodes can join the next run.
+    fn finish_run(&mut self) {
+        let results = CampaignRunResults {
+            run_index: self.run_index,
+            total_nodes: self.config.config.total_nodes.get(),
+            transaction_size: self.config.transaction_size,
+            transactions_per_round: self.config.transactions_per_round,
+            node_results: std::mem::take(&mut self.run_results),
+        };
+        println!(
+            "Run {} ({} nodes, {} byte transactions, {} transactions per round) finished: {:.2} transactions/s",
+            results.run_index,
+            results.total_nodes,
+            results.transaction_size,
+            results.transactions_per_round,
+            results.mean_throughput()
+        );
+        let (recovered, partitioned) = results.recovered_nodes();
+        if partitioned > 0 {
+            println!(
+                "Run {}: consensus resumed after every partition on {recovered} of the \
+                 {partitioned} partitioned nodes",
+                results.run_index
+            );
+        }
+        self.campaign_results.push(results);
+
+        let Some(next_config) = self.pending_configs.pop_front() else {
+            println!(
+                "Campaign finished after {} runs",
+                self.campaign_results.len()
+            );
+            self.campaign_finished = true;
+            return;
+        };
+        let run_index = self.run_index + 1;
+        let pending_configs = std::mem::take(&mut self.pending_configs);
+        let campaign_results = std::mem::take(&mut self.campaign_results);
+        *self = Self::new(next_config);
+        self.run_index = run_index;
+        self.pending_configs = pending_configs;
+        self.campaign_results = campaign_results;
+    }
+
+    /// Finish the current run if `results_timeout_seconds` passed by `now_ms` since the first
+    /// node posted its results, as the nodes which did not post theirs by then may have
+    /// crashed. Returns whether the run finished.
+    pub fn expire_run(&mut self, now_ms: u64) -> bool {
+        let Some(first_result_ms) = self.first_result_ms else {
+            return false;
+        };
+        if self.campaign_finished
+            || now_ms.saturating_sub(first_result_ms) < self.config.results_timeout_seconds * 1000
+        {
+            return false;
         }
+        println!(
+            "Run {} timed out with the results of {} of its {} nodes",
+            self.run_index,
+            self.run_results.len(),
+            self.pub_posted.len()
+        );
+        self.finish_run();
+        true
+    }
+
+    /// The number of nodes which must be ready before the run starts
+    fn start_threshold(&self) -> u64 {
+        let total_nodes = self.config.config.total_nodes.get();
+        let threshold = self
+            .config
+            .start_threshold
+            .unwrap_or(total_nodes)
+            .min(total_nodes);
+        threshold as u64
     }
 }
 
 /// An api exposed by the orchestrator
 pub trait OrchestratorApi<KEY: SignatureKey, ELECTION: ElectionConfig> {
-    /// post endpoint for identity
+    /// post endpoint for identity: the addresses of the node, one per address family it is
+    /// reachable on
     /// # Errors
     /// if unable to serve
-    fn post_identity(&mut self, identity: IpAddr) -> Result<u16, ServerError>;
+    fn post_identity(&mut self, identity: Vec<IpAddr>) -> Result<u16, ServerError>;
     /// post endpoint for each node's config
     /// # Errors
     /// if unable to serve
@@ -120,7 +284,57 @@ pub trait OrchestratorApi<KEY: SignatureKey, ELECTION: ElectionConfig> {
     /// post endpoint for the results of the run
     /// # Errors
     /// if unable to serve
-    fn post_run_results(&mut self) -> Result<(), ServerError>;
+    fn post_run_results(&mut self, results: RunResults) -> Result<(), ServerError>;
+    /// get endpoint for the index of the run currently accepting nodes, or `None` once the
+    /// campaign has finished
+    /// # Errors
+    /// if unable to serve
+    fn get_run_index(&self) -> Result<Option<u64>, ServerError>;
+    /// get endpoint for the number of runs of the campaign after run `run_index`
+    /// # Errors
+    /// if unable to serve
+    fn get_runs_after(&self, run_index: u64) -> Result<u64, ServerError>;
+    /// get endpoint for the aggregated results of every finished run
+    /// # Errors
+    /// if unable to serve
+    fn get_campaign_results(&self) -> Result<Vec<CampaignRunResults>, ServerError>;
+    /// get endpoint for the current stake table, including any nodes which joined late
+    /// # Errors
+    /// if unable to serve
+    fn get_stake_table(&self) -> Result<Vec<KEY::StakeTableEntry>, ServerError>;
+    /// get endpoint for a snapshot of the current network config, for nodes joining late
+    /// # Errors
+    /// if unable to serve
+    fn get_config_snapshot(&self) -> Result<NetworkConfig<KEY, ELECTION>, ServerError>;
+    /// get endpoint for the indices of the nodes which joined after the run started, starting
+    /// from the `from`th newcomer
+    /// # Errors
+    /// if unable to serve
+    fn get_newcomers(&self, from: usize) -> Result<Vec<u64>, ServerError>;
+    /// post endpoint for a node leaving the run, whose index is handed out again
+    /// # Errors
+    /// if the node is not part of the run
+    fn post_leave(&mut self, node_index: u64) -> Result<(), ServerError>;
+    /// get endpoint for the wall-clock instant at which the run begins
+    /// # Errors
+    /// if the run has not started yet
+    fn get_genesis_timestamp(&self) -> Result<GenesisTimestamp, ServerError>;
+    /// get endpoint for the orchestrator's clock, in milliseconds since the unix epoch
+    /// # Errors
+    /// if unable to serve
+    fn get_time(&self) -> Result<u64, ServerError>;
+    /// post endpoint for the heartbeats nodes send during the run, returning the partition the
+    /// node must be in, if any
+    /// # Errors
+    /// if unable to serve
+    fn post_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<Option<PartitionOrder>, ServerError>;
+    /// post endpoint checking the health of every node which sent a heartbeat, by node index
+    /// # Errors
+    /// if unable to serve
+    fn check_node_health(&mut self) -> Result<BTreeMap<u64, NodeHealthReport>, ServerError>;
 }
 
 impl<KEY, ELECTION> OrchestratorApi<KEY, ELECTION> for OrchestratorState<KEY, ELECTION>
@@ -128,17 +342,14 @@ where
     KEY: serde::Serialize + Clone + SignatureKey,
     ELECTION: serde::Serialize + Clone + Send + ElectionConfig,
 {
-    fn post_identity(&mut self, identity: IpAddr) -> Result<u16, ServerError> {
-        let node_index = self.latest_index;
-        self.latest_index += 1;
-
+    fn post_identity(&mut self, identity: Vec<IpAddr>) -> Result<u16, ServerError> {
         // TODO https://github.com/EspressoSystems/HotShot/issues/850
-        if usize::from(node_index) >= self.config.config.total_nodes.get() {
+        let Some(node_index) = self.available_indices.pop_first() else {
             return Err(ServerError {
                 status: tide_disco::StatusCode::BadRequest,
                 message: "Network has reached capacity".to_string(),
             });
-        }
+        };
 
         if self.config.libp2p_config.clone().is_some() {
             let libp2p_config_clone = self.config.libp2p_config.clone().unwrap();
@@ -149,15 +360,19 @@ where
                 } else {
                     0
                 };
-                let socketaddr =
-                    SocketAddr::new(identity, libp2p_config_clone.base_port + port_index);
-                let keypair = libp2p_generate_indexed_identity(self.config.seed, node_index.into());
+                let mut socketaddrs: Vec<SocketAddr> = identity
+                    .iter()
+                    .map(|ip| SocketAddr::new(*ip, libp2p_config_clone.base_port + port_index))
+                    .collect();
+                prefer_family(&mut socketaddrs, libp2p_config_clone.preferred_ip_family);
+                let keypair =
+                    libp2p_generate_indexed_identity(self.config.libp2p_seed, node_index.into());
                 self.config
                     .libp2p_config
                     .as_mut()
                     .unwrap()
                     .bootstrap_nodes
-                    .push((socketaddr, keypair.to_protobuf_encoding().unwrap()));
+                    .push((socketaddrs, keypair.to_protobuf_encoding().unwrap()));
             }
         }
         Ok(node_index)
@@ -167,7 +382,7 @@ where
     // 'identity' endpoint
     fn post_getconfig(
         &mut self,
-        _node_index: u16,
+        node_index: u16,
     ) -> Result<NetworkConfig<KEY, ELECTION>, ServerError> {
         if self.config.libp2p_config.is_some() {
             let libp2p_config = self.config.clone().libp2p_config.unwrap();
@@ -178,7 +393,7 @@ where
                 });
             }
         }
-        Ok(self.config.clone())
+        Ok(self.config.for_node(node_index.into()))
     }
 
     #[allow(clippy::cast_possible_truncation)]
@@ -193,22 +408,46 @@ where
                 message: "Node has already posted public key".to_string(),
             });
         }
-        self.pub_posted.insert(node_index);
+        let stake = self.config.stake_of(node_index);
+        let Some(slot) = self
+            .config
+            .config
+            .known_nodes_with_stake
+            .get_mut(node_index as usize)
+        else {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BadRequest,
+                message: format!("There is no node {node_index}"),
+            });
+        };
 
         // Sishan NOTE: let me know if there's a better way to remove the first extra 8 bytes
         // The guess is extra bytes are from orchestrator serialization
-        pubkey.drain(..8);
-        let register_pub_key = <KEY as SignatureKey>::from_bytes(pubkey).unwrap();
-        let register_pub_key_with_stake = register_pub_key.get_stake_table_entry(1u64);
-        self.config.config.known_nodes_with_stake[node_index as usize] =
-            register_pub_key_with_stake;
+        pubkey.drain(..pubkey.len().min(8));
+        let Ok(register_pub_key) = <KEY as SignatureKey>::from_bytes(pubkey) else {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BadRequest,
+                message: "Invalid public key".to_string(),
+            });
+        };
+        *slot = register_pub_key.get_stake_table_entry(stake);
+        self.pub_posted.insert(node_index);
         self.nodes_with_pubkey += 1;
         println!(
             "Node {:?} posted public key, now total num posted public key: {:?}",
             node_index, self.nodes_with_pubkey
         );
-        if self.nodes_with_pubkey >= (self.config.config.total_nodes.get() as u64) {
+        if self.start {
+            println!("Node {node_index:?} joined after the run started");
+            self.newcomers.push(node_index);
+        }
+        if self.nodes_with_pubkey >= self.start_threshold() {
             self.peer_pub_ready = true;
+            if self.config.ramp_up.is_some() && self.config.ramp_up_start_ms.is_none() {
+                // the waves are timed from one instant, rather than from whenever each node
+                // happened to notice every public key was posted
+                self.config.ramp_up_start_ms = Some(unix_time_ms());
+            }
         }
         Ok(())
     }
@@ -249,15 +488,126 @@ where
     fn post_ready(&mut self) -> Result<(), ServerError> {
         self.nodes_connected += 1;
         println!("Nodes connected: {}", self.nodes_connected);
-        if self.nodes_connected >= (self.config.config.total_nodes.get() as u64) {
+        if self.nodes_connected >= self.start_threshold() {
             self.start = true;
+            if self.config.genesis_timestamp_ms.is_none() {
+                // every node begins at the same instant, rather than `start_delay_seconds` after
+                // it happened to notice the start
+                let genesis_ms = unix_time_ms() + self.config.start_delay_seconds * 1000;
+                self.config.genesis_timestamp_ms = Some(genesis_ms);
+            }
+        }
+        Ok(())
+    }
+
+    fn post_run_results(&mut self, results: RunResults) -> Result<(), ServerError> {
+        if self
+            .run_results
+            .iter()
+            .any(|posted| posted.node_index == results.node_index)
+        {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BadRequest,
+                message: "Node has already posted results for this run".to_string(),
+            });
+        }
+        self.run_results.push(results);
+        self.first_result_ms.get_or_insert_with(unix_time_ms);
+        if self.run_results.len() >= self.pub_posted.len() {
+            self.finish_run();
         }
         Ok(())
     }
 
-    fn post_run_results(&mut self) -> Result<(), ServerError> {
+    fn get_run_index(&self) -> Result<Option<u64>, ServerError> {
+        Ok((!self.campaign_finished).then_some(self.run_index))
+    }
+
+    fn get_runs_after(&self, run_index: u64) -> Result<u64, ServerError> {
+        if self.campaign_finished {
+            return Ok(0);
+        }
+        // the runs still to come, and the current one if `run_index` is over already
+        Ok(self.pending_configs.len() as u64 + self.run_index.saturating_sub(run_index))
+    }
+
+    fn get_campaign_results(&self) -> Result<Vec<CampaignRunResults>, ServerError> {
+        Ok(self.campaign_results.clone())
+    }
+
+    fn get_stake_table(&self) -> Result<Vec<KEY::StakeTableEntry>, ServerError> {
+        Ok(self.config.config.known_nodes_with_stake.clone())
+    }
+
+    fn get_config_snapshot(&self) -> Result<NetworkConfig<KEY, ELECTION>, ServerError> {
+        Ok(self.config.clone())
+    }
+
+    fn get_newcomers(&self, from: usize) -> Result<Vec<u64>, ServerError> {
+        Ok(self.newcomers.get(from..).unwrap_or_default().to_vec())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn post_leave(&mut self, node_index: u64) -> Result<(), ServerError> {
+        if !self.pub_posted.remove(&node_index) {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BadRequest,
+                message: "Node is not part of the current run".to_string(),
+            });
+        }
+        self.nodes_with_pubkey -= 1;
+        self.health.forget(node_index);
+        // indices are handed out as u16, so that of a registered node fits
+        self.available_indices.insert(node_index as u16);
+        println!("Node {node_index} left the run, its index is available again");
+        // the run is over once every node still in it posted its results
+        if !self.run_results.is_empty() && self.run_results.len() >= self.pub_posted.len() {
+            self.finish_run();
+        }
         Ok(())
     }
+
+    fn get_genesis_timestamp(&self) -> Result<GenesisTimestamp, ServerError> {
+        let Some(genesis_ms) = self.config.genesis_timestamp_ms else {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BadRequest,
+                message: "Network is not ready to start".to_string(),
+            });
+        };
+        Ok(GenesisTimestamp {
+            genesis_ms,
+            orchestrator_now_ms: unix_time_ms(),
+        })
+    }
+
+    fn get_time(&self) -> Result<u64, ServerError> {
+        Ok(unix_time_ms())
+    }
+
+    fn post_heartbeat(
+        &mut self,
+        heartbeat: Heartbeat,
+    ) -> Result<Option<PartitionOrder>, ServerError> {
+        if !self.pub_posted.contains(&heartbeat.node_index) {
+            return Err(ServerError {
+                status: tide_disco::StatusCode::BadRequest,
+                message: "Node is not part of the current run".to_string(),
+            });
+        }
+        let now_ms = unix_time_ms();
+        let order = self
+            .partitions
+            .order(heartbeat.node_index, heartbeat.current_view, now_ms);
+        self.health.record(heartbeat, now_ms);
+        // every heartbeat doubles as a check of the other nodes, so that silent nodes are
+        // reported as long as a single node is alive
+        self.health.check(now_ms);
+        Ok(order)
+    }
+
+    fn check_node_health(&mut self) -> Result<BTreeMap<u64, NodeHealthReport>, ServerError> {
+        Ok(self.health.check(unix_time_ms()))
+    }
 }
 
 /// Sets up all API routes
@@ -277,14 +627,18 @@ where
     let mut api = Api::<State, ServerError>::new(api_toml)?;
     api.post("postidentity", |req, state| {
         async move {
-            let identity = req.string_param("identity")?.parse::<IpAddr>();
-            if identity.is_err() {
-                return Err(ServerError {
+            let identity = req
+                .string_param("identity")?
+                .split(',')
+                .map(str::parse::<IpAddr>)
+                .collect::<Result<Vec<_>, _>>();
+            match identity {
+                Ok(identity) if !identity.is_empty() => state.post_identity(identity),
+                _ => Err(ServerError {
                     status: tide_disco::StatusCode::BadRequest,
-                    message: "Identity is not a properly formed IP address".to_string(),
-                });
+                    message: "Identity is not a comma separated list of IP addresses".to_string(),
+                }),
             }
-            state.post_identity(identity.unwrap())
         }
         .boxed()
     })?
@@ -316,17 +670,69 @@ where
     .get("getstart", |_req, state| {
         async move { state.get_start() }.boxed()
     })?
-    .post("postresults", |_req, state| {
-        async move { state.post_run_results() }.boxed()
+    .post("postresults", |req, state| {
+        async move {
+            let results = req.body_json()?;
+            state.post_run_results(results)
+        }
+        .boxed()
+    })?
+    .get("run_index", |_req, state| {
+        async move { state.get_run_index() }.boxed()
+    })?
+    .get("runs_after", |req, state| {
+        async move {
+            let run_index = req.integer_param("run_index")?;
+            state.get_runs_after(run_index)
+        }
+        .boxed()
+    })?
+    .get("campaign_results", |_req, state| {
+        async move { state.get_campaign_results() }.boxed()
+    })?
+    .get("stake_table", |_req, state| {
+        async move { state.get_stake_table() }.boxed()
+    })?
+    .get("config_snapshot", |_req, state| {
+        async move { state.get_config_snapshot() }.boxed()
+    })?
+    .get("newcomers", |req, state| {
+        async move {
+            let from = req.integer_param("from")?;
+            state.get_newcomers(from)
+        }
+        .boxed()
+    })?
+    .post("leave", |req, state| {
+        async move {
+            let node_index = req.integer_param("node_index")?;
+            state.post_leave(node_index)
+        }
+        .boxed()
+    })?
+    .get("genesis_timestamp", |_req, state| {
+        async move { state.get_genesis_timestamp() }.boxed()
+    })?
+    .get("time", |_req, state| {
+        async move { state.get_time() }.boxed()
+    })?
+    .post("heartbeat", |req, state| {
+        async move {
+            let heartbeat = req.body_json()?;
+            state.post_heartbeat(heartbeat)
+        }
+        .boxed()
+    })?
+    .post("node_health", |_req, state| {
+        async move { state.check_node_health() }.boxed()
     })?;
     Ok(api)
 }
 
 /// Runs the orchestrator
 /// # Errors
-/// This errors if tide disco runs into an issue during serving
-/// # Panics
-/// This panics if unable to register the api with tide disco
+/// This errors if unable to register the api with tide disco, or if tide disco runs into an
+/// issue during serving
 pub async fn run_orchestrator<KEY, ELECTION>(
     network_config: NetworkConfig<KEY, ELECTION>,
     url: Url,
@@ -335,15 +741,47 @@ where
     KEY: SignatureKey + 'static + serde::Serialize,
     ELECTION: ElectionConfig + 'static + serde::Serialize,
 {
+    run_campaign(vec![network_config], url).await
+}
+
+/// Runs the orchestrator for a campaign, running each of `configs` in turn.  Nodes re-register
+/// with the orchestrator between runs.
+/// # Errors
+/// This errors if `configs` is empty, if unable to register the api with tide disco, or if
+/// tide disco runs into an issue during serving
+pub async fn run_campaign<KEY, ELECTION>(
+    configs: Vec<NetworkConfig<KEY, ELECTION>>,
+    url: Url,
+) -> io::Result<()>
+where
+    KEY: SignatureKey + 'static + serde::Serialize,
+    ELECTION: ElectionConfig + 'static + serde::Serialize,
+{
+    if configs.is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "A campaign needs at least one configuration",
+        ));
+    }
     let web_api =
-        define_api().map_err(|_e| io::Error::new(ErrorKind::Other, "Failed to define api"));
+        define_api().map_err(|_e| io::Error::new(ErrorKind::Other, "Failed to define api"))?;
 
-    let state: RwLock<OrchestratorState<KEY, ELECTION>> =
-        RwLock::new(OrchestratorState::new(network_config));
+    let state = Arc::new(RwLock::new(
+        OrchestratorState::<KEY, ELECTION>::new_campaign(configs),
+    ));
+    // nodes which crash never post their results, so the runs they are part of time out
+    let expiring = Arc::clone(&state);
+    async_spawn(async move {
+        loop {
+            async_sleep(RUN_EXPIRY_CHECK_INTERVAL).await;
+            expiring.write().await.expire_run(unix_time_ms());
+        }
+    });
 
-    let mut app = App::<RwLock<OrchestratorState<KEY, ELECTION>>, ServerError>::with_state(state);
-    app.register_module("api", web_api.unwrap())
-        .expect("Error registering api");
+    let mut app =
+        App::<Arc<RwLock<OrchestratorState<KEY, ELECTION>>>, ServerError>::with_state(state);
+    app.register_module("api", web_api)
+        .map_err(|e| io::Error::new(ErrorKind::Other, format!("Error registering api: {e}")))?;
     tracing::error!("listening on {:?}", url);
     app.serve(url).await
 }
@@ -0,0 +1,112 @@
+//! Closed-loop rate control of the transactions a node submits
+//!
+//! A fixed number of transactions per round either starves consensus or overwhelms it. A
+//! [`LoadController`] instead adjusts the number of transactions a node submits after each
+//! decide to what consensus keeps up with: it raises the rate by a step while the mempool stays
+//! shallow and decides stay fast, and cuts it by a percentage as soon as either crosses its
+//! target, so that the rate settles around the saturation point. The throughput committed while
+//! the rate is held there is the sustainable throughput of the network.
+
+use std::time::Duration;
+
+use crate::config::LoadGeneratorConfig;
+
+/// What a node observed of consensus at a decide
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LoadObservation {
+    /// transactions waiting in the mempool of the node
+    pub mempool_depth: usize,
+    /// how long a transaction submitted now is expected to take to be decided, if known
+    pub decide_latency: Option<Duration>,
+    /// transactions committed by the decide
+    pub transactions_committed: u64,
+    /// time since the previous decide
+    pub elapsed: Duration,
+}
+
+/// Adjusts the transactions submitted after each decide, see the [module documentation](self)
+#[derive(Clone, Debug)]
+pub struct LoadController {
+    /// the targets and bounds of the rate
+    config: LoadGeneratorConfig,
+    /// transactions to submit after the current decide
+    rate: u64,
+    /// whether consensus has fallen behind at least once, i.e. the saturation point was found
+    saturated: bool,
+    /// transactions committed while the rate was held around the saturation point
+    held_transactions: u64,
+    /// time the rate was held around the saturation point
+    held_time: Duration,
+}
+
+impl LoadController {
+    /// A controller starting at the initial rate of `config`
+    #[must_use]
+    pub fn new(config: LoadGeneratorConfig) -> Self {
+        Self {
+            config,
+            rate: config
+                .initial_rate
+                .max(config.min_rate)
+                .min(config.max_rate),
+            saturated: false,
+            held_transactions: 0,
+            held_time: Duration::ZERO,
+        }
+    }
+
+    /// Transactions to submit after the current decide
+    #[must_use]
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Whether consensus has fallen behind at least once, so the rate is held around the
+    /// saturation point
+    #[must_use]
+    pub fn is_saturated(&self) -> bool {
+        self.saturated
+    }
+
+    /// Whether consensus is falling behind, according to `observation`
+    #[must_use]
+    pub fn is_overloaded(&self, observation: &LoadObservation) -> bool {
+        let max_decide_latency = Duration::from_millis(self.config.max_decide_latency_ms);
+        observation.mempool_depth > self.config.target_mempool_depth
+            || observation
+                .decide_latency
+                .is_some_and(|latency| latency > max_decide_latency)
+    }
+
+    /// Adjust the rate to `observation` of a decide, and return the transactions to submit
+    /// after it
+    pub fn observe(&mut self, observation: &LoadObservation) -> u64 {
+        if self.is_overloaded(observation) {
+            self.saturated = true;
+            let cut = self
+                .rate
+                .saturating_mul(self.config.decrease_percent.min(100))
+                / 100;
+            self.rate = (self.rate - cut).max(self.config.min_rate);
+        } else {
+            if self.saturated {
+                self.held_transactions += observation.transactions_committed;
+                self.held_time += observation.elapsed;
+            }
+            self.rate = self
+                .rate
+                .saturating_add(self.config.increase_step)
+                .min(self.config.max_rate);
+        }
+        self.rate
+    }
+
+    /// Transactions committed per second while consensus kept up with the rate, once the
+    /// saturation point was found. [`None`] until then.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn sustainable_tps(&self) -> Option<f64> {
+        (!self.held_time.is_zero())
+            .then(|| self.held_transactions as f64 / self.held_time.as_secs_f64())
+    }
+}
@@ -0,0 +1,419 @@
+//! Checks of a node's environment before it joins a run
+//!
+//! A node which cannot bind its port, reach the web server or keep up with its disk only fails
+//! once consensus has started, in ways which are hard to tell apart from bugs. Before joining a
+//! run, each validator checks its clock against the orchestrator's, the space and write latency
+//! of the disk payloads are spilled to, that the ports it listens on are free, and that it can
+//! reach the web servers and libp2p bootstrap peers of the run. The outcome of every check is
+//! printed as a [`PreflightReport`], and validators refuse to start on hard failures unless
+//! forced to. The probes block, so validators run them through [`check`], off the executor.
+
+use std::{
+    fmt, fs,
+    io::{ErrorKind, Write},
+    net::{SocketAddr, TcpStream, UdpSocket},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+#[cfg(async_executor_impl = "async-std")]
+use async_std::task::spawn_blocking;
+use hotshot_types::traits::{election::ElectionConfig, signature_key::SignatureKey};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tide_disco::Url;
+#[cfg(async_executor_impl = "tokio")]
+use tokio::task::spawn_blocking;
+
+use crate::config::{IpFamily, NetworkConfig};
+
+/// Clock offsets from the orchestrator larger than this are warned about
+pub const CLOCK_SKEW_WARN_MS: i128 = 500;
+/// Clock offsets from the orchestrator larger than this fail the checks, as nodes would not
+/// start their first view together
+pub const CLOCK_SKEW_FAIL_MS: i128 = 5_000;
+/// Disks with less free space than this fail the checks
+pub const MIN_FREE_DISK_BYTES: u64 = 1 << 30;
+/// Writes of [`WRITE_PROBE_BYTES`] slower than this are warned about
+pub const MAX_WRITE_LATENCY: Duration = Duration::from_millis(200);
+/// The bytes written and synced to disk to measure its write latency
+const WRITE_PROBE_BYTES: usize = 1 << 20;
+/// How long network probes wait for a connection or an error
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The outcome of a check
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    /// nothing is wrong
+    Pass,
+    /// the node may run, but likely worse than it should
+    Warn,
+    /// the node should not join the run
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "PASS",
+            Self::Warn => "WARN",
+            Self::Fail => "FAIL",
+        })
+    }
+}
+
+/// The outcome of one check, with what to do about it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CheckResult {
+    /// what was checked
+    pub name: String,
+    /// whether it passed
+    pub status: CheckStatus,
+    /// what was found
+    pub detail: String,
+    /// how to fix a warning or failure
+    pub remedy: Option<String>,
+}
+
+impl CheckResult {
+    /// A check which passed
+    #[must_use]
+    pub fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            remedy: None,
+        }
+    }
+
+    /// A check which found something the node can run with
+    #[must_use]
+    pub fn warn(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        remedy: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+
+    /// A check which failed
+    #[must_use]
+    pub fn fail(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        remedy: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            remedy: Some(remedy.into()),
+        }
+    }
+}
+
+/// The outcomes of every check of a node
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PreflightReport {
+    /// the checks, in the order they ran
+    pub checks: Vec<CheckResult>,
+}
+
+impl PreflightReport {
+    /// The checks which failed
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+    }
+
+    /// Whether any check failed, in which case the node should not join the run
+    #[must_use]
+    pub fn has_hard_failures(&self) -> bool {
+        self.failures().next().is_some()
+    }
+
+    /// The number of checks with `status`
+    #[must_use]
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    }
+}
+
+impl fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Preflight checks: {} passed, {} warned, {} failed",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail)
+        )?;
+        for check in &self.checks {
+            writeln!(f, "  [{}] {}: {}", check.status, check.name, check.detail)?;
+            if let Some(remedy) = &check.remedy {
+                writeln!(f, "         fix: {remedy}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why the preflight checks keep a node out of a run
+#[derive(Error, Debug)]
+pub enum PreflightError {
+    /// At least one check failed
+    #[error("{} preflight check(s) failed", report.count(CheckStatus::Fail))]
+    Failed {
+        /// the outcome of every check
+        report: PreflightReport,
+    },
+    /// The checks stopped before they finished
+    #[error("the preflight checks did not finish: {0}")]
+    Interrupted(String),
+}
+
+/// Check the offset of the local clock from the orchestrator's, `None` if it could not be
+/// measured
+#[must_use]
+pub fn check_clock_skew(offset_ms: Option<i128>) -> CheckResult {
+    let name = "clock skew";
+    let remedy = "synchronize the clock over NTP, e.g. enable chronyd or systemd-timesyncd";
+    match offset_ms {
+        None => CheckResult::warn(
+            name,
+            "could not read the orchestrator's clock",
+            "check that the orchestrator is running and reachable",
+        ),
+        Some(offset) if offset.abs() > CLOCK_SKEW_FAIL_MS => CheckResult::fail(
+            name,
+            format!("local clock is {offset}ms off the orchestrator's"),
+            remedy,
+        ),
+        Some(offset) if offset.abs() > CLOCK_SKEW_WARN_MS => CheckResult::warn(
+            name,
+            format!("local clock is {offset}ms off the orchestrator's"),
+            remedy,
+        ),
+        Some(offset) => CheckResult::pass(name, format!("{offset}ms off the orchestrator's")),
+    }
+}
+
+/// The free space of the file system holding `directory`, in bytes, `None` if unknown
+#[cfg(target_os = "linux")]
+fn free_disk_bytes(directory: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(directory.as_os_str().as_bytes()).ok()?;
+    // SAFETY: the path is a valid nul terminated string, and the kernel only writes the
+    // statistics it returns
+    let stat = unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(path.as_ptr(), &mut stat) != 0 {
+            return None;
+        }
+        stat
+    };
+    // the types of the fields differ between platforms
+    #[allow(clippy::useless_conversion)]
+    let free = u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize));
+    Some(free)
+}
+
+/// The free space of the file system holding `directory`, which is not known outside of linux
+#[cfg(not(target_os = "linux"))]
+fn free_disk_bytes(_directory: &Path) -> Option<u64> {
+    None
+}
+
+/// Check the free space of the disk holding `directory`, and how long it takes to write and
+/// sync a file there
+#[must_use]
+pub fn check_disk(directory: &Path, min_free_bytes: u64) -> Vec<CheckResult> {
+    let shown = directory.display();
+    let space = match free_disk_bytes(directory) {
+        Some(free) if free < min_free_bytes => CheckResult::fail(
+            "disk space",
+            format!(
+                "{}MiB free in {shown}, {}MiB needed",
+                free >> 20,
+                min_free_bytes >> 20
+            ),
+            "free up space, or set `payload_spill.directory` to a larger volume",
+        ),
+        Some(free) => CheckResult::pass("disk space", format!("{}MiB free in {shown}", free >> 20)),
+        None => CheckResult::warn(
+            "disk space",
+            format!("could not read the free space in {shown}"),
+            "check the free space by hand",
+        ),
+    };
+
+    let probe = directory.join(format!(".hotshot-preflight-{}", std::process::id()));
+    let started = Instant::now();
+    let written = fs::File::create(&probe).and_then(|mut file| {
+        file.write_all(&vec![0u8; WRITE_PROBE_BYTES])?;
+        file.sync_all()
+    });
+    let latency = started.elapsed();
+    let _ = fs::remove_file(&probe);
+    let write = match written {
+        Err(e) => CheckResult::fail(
+            "disk write",
+            format!("could not write to {shown}: {e}"),
+            "create the directory and make it writable, or set `payload_spill.directory`",
+        ),
+        Ok(()) if latency > MAX_WRITE_LATENCY => CheckResult::warn(
+            "disk write",
+            format!(
+                "writing {}KiB to {shown} took {}ms",
+                WRITE_PROBE_BYTES >> 10,
+                latency.as_millis()
+            ),
+            "spill payloads to a faster disk, or raise `payload_spill.memory_budget`",
+        ),
+        Ok(()) => CheckResult::pass(
+            "disk write",
+            format!(
+                "wrote {}KiB to {shown} in {}ms",
+                WRITE_PROBE_BYTES >> 10,
+                latency.as_millis()
+            ),
+        ),
+    };
+    vec![space, write]
+}
+
+/// Check that nothing else listens on UDP port `addr`, where libp2p listens over QUIC
+#[must_use]
+pub fn check_udp_port(addr: SocketAddr) -> CheckResult {
+    let name = format!("udp port {addr}");
+    match UdpSocket::bind(addr) {
+        Ok(_) => CheckResult::pass(name, "free"),
+        Err(e) => CheckResult::fail(
+            name,
+            format!("could not bind: {e}"),
+            "stop the process holding the port, or change `base_port` or `bind_ips`",
+        ),
+    }
+}
+
+/// Check whether bootstrap peer `addr` can be reached over UDP.
+///
+/// Nothing answers a bare datagram, so a peer counts as reachable unless sending fails or the
+/// peer's host reports that nothing listens on the port. Bootstrap peers may start after this
+/// node, so a closed port is only a warning.
+#[must_use]
+pub fn check_udp_peer(addr: SocketAddr) -> CheckResult {
+    let name = format!("bootstrap peer {addr}");
+    let remedy = "check the firewall lets UDP through to the peer, and that the peer runs";
+    let local = SocketAddr::new(IpFamily::of(&addr.ip()).unspecified(), 0);
+    let probe = UdpSocket::bind(local).and_then(|socket| {
+        socket.set_read_timeout(Some(PROBE_TIMEOUT))?;
+        socket.connect(addr)?;
+        socket.send(&[0])?;
+        Ok(socket)
+    });
+    let socket = match probe {
+        Ok(socket) => socket,
+        Err(e) => return CheckResult::fail(name, format!("unreachable: {e}"), remedy),
+    };
+    match socket.recv(&mut [0u8; 64]) {
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+            CheckResult::warn(name, "nothing listens on the port yet", remedy)
+        }
+        _ => CheckResult::pass(name, "no error reaching it"),
+    }
+}
+
+/// Check that a web server listens at `url`
+#[must_use]
+pub fn check_web_server(url: &Url) -> CheckResult {
+    let name = format!("web server {url}");
+    let remedy = "start the web server, or check the url of the run configuration";
+    let addrs = match url.socket_addrs(|| None) {
+        Ok(addrs) if !addrs.is_empty() => addrs,
+        Ok(_) => return CheckResult::fail(name, "the url resolves to no address", remedy),
+        Err(e) => return CheckResult::fail(name, format!("could not resolve: {e}"), remedy),
+    };
+    let mut last_error = None;
+    for addr in &addrs {
+        match TcpStream::connect_timeout(addr, PROBE_TIMEOUT) {
+            Ok(_) => return CheckResult::pass(name, format!("reachable at {addr}")),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    let detail = last_error.map_or_else(
+        || "unreachable".to_string(),
+        |e| format!("unreachable: {e}"),
+    );
+    CheckResult::fail(name, detail, remedy)
+}
+
+/// Run every check which applies to the node of `config`, given the offset of its clock from
+/// the orchestrator's. The network checks block for up to a few seconds each.
+#[must_use]
+pub fn run<KEY: SignatureKey, ELECTION: ElectionConfig>(
+    config: &NetworkConfig<KEY, ELECTION>,
+    clock_offset_ms: Option<i128>,
+) -> PreflightReport {
+    let mut checks = vec![check_clock_skew(clock_offset_ms)];
+
+    let spill_directory = config
+        .config
+        .payload_spill
+        .directory
+        .clone()
+        .unwrap_or_else(std::env::temp_dir);
+    checks.extend(check_disk(&spill_directory, MIN_FREE_DISK_BYTES));
+
+    if let Some(libp2p_config) = &config.libp2p_config {
+        let port = libp2p_config.listen_port(config.node_index);
+        for ip in libp2p_config.listen_ips() {
+            checks.push(check_udp_port(SocketAddr::new(ip, port)));
+        }
+        let own = usize::try_from(config.node_index).ok();
+        for (i, (addrs, _)) in libp2p_config.bootstrap_nodes.iter().enumerate() {
+            if Some(i) != own {
+                checks.extend(addrs.iter().map(|addr| check_udp_peer(*addr)));
+            }
+        }
+    }
+
+    for web_server_config in [&config.web_server_config, &config.da_web_server_config]
+        .into_iter()
+        .flatten()
+    {
+        let urls = std::iter::once(&web_server_config.url).chain(&web_server_config.shard_urls);
+        checks.extend(urls.map(check_web_server));
+    }
+
+    PreflightReport { checks }
+}
+
+/// [`run`] the checks of the node of `config` on the blocking threads of the executor, so the
+/// probes do not hold up the tasks of the node while they wait on the disk and the network
+/// # Errors
+/// [`PreflightError::Failed`], with the report, if any check failed, and
+/// [`PreflightError::Interrupted`] if the checks panicked
+pub async fn check<KEY: SignatureKey + 'static, ELECTION: ElectionConfig + 'static>(
+    config: NetworkConfig<KEY, ELECTION>,
+    clock_offset_ms: Option<i128>,
+) -> Result<PreflightReport, PreflightError> {
+    let report = spawn_blocking(move || run(&config, clock_offset_ms)).await;
+    #[cfg(async_executor_impl = "tokio")]
+    let report = report.map_err(|e| PreflightError::Interrupted(e.to_string()))?;
+    if report.has_hard_failures() {
+        return Err(PreflightError::Failed { report });
+    }
+    Ok(report)
+}
@@ -0,0 +1,139 @@
+//! The JSON Schema of run configuration files, and their validation against it
+//!
+//! The schema is derived from [`NetworkConfigFile`] and the types of its fields, so it follows
+//! them as they change. Unlike the deserialization of a run configuration, which stops at the
+//! first error and ignores fields it does not know, validation reports every field which does
+//! not match the schema, misspelt fields included, by its dotted TOML path.
+
+use std::{fmt, fs};
+
+use hotshot_types::traits::signature_key::SignatureKey;
+use jsonschema::JSONSchema;
+use schemars::schema_for;
+use serde_json::Value;
+
+use crate::config::NetworkConfigFile;
+
+/// A field of a run configuration file which does not match the schema
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ConfigViolation {
+    /// the field, as a TOML path such as `node_override[0].role`, empty for the whole file
+    pub path: String,
+    /// what is wrong with the field
+    pub message: String,
+}
+
+impl ConfigViolation {
+    /// A violation of the whole file, rather than of one of its fields
+    fn whole_file(message: impl fmt::Display) -> Self {
+        Self {
+            path: String::new(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// The JSON Schema of run configuration files, read as a [`NetworkConfigFile`]. Fields the
+/// schema does not describe are not allowed, as they are most likely misspelt.
+/// # Panics
+/// Never: schemas always serialize to JSON
+#[must_use]
+pub fn network_config_schema<KEY: SignatureKey>() -> Value {
+    let mut schema = serde_json::to_value(schema_for!(NetworkConfigFile<KEY>))
+        .expect("schemas always serialize to JSON");
+    deny_unknown_fields(&mut schema);
+    schema
+}
+
+/// Check the run configuration `contents`, in TOML, against [`network_config_schema`]
+/// # Errors
+/// Every field which does not match the schema, or why `contents` cannot be read as a run
+/// configuration otherwise
+pub fn validate_config<KEY: SignatureKey>(contents: &str) -> Result<(), Vec<ConfigViolation>> {
+    let document: toml::Table =
+        toml::from_str(contents).map_err(|e| vec![ConfigViolation::whole_file(e)])?;
+    let instance =
+        serde_json::to_value(document).map_err(|e| vec![ConfigViolation::whole_file(e)])?;
+    let schema = network_config_schema::<KEY>();
+    let compiled = JSONSchema::compile(&schema).map_err(|e| {
+        vec![ConfigViolation::whole_file(format!(
+            "the schema of run configurations is invalid: {e}"
+        ))]
+    })?;
+    if let Err(errors) = compiled.validate(&instance) {
+        let mut violations: Vec<_> = errors
+            .map(|error| ConfigViolation {
+                path: toml_path(&error.instance_path.to_string()),
+                message: error.to_string(),
+            })
+            .collect();
+        violations.sort();
+        violations.dedup();
+        return Err(violations);
+    }
+
+    // the schema does not capture every check of the deserialization, e.g. of durations, nor
+    // whether the mnemonic is valid
+    let file = toml::from_str::<NetworkConfigFile<KEY>>(contents)
+        .map_err(|e| vec![ConfigViolation::whole_file(e)])?;
+    file.seeds().map(|_| ()).map_err(|e| {
+        vec![ConfigViolation {
+            path: "mnemonic".to_string(),
+            message: e.to_string(),
+        }]
+    })
+}
+
+/// Check the run configuration file at `path` against [`network_config_schema`]
+/// # Errors
+/// If the file cannot be read, or does not match the schema, see [`validate_config`]
+pub fn validate_config_file<KEY: SignatureKey>(path: &str) -> Result<(), Vec<ConfigViolation>> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        vec![ConfigViolation::whole_file(format!(
+            "cannot read {path}: {e}"
+        ))]
+    })?;
+    validate_config::<KEY>(&contents)
+}
+
+/// Forbid the fields `schema` does not describe in every object it describes
+fn deny_unknown_fields(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.contains_key("properties") && !object.contains_key("additionalProperties") {
+                object.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            object.values_mut().for_each(deny_unknown_fields);
+        }
+        Value::Array(items) => items.iter_mut().for_each(deny_unknown_fields),
+        _ => {}
+    }
+}
+
+/// The TOML path, such as `node_override[0].role`, of the JSON pointer `pointer`, such as
+/// `/node_override/0/role`
+fn toml_path(pointer: &str) -> String {
+    let mut path = String::new();
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if segment.parse::<usize>().is_ok() {
+            path.push_str(&format!("[{segment}]"));
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&segment);
+        }
+    }
+    path
+}
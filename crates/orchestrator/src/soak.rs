@@ -0,0 +1,251 @@
+//! Resource leak detection over long soak runs
+//!
+//! Leaks which add a few bytes, a file descriptor or a task per view go unnoticed over a benchmark
+//! of minutes. In a soak run, each node samples its resource usage at a fixed interval for hours,
+//! and a [`SoakMonitor`] fits a line through the samples of each metric taken after the warm-up.
+//! A metric whose line rises by more than its threshold over the run is reported as a leak, and
+//! fails the run. Fitting a trend rather than comparing the first and last samples keeps short
+//! spikes, e.g. of memory during a burst of large blocks, from being taken for leaks.
+
+use std::{fmt, fs, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::SoakConfig;
+
+/// The resource usage of a node at one instant. Metrics the platform does not expose are `None`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceSample {
+    /// time since the run started
+    pub elapsed: Duration,
+    /// resident set size of the process, in bytes
+    pub rss_bytes: Option<u64>,
+    /// file descriptors the process holds open
+    pub open_fds: Option<u64>,
+    /// threads of the process
+    pub threads: Option<u64>,
+    /// tasks of the node, counted as the receivers of its internal event channel
+    pub tasks: Option<u64>,
+    /// events queued in the event channels of the node
+    pub channel_depth: Option<u64>,
+}
+
+/// The usage of the resources of the current process which the operating system reports
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProcessUsage {
+    /// resident set size, in bytes
+    pub rss_bytes: Option<u64>,
+    /// number of open file descriptors
+    pub open_fds: Option<u64>,
+    /// number of threads
+    pub threads: Option<u64>,
+}
+
+impl ProcessUsage {
+    /// The usage of the current process, read from `/proc`. Nothing is known on platforms
+    /// without it.
+    #[must_use]
+    pub fn current() -> Self {
+        let status = fs::read_to_string("/proc/self/status").ok();
+        // the value of a field of the status, without its unit
+        let field = |name: &str| {
+            status.as_deref()?.lines().find_map(|line| {
+                let value = line.strip_prefix(name)?.strip_prefix(':')?;
+                value.split_whitespace().next()?.parse::<u64>().ok()
+            })
+        };
+        Self {
+            rss_bytes: field("VmRSS").map(|kilobytes| kilobytes * 1024),
+            open_fds: fs::read_dir("/proc/self/fd")
+                .ok()
+                .map(|entries| entries.count() as u64),
+            threads: field("Threads"),
+        }
+    }
+}
+
+/// A metric sampled through a soak run
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceMetric {
+    /// resident memory, whose growth is a percentage of its size after the warm-up
+    Rss,
+    /// open file descriptors
+    OpenFds,
+    /// threads of the process
+    Threads,
+    /// tasks of the node
+    Tasks,
+    /// events queued in the event channels
+    ChannelDepth,
+}
+
+impl ResourceMetric {
+    /// Every metric, in the order they are checked
+    pub const ALL: [Self; 5] = [
+        Self::Rss,
+        Self::OpenFds,
+        Self::Threads,
+        Self::Tasks,
+        Self::ChannelDepth,
+    ];
+
+    /// The value of the metric in `sample`, if it was sampled
+    #[must_use]
+    pub fn of(self, sample: &ResourceSample) -> Option<u64> {
+        match self {
+            Self::Rss => sample.rss_bytes,
+            Self::OpenFds => sample.open_fds,
+            Self::Threads => sample.threads,
+            Self::Tasks => sample.tasks,
+            Self::ChannelDepth => sample.channel_depth,
+        }
+    }
+
+    /// The most the metric may grow over a run, in the unit of [`ResourceLeak::growth`]
+    #[must_use]
+    pub fn threshold(self, config: &SoakConfig) -> u64 {
+        match self {
+            Self::Rss => config.max_rss_growth_percent,
+            Self::OpenFds => config.max_fd_growth,
+            Self::Threads | Self::Tasks => config.max_task_growth,
+            Self::ChannelDepth => config.max_channel_depth_growth,
+        }
+    }
+}
+
+impl fmt::Display for ResourceMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rss => "resident memory",
+            Self::OpenFds => "open file descriptors",
+            Self::Threads => "threads",
+            Self::Tasks => "tasks",
+            Self::ChannelDepth => "channel depth",
+        })
+    }
+}
+
+/// A metric which trended upward beyond its threshold over a soak run
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ResourceLeak {
+    /// the metric
+    pub metric: ResourceMetric,
+    /// how much the trend of the metric rose over the run: a percentage of the size after the
+    /// warm-up for resident memory, a count for the other metrics
+    pub growth: f64,
+    /// the most the metric was allowed to grow
+    pub threshold: u64,
+}
+
+impl fmt::Display for ResourceLeak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = if self.metric == ResourceMetric::Rss {
+            "%"
+        } else {
+            ""
+        };
+        write!(
+            f,
+            "{} grew by {:.1}{unit}, beyond the threshold of {}{unit}",
+            self.metric, self.growth, self.threshold
+        )
+    }
+}
+
+/// The slope of the least squares line through `points`, or `None` if there are fewer than two
+/// distinct instants
+#[allow(clippy::cast_precision_loss)]
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance < f64::EPSILON {
+        return None;
+    }
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    Some(covariance / variance)
+}
+
+/// Collects the resource samples of a soak run and finds the metrics which leak, see the
+/// [module documentation](self)
+#[derive(Clone, Debug)]
+pub struct SoakMonitor {
+    /// the sampling interval and thresholds
+    config: SoakConfig,
+    /// every sample taken, oldest first
+    samples: Vec<ResourceSample>,
+}
+
+impl SoakMonitor {
+    /// A monitor with no samples yet
+    #[must_use]
+    pub fn new(config: SoakConfig) -> Self {
+        Self {
+            config,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Time between samples
+    #[must_use]
+    pub fn sample_interval(&self) -> Duration {
+        Duration::from_secs(self.config.sample_interval_seconds)
+    }
+
+    /// Record a sample, taken after every sample recorded before
+    pub fn record(&mut self, sample: ResourceSample) {
+        self.samples.push(sample);
+    }
+
+    /// Every sample recorded, oldest first
+    #[must_use]
+    pub fn samples(&self) -> &[ResourceSample] {
+        &self.samples
+    }
+
+    /// The metrics whose trend after the warm-up rose beyond their threshold. Nothing leaks
+    /// before at least two samples were taken after the warm-up.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn leaks(&self) -> Vec<ResourceLeak> {
+        let measured = self
+            .samples
+            .get(self.config.warmup_samples..)
+            .unwrap_or_default();
+        ResourceMetric::ALL
+            .into_iter()
+            .filter_map(|metric| {
+                let points: Vec<(f64, f64)> = measured
+                    .iter()
+                    .filter_map(|sample| {
+                        let value = metric.of(sample)?;
+                        Some((sample.elapsed.as_secs_f64(), value as f64))
+                    })
+                    .collect();
+                let span = points.last()?.0 - points.first()?.0;
+                let mut growth = slope(&points)? * span;
+                if metric == ResourceMetric::Rss {
+                    let baseline = points.first()?.1;
+                    if baseline <= 0.0 {
+                        return None;
+                    }
+                    growth = growth / baseline * 100.0;
+                }
+                let threshold = metric.threshold(&self.config);
+                (growth > threshold as f64).then_some(ResourceLeak {
+                    metric,
+                    growth,
+                    threshold,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,190 @@
+//! Readiness and watchdog notifications to systemd
+//!
+//! A validator run as a `Type=notify` systemd service tells systemd it is ready once its networks
+//! are connected and consensus has started, and, with `WatchdogSec=` set, keeps pinging the
+//! watchdog from a timer of its own while consensus makes progress, so that systemd restarts a
+//! node whose consensus is stuck. The notifications are sent to the socket systemd passes in `NOTIFY_SOCKET`; nodes not
+//! run by systemd have no such socket and send nothing.
+
+use std::{env, time::Duration};
+
+use tracing::{info, warn};
+
+/// The socket to systemd
+#[cfg(unix)]
+struct NotifySocket {
+    /// the socket notifications are sent from
+    socket: std::os::unix::net::UnixDatagram,
+    /// the address of the socket of systemd
+    addr: std::os::unix::net::SocketAddr,
+}
+
+#[cfg(unix)]
+impl NotifySocket {
+    /// Connect to the socket named `name`, an abstract socket if it starts with `@`
+    fn connect(name: &str) -> std::io::Result<Self> {
+        use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+        let addr = match name.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            Some(abstract_name) => {
+                use std::os::linux::net::SocketAddrExt;
+                SocketAddr::from_abstract_name(abstract_name)?
+            }
+            #[cfg(not(target_os = "linux"))]
+            Some(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "abstract sockets are only supported on linux",
+                ))
+            }
+            None => SocketAddr::from_pathname(name)?,
+        };
+        Ok(Self {
+            socket: UnixDatagram::unbound()?,
+            addr,
+        })
+    }
+
+    /// Send `state` to systemd
+    fn send(&self, state: &str) -> std::io::Result<()> {
+        self.socket.send_to_addr(state.as_bytes(), &self.addr)?;
+        Ok(())
+    }
+
+    /// Another socket sending to the same systemd
+    fn try_clone(&self) -> std::io::Result<Self> {
+        Ok(Self {
+            socket: self.socket.try_clone()?,
+            addr: self.addr.clone(),
+        })
+    }
+}
+
+/// Sends readiness and watchdog notifications to systemd, if the node is run by it
+pub struct SystemdNotifier {
+    /// the socket to systemd, if the node is run by it
+    #[cfg(unix)]
+    socket: Option<NotifySocket>,
+    /// how often the watchdog is pinged, if systemd watches the node
+    watchdog_interval: Option<Duration>,
+}
+
+impl SystemdNotifier {
+    /// A notifier to the systemd which started this process, doing nothing if there is none
+    #[must_use]
+    pub fn from_env() -> Self {
+        #[cfg(unix)]
+        let socket =
+            env::var("NOTIFY_SOCKET")
+                .ok()
+                .and_then(|name| match NotifySocket::connect(&name) {
+                    Ok(socket) => Some(socket),
+                    Err(e) => {
+                        warn!("Cannot notify systemd at {name}: {e}");
+                        None
+                    }
+                });
+        #[cfg(unix)]
+        let watched = socket.is_some();
+        #[cfg(not(unix))]
+        let watched = false;
+
+        // the watchdog is for this process only if systemd says so, or does not say otherwise
+        let watchdog_pid_matches = !env::var("WATCHDOG_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .is_some_and(|pid| pid != std::process::id());
+        // systemd expects pings at least every `WATCHDOG_USEC`, twice as often leaves a margin
+        let watchdog_interval = env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|usec| *usec > 0 && watched && watchdog_pid_matches)
+            .map(|usec| Duration::from_micros(usec) / 2);
+        if let Some(interval) = watchdog_interval {
+            info!("Pinging the systemd watchdog every {interval:?}");
+        }
+
+        Self {
+            #[cfg(unix)]
+            socket,
+            watchdog_interval,
+        }
+    }
+
+    /// Whether systemd watches the node, and expects watchdog pings
+    #[must_use]
+    pub fn is_watched(&self) -> bool {
+        self.watchdog_interval.is_some()
+    }
+
+    /// Tell systemd the node is ready: its networks are connected and consensus has started
+    pub fn notify_ready(&self) {
+        self.notify("READY=1\nSTATUS=Running consensus");
+    }
+
+    /// Tell systemd the node is stopping
+    pub fn notify_stopping(&self) {
+        self.notify("STOPPING=1");
+    }
+
+    /// The watchdog of systemd, if it watches the node. It is pinged from a timer of its own,
+    /// so that pings do not depend on the loop handling the events of the node.
+    #[must_use]
+    pub fn watchdog(&self) -> Option<Watchdog> {
+        let interval = self.watchdog_interval?;
+        #[cfg(unix)]
+        {
+            let socket = match self.socket.as_ref()?.try_clone() {
+                Ok(socket) => socket,
+                Err(e) => {
+                    warn!("Cannot ping the systemd watchdog: {e}");
+                    return None;
+                }
+            };
+            Some(Watchdog { socket, interval })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = interval;
+            None
+        }
+    }
+
+    /// Send `state` to systemd, if the node is run by it
+    #[cfg_attr(not(unix), allow(clippy::unused_self, unused_variables))]
+    fn notify(&self, state: &str) {
+        #[cfg(unix)]
+        if let Some(socket) = &self.socket {
+            if let Err(e) = socket.send(state) {
+                warn!("Failed to notify systemd of {state:?}: {e}");
+            }
+        }
+    }
+}
+
+/// The watchdog of the systemd watching the node, see [`SystemdNotifier::watchdog`]
+pub struct Watchdog {
+    /// the socket to systemd
+    #[cfg(unix)]
+    socket: NotifySocket,
+    /// how often the watchdog is pinged
+    interval: Duration,
+}
+
+impl Watchdog {
+    /// How often the watchdog is to be pinged, half the time systemd waits for a ping
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Ping the watchdog, as consensus made progress
+    #[cfg_attr(not(unix), allow(clippy::unused_self))]
+    pub fn ping(&self) {
+        #[cfg(unix)]
+        if let Err(e) = self.socket.send("WATCHDOG=1") {
+            warn!("Failed to ping the systemd watchdog: {e}");
+        }
+    }
+}
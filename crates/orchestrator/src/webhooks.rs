@@ -0,0 +1,179 @@
+//! Webhooks notifying downstream systems of decided leaves
+//!
+//! Every configured endpoint receives a JSON notification, signed by the node's consensus key,
+//! for each Decide event. Failed deliveries are retried with exponential backoff, and given up on
+//! once `max_attempts` is reached.
+
+use std::time::Duration;
+
+use async_compatibility_layer::art::{async_sleep, async_spawn};
+use hotshot_types::{
+    signing::{self, SigningDomain},
+    traits::signature_key::SignatureKey,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_inline_default::serde_inline_default;
+use surf_disco::Url;
+use tracing::{debug, error, warn};
+
+/// default number of attempts to deliver a notification to an endpoint
+pub const WEBHOOK_DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// default delay before retrying a failed delivery, in milliseconds
+pub const WEBHOOK_DEFAULT_INITIAL_BACKOFF_MS: u64 = 500;
+/// default cap on the delay between retries, in milliseconds
+pub const WEBHOOK_DEFAULT_MAX_BACKOFF_MS: u64 = 30_000;
+
+/// Where and how to deliver Decide notifications
+#[serde_inline_default]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Eq)]
+pub struct WebhookConfig {
+    /// the endpoints notifications are posted to
+    #[schemars(with = "Vec<String>")]
+    pub urls: Vec<Url>,
+    /// number of attempts to deliver a notification to an endpoint before giving up
+    #[serde_inline_default(WEBHOOK_DEFAULT_MAX_ATTEMPTS)]
+    pub max_attempts: u32,
+    /// delay before the first retry, doubled after every failed attempt
+    #[serde_inline_default(WEBHOOK_DEFAULT_INITIAL_BACKOFF_MS)]
+    pub initial_backoff_ms: u64,
+    /// cap on the delay between retries
+    #[serde_inline_default(WEBHOOK_DEFAULT_MAX_BACKOFF_MS)]
+    pub max_backoff_ms: u64,
+}
+
+/// What a node reports about a decided leaf
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DecideNotification {
+    /// view of the newest decided leaf
+    pub view: u64,
+    /// commitment of the newest decided leaf
+    pub leaf_commitment: String,
+    /// number of transactions decided, if known
+    pub block_size: Option<u64>,
+    /// when the node saw the decide, in milliseconds since the unix epoch
+    pub timestamp: u64,
+}
+
+/// A [`DecideNotification`] with the signature of the node which sent it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct SignedDecideNotification<KEY: SignatureKey> {
+    /// the notification
+    pub notification: DecideNotification,
+    /// the public key of the node
+    pub signer: KEY,
+    /// signature of the JSON serialization of `notification`, in the decide notification
+    /// signing domain
+    pub signature: KEY::PureAssembledSignatureType,
+}
+
+impl<KEY: SignatureKey> SignedDecideNotification<KEY> {
+    /// Whether `signature` is `signer`'s signature of `notification`
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        serde_json::to_vec(&self.notification).is_ok_and(|bytes| {
+            signing::validate(
+                &self.signer,
+                &self.signature,
+                SigningDomain::DecideNotification,
+                &bytes,
+            )
+        })
+    }
+}
+
+/// Sends signed Decide notifications to the configured webhooks
+pub struct WebhookNotifier<KEY: SignatureKey> {
+    /// where and how to deliver notifications
+    config: WebhookConfig,
+    /// the client notifications are posted with
+    client: surf::Client,
+    /// the public key of this node
+    public_key: KEY,
+    /// the private key notifications are signed with
+    private_key: KEY::PrivateKey,
+}
+
+impl<KEY: SignatureKey + 'static> WebhookNotifier<KEY> {
+    /// Create a notifier signing with the given keys
+    #[must_use]
+    pub fn new(config: WebhookConfig, public_key: KEY, private_key: KEY::PrivateKey) -> Self {
+        Self {
+            config,
+            client: surf::Client::new(),
+            public_key,
+            private_key,
+        }
+    }
+
+    /// Sign `notification` and deliver it to every endpoint in the background
+    pub fn notify(&self, notification: DecideNotification) {
+        let body = match self.sign(notification) {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Failed to sign decide notification: {e}");
+                return;
+            }
+        };
+        for url in &self.config.urls {
+            async_spawn(deliver(
+                self.client.clone(),
+                url.clone(),
+                body.clone(),
+                self.config.clone(),
+            ));
+        }
+    }
+
+    /// The JSON body of the signed notification
+    fn sign(&self, notification: DecideNotification) -> Result<String, String> {
+        let bytes = serde_json::to_vec(&notification).map_err(|e| e.to_string())?;
+        let signature =
+            signing::sign::<KEY>(&self.private_key, SigningDomain::DecideNotification, &bytes)
+                .map_err(|e| e.to_string())?;
+        serde_json::to_string(&SignedDecideNotification {
+            notification,
+            signer: self.public_key.clone(),
+            signature,
+        })
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Post `body` to `url`, retrying with exponential backoff until it is accepted
+async fn deliver(client: surf::Client, url: Url, body: String, config: WebhookConfig) {
+    let mut backoff = Duration::from_millis(config.initial_backoff_ms);
+    let max_backoff = Duration::from_millis(config.max_backoff_ms);
+    for attempt in 1..=config.max_attempts {
+        let result = client
+            .post(url.as_str())
+            .body_string(body.clone())
+            .content_type(surf::http::mime::JSON)
+            .await;
+        match result {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered decide notification to {url}");
+                return;
+            }
+            Ok(response) => {
+                warn!(
+                    "Webhook {url} answered {} (attempt {attempt} of {})",
+                    response.status(),
+                    config.max_attempts
+                );
+            }
+            Err(e) => {
+                warn!(
+                    "Webhook {url} failed: {e} (attempt {attempt} of {})",
+                    config.max_attempts
+                );
+            }
+        }
+        if attempt < config.max_attempts {
+            async_sleep(backoff).await;
+            backoff = (backoff * 2).min(max_backoff);
+        }
+    }
+    error!("Giving up on delivering a decide notification to {url}");
+}
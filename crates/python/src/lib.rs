@@ -0,0 +1,571 @@
+//! Python bindings for the orchestrator client and benchmark analytics
+//!
+//! Performance engineers script benchmark campaigns and analyze their results from notebooks
+//! through the `hotshot_bench` module, without writing Rust. Its `OrchestratorClient` follows
+//! a running campaign and downloads its results, and the results, whether downloaded or parsed
+//! from the JSON the orchestrator serves, flatten into records which load straight into a
+//! `pandas.DataFrame`:
+//!
+//! ```python
+//! import hotshot_bench, pandas
+//!
+//! client = hotshot_bench.OrchestratorClient("http://localhost:4444")
+//! runs = client.campaign_results()
+//! nodes = pandas.DataFrame(hotshot_bench.node_records(runs))
+//! samples = pandas.DataFrame(hotshot_bench.sample_records(runs))
+//! ```
+//!
+//! Build the module with `maturin develop` from this directory, with the same
+//! `async_executor_impl` cfg as the rest of the workspace in `RUSTFLAGS`.
+
+use std::{fs, future::Future, time::Duration};
+
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_orchestrator::{
+    campaign::{CampaignRunResults, RunResults},
+    client::OrchestratorClient,
+};
+use hotshot_types::traits::node_implementation::NodeType;
+use pyo3::{
+    exceptions::{PyConnectionError, PyOSError, PyValueError},
+    prelude::*,
+};
+use serde::Serialize;
+use tide_disco::Url;
+
+/// The signature key of the nodes of the campaigns the client follows
+type Key = <TestTypes as NodeType>::SignatureKey;
+
+/// The election config of the nodes of the campaigns the client follows
+type Election = <TestTypes as NodeType>::ElectionConfigType;
+
+/// Run `future` to completion on the executor the client runs on
+#[cfg(async_executor_impl = "tokio")]
+fn block_on<F: Future>(future: F) -> F::Output {
+    use std::sync::OnceLock;
+    /// the runtime every request of the process is sent from
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to start the tokio runtime")
+        })
+        .block_on(future)
+}
+
+/// Run `future` to completion on the executor the client runs on
+#[cfg(async_executor_impl = "async-std")]
+fn block_on<F: Future>(future: F) -> F::Output {
+    async_std::task::block_on(future)
+}
+
+/// Convert `value` to the Python objects its JSON encoding decodes to
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+}
+
+/// The `ConnectionError` raised when a query of the orchestrator fails with `error`
+fn unreachable(error: impl std::fmt::Display) -> PyErr {
+    PyConnectionError::new_err(error.to_string())
+}
+
+/// Committed transactions per second of a node over its run, excluding the warm-up
+#[allow(clippy::cast_precision_loss)]
+fn throughput(results: &RunResults) -> f64 {
+    if results.total_time_elapsed.is_zero() {
+        return 0.0;
+    }
+    results.total_transactions_committed as f64 / results.total_time_elapsed.as_secs_f64()
+}
+
+/// One row per node and run: the parameters of the run with the results of the node
+#[derive(Serialize, Clone, Debug, PartialEq)]
+struct NodeRecord {
+    /// index of the run within the campaign
+    run_index: u64,
+    /// total number of nodes of the run
+    total_nodes: usize,
+    /// size of transactions of the run
+    transaction_size: usize,
+    /// number of transactions per view of the run
+    transactions_per_round: usize,
+    /// index of the node
+    node_index: u64,
+    /// number of views decided after the warm-up
+    num_successful_commits: usize,
+    /// number of transactions the node submitted
+    total_transactions_sent: u64,
+    /// number of transactions committed after the warm-up, as seen by the node
+    total_transactions_committed: u64,
+    /// wall clock seconds the run took, excluding the warm-up
+    total_time_elapsed: f64,
+    /// committed transactions per second
+    throughput: f64,
+    /// committed transactions per second the load generator held consensus at, if it found it
+    sustainable_tps: Option<f64>,
+    /// number of partitions the node went through
+    partitions: usize,
+    /// number of those partitions after which the node saw consensus resume
+    recovered_partitions: usize,
+    /// number of resources the node leaked during a soak run
+    resource_leaks: usize,
+}
+
+impl NodeRecord {
+    /// The row of `results`, posted by a node for `run`
+    fn new(run: &CampaignRunResults, results: &RunResults) -> Self {
+        Self {
+            run_index: run.run_index,
+            total_nodes: run.total_nodes,
+            transaction_size: run.transaction_size,
+            transactions_per_round: run.transactions_per_round,
+            node_index: results.node_index,
+            num_successful_commits: results.num_successful_commits,
+            total_transactions_sent: results.total_transactions_sent,
+            total_transactions_committed: results.total_transactions_committed,
+            total_time_elapsed: results.total_time_elapsed.as_secs_f64(),
+            throughput: throughput(results),
+            sustainable_tps: results.sustainable_tps,
+            partitions: results.partitions.len(),
+            recovered_partitions: results
+                .partitions
+                .iter()
+                .filter(|outcome| outcome.recovered_after.is_some())
+                .count(),
+            resource_leaks: results.resource_leaks.len(),
+        }
+    }
+}
+
+/// One row per throughput sample of a node
+#[derive(Serialize, Clone, Debug, PartialEq)]
+struct SampleRecord {
+    /// index of the run within the campaign, if the sample is from a campaign
+    run_index: Option<u64>,
+    /// index of the node which took the sample
+    node_index: u64,
+    /// seconds since the end of the warm-up at which the sample was taken
+    elapsed: f64,
+    /// seconds the sample covers
+    interval: f64,
+    /// number of transactions committed during the interval
+    transactions_committed: u64,
+    /// number of views decided during the interval
+    commits: usize,
+    /// committed transactions per second over the interval
+    throughput: f64,
+}
+
+/// The rows of the throughput samples of `results`, from run `run_index` if known
+#[allow(clippy::cast_precision_loss)]
+fn node_samples(results: &RunResults, run_index: Option<u64>) -> Vec<SampleRecord> {
+    results
+        .throughput_samples
+        .iter()
+        .map(|sample| SampleRecord {
+            run_index,
+            node_index: results.node_index,
+            elapsed: sample.elapsed.as_secs_f64(),
+            interval: sample.interval.as_secs_f64(),
+            transactions_committed: sample.transactions_committed,
+            commits: sample.commits,
+            throughput: if sample.interval.is_zero() {
+                0.0
+            } else {
+                sample.transactions_committed as f64 / sample.interval.as_secs_f64()
+            },
+        })
+        .collect()
+}
+
+/// One row per partition a node went through
+#[derive(Serialize, Clone, Debug, PartialEq)]
+struct PartitionRecord {
+    /// index of the node
+    node_index: u64,
+    /// the view at which the partition started
+    view: u64,
+    /// seconds the partition lasted
+    duration: f64,
+    /// seconds from the heal to the first decide after it, if the node decided again
+    recovered_after: Option<f64>,
+}
+
+/// A client of the orchestrator of a benchmark campaign.
+///
+/// Every call blocks until the orchestrator answers, with the GIL released. `run_index` and
+/// `wait_for_next_run` retry until the orchestrator can be reached, and the other queries
+/// raise a `ConnectionError` if it cannot.
+#[pyclass(name = "OrchestratorClient")]
+struct PyOrchestratorClient {
+    /// the client of the orchestrator
+    client: OrchestratorClient,
+}
+
+#[pymethods]
+impl PyOrchestratorClient {
+    /// Connect to the orchestrator at `url`
+    #[new]
+    #[pyo3(signature = (url, identity = String::new()))]
+    fn new(url: &str, identity: String) -> PyResult<Self> {
+        let url = Url::parse(url).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self {
+            client: OrchestratorClient::connect(url, identity),
+        })
+    }
+
+    /// The index of the run currently accepting nodes, or `None` once the campaign has finished
+    fn run_index(&self, py: Python<'_>) -> Option<u64> {
+        py.allow_threads(|| block_on(self.client.get_run_index()))
+    }
+
+    /// Wait for the orchestrator to move past run `finished_run`, and return whether there is
+    /// another run of the campaign
+    fn wait_for_next_run(&self, py: Python<'_>, finished_run: u64) -> bool {
+        py.allow_threads(|| block_on(self.client.wait_for_next_run(finished_run)))
+    }
+
+    /// The results of every finished run of the campaign
+    fn campaign_results(&self, py: Python<'_>) -> PyResult<Vec<PyCampaignRun>> {
+        let runs = py
+            .allow_threads(|| block_on(self.client.get_campaign_results()))
+            .map_err(unreachable)?;
+        Ok(runs
+            .into_iter()
+            .map(|inner| PyCampaignRun { inner })
+            .collect())
+    }
+
+    /// Post `results` as a node's results for the current run
+    fn post_run_results(&self, py: Python<'_>, results: &PyRunResults) -> PyResult<()> {
+        let results = results.inner.clone();
+        py.allow_threads(|| block_on(self.client.post_run_results(results)))
+            .map_err(unreachable)
+    }
+
+    /// The current stake table, as a list of the JSON objects of its entries
+    fn stake_table(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let stake_table = py
+            .allow_threads(|| block_on(self.client.get_stake_table::<Key>()))
+            .map_err(unreachable)?;
+        to_python(py, &stake_table)
+    }
+
+    /// A snapshot of the network config of the current run, as a dict of its JSON encoding
+    fn config_snapshot(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let config = py
+            .allow_threads(|| block_on(self.client.get_config_snapshot::<Key, Election>()))
+            .map_err(unreachable)?;
+        to_python(py, &config)
+    }
+
+    /// The node indices of the nodes which joined after the run started, skipping the first
+    /// `start` of them
+    #[pyo3(signature = (start = 0))]
+    fn newcomers(&self, py: Python<'_>, start: usize) -> PyResult<Vec<u64>> {
+        py.allow_threads(|| block_on(self.client.get_newcomers(start)))
+            .map_err(unreachable)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("OrchestratorClient(identity={:?})", self.client.identity)
+    }
+}
+
+/// The results of a single node's run
+#[pyclass(name = "RunResults")]
+#[derive(Clone)]
+struct PyRunResults {
+    /// the results
+    inner: RunResults,
+}
+
+#[pymethods]
+impl PyRunResults {
+    /// Parse the JSON results a node posts to the orchestrator
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        serde_json::from_str(json)
+            .map(|inner| Self { inner })
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// The JSON encoding of the results, as the orchestrator expects them
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Index of the node which ran
+    #[getter]
+    fn node_index(&self) -> u64 {
+        self.inner.node_index
+    }
+
+    /// Number of views decided after the warm-up
+    #[getter]
+    fn num_successful_commits(&self) -> usize {
+        self.inner.num_successful_commits
+    }
+
+    /// Number of transactions the node submitted
+    #[getter]
+    fn total_transactions_sent(&self) -> u64 {
+        self.inner.total_transactions_sent
+    }
+
+    /// Number of transactions committed after the warm-up, as seen by the node
+    #[getter]
+    fn total_transactions_committed(&self) -> u64 {
+        self.inner.total_transactions_committed
+    }
+
+    /// Wall clock seconds the run took, excluding the warm-up
+    #[getter]
+    fn total_time_elapsed(&self) -> f64 {
+        self.inner.total_time_elapsed.as_secs_f64()
+    }
+
+    /// Committed transactions per second the load generator held consensus at, if it found it
+    #[getter]
+    fn sustainable_tps(&self) -> Option<f64> {
+        self.inner.sustainable_tps
+    }
+
+    /// Committed transactions per second over the run
+    fn throughput(&self) -> f64 {
+        throughput(&self.inner)
+    }
+
+    /// One dict per throughput sample
+    fn samples(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_python(py, &node_samples(&self.inner, None))
+    }
+
+    /// One dict per partition the node went through
+    fn partitions(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let records: Vec<_> = self
+            .inner
+            .partitions
+            .iter()
+            .map(|outcome| PartitionRecord {
+                node_index: self.inner.node_index,
+                view: outcome.view,
+                duration: outcome.duration.as_secs_f64(),
+                recovered_after: outcome.recovered_after.as_ref().map(Duration::as_secs_f64),
+            })
+            .collect();
+        to_python(py, &records)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RunResults(node_index={}, throughput={:.1})",
+            self.inner.node_index,
+            throughput(&self.inner)
+        )
+    }
+}
+
+/// The results of every node for one configuration of a campaign
+#[pyclass(name = "CampaignRun")]
+#[derive(Clone)]
+struct PyCampaignRun {
+    /// the results
+    inner: CampaignRunResults,
+}
+
+#[pymethods]
+impl PyCampaignRun {
+    /// Index of the run within the campaign
+    #[getter]
+    fn run_index(&self) -> u64 {
+        self.inner.run_index
+    }
+
+    /// Total number of nodes
+    #[getter]
+    fn total_nodes(&self) -> usize {
+        self.inner.total_nodes
+    }
+
+    /// Size of transactions
+    #[getter]
+    fn transaction_size(&self) -> usize {
+        self.inner.transaction_size
+    }
+
+    /// Number of transactions per view
+    #[getter]
+    fn transactions_per_round(&self) -> usize {
+        self.inner.transactions_per_round
+    }
+
+    /// The results posted by each node
+    #[getter]
+    fn node_results(&self) -> Vec<PyRunResults> {
+        self.inner
+            .node_results
+            .iter()
+            .cloned()
+            .map(|inner| PyRunResults { inner })
+            .collect()
+    }
+
+    /// Mean committed transactions per second across the nodes
+    fn mean_throughput(&self) -> f64 {
+        self.inner.mean_throughput()
+    }
+
+    /// The number of nodes which saw consensus resume after every partition, and the number
+    /// of nodes which went through at least one
+    fn recovered_nodes(&self) -> (usize, usize) {
+        self.inner.recovered_nodes()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "CampaignRun(run_index={}, total_nodes={}, mean_throughput={:.1})",
+            self.inner.run_index,
+            self.inner.total_nodes,
+            self.inner.mean_throughput()
+        )
+    }
+}
+
+/// Parse the JSON campaign results the orchestrator serves
+fn parse_runs(json: &str) -> PyResult<Vec<PyCampaignRun>> {
+    serde_json::from_str::<Vec<CampaignRunResults>>(json)
+        .map(|runs| {
+            runs.into_iter()
+                .map(|inner| PyCampaignRun { inner })
+                .collect()
+        })
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Parse the JSON results of every run of a campaign, as the orchestrator serves them
+#[pyfunction]
+fn parse_campaign_results(json: &str) -> PyResult<Vec<PyCampaignRun>> {
+    parse_runs(json)
+}
+
+/// Load campaign results saved as JSON at `path`
+#[pyfunction]
+fn load_campaign_results(path: &str) -> PyResult<Vec<PyCampaignRun>> {
+    let json = fs::read_to_string(path).map_err(|e| PyOSError::new_err(e.to_string()))?;
+    parse_runs(&json)
+}
+
+/// One dict per node and run of `runs`, with the parameters of the run
+#[pyfunction]
+fn node_records(py: Python<'_>, runs: Vec<PyCampaignRun>) -> PyResult<PyObject> {
+    let records: Vec<_> = runs
+        .iter()
+        .flat_map(|run| {
+            run.inner
+                .node_results
+                .iter()
+                .map(|results| NodeRecord::new(&run.inner, results))
+        })
+        .collect();
+    to_python(py, &records)
+}
+
+/// One dict per throughput sample of every node and run of `runs`
+#[pyfunction]
+fn sample_records(py: Python<'_>, runs: Vec<PyCampaignRun>) -> PyResult<PyObject> {
+    let records: Vec<_> = runs
+        .iter()
+        .flat_map(|run| {
+            run.inner
+                .node_results
+                .iter()
+                .flat_map(|results| node_samples(results, Some(run.inner.run_index)))
+        })
+        .collect();
+    to_python(py, &records)
+}
+
+/// The `hotshot_bench` Python module
+#[pymodule]
+fn hotshot_bench(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyOrchestratorClient>()?;
+    module.add_class::<PyRunResults>()?;
+    module.add_class::<PyCampaignRun>()?;
+    module.add_function(wrap_pyfunction!(parse_campaign_results, module)?)?;
+    module.add_function(wrap_pyfunction!(load_campaign_results, module)?)?;
+    module.add_function(wrap_pyfunction!(node_records, module)?)?;
+    module.add_function(wrap_pyfunction!(sample_records, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use hotshot_orchestrator::campaign::{PartitionOutcome, ThroughputSample};
+
+    use super::*;
+
+    /// Results of node `node_index`, which committed 500 transactions over 10 seconds
+    fn results(node_index: u64) -> RunResults {
+        RunResults {
+            node_index,
+            num_successful_commits: 20,
+            total_transactions_sent: 600,
+            total_transactions_committed: 500,
+            total_time_elapsed: Duration::from_secs(10),
+            throughput_samples: vec![ThroughputSample {
+                elapsed: Duration::from_secs(2),
+                interval: Duration::from_millis(500),
+                transactions_committed: 40,
+                commits: 2,
+            }],
+            sustainable_tps: None,
+            partitions: vec![PartitionOutcome {
+                view: 5,
+                duration: Duration::from_secs(1),
+                recovered_after: Some(Duration::from_millis(300)),
+            }],
+            ..RunResults::default()
+        }
+    }
+
+    #[test]
+    fn campaign_results_flatten_into_records() {
+        let run = CampaignRunResults {
+            run_index: 3,
+            total_nodes: 2,
+            transaction_size: 64,
+            transactions_per_round: 10,
+            node_results: vec![results(0), results(1)],
+        };
+        let record = NodeRecord::new(&run, &run.node_results[1]);
+        assert_eq!(record.run_index, 3);
+        assert_eq!(record.node_index, 1);
+        assert!((record.throughput - 50.0).abs() < f64::EPSILON);
+        assert_eq!((record.partitions, record.recovered_partitions), (1, 1));
+
+        let samples = node_samples(&run.node_results[0], Some(run.run_index));
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].run_index, Some(3));
+        assert!((samples[0].throughput - 80.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn campaign_results_parse_from_the_orchestrator_json() {
+        let run = CampaignRunResults {
+            run_index: 0,
+            total_nodes: 1,
+            transaction_size: 64,
+            transactions_per_round: 10,
+            node_results: vec![results(0)],
+        };
+        let json = serde_json::to_string(&vec![run]).expect("Failed to encode the results");
+        let runs = parse_runs(&json).expect("Failed to parse the results");
+        assert_eq!(runs.len(), 1);
+        assert!((runs[0].mean_throughput() - 50.0).abs() < f64::EPSILON);
+        assert!(parse_runs("{}").is_err());
+    }
+}
@@ -0,0 +1,418 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use async_broadcast::Sender;
+use async_lock::RwLock;
+use hotshot_constants::{
+    AVAILABILITY_SAMPLING_RETENTION_VIEWS, AVAILABILITY_SAMPLING_TIMEOUT_VIEWS,
+};
+use hotshot_task::task::{Task, TaskState};
+use hotshot_types::{
+    consensus::Consensus,
+    data::{test_srs, VidCommitment, VidDisperse, VidSample, VidScheme, VidSchemeTrait},
+    event::{Event, EventType},
+    signing::{self, SigningDomain},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+    vote::{Certificate, HasViewNumber},
+};
+use rand::seq::{IteratorRandom, SliceRandom};
+use tracing::{debug, error, instrument, warn};
+
+use crate::{
+    events::{HotShotEvent, HotShotTaskCompleted},
+    helpers::broadcast_event,
+};
+
+/// The VID shares of a certified payload this node is sampling
+struct PendingSampling<TYPES: NodeType> {
+    /// View the payload was proposed in
+    view: TYPES::Time,
+    /// Number of shares sampled
+    samples: usize,
+    /// The DA member asked for each share not returned yet, by the node it was dispersed to
+    outstanding: BTreeMap<TYPES::SignatureKey, TYPES::SignatureKey>,
+    /// Number of shares returned which did not match the payload commitment
+    invalid: usize,
+    /// View in which the shares were requested
+    requested_in: TYPES::Time,
+}
+
+/// The VID scheme for `num_storage_nodes`, if one can be set up
+fn vid_scheme(num_storage_nodes: usize) -> Option<VidScheme> {
+    // TODO <https://github.com/EspressoSystems/HotShot/issues/1686>
+    let srs = test_srs(num_storage_nodes);
+    // TODO change after https://github.com/EspressoSystems/jellyfish/issues/339
+    let chunk_size = 1 << num_storage_nodes.ilog2();
+    VidScheme::new(chunk_size, num_storage_nodes, &srs).ok()
+}
+
+/// Tracks state of the availability sampling task, which lets nodes outside the DA committee
+/// check that a certified payload is available by fetching random VID shares of it from DA
+/// members, and lets DA members serve such requests from the dispersals they received.
+pub struct AvailabilitySamplingTaskState<TYPES: NodeType> {
+    /// View number this view is executing in.
+    cur_view: TYPES::Time,
+
+    /// Reference to consensus, for its metrics
+    consensus: Arc<RwLock<Consensus<TYPES>>>,
+
+    /// Membership for the DA committee, which is asked for shares
+    da_membership: Arc<TYPES::Membership>,
+
+    /// Membership for the quorum committee, to which shares are dispersed
+    quorum_membership: Arc<TYPES::Membership>,
+
+    /// This Nodes public key
+    public_key: TYPES::SignatureKey,
+
+    /// This Nodes private key, to sign share requests
+    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+
+    /// The VID scheme shares are checked with, set up once per number of storage nodes since
+    /// generating its SRS is expensive
+    vid: Option<(usize, VidScheme)>,
+
+    /// Number of shares sampled for every certified payload, 0 to not sample
+    samples: usize,
+
+    /// Stream for external events, where availability alarms are raised
+    output_event_stream: Sender<Event<TYPES>>,
+
+    /// Recent VID dispersals, kept to serve sampled shares while this node is a DA member
+    dispersals: BTreeMap<TYPES::Time, VidDisperse<TYPES>>,
+
+    /// Payloads whose shares this node is waiting on
+    pending: HashMap<VidCommitment, PendingSampling<TYPES>>,
+
+    /// This state's ID
+    id: u64,
+}
+
+impl<TYPES: NodeType> AvailabilitySamplingTaskState<TYPES> {
+    /// Create the task state, sampling `samples` shares of every certified payload
+    #[must_use]
+    pub fn new(
+        consensus: Arc<RwLock<Consensus<TYPES>>>,
+        da_membership: Arc<TYPES::Membership>,
+        quorum_membership: Arc<TYPES::Membership>,
+        public_key: TYPES::SignatureKey,
+        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        samples: usize,
+        output_event_stream: Sender<Event<TYPES>>,
+        id: u64,
+    ) -> Self {
+        Self {
+            cur_view: TYPES::Time::new(0),
+            consensus,
+            da_membership,
+            quorum_membership,
+            public_key,
+            private_key,
+            vid: None,
+            samples,
+            output_event_stream,
+            dispersals: BTreeMap::new(),
+            pending: HashMap::new(),
+            id,
+        }
+    }
+
+    /// main task event handler
+    #[instrument(skip_all, fields(id = self.id, view = *self.cur_view), name = "Availability Sampling Task", level = "error")]
+    pub async fn handle(
+        &mut self,
+        event: HotShotEvent<TYPES>,
+        event_stream: Sender<HotShotEvent<TYPES>>,
+    ) -> Option<HotShotTaskCompleted> {
+        match event {
+            HotShotEvent::VidDisperseRecv(disperse, sender) => {
+                let view = disperse.data.get_view_number();
+                if !self.is_da_member(view) || view < self.retained_from() {
+                    return None;
+                }
+                if sender != self.quorum_membership.get_leader(view)
+                    || !signing::validate(
+                        &sender,
+                        &disperse.signature,
+                        SigningDomain::VidDisperse,
+                        disperse.data.payload_commitment.as_ref(),
+                    )
+                {
+                    return None;
+                }
+                self.dispersals.insert(view, disperse.data);
+            }
+            HotShotEvent::DACRecv(cert) => {
+                let view = cert.view_number;
+                let payload_commitment = cert.get_data().payload_commit;
+                if self.samples == 0
+                    || self.is_da_member(view)
+                    || self.pending.contains_key(&payload_commitment)
+                    || !cert.is_valid_da_cert(
+                        self.da_membership.as_ref(),
+                        self.quorum_membership.as_ref(),
+                    )
+                {
+                    return None;
+                }
+                self.sample(payload_commitment, view, &event_stream).await;
+            }
+            HotShotEvent::AvailabilitySampleRequestRecv(
+                payload_commitment,
+                view,
+                owner,
+                requester,
+                signature,
+            ) => {
+                // only staked nodes sample, and the share goes to the node which signed the
+                // request, never to a node whose key a request merely names
+                if !self.quorum_membership.has_stake(&requester)
+                    || !signing::validate(
+                        &requester,
+                        &signature,
+                        SigningDomain::VidShareRequest,
+                        &VidSample::<TYPES>::request_bytes(
+                            &payload_commitment,
+                            view,
+                            &owner,
+                            &self.public_key,
+                        ),
+                    )
+                {
+                    debug!("Dropping a VID share request not signed by a staked requester");
+                    return None;
+                }
+                let Some(sample) = self
+                    .dispersals
+                    .get(&view)
+                    .filter(|disperse| disperse.payload_commitment == payload_commitment)
+                    .and_then(|disperse| VidSample::from_disperse(disperse, &owner))
+                else {
+                    debug!("Received a request for a VID share we do not have");
+                    return None;
+                };
+                broadcast_event(
+                    HotShotEvent::AvailabilitySampleResponseSend(
+                        sample,
+                        requester,
+                        self.public_key.clone(),
+                    ),
+                    &event_stream,
+                )
+                .await;
+            }
+            HotShotEvent::AvailabilitySampleResponseRecv(sample, sender) => {
+                let num_storage_nodes = self.quorum_membership.total_nodes();
+                if self
+                    .pending
+                    .get(&sample.payload_commitment)
+                    .and_then(|sampling| sampling.outstanding.get(&sample.owner))
+                    != Some(&sender)
+                {
+                    return None;
+                }
+                let valid = self.is_valid_share(&sample, num_storage_nodes);
+                let Some(sampling) = self.pending.get_mut(&sample.payload_commitment) else {
+                    return None;
+                };
+                sampling.outstanding.remove(&sample.owner);
+                if !valid {
+                    warn!("Sampled VID share from {sender:?} does not match its commitment");
+                    sampling.invalid += 1;
+                }
+                if sampling.outstanding.is_empty() {
+                    self.finish(sample.payload_commitment).await;
+                }
+            }
+            HotShotEvent::ViewChange(view) => {
+                if view <= self.cur_view {
+                    return None;
+                }
+                self.cur_view = view;
+                let retained_from = self.retained_from();
+                self.dispersals = self.dispersals.split_off(&retained_from);
+
+                let expired: Vec<_> = self
+                    .pending
+                    .iter()
+                    .filter(|(_, sampling)| {
+                        self.cur_view >= sampling.requested_in + AVAILABILITY_SAMPLING_TIMEOUT_VIEWS
+                    })
+                    .map(|(payload_commitment, _)| *payload_commitment)
+                    .collect();
+                for payload_commitment in expired {
+                    self.finish(payload_commitment).await;
+                }
+            }
+            HotShotEvent::Shutdown => {
+                error!("Shutting down because of shutdown signal!");
+                return Some(HotShotTaskCompleted);
+            }
+            _ => {
+                error!("unexpected event {:?}", event);
+            }
+        }
+        None
+    }
+
+    /// Whether `sample` is a share, among `num_storage_nodes`, of the payload with its commitment
+    fn is_valid_share(&mut self, sample: &VidSample<TYPES>, num_storage_nodes: usize) -> bool {
+        if self.vid.as_ref().map(|(nodes, _)| *nodes) != Some(num_storage_nodes) {
+            self.vid = vid_scheme(num_storage_nodes).map(|vid| (num_storage_nodes, vid));
+        }
+        let Some((_, vid)) = &self.vid else {
+            return false;
+        };
+        matches!(
+            vid.verify_share(&sample.share, &sample.common, &sample.payload_commitment),
+            Ok(Ok(()))
+        )
+    }
+
+    /// Whether this node is a DA member in `view`
+    fn is_da_member(&self, view: TYPES::Time) -> bool {
+        self.da_membership
+            .get_committee(view)
+            .contains(&self.public_key)
+    }
+
+    /// The earliest view whose dispersal is kept
+    fn retained_from(&self) -> TYPES::Time {
+        TYPES::Time::new((*self.cur_view).saturating_sub(AVAILABILITY_SAMPLING_RETENTION_VIEWS))
+    }
+
+    /// Ask random DA members for the shares of random nodes of a certified payload
+    async fn sample(
+        &mut self,
+        payload_commitment: VidCommitment,
+        view: TYPES::Time,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        let da_members: Vec<_> = self.da_membership.get_committee(view).into_iter().collect();
+        let mut rng = rand::thread_rng();
+        let outstanding: BTreeMap<_, _> = self
+            .quorum_membership
+            .get_committee(view)
+            .into_iter()
+            .choose_multiple(&mut rng, self.samples)
+            .into_iter()
+            .filter_map(|owner| Some((owner, da_members.choose(&mut rng)?.clone())))
+            .collect();
+        if outstanding.is_empty() {
+            return;
+        }
+
+        for (owner, recipient) in &outstanding {
+            let Ok(signature) = signing::sign::<TYPES::SignatureKey>(
+                &self.private_key,
+                SigningDomain::VidShareRequest,
+                &VidSample::<TYPES>::request_bytes(&payload_commitment, view, owner, recipient),
+            ) else {
+                error!("Failed to sign a VID share request");
+                return;
+            };
+            broadcast_event(
+                HotShotEvent::AvailabilitySampleRequestSend(
+                    payload_commitment,
+                    view,
+                    owner.clone(),
+                    recipient.clone(),
+                    self.public_key.clone(),
+                    signature,
+                ),
+                event_stream,
+            )
+            .await;
+        }
+        self.pending.insert(
+            payload_commitment,
+            PendingSampling {
+                view,
+                samples: outstanding.len(),
+                outstanding,
+                invalid: 0,
+                requested_in: self.cur_view,
+            },
+        );
+    }
+
+    /// Stop sampling a payload, raising an alarm if any of its shares was not returned or was
+    /// invalid
+    async fn finish(&mut self, payload_commitment: VidCommitment) {
+        let Some(sampling) = self.pending.remove(&payload_commitment) else {
+            return;
+        };
+        let failed_samples = sampling.outstanding.len() + sampling.invalid;
+        if failed_samples == 0 {
+            debug!(
+                "Sampled {} VID shares of the payload of view {:?}",
+                sampling.samples, sampling.view
+            );
+            return;
+        }
+
+        warn!(
+            "{failed_samples} of {} VID shares sampled for the payload of view {:?} are missing \
+             or invalid",
+            sampling.samples, sampling.view
+        );
+        {
+            let consensus = self.consensus.read().await;
+            consensus
+                .metrics
+                .availability_samples_failed
+                .add(failed_samples);
+            consensus.metrics.availability_alarms.add(1);
+        }
+        broadcast_event(
+            Event {
+                view_number: sampling.view,
+                event: EventType::AvailabilityAlarm {
+                    view: sampling.view,
+                    payload_commitment,
+                    samples: sampling.samples,
+                    failed_samples,
+                },
+            },
+            &self.output_event_stream,
+        )
+        .await;
+    }
+}
+
+/// task state implementation for the availability sampling task
+impl<TYPES: NodeType> TaskState for AvailabilitySamplingTaskState<TYPES> {
+    type Event = HotShotEvent<TYPES>;
+
+    type Output = HotShotTaskCompleted;
+
+    fn filter(&self, event: &HotShotEvent<TYPES>) -> bool {
+        !matches!(
+            event,
+            HotShotEvent::VidDisperseRecv(_, _)
+                | HotShotEvent::DACRecv(_)
+                | HotShotEvent::AvailabilitySampleRequestRecv(_, _, _, _, _)
+                | HotShotEvent::AvailabilitySampleResponseRecv(_, _)
+                | HotShotEvent::ViewChange(_)
+                | HotShotEvent::Shutdown
+        )
+    }
+
+    async fn handle_event(
+        event: Self::Event,
+        task: &mut Task<Self>,
+    ) -> Option<HotShotTaskCompleted> {
+        let sender = task.clone_sender();
+        task.state_mut().handle(event, sender).await
+    }
+
+    fn should_shutdown(event: &Self::Event) -> bool {
+        matches!(event, HotShotEvent::Shutdown)
+    }
+}
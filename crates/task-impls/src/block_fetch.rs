@@ -0,0 +1,504 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use async_broadcast::Sender;
+use async_compatibility_layer::art::async_spawn;
+use async_lock::RwLock;
+use bytes::Bytes;
+use hotshot_constants::{
+    BLOCK_FETCH_CACHE_SIZE, BLOCK_FETCH_MAX_ATTEMPTS, BLOCK_FETCH_MAX_BYTES_PER_VIEW,
+    BLOCK_FETCH_RETRY_VIEWS,
+};
+use hotshot_task::task::{Task, TaskState};
+use hotshot_types::{
+    consensus::Consensus,
+    data::VidCommitment,
+    payload_store::StoredPayload,
+    rpc::{self, RpcClient, RpcMethod},
+    traits::{
+        block_contents::{vid_commitment, BlockHeader},
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        BlockPayload,
+    },
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, instrument, warn};
+
+use crate::{
+    events::{HotShotEvent, HotShotTaskCompleted},
+    helpers::broadcast_event,
+};
+
+/// Asks a node for the block payload with a commitment, answered with the encoded transactions
+/// of the payload
+pub struct FetchBlock<TYPES>(PhantomData<TYPES>);
+
+/// A request of [`FetchBlock`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct FetchBlockRequest<TYPES: NodeType> {
+    /// the commitment of the payload
+    pub payload_commitment: VidCommitment,
+    /// the view the payload was proposed in
+    pub view: TYPES::Time,
+}
+
+impl<TYPES: NodeType> RpcMethod for FetchBlock<TYPES> {
+    const NAME: &'static str = "fetch_block";
+    type Request = FetchBlockRequest<TYPES>;
+    type Response = Vec<u8>;
+}
+
+/// The payloads a node serves besides those consensus holds, and what it served in the current
+/// view
+#[derive(Default)]
+struct ServedPayloads {
+    /// Recently decided payloads, kept to serve requests after they leave `saved_payloads`
+    cache: HashMap<VidCommitment, StoredPayload>,
+
+    /// Commitments in `cache`, oldest first
+    cache_order: VecDeque<VidCommitment>,
+
+    /// Payload bytes served to other nodes in the current view
+    served_bytes: usize,
+}
+
+/// Serves the payloads this node holds, to the block fetch task and over RPC
+struct BlockServer<TYPES: NodeType> {
+    /// Reference to consensus, for the payloads of undecided views
+    consensus: Arc<RwLock<Consensus<TYPES>>>,
+
+    /// Membership for the quorum committee, needed to recompute payload commitments
+    quorum_membership: Arc<TYPES::Membership>,
+
+    /// The payloads served besides those of consensus, shared with the RPC handler
+    served: Arc<RwLock<ServedPayloads>>,
+}
+
+impl<TYPES: NodeType> Clone for BlockServer<TYPES> {
+    fn clone(&self) -> Self {
+        Self {
+            consensus: Arc::clone(&self.consensus),
+            quorum_membership: Arc::clone(&self.quorum_membership),
+            served: Arc::clone(&self.served),
+        }
+    }
+}
+
+impl<TYPES: NodeType> BlockServer<TYPES> {
+    /// Answer `request`, within [`BLOCK_FETCH_MAX_BYTES_PER_VIEW`] of the current view. Once the
+    /// budget of the view is spent, requests are refused before any payload is looked up.
+    async fn serve(&self, request: FetchBlockRequest<TYPES>) -> Result<Vec<u8>, String> {
+        let remaining =
+            BLOCK_FETCH_MAX_BYTES_PER_VIEW.saturating_sub(self.served.read().await.served_bytes);
+        if remaining == 0 {
+            debug!("Block fetch bandwidth limit reached, dropping request");
+            return Err("bandwidth limit reached".to_string());
+        }
+        let Some(payload) = self
+            .local_payload(request.payload_commitment, request.view, remaining)
+            .await
+        else {
+            debug!("Received a block fetch request for a payload we do not have");
+            return Err("payload not held".to_string());
+        };
+        let mut served = self.served.write().await;
+        if served.served_bytes + payload.len() > BLOCK_FETCH_MAX_BYTES_PER_VIEW {
+            warn!("Block fetch bandwidth limit reached, dropping request");
+            return Err("bandwidth limit reached".to_string());
+        }
+        served.served_bytes += payload.len();
+        Ok(payload.to_vec())
+    }
+
+    /// The payload with this commitment, if this node holds it. The cache and the payload store
+    /// are keyed by commitment, so their payloads are taken as they are. The payload saved for
+    /// `view` may be that of another proposal of the view though, so its commitment is computed
+    /// again and compared, unless it is larger than `max_len` bytes and could not be served
+    /// anyway.
+    async fn local_payload(
+        &self,
+        payload_commitment: VidCommitment,
+        view: TYPES::Time,
+        max_len: usize,
+    ) -> Option<Bytes> {
+        let cached = self
+            .served
+            .read()
+            .await
+            .cache
+            .get(&payload_commitment)
+            .map(StoredPayload::to_bytes);
+        if cached.is_some() {
+            return cached;
+        }
+        let saved = {
+            let consensus = self.consensus.read().await;
+            if let Some(stored) = consensus.payload_store.get(&payload_commitment) {
+                return Some(stored.to_bytes());
+            }
+            consensus.saved_payloads.get(&view).cloned()?
+        };
+        let payload = saved.to_bytes();
+        if payload.len() > max_len {
+            return None;
+        }
+        if vid_commitment(&payload, self.quorum_membership.total_nodes()) != payload_commitment {
+            debug!("The payload held for view {view:?} does not match the commitment asked for");
+            return None;
+        }
+        Some(payload)
+    }
+}
+
+/// A block payload this node is fetching from the DA committee
+struct PendingFetch<TYPES: NodeType> {
+    /// View the payload was proposed in
+    view: TYPES::Time,
+    /// Number of DA members asked so far
+    attempts: usize,
+    /// View in which the latest request was sent
+    requested_in: TYPES::Time,
+}
+
+/// Tracks state of the block fetch task, which lets nodes holding a DAC but not the payload
+/// fetch it from the DA committee over RPC, and serves such requests from the payloads this
+/// node holds.
+pub struct BlockFetchTaskState<TYPES: NodeType> {
+    /// View number this view is executing in.
+    cur_view: TYPES::Time,
+
+    /// Reference to consensus, for the payloads of undecided views
+    consensus: Arc<RwLock<Consensus<TYPES>>>,
+
+    /// Membership for the DA committee, which is asked for payloads
+    da_membership: Arc<TYPES::Membership>,
+
+    /// Membership for the quorum committee, needed to recompute payload commitments
+    quorum_membership: Arc<TYPES::Membership>,
+
+    /// This Nodes public key
+    public_key: TYPES::SignatureKey,
+
+    /// The calls payloads are fetched and served with, if this node makes any
+    rpc: Option<Arc<dyn RpcClient<TYPES::SignatureKey>>>,
+
+    /// Serves the payloads this node holds
+    server: BlockServer<TYPES>,
+
+    /// Payloads this node is waiting on
+    pending: HashMap<VidCommitment, PendingFetch<TYPES>>,
+
+    /// This state's ID
+    id: u64,
+}
+
+impl<TYPES: NodeType> BlockFetchTaskState<TYPES> {
+    /// Create the task state, with an empty cache, serving the [`FetchBlock`] requests of `rpc`
+    #[must_use]
+    pub fn new(
+        consensus: Arc<RwLock<Consensus<TYPES>>>,
+        da_membership: Arc<TYPES::Membership>,
+        quorum_membership: Arc<TYPES::Membership>,
+        public_key: TYPES::SignatureKey,
+        rpc: Option<Arc<dyn RpcClient<TYPES::SignatureKey>>>,
+        id: u64,
+    ) -> Self {
+        let server = BlockServer {
+            consensus: Arc::clone(&consensus),
+            quorum_membership: Arc::clone(&quorum_membership),
+            served: Arc::default(),
+        };
+        if let Some(rpc) = &rpc {
+            let server = server.clone();
+            rpc::serve::<FetchBlock<TYPES>, _, _, _>(&**rpc, move |_, request| {
+                let server = server.clone();
+                async move { server.serve(request).await }
+            });
+        }
+        Self {
+            cur_view: TYPES::Time::new(0),
+            consensus,
+            da_membership,
+            quorum_membership,
+            public_key,
+            rpc,
+            server,
+            pending: HashMap::new(),
+            id,
+        }
+    }
+
+    /// Answer a [`FetchBlock`] request as this node does over RPC
+    ///
+    /// # Errors
+    ///
+    /// If this node does not hold the payload, or served as much as it may in the current view
+    pub async fn serve(&self, request: FetchBlockRequest<TYPES>) -> Result<Vec<u8>, String> {
+        self.server.serve(request).await
+    }
+
+    /// main task event handler
+    #[instrument(skip_all, fields(id = self.id, view = *self.cur_view), name = "Block Fetch Task", level = "error")]
+    pub async fn handle(
+        &mut self,
+        event: HotShotEvent<TYPES>,
+        event_stream: Sender<HotShotEvent<TYPES>>,
+    ) -> Option<HotShotTaskCompleted> {
+        match event {
+            HotShotEvent::BlockFetchNeeded(payload_commitment, view) => {
+                if let Some(payload) = self
+                    .server
+                    .local_payload(payload_commitment, view, usize::MAX)
+                    .await
+                {
+                    broadcast_event(
+                        HotShotEvent::BlockFetched(payload_commitment, view, payload),
+                        &event_stream,
+                    )
+                    .await;
+                    return None;
+                }
+                if self.pending.contains_key(&payload_commitment) {
+                    return None;
+                }
+                self.pending.insert(
+                    payload_commitment,
+                    PendingFetch {
+                        view,
+                        attempts: 0,
+                        requested_in: self.cur_view,
+                    },
+                );
+                self.request(payload_commitment, &event_stream).await;
+            }
+            HotShotEvent::BlockFetchResponseRecv(payload_commitment, view, payload) => {
+                if !self.pending.contains_key(&payload_commitment) {
+                    return None;
+                }
+                if vid_commitment(&payload, self.quorum_membership.total_nodes())
+                    != payload_commitment
+                {
+                    warn!("Fetched block payload does not match its commitment");
+                    return None;
+                }
+                self.pending.remove(&payload_commitment);
+                self.insert_cache(payload_commitment, payload.clone()).await;
+                let stored = self
+                    .server
+                    .served
+                    .read()
+                    .await
+                    .cache
+                    .get(&payload_commitment)
+                    .cloned();
+                if let Some(stored) = stored {
+                    let mut consensus = self.consensus.write().await;
+                    if view > consensus.last_decided_view {
+                        consensus
+                            .saved_payloads
+                            .entry(view)
+                            .or_insert_with(|| stored.clone());
+                    }
+                }
+                broadcast_event(
+                    HotShotEvent::BlockFetched(payload_commitment, view, payload),
+                    &event_stream,
+                )
+                .await;
+            }
+            HotShotEvent::LeafDecided(leaves) => {
+                for leaf in leaves {
+                    let payload_commitment = leaf.get_block_header().payload_commitment();
+                    // share the payload consensus holds rather than encode it again
+                    let held = self
+                        .consensus
+                        .read()
+                        .await
+                        .payload_store
+                        .get(&payload_commitment);
+                    if let Some(payload) = held {
+                        self.cache_stored(payload_commitment, payload).await;
+                        continue;
+                    }
+                    let Some(payload) = leaf.get_block_payload() else {
+                        continue;
+                    };
+                    let Ok(encoded) = payload.encode() else {
+                        continue;
+                    };
+                    self.insert_cache(payload_commitment, encoded.into_iter().collect())
+                        .await;
+                }
+            }
+            HotShotEvent::ViewChange(view) => {
+                if view <= self.cur_view {
+                    return None;
+                }
+                self.cur_view = view;
+                self.server.served.write().await.served_bytes = 0;
+
+                let stale: Vec<_> = self
+                    .pending
+                    .iter()
+                    .filter(|(_, fetch)| {
+                        self.cur_view >= fetch.requested_in + BLOCK_FETCH_RETRY_VIEWS
+                    })
+                    .map(|(payload_commitment, _)| *payload_commitment)
+                    .collect();
+                for payload_commitment in stale {
+                    self.request(payload_commitment, &event_stream).await;
+                }
+            }
+            HotShotEvent::Shutdown => {
+                error!("Shutting down because of shutdown signal!");
+                return Some(HotShotTaskCompleted);
+            }
+            _ => {
+                error!("unexpected event {:?}", event);
+            }
+        }
+        None
+    }
+
+    /// Add a payload to the cache, evicting the oldest entry if it is full. The payload is
+    /// shared with consensus if the payload store holds it already, and written to disk if the
+    /// payload store says so.
+    async fn insert_cache(&mut self, payload_commitment: VidCommitment, payload: Bytes) {
+        if self
+            .server
+            .served
+            .read()
+            .await
+            .cache
+            .contains_key(&payload_commitment)
+        {
+            return;
+        }
+        let payload = self
+            .consensus
+            .read()
+            .await
+            .payload_store
+            .intern(payload_commitment, payload);
+        self.cache_stored(payload_commitment, payload).await;
+    }
+
+    /// Add a payload held by the payload store to the cache, evicting the oldest entry if it
+    /// is full
+    async fn cache_stored(&self, payload_commitment: VidCommitment, payload: StoredPayload) {
+        let mut served = self.server.served.write().await;
+        if served.cache.contains_key(&payload_commitment) {
+            return;
+        }
+        served.cache.insert(payload_commitment, payload);
+        served.cache_order.push_back(payload_commitment);
+        while served.cache_order.len() > BLOCK_FETCH_CACHE_SIZE {
+            if let Some(evicted) = served.cache_order.pop_front() {
+                served.cache.remove(&evicted);
+            }
+        }
+    }
+
+    /// Ask the next DA member for a pending payload over RPC, in the background, giving up after
+    /// [`BLOCK_FETCH_MAX_ATTEMPTS`] members. The payload comes back as a
+    /// [`HotShotEvent::BlockFetchResponseRecv`], and a member which does not answer is given up on
+    /// after [`BLOCK_FETCH_RETRY_VIEWS`] views.
+    async fn request(
+        &mut self,
+        payload_commitment: VidCommitment,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        let Some(fetch) = self.pending.get_mut(&payload_commitment) else {
+            return;
+        };
+        if fetch.attempts >= BLOCK_FETCH_MAX_ATTEMPTS {
+            warn!(
+                "Giving up on fetching the block payload for view {:?}",
+                fetch.view
+            );
+            self.pending.remove(&payload_commitment);
+            return;
+        }
+
+        // the DA leader of the view proposed the payload, so is asked first
+        let recipient = if fetch.attempts == 0 {
+            self.da_membership.get_leader(fetch.view)
+        } else {
+            let committee: Vec<_> = self
+                .da_membership
+                .get_committee(fetch.view)
+                .into_iter()
+                .filter(|member| *member != self.public_key)
+                .collect();
+            if committee.is_empty() {
+                self.pending.remove(&payload_commitment);
+                return;
+            }
+            committee[(fetch.attempts - 1) % committee.len()].clone()
+        };
+        fetch.attempts += 1;
+        fetch.requested_in = self.cur_view;
+        let view = fetch.view;
+
+        let Some(rpc) = self.rpc.clone() else {
+            debug!("No RPC to fetch the block payload for view {:?} with", view);
+            return;
+        };
+        let event_stream = event_stream.clone();
+        async_spawn(async move {
+            let request = FetchBlockRequest {
+                payload_commitment,
+                view,
+            };
+            match rpc::call::<FetchBlock<TYPES>, _>(&*rpc, recipient, &request).await {
+                Ok(payload) => {
+                    broadcast_event(
+                        HotShotEvent::BlockFetchResponseRecv(
+                            payload_commitment,
+                            view,
+                            payload.into(),
+                        ),
+                        &event_stream,
+                    )
+                    .await;
+                }
+                Err(e) => debug!("Failed to fetch the block payload for view {view:?}: {e}"),
+            }
+        });
+    }
+}
+
+/// task state implementation for the block fetch task
+impl<TYPES: NodeType> TaskState for BlockFetchTaskState<TYPES> {
+    type Event = HotShotEvent<TYPES>;
+
+    type Output = HotShotTaskCompleted;
+
+    fn filter(&self, event: &HotShotEvent<TYPES>) -> bool {
+        !matches!(
+            event,
+            HotShotEvent::BlockFetchNeeded(_, _)
+                | HotShotEvent::BlockFetchResponseRecv(_, _, _)
+                | HotShotEvent::LeafDecided(_)
+                | HotShotEvent::ViewChange(_)
+                | HotShotEvent::Shutdown
+        )
+    }
+
+    async fn handle_event(
+        event: Self::Event,
+        task: &mut Task<Self>,
+    ) -> Option<HotShotTaskCompleted> {
+        let sender = task.clone_sender();
+        task.state_mut().handle(event, sender).await
+    }
+
+    fn should_shutdown(event: &Self::Event) -> bool {
+        matches!(event, HotShotEvent::Shutdown)
+    }
+}
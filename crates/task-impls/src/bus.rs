@@ -0,0 +1,141 @@
+//! The event bus, on which tasks publish structured events about what they are doing
+//!
+//! The internal event stream carries whatever the tasks need to exchange, and changes with them.
+//! The event bus carries a smaller set of [`BusEvent`]s about the lifecycle of views, proposals,
+//! certificates, view sync and messages, which the consensus, DA, network and view sync tasks
+//! publish for plugins to follow. Publishing never blocks the tasks: events are dropped right
+//! away while no one listens, and a subscriber which falls more than [`EVENT_BUS_CAPACITY`]
+//! events behind misses the oldest ones.
+
+use std::fmt::{self, Debug};
+
+use async_broadcast::{broadcast, InactiveReceiver, Receiver, Sender};
+use hotshot_constants::EVENT_BUS_CAPACITY;
+use hotshot_types::{message::MessagePurpose, traits::node_implementation::NodeType};
+
+/// A lifecycle event published on the [`EventBus`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BusEvent<TYPES: NodeType> {
+    /// The consensus task entered `view`
+    ViewStarted {
+        /// the view entered
+        view: TYPES::Time,
+    },
+    /// The consensus task timed out in `view`
+    ViewTimedOut {
+        /// the view which timed out
+        view: TYPES::Time,
+    },
+    /// The consensus task received a quorum proposal from the leader of its view
+    QuorumProposalReceived {
+        /// the view of the proposal
+        view: TYPES::Time,
+        /// the leader which sent it
+        proposer: TYPES::SignatureKey,
+    },
+    /// The consensus task sent the quorum proposal of `view`, as its leader
+    QuorumProposalSent {
+        /// the view of the proposal
+        view: TYPES::Time,
+    },
+    /// The consensus task voted for the quorum proposal of `view`
+    QuorumVoteSent {
+        /// the view of the proposal voted for
+        view: TYPES::Time,
+    },
+    /// The consensus task formed, or received, a quorum certificate or timeout certificate
+    CertificateFormed {
+        /// the view of the certificate
+        view: TYPES::Time,
+        /// whether it is a timeout certificate
+        timeout: bool,
+    },
+    /// The consensus task decided leaves, up to the new anchor of `view`
+    Decided {
+        /// the view of the new anchor
+        view: TYPES::Time,
+        /// the number of leaves decided
+        leaves: usize,
+    },
+    /// The DA task received the DA proposal of `view`
+    DAProposalReceived {
+        /// the view of the DA proposal
+        view: TYPES::Time,
+    },
+    /// The DA task formed the DA certificate of `view`
+    DACertificateFormed {
+        /// the view of the DA certificate
+        view: TYPES::Time,
+    },
+    /// The view sync task started to synchronize the nodes on a view after `view`
+    ViewSyncStarted {
+        /// the view which timed out
+        view: TYPES::Time,
+    },
+    /// The view sync task synchronized the nodes on `view`
+    ViewSyncCompleted {
+        /// the view the nodes are synchronized on
+        view: TYPES::Time,
+    },
+    /// The network task sent a message
+    MessageSent {
+        /// what the message is for
+        purpose: MessagePurpose,
+        /// whether it was broadcast, rather than sent to one node
+        broadcast: bool,
+    },
+    /// The network task received a message
+    MessageReceived {
+        /// what the message is for
+        purpose: MessagePurpose,
+        /// the node which sent it
+        sender: TYPES::SignatureKey,
+    },
+}
+
+/// The bus tasks publish [`BusEvent`]s on, see the [module documentation](self). Clones publish
+/// on the same bus.
+#[derive(Clone)]
+pub struct EventBus<TYPES: NodeType> {
+    /// publishes events to the subscribers
+    sender: Sender<BusEvent<TYPES>>,
+    /// keeps the bus open while no one subscribed, and subscribes
+    receiver: InactiveReceiver<BusEvent<TYPES>>,
+}
+
+impl<TYPES: NodeType> Debug for EventBus<TYPES> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.sender.receiver_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<TYPES: NodeType> Default for EventBus<TYPES> {
+    fn default() -> Self {
+        let (mut sender, receiver) = broadcast(EVENT_BUS_CAPACITY);
+        sender.set_overflow(true);
+        sender.set_await_active(false);
+        Self {
+            sender,
+            receiver: receiver.deactivate(),
+        }
+    }
+}
+
+impl<TYPES: NodeType> EventBus<TYPES> {
+    /// Publish `event` to the subscribers, if there are any
+    pub fn publish(&self, event: BusEvent<TYPES>) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        // the bus overflows rather than fills up, so publishing only fails once it is closed
+        let _ = self.sender.try_broadcast(event);
+    }
+
+    /// Receive the events published from now on
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<BusEvent<TYPES>> {
+        self.receiver.activate_cloned()
+    }
+}
@@ -0,0 +1,149 @@
+//! Bookkeeping of compact DA proposals
+//!
+//! Under mempool sync most transactions reach most mempools before they are proposed, so a DA
+//! leader sending their full bytes to the DA committee mostly sends them again. In compact mode
+//! the leader replaces the transactions it saw most committee members announce in their mempools
+//! by their commitments. A committee member rebuilds the payload from its own mempool, asks the
+//! leader for the transactions it does not hold, again if they do not arrive within a view, and
+//! handles the rebuilt proposal as a full one once it has them all. This module tracks the
+//! announcements a leader believes the committee holds, and the transactions of a proposal
+//! being rebuilt.
+
+use std::{collections::HashMap, hash::Hash};
+
+use commit::{Commitment, Committable};
+use hotshot_types::{
+    data::CompactTransaction,
+    traits::node_implementation::{ConsensusTime, NodeType},
+};
+
+/// The transactions a DA leader believes the DA committee members hold: those enough distinct
+/// members announced recently. A single announcer only shows that it holds a transaction, not
+/// that the rest of the committee does.
+#[derive(Debug)]
+pub struct AnnouncedTransactions<TIME: ConsensusTime, ID: Copy + Eq + Hash, KEY: Eq + Hash> {
+    /// the announcers of each transaction, by the view of their latest announcement
+    announced: HashMap<ID, HashMap<KEY, TIME>>,
+    /// number of views announcements are remembered for
+    retention: u64,
+}
+
+impl<TIME: ConsensusTime, ID: Copy + Eq + Hash, KEY: Clone + Eq + Hash>
+    AnnouncedTransactions<TIME, ID, KEY>
+{
+    /// Remember announcements for `retention` views
+    #[must_use]
+    pub fn new(retention: u64) -> Self {
+        Self {
+            announced: HashMap::new(),
+            retention,
+        }
+    }
+
+    /// Record that `announcer` announced holding the transactions `ids`, in `view`
+    pub fn announced(&mut self, view: TIME, announcer: &KEY, ids: impl IntoIterator<Item = ID>) {
+        for id in ids {
+            self.announced
+                .entry(id)
+                .or_default()
+                .insert(announcer.clone(), view);
+        }
+    }
+
+    /// Whether transaction `id` is believed held by the DA committee members, that is whether
+    /// at least `min_announcers` distinct nodes announced it
+    #[must_use]
+    pub fn is_believed_held(&self, id: &ID, min_announcers: usize) -> bool {
+        self.announced
+            .get(id)
+            .is_some_and(|announcers| announcers.len() >= min_announcers)
+    }
+
+    /// Forget the announcements more than the retention before `view`
+    pub fn prune(&mut self, view: TIME) {
+        let retention = self.retention;
+        self.announced.retain(|_, announcers| {
+            announcers.retain(|_, announced| *announced + retention > *view);
+            !announcers.is_empty()
+        });
+    }
+}
+
+/// The transactions of a compact DA proposal being rebuilt: those it carried, and those found
+/// in the mempool or fetched since
+#[derive(Debug)]
+pub struct CompactRebuild<TYPES: NodeType> {
+    /// the transactions of the block in order, those not found yet unset
+    transactions: Vec<Option<TYPES::Transaction>>,
+    /// the positions of the transactions not found yet, by commitment
+    missing: HashMap<Commitment<TYPES::Transaction>, Vec<usize>>,
+    /// number of times the missing transactions were requested
+    requests: u32,
+}
+
+impl<TYPES: NodeType> CompactRebuild<TYPES> {
+    /// Start rebuilding a block of `transactions`, with only the full ones found
+    #[must_use]
+    pub fn new(transactions: &[CompactTransaction<TYPES>]) -> Self {
+        let mut missing: HashMap<_, Vec<usize>> = HashMap::new();
+        let transactions = transactions
+            .iter()
+            .enumerate()
+            .map(|(position, transaction)| match transaction {
+                CompactTransaction::Held(commitment) => {
+                    missing.entry(*commitment).or_default().push(position);
+                    None
+                }
+                CompactTransaction::Full(transaction) => Some(transaction.clone()),
+            })
+            .collect();
+        Self {
+            transactions,
+            missing,
+            requests: 0,
+        }
+    }
+
+    /// Fill in the missing transactions among `found`. Returns the number filled in.
+    pub fn fill(&mut self, found: impl IntoIterator<Item = TYPES::Transaction>) -> usize {
+        let mut filled = 0;
+        for transaction in found {
+            let Some(positions) = self.missing.remove(&transaction.commit()) else {
+                continue;
+            };
+            for position in positions {
+                self.transactions[position] = Some(transaction.clone());
+            }
+            filled += 1;
+        }
+        filled
+    }
+
+    /// Record a request for the missing transactions, unless `max_requests` were made already.
+    /// Returns whether to make it.
+    pub fn request(&mut self, max_requests: u32) -> bool {
+        if self.requests >= max_requests {
+            return false;
+        }
+        self.requests += 1;
+        true
+    }
+
+    /// The commitments of the transactions not found yet
+    #[must_use]
+    pub fn missing(&self) -> Vec<Commitment<TYPES::Transaction>> {
+        self.missing.keys().copied().collect()
+    }
+
+    /// Whether every transaction was found
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// The transactions of the block in order, once they were all found
+    #[must_use]
+    pub fn into_transactions(self) -> Option<Vec<TYPES::Transaction>> {
+        self.transactions.into_iter().collect()
+    }
+}
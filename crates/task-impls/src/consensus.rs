@@ -1,43 +1,57 @@
 use crate::{
-    events::{HotShotEvent, HotShotTaskCompleted},
+    bus::{BusEvent, EventBus},
+    events::HotShotEvent,
+    future_view_buffer::{BufferOutcome, FutureViewBuffer},
     helpers::{broadcast_event, cancel_task},
-    vote::{create_vote_accumulator, AccumulatorInfo, VoteCollectionTaskState},
+    state_pipeline::{CommittedApplication, StatePipeline},
+    vote::{handle_vote, AccumulatorInfo, VoteCollectionTaskState},
 };
-use async_compatibility_layer::art::{async_sleep, async_spawn};
-use async_lock::{RwLock, RwLockUpgradableReadGuard};
+use async_compatibility_layer::art::async_spawn;
+use async_lock::{RwLock, RwLockUpgradableReadGuard, RwLockWriteGuard};
 #[cfg(async_executor_impl = "async-std")]
 use async_std::task::JoinHandle;
-use commit::Committable;
+use commit::{Commitment, Committable};
 use core::time::Duration;
 use hotshot_constants::Version;
-use hotshot_constants::LOOK_AHEAD;
+use hotshot_constants::{LOOK_AHEAD, MAX_HEADER_EXTENSIONS_SIZE, PARTITION_SUSPECTED_VIEWS};
 use hotshot_task::task::{Task, TaskState};
+use hotshot_utils::log_throttle;
+use std::time::Instant;
 
 use async_broadcast::Sender;
 
 use hotshot_types::{
+    anchor::{self, AnchorAnnouncement},
     consensus::{Consensus, View},
     data::{Leaf, QuorumProposal, VidCommitment, VidDisperse},
+    error::HotShotError,
     event::{Event, EventType},
+    governance::GovernancePolicy,
+    inclusion_proof::TransactionsRoot,
     message::{GeneralConsensusMessage, Proposal},
+    payload_validation::{validate_block_contents, validate_header},
+    safety::{SafetyEvidence, SafetyMonitor},
+    signing::{self, SigningDomain},
     simple_certificate::{QuorumCertificate, TimeoutCertificate, UpgradeCertificate},
     simple_vote::{QuorumData, QuorumVote, TimeoutData, TimeoutVote},
     traits::{
         block_contents::BlockHeader,
+        clock::Clock,
         consensus_api::ConsensusApi,
         election::Membership,
+        header_extension::HeaderExtender,
         network::{ConnectedNetwork, ConsensusIntentEvent},
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
         signature_key::SignatureKey,
         states::ValidatedState,
+        storage::ViewArtifacts,
         BlockPayload,
     },
     utils::{Terminator, ViewInner},
-    vote::{Certificate, HasViewNumber},
+    vote::{Certificate, HasViewNumber, Vote},
 };
 use tracing::warn;
 
-use crate::vote::HandleVoteEvent;
 use chrono::Utc;
 use snafu::Snafu;
 use std::{
@@ -57,12 +71,75 @@ pub struct ConsensusTaskError {}
 pub struct CommitmentAndMetadata<PAYLOAD: BlockPayload> {
     /// Vid Commitment
     pub commitment: VidCommitment,
+    /// Root of the transactions of the block payload
+    pub transactions_root: TransactionsRoot,
     /// Metadata for the block payload
     pub metadata: <PAYLOAD as BlockPayload>::Metadata,
     /// Flag for if this data represents the genesis block
     pub is_genesis: bool,
 }
 
+/// The public keys whose signatures are aggregated in `qc`
+fn qc_signers<TYPES: NodeType>(
+    qc: &QuorumCertificate<TYPES>,
+    membership: &TYPES::Membership,
+) -> Vec<TYPES::SignatureKey> {
+    let Some(signatures) = &qc.signatures else {
+        return Vec::new();
+    };
+    let (_, signed) = TYPES::SignatureKey::get_sig_proof(signatures);
+    membership
+        .get_committee_qc_stake_table()
+        .iter()
+        .zip(signed.iter().by_vals())
+        .filter(|(_, signed)| *signed)
+        .map(|(entry, _)| TYPES::SignatureKey::get_public_key(entry))
+        .collect()
+}
+
+/// The leaf of `proposal`, sent by `sender`, extending the leaf of `parent_commitment`
+pub(crate) fn proposed_leaf<TYPES: NodeType>(
+    proposal: &QuorumProposal<TYPES>,
+    parent_commitment: Commitment<Leaf<TYPES>>,
+    sender: TYPES::SignatureKey,
+) -> Leaf<TYPES> {
+    Leaf {
+        view_number: proposal.view_number,
+        justify_qc: proposal.justify_qc.clone(),
+        parent_commitment,
+        block_header: proposal.block_header.clone(),
+        block_payload: None,
+        proposer_id: sender,
+        governance: proposal.governance.clone(),
+    }
+}
+
+/// A proposal whose block header is applied to the state of its parent on a worker task, with
+/// its sender and the commitment of its parent leaf
+pub type PendingStateApplication<TYPES> = (
+    Proposal<TYPES, QuorumProposal<TYPES>>,
+    <TYPES as NodeType>::SignatureKey,
+    Commitment<Leaf<TYPES>>,
+);
+
+/// The block header applications of the consensus task running on worker tasks, whose outcome
+/// is the new state, `None` if the header is invalid
+pub type ConsensusStatePipeline<TYPES> = StatePipeline<
+    <TYPES as NodeType>::Time,
+    Commitment<Leaf<TYPES>>,
+    PendingStateApplication<TYPES>,
+    Option<Arc<<TYPES as NodeType>::ValidatedState>>,
+    HotShotEvent<TYPES>,
+>;
+
+/// An application of [`ConsensusStatePipeline`] whose turn it is to be committed
+pub type ConsensusCommittedApplication<TYPES> = CommittedApplication<
+    <TYPES as NodeType>::Time,
+    PendingStateApplication<TYPES>,
+    Option<Arc<<TYPES as NodeType>::ValidatedState>>,
+    HotShotEvent<TYPES>,
+>;
+
 /// Alias for Optional type for Vote Collectors
 type VoteCollectorOption<TYPES, VOTE, CERT> = Option<VoteCollectionTaskState<TYPES, VOTE, CERT>>;
 
@@ -79,8 +156,6 @@ pub struct ConsensusTaskState<
     pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
     /// Reference to consensus. The replica will require a write lock on this.
     pub consensus: Arc<RwLock<Consensus<TYPES>>>,
-    /// View timeout from config.
-    pub timeout: u64,
     /// View number this view is executing in.
     pub cur_view: TYPES::Time,
 
@@ -119,6 +194,12 @@ pub struct ConsensusTaskState<
     /// timeout task handle
     pub timeout_task: Option<JoinHandle<()>>,
 
+    /// Proposal availability timeout from config.
+    pub proposal_timeout: u64,
+
+    /// proposal timeout task handle
+    pub proposal_timeout_task: Option<JoinHandle<()>>,
+
     /// last Timeout Certificate this node formed
     pub timeout_cert: Option<TimeoutCertificate<TYPES>>,
 
@@ -145,6 +226,41 @@ pub struct ConsensusTaskState<
     /// Will be none if the view advanced through timeout/view_sync
     pub current_proposal: Option<QuorumProposal<TYPES>>,
 
+    /// The signed proposals we accepted and which are not decided yet, kept for the artifacts
+    /// of their views once decided
+    pub proposals: BTreeMap<TYPES::Time, Proposal<TYPES, QuorumProposal<TYPES>>>,
+
+    /// Proposals and votes which arrived before we reached their view, replayed once we do
+    pub future_events: FutureViewBuffer<TYPES::Time, HotShotEvent<TYPES>>,
+
+    /// Checks the certificates and decides we see for conflicts
+    pub safety_monitor: SafetyMonitor<TYPES>,
+
+    /// Whether a safety violation was observed, after which we neither vote nor propose
+    pub halted: bool,
+
+    /// Fills in the extensions of the headers we propose, and checks those we are proposed
+    pub header_extender: Arc<dyn HeaderExtender<TYPES>>,
+
+    /// Decides which decided governance transactions change the consensus parameters
+    pub governance_policy: Arc<dyn GovernancePolicy>,
+
+    /// Whether block headers are applied to the state of their parent on worker tasks rather
+    /// than while handling their proposal
+    pub parallel_state_application: bool,
+
+    /// The block header applications running on worker tasks, committed in view order
+    pub state_pipeline: ConsensusStatePipeline<TYPES>,
+
+    /// The time source of the view and proposal timeouts
+    pub clock: Arc<dyn Clock>,
+
+    /// When we entered the current view, to estimate how long views last
+    pub view_start: Option<Instant>,
+
+    /// The bus lifecycle events are published on
+    pub event_bus: EventBus<TYPES>,
+
     // ED Should replace this with config information since we need it anyway
     /// The node's id
     pub id: u64,
@@ -157,6 +273,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
     // Check if we are able to vote, like whether the proposal is valid,
     // whether we have DAC and VID share, and if so, vote.
     async fn vote_if_able(&mut self, event_stream: &Sender<HotShotEvent<TYPES>>) -> bool {
+        if self.halted {
+            return false;
+        }
         if !self.quorum_membership.has_stake(&self.public_key) {
             debug!(
                 "We were not chosen for consensus committee on {:?}",
@@ -172,6 +291,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
             // No need to check vid share nor da cert for genesis
             if proposal.justify_qc.is_genesis && proposal.view_number == TYPES::Time::new(1) {
                 info!("Proposal is genesis!");
+                // without a DA certificate, nothing attests to governance transactions
+                if !proposal.governance.is_empty() {
+                    error!("Genesis proposal carries governance transactions");
+                    return false;
+                }
 
                 let view = TYPES::Time::new(*proposal.view_number);
                 let justify_qc = proposal.justify_qc.clone();
@@ -201,6 +325,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     block_header: proposal.block_header.clone(),
                     block_payload: None,
                     proposer_id: self.quorum_membership.get_leader(view),
+                    governance: proposal.governance.clone(),
                 };
                 let Ok(vote) = QuorumVote::<TYPES>::create_signed_vote(
                     QuorumData {
@@ -221,6 +346,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                         "Sending vote to next quorum leader {:?}",
                         vote.get_view_number() + 1
                     );
+                    self.event_bus.publish(BusEvent::QuorumVoteSent {
+                        view: vote.get_view_number(),
+                    });
                     broadcast_event(HotShotEvent::QuorumVoteSend(vote), event_stream).await;
                     if let Some(commit_and_metadata) = &self.payload_commitment_and_metadata {
                         if commit_and_metadata.is_genesis {
@@ -274,10 +402,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     block_header: proposal.block_header.clone(),
                     block_payload: None,
                     proposer_id: self.quorum_membership.get_leader(view),
+                    governance: proposal.governance.clone(),
                 };
 
                 // Validate the DAC.
-                let message = if cert.is_valid_cert(self.committee_membership.as_ref()) {
+                let message = if cert.is_valid_da_cert(
+                    self.committee_membership.as_ref(),
+                    self.quorum_membership.as_ref(),
+                ) {
                     // Validate the block payload commitment for non-genesis DAC.
                     if !cert.is_genesis
                         && cert.get_data().payload_commit
@@ -286,6 +418,20 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                         error!("Block payload commitment does not equal da cert payload commitment. View = {}", *view);
                         return false;
                     }
+                    // the DA committee certified the governance transactions of the block, which
+                    // the leader must have copied into the proposal unchanged
+                    let certified_governance: &[_] = if cert.is_genesis {
+                        &[]
+                    } else {
+                        &cert.get_data().governance
+                    };
+                    if proposal.governance != certified_governance {
+                        error!(
+                            "Proposal's governance does not match the DA cert. View = {}",
+                            *view
+                        );
+                        return false;
+                    }
                     if let Ok(vote) = QuorumVote::<TYPES>::create_signed_vote(
                         QuorumData {
                             leaf_commit: leaf.commit(),
@@ -312,6 +458,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                         "Sending vote to next quorum leader {:?}",
                         vote.get_view_number() + 1
                     );
+                    self.event_bus.publish(BusEvent::QuorumVoteSent {
+                        view: vote.get_view_number(),
+                    });
                     broadcast_event(HotShotEvent::QuorumVoteSend(vote), event_stream).await;
                     return true;
                 }
@@ -353,6 +502,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
             if let Some(timeout_task) = self.timeout_task.take() {
                 cancel_task(timeout_task).await;
             }
+            if let Some(proposal_timeout_task) = self.proposal_timeout_task.take() {
+                cancel_task(proposal_timeout_task).await;
+            }
+            let previous_view = self.cur_view;
             self.cur_view = new_view;
 
             // Poll the future leader for lookahead
@@ -384,15 +537,45 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
 
             broadcast_event(HotShotEvent::ViewChange(new_view), event_stream).await;
 
-            // Spawn a timeout task if we did actually update view
-            let timeout = self.timeout;
+            let released = self.future_events.take_ready(new_view);
+            if !released.is_empty() {
+                debug!(
+                    "Replaying {} messages held for view {}",
+                    released.len(),
+                    *new_view
+                );
+                let consensus = self.consensus.read().await;
+                consensus
+                    .metrics
+                    .future_view_messages_released
+                    .add(released.len());
+                consensus
+                    .metrics
+                    .future_view_messages_buffered
+                    .set(self.future_events.len());
+                drop(consensus);
+                for event in released {
+                    broadcast_event(event, event_stream).await;
+                }
+            }
+
+            // Spawn a timeout task if we did actually update view, as long as governance
+            // transactions set for the epoch of the view, if they changed it
+            let next_view_timeout = self
+                .consensus
+                .read()
+                .await
+                .parameter_schedule
+                .parameters_at_view(self.cur_view + 1)
+                .next_view_timeout;
+            let timeout = self.clock.sleep(Duration::from_millis(next_view_timeout));
             self.timeout_task = Some(async_spawn({
                 let stream = event_stream.clone();
                 // Nuance: We timeout on the view + 1 here because that means that we have
                 // not seen evidence to transition to this new view
                 let view_number = self.cur_view + 1;
                 async move {
-                    async_sleep(Duration::from_millis(timeout)).await;
+                    timeout.await;
                     broadcast_event(
                         HotShotEvent::Timeout(TYPES::Time::new(*view_number)),
                         &stream,
@@ -400,7 +583,58 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     .await;
                 }
             }));
-            let consensus = self.consensus.read().await;
+            // A proposal for the next view moves us into it, so if we are still in this view
+            // once the proposal timeout elapses, its leader has not proposed (yet)
+            let proposal_timeout = self
+                .clock
+                .sleep(Duration::from_millis(self.proposal_timeout));
+            self.proposal_timeout_task = Some(async_spawn({
+                let stream = event_stream.clone();
+                let view_number = self.cur_view + 1;
+                async move {
+                    proposal_timeout.await;
+                    broadcast_event(HotShotEvent::ProposalTimeout(view_number), &stream).await;
+                }
+            }));
+            let now = self.clock.now();
+            let mut consensus = self.consensus.write().await;
+            if let Some(start) = self.view_start.replace(now) {
+                // views we skipped, e.g. through view sync, are taken to have lasted as long
+                // as each other
+                let views = u32::try_from(*new_view - *previous_view).unwrap_or(u32::MAX);
+                let duration = now.saturating_duration_since(start) / views;
+                consensus.view_latency.record(duration);
+                consensus
+                    .metrics
+                    .view_duration
+                    .add_point(duration.as_secs_f64());
+                if let Some(average) = consensus.view_latency.average() {
+                    consensus
+                        .metrics
+                        .view_duration_average
+                        .set(usize::try_from(average.as_millis()).unwrap_or(usize::MAX));
+                }
+                if let Some(latency) = consensus.view_latency.estimated_finality_latency() {
+                    consensus
+                        .metrics
+                        .estimated_finality_latency
+                        .set(usize::try_from(latency.as_millis()).unwrap_or(usize::MAX));
+                }
+            }
+            if let Some(stats) = self.quorum_membership.stake_table_cache_stats() {
+                let metrics = &consensus.metrics;
+                metrics
+                    .stake_table_cache_hits
+                    .set(usize::try_from(stats.hits).unwrap_or(usize::MAX));
+                metrics
+                    .stake_table_cache_misses
+                    .set(usize::try_from(stats.misses).unwrap_or(usize::MAX));
+                if let Some(rate) = stats.hit_rate_percent() {
+                    metrics
+                        .stake_table_cache_hit_rate
+                        .set(usize::try_from(rate).unwrap_or(usize::MAX));
+                }
+            }
             consensus
                 .metrics
                 .current_view
@@ -409,12 +643,651 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 usize::try_from(self.cur_view.get_u64()).unwrap()
                     - usize::try_from(consensus.last_decided_view.get_u64()).unwrap(),
             );
+            self.event_bus
+                .publish(BusEvent::ViewStarted { view: new_view });
 
             return true;
         }
         false
     }
 
+    /// Hold `event` until we reach `release_view`. Returns whether it was held; if not, it
+    /// should be handled right away.
+    async fn hold_until_view(
+        &mut self,
+        release_view: TYPES::Time,
+        event: HotShotEvent<TYPES>,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) -> bool {
+        let outcome = self
+            .future_events
+            .insert(self.cur_view, release_view, event);
+        if matches!(outcome, BufferOutcome::Ready(_)) {
+            return false;
+        }
+        let consensus = self.consensus.read().await;
+        consensus
+            .metrics
+            .future_view_messages_buffered
+            .set(self.future_events.len());
+        if let BufferOutcome::Evicted(evicted) = outcome {
+            consensus.metrics.future_view_messages_evicted.add(1);
+            drop(consensus);
+            // nothing further ahead is held now, so the evicted message is handled right away
+            // once it comes back
+            broadcast_event(evicted, event_stream).await;
+        }
+        true
+    }
+
+    /// Halt after a safety violation: persist `evidence`, tell the application and stop voting
+    /// and proposing for good
+    async fn halt(&mut self, evidence: SafetyEvidence<TYPES>) {
+        let view = evidence.view_number();
+        error!(
+            "Safety violation in view {}, halting: {:?}",
+            *view, evidence
+        );
+        self.halted = true;
+        if let Err(e) = self.api.store_safety_evidence(evidence.clone()).await {
+            error!(
+                "Could not persist the evidence of a safety violation: {:?}",
+                e
+            );
+        }
+        self.report_error(
+            view,
+            HotShotError::SafetyViolation {
+                view_number: view,
+                context: format!("{evidence:?}"),
+            },
+        )
+        .await;
+        broadcast_event(
+            Event {
+                view_number: view,
+                event: EventType::SafetyViolation {
+                    evidence: Arc::new(evidence),
+                },
+            },
+            &self.output_event_stream,
+        )
+        .await;
+    }
+
+    /// Record that `leader` sent a proposal which failed validation
+    async fn record_invalid_proposal(&self, leader: TYPES::SignatureKey) {
+        self.consensus
+            .write()
+            .await
+            .leader_scores
+            .record_invalid(leader);
+    }
+
+    /// Report `error`, which happened in `view`, to the application
+    async fn report_error(&self, view: TYPES::Time, error: HotShotError<TYPES>) {
+        broadcast_event(
+            Event {
+                view_number: view,
+                event: EventType::Error {
+                    error: Arc::new(error),
+                },
+            },
+            &self.output_event_stream,
+        )
+        .await;
+    }
+
+    /// Apply the block header of `proposal` to the state of its parent `parent_leaf` on a
+    /// worker task, which reports the new state with [`HotShotEvent::StateApplied`]. The
+    /// proposal is accepted once every application of an earlier view is committed.
+    fn start_state_application(
+        &mut self,
+        proposal: Proposal<TYPES, QuorumProposal<TYPES>>,
+        sender: TYPES::SignatureKey,
+        parent_leaf: &Leaf<TYPES>,
+        parent_state: Arc<TYPES::ValidatedState>,
+        instance_state: TYPES::InstanceState,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        let view = proposal.data.get_view_number();
+        let parent_commitment = parent_leaf.commit();
+        let leaf = proposed_leaf(&proposal.data, parent_commitment, sender.clone()).commit();
+        let parent_header = parent_leaf.block_header.clone();
+        let header = proposal.data.block_header.clone();
+        // only proposals whose signature verifies get here, so no one but the leader of `view`
+        // can claim its slot
+        if !self
+            .state_pipeline
+            .start(view, leaf, (proposal, sender, parent_commitment))
+        {
+            debug!(
+                "The state of a proposal for view {} is already applied",
+                *view
+            );
+            return;
+        }
+        let stream = event_stream.clone();
+        async_spawn(async move {
+            let state = match parent_state.validate_and_apply_header(
+                &instance_state,
+                &parent_header,
+                &header,
+            ) {
+                Ok(state) => Some(Arc::new(state)),
+                Err(e) => {
+                    debug!("Block header of view {} is invalid: {e}", *view);
+                    None
+                }
+            };
+            broadcast_event(HotShotEvent::StateApplied(view, state), &stream).await;
+        });
+    }
+
+    /// Handle the outcome of an application to the state started by
+    /// `start_state_application`: accept the proposals whose turn it is, in view order, with
+    /// their new state, and handle again the proposals held until their parent was accepted
+    async fn handle_state_applied(
+        &mut self,
+        view: TYPES::Time,
+        state: Option<Arc<TYPES::ValidatedState>>,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        let committed = self.state_pipeline.finish(view, state);
+        self.commit_state_applications(committed, event_stream)
+            .await;
+    }
+
+    /// Give up on the applications to the state of views before `view` whose outcome never
+    /// arrived, e.g. as their worker task panicked, so that the later ones are not held up
+    async fn prune_state_applications(
+        &mut self,
+        view: TYPES::Time,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        let pending = self.state_pipeline.len();
+        let committed = self.state_pipeline.prune(view);
+        if self.state_pipeline.len() + committed.len() < pending {
+            warn!(
+                "Gave up on applying the state of proposals before view {}",
+                *view
+            );
+        }
+        self.commit_state_applications(committed, event_stream)
+            .await;
+    }
+
+    /// Accept the proposals of the committed applications to the state, in view order, and
+    /// handle again the proposals held until their parent was accepted
+    async fn commit_state_applications(
+        &mut self,
+        committed: Vec<ConsensusCommittedApplication<TYPES>>,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        for committed in committed {
+            let (proposal, sender, parent_commitment) = committed.job;
+            match committed.outcome {
+                Some(state) => {
+                    let consensus_lock = Arc::clone(&self.consensus);
+                    let consensus = consensus_lock.write().await;
+                    self.accept_proposal(
+                        proposal,
+                        sender,
+                        parent_commitment,
+                        state,
+                        consensus,
+                        event_stream,
+                    )
+                    .await;
+                }
+                None => {
+                    error!("Block header doesn't extend the proposal",);
+                    self.record_invalid_proposal(sender.clone()).await;
+                    self.report_error(
+                        committed.view,
+                        HotShotError::InvalidProposal {
+                            view_number: committed.view,
+                            leader: sender,
+                            reason: "block header does not extend its parent".to_string(),
+                        },
+                    )
+                    .await;
+                }
+            }
+            for event in committed.released {
+                broadcast_event(event, event_stream).await;
+            }
+        }
+    }
+
+    /// Accept `proposal`, whose block header applied to the state of its parent gives `state`,
+    /// if it is validly signed and passes the safety or liveness check: record its leaf, update
+    /// the locked and decided views, then propose or vote if we can
+    #[allow(clippy::too_many_lines)]
+    async fn accept_proposal(
+        &mut self,
+        proposal: Proposal<TYPES, QuorumProposal<TYPES>>,
+        sender: TYPES::SignatureKey,
+        parent_commitment: Commitment<Leaf<TYPES>>,
+        state: Arc<TYPES::ValidatedState>,
+        mut consensus: RwLockWriteGuard<'_, Consensus<TYPES>>,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        let view = proposal.data.get_view_number();
+        let justify_qc = proposal.data.justify_qc.clone();
+        let view_leader_key = self.quorum_membership.get_leader(view);
+        let leaf = proposed_leaf(&proposal.data, parent_commitment, sender.clone());
+        let leaf_commitment = leaf.commit();
+
+        // Validate the signature. This should also catch if the leaf_commitment does not equal our calculated parent commitment
+        if !signing::validate(
+            &view_leader_key,
+            &proposal.signature,
+            SigningDomain::QuorumProposal,
+            leaf_commitment.as_ref(),
+        ) {
+            error!(?proposal.signature, "Could not verify proposal.");
+            consensus.leader_scores.record_invalid(sender.clone());
+            self.report_error(
+                view,
+                HotShotError::InvalidProposal {
+                    view_number: view,
+                    leader: sender,
+                    reason: "invalid signature".to_string(),
+                },
+            )
+            .await;
+            return;
+        }
+        // the nodes holding the payload check that the header commits to the transactions of
+        // the block, which inclusion proofs are verified against, and that the leader copied
+        // every governance transaction of the block into the proposal, and nothing else
+        if let Some(encoded_txns) = consensus.saved_payloads.get(&view) {
+            let payload = TYPES::BlockPayload::from_bytes(
+                encoded_txns.iter().copied(),
+                proposal.data.block_header.metadata(),
+            );
+            if let Err(e) = validate_block_contents(
+                &consensus.parameter_schedule.parameters_at_view(view),
+                &proposal.data.block_header,
+                &proposal.data.governance,
+                &payload,
+            ) {
+                warn!("Proposal's block rejected: {e}");
+                consensus.leader_scores.record_invalid(sender.clone());
+                self.report_error(
+                    view,
+                    HotShotError::InvalidProposal {
+                        view_number: view,
+                        leader: sender,
+                        reason: e.to_string(),
+                    },
+                )
+                .await;
+                return;
+            }
+        }
+        consensus
+            .leader_scores
+            .record_proposal(*view, sender.clone());
+        // Create a positive vote if either liveness or safety check
+        // passes.
+
+        // Liveness check.
+        let liveness_check = justify_qc.get_view_number() > consensus.locked_view;
+
+        // Safety check.
+        // Check if proposal extends from the locked leaf.
+        let outcome = consensus.visit_leaf_ancestors(
+            justify_qc.get_view_number(),
+            Terminator::Inclusive(consensus.locked_view),
+            false,
+            |leaf| {
+                // if leaf view no == locked view no then we're done, report success by
+                // returning true
+                leaf.view_number != consensus.locked_view
+            },
+        );
+        let safety_check = outcome.is_ok();
+        if let Err(e) = outcome {
+            self.api
+                .send_event(Event {
+                    view_number: view,
+                    event: EventType::Error { error: Arc::new(e) },
+                })
+                .await;
+        }
+
+        // Skip if both saftey and liveness checks fail.
+        if !safety_check && !liveness_check {
+            error!("Failed safety and liveness check \n High QC is {:?}  Proposal QC is {:?}  Locked view is {:?}", consensus.high_qc, proposal.data.clone(), consensus.locked_view);
+            return;
+        }
+
+        self.current_proposal = Some(proposal.data.clone());
+        self.proposals.insert(view, proposal.clone());
+
+        // We accept the proposal, notify the application layer
+        self.api
+            .send_event(Event {
+                view_number: self.cur_view,
+                event: EventType::QuorumProposal {
+                    proposal: proposal.clone(),
+                    sender,
+                },
+            })
+            .await;
+
+        let mut new_anchor_view = consensus.last_decided_view;
+        let mut new_locked_view = consensus.locked_view;
+        let mut last_view_number_visited = view;
+        let mut new_commit_reached: bool = false;
+        let mut new_decide_reached = false;
+        let mut new_decide_qc = None;
+        let mut leaf_views = Vec::new();
+        let mut leafs_decided = Vec::new();
+        // whether the decided chain reaches back to the previous anchor
+        let mut decide_chain_complete = true;
+        let mut view_artifacts = Vec::new();
+        let mut decided_payloads = Vec::new();
+        // the QC certifying the leaf being visited, which is the justify QC of its child
+        let mut certifying_qc = Some(leaf.justify_qc.clone());
+        let mut included_txns = HashSet::new();
+        let old_anchor_view = consensus.last_decided_view;
+        let parent_view = leaf.justify_qc.get_view_number();
+        let mut current_chain_length = 0usize;
+        if parent_view + 1 == view {
+            current_chain_length += 1;
+            if let Err(e) = consensus.visit_leaf_ancestors(
+                parent_view,
+                Terminator::Exclusive(old_anchor_view),
+                true,
+                |leaf| {
+                    if !new_decide_reached {
+                        if last_view_number_visited == leaf.view_number + 1 {
+                            last_view_number_visited = leaf.view_number;
+                            current_chain_length += 1;
+                            if current_chain_length == 2 {
+                                new_locked_view = leaf.view_number;
+                                new_commit_reached = true;
+                                // The next leaf in the chain, if there is one, is decided, so this
+                                // leaf's justify_qc would become the QC for the decided chain.
+                                new_decide_qc = Some(leaf.justify_qc.clone());
+                            } else if current_chain_length == 3 {
+                                new_anchor_view = leaf.view_number;
+                                new_decide_reached = true;
+                            }
+                        } else {
+                            // nothing more to do here... we don't have a new chain extension
+                            return false;
+                        }
+                    }
+                    // starting from the first iteration with a three chain, e.g. right after the else if case nested in the if case above
+                    if new_decide_reached {
+                        let quorum_certificate = certifying_qc
+                            .take()
+                            .filter(|qc| qc.view_number == leaf.view_number);
+                        view_artifacts.push(ViewArtifacts {
+                            view_number: leaf.view_number,
+                            proposal: self.proposals.get(&leaf.view_number).cloned(),
+                            da_certificate: consensus
+                                .saved_da_certs
+                                .get(&leaf.view_number)
+                                .cloned(),
+                            signers: quorum_certificate
+                                .as_ref()
+                                .map(|qc| qc_signers(qc, &*self.quorum_membership))
+                                .unwrap_or_default(),
+                            quorum_certificate,
+                        });
+                        let mut leaf = leaf.clone();
+                        if leaf.view_number == new_anchor_view {
+                            consensus
+                                .metrics
+                                .last_synced_block_height
+                                .set(usize::try_from(leaf.get_height()).unwrap_or(0));
+                        }
+                        if let Some(ref upgrade_cert) = proposal.data.upgrade_certificate {
+                            info!(
+                                "Updating consensus state with decided upgrade certificate: {:?}",
+                                upgrade_cert
+                            );
+                            self.decided_upgrade_cert = Some(upgrade_cert.clone());
+                        }
+                        // If the block payload is available for this leaf, include it in
+                        // the leaf chain that we send to the client.
+                        if let Some(encoded_txns) =
+                            consensus.saved_payloads.get(&leaf.get_view_number())
+                        {
+                            decided_payloads.push((
+                                leaf.get_view_number(),
+                                leaf.get_payload_commitment(),
+                                encoded_txns.clone(),
+                            ));
+                            let payload = BlockPayload::from_bytes(
+                                encoded_txns.iter().copied(),
+                                leaf.get_block_header().metadata(),
+                            );
+
+                            leaf.fill_block_payload_unchecked(payload);
+                        }
+
+                        let vid = self
+                            .vid_shares
+                            .get(&leaf.get_view_number())
+                            .map(|vid_proposal| vid_proposal.data.clone());
+
+                        leaf_views.push((leaf.clone(), vid));
+                        leafs_decided.push(leaf.clone());
+                        if let Some(ref payload) = leaf.block_payload {
+                            for txn in
+                                payload.transaction_commitments(leaf.get_block_header().metadata())
+                            {
+                                included_txns.insert(txn);
+                            }
+                        }
+                    }
+                    certifying_qc = Some(leaf.justify_qc.clone());
+                    true
+                },
+            ) {
+                error!("view publish error {e}");
+                decide_chain_complete = false;
+                broadcast_event(
+                    Event {
+                        view_number: view,
+                        event: EventType::Error { error: e.into() },
+                    },
+                    &self.output_event_stream,
+                )
+                .await;
+            }
+        }
+
+        let included_txns_set: HashSet<_> = if new_decide_reached {
+            included_txns
+        } else {
+            HashSet::new()
+        };
+
+        consensus.validated_state_map.insert(
+            view,
+            View {
+                view_inner: ViewInner::Leaf {
+                    leaf: leaf.commit(),
+                    state,
+                },
+            },
+        );
+        consensus.saved_leaves.insert(leaf.commit(), leaf.clone());
+        if new_commit_reached {
+            consensus.locked_view = new_locked_view;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        if new_decide_reached {
+            if let Some(evidence) = self
+                .safety_monitor
+                .observe_decide(&leafs_decided, decide_chain_complete)
+            {
+                drop(consensus);
+                self.halt(evidence).await;
+                return;
+            }
+            let decided_views: Vec<_> = leafs_decided.iter().map(|leaf| leaf.view_number).collect();
+            self.quorum_membership.record_decided_views(&decided_views);
+            // schedule the authorized parameter changes of the decided leaves, oldest first, so
+            // that a later change of the same epoch wins
+            let mut scheduled = false;
+            for leaf in leafs_decided.iter().rev() {
+                for transaction in leaf.get_governance() {
+                    if !self.governance_policy.authorizes(transaction) {
+                        warn!(
+                            "Ignoring unauthorized governance transaction for epoch {}",
+                            transaction.activation_epoch
+                        );
+                        continue;
+                    }
+                    match consensus
+                        .parameter_schedule
+                        .schedule(leaf.view_number, transaction)
+                    {
+                        Ok(()) => {
+                            scheduled = true;
+                            let epoch = transaction.activation_epoch;
+                            let parameters = consensus.parameter_schedule.parameters_at(epoch);
+                            info!("Scheduled parameters {parameters:?} for epoch {epoch}");
+                            broadcast_event(
+                                Event {
+                                    view_number: leaf.view_number,
+                                    event: EventType::ParameterChangeScheduled {
+                                        epoch,
+                                        parameters,
+                                    },
+                                },
+                                &self.output_event_stream,
+                            )
+                            .await;
+                        }
+                        Err(e) => warn!("Ignoring governance transaction: {e}"),
+                    }
+                }
+            }
+            if scheduled {
+                if let Err(e) = self
+                    .api
+                    .store_parameter_schedule(consensus.parameter_schedule.clone())
+                    .await
+                {
+                    error!("Failed to persist the parameter schedule: {e:?}");
+                }
+            }
+            // the first decided leaf is the new anchor, certified by the decide QC
+            let anchor_announcement = leafs_decided
+                .first()
+                .zip(new_decide_qc.as_ref())
+                .map(|(anchor, qc)| AnchorAnnouncement::new(anchor, qc));
+            if let Some(anchor_view) = decided_views.first() {
+                self.event_bus.publish(BusEvent::Decided {
+                    view: *anchor_view,
+                    leaves: decided_views.len(),
+                });
+            }
+            broadcast_event(HotShotEvent::LeafDecided(leafs_decided), event_stream).await;
+            let decide_sent = broadcast_event(
+                Event {
+                    view_number: consensus.last_decided_view,
+                    event: EventType::Decide {
+                        leaf_chain: Arc::new(leaf_views),
+                        qc: Arc::new(new_decide_qc.unwrap()),
+                        block_size: Some(included_txns_set.len().try_into().unwrap()),
+                    },
+                },
+                &self.output_event_stream,
+            );
+            let old_anchor_view = consensus.last_decided_view;
+            // the leader of the view announces the anchor now and then, for joining nodes
+            if let Some(announcement) = anchor_announcement {
+                if anchor::is_announcement_due(*old_anchor_view, *new_anchor_view)
+                    && self.quorum_membership.get_leader(view) == self.public_key
+                {
+                    broadcast_event(
+                        HotShotEvent::AnchorAnnouncementSend(announcement, self.public_key.clone()),
+                        event_stream,
+                    )
+                    .await;
+                }
+            }
+            consensus
+                .collect_garbage(old_anchor_view, new_anchor_view)
+                .await;
+            self.vid_shares = self.vid_shares.split_off(&new_anchor_view);
+            self.proposals = self.proposals.split_off(&new_anchor_view);
+            consensus.last_decided_view = new_anchor_view;
+            consensus
+                .metrics
+                .last_decided_time
+                .set(Utc::now().timestamp().try_into().unwrap());
+            consensus.metrics.invalid_qc.set(0);
+            consensus
+                .metrics
+                .last_decided_view
+                .set(usize::try_from(consensus.last_decided_view.get_u64()).unwrap());
+            let cur_number_of_views_per_decide_event =
+                *self.cur_view - consensus.last_decided_view.get_u64();
+            consensus
+                .metrics
+                .number_of_views_per_decide_event
+                .add_point(cur_number_of_views_per_decide_event as f64);
+
+            if let Err(e) = self.api.store_payloads(decided_payloads).await {
+                error!("Could not store the payloads of decided views: {:?}", e);
+                self.report_error(view, HotShotError::StorageError { source: e })
+                    .await;
+            }
+
+            if let Err(e) = self.api.store_view_artifacts(view_artifacts).await {
+                error!("Could not store the artifacts of decided views: {:?}", e);
+                self.report_error(view, HotShotError::StorageError { source: e })
+                    .await;
+            }
+
+            // We're only storing the last QC. We could store more but we're realistically only going to retrieve the last one.
+            if let Err(e) = self.api.store_leaf(old_anchor_view, leaf).await {
+                error!("Could not insert new anchor into the storage API: {:?}", e);
+                self.report_error(view, HotShotError::StorageError { source: e })
+                    .await;
+            }
+
+            debug!("Sending Decide for view {:?}", consensus.last_decided_view);
+            debug!("Decided txns len {:?}", included_txns_set.len());
+            decide_sent.await;
+            debug!("decide send succeeded");
+        }
+
+        let new_view = self.current_proposal.clone().unwrap().view_number + 1;
+        // In future we can use the mempool model where we fetch the proposal if we don't have it, instead of having to wait for it here
+        // This is for the case where we form a QC but have not yet seen the previous proposal ourselves
+        let should_propose = self.quorum_membership.get_leader(new_view) == self.public_key
+            && consensus.high_qc.view_number == self.current_proposal.clone().unwrap().view_number;
+        // todo get rid of this clone
+        let qc = consensus.high_qc.clone();
+
+        drop(consensus);
+        if should_propose {
+            debug!(
+                "Attempting to publish proposal after voting; now in view: {}",
+                *new_view
+            );
+            self.publish_proposal_if_able(qc.view_number + 1, None, event_stream)
+                .await;
+        }
+
+        if !self.vote_if_able(event_stream).await {
+            return;
+        }
+        self.current_proposal = None;
+    }
+
     /// Handles a consensus event received on the event stream
     #[instrument(skip_all, fields(id = self.id, view = *self.cur_view), name = "Consensus replica task", level = "error")]
     pub async fn handle(
@@ -447,6 +1320,52 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     warn!("Leader key does not match key in proposal");
                     return;
                 }
+                // Anyone can send a proposal claiming to be from the leader, so the leader is
+                // only penalized for proposals it has signed
+                let parent_commitment = if proposal.data.justify_qc.is_genesis {
+                    Leaf::genesis(&self.consensus.read().await.instance_state).commit()
+                } else {
+                    proposal.data.justify_qc.get_data().leaf_commit
+                };
+                let leaf_commitment =
+                    proposed_leaf(&proposal.data, parent_commitment, sender.clone()).commit();
+                if !signing::validate(
+                    &view_leader_key,
+                    &proposal.signature,
+                    SigningDomain::QuorumProposal,
+                    leaf_commitment.as_ref(),
+                ) {
+                    warn!(?proposal.signature, "Could not verify proposal for view {}", *view);
+                    return;
+                }
+
+                // A proposal more than a view ahead may well arrive before the one it extends.
+                // Hold it until we reach the preceding view rather than voting without its parent.
+                // Only proposals signed by their leader are held, so that forged ones cannot
+                // crowd the real ones out of the bounded buffer.
+                if view > self.cur_view + 1 && !proposal.data.justify_qc.is_genesis {
+                    let parent_known = self
+                        .consensus
+                        .read()
+                        .await
+                        .saved_leaves
+                        .contains_key(&proposal.data.justify_qc.get_data().leaf_commit);
+                    if !parent_known
+                        && self
+                            .hold_until_view(
+                                view - 1,
+                                HotShotEvent::QuorumProposalRecv(proposal.clone(), sender.clone()),
+                                &event_stream,
+                            )
+                            .await
+                    {
+                        debug!(
+                            "Holding proposal for view {} until its parent arrives",
+                            *view
+                        );
+                        return;
+                    }
+                }
 
                 // Verify a timeout certificate exists and is valid
                 if proposal.data.justify_qc.get_view_number() != view - 1 {
@@ -454,16 +1373,19 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                         warn!(
                             "Quorum proposal for view {} needed a timeout certificate but did not have one",
                             *view);
+                        self.record_invalid_proposal(sender).await;
                         return;
                     };
 
                     if timeout_cert.get_data().view != view - 1 {
                         warn!("Timeout certificate for view {} was not for the immediately preceding view", *view);
+                        self.record_invalid_proposal(sender).await;
                         return;
                     }
 
                     if !timeout_cert.is_valid_cert(self.timeout_membership.as_ref()) {
                         warn!("Timeout certificate for view {} was invalid", *view);
+                        self.record_invalid_proposal(sender).await;
                         return;
                     }
                 }
@@ -472,8 +1394,13 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
 
                 if !justify_qc.is_valid_cert(self.quorum_membership.as_ref()) {
                     error!("Invalid justify_qc in proposal for view {}", *view);
-                    let consensus = self.consensus.write().await;
+                    let mut consensus = self.consensus.write().await;
                     consensus.metrics.invalid_qc.update(1);
+                    consensus.leader_scores.record_invalid(sender);
+                    return;
+                }
+                if let Some(evidence) = self.safety_monitor.observe_certificate(&justify_qc) {
+                    self.halt(evidence).await;
                     return;
                 }
 
@@ -495,14 +1422,41 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 if let Some(ref upgrade_cert) = proposal.data.upgrade_certificate {
                     if !upgrade_cert.is_valid_cert(self.quorum_membership.as_ref()) {
                         error!("Invalid upgrade_cert in proposal for view {}", *view);
+                        self.record_invalid_proposal(sender).await;
                         return;
                     }
                 }
 
+                // the proposal is only reported once its signature and certificates verify
+                self.event_bus.publish(BusEvent::QuorumProposalReceived {
+                    view,
+                    proposer: sender.clone(),
+                });
+
                 // NOTE: We could update our view with a valid TC but invalid QC, but that is not what we do here
                 self.update_view(view, &event_stream).await;
 
-                let consensus = self.consensus.upgradable_read().await;
+                // the state of a parent still applied on a worker task is not known yet
+                let parent_commitment = justify_qc.get_data().leaf_commit;
+                if self.state_pipeline.is_applying(&parent_commitment) {
+                    debug!(
+                        "Holding proposal for view {} until the state of its parent is applied",
+                        *view
+                    );
+                    if !self.state_pipeline.hold(
+                        parent_commitment,
+                        HotShotEvent::QuorumProposalRecv(proposal, sender),
+                    ) {
+                        warn!(
+                            "Too many proposals held, dropping the proposal for view {}",
+                            *view
+                        );
+                    }
+                    return;
+                }
+
+                let consensus_lock = Arc::clone(&self.consensus);
+                let consensus = consensus_lock.upgradable_read().await;
 
                 // Get the parent leaf and state.
                 let parent = if justify_qc.is_genesis {
@@ -560,6 +1514,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                         block_header: proposal.data.block_header.clone(),
                         block_payload: None,
                         proposer_id: sender,
+                        governance: proposal.data.governance.clone(),
                     };
                     let state = Arc::new(<TYPES::ValidatedState as ValidatedState>::from_header(
                         &proposal.data.block_header,
@@ -587,6 +1542,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
 
                     if liveness_check {
                         self.current_proposal = Some(proposal.data.clone());
+                        self.proposals.insert(view, proposal.clone());
                         let new_view = proposal.data.view_number + 1;
 
                         // This is for the case where we form a QC but have not yet seen the previous proposal ourselves
@@ -607,265 +1563,74 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                             self.current_proposal = None;
                         }
                     }
-                    warn!("Failed liveneess check; cannot find parent either\n High QC is {:?}  Proposal QC is {:?}  Locked view is {:?}", high_qc, proposal.data.clone(), locked_view);
+                    if let Some(summary) = log_throttle::check("liveness_check") {
+                        warn!("Failed liveneess check; cannot find parent either\n High QC is {:?}  Proposal QC is {:?}  Locked view is {:?}{summary}", high_qc, proposal.data.clone(), locked_view);
+                    }
 
                     return;
                 };
+                if let Err(e) = validate_header(
+                    self.header_extender.as_ref(),
+                    view,
+                    &parent_leaf.block_header,
+                    &proposal.data.block_header,
+                ) {
+                    error!("Block header rejected: {e}");
+                    consensus.leader_scores.record_invalid(sender.clone());
+                    self.report_error(
+                        view,
+                        HotShotError::InvalidProposal {
+                            view_number: view,
+                            leader: sender,
+                            reason: e.to_string(),
+                        },
+                    )
+                    .await;
+                    return;
+                }
+                if self.parallel_state_application {
+                    let instance_state = consensus.instance_state.clone();
+                    drop(consensus);
+                    self.start_state_application(
+                        proposal,
+                        sender,
+                        &parent_leaf,
+                        parent_state,
+                        instance_state,
+                        &event_stream,
+                    );
+                    return;
+                }
                 let Ok(state) = parent_state.validate_and_apply_header(
                     &consensus.instance_state,
                     &parent_leaf.block_header.clone(),
                     &proposal.data.block_header.clone(),
                 ) else {
                     error!("Block header doesn't extend the proposal",);
-                    return;
-                };
-                let state = Arc::new(state);
-                let parent_commitment = parent_leaf.commit();
-                let leaf: Leaf<_> = Leaf {
-                    view_number: view,
-                    justify_qc: justify_qc.clone(),
-                    parent_commitment,
-                    block_header: proposal.data.block_header.clone(),
-                    block_payload: None,
-                    proposer_id: sender.clone(),
-                };
-                let leaf_commitment = leaf.commit();
-
-                // Validate the signature. This should also catch if the leaf_commitment does not equal our calculated parent commitment
-                if !view_leader_key.validate(&proposal.signature, leaf_commitment.as_ref()) {
-                    error!(?proposal.signature, "Could not verify proposal.");
-                    return;
-                }
-                // Create a positive vote if either liveness or safety check
-                // passes.
-
-                // Liveness check.
-                let liveness_check = justify_qc.get_view_number() > consensus.locked_view;
-
-                // Safety check.
-                // Check if proposal extends from the locked leaf.
-                let outcome = consensus.visit_leaf_ancestors(
-                    justify_qc.get_view_number(),
-                    Terminator::Inclusive(consensus.locked_view),
-                    false,
-                    |leaf| {
-                        // if leaf view no == locked view no then we're done, report success by
-                        // returning true
-                        leaf.view_number != consensus.locked_view
-                    },
-                );
-                let safety_check = outcome.is_ok();
-                if let Err(e) = outcome {
-                    self.api
-                        .send_event(Event {
+                    consensus.leader_scores.record_invalid(sender.clone());
+                    self.report_error(
+                        view,
+                        HotShotError::InvalidProposal {
                             view_number: view,
-                            event: EventType::Error { error: Arc::new(e) },
-                        })
-                        .await;
-                }
-
-                // Skip if both saftey and liveness checks fail.
-                if !safety_check && !liveness_check {
-                    error!("Failed safety and liveness check \n High QC is {:?}  Proposal QC is {:?}  Locked view is {:?}", consensus.high_qc, proposal.data.clone(), consensus.locked_view);
-                    return;
-                }
-
-                self.current_proposal = Some(proposal.data.clone());
-
-                // We accept the proposal, notify the application layer
-                self.api
-                    .send_event(Event {
-                        view_number: self.cur_view,
-                        event: EventType::QuorumProposal {
-                            proposal: proposal.clone(),
-                            sender,
+                            leader: sender,
+                            reason: "block header does not extend its parent".to_string(),
                         },
-                    })
+                    )
                     .await;
-
-                let mut new_anchor_view = consensus.last_decided_view;
-                let mut new_locked_view = consensus.locked_view;
-                let mut last_view_number_visited = view;
-                let mut new_commit_reached: bool = false;
-                let mut new_decide_reached = false;
-                let mut new_decide_qc = None;
-                let mut leaf_views = Vec::new();
-                let mut leafs_decided = Vec::new();
-                let mut included_txns = HashSet::new();
-                let old_anchor_view = consensus.last_decided_view;
-                let parent_view = leaf.justify_qc.get_view_number();
-                let mut current_chain_length = 0usize;
-                if parent_view + 1 == view {
-                    current_chain_length += 1;
-                    if let Err(e) = consensus.visit_leaf_ancestors(
-                        parent_view,
-                        Terminator::Exclusive(old_anchor_view),
-                        true,
-                        |leaf| {
-                            if !new_decide_reached {
-                                if last_view_number_visited == leaf.view_number + 1 {
-                                    last_view_number_visited = leaf.view_number;
-                                    current_chain_length += 1;
-                                    if current_chain_length == 2 {
-                                        new_locked_view = leaf.view_number;
-                                        new_commit_reached = true;
-                                        // The next leaf in the chain, if there is one, is decided, so this
-                                        // leaf's justify_qc would become the QC for the decided chain.
-                                        new_decide_qc = Some(leaf.justify_qc.clone());
-                                    } else if current_chain_length == 3 {
-                                        new_anchor_view = leaf.view_number;
-                                        new_decide_reached = true;
-                                    }
-                                } else {
-                                    // nothing more to do here... we don't have a new chain extension
-                                    return false;
-                                }
-                            }
-                            // starting from the first iteration with a three chain, e.g. right after the else if case nested in the if case above
-                            if new_decide_reached {
-                                let mut leaf = leaf.clone();
-                                if leaf.view_number == new_anchor_view {
-                                    consensus
-                                        .metrics
-                                        .last_synced_block_height
-                                        .set(usize::try_from(leaf.get_height()).unwrap_or(0));
-                                }
-                                if let Some(ref upgrade_cert) = proposal.data.upgrade_certificate {
-                                    info!("Updating consensus state with decided upgrade certificate: {:?}", upgrade_cert);
-                                    self.decided_upgrade_cert = Some(upgrade_cert.clone());
-                                }
-                                // If the block payload is available for this leaf, include it in
-                                // the leaf chain that we send to the client.
-                                if let Some(encoded_txns) =
-                                    consensus.saved_payloads.get(&leaf.get_view_number())
-                                {
-                                    let payload = BlockPayload::from_bytes(
-                                        encoded_txns.clone().into_iter(),
-                                        leaf.get_block_header().metadata(),
-                                    );
-
-                                    leaf.fill_block_payload_unchecked(payload);
-                                }
-
-                                let vid = self
-                                    .vid_shares
-                                    .get(&leaf.get_view_number())
-                                    .map(|vid_proposal| vid_proposal.data.clone());
-
-                                leaf_views.push((leaf.clone(), vid));
-                                leafs_decided.push(leaf.clone());
-                                if let Some(ref payload) = leaf.block_payload {
-                                    for txn in payload
-                                        .transaction_commitments(leaf.get_block_header().metadata())
-                                    {
-                                        included_txns.insert(txn);
-                                    }
-                                }
-                            }
-                            true
-                        },
-                    ) {
-                        error!("view publish error {e}");
-                        broadcast_event(
-                            Event {
-                                view_number: view,
-                                event: EventType::Error { error: e.into() },
-                            },
-                            &self.output_event_stream,
-                        )
-                        .await;
-                    }
-                }
-
-                let included_txns_set: HashSet<_> = if new_decide_reached {
-                    included_txns
-                } else {
-                    HashSet::new()
-                };
-
-                consensus.validated_state_map.insert(
-                    view,
-                    View {
-                        view_inner: ViewInner::Leaf {
-                            leaf: leaf.commit(),
-                            state,
-                        },
-                    },
-                );
-                consensus.saved_leaves.insert(leaf.commit(), leaf.clone());
-                if new_commit_reached {
-                    consensus.locked_view = new_locked_view;
-                }
-                #[allow(clippy::cast_precision_loss)]
-                if new_decide_reached {
-                    broadcast_event(HotShotEvent::LeafDecided(leafs_decided), &event_stream).await;
-                    let decide_sent = broadcast_event(
-                        Event {
-                            view_number: consensus.last_decided_view,
-                            event: EventType::Decide {
-                                leaf_chain: Arc::new(leaf_views),
-                                qc: Arc::new(new_decide_qc.unwrap()),
-                                block_size: Some(included_txns_set.len().try_into().unwrap()),
-                            },
-                        },
-                        &self.output_event_stream,
-                    );
-                    let old_anchor_view = consensus.last_decided_view;
-                    consensus
-                        .collect_garbage(old_anchor_view, new_anchor_view)
-                        .await;
-                    self.vid_shares = self.vid_shares.split_off(&new_anchor_view);
-                    consensus.last_decided_view = new_anchor_view;
-                    consensus
-                        .metrics
-                        .last_decided_time
-                        .set(Utc::now().timestamp().try_into().unwrap());
-                    consensus.metrics.invalid_qc.set(0);
-                    consensus
-                        .metrics
-                        .last_decided_view
-                        .set(usize::try_from(consensus.last_decided_view.get_u64()).unwrap());
-                    let cur_number_of_views_per_decide_event =
-                        *self.cur_view - consensus.last_decided_view.get_u64();
-                    consensus
-                        .metrics
-                        .number_of_views_per_decide_event
-                        .add_point(cur_number_of_views_per_decide_event as f64);
-
-                    // We're only storing the last QC. We could store more but we're realistically only going to retrieve the last one.
-                    if let Err(e) = self.api.store_leaf(old_anchor_view, leaf).await {
-                        error!("Could not insert new anchor into the storage API: {:?}", e);
-                    }
-
-                    debug!("Sending Decide for view {:?}", consensus.last_decided_view);
-                    debug!("Decided txns len {:?}", included_txns_set.len());
-                    decide_sent.await;
-                    debug!("decide send succeeded");
-                }
-
-                let new_view = self.current_proposal.clone().unwrap().view_number + 1;
-                // In future we can use the mempool model where we fetch the proposal if we don't have it, instead of having to wait for it here
-                // This is for the case where we form a QC but have not yet seen the previous proposal ourselves
-                let should_propose = self.quorum_membership.get_leader(new_view) == self.public_key
-                    && consensus.high_qc.view_number
-                        == self.current_proposal.clone().unwrap().view_number;
-                // todo get rid of this clone
-                let qc = consensus.high_qc.clone();
-
-                drop(consensus);
-                if should_propose {
-                    debug!(
-                        "Attempting to publish proposal after voting; now in view: {}",
-                        *new_view
-                    );
-                    self.publish_proposal_if_able(qc.view_number + 1, None, &event_stream)
-                        .await;
-                }
-
-                if !self.vote_if_able(&event_stream).await {
                     return;
-                }
-                self.current_proposal = None;
+                };
+                self.accept_proposal(
+                    proposal,
+                    sender,
+                    parent_leaf.commit(),
+                    Arc::new(state),
+                    consensus,
+                    &event_stream,
+                )
+                .await;
+            }
+            HotShotEvent::StateApplied(view, state) => {
+                self.handle_state_applied(view, state, &event_stream).await;
             }
             HotShotEvent::QuorumVoteRecv(ref vote) => {
                 debug!("Received quroum vote: {:?}", vote.get_view_number());
@@ -883,36 +1648,54 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     );
                     return;
                 }
-                let mut collector = self.vote_collector.write().await;
-
-                if collector.is_none() || vote.get_view_number() > collector.as_ref().unwrap().view
-                {
-                    debug!("Starting vote handle for view {:?}", vote.get_view_number());
-                    let info = AccumulatorInfo {
-                        public_key: self.public_key.clone(),
-                        membership: self.quorum_membership.clone(),
-                        view: vote.get_view_number(),
-                        id: self.id,
-                    };
-                    *collector = create_vote_accumulator::<
-                        TYPES,
-                        QuorumVote<TYPES>,
-                        QuorumCertificate<TYPES>,
-                    >(&info, vote.clone(), event, &event_stream)
-                    .await;
-                } else {
-                    let result = collector
-                        .as_mut()
-                        .unwrap()
-                        .handle_event(event.clone(), &event_stream)
-                        .await;
-
-                    if result == Some(HotShotTaskCompleted) {
-                        *collector = None;
-                        // The protocol has finished
+                // A vote for a view we have not reached would re-arm the collector and drop the
+                // votes of the certificate it is still forming, so hold it until we get there.
+                // Only votes signed by their voter are held, so that forged ones cannot crowd
+                // the real ones out of the bounded buffer.
+                let collecting_earlier = self
+                    .vote_collector
+                    .read()
+                    .await
+                    .as_ref()
+                    .is_some_and(|state| !state.completed && state.view < vote.get_view_number());
+                if collecting_earlier && vote.get_view_number() > self.cur_view {
+                    if !signing::validate_offloaded(
+                        vote.get_signing_key(),
+                        vote.get_signature(),
+                        SigningDomain::Vote,
+                        vote.get_data_commitment().as_ref(),
+                    )
+                    .await
+                    {
+                        warn!(
+                            "Invalid vote for future view {}, not holding it",
+                            *vote.get_view_number()
+                        );
+                        return;
+                    }
+                    if self
+                        .hold_until_view(vote.get_view_number(), event.clone(), &event_stream)
+                        .await
+                    {
                         return;
                     }
                 }
+                let mut collector = self.vote_collector.write().await;
+                let info = AccumulatorInfo {
+                    public_key: self.public_key.clone(),
+                    membership: self.quorum_membership.clone(),
+                    view: vote.get_view_number(),
+                    id: self.id,
+                };
+                handle_vote(
+                    &mut collector,
+                    vote,
+                    event.clone(),
+                    &info,
+                    &self.api,
+                    &event_stream,
+                )
+                .await;
             }
             HotShotEvent::TimeoutVoteRecv(ref vote) => {
                 if self
@@ -930,38 +1713,30 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     return;
                 }
                 let mut collector = self.timeout_vote_collector.write().await;
-
-                if collector.is_none() || vote.get_view_number() > collector.as_ref().unwrap().view
-                {
-                    debug!("Starting vote handle for view {:?}", vote.get_view_number());
-                    let info = AccumulatorInfo {
-                        public_key: self.public_key.clone(),
-                        membership: self.quorum_membership.clone(),
-                        view: vote.get_view_number(),
-                        id: self.id,
-                    };
-                    *collector = create_vote_accumulator::<
-                        TYPES,
-                        TimeoutVote<TYPES>,
-                        TimeoutCertificate<TYPES>,
-                    >(&info, vote.clone(), event, &event_stream)
-                    .await;
-                } else {
-                    let result = collector
-                        .as_mut()
-                        .unwrap()
-                        .handle_event(event.clone(), &event_stream)
-                        .await;
-
-                    if result == Some(HotShotTaskCompleted) {
-                        *collector = None;
-                        // The protocol has finished
-                        return;
-                    }
-                }
+                let info = AccumulatorInfo {
+                    public_key: self.public_key.clone(),
+                    membership: self.quorum_membership.clone(),
+                    view: vote.get_view_number(),
+                    id: self.id,
+                };
+                handle_vote(
+                    &mut collector,
+                    vote,
+                    event.clone(),
+                    &info,
+                    &self.api,
+                    &event_stream,
+                )
+                .await;
             }
             HotShotEvent::QCFormed(cert) => {
                 debug!("QC Formed event happened!");
+                self.event_bus.publish(BusEvent::CertificateFormed {
+                    view: cert
+                        .as_ref()
+                        .either(|qc| qc.get_view_number(), |tc| tc.get_view_number()),
+                    timeout: cert.is_right(),
+                });
 
                 if let either::Right(qc) = cert.clone() {
                     self.timeout_cert = Some(qc.clone());
@@ -988,6 +1763,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     }
                 }
                 if let either::Left(qc) = cert {
+                    if let Some(evidence) = self.safety_monitor.observe_certificate(&qc) {
+                        self.halt(evidence).await;
+                        return;
+                    }
                     let mut consensus = self.consensus.write().await;
                     consensus.high_qc = qc.clone();
 
@@ -1077,7 +1856,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     return;
                 }
 
-                if !view_leader_key.validate(&disperse.signature, payload_commitment.as_ref()) {
+                if !signing::validate(
+                    &view_leader_key,
+                    &disperse.signature,
+                    SigningDomain::VidDisperse,
+                    payload_commitment.as_ref(),
+                ) {
                     warn!("Could not verify VID dispersal/share sig.");
                     return;
                 }
@@ -1134,8 +1918,12 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 .await;
             }
             HotShotEvent::Timeout(view) => {
+                // an application of the state still running when a later view timed out is
+                // taken to be lost
+                self.prune_state_applications(view, &event_stream).await;
+
                 // NOTE: We may optionally have the timeout task listen for view change events
-                if self.cur_view >= view {
+                if self.cur_view >= view || self.halted {
                     return;
                 }
                 if !self.timeout_membership.has_stake(&self.public_key) {
@@ -1145,6 +1933,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     );
                     return;
                 }
+                self.event_bus.publish(BusEvent::ViewTimedOut { view });
 
                 // cancel poll for votes
                 self.quorum_network
@@ -1182,11 +1971,59 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 .await;
                 let consensus = self.consensus.read().await;
                 consensus.metrics.number_of_timeouts.add(1);
+                // report once, when the node has gone long enough without a decide
+                let views_without_decide = (*view).saturating_sub(*consensus.last_decided_view);
+                drop(consensus);
+                if views_without_decide == PARTITION_SUSPECTED_VIEWS {
+                    warn!(
+                        "Nothing decided for {views_without_decide} views, suspecting a partition"
+                    );
+                    self.report_error(
+                        view,
+                        HotShotError::PartitionSuspected {
+                            view_number: view,
+                            views_without_decide,
+                        },
+                    )
+                    .await;
+                }
             }
-            HotShotEvent::SendPayloadCommitmentAndMetadata(payload_commitment, metadata, view) => {
+            HotShotEvent::ProposalTimeout(view) => {
+                if self.cur_view >= view {
+                    return;
+                }
+                let leader = self.quorum_membership.get_leader(view);
+                if let Some(summary) = log_throttle::check("proposal_missing") {
+                    warn!(
+                        "No proposal for view {} from leader {:?} arrived in time{summary}",
+                        *view, leader
+                    );
+                }
+                broadcast_event(
+                    Event {
+                        view_number: view,
+                        event: EventType::ProposalMissing {
+                            view,
+                            leader: leader.clone(),
+                        },
+                    },
+                    &self.output_event_stream,
+                )
+                .await;
+                let mut consensus = self.consensus.write().await;
+                consensus.metrics.number_of_missing_proposals.add(1);
+                consensus.leader_scores.record_missed(*view, leader);
+            }
+            HotShotEvent::SendPayloadCommitmentAndMetadata(
+                payload_commitment,
+                transactions_root,
+                metadata,
+                view,
+            ) => {
                 debug!("got commit and meta {:?}", payload_commitment);
                 self.payload_commitment_and_metadata = Some(CommitmentAndMetadata {
                     commitment: payload_commitment,
+                    transactions_root,
                     metadata,
                     is_genesis: false,
                 });
@@ -1221,6 +2058,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
         timeout_certificate: Option<TimeoutCertificate<TYPES>>,
         event_stream: &Sender<HotShotEvent<TYPES>>,
     ) -> bool {
+        if self.halted {
+            return false;
+        }
         if self.quorum_membership.get_leader(view) != self.public_key {
             // This is expected for view 1, so skipping the logging.
             if view != TYPES::Time::new(1) {
@@ -1287,13 +2127,43 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
         }
 
         if let Some(commit_and_metadata) = &self.payload_commitment_and_metadata {
+            let mut extensions = self.header_extender.extensions(view, &parent_header);
+            if extensions.len() > MAX_HEADER_EXTENSIONS_SIZE {
+                // replicas would reject the proposal, so propose the block without them
+                error!(
+                    "Header extensions of {} bytes exceed {} bytes, proposing without them",
+                    extensions.len(),
+                    MAX_HEADER_EXTENSIONS_SIZE
+                );
+                extensions = Vec::new();
+            }
             let block_header = TYPES::BlockHeader::new(
                 state,
                 &consensus.instance_state,
                 &parent_header,
                 commit_and_metadata.commitment,
+                commit_and_metadata.transactions_root,
                 commit_and_metadata.metadata.clone(),
+                extensions,
             );
+            // the leaf commits to the governance transactions of the block, so that the nodes
+            // without the payload schedule the changes it decides as well. The block is the one
+            // we disseminated, whose payload we hold.
+            let governance = if commit_and_metadata.is_genesis {
+                Vec::new()
+            } else {
+                let Some(encoded_txns) =
+                    consensus.payload_store.get(&commit_and_metadata.commitment)
+                else {
+                    error!("Not proposing for view {view}: the payload of the block is missing");
+                    return false;
+                };
+                TYPES::BlockPayload::from_bytes(
+                    encoded_txns.iter().copied(),
+                    &commit_and_metadata.metadata,
+                )
+                .governance_transactions(&commit_and_metadata.metadata)
+            };
             let leaf = Leaf {
                 view_number: view,
                 justify_qc: consensus.high_qc.clone(),
@@ -1301,11 +2171,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 block_header: block_header.clone(),
                 block_payload: None,
                 proposer_id: self.api.public_key().clone(),
+                governance,
             };
 
-            let Ok(signature) =
-                TYPES::SignatureKey::sign(&self.private_key, leaf.commit().as_ref())
-            else {
+            let Ok(signature) = signing::sign::<TYPES::SignatureKey>(
+                &self.private_key,
+                SigningDomain::QuorumProposal,
+                leaf.commit().as_ref(),
+            ) else {
                 error!("Failed to sign leaf.commit()!");
                 return false;
             };
@@ -1333,6 +2206,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 timeout_certificate: timeout_certificate.or_else(|| None),
                 upgrade_certificate: upgrade_cert,
                 proposer_id: leaf.proposer_id,
+                governance: leaf.governance,
             };
 
             self.timeout_cert = None;
@@ -1346,6 +2220,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 leaf.view_number, ""
             );
 
+            self.event_bus.publish(BusEvent::QuorumProposalSent {
+                view: leaf.view_number,
+            });
             broadcast_event(
                 HotShotEvent::QuorumProposalSend(message.clone(), self.public_key.clone()),
                 event_stream,
@@ -1375,8 +2252,10 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 | HotShotEvent::ViewChange(_)
                 | HotShotEvent::SendPayloadCommitmentAndMetadata(..)
                 | HotShotEvent::Timeout(_)
+                | HotShotEvent::ProposalTimeout(_)
                 | HotShotEvent::TimeoutVoteRecv(_)
                 | HotShotEvent::VidDisperseRecv(..)
+                | HotShotEvent::StateApplied(..)
                 | HotShotEvent::Shutdown,
         )
     }
@@ -1,17 +1,24 @@
 use crate::{
+    bus::{BusEvent, EventBus},
+    compact_block::{AnnouncedTransactions, CompactRebuild},
     events::{HotShotEvent, HotShotTaskCompleted},
     helpers::broadcast_event,
-    vote::{create_vote_accumulator, AccumulatorInfo, VoteCollectionTaskState},
+    mempool::ShardedMempool,
+    vote::{handle_vote, AccumulatorInfo, VoteCollectionTaskState},
 };
 use async_broadcast::Sender;
 use async_lock::RwLock;
+use bytes::Bytes;
+use commit::{Commitment, Committable};
 
 use hotshot_task::task::{Task, TaskState};
 use hotshot_types::{
     consensus::{Consensus, View},
-    data::DAProposal,
+    data::{CompactDAProposal, CompactTransaction, DAProposal, DAProposalCommitment},
     event::{Event, EventType},
     message::Proposal,
+    receipt::TransactionIndex,
+    signing::{self, SigningDomain},
     simple_certificate::DACertificate,
     simple_vote::{DAData, DAVote},
     traits::{
@@ -21,24 +28,123 @@ use hotshot_types::{
         network::{ConnectedNetwork, ConsensusIntentEvent},
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
         signature_key::SignatureKey,
+        BlockPayload,
     },
     utils::ViewInner,
     vote::HasViewNumber,
 };
 use sha2::{Digest, Sha256};
 
-use crate::vote::HandleVoteEvent;
 use snafu::Snafu;
-use std::{marker::PhantomData, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    marker::PhantomData,
+    sync::Arc,
+};
 use tracing::{debug, error, instrument, warn};
 
 /// Alias for Optional type for Vote Collectors
 type VoteCollectorOption<TYPES, VOTE, CERT> = Option<VoteCollectionTaskState<TYPES, VOTE, CERT>>;
 
+/// Number of times a DA committee member asks the leader for the transactions of a compact DA
+/// proposal it misses: once on receiving it, then on each view change while it is rebuilt
+const COMPACT_REBUILD_MAX_REQUESTS: u32 = 3;
+
+/// A compact DA proposal being rebuilt, with its sender
+type PendingCompactProposal<TYPES> = (
+    Proposal<TYPES, CompactDAProposal<TYPES>>,
+    <TYPES as NodeType>::SignatureKey,
+    CompactRebuild<TYPES>,
+);
+
 #[derive(Snafu, Debug)]
 /// Error type for consensus tasks
 pub struct ConsensusTaskError {}
 
+/// When DA falls back from the DA committee to the whole quorum.
+///
+/// Without enough responsive DA committee members, no DA certificate forms and the chain halts.
+/// Once the views of `after_failures` in a row end without a DA certificate, DA leaders
+/// disseminate their proposals to every node for `views` views, and every node votes on them.
+/// A certificate signed by the quorum attests availability at least as well as one signed by
+/// the committee, so replicas accept either, whether or not they have fallen back themselves.
+pub struct DAFallbackState<TYPES: NodeType> {
+    /// consecutive views without a DA certificate after which DA falls back, `None` to never
+    after_failures: Option<u64>,
+    /// views DA stays fallen back for
+    views: u64,
+    /// the latest view a DA certificate was seen for
+    last_certified_view: TYPES::Time,
+    /// the last view whose DA proposal goes to the whole quorum, while fallen back
+    until: Option<TYPES::Time>,
+    /// the views we disseminated our DA proposal to the whole quorum for
+    proposed: BTreeSet<TYPES::Time>,
+}
+
+impl<TYPES: NodeType> DAFallbackState<TYPES> {
+    /// Falls back after `after_failures` views in a row without a DA certificate, if given, for
+    /// `views` views
+    #[must_use]
+    pub fn new(after_failures: Option<u64>, views: u64) -> Self {
+        Self {
+            after_failures,
+            views,
+            last_certified_view: TYPES::Time::genesis(),
+            until: None,
+            proposed: BTreeSet::new(),
+        }
+    }
+
+    /// Record that a DA certificate was seen for `view`
+    pub fn certified(&mut self, view: TYPES::Time) {
+        self.last_certified_view = self.last_certified_view.max(view);
+    }
+
+    /// Enter `view`. Returns the number of views which ended without a DA certificate if DA
+    /// falls back from `view` on.
+    pub fn enter_view(&mut self, view: TYPES::Time) -> Option<u64> {
+        self.proposed.retain(|proposed| *proposed + 1 >= view);
+        if self.until.is_some_and(|until| view > until) {
+            self.until = None;
+        }
+        let after_failures = self.after_failures?;
+        if self.until.is_some() {
+            return None;
+        }
+        let failed_views = (*view).saturating_sub(*self.last_certified_view + 1);
+        if failed_views < after_failures.max(1) {
+            return None;
+        }
+        self.until = Some(view + self.views);
+        // the committee gets `after_failures` views again once the fallback ends
+        self.last_certified_view = view + self.views;
+        Some(failed_views)
+    }
+
+    /// The last view DA proposals go to the whole quorum for, if DA has fallen back
+    #[must_use]
+    pub fn until(&self) -> Option<TYPES::Time> {
+        self.until
+    }
+
+    /// Whether our DA proposal for `view` goes to the whole quorum
+    #[must_use]
+    pub fn is_active(&self, view: TYPES::Time) -> bool {
+        self.until.is_some_and(|until| view <= until)
+    }
+
+    /// Record that our DA proposal for `view` went to the whole quorum
+    pub fn record_proposal(&mut self, view: TYPES::Time) {
+        self.proposed.insert(view);
+    }
+
+    /// Whether our DA proposal for `view` went to the whole quorum, whose votes it collects
+    #[must_use]
+    pub fn was_proposed(&self, view: TYPES::Time) -> bool {
+        self.proposed.contains(&view)
+    }
+}
+
 /// Tracks state of a DA task
 pub struct DATaskState<
     TYPES: NodeType,
@@ -74,6 +180,34 @@ pub struct DATaskState<
     /// This Nodes private key
     pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
 
+    /// Whether to send the payload of our DA proposals to the DA committee members only, and
+    /// broadcast just its commitment to the other nodes
+    pub unicast_payload: bool,
+
+    /// Whether to send our DA proposals compact, with the transactions the committee members are
+    /// believed to hold replaced by their commitments, to the members only
+    pub compact_proposals: bool,
+
+    /// The transactions DA committee members announced, which our compact DA proposals leave out
+    /// once most members announced them
+    pub announced:
+        AnnouncedTransactions<TYPES::Time, Commitment<TYPES::Transaction>, TYPES::SignatureKey>,
+
+    /// The compact DA proposals being rebuilt, by view
+    pub rebuilds: BTreeMap<TYPES::Time, PendingCompactProposal<TYPES>>,
+
+    /// The mempool of the node, which compact DA proposals are rebuilt from
+    pub mempool: Arc<ShardedMempool<TYPES>>,
+
+    /// The bus lifecycle events are published on
+    pub event_bus: EventBus<TYPES>,
+
+    /// When DA falls back to the whole quorum
+    pub fallback: DAFallbackState<TYPES>,
+
+    /// What became of the transactions the node saw proposed, shared with the handle of the node
+    pub tx_index: Arc<RwLock<TransactionIndex<TYPES>>>,
+
     /// This state's ID
     pub id: u64,
 }
@@ -81,6 +215,279 @@ pub struct DATaskState<
 impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> + 'static>
     DATaskState<TYPES, I, A>
 {
+    /// Validate a DA proposal, and vote on it if we are on the DA committee, or on the quorum
+    /// for a proposal which was disseminated to the whole quorum because DA fell back
+    async fn handle_proposal(
+        &mut self,
+        proposal: Proposal<TYPES, DAProposal<TYPES>>,
+        sender: TYPES::SignatureKey,
+        fallback: bool,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        debug!(
+            "DA proposal received for view: {:?}, disseminated to the whole quorum: {fallback}",
+            proposal.data.get_view_number()
+        );
+        // ED NOTE: Assuming that the next view leader is the one who sends DA proposal for this view
+        let view = proposal.data.get_view_number();
+
+        // Allow a DA proposal that is one view older, in case we have voted on a quorum
+        // proposal and updated the view.
+        // `self.cur_view` should be at least 1 since there is a view change before getting
+        // the `DAProposalRecv` event. Otherewise, the view number subtraction below will
+        // cause an overflow error.
+        // TODO ED Come back to this - we probably don't need this, but we should also never receive a DAC where this fails, investigate block ready so it doesn't make one for the genesis block
+
+        // stop polling for the received proposal
+        self.da_network
+            .inject_consensus_info(ConsensusIntentEvent::CancelPollForProposal(
+                *proposal.data.view_number,
+            ))
+            .await;
+
+        if self.cur_view != TYPES::Time::genesis() && view < self.cur_view - 1 {
+            warn!("Throwing away DA proposal that is more than one view older");
+            return;
+        }
+
+        let payload_commitment = vid_commitment(
+            &proposal.data.encoded_transactions,
+            self.quorum_membership.total_nodes(),
+        );
+        let encoded_transactions_hash = Sha256::digest(&proposal.data.encoded_transactions);
+
+        // ED Is this the right leader?
+        let view_leader_key = self.da_membership.get_leader(view);
+        if view_leader_key != sender {
+            error!(
+                "DA proposal doesn't have expected leader key for view {} \n DA proposal is: {:?}",
+                *view,
+                proposal.data.clone()
+            );
+            return;
+        }
+
+        if !signing::validate(
+            &view_leader_key,
+            &proposal.signature,
+            SigningDomain::DAProposal,
+            &encoded_transactions_hash,
+        ) {
+            error!("Could not verify proposal.");
+            return;
+        }
+
+        let payload = TYPES::BlockPayload::from_bytes(
+            proposal.data.encoded_transactions.iter().copied(),
+            &proposal.data.metadata,
+        );
+        let transactions = payload.transaction_commitments(&proposal.data.metadata);
+        // the DA certificate attests that the block is within the limit governance set for the
+        // view, so that the replicas without the payload enforce it as well
+        let max_transactions = self
+            .consensus
+            .read()
+            .await
+            .parameter_schedule
+            .parameters_at_view(view)
+            .max_transactions;
+        if transactions.len() > max_transactions.get() {
+            error!(
+                "DA proposal for view {} has {} transactions, more than the maximum of {}",
+                *view,
+                transactions.len(),
+                max_transactions
+            );
+            return;
+        }
+
+        // Proposal is fresh and valid, so its transactions are included until decided or replaced
+        self.tx_index
+            .write()
+            .await
+            .record_included(view, transactions);
+
+        // notify the application layer
+        self.event_bus
+            .publish(BusEvent::DAProposalReceived { view });
+        self.api
+            .send_event(Event {
+                view_number: self.cur_view,
+                event: EventType::DAProposal {
+                    proposal: proposal.clone(),
+                    sender: sender.clone(),
+                },
+            })
+            .await;
+
+        // every node of the quorum votes on a proposal disseminated to all of them
+        let voters = if fallback {
+            &self.quorum_membership
+        } else {
+            &self.da_membership
+        };
+        if !voters.has_stake(&self.public_key) {
+            debug!(
+                "We were not chosen for consensus committee on {:?}",
+                self.cur_view
+            );
+            return;
+        }
+        // Generate and send vote
+        let Ok(vote) = DAVote::create_signed_vote(
+            DAData {
+                payload_commit: payload_commitment,
+                governance: payload.governance_transactions(&proposal.data.metadata),
+            },
+            view,
+            &self.public_key,
+            &self.private_key,
+        ) else {
+            error!("Failed to sign DA Vote!");
+            return;
+        };
+
+        // ED Don't think this is necessary?
+        // self.cur_view = view;
+
+        debug!("Sending vote to the DA leader {:?}", vote.get_view_number());
+
+        let event = if fallback {
+            HotShotEvent::DAFallbackVoteSend(vote)
+        } else {
+            HotShotEvent::DAVoteSend(vote)
+        };
+        broadcast_event(event, event_stream).await;
+        let mut consensus = self.consensus.write().await;
+
+        // Ensure this view is in the view map for garbage collection, but do not overwrite if
+        // there is already a view there: the replica task may have inserted a `Leaf` view which
+        // contains strictly more information.
+        consensus.validated_state_map.entry(view).or_insert(View {
+            view_inner: ViewInner::DA { payload_commitment },
+        });
+
+        // Record the payload we have promised to make available.
+        consensus.save_payload(view, payload_commitment, proposal.data.encoded_transactions);
+    }
+
+    /// The compact form of our DA proposal `message`, leaving out the transactions other nodes
+    /// announced, if its payload lists its transactions and members can rebuild it from them
+    fn compact_proposal(
+        &self,
+        message: &Proposal<TYPES, DAProposal<TYPES>>,
+    ) -> Option<Proposal<TYPES, CompactDAProposal<TYPES>>> {
+        let proposal = &message.data;
+        let payload = TYPES::BlockPayload::from_bytes(
+            proposal.encoded_transactions.iter().copied(),
+            &proposal.metadata,
+        );
+        let transactions = payload.transactions(&proposal.metadata)?;
+        // members rebuild the payload from its transactions, which must give back the bytes the
+        // proposal signature signs
+        let (rebuilt, metadata) =
+            TYPES::BlockPayload::from_transactions(transactions.clone()).ok()?;
+        let encoded: Vec<u8> = rebuilt.encode().ok()?.collect();
+        if proposal.encoded_transactions != encoded || proposal.metadata != metadata {
+            warn!("The payload cannot be rebuilt from its transactions, sending it in full");
+            return None;
+        }
+        // a transaction is left out once most of the committee announced it
+        let min_announcers = self.da_membership.total_nodes() / 2 + 1;
+        let transactions = transactions
+            .into_iter()
+            .map(|transaction| {
+                let commitment = transaction.commit();
+                if self.announced.is_believed_held(&commitment, min_announcers) {
+                    CompactTransaction::Held(commitment)
+                } else {
+                    CompactTransaction::Full(transaction)
+                }
+            })
+            .collect();
+        let data = CompactDAProposal {
+            transactions,
+            metadata: proposal.metadata.clone(),
+            view_number: proposal.view_number,
+            payload_signature: message.signature.clone(),
+        };
+        let Ok(signature) = signing::sign::<TYPES::SignatureKey>(
+            &self.private_key,
+            SigningDomain::CompactDAProposal,
+            &data.signed_bytes(),
+        ) else {
+            error!("Failed to sign compact DA proposal, sending it in full");
+            return None;
+        };
+        Some(Proposal {
+            data,
+            signature,
+            _pd: PhantomData,
+        })
+    }
+
+    /// Handle the compact DA proposal of `view` as a full one once it is rebuilt, or, if
+    /// `request_missing`, ask its leader for the transactions still missing unless it was asked
+    /// too often already
+    async fn advance_rebuild(
+        &mut self,
+        view: TYPES::Time,
+        request_missing: bool,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) {
+        let Some((_, sender, rebuild)) = self.rebuilds.get_mut(&view) else {
+            return;
+        };
+        if !rebuild.is_complete() {
+            if !request_missing || !rebuild.request(COMPACT_REBUILD_MAX_REQUESTS) {
+                return;
+            }
+            let missing = rebuild.missing();
+            debug!(
+                "Requesting {} transactions of the compact DA proposal of view {} from its leader",
+                missing.len(),
+                *view
+            );
+            broadcast_event(
+                HotShotEvent::MempoolRequestSend(missing, sender.clone(), self.public_key.clone()),
+                event_stream,
+            )
+            .await;
+            return;
+        }
+        let Some((proposal, sender, rebuild)) = self.rebuilds.remove(&view) else {
+            return;
+        };
+        let Some(transactions) = rebuild.into_transactions() else {
+            return;
+        };
+        let encoded_transactions = match TYPES::BlockPayload::from_transactions(transactions)
+            .and_then(|(payload, _)| payload.encode().map(Iterator::collect::<Bytes>))
+        {
+            Ok(encoded_transactions) => encoded_transactions,
+            Err(e) => {
+                error!(
+                    "Failed to rebuild the compact DA proposal of view {}: {e:?}",
+                    *view
+                );
+                return;
+            }
+        };
+        // the compact proposal carries the signature of the full proposal, which
+        // `handle_proposal` checks against the rebuilt payload
+        let proposal = Proposal {
+            data: DAProposal {
+                encoded_transactions,
+                metadata: proposal.data.metadata,
+                view_number: view,
+            },
+            signature: proposal.data.payload_signature,
+            _pd: PhantomData,
+        };
+        self.handle_proposal(proposal, sender, false, event_stream)
+            .await;
+    }
+
     /// main task event handler
     #[instrument(skip_all, fields(id = self.id, view = *self.cur_view), name = "DA Main Task", level = "error")]
     pub async fn handle(
@@ -90,100 +497,78 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
     ) -> Option<HotShotTaskCompleted> {
         match event {
             HotShotEvent::DAProposalRecv(proposal, sender) => {
-                debug!(
-                    "DA proposal received for view: {:?}",
-                    proposal.data.get_view_number()
-                );
-                // ED NOTE: Assuming that the next view leader is the one who sends DA proposal for this view
-                let view = proposal.data.get_view_number();
-
-                // Allow a DA proposal that is one view older, in case we have voted on a quorum
-                // proposal and updated the view.
-                // `self.cur_view` should be at least 1 since there is a view change before getting
-                // the `DAProposalRecv` event. Otherewise, the view number subtraction below will
-                // cause an overflow error.
-                // TODO ED Come back to this - we probably don't need this, but we should also never receive a DAC where this fails, investigate block ready so it doesn't make one for the genesis block
-
-                // stop polling for the received proposal
-                self.da_network
-                    .inject_consensus_info(ConsensusIntentEvent::CancelPollForProposal(
-                        *proposal.data.view_number,
-                    ))
+                self.handle_proposal(proposal, sender, false, &event_stream)
                     .await;
-
+            }
+            HotShotEvent::DAFallbackProposalRecv(proposal, sender) => {
+                self.handle_proposal(proposal, sender, true, &event_stream)
+                    .await;
+            }
+            HotShotEvent::CompactDAProposalRecv(proposal, sender) => {
+                let view = proposal.data.get_view_number();
                 if self.cur_view != TYPES::Time::genesis() && view < self.cur_view - 1 {
-                    warn!("Throwing away DA proposal that is more than one view older");
+                    warn!("Throwing away compact DA proposal that is more than one view older");
                     return None;
                 }
-
-                let payload_commitment = vid_commitment(
-                    &proposal.data.encoded_transactions,
-                    self.quorum_membership.total_nodes(),
-                );
-                let encoded_transactions_hash = Sha256::digest(&proposal.data.encoded_transactions);
-
-                // ED Is this the right leader?
-                let view_leader_key = self.da_membership.get_leader(view);
-                if view_leader_key != sender {
-                    error!("DA proposal doesn't have expected leader key for view {} \n DA proposal is: {:?}", *view, proposal.data.clone());
+                if self.da_membership.get_leader(view) != sender
+                    || !signing::validate(
+                        &sender,
+                        &proposal.signature,
+                        SigningDomain::CompactDAProposal,
+                        &proposal.data.signed_bytes(),
+                    )
+                {
+                    error!("Could not verify the compact DA proposal of view {}", *view);
                     return None;
                 }
-
-                if !view_leader_key.validate(&proposal.signature, &encoded_transactions_hash) {
-                    error!("Could not verify proposal.");
+                // the leader proposes once per view, any other proposal is an equivocation
+                if self.rebuilds.contains_key(&view) {
+                    warn!("Ignoring a second compact DA proposal for view {}", *view);
                     return None;
                 }
-
-                // Proposal is fresh and valid, notify the application layer
-                self.api
-                    .send_event(Event {
-                        view_number: self.cur_view,
-                        event: EventType::DAProposal {
-                            proposal: proposal.clone(),
-                            sender: sender.clone(),
-                        },
-                    })
-                    .await;
-
-                if !self.da_membership.has_stake(&self.public_key) {
-                    debug!(
-                        "We were not chosen for consensus committee on {:?}",
-                        self.cur_view
+                // a block over the limit is not voted for whatever it holds, so its missing
+                // transactions are not worth tracking
+                let max_transactions = self
+                    .consensus
+                    .read()
+                    .await
+                    .parameter_schedule
+                    .parameters_at_view(view)
+                    .max_transactions
+                    .get();
+                if proposal.data.transactions.len() > max_transactions {
+                    warn!(
+                        "Compact DA proposal of view {} holds {} transactions, over the limit of \
+                         {max_transactions}",
+                        *view,
+                        proposal.data.transactions.len()
                     );
                     return None;
                 }
-                // Generate and send vote
-                let Ok(vote) = DAVote::create_signed_vote(
-                    DAData {
-                        payload_commit: payload_commitment,
-                    },
-                    view,
-                    &self.public_key,
-                    &self.private_key,
-                ) else {
-                    error!("Failed to sign DA Vote!");
-                    return None;
-                };
-
-                // ED Don't think this is necessary?
-                // self.cur_view = view;
-
-                debug!("Sending vote to the DA leader {:?}", vote.get_view_number());
-
-                broadcast_event(HotShotEvent::DAVoteSend(vote), &event_stream).await;
-                let mut consensus = self.consensus.write().await;
-
-                // Ensure this view is in the view map for garbage collection, but do not overwrite if
-                // there is already a view there: the replica task may have inserted a `Leaf` view which
-                // contains strictly more information.
-                consensus.validated_state_map.entry(view).or_insert(View {
-                    view_inner: ViewInner::DA { payload_commitment },
-                });
-
-                // Record the payload we have promised to make available.
-                consensus
-                    .saved_payloads
-                    .insert(view, proposal.data.encoded_transactions);
+                let mut rebuild = CompactRebuild::new(&proposal.data.transactions);
+                rebuild.fill(self.mempool.get_all(&rebuild.missing()).await);
+                self.rebuilds.insert(view, (proposal, sender, rebuild));
+                self.advance_rebuild(view, true, &event_stream).await;
+            }
+            HotShotEvent::TransactionsRecv(transactions) => {
+                // the transactions fetched for compact proposals arrive as any other
+                let progressed: Vec<_> = self
+                    .rebuilds
+                    .iter_mut()
+                    .filter_map(|(view, (_, _, rebuild))| {
+                        (rebuild.fill(transactions.iter().cloned()) > 0).then_some(*view)
+                    })
+                    .collect();
+                for view in progressed {
+                    self.advance_rebuild(view, false, &event_stream).await;
+                }
+            }
+            HotShotEvent::MempoolInventoryRecv(commitments, announcer) => {
+                // only the mempools of DA committee members matter to compact proposals
+                if announcer != self.public_key && self.da_membership.has_stake(&announcer) {
+                    self.announced
+                        .announced(self.cur_view, &announcer, commitments);
+                }
             }
             HotShotEvent::DAVoteRecv(ref vote) => {
                 debug!("DA vote recv, Main Task {:?}", vote.get_view_number());
@@ -194,35 +579,36 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     return None;
                 }
                 let mut collector = self.vote_collector.write().await;
-
-                if collector.is_none() || vote.get_view_number() > collector.as_ref().unwrap().view
-                {
-                    debug!("Starting vote handle for view {:?}", vote.get_view_number());
-                    let info = AccumulatorInfo {
-                        public_key: self.public_key.clone(),
-                        membership: self.da_membership.clone(),
-                        view: vote.get_view_number(),
-                        id: self.id,
-                    };
-                    *collector = create_vote_accumulator::<
-                        TYPES,
-                        DAVote<TYPES>,
-                        DACertificate<TYPES>,
-                    >(&info, vote.clone(), event, &event_stream)
-                    .await;
+                // votes on a proposal disseminated to the whole quorum form a certificate of it
+                let membership = if self.fallback.was_proposed(view) {
+                    self.quorum_membership.clone()
                 } else {
-                    let result = collector
-                        .as_mut()
-                        .unwrap()
-                        .handle_event(event.clone(), &event_stream)
-                        .await;
-
-                    if result == Some(HotShotTaskCompleted) {
-                        *collector = None;
-                        // The protocol has finished
-                        return None;
-                    }
-                }
+                    self.da_membership.clone()
+                };
+                let info = AccumulatorInfo {
+                    public_key: self.public_key.clone(),
+                    membership,
+                    view: vote.get_view_number(),
+                    id: self.id,
+                };
+                handle_vote(
+                    &mut collector,
+                    vote,
+                    event.clone(),
+                    &info,
+                    &self.api,
+                    &event_stream,
+                )
+                .await;
+            }
+            HotShotEvent::DACSend(certificate, _) => {
+                self.fallback.certified(certificate.get_view_number());
+                self.event_bus.publish(BusEvent::DACertificateFormed {
+                    view: certificate.get_view_number(),
+                });
+            }
+            HotShotEvent::DACRecv(certificate) => {
+                self.fallback.certified(certificate.get_view_number());
             }
             HotShotEvent::ViewChange(view) => {
                 if *self.cur_view >= *view {
@@ -233,6 +619,32 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     warn!("View changed by more than 1 going to view {:?}", view);
                 }
                 self.cur_view = view;
+                self.rebuilds
+                    .retain(|rebuilding, _| *rebuilding + 1 >= view);
+                self.announced.prune(view);
+                // ask again for the transactions that did not arrive
+                let rebuilding: Vec<_> = self.rebuilds.keys().copied().collect();
+                for rebuilding in rebuilding {
+                    self.advance_rebuild(rebuilding, true, &event_stream).await;
+                }
+
+                if let Some(failed_views) = self.fallback.enter_view(view) {
+                    let until_view = self.fallback.until().unwrap_or(view);
+                    warn!(
+                        "{failed_views} views ended without a DA certificate, disseminating DA \
+                         proposals to the whole quorum until view {}",
+                        *until_view
+                    );
+                    self.api
+                        .send_event(Event {
+                            view_number: view,
+                            event: EventType::DAFallback {
+                                failed_views,
+                                until_view,
+                            },
+                        })
+                        .await;
+                }
 
                 // Inject view info into network
                 let is_da = self
@@ -280,9 +692,11 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 let encoded_transactions_hash = Sha256::digest(&encoded_transactions);
 
                 // sign the encoded transactions as opposed to the VID commitment
-                let Ok(signature) =
-                    TYPES::SignatureKey::sign(&self.private_key, &encoded_transactions_hash)
-                else {
+                let Ok(signature) = signing::sign::<TYPES::SignatureKey>(
+                    &self.private_key,
+                    SigningDomain::DAProposal,
+                    &encoded_transactions_hash,
+                ) else {
                     error!("Failed to sign block payload!");
                     return None;
                 };
@@ -301,12 +715,112 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     _pd: PhantomData,
                 };
 
+                if self.fallback.is_active(view) {
+                    self.fallback.record_proposal(view);
+                    broadcast_event(
+                        HotShotEvent::DAFallbackProposalSend(
+                            message.clone(),
+                            self.public_key.clone(),
+                        ),
+                        &event_stream,
+                    )
+                    .await;
+                    // we vote on our own proposal too, as every other node
+                    broadcast_event(
+                        HotShotEvent::DAFallbackProposalRecv(message, self.public_key.clone()),
+                        &event_stream,
+                    )
+                    .await;
+                    return None;
+                }
+
+                if !self.unicast_payload && !self.compact_proposals {
+                    broadcast_event(
+                        HotShotEvent::DAProposalSend(message.clone(), self.public_key.clone()),
+                        &event_stream,
+                    )
+                    .await;
+                    return None;
+                }
+
+                let commitment =
+                    DAProposalCommitment::new(&message.data, self.quorum_membership.total_nodes());
+                let Ok(commitment_signature) = signing::sign::<TYPES::SignatureKey>(
+                    &self.private_key,
+                    SigningDomain::DAProposalCommitment,
+                    &commitment.signed_bytes(),
+                ) else {
+                    error!("Failed to sign DA proposal commitment!");
+                    return None;
+                };
+                let commitment = Proposal {
+                    data: commitment,
+                    signature: commitment_signature,
+                    _pd: PhantomData,
+                };
+                // proposals which cannot be compacted go to the committee members in full
+                let compact = if self.compact_proposals {
+                    self.compact_proposal(&message)
+                } else {
+                    None
+                };
+                let committee_proposal = match compact {
+                    Some(compact) => {
+                        HotShotEvent::CompactDAProposalSend(compact, self.public_key.clone())
+                    }
+                    None => HotShotEvent::DAProposalCommitteeSend(
+                        message.clone(),
+                        self.public_key.clone(),
+                    ),
+                };
+                broadcast_event(committee_proposal, &event_stream).await;
+                broadcast_event(
+                    HotShotEvent::DAProposalCommitmentSend(commitment, self.public_key.clone()),
+                    &event_stream,
+                )
+                .await;
+                // the proposal is not sent back to us, so handle it as if it had been
                 broadcast_event(
-                    HotShotEvent::DAProposalSend(message.clone(), self.public_key.clone()),
+                    HotShotEvent::DAProposalRecv(message, self.public_key.clone()),
                     &event_stream,
                 )
                 .await;
             }
+            HotShotEvent::DAProposalCommitmentRecv(proposal, sender) => {
+                // DA committee members receive the payload itself
+                if self.da_membership.has_stake(&self.public_key) {
+                    return None;
+                }
+                let view = proposal.data.get_view_number();
+                if self.cur_view != TYPES::Time::genesis() && view < self.cur_view - 1 {
+                    warn!("Throwing away DA proposal commitment that is more than one view older");
+                    return None;
+                }
+                if self.da_membership.get_leader(view) != sender
+                    || !signing::validate(
+                        &sender,
+                        &proposal.signature,
+                        SigningDomain::DAProposalCommitment,
+                        &proposal.data.signed_bytes(),
+                    )
+                {
+                    error!(
+                        "Could not verify the DA proposal commitment of view {}",
+                        *view
+                    );
+                    return None;
+                }
+                debug!("DA proposal commitment received for view {:?}", view);
+
+                // Ensure this view is in the view map for garbage collection, as DA committee
+                // members do when they receive the proposal itself
+                let mut consensus = self.consensus.write().await;
+                consensus.validated_state_map.entry(view).or_insert(View {
+                    view_inner: ViewInner::DA {
+                        payload_commitment: proposal.data.payload_commitment,
+                    },
+                });
+            }
 
             HotShotEvent::Timeout(view) => {
                 self.da_network
@@ -338,7 +852,14 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
         !matches!(
             event,
             HotShotEvent::DAProposalRecv(_, _)
+                | HotShotEvent::DAFallbackProposalRecv(_, _)
                 | HotShotEvent::DAVoteRecv(_)
+                | HotShotEvent::DACSend(_, _)
+                | HotShotEvent::DACRecv(_)
+                | HotShotEvent::DAProposalCommitmentRecv(_, _)
+                | HotShotEvent::CompactDAProposalRecv(_, _)
+                | HotShotEvent::TransactionsRecv(_)
+                | HotShotEvent::MempoolInventoryRecv(_, _)
                 | HotShotEvent::Shutdown
                 | HotShotEvent::TransactionsSequenced(_, _, _)
                 | HotShotEvent::Timeout(_)
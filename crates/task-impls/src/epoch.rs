@@ -0,0 +1,354 @@
+use crate::{
+    events::{HotShotEvent, HotShotTaskCompleted},
+    helpers::broadcast_event,
+    vote::{handle_vote, AccumulatorInfo, VoteCollectionTaskState},
+};
+use async_broadcast::Sender;
+use async_lock::RwLock;
+
+use hotshot_task::task::TaskState;
+use hotshot_types::{
+    consensus::Consensus,
+    epoch::{stake_table_commitment, Epoch, EpochParameters, EpochSchedule},
+    event::{Event, EventType},
+    qc_chain::StakeTable,
+    simple_certificate::EpochChangeCertificate,
+    simple_vote::{EpochChangeData, EpochChangeVote},
+    traits::{
+        consensus_api::ConsensusApi,
+        election::Membership,
+        network::ConnectedNetwork,
+        node_implementation::{NodeImplementation, NodeType},
+        signature_key::SignatureKey,
+    },
+    vote::{Certificate, HasViewNumber},
+};
+
+use std::{collections::BTreeSet, sync::Arc};
+use tracing::{debug, error, info, instrument, warn};
+
+/// Alias for Optional type for Vote Collectors
+type VoteCollectorOption<TYPES, VOTE, CERT> = Option<VoteCollectionTaskState<TYPES, VOTE, CERT>>;
+
+/// Tracks state of the epoch task, which certifies the stake table and parameters of every epoch
+/// at its boundary
+pub struct EpochTaskState<
+    TYPES: NodeType,
+    I: NodeImplementation<TYPES>,
+    A: ConsensusApi<TYPES, I> + 'static,
+> {
+    /// The state's api
+    pub api: A,
+    /// View number this view is executing in.
+    pub cur_view: TYPES::Time,
+
+    /// How views are divided into epochs
+    pub schedule: EpochSchedule,
+    /// The parameters every epoch runs with, before governance changes them
+    pub parameters: EpochParameters,
+    /// Reference to consensus, whose parameter schedule holds the changes of governance
+    pub consensus: Arc<RwLock<Consensus<TYPES>>>,
+
+    /// Membership for Quorum Certs/votes
+    pub quorum_membership: Arc<TYPES::Membership>,
+
+    /// Membership of the DA committee, resized as governance changes its size
+    pub da_membership: Arc<TYPES::Membership>,
+
+    /// Network for all nodes, which learns the stake table of every certified epoch
+    pub quorum_network: Arc<I::QuorumNetwork>,
+
+    /// The current vote collection task, if there is one.
+    pub vote_collector:
+        RwLock<VoteCollectorOption<TYPES, EpochChangeVote<TYPES>, EpochChangeCertificate<TYPES>>>,
+
+    /// The last epoch this node voted on
+    pub voted_epoch: Option<Epoch>,
+    /// The last epoch whose certificate this node handled
+    pub certified_epoch: Option<Epoch>,
+
+    /// This Nodes public key
+    pub public_key: TYPES::SignatureKey,
+
+    /// This Nodes private key
+    pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+
+    /// This state's ID
+    pub id: u64,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> + 'static>
+    EpochTaskState<TYPES, I, A>
+{
+    /// The stake table `epoch` runs with: the one the committee has in effect at its first
+    /// view, `None` if the committee does not know it
+    fn stake_table(&self, epoch: Epoch) -> Option<StakeTable<TYPES>> {
+        let first_view = self.schedule.first_view(epoch);
+        self.quorum_membership.get_stake_table_at(first_view)
+    }
+
+    /// What this node expects the certificate of `epoch` to commit to, `None` if it does not
+    /// know the stake table of the epoch
+    async fn expected_data(&self, epoch: Epoch) -> Option<EpochChangeData<TYPES>> {
+        let stake_table = self.stake_table(epoch)?;
+        let da_committee_size = self
+            .consensus
+            .read()
+            .await
+            .parameter_schedule
+            .parameters_at(epoch)
+            .da_committee_size;
+        Some(EpochChangeData {
+            epoch,
+            first_view: self.schedule.first_view(epoch),
+            stake_table_commitment: stake_table_commitment::<TYPES::SignatureKey>(&stake_table),
+            parameters: EpochParameters {
+                da_committee_size,
+                ..self.parameters
+            },
+        })
+    }
+
+    /// Resize the DA committee to the size the current view runs with, which governance may
+    /// have changed at the start of its epoch. Until governance changes it the committee keeps
+    /// the size it was created with.
+    async fn apply_da_committee_size(&self) {
+        let epoch = self.schedule.epoch_of(self.cur_view);
+        let consensus = self.consensus.read().await;
+        let schedule = &consensus.parameter_schedule;
+        let size = schedule.parameters_at(epoch).da_committee_size;
+        let initial = schedule.parameters_at(Epoch::new(0)).da_committee_size;
+        let previous = match (*epoch).checked_sub(1) {
+            Some(previous) => {
+                schedule
+                    .parameters_at(Epoch::new(previous))
+                    .da_committee_size
+            }
+            None => initial,
+        };
+        if size != initial || size != previous {
+            self.da_membership.resize_committee(size);
+        }
+    }
+
+    /// Vote on the epoch after the current one from the last view of the current one on. A
+    /// node which skips that view, e.g. through view sync, still votes on the epoch it
+    /// entered, until its certificate is seen.
+    async fn vote_on_next_epoch(&mut self, tx: &Sender<HotShotEvent<TYPES>>) {
+        let current = self.schedule.epoch_of(self.cur_view);
+        let epoch = if self.schedule.is_last_view(self.cur_view) {
+            current.next()
+        } else {
+            current
+        };
+        // the genesis epoch needs no certificate
+        if *epoch == 0
+            || self.voted_epoch.is_some_and(|voted| voted >= epoch)
+            || self
+                .certified_epoch
+                .is_some_and(|certified| certified >= epoch)
+        {
+            return;
+        }
+
+        let Some(data) = self.expected_data(epoch).await else {
+            error!(
+                "Not voting on epoch {}, whose stake table is unknown",
+                epoch
+            );
+            return;
+        };
+        self.voted_epoch = Some(epoch);
+        let Ok(vote) = EpochChangeVote::create_signed_vote(
+            data.clone(),
+            data.first_view,
+            &self.public_key,
+            &self.private_key,
+        ) else {
+            error!("Failed to sign EpochChangeVote!");
+            return;
+        };
+        debug!("Sending epoch change vote for epoch {}", epoch);
+        broadcast_event(HotShotEvent::EpochChangeVoteSend(vote), tx).await;
+    }
+
+    /// Validate the certificate of an epoch and, if it is new, persist the stake table it
+    /// certifies and notify the application layer
+    async fn handle_certificate(&mut self, certificate: EpochChangeCertificate<TYPES>) {
+        let epoch = certificate.data.epoch;
+        if self
+            .certified_epoch
+            .is_some_and(|certified| certified >= epoch)
+        {
+            return;
+        }
+        if !certificate.is_valid_cert(self.quorum_membership.as_ref()) {
+            warn!("Invalid epoch change certificate for epoch {}", epoch);
+            return;
+        }
+        let Some(stake_table) = self.stake_table(epoch) else {
+            error!(
+                "Ignoring the epoch change certificate of epoch {}, whose stake table is unknown",
+                epoch
+            );
+            return;
+        };
+        if Some(&certificate.data) != self.expected_data(epoch).await.as_ref()
+            || certificate.get_view_number() != certificate.data.first_view
+        {
+            error!(
+                "Epoch change certificate for epoch {} does not match our view of it: {:?}",
+                epoch, certificate.data
+            );
+            return;
+        }
+        self.certified_epoch = Some(epoch);
+        // the parameters governance set for the epoch take effect with its certificate
+        let schedule = {
+            let mut consensus = self.consensus.write().await;
+            consensus.parameter_schedule.certify(epoch);
+            consensus.parameter_schedule.clone()
+        };
+        if let Err(e) = self.api.store_parameter_schedule(schedule).await {
+            error!(
+                "Failed to persist the parameter schedule certified for epoch {}: {:?}",
+                epoch, e
+            );
+        }
+        self.apply_da_committee_size().await;
+
+        info!(
+            "Epoch {} starts at view {:?}",
+            epoch, certificate.data.first_view
+        );
+        // networks admitting only the peers of the stake table follow it into the epoch
+        let keys: BTreeSet<TYPES::SignatureKey> = stake_table
+            .iter()
+            .map(TYPES::SignatureKey::get_public_key)
+            .collect();
+        self.quorum_network.update_stake_table(keys).await;
+        if let Err(e) = self
+            .api
+            .store_stake_table(certificate.data.first_view, stake_table)
+            .await
+        {
+            error!(
+                "Failed to persist the stake table of epoch {}: {:?}",
+                epoch, e
+            );
+        }
+        self.api
+            .send_event(Event {
+                view_number: self.cur_view,
+                event: EventType::EpochChange { certificate },
+            })
+            .await;
+    }
+
+    /// main task event handler
+    #[instrument(skip_all, fields(id = self.id, view = *self.cur_view), name = "Epoch Task", level = "error")]
+    pub async fn handle(
+        &mut self,
+        event: HotShotEvent<TYPES>,
+        tx: Sender<HotShotEvent<TYPES>>,
+    ) -> Option<HotShotTaskCompleted> {
+        match event {
+            HotShotEvent::EpochChangeVoteRecv(ref vote) => {
+                debug!(
+                    "Epoch change vote recv, Main Task {:?}",
+                    vote.get_view_number()
+                );
+                // Check if we are the leader of the first view of the epoch.
+                let view = vote.get_view_number();
+                if self.quorum_membership.get_leader(view) != self.public_key {
+                    error!(
+                        "We are not the leader for view {}, the first of epoch {}",
+                        *view, vote.data.epoch
+                    );
+                    return None;
+                }
+                if Some(&vote.data) != self.expected_data(vote.data.epoch).await.as_ref() {
+                    warn!(
+                        "Epoch change vote does not match our view of epoch {}: {:?}",
+                        vote.data.epoch, vote.data
+                    );
+                    return None;
+                }
+                let mut collector = self.vote_collector.write().await;
+                let info = AccumulatorInfo {
+                    public_key: self.public_key.clone(),
+                    membership: self.quorum_membership.clone(),
+                    view,
+                    id: self.id,
+                };
+                handle_vote(&mut collector, vote, event.clone(), &info, &self.api, &tx).await;
+            }
+            HotShotEvent::EpochChangeCertificateFormed(certificate) => {
+                debug!(
+                    "Epoch change certificate formed for epoch {}",
+                    certificate.data.epoch
+                );
+                broadcast_event(
+                    HotShotEvent::EpochChangeCertificateSend(
+                        certificate.clone(),
+                        self.public_key.clone(),
+                    ),
+                    &tx,
+                )
+                .await;
+                self.handle_certificate(certificate).await;
+            }
+            HotShotEvent::EpochChangeCertificateRecv(certificate) => {
+                self.handle_certificate(certificate).await;
+            }
+            HotShotEvent::ViewChange(view) => {
+                if self.cur_view >= view {
+                    return None;
+                }
+                self.cur_view = view;
+                self.apply_da_committee_size().await;
+                self.vote_on_next_epoch(&tx).await;
+            }
+            HotShotEvent::Shutdown => {
+                error!("Shutting down because of shutdown signal!");
+                return Some(HotShotTaskCompleted);
+            }
+            _ => {
+                error!("unexpected event {:?}", event);
+            }
+        }
+        None
+    }
+}
+
+/// task state implementation for the epoch task
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> + 'static> TaskState
+    for EpochTaskState<TYPES, I, A>
+{
+    type Event = HotShotEvent<TYPES>;
+
+    type Output = HotShotTaskCompleted;
+
+    async fn handle_event(
+        event: Self::Event,
+        task: &mut hotshot_task::task::Task<Self>,
+    ) -> Option<Self::Output> {
+        let sender = task.clone_sender();
+        task.state_mut().handle(event, sender).await
+    }
+
+    fn should_shutdown(event: &Self::Event) -> bool {
+        matches!(event, HotShotEvent::Shutdown)
+    }
+
+    fn filter(&self, event: &Self::Event) -> bool {
+        !matches!(
+            event,
+            HotShotEvent::EpochChangeVoteRecv(_)
+                | HotShotEvent::EpochChangeCertificateFormed(_)
+                | HotShotEvent::EpochChangeCertificateRecv(_)
+                | HotShotEvent::Shutdown
+                | HotShotEvent::ViewChange(_)
+        )
+    }
+}
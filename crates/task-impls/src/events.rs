@@ -1,18 +1,28 @@
 use crate::view_sync::ViewSyncPhase;
 
+use std::sync::Arc;
+
+use bytes::Bytes;
+use commit::Commitment;
 use either::Either;
 use hotshot_types::{
-    data::{DAProposal, Leaf, QuorumProposal, UpgradeProposal, VidCommitment, VidDisperse},
+    anchor::AnchorAnnouncement,
+    data::{
+        CompactDAProposal, DAProposal, DAProposalCommitment, Leaf, QuorumProposal, UpgradeProposal,
+        VidCommitment, VidDisperse, VidSample,
+    },
+    inclusion_proof::TransactionsRoot,
     message::Proposal,
     simple_certificate::{
-        DACertificate, QuorumCertificate, TimeoutCertificate, UpgradeCertificate,
-        ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate2,
+        DACertificate, EpochChangeCertificate, QuorumCertificate, TimeoutCertificate,
+        UpgradeCertificate, ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate2,
+        ViewSyncPreCommitCertificate2,
     },
     simple_vote::{
-        DAVote, QuorumVote, TimeoutVote, UpgradeVote, ViewSyncCommitVote, ViewSyncFinalizeVote,
-        ViewSyncPreCommitVote,
+        DAVote, EpochChangeVote, QuorumVote, TimeoutVote, UpgradeVote, ViewSyncCommitVote,
+        ViewSyncFinalizeVote, ViewSyncPreCommitVote,
     },
-    traits::{node_implementation::NodeType, BlockPayload},
+    traits::{node_implementation::NodeType, signature_key::SignatureKey, BlockPayload},
 };
 
 /// Marker that the task completed
@@ -34,6 +44,18 @@ pub enum HotShotEvent<TYPES: NodeType> {
     TimeoutVoteSend(TimeoutVote<TYPES>),
     /// A DA proposal has been received from the network; handled by the DA task
     DAProposalRecv(Proposal<TYPES, DAProposal<TYPES>>, TYPES::SignatureKey),
+    /// The commitment of a DA proposal whose payload is unicast to the DA committee has been received from the network; handled by the DA task
+    DAProposalCommitmentRecv(
+        Proposal<TYPES, DAProposalCommitment<TYPES>>,
+        TYPES::SignatureKey,
+    ),
+    /// A DA proposal disseminated to the whole quorum has been received from the network; handled by the DA task
+    DAFallbackProposalRecv(Proposal<TYPES, DAProposal<TYPES>>, TYPES::SignatureKey),
+    /// A compact DA proposal has been received from the network; rebuilt and handled by the DA task
+    CompactDAProposalRecv(
+        Proposal<TYPES, CompactDAProposal<TYPES>>,
+        TYPES::SignatureKey,
+    ),
     /// A DA vote has been received by the network; handled by the DA task
     DAVoteRecv(DAVote<TYPES>),
     /// A Data Availability Certificate (DAC) has been recieved by the network; handled by the consensus task
@@ -44,8 +66,24 @@ pub enum HotShotEvent<TYPES: NodeType> {
     QuorumVoteSend(QuorumVote<TYPES>),
     /// Send a DA proposal to the DA committee; emitted by the DA leader (which is the same node as the leader of view v + 1) in the DA task
     DAProposalSend(Proposal<TYPES, DAProposal<TYPES>>, TYPES::SignatureKey),
+    /// Send a DA proposal to each DA committee member in a direct message; emitted by the DA leader in the DA task instead of `DAProposalSend` when the payload is unicast
+    DAProposalCommitteeSend(Proposal<TYPES, DAProposal<TYPES>>, TYPES::SignatureKey),
+    /// Broadcast the commitment of a DA proposal whose payload is unicast to the DA committee; emitted by the DA leader in the DA task
+    DAProposalCommitmentSend(
+        Proposal<TYPES, DAProposalCommitment<TYPES>>,
+        TYPES::SignatureKey,
+    ),
+    /// Send a compact DA proposal to each DA committee member in a direct message; emitted by the DA leader in the DA task instead of `DAProposalCommitteeSend` when DA proposals are compact
+    CompactDAProposalSend(
+        Proposal<TYPES, CompactDAProposal<TYPES>>,
+        TYPES::SignatureKey,
+    ),
     /// Send a DA vote to the DA leader; emitted by DA committee members in the DA task after seeing a valid DA proposal
     DAVoteSend(DAVote<TYPES>),
+    /// Broadcast a DA proposal to the whole quorum over the quorum network; emitted by the DA leader in the DA task instead of `DAProposalSend` once DA has fallen back
+    DAFallbackProposalSend(Proposal<TYPES, DAProposal<TYPES>>, TYPES::SignatureKey),
+    /// Send a DA vote to the DA leader over the quorum network; emitted by every node in the DA task after seeing a valid DA proposal disseminated to the whole quorum
+    DAFallbackVoteSend(DAVote<TYPES>),
     /// The next leader has collected enough votes to form a QC; emitted by the next leader in the consensus task; an internal event only
     QCFormed(Either<QuorumCertificate<TYPES>, TimeoutCertificate<TYPES>>),
     /// The DA leader has collected enough votes to form a DAC; emitted by the DA leader in the DA task; sent to the entire network via the networking task
@@ -87,19 +125,24 @@ pub enum HotShotEvent<TYPES: NodeType> {
     ViewSyncTrigger(TYPES::Time),
     /// A consensus view has timed out; emitted by a replica in the consensus task; received by the view sync task; internal event only
     Timeout(TYPES::Time),
+    /// No proposal for a view arrived in time; emitted by a replica in the consensus task; internal event only
+    ProposalTimeout(TYPES::Time),
+    /// The block header of the proposal of a view was applied to the state of its parent on a worker task, giving the new state, or `None` if it is invalid; emitted and handled by the consensus task in parallel state application; internal event only
+    StateApplied(TYPES::Time, Option<Arc<TYPES::ValidatedState>>),
     /// Receive transactions from the network
     TransactionsRecv(Vec<TYPES::Transaction>),
     /// Send transactions to the network
     TransactionSend(TYPES::Transaction, TYPES::SignatureKey),
-    /// Event to send block payload commitment and metadata from DA leader to the quorum; internal event only
+    /// Event to send block payload commitment, transactions root and metadata from DA leader to the quorum; internal event only
     SendPayloadCommitmentAndMetadata(
         VidCommitment,
+        TransactionsRoot,
         <TYPES::BlockPayload as BlockPayload>::Metadata,
         TYPES::Time,
     ),
     /// Event when the transactions task has sequenced transactions. Contains the encoded transactions, the metadata, and the view number
     TransactionsSequenced(
-        Vec<u8>,
+        Bytes,
         <TYPES::BlockPayload as BlockPayload>::Metadata,
         TYPES::Time,
     ),
@@ -125,4 +168,66 @@ pub enum HotShotEvent<TYPES: NodeType> {
     UpgradeVoteSend(UpgradeVote<TYPES>),
     /// Upgrade certificate has been sent to the network
     UpgradeCertificateFormed(UpgradeCertificate<TYPES>),
+    /// Send a vote on the next epoch to the leader of its first view; emitted by the epoch task
+    EpochChangeVoteSend(EpochChangeVote<TYPES>),
+    /// A vote on the next epoch has been received from the network; handled by the epoch task
+    EpochChangeVoteRecv(EpochChangeVote<TYPES>),
+    /// The votes on the next epoch have formed its certificate
+    EpochChangeCertificateFormed(EpochChangeCertificate<TYPES>),
+    /// Broadcast the certificate of the next epoch; emitted by the leader of its first view
+    EpochChangeCertificateSend(EpochChangeCertificate<TYPES>, TYPES::SignatureKey),
+    /// The certificate of the next epoch has been received from the network; handled by the
+    /// epoch task
+    EpochChangeCertificateRecv(EpochChangeCertificate<TYPES>),
+    /// A block payload with the given commitment and view is needed but not held locally; handled by the block fetch task
+    BlockFetchNeeded(VidCommitment, TYPES::Time),
+    /// A block payload has been fetched from a DA member over RPC; emitted and handled by the block fetch task
+    BlockFetchResponseRecv(VidCommitment, TYPES::Time, Bytes),
+    /// A fetched block payload has been checked against its commitment; emitted by the block fetch task
+    BlockFetched(VidCommitment, TYPES::Time, Bytes),
+    /// Announce the transactions of our mempool to every node; emitted by the transaction task. Contains the transaction commitments and our key
+    MempoolInventorySend(Vec<Commitment<TYPES::Transaction>>, TYPES::SignatureKey),
+    /// Another node announced the transactions of its mempool; handled by the transaction task
+    MempoolInventoryRecv(Vec<Commitment<TYPES::Transaction>>, TYPES::SignatureKey),
+    /// Ask a node for announced transactions we do not hold; emitted by the transaction task. Contains the transaction commitments, the announcer, and our key
+    MempoolRequestSend(
+        Vec<Commitment<TYPES::Transaction>>,
+        TYPES::SignatureKey,
+        TYPES::SignatureKey,
+    ),
+    /// Another node asked for transactions we announced; handled by the transaction task
+    MempoolRequestRecv(Vec<Commitment<TYPES::Transaction>>, TYPES::SignatureKey),
+    /// Send requested transactions to the node which asked for them; emitted by the transaction task. Contains the transactions, the requester, and our key
+    MempoolTransactionsSend(
+        Vec<TYPES::Transaction>,
+        TYPES::SignatureKey,
+        TYPES::SignatureKey,
+    ),
+    /// Ask a DA member for a VID share of a certified payload; emitted by the availability sampling task. Contains the payload commitment, the view, the node the share was dispersed to, the DA member, our key, and our signature of the request
+    AvailabilitySampleRequestSend(
+        VidCommitment,
+        TYPES::Time,
+        TYPES::SignatureKey,
+        TYPES::SignatureKey,
+        TYPES::SignatureKey,
+        <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ),
+    /// A VID share has been requested by a sampling node; handled by the availability sampling task. Contains the payload commitment, the view, the node the share was dispersed to, the requester, and its signature of the request
+    AvailabilitySampleRequestRecv(
+        VidCommitment,
+        TYPES::Time,
+        TYPES::SignatureKey,
+        TYPES::SignatureKey,
+        <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ),
+    /// Send a VID share to the node which sampled it; emitted by the availability sampling task. Contains the share, the requester, and our key
+    AvailabilitySampleResponseSend(VidSample<TYPES>, TYPES::SignatureKey, TYPES::SignatureKey),
+    /// A sampled VID share has been received from the network; handled by the availability sampling task. Contains the share and the DA member which sent it
+    AvailabilitySampleResponseRecv(VidSample<TYPES>, TYPES::SignatureKey),
+    /// Announce the latest anchor to every node; emitted by the consensus task when the anchor passes an announcement interval. Contains the anchor with its certificate and our key
+    AnchorAnnouncementSend(AnchorAnnouncement<TYPES>, TYPES::SignatureKey),
+    /// Another node announced its latest anchor; handled by the network task, which records it in the network once its certificate verifies. Contains the anchor with its certificate and the announcer
+    AnchorAnnouncementRecv(AnchorAnnouncement<TYPES>, TYPES::SignatureKey),
+    /// The stall timeout of the watchdog ran out for a view; handled by the watchdog task, which reports the node stalled if it is still in the view
+    StallCheck(TYPES::Time),
 }
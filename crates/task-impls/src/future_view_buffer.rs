@@ -0,0 +1,114 @@
+//! Buffering of consensus messages which arrive before the node reaches their view
+//!
+//! A slow node may receive the proposal or votes of a view before the proposal it builds on.
+//! Handling them right away makes a replica vote without the proposal's parent and a leader
+//! re-arm its vote collector, dropping the votes it accumulated so far. Instead they are held
+//! here, up to a fixed number of views ahead, until the node catches up.
+
+use std::collections::BTreeMap;
+
+use hotshot_types::traits::node_implementation::ConsensusTime;
+
+/// Messages held until the node reaches the view they are released in
+#[derive(Debug)]
+pub struct FutureViewBuffer<TIME: ConsensusTime, MSG> {
+    /// the buffered messages, by the view in which they are released
+    messages: BTreeMap<TIME, Vec<MSG>>,
+    /// number of buffered messages
+    len: usize,
+    /// maximum number of buffered messages
+    capacity: usize,
+    /// how many views ahead of the current view messages are buffered
+    horizon: u64,
+}
+
+/// What happened to a message offered to a [`FutureViewBuffer`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum BufferOutcome<MSG> {
+    /// the message is released right away, either because its view was reached or because it
+    /// is too far ahead to be worth waiting for
+    Ready(MSG),
+    /// the message was buffered
+    Buffered,
+    /// the message was buffered, and the message released furthest in the future evicted to
+    /// make room
+    Evicted(MSG),
+}
+
+impl<TIME: ConsensusTime, MSG> FutureViewBuffer<TIME, MSG> {
+    /// Create a buffer holding at most `capacity` messages, released at most `horizon` views
+    /// ahead of the current view
+    #[must_use]
+    pub fn new(capacity: usize, horizon: u64) -> Self {
+        Self {
+            messages: BTreeMap::new(),
+            len: 0,
+            capacity,
+            horizon,
+        }
+    }
+
+    /// Offer `message`, to be released once the node is in `release_view`, given the node is in
+    /// `cur_view`
+    pub fn insert(
+        &mut self,
+        cur_view: TIME,
+        release_view: TIME,
+        message: MSG,
+    ) -> BufferOutcome<MSG> {
+        if release_view <= cur_view
+            || *release_view > *cur_view + self.horizon
+            || self.capacity == 0
+        {
+            return BufferOutcome::Ready(message);
+        }
+        let evicted = if self.len >= self.capacity {
+            match self.messages.last_entry() {
+                // the new message is released no earlier than everything buffered
+                Some(entry) if *entry.key() <= release_view => {
+                    return BufferOutcome::Ready(message);
+                }
+                Some(mut entry) => {
+                    let evicted = entry.get_mut().pop();
+                    if entry.get().is_empty() {
+                        entry.remove();
+                    }
+                    self.len -= 1;
+                    evicted
+                }
+                None => None,
+            }
+        } else {
+            None
+        };
+        self.messages.entry(release_view).or_default().push(message);
+        self.len += 1;
+        match evicted {
+            Some(evicted) => BufferOutcome::Evicted(evicted),
+            None => BufferOutcome::Buffered,
+        }
+    }
+
+    /// Take the messages released once the node is in `cur_view`, in the order of their views
+    pub fn take_ready(&mut self, cur_view: TIME) -> Vec<MSG> {
+        let later = self.messages.split_off(&(cur_view + 1));
+        let ready: Vec<MSG> = std::mem::replace(&mut self.messages, later)
+            .into_values()
+            .flatten()
+            .collect();
+        self.len -= ready.len();
+        ready
+    }
+
+    /// Number of buffered messages
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no messages are buffered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
@@ -7,12 +7,30 @@ pub mod consensus;
 /// The task which implements the main parts of data availability.
 pub mod da;
 
+/// The task which samples VID shares from the DA committee to check payloads are available
+pub mod availability_sampling;
+
+/// The task which fetches block payloads from the DA committee on demand
+pub mod block_fetch;
+
 /// The task which implements all transaction handling
 pub mod transactions;
 
+/// The sharded pool of undecided transactions used by the transaction task
+pub mod mempool;
+
+/// Bookkeeping of the protocol syncing the mempools of the nodes
+pub mod mempool_sync;
+
+/// Bookkeeping of DA proposals carrying the commitments of transactions the committee holds
+pub mod compact_block;
+
 /// Defines the events passed between tasks
 pub mod events;
 
+/// The bus tasks publish lifecycle events on, for plugins
+pub mod bus;
+
 /// The task which implements the network.
 pub mod network;
 
@@ -28,8 +46,20 @@ pub mod vid;
 /// Generic task for collecting votes
 pub mod vote;
 
+/// Buffer of consensus messages which arrived ahead of the node's view
+pub mod future_view_buffer;
+
+/// Ordering of the block header applications the consensus task runs on worker tasks
+pub mod state_pipeline;
+
 /// Task for handling upgrades
 pub mod upgrade;
 
+/// Task for certifying the stake table and parameters of every epoch at its boundary
+pub mod epoch;
+
+/// Task for reporting the node stalled, with diagnostics, when its view stops advancing
+pub mod watchdog;
+
 /// Helper functions used by any task
 pub mod helpers;
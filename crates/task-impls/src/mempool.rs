@@ -0,0 +1,447 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use async_broadcast::{broadcast, InactiveReceiver, Receiver, Sender};
+use async_lock::RwLock;
+#[cfg(async_executor_impl = "async-std")]
+use async_std::task::spawn_blocking;
+use bincode::config::Options;
+use commit::{Commitment, Committable};
+use futures::future::join_all;
+use hotshot_constants::MAX_SYSTEM_LANE_TRANSACTIONS;
+use hotshot_types::{
+    consensus::ConsensusMetricsValue,
+    traits::{
+        block_contents::{Transaction, TransactionLane},
+        metrics::{Counter, Gauge, NoMetrics},
+        node_implementation::NodeType,
+        transaction_validator::{MaxSizeValidator, TransactionValidator},
+    },
+};
+use hotshot_utils::bincode::bincode_opts;
+#[cfg(async_executor_impl = "tokio")]
+use tokio::task::spawn_blocking;
+use tracing::{debug, warn};
+
+/// A type alias for `HashMap<Commitment<T>, T>`
+type CommitmentMap<T> = HashMap<Commitment<T>, T>;
+
+/// Batches smaller than this are validated inline rather than on the worker threads, since
+/// handing them off costs more than checking them.
+pub const MIN_PARALLEL_BATCH: usize = 64;
+
+/// A transaction which has passed validation at ingestion.
+pub struct ValidatedTransaction<TYPES: NodeType> {
+    /// Commitment of the transaction
+    pub commitment: Commitment<TYPES::Transaction>,
+    /// Size in bytes of the serialized transaction
+    pub size: u64,
+    /// The transaction itself
+    pub transaction: TYPES::Transaction,
+}
+
+/// One independently locked partition of the mempool
+struct MempoolShard<TYPES: NodeType> {
+    /// Transactions whose commitment maps to this shard
+    transactions: RwLock<CommitmentMap<TYPES::Transaction>>,
+    /// Number of transactions currently held by this shard
+    size: Box<dyn Gauge>,
+}
+
+/// The pool of undecided transactions, split into shards by commitment prefix so that
+/// ingestion and removal of unrelated transactions do not contend on a single lock. System
+/// transactions wait apart from the shards, in the order they were received.
+pub struct ShardedMempool<TYPES: NodeType> {
+    /// The shards of the mempool, holding the user lane
+    shards: Vec<MempoolShard<TYPES>>,
+    /// The system lane, oldest transaction first
+    system: RwLock<Vec<(Commitment<TYPES::Transaction>, TYPES::Transaction)>>,
+    /// The most transactions the system lane holds
+    system_capacity: usize,
+    /// Checks every transaction before it enters the mempool
+    validator: Arc<dyn TransactionValidator<TYPES>>,
+    /// Number of transactions the validator rejected
+    rejected: Box<dyn Counter>,
+    /// Wakes up anyone waiting for transactions whenever new ones are inserted
+    notify: Sender<()>,
+    /// Keeps `notify` open while nobody is subscribed
+    notify_receiver: InactiveReceiver<()>,
+}
+
+impl<TYPES: NodeType> ShardedMempool<TYPES> {
+    /// Create an empty mempool with one shard per gauge in
+    /// [`ConsensusMetricsValue::mempool_shard_transactions`].
+    #[must_use]
+    pub fn new(metrics: &ConsensusMetricsValue) -> Self {
+        let mut shards: Vec<_> = metrics
+            .mempool_shard_transactions
+            .iter()
+            .map(|size| MempoolShard {
+                transactions: RwLock::new(HashMap::new()),
+                size: size.clone(),
+            })
+            .collect();
+        if shards.is_empty() {
+            shards.push(MempoolShard {
+                transactions: RwLock::new(HashMap::new()),
+                size: Box::new(NoMetrics),
+            });
+        }
+        let (mut notify, notify_receiver) = broadcast(1);
+        notify.set_overflow(true);
+        notify.set_await_active(false);
+        Self {
+            shards,
+            system: RwLock::new(Vec::new()),
+            system_capacity: MAX_SYSTEM_LANE_TRANSACTIONS,
+            validator: Arc::new(MaxSizeValidator::default()),
+            rejected: metrics.transactions_rejected.clone(),
+            notify,
+            notify_receiver: notify_receiver.deactivate(),
+        }
+    }
+
+    /// Hold at most `capacity` transactions in the system lane, rather than
+    /// [`MAX_SYSTEM_LANE_TRANSACTIONS`]
+    #[must_use]
+    pub fn with_system_capacity(mut self, capacity: usize) -> Self {
+        self.system_capacity = capacity;
+        self
+    }
+
+    /// Check transactions with `validator`, rather than only limiting their size to the
+    /// default maximum
+    #[must_use]
+    pub fn with_validator(mut self, validator: Arc<dyn TransactionValidator<TYPES>>) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    /// Number of shards in this mempool
+    #[must_use]
+    pub fn num_shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The shard a transaction with the given commitment belongs to
+    fn shard_of(&self, commitment: &Commitment<TYPES::Transaction>) -> &MempoolShard<TYPES> {
+        let prefix = commitment.as_ref().first().copied().unwrap_or_default();
+        &self.shards[usize::from(prefix) % self.shards.len()]
+    }
+
+    /// Validate a batch of incoming transactions with the validator of the mempool, spreading
+    /// the work across one blocking worker per shard. Only the transactions which pass are
+    /// returned.
+    ///
+    /// # Panics
+    /// Propagates a panic from one of the validation workers.
+    pub async fn validate(
+        &self,
+        transactions: Vec<TYPES::Transaction>,
+    ) -> Vec<ValidatedTransaction<TYPES>> {
+        let received = transactions.len();
+        let validated = if received < MIN_PARALLEL_BATCH {
+            validate_batch::<TYPES>(self.validator.as_ref(), transactions)
+        } else {
+            let chunk_size = (received + self.shards.len() - 1) / self.shards.len();
+            let mut chunks = Vec::with_capacity(self.shards.len());
+            let mut rest = transactions;
+            while rest.len() > chunk_size {
+                let tail = rest.split_off(chunk_size);
+                chunks.push(rest);
+                rest = tail;
+            }
+            chunks.push(rest);
+
+            let results = join_all(chunks.into_iter().map(|chunk| {
+                let validator = Arc::clone(&self.validator);
+                spawn_blocking(move || validate_batch::<TYPES>(validator.as_ref(), chunk))
+            }))
+            .await;
+
+            #[cfg(async_executor_impl = "tokio")]
+            // Unwrap here will just propogate any panic from the spawned task, it's not a new place we can panic.
+            let results = results.into_iter().map(|result| result.unwrap());
+
+            results.into_iter().flatten().collect()
+        };
+        let rejected = received - validated.len();
+        if rejected > 0 {
+            self.rejected.add(rejected);
+        }
+        validated
+    }
+
+    /// Insert validated transactions into their shards.  Returns the transactions which were not
+    /// already present. System transactions are refused while the system lane is full.
+    pub async fn insert(
+        &self,
+        transactions: Vec<ValidatedTransaction<TYPES>>,
+    ) -> Vec<ValidatedTransaction<TYPES>> {
+        let mut inserted = Vec::with_capacity(transactions.len());
+        let mut refused = 0;
+        for txn in transactions {
+            if txn.transaction.lane() == TransactionLane::System {
+                let mut system = self.system.write().await;
+                if system
+                    .iter()
+                    .any(|(commitment, _)| *commitment == txn.commitment)
+                {
+                    continue;
+                }
+                if system.len() >= self.system_capacity {
+                    refused += 1;
+                    continue;
+                }
+                system.push((txn.commitment, txn.transaction.clone()));
+                inserted.push(txn);
+                continue;
+            }
+            let shard = self.shard_of(&txn.commitment);
+            let mut shard_txns = shard.transactions.write().await;
+            if shard_txns.contains_key(&txn.commitment) {
+                continue;
+            }
+            shard_txns.insert(txn.commitment, txn.transaction.clone());
+            shard.size.update(1);
+            inserted.push(txn);
+        }
+        if refused > 0 {
+            warn!(
+                "Refused {refused} system transactions, the system lane holds {} already",
+                self.system_capacity
+            );
+        }
+        if !inserted.is_empty() {
+            let _ = self.notify.try_broadcast(());
+        }
+        inserted
+    }
+
+    /// Remove every transaction whose commitment is in `commitments`, returning the removed
+    /// transactions.
+    pub async fn remove_all(
+        &self,
+        commitments: &HashSet<Commitment<TYPES::Transaction>>,
+    ) -> Vec<TYPES::Transaction> {
+        let mut removed = Vec::new();
+        self.system.write().await.retain(|(commitment, txn)| {
+            if commitments.contains(commitment) {
+                removed.push(txn.clone());
+                false
+            } else {
+                true
+            }
+        });
+        for shard in &self.shards {
+            let mut shard_txns = shard.transactions.write().await;
+            let before = removed.len();
+            shard_txns.retain(|commitment, txn| {
+                if commitments.contains(commitment) {
+                    removed.push(txn.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            shard
+                .size
+                .update(-i64::try_from(removed.len() - before).unwrap_or(i64::MAX));
+        }
+        removed
+    }
+
+    /// Remove every transaction which has expired by `view`, returning the removed transactions.
+    pub async fn remove_expired(&self, view: u64) -> Vec<TYPES::Transaction> {
+        let mut removed = Vec::new();
+        self.system.write().await.retain(|(_, txn)| {
+            if txn.view_window().has_expired(view) {
+                removed.push(txn.clone());
+                false
+            } else {
+                true
+            }
+        });
+        for shard in &self.shards {
+            let mut shard_txns = shard.transactions.write().await;
+            let before = removed.len();
+            shard_txns.retain(|_, txn| {
+                if txn.view_window().has_expired(view) {
+                    removed.push(txn.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            shard
+                .size
+                .update(-i64::try_from(removed.len() - before).unwrap_or(i64::MAX));
+        }
+        removed
+    }
+
+    /// Whether the mempool contains a transaction with this commitment
+    pub async fn contains(&self, commitment: &Commitment<TYPES::Transaction>) -> bool {
+        self.shard_of(commitment)
+            .transactions
+            .read()
+            .await
+            .contains_key(commitment)
+            || self
+                .system
+                .read()
+                .await
+                .iter()
+                .any(|(c, _)| c == commitment)
+    }
+
+    /// The commitments among `commitments` of transactions the mempool does not hold
+    pub async fn missing(
+        &self,
+        commitments: Vec<Commitment<TYPES::Transaction>>,
+    ) -> Vec<Commitment<TYPES::Transaction>> {
+        let mut missing = Vec::new();
+        for commitment in commitments {
+            if !self.contains(&commitment).await {
+                missing.push(commitment);
+            }
+        }
+        missing
+    }
+
+    /// The transactions of the mempool with a commitment in `commitments`
+    pub async fn get_all(
+        &self,
+        commitments: &[Commitment<TYPES::Transaction>],
+    ) -> Vec<TYPES::Transaction> {
+        let mut transactions = Vec::new();
+        let system = self.system.read().await;
+        for commitment in commitments {
+            if let Some(txn) = self
+                .shard_of(commitment)
+                .transactions
+                .read()
+                .await
+                .get(commitment)
+            {
+                transactions.push(txn.clone());
+            } else if let Some((_, txn)) = system.iter().find(|(c, _)| c == commitment) {
+                transactions.push(txn.clone());
+            }
+        }
+        transactions
+    }
+
+    /// The commitments of at most `limit` transactions of the mempool, for announcing them
+    pub async fn commitments(&self, limit: usize) -> Vec<Commitment<TYPES::Transaction>> {
+        let mut commitments: Vec<_> = self
+            .system
+            .read()
+            .await
+            .iter()
+            .take(limit)
+            .map(|(commitment, _)| *commitment)
+            .collect();
+        for shard in &self.shards {
+            let remaining = limit.saturating_sub(commitments.len());
+            if remaining == 0 {
+                break;
+            }
+            commitments.extend(
+                shard
+                    .transactions
+                    .read()
+                    .await
+                    .keys()
+                    .take(remaining)
+                    .copied(),
+            );
+        }
+        commitments
+    }
+
+    /// Total number of transactions across all shards and the system lane
+    pub async fn len(&self) -> usize {
+        let mut len = self.system.read().await.len();
+        for shard in &self.shards {
+            len += shard.transactions.read().await.len();
+        }
+        len
+    }
+
+    /// Whether every shard is empty
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Collect the transactions of the system lane, oldest first, for the block builder.
+    pub async fn system_transactions(&self) -> Vec<TYPES::Transaction> {
+        self.system
+            .read()
+            .await
+            .iter()
+            .map(|(_, txn)| txn.clone())
+            .collect()
+    }
+
+    /// The transactions of a block of `block_view` holding at most `capacity` transactions: the
+    /// oldest system transactions first, up to `reserved`, then user transactions filling the
+    /// rest. Transactions whose view window excludes `block_view` stay in the mempool.
+    pub async fn block_transactions(
+        &self,
+        block_view: u64,
+        reserved: usize,
+        capacity: usize,
+    ) -> Vec<TYPES::Transaction> {
+        let mut txns = self.system_transactions().await;
+        txns.retain(|txn| txn.view_window().includes(block_view));
+        txns.truncate(reserved.min(capacity));
+        let mut user_txns = self.transactions().await;
+        user_txns.retain(|txn| txn.view_window().includes(block_view));
+        user_txns.truncate(capacity - txns.len());
+        txns.extend(user_txns);
+        txns
+    }
+
+    /// Collect the transactions of every shard, for the block builder.
+    pub async fn transactions(&self) -> Vec<TYPES::Transaction> {
+        let mut transactions = Vec::new();
+        for shard in &self.shards {
+            transactions.extend(shard.transactions.read().await.values().cloned());
+        }
+        transactions
+    }
+
+    /// Subscribe to notifications of newly inserted transactions
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<()> {
+        self.notify_receiver.activate_cloned()
+    }
+}
+
+/// Check a batch of transactions with `validator`, computing the commitment and serialized size
+/// of each one which passes.
+fn validate_batch<TYPES: NodeType>(
+    validator: &dyn TransactionValidator<TYPES>,
+    transactions: Vec<TYPES::Transaction>,
+) -> Vec<ValidatedTransaction<TYPES>> {
+    transactions
+        .into_iter()
+        .filter_map(|transaction| {
+            let commitment = transaction.commit();
+            if let Err(e) = validator.validate(&transaction) {
+                debug!("Dropping transaction {commitment:?}: {e}");
+                return None;
+            }
+            Some(ValidatedTransaction {
+                commitment,
+                size: bincode_opts()
+                    .serialized_size(&transaction)
+                    .unwrap_or_default(),
+                transaction,
+            })
+        })
+        .collect()
+}
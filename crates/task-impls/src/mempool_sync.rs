@@ -0,0 +1,109 @@
+//! Bookkeeping of the mempool sync protocol
+//!
+//! Every few views each node broadcasts the commitments of the transactions in its mempool. A
+//! node hearing of transactions it does not hold asks the announcer for them, so that pending
+//! transactions outlive the node they were submitted to and reach whichever node leads next.
+//! This module decides when to announce and which announced transactions to ask for: those not
+//! already asked for recently, and not decided already, which would otherwise be proposed twice.
+
+use std::{collections::HashMap, hash::Hash};
+
+use hotshot_types::traits::node_implementation::ConsensusTime;
+
+/// Tracks what the mempool sync protocol announced, requested and saw decided
+#[derive(Debug)]
+pub struct MempoolSync<TIME: ConsensusTime, ID: Copy + Eq + Hash> {
+    /// the view of the last announcement
+    last_announcement: Option<TIME>,
+    /// the transactions requested, by the view they were requested in
+    requested: HashMap<ID, TIME>,
+    /// the transactions seen decided, by the view they were seen decided in
+    decided: HashMap<ID, TIME>,
+    /// number of views between announcements, and before a request may be repeated
+    interval: u64,
+    /// maximum number of transactions announced or requested in a single message
+    max_commitments: usize,
+    /// number of views decided transactions are remembered for
+    decided_retention: u64,
+}
+
+impl<TIME: ConsensusTime, ID: Copy + Eq + Hash> MempoolSync<TIME, ID> {
+    /// Create the bookkeeping of a node announcing its mempool every `interval` views, at most
+    /// `max_commitments` transactions per message, and remembering decided transactions for
+    /// `decided_retention` views
+    #[must_use]
+    pub fn new(interval: u64, max_commitments: usize, decided_retention: u64) -> Self {
+        Self {
+            last_announcement: None,
+            requested: HashMap::new(),
+            decided: HashMap::new(),
+            interval,
+            max_commitments,
+            decided_retention,
+        }
+    }
+
+    /// Maximum number of transactions announced or requested in a single message
+    #[must_use]
+    pub fn max_commitments(&self) -> usize {
+        self.max_commitments
+    }
+
+    /// Whether to announce the mempool now that the node is in `view`. If so, the announcement
+    /// is recorded and what is no longer worth remembering is forgotten.
+    pub fn should_announce(&mut self, view: TIME) -> bool {
+        if self
+            .last_announcement
+            .is_some_and(|last| *view < *last + self.interval)
+        {
+            return false;
+        }
+        self.last_announcement = Some(view);
+        let (interval, retention) = (self.interval, self.decided_retention);
+        self.requested
+            .retain(|_, requested| *requested + interval > *view);
+        self.decided
+            .retain(|_, decided| *decided + retention > *view);
+        true
+    }
+
+    /// The transactions to request among the announced transactions the node does not hold,
+    /// given it is in `view`. They are recorded as requested.
+    pub fn request_missing(
+        &mut self,
+        view: TIME,
+        missing: impl IntoIterator<Item = ID>,
+    ) -> Vec<ID> {
+        let mut to_request = Vec::new();
+        for id in missing {
+            if to_request.len() >= self.max_commitments {
+                break;
+            }
+            if self.decided.contains_key(&id) || self.requested.contains_key(&id) {
+                continue;
+            }
+            self.requested.insert(id, view);
+            to_request.push(id);
+        }
+        to_request
+    }
+
+    /// Whether transaction `id` was seen decided recently
+    #[must_use]
+    pub fn is_decided(&self, id: &ID) -> bool {
+        self.decided.contains_key(id)
+    }
+
+    /// Record that transaction `id` was received
+    pub fn received(&mut self, id: &ID) {
+        self.requested.remove(id);
+    }
+
+    /// Record that the transactions `ids` were decided, with the node in `view`
+    pub fn decided(&mut self, view: TIME, ids: impl IntoIterator<Item = ID>) {
+        for id in ids {
+            self.requested.remove(&id);
+            self.decided.insert(id, view);
+        }
+    }
+}
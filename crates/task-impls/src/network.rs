@@ -1,27 +1,44 @@
 use crate::{
+    bus::{BusEvent, EventBus},
     events::{HotShotEvent, HotShotTaskCompleted},
     helpers::broadcast_event,
 };
 use async_broadcast::Sender;
-use async_compatibility_layer::art::async_spawn;
+use async_compatibility_layer::art::{async_sleep, async_spawn};
 use either::Either::{self, Left, Right};
 use hotshot_constants::VERSION_0_1;
-use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
+use bincode::Options;
 use hotshot_task::task::{Task, TaskState};
 use hotshot_types::{
+    bandwidth::{Direction, BROADCAST_PEER, UNSTAKED_PEER},
+    consensus::ConsensusMetricsValue,
     message::{
-        CommitteeConsensusMessage, GeneralConsensusMessage, Message, MessageKind, SequencingMessage,
+        CommitteeConsensusMessage, DataMessage, GeneralConsensusMessage, Message, MessageKind,
+        MessagePurpose, SequencingMessage, VoteBatch,
     },
+    rpc::RpcEndpoint,
     traits::{
         election::Membership,
+        metrics::Counter,
         network::{ConnectedNetwork, TransmitType, ViewMessage},
         node_implementation::NodeType,
+        transaction_validator::TransactionValidator,
     },
     vote::{HasViewNumber, Vote},
 };
-use tracing::error;
+use hotshot_utils::bincode::bincode_opts;
 use tracing::instrument;
+use tracing::warn;
+use tracing::{debug, error};
 
 /// quorum filter
 pub fn quorum_filter<TYPES: NodeType>(event: &HotShotEvent<TYPES>) -> bool {
@@ -31,8 +48,33 @@ pub fn quorum_filter<TYPES: NodeType>(event: &HotShotEvent<TYPES>) -> bool {
             | HotShotEvent::QuorumVoteSend(_)
             | HotShotEvent::Shutdown
             | HotShotEvent::DACSend(_, _)
+            | HotShotEvent::DAProposalCommitmentSend(_, _)
+            | HotShotEvent::DAFallbackProposalSend(_, _)
+            | HotShotEvent::DAFallbackVoteSend(_)
             | HotShotEvent::ViewChange(_)
             | HotShotEvent::TimeoutVoteSend(_)
+            | HotShotEvent::EpochChangeVoteSend(_)
+            | HotShotEvent::EpochChangeCertificateSend(_, _)
+            | HotShotEvent::MempoolInventorySend(_, _)
+            | HotShotEvent::MempoolRequestSend(_, _, _)
+            | HotShotEvent::MempoolTransactionsSend(_, _, _)
+            | HotShotEvent::AvailabilitySampleRequestSend(_, _, _, _, _, _)
+            | HotShotEvent::AvailabilitySampleResponseSend(_, _, _)
+            | HotShotEvent::AnchorAnnouncementSend(_, _)
+            | HotShotEvent::AnchorAnnouncementRecv(_, _)
+    )
+}
+
+/// observer filter: an observer follows consensus and serves blocks and samples, but never
+/// proposes nor votes
+pub fn observer_filter<TYPES: NodeType>(event: &HotShotEvent<TYPES>) -> bool {
+    !matches!(
+        event,
+        HotShotEvent::Shutdown
+            | HotShotEvent::ViewChange(_)
+            | HotShotEvent::AvailabilitySampleRequestSend(_, _, _, _, _, _)
+            | HotShotEvent::AvailabilitySampleResponseSend(_, _, _)
+            | HotShotEvent::AnchorAnnouncementRecv(_, _)
     )
 }
 
@@ -41,6 +83,8 @@ pub fn committee_filter<TYPES: NodeType>(event: &HotShotEvent<TYPES>) -> bool {
     !matches!(
         event,
         HotShotEvent::DAProposalSend(_, _)
+            | HotShotEvent::DAProposalCommitteeSend(_, _)
+            | HotShotEvent::CompactDAProposalSend(_, _)
             | HotShotEvent::DAVoteSend(_)
             | HotShotEvent::Shutdown
             | HotShotEvent::ViewChange(_)
@@ -69,11 +113,129 @@ pub fn view_sync_filter<TYPES: NodeType>(event: &HotShotEvent<TYPES>) -> bool {
             | HotShotEvent::ViewChange(_)
     )
 }
+
+/// Recognizes consensus messages for views the node has left, so that they are dropped as soon as
+/// they are received rather than deep in the tasks. Clones share the view the node is in.
+#[derive(Clone, Debug)]
+pub struct StaleViewFilter {
+    /// the latest view the node entered
+    current_view: Arc<AtomicU64>,
+    /// the number of views before the current view whose messages are still handled
+    grace: u64,
+}
+
+impl StaleViewFilter {
+    /// Create a filter handling the messages of the `grace` views before the current view
+    #[must_use]
+    pub fn new(grace: u64) -> Self {
+        Self {
+            current_view: Arc::new(AtomicU64::new(0)),
+            grace,
+        }
+    }
+
+    /// Record that the node entered `view`; views never go back
+    pub fn enter_view(&self, view: u64) {
+        self.current_view.fetch_max(view, Ordering::Relaxed);
+    }
+
+    /// Whether messages for `view` are too old to handle
+    #[must_use]
+    pub fn is_stale(&self, view: u64) -> bool {
+        view.saturating_add(self.grace) < self.current_view.load(Ordering::Relaxed)
+    }
+}
+
+/// Holds the votes sent to each leader for a short delay, so that those cast meanwhile, e.g. by
+/// a node catching up, go in one message. Clones share the held votes.
+#[derive(Clone, Debug)]
+pub struct VoteBatcher<TYPES: NodeType> {
+    /// how long the first vote for a leader waits for others
+    delay: Duration,
+    /// the votes waiting, by leader
+    pending: Arc<Mutex<BTreeMap<TYPES::SignatureKey, VoteBatch<TYPES>>>>,
+}
+
+impl<TYPES: NodeType> VoteBatcher<TYPES> {
+    /// Create a batcher holding votes for `delay`
+    #[must_use]
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: Arc::default(),
+        }
+    }
+
+    /// Hold a vote for `leader`, added to its batch by `add`. Returns whether the vote starts a
+    /// new batch, which the caller sends once the delay has elapsed.
+    /// # Panics
+    /// If the lock on the held votes is poisoned
+    pub fn hold(
+        &self,
+        leader: TYPES::SignatureKey,
+        add: impl FnOnce(&mut VoteBatch<TYPES>),
+    ) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        let new_batch = !pending.contains_key(&leader);
+        add(pending.entry(leader).or_default());
+        new_batch
+    }
+
+    /// Take the votes held for `leader`
+    /// # Panics
+    /// If the lock on the held votes is poisoned
+    #[must_use]
+    pub fn take(&self, leader: &TYPES::SignatureKey) -> Option<VoteBatch<TYPES>> {
+        self.pending.lock().unwrap().remove(leader)
+    }
+}
+
+/// The counter of the stale consensus messages of `purpose` dropped
+fn stale_messages_dropped(
+    metrics: &ConsensusMetricsValue,
+    purpose: MessagePurpose,
+) -> &dyn Counter {
+    match purpose {
+        MessagePurpose::Vote => &*metrics.stale_votes_dropped,
+        MessagePurpose::ViewSyncVote | MessagePurpose::ViewSyncCertificate => {
+            &*metrics.stale_view_sync_messages_dropped
+        }
+        MessagePurpose::DAC => &*metrics.stale_certificates_dropped,
+        MessagePurpose::VidDisperse => &*metrics.stale_vid_disperses_dropped,
+        MessagePurpose::Upgrade => &*metrics.stale_upgrade_messages_dropped,
+        _ => &*metrics.stale_proposals_dropped,
+    }
+}
+
+/// The size of `message` on the wire, as the network implementations serialize it
+fn serialized_size<TYPES: NodeType>(message: &Message<TYPES>) -> usize {
+    bincode_opts()
+        .serialized_size(message)
+        .ok()
+        .and_then(|size| usize::try_from(size).ok())
+        .unwrap_or(0)
+}
+
 /// the network message task state
 #[derive(Clone)]
 pub struct NetworkMessageTaskState<TYPES: NodeType> {
     /// Sender to send internal events this task generates to other tasks
     pub event_stream: Sender<HotShotEvent<TYPES>>,
+    /// The chain id of our network; messages carrying another one are dropped
+    pub chain_id: u64,
+    /// Metrics to count dropped messages and received bytes in
+    pub metrics: Arc<ConsensusMetricsValue>,
+    /// Checks received transactions before they reach the mempool
+    pub transaction_validator: Arc<dyn TransactionValidator<TYPES>>,
+    /// Drops consensus messages for views the node has left
+    pub stale_view_filter: StaleViewFilter,
+    /// The bus lifecycle events are published on
+    pub event_bus: EventBus<TYPES>,
+    /// Receives the requests and responses of request/response calls, if this network carries
+    /// them
+    pub rpc: Option<Arc<dyn RpcEndpoint<TYPES::SignatureKey>>>,
+    /// The stake table, whose members the bytes received are recorded against
+    pub quorum_membership: Arc<TYPES::Membership>,
 }
 
 impl<TYPES: NodeType> TaskState for NetworkMessageTaskState<TYPES> {
@@ -98,11 +260,78 @@ impl<TYPES: NodeType> TaskState for NetworkMessageTaskState<TYPES> {
 }
 
 impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
+    /// Hand the votes of `batch` to the vote collection tasks one by one, as if each had come in
+    /// its own message, dropping those of views which have passed
+    async fn handle_vote_batch(&self, batch: VoteBatch<TYPES>) {
+        let is_stale = |view: TYPES::Time| {
+            let stale = self.stale_view_filter.is_stale(*view);
+            if stale {
+                self.metrics.stale_votes_dropped.add(1);
+            }
+            stale
+        };
+        let events: Vec<_> = batch
+            .quorum_votes
+            .into_iter()
+            .filter(|vote| !is_stale(vote.get_view_number()))
+            .map(HotShotEvent::QuorumVoteRecv)
+            .chain(
+                batch
+                    .timeout_votes
+                    .into_iter()
+                    .filter(|vote| !is_stale(vote.get_view_number()))
+                    .map(HotShotEvent::TimeoutVoteRecv),
+            )
+            .collect();
+        for event in events {
+            broadcast_event(event, &self.event_stream).await;
+        }
+    }
+
     /// Handle the message.
     pub async fn handle_messages(&mut self, messages: Vec<Message<TYPES>>) {
         // We will send only one event for a vector of transactions.
         let mut transactions = Vec::new();
         for message in messages {
+            // a node on the wrong network gets nothing else, so this is counted rather than warned
+            if message.chain_id != self.chain_id {
+                debug!(
+                    "Dropping message for chain {} from {:?}, we are on chain {}",
+                    message.chain_id, message.sender, self.chain_id
+                );
+                self.metrics.chain_id_mismatches.add(1);
+                continue;
+            }
+            // data messages are still useful after their view, to nodes catching up
+            if matches!(message.kind, MessageKind::Consensus(_))
+                && self
+                    .stale_view_filter
+                    .is_stale(*message.kind.get_view_number())
+            {
+                debug!(
+                    "Dropping message for view {:?} from {:?}, which has passed",
+                    message.kind.get_view_number(),
+                    message.sender
+                );
+                stale_messages_dropped(&self.metrics, message.kind.purpose()).add(1);
+                continue;
+            }
+            // senders are not authenticated here, so only stake table members are told apart
+            let peer = if self.quorum_membership.has_stake(&message.sender) {
+                message.sender.to_string()
+            } else {
+                UNSTAKED_PEER.to_string()
+            };
+            self.metrics.bandwidth.record(
+                &peer,
+                message.kind.purpose(),
+                Direction::Received,
+                serialized_size(&message),
+            );
+            self.event_bus.publish(BusEvent::MessageReceived {
+                purpose: message.kind.purpose(),
+                sender: message.sender.clone(),
+            });
             let sender = message.sender;
             match message.kind {
                 MessageKind::Consensus(consensus_message) => {
@@ -144,6 +373,16 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
                             GeneralConsensusMessage::UpgradeVote(message) => {
                                 HotShotEvent::UpgradeVoteRecv(message)
                             }
+                            GeneralConsensusMessage::EpochChangeVote(message) => {
+                                HotShotEvent::EpochChangeVoteRecv(message)
+                            }
+                            GeneralConsensusMessage::EpochChangeCertificate(message) => {
+                                HotShotEvent::EpochChangeCertificateRecv(message)
+                            }
+                            GeneralConsensusMessage::VoteBatch(batch) => {
+                                self.handle_vote_batch(batch).await;
+                                continue;
+                            }
                         },
                         Either::Right(committee_message) => match committee_message {
                             CommitteeConsensusMessage::DAProposal(proposal) => {
@@ -158,6 +397,15 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
                             CommitteeConsensusMessage::VidDisperseMsg(proposal) => {
                                 HotShotEvent::VidDisperseRecv(proposal, sender)
                             }
+                            CommitteeConsensusMessage::DAProposalCommitment(proposal) => {
+                                HotShotEvent::DAProposalCommitmentRecv(proposal, sender)
+                            }
+                            CommitteeConsensusMessage::DAFallbackProposal(proposal) => {
+                                HotShotEvent::DAFallbackProposalRecv(proposal, sender)
+                            }
+                            CommitteeConsensusMessage::CompactDAProposal(proposal) => {
+                                HotShotEvent::CompactDAProposalRecv(proposal, sender)
+                            }
                         },
                     };
                     // TODO (Keyao benchmarking) Update these event variants (similar to the
@@ -166,9 +414,75 @@ impl<TYPES: NodeType> NetworkMessageTaskState<TYPES> {
                     broadcast_event(event, &self.event_stream).await;
                 }
                 MessageKind::Data(message) => match message {
-                    hotshot_types::message::DataMessage::SubmitTransaction(transaction, _) => {
-                        transactions.push(transaction);
+                    DataMessage::SubmitTransaction(transaction, _) => {
+                        match self.transaction_validator.validate(&transaction) {
+                            Ok(()) => transactions.push(transaction),
+                            Err(e) => {
+                                debug!("Dropping transaction from {:?}: {e}", sender);
+                                self.metrics.transactions_rejected.add(1);
+                            }
+                        }
+                    }
+                    DataMessage::MempoolInventory(commitments, _) => {
+                        broadcast_event(
+                            HotShotEvent::MempoolInventoryRecv(commitments, sender),
+                            &self.event_stream,
+                        )
+                        .await;
+                    }
+                    DataMessage::RequestTransactions(commitments, _) => {
+                        broadcast_event(
+                            HotShotEvent::MempoolRequestRecv(commitments, sender),
+                            &self.event_stream,
+                        )
+                        .await;
+                    }
+                    DataMessage::TransactionsResponse(response, _) => {
+                        for transaction in response {
+                            match self.transaction_validator.validate(&transaction) {
+                                Ok(()) => transactions.push(transaction),
+                                Err(e) => {
+                                    debug!("Dropping transaction from {:?}: {e}", sender);
+                                    self.metrics.transactions_rejected.add(1);
+                                }
+                            }
+                        }
                     }
+                    DataMessage::RequestVidShare(payload_commitment, view, owner, signature) => {
+                        broadcast_event(
+                            HotShotEvent::AvailabilitySampleRequestRecv(
+                                payload_commitment,
+                                view,
+                                owner,
+                                sender,
+                                signature,
+                            ),
+                            &self.event_stream,
+                        )
+                        .await;
+                    }
+                    DataMessage::VidShareResponse(sample) => {
+                        broadcast_event(
+                            HotShotEvent::AvailabilitySampleResponseRecv(sample, sender),
+                            &self.event_stream,
+                        )
+                        .await;
+                    }
+                    DataMessage::AnchorAnnouncement(announcement) => {
+                        broadcast_event(
+                            HotShotEvent::AnchorAnnouncementRecv(announcement, sender),
+                            &self.event_stream,
+                        )
+                        .await;
+                    }
+                    DataMessage::RpcRequest(request) => match &self.rpc {
+                        Some(rpc) => rpc.handle_request(sender, request),
+                        None => debug!("Dropping RPC request from {:?}", sender),
+                    },
+                    DataMessage::RpcResponse(response) => match &self.rpc {
+                        Some(rpc) => rpc.handle_response(sender, response),
+                        None => debug!("Dropping RPC response from {:?}", sender),
+                    },
                 },
             };
         }
@@ -191,11 +505,21 @@ pub struct NetworkEventTaskState<
     pub channel: Arc<COMMCHANNEL>,
     /// view number
     pub view: TYPES::Time,
+    /// The chain id stamped on every message we send
+    pub chain_id: u64,
     /// membership for the channel
     pub membership: TYPES::Membership,
     // TODO ED Need to add exchange so we can get the recipient key and our own key?
     /// Filter which returns false for the events that this specific network task cares about
     pub filter: fn(&HotShotEvent<TYPES>) -> bool,
+    /// Told of the views the node enters, for the network message tasks
+    pub stale_view_filter: StaleViewFilter,
+    /// Metrics to count sent bytes in
+    pub metrics: Arc<ConsensusMetricsValue>,
+    /// If set, holds the votes to send so that those for the same leader go in one message
+    pub vote_batcher: Option<VoteBatcher<TYPES>>,
+    /// The bus lifecycle events are published on
+    pub event_bus: EventBus<TYPES>,
 }
 
 impl<TYPES: NodeType, COMMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>> TaskState
@@ -242,6 +566,9 @@ impl<TYPES: NodeType, COMMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::Signa
         event: HotShotEvent<TYPES>,
         membership: &TYPES::Membership,
     ) -> Option<HotShotTaskCompleted> {
+        if self.hold_vote(&event, membership) {
+            return None;
+        }
         let (sender, message_kind, transmit_type, recipient) = match event.clone() {
             HotShotEvent::QuorumProposalSend(proposal, sender) => (
                 sender,
@@ -277,6 +604,55 @@ impl<TYPES: NodeType, COMMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::Signa
                 TransmitType::Broadcast,
                 None,
             ),
+            HotShotEvent::DAProposalCommitteeSend(proposal, sender) => {
+                let view = proposal.data.get_view_number();
+                let kind = MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Right(
+                    CommitteeConsensusMessage::DAProposal(proposal),
+                )));
+                // the leader hands its own proposal to its DA task without the network
+                for member in membership
+                    .get_committee(view)
+                    .into_iter()
+                    .filter(|member| *member != sender)
+                {
+                    self.transmit(
+                        sender.clone(),
+                        kind.clone(),
+                        TransmitType::Direct,
+                        Some(member),
+                        membership,
+                    );
+                }
+                return None;
+            }
+            HotShotEvent::CompactDAProposalSend(proposal, sender) => {
+                let view = proposal.data.get_view_number();
+                let kind = MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Right(
+                    CommitteeConsensusMessage::CompactDAProposal(proposal),
+                )));
+                for member in membership
+                    .get_committee(view)
+                    .into_iter()
+                    .filter(|member| *member != sender)
+                {
+                    self.transmit(
+                        sender.clone(),
+                        kind.clone(),
+                        TransmitType::Direct,
+                        Some(member),
+                        membership,
+                    );
+                }
+                return None;
+            }
+            HotShotEvent::DAProposalCommitmentSend(proposal, sender) => (
+                sender,
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Right(
+                    CommitteeConsensusMessage::DAProposalCommitment(proposal),
+                ))),
+                TransmitType::Broadcast,
+                None,
+            ),
             HotShotEvent::DAVoteSend(vote) => (
                 vote.get_signing_key(),
                 MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Right(
@@ -285,6 +661,22 @@ impl<TYPES: NodeType, COMMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::Signa
                 TransmitType::Direct,
                 Some(membership.get_leader(vote.get_view_number())),
             ),
+            HotShotEvent::DAFallbackProposalSend(proposal, sender) => (
+                sender,
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Right(
+                    CommitteeConsensusMessage::DAFallbackProposal(proposal),
+                ))),
+                TransmitType::Broadcast,
+                None,
+            ),
+            HotShotEvent::DAFallbackVoteSend(vote) => (
+                vote.get_signing_key(),
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Right(
+                    CommitteeConsensusMessage::DAVote(vote.clone()),
+                ))),
+                TransmitType::Direct,
+                Some(membership.get_leader(vote.get_view_number())),
+            ),
             // ED NOTE: This needs to be broadcasted to all nodes, not just ones on the DA committee
             HotShotEvent::DACSend(certificate, sender) => (
                 sender,
@@ -351,8 +743,95 @@ impl<TYPES: NodeType, COMMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::Signa
                 TransmitType::Direct,
                 Some(membership.get_leader(vote.get_view_number() + 1)),
             ),
+            HotShotEvent::EpochChangeVoteSend(vote) => (
+                vote.get_signing_key(),
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Left(
+                    GeneralConsensusMessage::EpochChangeVote(vote.clone()),
+                ))),
+                TransmitType::Direct,
+                Some(membership.get_leader(vote.get_view_number())),
+            ),
+            HotShotEvent::EpochChangeCertificateSend(certificate, sender) => (
+                sender,
+                MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Left(
+                    GeneralConsensusMessage::EpochChangeCertificate(certificate),
+                ))),
+                TransmitType::Broadcast,
+                None,
+            ),
+            HotShotEvent::MempoolInventorySend(commitments, sender) => (
+                sender,
+                MessageKind::<TYPES>::from(DataMessage::MempoolInventory(commitments, self.view)),
+                TransmitType::Broadcast,
+                None,
+            ),
+            HotShotEvent::MempoolRequestSend(commitments, recipient, sender) => (
+                sender,
+                MessageKind::<TYPES>::from(DataMessage::RequestTransactions(
+                    commitments,
+                    self.view,
+                )),
+                TransmitType::Direct,
+                Some(recipient),
+            ),
+            HotShotEvent::MempoolTransactionsSend(transactions, recipient, sender) => (
+                sender,
+                MessageKind::<TYPES>::from(DataMessage::TransactionsResponse(
+                    transactions,
+                    self.view,
+                )),
+                TransmitType::Direct,
+                Some(recipient),
+            ),
+            HotShotEvent::AvailabilitySampleRequestSend(
+                payload_commitment,
+                view,
+                owner,
+                recipient,
+                sender,
+                signature,
+            ) => (
+                sender,
+                MessageKind::<TYPES>::from(DataMessage::RequestVidShare(
+                    payload_commitment,
+                    view,
+                    owner,
+                    signature,
+                )),
+                TransmitType::Direct,
+                Some(recipient),
+            ),
+            HotShotEvent::AvailabilitySampleResponseSend(sample, recipient, sender) => (
+                sender,
+                MessageKind::<TYPES>::from(DataMessage::VidShareResponse(sample)),
+                TransmitType::Direct,
+                Some(recipient),
+            ),
+            HotShotEvent::AnchorAnnouncementSend(announcement, sender) => (
+                sender,
+                MessageKind::<TYPES>::from(DataMessage::AnchorAnnouncement(announcement)),
+                TransmitType::Broadcast,
+                None,
+            ),
+            HotShotEvent::AnchorAnnouncementRecv(announcement, sender) => {
+                // anyone can send an announcement, but only a certified anchor is recorded
+                let Some(hint) = announcement.verify(membership) else {
+                    warn!(
+                        "Node {:?} announced an anchor without its certificate",
+                        sender
+                    );
+                    return None;
+                };
+                debug!(
+                    "Node {:?} announced the anchor of view {}",
+                    sender, hint.view
+                );
+                self.channel.record_anchor_hint(hint);
+                return None;
+            }
             HotShotEvent::ViewChange(view) => {
                 self.view = view;
+                self.stale_view_filter.enter_view(*view);
                 return None;
             }
             HotShotEvent::Shutdown => {
@@ -364,11 +843,41 @@ impl<TYPES: NodeType, COMMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::Signa
                 return None;
             }
         };
+        self.transmit(sender, message_kind, transmit_type, recipient, membership);
+
+        None
+    }
+
+    /// Send a message of `kind` from `sender`, directly to `recipient` or broadcast to the
+    /// committee of its view
+    fn transmit(
+        &self,
+        sender: TYPES::SignatureKey,
+        kind: MessageKind<TYPES>,
+        transmit_type: TransmitType,
+        recipient: Option<TYPES::SignatureKey>,
+        membership: &TYPES::Membership,
+    ) {
         let message = Message {
             version: VERSION_0_1,
+            chain_id: self.chain_id,
             sender,
-            kind: message_kind,
+            kind,
         };
+        let peer = match (&transmit_type, &recipient) {
+            (TransmitType::Direct, Some(recipient)) => recipient.to_string(),
+            _ => BROADCAST_PEER.to_string(),
+        };
+        self.metrics.bandwidth.record(
+            &peer,
+            message.kind.purpose(),
+            Direction::Sent,
+            serialized_size(&message),
+        );
+        self.event_bus.publish(BusEvent::MessageSent {
+            purpose: message.kind.purpose(),
+            broadcast: matches!(transmit_type, TransmitType::Broadcast),
+        });
         let view = message.kind.get_view_number();
         let committee = membership.get_committee(view);
         let net = self.channel.clone();
@@ -383,7 +892,62 @@ impl<TYPES: NodeType, COMMCHANNEL: ConnectedNetwork<Message<TYPES>, TYPES::Signa
                 Err(e) => error!("Failed to send message from network task: {:?}", e),
             }
         });
+    }
 
-        None
+    /// Hold the vote `event` sends, if votes are batched. Returns whether it was held.
+    fn hold_vote(&self, event: &HotShotEvent<TYPES>, membership: &TYPES::Membership) -> bool {
+        let Some(batcher) = self.vote_batcher.clone() else {
+            return false;
+        };
+        let (sender, view, vote) = match event {
+            HotShotEvent::QuorumVoteSend(vote) => (
+                vote.get_signing_key(),
+                vote.get_view_number(),
+                Left(vote.clone()),
+            ),
+            HotShotEvent::TimeoutVoteSend(vote) => (
+                vote.get_signing_key(),
+                vote.get_view_number(),
+                Right(vote.clone()),
+            ),
+            _ => return false,
+        };
+        let leader = membership.get_leader(view + 1);
+        let new_batch = batcher.hold(leader.clone(), |batch| match vote {
+            Left(vote) => batch.quorum_votes.push(vote),
+            Right(vote) => batch.timeout_votes.push(vote),
+        });
+        if !new_batch {
+            return true;
+        }
+
+        let net = self.channel.clone();
+        let chain_id = self.chain_id;
+        let metrics = self.metrics.clone();
+        async_spawn(async move {
+            async_sleep(batcher.delay).await;
+            let Some(general_message) = batcher.take(&leader).and_then(VoteBatch::into_message)
+            else {
+                return;
+            };
+            let message = Message {
+                version: VERSION_0_1,
+                chain_id,
+                sender,
+                kind: MessageKind::<TYPES>::from_consensus_message(SequencingMessage(Left(
+                    general_message,
+                ))),
+            };
+            metrics.bandwidth.record(
+                &leader.to_string(),
+                message.kind.purpose(),
+                Direction::Sent,
+                serialized_size(&message),
+            );
+            if let Err(e) = net.direct_message(message, leader).await {
+                error!("Failed to send votes from network task: {:?}", e);
+            }
+        });
+        true
     }
 }
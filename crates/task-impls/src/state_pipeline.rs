@@ -0,0 +1,174 @@
+//! Ordering of the block header applications run on worker tasks
+//!
+//! Validating a proposed block header and applying it to the state of its parent can take long
+//! for large blocks. Run on the consensus task, it holds up every other event until it is done,
+//! votes of the next view included. In parallel mode the consensus task starts the application
+//! on a worker task and goes on, and this pipeline hands the finished applications back in view
+//! order, so the new states are committed as they would be one at a time. A proposal building
+//! on a leaf whose application is still running is held until that one is committed, as its
+//! parent state is not known before. An application whose outcome never arrives, e.g. as its
+//! worker task panicked, is given up once a later view times out, so that it does not hold up
+//! every later one.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
+
+use hotshot_types::traits::node_implementation::ConsensusTime;
+
+/// Maximum number of messages held until the applications they build on are committed
+pub const MAX_HELD_MESSAGES: usize = 64;
+
+/// An application started on a worker task
+#[derive(Debug)]
+struct Application<ID, JOB, OUT> {
+    /// the leaf whose state is computed
+    leaf: ID,
+    /// what the application is for, handed back with its outcome
+    job: JOB,
+    /// the outcome, once the application finished
+    outcome: Option<OUT>,
+}
+
+/// A finished application, committed in view order
+#[derive(Debug, PartialEq, Eq)]
+pub struct CommittedApplication<TIME, JOB, OUT, MSG> {
+    /// the view of the leaf whose state was computed
+    pub view: TIME,
+    /// what the application was for
+    pub job: JOB,
+    /// the outcome of the application
+    pub outcome: OUT,
+    /// the messages held until the state of the leaf was known
+    pub released: Vec<MSG>,
+}
+
+/// The applications running on worker tasks, by the view of their leaf
+#[derive(Debug)]
+pub struct StatePipeline<TIME: ConsensusTime, ID: Copy + Eq + Hash, JOB, OUT, MSG> {
+    /// the applications started and not committed yet
+    applications: BTreeMap<TIME, Application<ID, JOB, OUT>>,
+    /// the messages held until the application of the leaf they build on is committed
+    held: HashMap<ID, Vec<MSG>>,
+}
+
+impl<TIME: ConsensusTime, ID: Copy + Eq + Hash, JOB, OUT, MSG> Default
+    for StatePipeline<TIME, ID, JOB, OUT, MSG>
+{
+    fn default() -> Self {
+        Self {
+            applications: BTreeMap::new(),
+            held: HashMap::new(),
+        }
+    }
+}
+
+impl<TIME: ConsensusTime, ID: Copy + Eq + Hash, JOB, OUT, MSG>
+    StatePipeline<TIME, ID, JOB, OUT, MSG>
+{
+    /// Record that the application computing the state of `leaf`, of `view`, started. Returns
+    /// whether it did: only one application runs for a view.
+    pub fn start(&mut self, view: TIME, leaf: ID, job: JOB) -> bool {
+        if self.applications.contains_key(&view) {
+            return false;
+        }
+        self.applications.insert(
+            view,
+            Application {
+                leaf,
+                job,
+                outcome: None,
+            },
+        );
+        true
+    }
+
+    /// Whether the application computing the state of `leaf` is running
+    #[must_use]
+    pub fn is_applying(&self, leaf: &ID) -> bool {
+        self.applications
+            .values()
+            .any(|application| application.leaf == *leaf)
+    }
+
+    /// Hold `message` until the application computing the state of `leaf`, which
+    /// [`is_applying`](Self::is_applying), is committed. Returns whether it is held: no more
+    /// than [`MAX_HELD_MESSAGES`] are.
+    pub fn hold(&mut self, leaf: ID, message: MSG) -> bool {
+        if self.held() >= MAX_HELD_MESSAGES {
+            return false;
+        }
+        self.held.entry(leaf).or_default().push(message);
+        true
+    }
+
+    /// Record that the application of `view` finished with `outcome`. Returns the applications
+    /// which can be committed now, in view order: the finished ones no running application
+    /// precedes.
+    pub fn finish(
+        &mut self,
+        view: TIME,
+        outcome: OUT,
+    ) -> Vec<CommittedApplication<TIME, JOB, OUT, MSG>> {
+        if let Some(application) = self.applications.get_mut(&view) {
+            application.outcome = Some(outcome);
+        }
+        self.commit_ready()
+    }
+
+    /// Give up on the applications of views before `view` still running, along with the
+    /// messages held for them. Returns the applications which can be committed now that they
+    /// no longer wait for those, in view order.
+    pub fn prune(&mut self, view: TIME) -> Vec<CommittedApplication<TIME, JOB, OUT, MSG>> {
+        let stale: Vec<TIME> = self
+            .applications
+            .range(..view)
+            .filter(|(_, application)| application.outcome.is_none())
+            .map(|(view, _)| *view)
+            .collect();
+        for view in stale {
+            if let Some(application) = self.applications.remove(&view) {
+                self.held.remove(&application.leaf);
+            }
+        }
+        self.commit_ready()
+    }
+
+    /// The number of messages held
+    #[must_use]
+    pub fn held(&self) -> usize {
+        self.held.values().map(Vec::len).sum()
+    }
+
+    /// Remove and return the finished applications no running application precedes
+    fn commit_ready(&mut self) -> Vec<CommittedApplication<TIME, JOB, OUT, MSG>> {
+        let mut committed = Vec::new();
+        while let Some(entry) = self.applications.first_entry() {
+            // every later application waits for the first one still running
+            if entry.get().outcome.is_none() {
+                break;
+            }
+            let (view, Application { leaf, job, outcome }) = entry.remove_entry();
+            committed.extend(outcome.map(|outcome| CommittedApplication {
+                view,
+                job,
+                outcome,
+                released: self.held.remove(&leaf).unwrap_or_default(),
+            }));
+        }
+        committed
+    }
+
+    /// The number of applications started and not committed yet
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.applications.len()
+    }
+
+    /// Whether every application started was committed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.applications.is_empty()
+    }
+}
@@ -1,22 +1,24 @@
 use crate::{
     events::{HotShotEvent, HotShotTaskCompleted},
     helpers::broadcast_event,
+    mempool::ShardedMempool,
+    mempool_sync::MempoolSync,
 };
 use async_broadcast::Sender;
-use async_compatibility_layer::{
-    art::async_timeout,
-    async_primitives::subscribable_rwlock::{ReadView, SubscribableRwLock},
-};
+use async_compatibility_layer::art::async_timeout;
 use async_lock::RwLock;
 use bincode::config::Options;
+use bytes::Bytes;
 use commit::{Commitment, Committable};
 
 use hotshot_task::task::{Task, TaskState};
 use hotshot_types::{
     consensus::Consensus,
+    data::Leaf,
     event::{Event, EventType},
+    receipt::{DropReason, TransactionIndex},
     traits::{
-        block_contents::BlockHeader,
+        block_contents::{BlockHeader, Transaction},
         consensus_api::ConsensusApi,
         election::Membership,
         node_implementation::{NodeImplementation, NodeType},
@@ -26,16 +28,9 @@ use hotshot_types::{
 };
 use hotshot_utils::bincode::bincode_opts;
 use snafu::Snafu;
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
-    time::Instant,
-};
+use std::{collections::HashSet, sync::Arc, time::Instant};
 use tracing::{debug, error, instrument, warn};
 
-/// A type alias for `HashMap<Commitment<T>, T>`
-type CommitmentMap<T> = HashMap<Commitment<T>, T>;
-
 #[derive(Snafu, Debug)]
 /// Error type for consensus tasks
 pub struct ConsensusTaskError {}
@@ -55,12 +50,19 @@ pub struct TransactionTaskState<
     /// Reference to consensus. Leader will require a read lock on this.
     pub consensus: Arc<RwLock<Consensus<TYPES>>>,
 
-    /// A list of undecided transactions
-    pub transactions: Arc<SubscribableRwLock<CommitmentMap<TYPES::Transaction>>>,
+    /// The undecided transactions, sharded by commitment, shared with the handle of the node
+    pub transactions: Arc<ShardedMempool<TYPES>>,
+
+    /// What became of the transactions the node saw decided or dropped, shared with the handle of
+    /// the node
+    pub tx_index: Arc<RwLock<TransactionIndex<TYPES>>>,
 
     /// A list of transactions we've seen decided, but didn't receive
     pub seen_transactions: HashSet<Commitment<TYPES::Transaction>>,
 
+    /// What the mempool sync protocol announced, requested and saw decided
+    pub mempool_sync: MempoolSync<TYPES::Time, Commitment<TYPES::Transaction>>,
+
     /// Network for all nodes
     pub network: Arc<I::QuorumNetwork>,
 
@@ -88,40 +90,54 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
     ) -> Option<HotShotTaskCompleted> {
         match event {
             HotShotEvent::TransactionsRecv(transactions) => {
+                let mut validated = self.transactions.validate(transactions.clone()).await;
+                // Transactions we've already seen decided, or which already expired, never enter
+                // the mempool.
+                let mut expired = Vec::new();
+                validated.retain(|txn| {
+                    if self.seen_transactions.remove(&txn.commitment)
+                        || self.mempool_sync.is_decided(&txn.commitment)
+                    {
+                        return false;
+                    }
+                    if txn.transaction.view_window().has_expired(*self.cur_view) {
+                        expired.push(txn.commitment);
+                        return false;
+                    }
+                    true
+                });
+                if !expired.is_empty() {
+                    self.tx_index.write().await.record_dropped(
+                        self.cur_view,
+                        DropReason::Expired,
+                        expired,
+                    );
+                }
+                for txn in &validated {
+                    self.mempool_sync.received(&txn.commitment);
+                }
+
                 futures::join! {
                     self.api
                         .send_event(Event {
                             view_number: self.cur_view,
                             event: EventType::Transactions {
-                                transactions: transactions.clone(),
+                                transactions,
                             },
                         }),
                     async {
                         let consensus = self.consensus.read().await;
-                        self.transactions
-                            .modify(|txns| {
-                                for transaction in transactions {
-                                    let size =
-                                        bincode_opts().serialized_size(&transaction).unwrap_or(0);
-
-                                    // If we didn't already know about this transaction, update our mempool metrics.
-                                    if !self.seen_transactions.remove(&transaction.commit())
-                                        && txns.insert(transaction.commit(), transaction).is_none()
-                                    {
-                                        consensus.metrics.outstanding_transactions.update(1);
-                                        consensus
-                                            .metrics
-                                            .outstanding_transactions_memory_size
-                                            .update(i64::try_from(size).unwrap_or_else(|e| {
-                                                warn!(
-                                                    "Conversion failed: {e}. Using the max value."
-                                                );
-                                                i64::MAX
-                                            }));
-                                    }
-                                }
-                            })
-                            .await;
+                        // Only transactions we didn't already know about update our mempool metrics.
+                        for txn in self.transactions.insert(validated).await {
+                            consensus.metrics.outstanding_transactions.update(1);
+                            consensus
+                                .metrics
+                                .outstanding_transactions_memory_size
+                                .update(i64::try_from(txn.size).unwrap_or_else(|e| {
+                                    warn!("Conversion failed: {e}. Using the max value.");
+                                    i64::MAX
+                                }));
+                        }
                     }
                 };
 
@@ -129,43 +145,37 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
             }
             HotShotEvent::LeafDecided(leaf_chain) => {
                 let mut included_txns = HashSet::new();
-                let mut included_txn_size = 0;
-                let mut included_txn_count = 0;
-                for leaf in leaf_chain {
+                // the index learns of the decided transactions before the mempool drops them, so
+                // that they never look dropped to the handle
+                let mut tx_index = self.tx_index.write().await;
+                for leaf in &leaf_chain {
                     if let Some(ref payload) = leaf.block_payload {
-                        for txn in
-                            payload.transaction_commitments(leaf.get_block_header().metadata())
-                        {
-                            included_txns.insert(txn);
-                        }
+                        let commitments =
+                            payload.transaction_commitments(leaf.get_block_header().metadata());
+                        tx_index
+                            .record_decided(leaf.get_view_number(), commitments.iter().copied());
+                        included_txns.extend(commitments);
                     }
                 }
+                if let Some(newest) = leaf_chain.iter().map(Leaf::get_view_number).max() {
+                    tx_index.prune(newest);
+                }
+                self.mempool_sync
+                    .decided(self.cur_view, included_txns.iter().copied());
                 let consensus = self.consensus.read().await;
-                let txns = self.transactions.cloned().await;
 
-                let _ = included_txns.iter().map(|hash| {
-                    if !txns.contains_key(hash) {
+                for hash in &included_txns {
+                    if !self.transactions.contains(hash).await {
                         self.seen_transactions.insert(*hash);
                     }
-                });
-                drop(txns);
-                self.transactions
-                    .modify(|txns| {
-                        *txns = txns
-                            .drain()
-                            .filter(|(txn_hash, txn)| {
-                                if included_txns.contains(txn_hash) {
-                                    included_txn_count += 1;
-                                    included_txn_size +=
-                                        bincode_opts().serialized_size(txn).unwrap_or_default();
-                                    false
-                                } else {
-                                    true
-                                }
-                            })
-                            .collect();
-                    })
-                    .await;
+                }
+                let removed = self.transactions.remove_all(&included_txns).await;
+                drop(tx_index);
+                let included_txn_count = i64::try_from(removed.len()).unwrap_or(i64::MAX);
+                let included_txn_size: u64 = removed
+                    .iter()
+                    .map(|txn| bincode_opts().serialized_size(txn).unwrap_or_default())
+                    .sum();
 
                 consensus
                     .metrics
@@ -189,15 +199,31 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     make_block = self.membership.get_leader(view) == self.public_key;
                 }
                 self.cur_view = view;
+                self.drop_expired().await;
+
+                if self.mempool_sync.should_announce(view) {
+                    let inventory = self
+                        .transactions
+                        .commitments(self.mempool_sync.max_commitments())
+                        .await;
+                    if !inventory.is_empty() {
+                        broadcast_event(
+                            HotShotEvent::MempoolInventorySend(inventory, self.public_key.clone()),
+                            &event_stream,
+                        )
+                        .await;
+                    }
+                }
 
                 // return if we aren't the next leader or we skipped last view and aren't the current leader.
                 if !make_block && self.membership.get_leader(self.cur_view + 1) != self.public_key {
                     debug!("Not next leader for view {:?}", self.cur_view);
                     return None;
                 }
+                let block_view = if make_block { view } else { view + 1 };
                 // TODO (Keyao) Determine whether to allow empty blocks.
                 // <https://github.com/EspressoSystems/HotShot/issues/1822>
-                let txns = self.wait_for_transactions().await?;
+                let txns = self.wait_for_transactions(block_view).await?;
                 let (payload, metadata) =
                     match <TYPES::BlockPayload as BlockPayload>::from_transactions(txns) {
                         Ok((payload, metadata)) => (payload, metadata),
@@ -209,7 +235,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
 
                 // encode the transactions
                 let encoded_transactions = match payload.encode() {
-                    Ok(encoded) => encoded.into_iter().collect::<Vec<u8>>(),
+                    Ok(encoded) => encoded.into_iter().collect::<Bytes>(),
                     Err(e) => {
                         error!("Failed to encode the block payload: {:?}.", e);
                         return None;
@@ -217,7 +243,6 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 };
 
                 // send the sequenced transactions to VID and DA tasks
-                let block_view = if make_block { view } else { view + 1 };
                 broadcast_event(
                     HotShotEvent::TransactionsSequenced(encoded_transactions, metadata, block_view),
                     &event_stream,
@@ -226,6 +251,63 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
 
                 return None;
             }
+            HotShotEvent::MempoolInventoryRecv(inventory, announcer) => {
+                if announcer == self.public_key {
+                    return None;
+                }
+                let missing = self.transactions.missing(inventory).await;
+                let to_request = self.mempool_sync.request_missing(self.cur_view, missing);
+                if to_request.is_empty() {
+                    return None;
+                }
+                debug!(
+                    "Requesting {} announced transactions from {:?}",
+                    to_request.len(),
+                    announcer
+                );
+                self.api
+                    .metrics()
+                    .mempool_sync_transactions_requested
+                    .add(to_request.len());
+                broadcast_event(
+                    HotShotEvent::MempoolRequestSend(
+                        to_request,
+                        announcer,
+                        self.public_key.clone(),
+                    ),
+                    &event_stream,
+                )
+                .await;
+            }
+            HotShotEvent::MempoolRequestRecv(mut commitments, requester) => {
+                // DA committee members rebuilding a compact proposal ask for up to a whole
+                // block at once, which is answered over as many messages as it takes
+                let max_transactions = self
+                    .consensus
+                    .read()
+                    .await
+                    .parameter_schedule
+                    .parameters_at_view(self.cur_view)
+                    .max_transactions
+                    .get();
+                let max_commitments = self.mempool_sync.max_commitments();
+                commitments.truncate(max_transactions.max(max_commitments));
+                for chunk in commitments.chunks(max_commitments.max(1)) {
+                    let transactions = self.transactions.get_all(chunk).await;
+                    if transactions.is_empty() {
+                        continue;
+                    }
+                    broadcast_event(
+                        HotShotEvent::MempoolTransactionsSend(
+                            transactions,
+                            requester.clone(),
+                            self.public_key.clone(),
+                        ),
+                        &event_stream,
+                    )
+                    .await;
+                }
+            }
             HotShotEvent::Shutdown => {
                 return Some(HotShotTaskCompleted);
             }
@@ -234,8 +316,43 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
         None
     }
 
+    /// Drop the transactions of the mempool which expired by the current view
+    async fn drop_expired(&self) {
+        let mut tx_index = self.tx_index.write().await;
+        let expired = self.transactions.remove_expired(*self.cur_view).await;
+        if expired.is_empty() {
+            return;
+        }
+        tx_index.record_dropped(
+            self.cur_view,
+            DropReason::Expired,
+            expired.iter().map(Committable::commit),
+        );
+        drop(tx_index);
+        debug!("Dropping {} expired transactions", expired.len());
+        let expired_size: u64 = expired
+            .iter()
+            .map(|txn| bincode_opts().serialized_size(txn).unwrap_or_default())
+            .sum();
+        let consensus = self.consensus.read().await;
+        consensus.metrics.transactions_expired.add(expired.len());
+        consensus
+            .metrics
+            .outstanding_transactions
+            .update(-i64::try_from(expired.len()).unwrap_or(i64::MAX));
+        consensus
+            .metrics
+            .outstanding_transactions_memory_size
+            .update(-(i64::try_from(expired_size).unwrap_or(i64::MAX)));
+    }
+
+    /// Wait for enough transactions for a block, then collect those the block of `block_view` may
+    /// include, system transactions first
     #[instrument(skip_all, fields(id = self.id, view = *self.cur_view), name = "Transaction Handling Task", level = "error")]
-    async fn wait_for_transactions(&self) -> Option<Vec<TYPES::Transaction>> {
+    async fn wait_for_transactions(
+        &self,
+        block_view: TYPES::Time,
+    ) -> Option<Vec<TYPES::Transaction>> {
         let task_start_time = Instant::now();
 
         // TODO (Keyao) Investigate the use of transaction hash
@@ -246,21 +363,20 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
         //     None => HashSet::new(),
         // };
 
-        let receiver = self.transactions.subscribe().await;
+        let mut receiver = self.transactions.subscribe();
 
         loop {
-            let all_txns = self.transactions.cloned().await;
-            debug!("Size of transactions: {}", all_txns.len());
+            let num_txns = self.transactions.len().await;
+            debug!("Size of transactions: {}", num_txns);
             // TODO (Keyao) Investigate the use of transaction hash
             // <https://github.com/EspressoSystems/HotShot/issues/1811>
             // let unclaimed_txns: Vec<_> = all_txns
             //     .iter()
             //     .filter(|(txn_hash, _txn)| !previous_used_txns.contains(txn_hash))
             //     .collect();
-            let unclaimed_txns = all_txns;
 
             let time_past = task_start_time.elapsed();
-            if unclaimed_txns.len() < self.api.min_transactions()
+            if num_txns < self.api.min_transactions()
                 && (time_past < self.api.propose_max_round_time())
             {
                 let duration = self.api.propose_max_round_time() - time_past;
@@ -274,7 +390,7 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     }
                     Ok(Err(e)) => {
                         // Something unprecedented is wrong, and `transactions` has been dropped
-                        error!("Channel receiver error for SubscribableRwLock {:?}", e);
+                        error!("Channel receiver error for the mempool {:?}", e);
                         return None;
                     }
                     Ok(Ok(_)) => continue,
@@ -282,10 +398,25 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
             }
             break;
         }
-        let all_txns = self.transactions.cloned().await;
         // TODO (Keyao) Investigate the use of transaction hash
         // <https://github.com/EspressoSystems/HotShot/issues/1811>
-        let txns: Vec<TYPES::Transaction> = all_txns.values().cloned().collect();
+        // The capacity is the one governance set for the view, if it changed it.
+        let capacity = self
+            .consensus
+            .read()
+            .await
+            .parameter_schedule
+            .parameters_at_view(block_view)
+            .max_transactions
+            .get();
+        let txns = self
+            .transactions
+            .block_transactions(
+                *block_view,
+                self.api.system_transactions_per_block(),
+                capacity,
+            )
+            .await;
         // let txns: Vec<TYPES::Transaction> = all_txns
         //     .iter()
         //     .filter_map(|(txn_hash, txn)| {
@@ -313,6 +444,8 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
             event,
             HotShotEvent::TransactionsRecv(_)
                 | HotShotEvent::LeafDecided(_)
+                | HotShotEvent::MempoolInventoryRecv(_, _)
+                | HotShotEvent::MempoolRequestRecv(_, _)
                 | HotShotEvent::Shutdown
                 | HotShotEvent::ViewChange(_)
         )
@@ -1,7 +1,7 @@
 use crate::{
     events::{HotShotEvent, HotShotTaskCompleted},
     helpers::broadcast_event,
-    vote::{create_vote_accumulator, AccumulatorInfo, VoteCollectionTaskState},
+    vote::{handle_vote, AccumulatorInfo, VoteCollectionTaskState},
 };
 use async_broadcast::Sender;
 use async_lock::RwLock;
@@ -20,7 +20,6 @@ use hotshot_types::{
     vote::HasViewNumber,
 };
 
-use crate::vote::HandleVoteEvent;
 use snafu::Snafu;
 use std::sync::Arc;
 use tracing::{debug, error, instrument, warn};
@@ -156,35 +155,13 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     return None;
                 }
                 let mut collector = self.vote_collector.write().await;
-
-                if collector.is_none() || vote.get_view_number() > collector.as_ref().unwrap().view
-                {
-                    debug!("Starting vote handle for view {:?}", vote.get_view_number());
-                    let info = AccumulatorInfo {
-                        public_key: self.public_key.clone(),
-                        membership: self.quorum_membership.clone(),
-                        view: vote.get_view_number(),
-                        id: self.id,
-                    };
-                    *collector = create_vote_accumulator::<
-                        TYPES,
-                        UpgradeVote<TYPES>,
-                        UpgradeCertificate<TYPES>,
-                    >(&info, vote.clone(), event, &tx)
-                    .await;
-                } else {
-                    let result = collector
-                        .as_mut()
-                        .unwrap()
-                        .handle_event(event.clone(), &tx)
-                        .await;
-
-                    if result == Some(HotShotTaskCompleted) {
-                        *collector = None;
-                        // The protocol has finished
-                        return None;
-                    }
-                }
+                let info = AccumulatorInfo {
+                    public_key: self.public_key.clone(),
+                    membership: self.quorum_membership.clone(),
+                    view: vote.get_view_number(),
+                    id: self.id,
+                };
+                handle_vote(&mut collector, vote, event.clone(), &info, &self.api, &tx).await;
             }
             HotShotEvent::ViewChange(view) => {
                 if *self.cur_view >= *view {
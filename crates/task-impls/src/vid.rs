@@ -11,11 +11,13 @@ use hotshot_types::{
     consensus::Consensus,
     data::VidDisperse,
     message::Proposal,
+    signing::{self, SigningDomain},
     traits::{
         consensus_api::ConsensusApi,
         election::Membership,
         node_implementation::{NodeImplementation, NodeType},
         signature_key::SignatureKey,
+        BlockPayload,
     },
 };
 use hotshot_types::{
@@ -85,20 +87,37 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 // issue: https://github.com/EspressoSystems/HotShot/issues/2152
                 let chunk_size = 1 << num_quorum_committee.ilog2();
 
+                // the header of the block commits to the root of its transactions
+                let transactions_root = TYPES::BlockPayload::from_bytes(
+                    encoded_transactions.iter().copied(),
+                    &metadata,
+                )
+                .transactions_root(&metadata);
+
                 // calculate vid shares
+                let payload = encoded_transactions.clone();
                 let vid_disperse = spawn_blocking(move || {
                     let vid = VidScheme::new(chunk_size, num_quorum_committee, &srs).unwrap();
-                    vid.disperse(encoded_transactions.clone()).unwrap()
+                    vid.disperse(encoded_transactions).unwrap()
                 })
                 .await;
 
                 #[cfg(async_executor_impl = "tokio")]
                 // Unwrap here will just propogate any panic from the spawned task, it's not a new place we can panic.
                 let vid_disperse = vid_disperse.unwrap();
+
+                // hold the payload we disperse under its commitment, so the DA task and the
+                // storage share it rather than keeping copies
+                self.consensus.write().await.save_payload(
+                    view_number,
+                    vid_disperse.commit,
+                    payload,
+                );
                 // send the commitment and metadata to consensus for block building
                 broadcast_event(
                     HotShotEvent::SendPayloadCommitmentAndMetadata(
                         vid_disperse.commit,
+                        transactions_root,
                         metadata,
                         view_number,
                     ),
@@ -118,8 +137,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
             }
 
             HotShotEvent::BlockReady(vid_disperse, view_number) => {
-                let Ok(signature) = TYPES::SignatureKey::sign(
+                let Ok(signature) = signing::sign::<TYPES::SignatureKey>(
                     &self.private_key,
+                    SigningDomain::VidDisperse,
                     vid_disperse.payload_commitment.as_ref().as_ref(),
                 ) else {
                     error!("VID: failed to sign dispersal payload");
@@ -1,11 +1,15 @@
 #![allow(clippy::module_name_repetitions)]
 use crate::{
+    bus::{BusEvent, EventBus},
     events::{HotShotEvent, HotShotTaskCompleted},
     helpers::{broadcast_event, cancel_task},
-    vote::{create_vote_accumulator, AccumulatorInfo, HandleVoteEvent, VoteCollectionTaskState},
+    vote::{
+        create_vote_accumulator, report_vote_progress, AccumulatorInfo, HandleVoteEvent,
+        VoteCollectionTaskState,
+    },
 };
 use async_broadcast::Sender;
-use async_compatibility_layer::art::{async_sleep, async_spawn};
+use async_compatibility_layer::art::async_spawn;
 use async_lock::RwLock;
 use hotshot_types::{
     simple_certificate::{
@@ -29,12 +33,14 @@ use hotshot_task::task::{Task, TaskState};
 use hotshot_types::{
     message::GeneralConsensusMessage,
     traits::{
+        clock::Clock,
         consensus_api::ConsensusApi,
         election::Membership,
         network::ConnectedNetwork,
         node_implementation::{ConsensusTime, NodeImplementation, NodeType},
     },
 };
+use hotshot_utils::log_throttle;
 use snafu::Snafu;
 use std::{collections::BTreeMap, collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
 #[cfg(async_executor_impl = "tokio")]
@@ -81,6 +87,8 @@ pub struct ViewSyncTaskState<
     pub private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
     /// HotShot consensus API
     pub api: A,
+    /// The bus lifecycle events are published on
+    pub event_bus: EventBus<TYPES>,
     /// Our node id; for logging
     pub id: u64,
 
@@ -103,6 +111,9 @@ pub struct ViewSyncTaskState<
     /// Timeout duration for view sync rounds
     pub view_sync_timeout: Duration,
 
+    /// The time source of the view sync timeouts
+    pub clock: Arc<dyn Clock>,
+
     /// Last view we garbage collected old tasks
     pub last_garbage_collected_view: TYPES::Time,
 }
@@ -152,6 +163,8 @@ pub struct ViewSyncReplicaTaskState<
 > {
     /// Timeout for view sync rounds
     pub view_sync_timeout: Duration,
+    /// The time source of the view sync timeouts
+    pub clock: Arc<dyn Clock>,
     /// Current round HotShot is in
     pub current_view: TYPES::Time,
     /// Round HotShot wishes to be in
@@ -164,6 +177,8 @@ pub struct ViewSyncReplicaTaskState<
     pub sent_view_change_event: bool,
     /// Timeout task handle, when it expires we try the next relay
     pub timeout_task: Option<JoinHandle<()>>,
+    /// The bus lifecycle events are published on
+    pub event_bus: EventBus<TYPES>,
     /// Our node id; for logging
     pub id: u64,
 
@@ -264,6 +279,8 @@ impl<
             private_key: self.private_key.clone(),
             api: self.api.clone(),
             view_sync_timeout: self.view_sync_timeout,
+            clock: self.clock.clone(),
+            event_bus: self.event_bus.clone(),
             id: self.id,
         };
 
@@ -318,7 +335,9 @@ impl<
                 let relay_map = map.entry(vote_view).or_insert(BTreeMap::new());
                 if let Some(relay_task) = relay_map.get_mut(&relay) {
                     debug!("Forwarding message");
+                    relay_task.progress_steps = self.api.vote_progress_steps();
                     let result = relay_task.handle_event(event.clone(), &event_stream).await;
+                    report_vote_progress(relay_task, &self.api).await;
 
                     if result == Some(HotShotTaskCompleted) {
                         // The protocol has finished
@@ -342,7 +361,8 @@ impl<
                 };
                 let vote_collector =
                     create_vote_accumulator(&info, vote.clone(), event, &event_stream).await;
-                if let Some(vote_task) = vote_collector {
+                if let Some(mut vote_task) = vote_collector {
+                    report_vote_progress(&mut vote_task, &self.api).await;
                     relay_map.insert(relay, vote_task);
                 }
             }
@@ -354,7 +374,9 @@ impl<
                 let relay_map = map.entry(vote_view).or_insert(BTreeMap::new());
                 if let Some(relay_task) = relay_map.get_mut(&relay) {
                     debug!("Forwarding message");
+                    relay_task.progress_steps = self.api.vote_progress_steps();
                     let result = relay_task.handle_event(event.clone(), &event_stream).await;
+                    report_vote_progress(relay_task, &self.api).await;
 
                     if result == Some(HotShotTaskCompleted) {
                         // The protocol has finished
@@ -378,7 +400,8 @@ impl<
                 };
                 let vote_collector =
                     create_vote_accumulator(&info, vote.clone(), event, &event_stream).await;
-                if let Some(vote_task) = vote_collector {
+                if let Some(mut vote_task) = vote_collector {
+                    report_vote_progress(&mut vote_task, &self.api).await;
                     relay_map.insert(relay, vote_task);
                 }
             }
@@ -390,7 +413,9 @@ impl<
                 let relay_map = map.entry(vote_view).or_insert(BTreeMap::new());
                 if let Some(relay_task) = relay_map.get_mut(&relay) {
                     debug!("Forwarding message");
+                    relay_task.progress_steps = self.api.vote_progress_steps();
                     let result = relay_task.handle_event(event.clone(), &event_stream).await;
+                    report_vote_progress(relay_task, &self.api).await;
 
                     if result == Some(HotShotTaskCompleted) {
                         // The protocol has finished
@@ -414,7 +439,8 @@ impl<
                 };
                 let vote_collector =
                     create_vote_accumulator(&info, vote.clone(), event, &event_stream).await;
-                if let Some(vote_task) = vote_collector {
+                if let Some(mut vote_task) = vote_collector {
+                    report_vote_progress(&mut vote_task, &self.api).await;
                     relay_map.insert(relay, vote_task);
                 }
             }
@@ -469,17 +495,23 @@ impl<
                     .await;
 
                 self.num_timeouts_tracked += 1;
-                error!(
-                    "Num timeouts tracked since last view change is {}. View {} timed out",
-                    self.num_timeouts_tracked, *view_number
-                );
+                if let Some(summary) = log_throttle::check("view_timeout") {
+                    error!(
+                        "Num timeouts tracked since last view change is {}. View {} timed out{summary}",
+                        self.num_timeouts_tracked, *view_number
+                    );
+                }
 
                 if self.num_timeouts_tracked >= 3 {
-                    error!("Too many consecutive timeouts!  This shouldn't happen");
+                    if let Some(summary) = log_throttle::check("consecutive_timeouts") {
+                        error!("Too many consecutive timeouts!  This shouldn't happen{summary}");
+                    }
                 }
 
                 if self.num_timeouts_tracked >= 2 {
                     error!("Starting view sync protocol for view {}", *view_number + 1);
+                    self.event_bus
+                        .publish(BusEvent::ViewSyncStarted { view: view_number });
                     // Start polling for view sync certificates
                     self.network
                         .inject_consensus_info(ConsensusIntentEvent::PollForViewSyncCertificate(
@@ -600,9 +632,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     let phase = last_seen_certificate;
                     let relay = self.relay;
                     let next_view = self.next_view;
-                    let timeout = self.view_sync_timeout;
+                    let timeout = self.clock.sleep(self.view_sync_timeout);
                     async move {
-                        async_sleep(timeout).await;
+                        timeout.await;
                         info!("Vote sending timed out in ViewSyncPreCommitCertificateRecv, Relay = {}", relay);
 
                         broadcast_event(
@@ -681,9 +713,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     let phase = last_seen_certificate;
                     let relay = self.relay;
                     let next_view = self.next_view;
-                    let timeout = self.view_sync_timeout;
+                    let timeout = self.clock.sleep(self.view_sync_timeout);
                     async move {
-                        async_sleep(timeout).await;
+                        timeout.await;
                         info!(
                             "Vote sending timed out in ViewSyncCommitCertificateRecv, relay = {}",
                             relay
@@ -738,6 +770,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                 if certificate.get_view_number() > self.next_view {
                     return Some(HotShotTaskCompleted);
                 }
+                self.event_bus.publish(BusEvent::ViewSyncCompleted {
+                    view: certificate.get_view_number(),
+                });
 
                 // cancel poll for votes
                 self.network
@@ -794,9 +829,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                     let stream = event_stream.clone();
                     let relay = self.relay;
                     let next_view = self.next_view;
-                    let timeout = self.view_sync_timeout;
+                    let timeout = self.clock.sleep(self.view_sync_timeout);
                     async move {
-                        async_sleep(timeout).await;
+                        timeout.await;
                         info!("Vote sending timed out in ViewSyncTrigger");
                         broadcast_event(
                             HotShotEvent::ViewSyncTimeout(
@@ -855,9 +890,9 @@ impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> +
                         let stream = event_stream.clone();
                         let relay = self.relay;
                         let next_view = self.next_view;
-                        let timeout = self.view_sync_timeout;
+                        let timeout = self.clock.sleep(self.view_sync_timeout);
                         async move {
-                            async_sleep(timeout).await;
+                            timeout.await;
                             info!(
                                 "Vote sending timed out in ViewSyncTimeout relay = {}",
                                 relay
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Debug, marker::PhantomData, sync::Arc};
+use std::{collections::HashSet, fmt::Debug, sync::Arc, time::Instant};
 
 use crate::{
     events::{HotShotEvent, HotShotTaskCompleted},
@@ -6,28 +6,43 @@ use crate::{
 };
 use async_broadcast::Sender;
 use async_trait::async_trait;
+use bincode::Options;
+use commit::Commitment;
 use either::Either::{self, Left, Right};
 
 use hotshot_task::task::{Task, TaskState};
 use hotshot_types::{
+    event::{Event, EventType},
     simple_certificate::{
-        DACertificate, QuorumCertificate, TimeoutCertificate, UpgradeCertificate,
-        ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate2,
+        DACertificate, EpochChangeCertificate, QuorumCertificate, TimeoutCertificate,
+        UpgradeCertificate, ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate2,
+        ViewSyncPreCommitCertificate2,
     },
     simple_vote::{
-        DAVote, QuorumVote, TimeoutVote, UpgradeVote, ViewSyncCommitVote, ViewSyncFinalizeVote,
-        ViewSyncPreCommitVote,
+        DAVote, EpochChangeVote, QuorumVote, TimeoutVote, UpgradeVote, ViewSyncCommitVote,
+        ViewSyncFinalizeVote, ViewSyncPreCommitVote,
     },
-    traits::{election::Membership, node_implementation::NodeType},
-    vote::{Certificate, HasViewNumber, Vote, VoteAccumulator},
+    stall::VoteKind,
+    traits::{
+        consensus_api::ConsensusApi,
+        election::Membership,
+        node_implementation::{NodeImplementation, NodeType},
+        storage::StorageError,
+    },
+    vote::{Certificate, HasViewNumber, Vote, VoteAccumulator, VoteProgress},
 };
+use hotshot_utils::bincode::bincode_opts;
+use serde::{de::DeserializeOwned, Serialize};
 use snafu::Snafu;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 #[derive(Snafu, Debug)]
 /// Stub of a vote error
 pub struct VoteTaskError {}
 
+/// Largest number of votes a collector holds unverified before verifying them as one batch
+pub const MAX_VOTE_BATCH: usize = 16;
+
 /// Task state for collecting votes of one type and emiting a certificate
 pub struct VoteCollectionTaskState<
     TYPES: NodeType,
@@ -41,11 +56,37 @@ pub struct VoteCollectionTaskState<
     pub membership: Arc<TYPES::Membership>,
 
     /// accumulator handles aggregating the votes
-    pub accumulator: Option<VoteAccumulator<TYPES, VOTE, CERT>>,
+    pub accumulator: VoteAccumulator<TYPES, VOTE, CERT>,
 
     /// The view which we are collecting votes for
     pub view: TYPES::Time,
 
+    /// Whether a certificate has already been formed for `view`.  Further votes for the
+    /// view are ignored until the collector is re-armed.
+    pub completed: bool,
+
+    /// Commitments of `view` whose persisted votes have already been fed into the accumulator
+    pub restored: HashSet<Commitment<VOTE::Commitment>>,
+
+    /// The progress of the commitment of `view` with the most votes collected
+    pub progress: Option<VoteProgress>,
+
+    /// The voters of the commitment of `progress`
+    pub progress_signers: Vec<TYPES::SignatureKey>,
+
+    /// The step of [`ConsensusApi::vote_progress_steps`] last reported for `view`
+    pub progress_reported: u64,
+
+    /// The steps of [`ConsensusApi::vote_progress_steps`] progress is reported at, if any
+    pub progress_steps: Option<u64>,
+
+    /// Votes received for `view` whose signatures are yet to be verified, see
+    /// [`Self::accumulate_vote`]
+    pub pending: Vec<VOTE>,
+
+    /// Votes of `pending` verified and recorded since they were last taken to be persisted
+    pub unpersisted: Vec<VOTE>,
+
     /// Node id
     pub id: u64,
 }
@@ -57,6 +98,9 @@ pub trait AggregatableVote<
     CERT: Certificate<TYPES, Voteable = VOTE::Commitment>,
 >
 {
+    /// the kind of these votes, in vote progress events
+    const KIND: VoteKind;
+
     /// return the leader for this votes
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey;
 
@@ -69,9 +113,109 @@ impl<
         VOTE: Vote<TYPES> + AggregatableVote<TYPES, VOTE, CERT>,
         CERT: Certificate<TYPES, Voteable = VOTE::Commitment> + Debug,
     > VoteCollectionTaskState<TYPES, VOTE, CERT>
+{
+    /// Create a fresh collector for the view in `info`
+    #[must_use]
+    pub fn new(info: &AccumulatorInfo<TYPES>) -> Self {
+        Self {
+            public_key: info.public_key.clone(),
+            membership: info.membership.clone(),
+            accumulator: VoteAccumulator::new(),
+            view: info.view,
+            completed: false,
+            restored: HashSet::new(),
+            progress: None,
+            progress_signers: Vec::new(),
+            progress_reported: 0,
+            progress_steps: None,
+            pending: Vec::new(),
+            unpersisted: Vec::new(),
+            id: info.id,
+        }
+    }
+
+    /// Re-arm this collector to gather the votes of `membership` for `view`, reusing the
+    /// allocations of the accumulator from the previous view.
+    pub fn rearm(&mut self, view: TYPES::Time, membership: Arc<TYPES::Membership>) {
+        self.accumulator.clear();
+        self.view = view;
+        self.membership = membership;
+        self.completed = false;
+        self.restored.clear();
+        self.progress = None;
+        self.progress_signers.clear();
+        self.progress_reported = 0;
+        self.pending.clear();
+        self.unpersisted.clear();
+    }
+
+    /// Record the progress of the votes collected for `commitment`, if it has the most votes
+    fn record_progress(&mut self, commitment: &Commitment<VOTE::Commitment>) {
+        let progress = self.accumulator.progress(commitment, &self.membership);
+        if !self
+            .progress
+            .is_some_and(|best| best.collected_weight >= progress.collected_weight)
+        {
+            self.progress = Some(progress);
+            self.progress_signers = self.accumulator.voters(commitment);
+        }
+    }
+
+    /// Take several votes at once and accumulate them, verifying their signatures as one batch,
+    /// see [`VoteAccumulator::accumulate_batch`]. Returns either the cert or the updated state
+    /// after the votes are accumulated: the votes persisted for the view, or those held in
+    /// `pending` by [`Self::accumulate_vote`]
+    pub async fn accumulate_votes(
+        &mut self,
+        votes: Vec<VOTE>,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) -> Option<HotShotTaskCompleted> {
+        if self.completed {
+            return None;
+        }
+        let votes: Vec<VOTE> = votes
+            .into_iter()
+            .filter(|vote| {
+                vote.get_leader(&self.membership) == self.public_key
+                    && vote.get_view_number() == self.view
+            })
+            .collect();
+        let result = self.accumulator.accumulate_batch(&votes, &self.membership);
+        for commitment in votes
+            .iter()
+            .map(Vote::get_data_commitment)
+            .collect::<HashSet<_>>()
+        {
+            self.record_progress(&commitment);
+        }
+        match result {
+            Either::Left(()) => None,
+            Either::Right(cert) => {
+                debug!("Certificate Formed! {:?}", cert);
+
+                broadcast_event(VOTE::make_cert_event(cert, &self.public_key), event_stream).await;
+                self.accumulator.clear();
+                self.completed = true;
+                Some(HotShotTaskCompleted)
+            }
+        }
+    }
+}
+
+impl<
+        TYPES: NodeType,
+        VOTE: Vote<TYPES> + AggregatableVote<TYPES, VOTE, CERT> + Clone,
+        CERT: Certificate<TYPES, Voteable = VOTE::Commitment> + Debug,
+    > VoteCollectionTaskState<TYPES, VOTE, CERT>
 {
     /// Take one vote and accumultate it. Returns either the cert or the updated state
-    /// after the vote is accumulated
+    /// after the vote is accumulated.
+    ///
+    /// The vote is held in `pending` rather than verified on its own, and the votes pending are
+    /// verified as one batch by [`Self::accumulate_votes`] once they may make a difference:
+    /// when they would meet the threshold of the certificate, or reach the next step of progress
+    /// to report, or once [`MAX_VOTE_BATCH`] of them are held. A batch with an invalid signature
+    /// in it is verified again one vote at a time, so the valid votes still count.
     pub async fn accumulate_vote(
         &mut self,
         vote: &VOTE,
@@ -89,19 +233,56 @@ impl<
             );
             return None;
         }
-        let Some(ref mut accumulator) = self.accumulator else {
+        if self.completed {
             return None;
-        };
-        match accumulator.accumulate(vote, &self.membership) {
-            Either::Left(()) => None,
-            Either::Right(cert) => {
-                debug!("Certificate Formed! {:?}", cert);
-
-                broadcast_event(VOTE::make_cert_event(cert, &self.public_key), event_stream).await;
-                self.accumulator = None;
-                Some(HotShotTaskCompleted)
+        }
+        let key = vote.get_signing_key();
+        let commitment = vote.get_data_commitment();
+        if self.accumulator.has_recorded(&key, &commitment) {
+            return None;
+        }
+        // a batch verifies one vote per voter, so a vote forged for the key of a voter must not
+        // keep out its real one: the votes held so far are verified first
+        if self.pending.iter().any(|pending| {
+            pending.get_signing_key() == key && pending.get_data_commitment() == commitment
+        }) {
+            if let Some(completed) = self.verify_pending(event_stream).await {
+                return Some(completed);
             }
+            if self.accumulator.has_recorded(&key, &commitment) {
+                return None;
+            }
+        }
+        self.pending.push(vote.clone());
+
+        let progress = self
+            .accumulator
+            .progress_with(&commitment, &self.pending, &self.membership);
+        if progress.collected_weight >= progress.threshold
+            || self.pending.len() >= MAX_VOTE_BATCH
+            || self
+                .progress_steps
+                .is_some_and(|steps| progress.step(steps) > self.progress_reported)
+        {
+            return self.verify_pending(event_stream).await;
         }
+        None
+    }
+
+    /// Verify and accumulate the votes held in `pending` as one batch, keeping those recorded to
+    /// be persisted
+    async fn verify_pending(
+        &mut self,
+        event_stream: &Sender<HotShotEvent<TYPES>>,
+    ) -> Option<HotShotTaskCompleted> {
+        let pending = std::mem::take(&mut self.pending);
+        let result = self.accumulate_votes(pending.clone(), event_stream).await;
+        // the votes of a formed certificate are no longer recorded, nor needed
+        self.unpersisted.extend(pending.into_iter().filter(|vote| {
+            self.accumulator
+                .has_recorded(&vote.get_signing_key(), &vote.get_data_commitment())
+        }));
+        result
     }
 }
 
@@ -197,19 +378,7 @@ where
         );
         return None;
     }
-    let new_accumulator = VoteAccumulator {
-        vote_outcomes: HashMap::new(),
-        signers: HashMap::new(),
-        phantom: PhantomData,
-    };
-
-    let mut state = VoteCollectionTaskState::<TYPES, VOTE, CERT> {
-        membership: info.membership.clone(),
-        public_key: info.public_key.clone(),
-        accumulator: Some(new_accumulator),
-        view: info.view,
-        id: info.id,
-    };
+    let mut state = VoteCollectionTaskState::<TYPES, VOTE, CERT>::new(info);
 
     let result = state.handle_event(event.clone(), sender).await;
 
@@ -221,6 +390,158 @@ where
     Some(state)
 }
 
+/// Report the progress of the votes `state` collected to the application through `api`, once
+/// for every further step of [`ConsensusApi::vote_progress_steps`] they reached, if it is set,
+/// with the voters so far and the members of the committee yet to vote.
+pub async fn report_vote_progress<TYPES, I, A, VOTE, CERT>(
+    state: &mut VoteCollectionTaskState<TYPES, VOTE, CERT>,
+    api: &A,
+) where
+    TYPES: NodeType,
+    I: NodeImplementation<TYPES>,
+    A: ConsensusApi<TYPES, I>,
+    VOTE: Vote<TYPES> + AggregatableVote<TYPES, VOTE, CERT>,
+    CERT: Certificate<TYPES, Voteable = VOTE::Commitment> + Debug,
+{
+    let (Some(steps), Some(progress)) = (api.vote_progress_steps(), state.progress) else {
+        return;
+    };
+    let step = progress.step(steps);
+    if step <= state.progress_reported {
+        return;
+    }
+    state.progress_reported = step;
+    let missing = state
+        .membership
+        .get_committee(state.view)
+        .into_iter()
+        .filter(|member| !state.progress_signers.contains(member))
+        .collect();
+    api.send_event(Event {
+        view_number: state.view,
+        event: EventType::VoteProgress {
+            view: state.view,
+            kind: VOTE::KIND,
+            collected_weight: progress.collected_weight,
+            threshold: progress.threshold,
+            signers: state.progress_signers.clone(),
+            missing,
+        },
+    })
+    .await;
+}
+
+/// Feed `vote` into the long-lived `collector`, creating it on first use and re-arming it
+/// whenever a vote for a newer view arrives, rather than building a new collector every view.
+/// Either way the collector checks the votes of the view against the membership of `info`.
+/// Time spent handling the vote is recorded in the metrics of `api`, both over all collectors
+/// and for the collector of this kind of votes.
+///
+/// Votes are held by the collector until they are verified as a batch, see
+/// [`VoteCollectionTaskState::accumulate_vote`], and persisted to storage through `api` once
+/// verified and accumulated, unless the storage does not keep votes. The first vote for a
+/// commitment seen by a collector first replays the votes persisted for it, so a leader
+/// restarting mid-view resumes forming the certificate instead of starting over.
+///
+/// Returns `Some(HotShotTaskCompleted)` once a certificate has been formed for the vote's view.
+pub async fn handle_vote<TYPES, I, A, VOTE, CERT>(
+    collector: &mut Option<VoteCollectionTaskState<TYPES, VOTE, CERT>>,
+    vote: &VOTE,
+    event: HotShotEvent<TYPES>,
+    info: &AccumulatorInfo<TYPES>,
+    api: &A,
+    sender: &Sender<HotShotEvent<TYPES>>,
+) -> Option<HotShotTaskCompleted>
+where
+    TYPES: NodeType,
+    I: NodeImplementation<TYPES>,
+    A: ConsensusApi<TYPES, I>,
+    VOTE: Vote<TYPES>
+        + AggregatableVote<TYPES, VOTE, CERT>
+        + Serialize
+        + DeserializeOwned
+        + std::marker::Send
+        + std::marker::Sync
+        + 'static,
+    CERT: Certificate<TYPES, Voteable = VOTE::Commitment>
+        + Debug
+        + std::marker::Send
+        + std::marker::Sync
+        + 'static,
+    VoteCollectionTaskState<TYPES, VOTE, CERT>: HandleVoteEvent<TYPES, VOTE, CERT>,
+{
+    let start = Instant::now();
+    match collector.as_mut() {
+        Some(state) if vote.get_view_number() > state.view => {
+            debug!(
+                "Re-arming vote handle for view {:?}",
+                vote.get_view_number()
+            );
+            state.rearm(vote.get_view_number(), info.membership.clone());
+            api.metrics().vote_collectors_rearmed.add(1);
+        }
+        Some(_) => {}
+        None => {
+            debug!("Starting vote handle for view {:?}", vote.get_view_number());
+            *collector = Some(VoteCollectionTaskState::new(info));
+        }
+    }
+    let state = collector.as_mut()?;
+    state.progress_steps = api.vote_progress_steps();
+    let commitment = vote.get_data_commitment();
+    let mut result = None;
+    if !state.completed && state.restored.insert(commitment) {
+        match api.get_stored_votes(state.view, commitment.as_ref()).await {
+            Ok(stored) => {
+                let stored_votes: Vec<VOTE> = stored
+                    .iter()
+                    .filter_map(|stored_vote| {
+                        let decoded = bincode_opts().deserialize::<VOTE>(stored_vote);
+                        if decoded.is_err() {
+                            warn!("Failed to deserialize a persisted vote");
+                        }
+                        decoded.ok()
+                    })
+                    .collect();
+                result = state.accumulate_votes(stored_votes, sender).await;
+            }
+            Err(StorageError::Unsupported { .. }) => {}
+            Err(e) => warn!("Failed to load persisted votes: {:?}", e),
+        }
+    }
+    if result.is_none() {
+        result = state.handle_event(event, sender).await;
+    }
+
+    // only votes the accumulator verified and recorded by now are persisted; the votes of a
+    // formed certificate are no longer needed
+    for verified in std::mem::take(&mut state.unpersisted) {
+        match bincode_opts().serialize(&verified) {
+            Ok(encoded) => {
+                match api
+                    .store_vote(
+                        state.view,
+                        verified.get_data_commitment().as_ref().to_vec(),
+                        encoded,
+                    )
+                    .await
+                {
+                    Ok(()) | Err(StorageError::Unsupported { .. }) => {}
+                    Err(e) => warn!("Failed to persist vote: {:?}", e),
+                }
+            }
+            Err(e) => warn!("Failed to serialize vote: {:?}", e),
+        }
+    }
+    report_vote_progress(state, api).await;
+    let elapsed = start.elapsed().as_secs_f64();
+    api.metrics().vote_collection_duration.add_point(elapsed);
+    api.metrics()
+        .vote_task_duration(VOTE::KIND)
+        .add_point(elapsed);
+    result
+}
+
 /// Alias for Quorum vote accumulator
 type QuorumVoteState<TYPES> =
     VoteCollectionTaskState<TYPES, QuorumVote<TYPES>, QuorumCertificate<TYPES>>;
@@ -232,6 +553,9 @@ type TimeoutVoteState<TYPES> =
 /// Alias for upgrade vote accumulator
 type UpgradeVoteState<TYPES> =
     VoteCollectionTaskState<TYPES, UpgradeVote<TYPES>, UpgradeCertificate<TYPES>>;
+/// Alias for epoch change vote accumulator
+type EpochChangeVoteState<TYPES> =
+    VoteCollectionTaskState<TYPES, EpochChangeVote<TYPES>, EpochChangeCertificate<TYPES>>;
 /// Alias for View Sync Pre Commit vote accumulator
 type ViewSyncPreCommitState<TYPES> = VoteCollectionTaskState<
     TYPES,
@@ -251,6 +575,8 @@ type ViewSyncFinalizeVoteState<TYPES> = VoteCollectionTaskState<
 impl<TYPES: NodeType> AggregatableVote<TYPES, QuorumVote<TYPES>, QuorumCertificate<TYPES>>
     for QuorumVote<TYPES>
 {
+    const KIND: VoteKind = VoteKind::Quorum;
+
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
         membership.get_leader(self.get_view_number() + 1)
     }
@@ -265,6 +591,8 @@ impl<TYPES: NodeType> AggregatableVote<TYPES, QuorumVote<TYPES>, QuorumCertifica
 impl<TYPES: NodeType> AggregatableVote<TYPES, UpgradeVote<TYPES>, UpgradeCertificate<TYPES>>
     for UpgradeVote<TYPES>
 {
+    const KIND: VoteKind = VoteKind::Upgrade;
+
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
         membership.get_leader(self.get_view_number())
     }
@@ -276,9 +604,27 @@ impl<TYPES: NodeType> AggregatableVote<TYPES, UpgradeVote<TYPES>, UpgradeCertifi
     }
 }
 
+impl<TYPES: NodeType> AggregatableVote<TYPES, EpochChangeVote<TYPES>, EpochChangeCertificate<TYPES>>
+    for EpochChangeVote<TYPES>
+{
+    const KIND: VoteKind = VoteKind::EpochChange;
+
+    fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
+        membership.get_leader(self.get_view_number())
+    }
+    fn make_cert_event(
+        certificate: EpochChangeCertificate<TYPES>,
+        _key: &TYPES::SignatureKey,
+    ) -> HotShotEvent<TYPES> {
+        HotShotEvent::EpochChangeCertificateFormed(certificate)
+    }
+}
+
 impl<TYPES: NodeType> AggregatableVote<TYPES, DAVote<TYPES>, DACertificate<TYPES>>
     for DAVote<TYPES>
 {
+    const KIND: VoteKind = VoteKind::DA;
+
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
         membership.get_leader(self.get_view_number())
     }
@@ -293,6 +639,8 @@ impl<TYPES: NodeType> AggregatableVote<TYPES, DAVote<TYPES>, DACertificate<TYPES
 impl<TYPES: NodeType> AggregatableVote<TYPES, TimeoutVote<TYPES>, TimeoutCertificate<TYPES>>
     for TimeoutVote<TYPES>
 {
+    const KIND: VoteKind = VoteKind::Timeout;
+
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
         membership.get_leader(self.get_view_number() + 1)
     }
@@ -308,6 +656,8 @@ impl<TYPES: NodeType>
     AggregatableVote<TYPES, ViewSyncCommitVote<TYPES>, ViewSyncCommitCertificate2<TYPES>>
     for ViewSyncCommitVote<TYPES>
 {
+    const KIND: VoteKind = VoteKind::ViewSync;
+
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
         membership.get_leader(self.get_data().round + self.get_data().relay)
     }
@@ -323,6 +673,8 @@ impl<TYPES: NodeType>
     AggregatableVote<TYPES, ViewSyncPreCommitVote<TYPES>, ViewSyncPreCommitCertificate2<TYPES>>
     for ViewSyncPreCommitVote<TYPES>
 {
+    const KIND: VoteKind = VoteKind::ViewSync;
+
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
         membership.get_leader(self.get_data().round + self.get_data().relay)
     }
@@ -338,6 +690,8 @@ impl<TYPES: NodeType>
     AggregatableVote<TYPES, ViewSyncFinalizeVote<TYPES>, ViewSyncFinalizeCertificate2<TYPES>>
     for ViewSyncFinalizeVote<TYPES>
 {
+    const KIND: VoteKind = VoteKind::ViewSync;
+
     fn get_leader(&self, membership: &TYPES::Membership) -> TYPES::SignatureKey {
         membership.get_leader(self.get_data().round + self.get_data().relay)
     }
@@ -389,6 +743,25 @@ impl<TYPES: NodeType> HandleVoteEvent<TYPES, UpgradeVote<TYPES>, UpgradeCertific
     }
 }
 
+#[async_trait]
+impl<TYPES: NodeType> HandleVoteEvent<TYPES, EpochChangeVote<TYPES>, EpochChangeCertificate<TYPES>>
+    for EpochChangeVoteState<TYPES>
+{
+    async fn handle_event(
+        &mut self,
+        event: HotShotEvent<TYPES>,
+        sender: &Sender<HotShotEvent<TYPES>>,
+    ) -> Option<HotShotTaskCompleted> {
+        match event {
+            HotShotEvent::EpochChangeVoteRecv(vote) => self.accumulate_vote(&vote, sender).await,
+            _ => None,
+        }
+    }
+    fn filter(event: &HotShotEvent<TYPES>) -> bool {
+        matches!(event, HotShotEvent::EpochChangeVoteRecv(_))
+    }
+}
+
 #[async_trait]
 impl<TYPES: NodeType> HandleVoteEvent<TYPES, DAVote<TYPES>, DACertificate<TYPES>>
     for DAVoteState<TYPES>
@@ -0,0 +1,274 @@
+use crate::{
+    consensus::proposed_leaf,
+    events::{HotShotEvent, HotShotTaskCompleted},
+    helpers::{broadcast_event, cancel_task},
+};
+use async_broadcast::Sender;
+use async_compatibility_layer::art::async_spawn;
+#[cfg(async_executor_impl = "async-std")]
+use async_std::task::JoinHandle;
+use commit::Committable;
+use core::time::Duration;
+use hotshot_task::task::TaskState;
+use hotshot_types::{
+    event::{Event, EventType},
+    signing::{self, SigningDomain},
+    stall::{StallTracker, VoteKind},
+    traits::{
+        clock::Clock,
+        consensus_api::ConsensusApi,
+        election::Membership,
+        node_implementation::{NodeImplementation, NodeType},
+    },
+    vote::{Certificate, HasViewNumber, Vote},
+};
+use sha2::{Digest, Sha256};
+use std::{marker::PhantomData, sync::Arc};
+#[cfg(async_executor_impl = "tokio")]
+use tokio::task::JoinHandle;
+use tracing::{debug, error, instrument};
+
+/// Tracks state of the watchdog task, which reports the node stalled when its view does not
+/// change for the stall timeout. Anyone can send a message claiming to be from a peer, so only
+/// the messages signed by their sender count towards the peers heard from and the votes.
+pub struct WatchdogTaskState<
+    TYPES: NodeType,
+    I: NodeImplementation<TYPES>,
+    A: ConsensusApi<TYPES, I> + 'static,
+> {
+    /// The state's api
+    pub api: A,
+    /// The clock the stall timeout runs on
+    pub clock: Arc<dyn Clock>,
+    /// How long the view may not change before the node is reported stalled
+    pub stall_timeout: Duration,
+
+    /// Membership for Quorum Certs/votes
+    pub quorum_membership: Arc<TYPES::Membership>,
+
+    /// Membership for the DA committee, whose leader signs DA proposals
+    pub da_membership: Arc<TYPES::Membership>,
+
+    /// What was seen of the current view
+    pub tracker: StallTracker<TYPES>,
+    /// The stall timeout of the current view
+    pub timer_task: Option<JoinHandle<()>>,
+
+    /// This Nodes public key
+    pub public_key: TYPES::SignatureKey,
+
+    /// This state's ID
+    pub id: u64,
+
+    /// phantom data for `I`
+    pub _pd: PhantomData<I>,
+}
+
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> + 'static>
+    WatchdogTaskState<TYPES, I, A>
+{
+    /// Start the stall timeout of the current view over, replacing the running one
+    pub async fn arm_timer(&mut self, tx: &Sender<HotShotEvent<TYPES>>) {
+        if let Some(timer_task) = self.timer_task.take() {
+            cancel_task(timer_task).await;
+        }
+        let timeout = self.clock.sleep(self.stall_timeout);
+        let stream = tx.clone();
+        let view = self.tracker.view();
+        self.timer_task = Some(async_spawn(async move {
+            timeout.await;
+            broadcast_event(HotShotEvent::StallCheck(view), &stream).await;
+        }));
+    }
+
+    /// Report the node stalled in the current view, with what it saw of it
+    async fn report_stall(&self) {
+        let view = self.tracker.view();
+        let diagnostics = self.tracker.diagnostics(
+            self.clock.now(),
+            &self.public_key,
+            self.quorum_membership.get_leader(view + 1),
+            &self.quorum_membership.get_committee(view),
+            self.quorum_membership.success_threshold().get(),
+        );
+        error!(
+            "No view change for {:?}, stalled in view {:?}: {:?}",
+            diagnostics.stalled_for, view, diagnostics
+        );
+        self.api
+            .send_event(Event {
+                view_number: view,
+                event: EventType::Stall {
+                    diagnostics: Arc::new(diagnostics),
+                },
+            })
+            .await;
+    }
+
+    /// Record `vote` of `kind`, if its voter signed it
+    fn record_vote<VOTE: Vote<TYPES> + HasViewNumber<TYPES>>(
+        &mut self,
+        kind: VoteKind,
+        vote: &VOTE,
+    ) {
+        if !signing::validate(
+            &vote.get_signing_key(),
+            &vote.get_signature(),
+            SigningDomain::Vote,
+            vote.get_data_commitment().as_ref(),
+        ) {
+            debug!("Watchdog dropped a vote not signed by its voter");
+            return;
+        }
+        self.tracker
+            .record_vote(kind, vote.get_view_number(), vote.get_signing_key());
+    }
+
+    /// main task event handler
+    #[instrument(skip_all, fields(id = self.id, view = *self.tracker.view()), name = "Watchdog Task", level = "error")]
+    pub async fn handle(
+        &mut self,
+        event: HotShotEvent<TYPES>,
+        tx: Sender<HotShotEvent<TYPES>>,
+    ) -> Option<HotShotTaskCompleted> {
+        match event {
+            HotShotEvent::ViewChange(view) => {
+                if self.tracker.enter_view(view, self.clock.now()) {
+                    self.arm_timer(&tx).await;
+                }
+            }
+            HotShotEvent::StallCheck(view) => {
+                // the timer of a view the node already left may still have run out
+                if view == self.tracker.view() {
+                    self.report_stall().await;
+                    self.arm_timer(&tx).await;
+                }
+            }
+            HotShotEvent::QuorumProposalRecv(proposal, sender) => {
+                let view = proposal.data.view_number;
+                debug!("Watchdog saw a proposal for view {:?}", view);
+                // the leaf of a proposal on the genesis leaf cannot be rebuilt without the
+                // instance state, so such proposals are not counted
+                if proposal.data.justify_qc.is_genesis
+                    || sender != self.quorum_membership.get_leader(view)
+                {
+                    return None;
+                }
+                let leaf = proposed_leaf(
+                    &proposal.data,
+                    proposal.data.justify_qc.get_data().leaf_commit,
+                    sender.clone(),
+                );
+                if signing::validate(
+                    &sender,
+                    &proposal.signature,
+                    SigningDomain::QuorumProposal,
+                    leaf.commit().as_ref(),
+                ) {
+                    self.tracker.record_proposal(view, sender);
+                }
+            }
+            HotShotEvent::DAProposalRecv(proposal, sender) => {
+                if sender == self.da_membership.get_leader(proposal.data.view_number)
+                    && signing::validate(
+                        &sender,
+                        &proposal.signature,
+                        SigningDomain::DAProposal,
+                        &Sha256::digest(&proposal.data.encoded_transactions),
+                    )
+                {
+                    self.tracker.record_peer(sender);
+                }
+            }
+            HotShotEvent::VidDisperseRecv(disperse, sender) => {
+                if sender == self.quorum_membership.get_leader(disperse.data.view_number)
+                    && signing::validate(
+                        &sender,
+                        &disperse.signature,
+                        SigningDomain::VidDisperse,
+                        disperse.data.payload_commitment.as_ref(),
+                    )
+                {
+                    self.tracker.record_peer(sender);
+                }
+            }
+            HotShotEvent::QuorumVoteRecv(vote) => self.record_vote(VoteKind::Quorum, &vote),
+            HotShotEvent::DAVoteRecv(vote) => self.record_vote(VoteKind::DA, &vote),
+            HotShotEvent::TimeoutVoteRecv(vote) => self.record_vote(VoteKind::Timeout, &vote),
+            HotShotEvent::ViewSyncPreCommitVoteRecv(vote) => {
+                self.record_vote(VoteKind::ViewSync, &vote);
+            }
+            HotShotEvent::ViewSyncCommitVoteRecv(vote) => {
+                self.record_vote(VoteKind::ViewSync, &vote);
+            }
+            HotShotEvent::ViewSyncFinalizeVoteRecv(vote) => {
+                self.record_vote(VoteKind::ViewSync, &vote);
+            }
+            HotShotEvent::Timeout(_) => {
+                self.tracker.timers_mut().view_timeouts += 1;
+            }
+            HotShotEvent::ProposalTimeout(_) => {
+                self.tracker.timers_mut().proposal_timeouts += 1;
+            }
+            HotShotEvent::ViewSyncTimeout(..) => {
+                self.tracker.timers_mut().view_sync_timeouts += 1;
+            }
+            HotShotEvent::ViewSyncTrigger(_) => {
+                self.tracker.timers_mut().view_sync_triggered = true;
+            }
+            HotShotEvent::Shutdown => {
+                if let Some(timer_task) = self.timer_task.take() {
+                    cancel_task(timer_task).await;
+                }
+                return Some(HotShotTaskCompleted);
+            }
+            _ => {
+                error!("unexpected event {:?}", event);
+            }
+        }
+        None
+    }
+}
+
+/// task state implementation for the watchdog task
+impl<TYPES: NodeType, I: NodeImplementation<TYPES>, A: ConsensusApi<TYPES, I> + 'static> TaskState
+    for WatchdogTaskState<TYPES, I, A>
+{
+    type Event = HotShotEvent<TYPES>;
+
+    type Output = HotShotTaskCompleted;
+
+    async fn handle_event(
+        event: Self::Event,
+        task: &mut hotshot_task::task::Task<Self>,
+    ) -> Option<Self::Output> {
+        let sender = task.clone_sender();
+        task.state_mut().handle(event, sender).await
+    }
+
+    fn should_shutdown(event: &Self::Event) -> bool {
+        matches!(event, HotShotEvent::Shutdown)
+    }
+
+    fn filter(&self, event: &Self::Event) -> bool {
+        !matches!(
+            event,
+            HotShotEvent::ViewChange(_)
+                | HotShotEvent::StallCheck(_)
+                | HotShotEvent::QuorumProposalRecv(..)
+                | HotShotEvent::DAProposalRecv(..)
+                | HotShotEvent::VidDisperseRecv(..)
+                | HotShotEvent::QuorumVoteRecv(_)
+                | HotShotEvent::DAVoteRecv(_)
+                | HotShotEvent::TimeoutVoteRecv(_)
+                | HotShotEvent::ViewSyncPreCommitVoteRecv(_)
+                | HotShotEvent::ViewSyncCommitVoteRecv(_)
+                | HotShotEvent::ViewSyncFinalizeVoteRecv(_)
+                | HotShotEvent::Timeout(_)
+                | HotShotEvent::ProposalTimeout(_)
+                | HotShotEvent::ViewSyncTimeout(..)
+                | HotShotEvent::ViewSyncTrigger(_)
+                | HotShotEvent::Shutdown
+        )
+    }
+}
@@ -4,5 +4,10 @@
 pub mod dependency;
 /// Task which can uses dependencies
 pub mod dependency_task;
+/// Gates to pause tasks between events
+#[cfg(any(test, feature = "testing"))]
+pub mod pause;
+/// Pools of threads tasks are spawned on, apart from the runtime of the caller
+pub mod pool;
 /// Basic task types
 pub mod task;
@@ -0,0 +1,69 @@
+//! Gates to pause tasks between events
+//!
+//! A [`Task`](crate::task::Task) with a [`PauseGate`] waits at the gate before handling each
+//! event, while the gate is paused. Events keep queueing on the channel of the task meanwhile, and
+//! are handled in order once the gate is resumed, so that tests can hold a task back
+//! deterministically while the others run.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use async_broadcast::{broadcast, InactiveReceiver, Sender};
+
+/// A gate tasks wait at while it is paused. Clones share the gate.
+#[derive(Clone, Debug)]
+pub struct PauseGate {
+    /// whether the gate is paused
+    paused: Arc<AtomicBool>,
+    /// wakes the tasks waiting at the gate when it is resumed
+    resumed: Sender<()>,
+    /// keeps the wake-up channel open, and subscribes the tasks about to wait
+    waiting: InactiveReceiver<()>,
+}
+
+impl Default for PauseGate {
+    fn default() -> Self {
+        let (mut resumed, waiting) = broadcast(1);
+        resumed.set_overflow(true);
+        resumed.set_await_active(false);
+        Self {
+            paused: Arc::default(),
+            resumed,
+            waiting: waiting.deactivate(),
+        }
+    }
+}
+
+impl PauseGate {
+    /// Hold the tasks at the gate, from the next event they handle on
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Let the tasks at the gate through
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        // the channel overflows rather than fills up, so this only fails once it is closed
+        let _ = self.resumed.try_broadcast(());
+    }
+
+    /// Whether the gate is paused
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the gate is not paused
+    pub async fn wait(&self) {
+        while self.is_paused() {
+            let mut waiting = self.waiting.activate_cloned();
+            // the gate may have been resumed before we subscribed to wake-ups
+            if !self.is_paused() {
+                return;
+            }
+            let _ = waiting.recv().await;
+        }
+    }
+}
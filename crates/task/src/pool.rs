@@ -0,0 +1,126 @@
+#[cfg(async_executor_impl = "tokio")]
+use std::sync::Arc;
+use std::{future::Future, io};
+
+#[cfg(async_executor_impl = "async-std")]
+use async_std::task::{spawn, JoinHandle};
+#[cfg(async_executor_impl = "tokio")]
+use tokio::task::{spawn, JoinHandle};
+use tracing::warn;
+
+/// A runtime owned by a pool. It is shut down in the background when the pool is dropped, as
+/// dropping it from within another runtime would panic.
+#[cfg(async_executor_impl = "tokio")]
+struct DedicatedRuntime(Option<tokio::runtime::Runtime>);
+
+#[cfg(async_executor_impl = "tokio")]
+impl Drop for DedicatedRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Where tasks are spawned: on the runtime of the caller, or on threads dedicated to them
+#[derive(Clone, Default)]
+pub struct TaskPool {
+    /// the runtime tasks are spawned on, if they are not spawned on the one of the caller
+    #[cfg(async_executor_impl = "tokio")]
+    runtime: Option<Arc<DedicatedRuntime>>,
+}
+
+impl TaskPool {
+    /// A pool spawning tasks on the runtime of the caller
+    #[must_use]
+    pub fn shared() -> Self {
+        Self::default()
+    }
+
+    /// A pool spawning tasks on `threads` threads of their own, named after `name`, and pinned
+    /// to `core` if given. async-std has a single runtime, under it tasks are spawned on that
+    /// runtime instead.
+    /// # Errors
+    /// If the threads cannot be started
+    pub fn dedicated(name: &str, threads: usize, core: Option<usize>) -> io::Result<Self> {
+        #[cfg(async_executor_impl = "tokio")]
+        {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(threads.max(1))
+                .thread_name(name)
+                .on_thread_start(move || {
+                    if let Some(core) = core {
+                        if !pin_current_thread(core) {
+                            warn!("Failed to pin a thread to core {core}");
+                        }
+                    }
+                })
+                .enable_all()
+                .build()?;
+            Ok(Self {
+                runtime: Some(Arc::new(DedicatedRuntime(Some(runtime)))),
+            })
+        }
+        #[cfg(async_executor_impl = "async-std")]
+        {
+            warn!(
+                "async-std has a single runtime, {name} tasks run on it rather than on {threads} \
+                 threads of their own (pinned to core {core:?})"
+            );
+            Ok(Self::default())
+        }
+    }
+
+    /// Whether the pool spawns tasks on threads of their own
+    #[must_use]
+    pub fn is_dedicated(&self) -> bool {
+        #[cfg(async_executor_impl = "tokio")]
+        {
+            self.runtime.is_some()
+        }
+        #[cfg(async_executor_impl = "async-std")]
+        {
+            false
+        }
+    }
+
+    /// Spawn `future` on the pool
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        #[cfg(async_executor_impl = "tokio")]
+        if let Some(runtime) = self.runtime.as_ref().and_then(|runtime| runtime.0.as_ref()) {
+            return runtime.spawn(future);
+        }
+        spawn(future)
+    }
+}
+
+/// Pin the current thread to `core`. Returns whether it was pinned, which it never is outside
+/// of linux.
+#[must_use]
+pub fn pin_current_thread(core: usize) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let Ok(max_cores) = usize::try_from(libc::CPU_SETSIZE) else {
+            return false;
+        };
+        if core >= max_cores {
+            return false;
+        }
+        // SAFETY: the set is initialized before use, `core` is within it, and the kernel only
+        // reads as many bytes of it as it is long
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_SET(core, &mut set);
+            libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = core;
+        false
+    }
+}
@@ -23,9 +23,12 @@ use tokio::{
 };
 use tracing::{error, warn};
 
+#[cfg(any(test, feature = "testing"))]
+use crate::pause::PauseGate;
 use crate::{
     dependency::Dependency,
     dependency_task::{DependencyTask, HandleDepOutput},
+    pool::TaskPool,
 };
 
 /// Type for mutable task state that can be used as the state for a `Task`
@@ -98,6 +101,9 @@ pub struct Task<S: TaskState> {
     /// and mutates it state ocordingly.  Also it signals the task
     /// if it is complete/should shutdown
     state: S,
+    /// The gate the task waits at before handling each event, if it can be paused
+    #[cfg(any(test, feature = "testing"))]
+    pause_gate: Option<PauseGate>,
 }
 
 impl<S: TaskState + Send + 'static> Task<S> {
@@ -113,34 +119,56 @@ impl<S: TaskState + Send + 'static> Task<S> {
             event_receiver: rx,
             registry,
             state,
+            #[cfg(any(test, feature = "testing"))]
+            pause_gate: None,
         }
     }
+
+    /// Make the task wait at `gate` before handling each event, while it is paused
+    #[cfg(any(test, feature = "testing"))]
+    #[must_use]
+    pub fn with_pause_gate(mut self, gate: PauseGate) -> Self {
+        self.pause_gate = Some(gate);
+        self
+    }
     /// Spawn the task loop, consuming self.  Will continue until
     /// the task reaches some shutdown condition
-    pub fn run(mut self) -> JoinHandle<()> {
-        spawn(async move {
-            loop {
-                match self.event_receiver.recv_direct().await {
-                    Ok(event) => {
-                        if S::should_shutdown(&event) {
-                            self.state.shutdown().await;
-                            break;
-                        }
-                        if self.state.filter(&event) {
-                            continue;
-                        }
-                        if let Some(res) = S::handle_event(event, &mut self).await {
-                            self.state.handle_result(&res).await;
-                            self.state.shutdown().await;
-                            break;
-                        }
+    pub fn run(self) -> JoinHandle<()> {
+        spawn(self.run_loop())
+    }
+
+    /// Spawn the task loop on `pool`, consuming self, see [`Task::run`]
+    pub fn run_on(self, pool: &TaskPool) -> JoinHandle<()> {
+        pool.spawn(self.run_loop())
+    }
+
+    /// The task loop
+    async fn run_loop(mut self) {
+        loop {
+            match self.event_receiver.recv_direct().await {
+                Ok(event) => {
+                    if S::should_shutdown(&event) {
+                        self.state.shutdown().await;
+                        break;
                     }
-                    Err(e) => {
-                        tracing::error!("Failed to receiving from event stream Error: {}", e);
+                    #[cfg(any(test, feature = "testing"))]
+                    if let Some(gate) = &self.pause_gate {
+                        gate.wait().await;
+                    }
+                    if self.state.filter(&event) {
+                        continue;
                     }
+                    if let Some(res) = S::handle_event(event, &mut self).await {
+                        self.state.handle_result(&res).await;
+                        self.state.shutdown().await;
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to receiving from event stream Error: {}", e);
                 }
             }
-        })
+        }
     }
 
     /// Create a new event `Receiver` from this Task's receiver.
@@ -174,6 +202,8 @@ impl<S: TaskState + Send + 'static> Task<S> {
             event_receiver: self.subscribe(),
             registry: self.registry.clone(),
             state,
+            #[cfg(any(test, feature = "testing"))]
+            pause_gate: self.pause_gate.clone(),
         };
         // Note: await here is only awaiting the task to be added to the
         // registry, not for the task to run.
@@ -295,6 +325,13 @@ impl TaskRegistry {
     {
         self.register(task.run()).await;
     }
+    /// Take a task, run it on `pool`, and register it
+    pub async fn run_task_on<S>(&self, task: Task<S>, pool: &TaskPool)
+    where
+        S: TaskState + Send + 'static,
+    {
+        self.register(task.run_on(pool)).await;
+    }
     /// Create a new `DependencyTask` run it, and register it
     pub async fn spawn_dependency_task<T, H>(
         &self,
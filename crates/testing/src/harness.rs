@@ -0,0 +1,186 @@
+use std::{marker::PhantomData, time::Duration};
+
+use hotshot::traits::{NetworkReliability, NodeImplementation, TestableNodeImplementation};
+use hotshot_example_types::state_types::TestInstanceState;
+use hotshot_types::{
+    message::Message,
+    traits::{network::ConnectedNetwork, node_implementation::NodeType},
+};
+
+use crate::{
+    completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+    overall_safety_task::OverallSafetyPropertiesDescription,
+    spinning_task::{ChangeNode, UpDown},
+    test_builder::{TestMetadata, TimingData},
+    test_launcher::Generator,
+    view_sync_task::ViewSyncTaskDescription,
+};
+
+/// Builder for an in-process network of `HotShot` nodes, for integration tests of crates
+/// embedding `HotShot`.
+///
+/// The network and storage implementations are those of `I`, e.g.
+/// `hotshot_example_types::node_types::MemoryImpl`; storage can be overridden with
+/// [`TestHarness::storage`]. The run succeeds once [`TestHarness::rounds`] views have been
+/// decided, and panics if that does not happen within [`TestHarness::timeout`] or if the
+/// success criteria are violated.
+///
+/// ```ignore
+/// TestHarness::<TestTypes, MemoryImpl>::new()
+///     .nodes(10)
+///     .faulty_nodes([8, 9], 5)
+///     .rounds(20)
+///     .run()
+///     .await;
+/// ```
+pub struct TestHarness<TYPES: NodeType, I: TestableNodeImplementation<TYPES>> {
+    /// the test being built
+    metadata: TestMetadata,
+    /// overrides the storage of `I`
+    storage: Option<Generator<<I as NodeImplementation<TYPES>>::Storage>>,
+    /// phantom for `TYPES`
+    _pd: PhantomData<TYPES>,
+}
+
+impl<TYPES: NodeType, I: TestableNodeImplementation<TYPES>> Default for TestHarness<TYPES, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TYPES: NodeType, I: TestableNodeImplementation<TYPES>> TestHarness<TYPES, I> {
+    /// A harness for the default test: 6 nodes, all honest, all in the DA committee
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_metadata(TestMetadata::default())
+    }
+
+    /// A harness starting from an existing test description
+    #[must_use]
+    pub fn from_metadata(metadata: TestMetadata) -> Self {
+        Self {
+            metadata,
+            storage: None,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Run `total_nodes` nodes, all of them in the DA committee and started at once
+    #[must_use]
+    pub fn nodes(mut self, total_nodes: usize) -> Self {
+        self.metadata.total_nodes = total_nodes;
+        self.metadata.start_nodes = total_nodes;
+        self.metadata.num_bootstrap_nodes = total_nodes;
+        self.metadata.da_committee_size = total_nodes;
+        self.metadata.view_sync_properties = ViewSyncTaskDescription::Threshold(0, total_nodes);
+        self
+    }
+
+    /// Only place the first `da_committee_size` nodes in the DA committee
+    #[must_use]
+    pub fn da_committee_size(mut self, da_committee_size: usize) -> Self {
+        self.metadata.da_committee_size = da_committee_size;
+        self
+    }
+
+    /// Crash `nodes` once `view` is reached. They stop taking part in consensus entirely.
+    #[must_use]
+    pub fn faulty_nodes(mut self, nodes: impl IntoIterator<Item = usize>, view: u64) -> Self {
+        let changes = nodes
+            .into_iter()
+            .map(|idx| ChangeNode {
+                idx,
+                updown: UpDown::Down,
+            })
+            .collect();
+        self.metadata
+            .spinning_properties
+            .node_changes
+            .push((view, changes));
+        self
+    }
+
+    /// Give node `i` a stake of `weights[i]`, and nodes past the end of `weights` a stake of 1
+    #[must_use]
+    pub fn stake_weights(mut self, weights: impl IntoIterator<Item = u64>) -> Self {
+        self.metadata.stake_weights = weights.into_iter().collect();
+        self
+    }
+
+    /// Succeed once `rounds` views have been decided
+    #[must_use]
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.metadata.overall_safety_properties.num_successful_views = rounds;
+        self
+    }
+
+    /// Replace the criteria the run is checked against. The number of rounds set by
+    /// [`TestHarness::rounds`] is part of these.
+    #[must_use]
+    pub fn success_criteria(mut self, criteria: OverallSafetyPropertiesDescription) -> Self {
+        self.metadata.overall_safety_properties = criteria;
+        self
+    }
+
+    /// Fail the run if it has not succeeded after `timeout`
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.metadata.completion_task_description =
+            CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+                TimeBasedCompletionTaskDescription { duration: timeout },
+            );
+        self
+    }
+
+    /// Time views with `timing_data`
+    #[must_use]
+    pub fn timing(mut self, timing_data: TimingData) -> Self {
+        self.metadata.timing_data = timing_data;
+        self
+    }
+
+    /// Delay, drop or duplicate messages according to `reliability`
+    #[must_use]
+    pub fn unreliable_network(mut self, reliability: Box<dyn NetworkReliability>) -> Self {
+        self.metadata.unreliable_network = Some(reliability);
+        self
+    }
+
+    /// Give each node the storage built by `storage` from its index, instead of the temporary
+    /// storage of `I`
+    #[must_use]
+    pub fn storage(
+        mut self,
+        storage: Generator<<I as NodeImplementation<TYPES>>::Storage>,
+    ) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// The test described so far
+    #[must_use]
+    pub fn metadata(&self) -> &TestMetadata {
+        &self.metadata
+    }
+}
+
+impl<
+        TYPES: NodeType<InstanceState = TestInstanceState>,
+        I: TestableNodeImplementation<TYPES>,
+        N: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>,
+    > TestHarness<TYPES, I>
+where
+    I: TestableNodeImplementation<TYPES, CommitteeElectionConfig = TYPES::ElectionConfigType>,
+    I: NodeImplementation<TYPES, QuorumNetwork = N, CommitteeNetwork = N>,
+{
+    /// Spin up the network and run it until it succeeds
+    /// # Panics
+    /// If the run fails
+    pub async fn run(self) {
+        let mut launcher = self.metadata.gen_launcher::<TYPES, I>(0);
+        if let Some(storage) = self.storage {
+            launcher.resource_generator.storage = storage;
+        }
+        launcher.launch::<N>().run_test().await;
+    }
+}
@@ -15,6 +15,9 @@ pub mod test_builder;
 /// launcher
 pub mod test_launcher;
 
+/// builder for spinning up networks of nodes from other crates' integration tests
+pub mod harness;
+
 /// runner
 pub mod test_runner;
 
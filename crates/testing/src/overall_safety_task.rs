@@ -176,6 +176,16 @@ impl<TYPES: NodeType, I: TestableNodeImplementation<TYPES>> TestTaskState
                     }
                 }
             }
+            EventType::SafetyViolation { evidence } => {
+                let error = Arc::new(HotShotError::<TYPES>::SafetyViolation {
+                    view_number,
+                    context: format!("{evidence:?}"),
+                });
+                task.state_mut()
+                    .ctx
+                    .insert_error_to_context(view_number, idx, error);
+                None
+            }
             EventType::ReplicaViewTimeout { view_number } => {
                 let error = Arc::new(HotShotError::<TYPES>::ViewTimeoutError {
                     view_number,
@@ -1,4 +1,9 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration,
+};
+
+use async_compatibility_layer::art::{async_sleep, async_spawn};
 
 use hotshot::traits::TestableNodeImplementation;
 
@@ -113,6 +118,36 @@ impl<TYPES: NodeType, I: TestableNodeImplementation<TYPES>> TestTaskState
                                 handle.networks.1.pause();
                             }
                         }
+                        UpDown::Partition {
+                            reachable,
+                            heal_after,
+                        } => {
+                            let reachable: BTreeSet<_> = reachable
+                                .iter()
+                                .filter_map(|node| state.handles.get(*node))
+                                .map(|node| node.handle.get_public_key())
+                                .collect();
+                            if let Some(handle) = state.handles.get(idx) {
+                                tracing::error!("Node {} partitioned for {:?}", idx, heal_after);
+                                let networks = handle.networks.clone();
+                                for network in [&networks.0, &networks.1] {
+                                    if let Err(e) =
+                                        network.set_partition(Some(reachable.clone())).await
+                                    {
+                                        tracing::error!("Node {} not partitioned: {}", idx, e);
+                                    }
+                                }
+                                async_spawn(async move {
+                                    async_sleep(heal_after).await;
+                                    tracing::error!("Node {} partition healing", idx);
+                                    for network in [&networks.0, &networks.1] {
+                                        if let Err(e) = network.set_partition(None).await {
+                                            tracing::error!("Node {} not healed: {}", idx, e);
+                                        }
+                                    }
+                                });
+                            }
+                        }
                     }
                 }
             }
@@ -136,6 +171,14 @@ pub enum UpDown {
     NetworkUp,
     /// spin the node's network down
     NetworkDown,
+    /// partition the node's networks from every node but `reachable`, by index, until they
+    /// heal after `heal_after`
+    Partition {
+        /// the nodes the node may still exchange messages with
+        reachable: Vec<usize>,
+        /// how long the partition lasts; views may not advance meanwhile, so it heals on a timer
+        heal_after: Duration,
+    },
 }
 
 /// denotes a change in node state
@@ -11,6 +11,7 @@ use crate::test_builder::TestMetadata;
 use commit::Committable;
 use ethereum_types::U256;
 use hotshot::{
+    builder::SystemContextBuilder,
     types::{BLSPubKey, SignatureKey, SystemContextHandle},
     HotShotConsensusApi, HotShotInitializer, Memberships, Networks, SystemContext,
 };
@@ -19,6 +20,7 @@ use hotshot_types::{
     consensus::ConsensusMetricsValue,
     data::{Leaf, QuorumProposal, VidScheme, ViewNumber},
     message::Proposal,
+    signing::{self, SigningDomain},
     simple_certificate::QuorumCertificate,
     simple_vote::SimpleVote,
     traits::{
@@ -30,6 +32,7 @@ use hotshot_types::{
         BlockPayload,
     },
     vote::HasViewNumber,
+    HotShotConfig,
 };
 
 use async_broadcast::{Receiver, Sender};
@@ -55,13 +58,37 @@ pub async fn build_system_handle(
     Sender<HotShotEvent<TestTypes>>,
     Receiver<HotShotEvent<TestTypes>>,
 ) {
+    system_context_builder(node_id)
+        .build()
+        .await
+        .expect("Could not init hotshot")
+}
+
+/// create a [`SystemContextBuilder`] from a node id, with every required part given
+/// # Panics
+/// if cannot create a [`HotShotInitializer`]
+#[must_use]
+pub fn system_context_builder(node_id: u64) -> SystemContextBuilder<TestTypes, MemoryImpl> {
+    system_context_builder_with_config(node_id, |_| {})
+}
+
+/// create a [`SystemContextBuilder`] from a node id, with every required part given, and its
+/// configuration changed by `configure`
+/// # Panics
+/// if cannot create a [`HotShotInitializer`]
+#[must_use]
+pub fn system_context_builder_with_config(
+    node_id: u64,
+    configure: impl FnOnce(&mut HotShotConfig<BLSPubKey, <TestTypes as NodeType>::ElectionConfigType>),
+) -> SystemContextBuilder<TestTypes, MemoryImpl> {
     let builder = TestMetadata::default_multiple_rounds();
 
     let launcher = builder.gen_launcher::<TestTypes, MemoryImpl>(node_id);
 
     let networks = (launcher.resource_generator.channel_generator)(node_id);
     let storage = (launcher.resource_generator.storage)(node_id);
-    let config = launcher.resource_generator.config.clone();
+    let mut config = launcher.resource_generator.config.clone();
+    configure(&mut config);
 
     let initializer = HotShotInitializer::<TestTypes>::from_genesis(&TestInstanceState {}).unwrap();
 
@@ -106,19 +133,12 @@ pub async fn build_system_handle(
         ),
     };
 
-    SystemContext::init(
-        public_key,
-        private_key,
-        node_id,
-        config,
-        storage,
-        memberships,
-        networks_bundle,
-        initializer,
-        ConsensusMetricsValue::default(),
-    )
-    .await
-    .expect("Could not init hotshot")
+    SystemContext::builder(public_key, private_key, node_id, config)
+        .storage(storage)
+        .memberships(memberships)
+        .networks(networks_bundle)
+        .initializer(initializer)
+        .metrics(ConsensusMetricsValue::default())
 }
 
 /// create certificate
@@ -232,7 +252,7 @@ async fn build_quorum_proposal_and_signature(
     // every event input is seen on the event stream in the output.
     let block = <TestBlockPayload as TestableBlock>::genesis();
     let payload_commitment = vid_commitment(
-        &block.encode().unwrap().collect(),
+        &block.encode().unwrap().collect::<Vec<u8>>(),
         handle
             .hotshot
             .inner
@@ -240,6 +260,7 @@ async fn build_quorum_proposal_and_signature(
             .quorum_membership
             .total_nodes(),
     );
+    let transactions_root = block.transactions_root(&());
     let mut parent_state = Arc::new(<TestValidatedState as ValidatedState>::from_header(
         &parent_leaf.block_header,
     ));
@@ -248,7 +269,9 @@ async fn build_quorum_proposal_and_signature(
         &TestInstanceState {},
         &parent_leaf.block_header,
         payload_commitment,
+        transactions_root,
         (),
+        Vec::new(),
     );
     // current leaf that can be re-assigned everytime when entering a new view
     let mut leaf = Leaf {
@@ -258,10 +281,15 @@ async fn build_quorum_proposal_and_signature(
         block_header: block_header.clone(),
         block_payload: None,
         proposer_id: *api.public_key(),
+        governance: Vec::new(),
     };
 
-    let mut signature = <BLSPubKey as SignatureKey>::sign(private_key, leaf.commit().as_ref())
-        .expect("Failed to sign leaf commitment!");
+    let mut signature = signing::sign::<BLSPubKey>(
+        private_key,
+        SigningDomain::QuorumProposal,
+        leaf.commit().as_ref(),
+    )
+    .expect("Failed to sign leaf commitment!");
     let mut proposal = QuorumProposal::<TestTypes> {
         block_header: block_header.clone(),
         view_number: ViewNumber::new(1),
@@ -269,6 +297,7 @@ async fn build_quorum_proposal_and_signature(
         timeout_certificate: None,
         upgrade_certificate: None,
         proposer_id: leaf.proposer_id,
+        governance: Vec::new(),
     };
 
     // Only view 2 is tested, higher views are not tested
@@ -309,24 +338,39 @@ async fn build_quorum_proposal_and_signature(
         );
         // create a new leaf for the current view
         let parent_leaf = leaf.clone();
+        // the header of each view extends the header of the view before it
+        let block_header_new_view = TestBlockHeader::new(
+            &*state_new_view,
+            &TestInstanceState {},
+            &parent_leaf.block_header,
+            payload_commitment,
+            transactions_root,
+            (),
+            Vec::new(),
+        );
         let leaf_new_view = Leaf {
             view_number: ViewNumber::new(cur_view),
             justify_qc: created_qc.clone(),
             parent_commitment: parent_leaf.commit(),
-            block_header: block_header.clone(),
+            block_header: block_header_new_view.clone(),
             block_payload: None,
             proposer_id: quorum_membership.get_leader(ViewNumber::new(cur_view)),
+            governance: Vec::new(),
         };
-        let signature_new_view =
-            <BLSPubKey as SignatureKey>::sign(private_key, leaf_new_view.commit().as_ref())
-                .expect("Failed to sign leaf commitment!");
+        let signature_new_view = signing::sign::<BLSPubKey>(
+            private_key,
+            SigningDomain::QuorumProposal,
+            leaf_new_view.commit().as_ref(),
+        )
+        .expect("Failed to sign leaf commitment!");
         let proposal_new_view = QuorumProposal::<TestTypes> {
-            block_header: block_header.clone(),
+            block_header: block_header_new_view,
             view_number: ViewNumber::new(cur_view),
             justify_qc: created_qc,
             timeout_certificate: None,
             upgrade_certificate: None,
             proposer_id: leaf_new_view.clone().proposer_id,
+            governance: Vec::new(),
         };
         proposal = proposal_new_view;
         signature = signature_new_view;
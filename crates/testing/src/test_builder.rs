@@ -4,8 +4,13 @@ use hotshot_types::traits::election::Membership;
 use std::{num::NonZeroUsize, sync::Arc, time::Duration};
 
 use hotshot::traits::{NodeImplementation, TestableNodeImplementation};
+use hotshot_constants::{
+    DEFAULT_DA_FALLBACK_VIEWS, DEFAULT_STALE_VIEW_GRACE_VIEWS,
+    DEFAULT_SYSTEM_TRANSACTIONS_PER_BLOCK,
+};
 
 use hotshot_types::{
+    payload_store::PayloadSpillConfig, rpc::RpcConfig, storage_sync::StorageSyncConfig,
     traits::node_implementation::NodeType, ExecutionType, HotShotConfig, ValidatorConfig,
 };
 
@@ -63,6 +68,8 @@ pub struct TestMetadata {
     pub unreliable_network: Option<Box<dyn NetworkReliability>>,
     /// view sync check task
     pub view_sync_properties: ViewSyncTaskDescription,
+    /// stake of each node, by node id. Nodes past the end of the list have a stake of 1.
+    pub stake_weights: Vec<u64>,
 }
 
 impl Default for TimingData {
@@ -193,11 +200,22 @@ impl Default for TestMetadata {
             ),
             unreliable_network: None,
             view_sync_properties: ViewSyncTaskDescription::Threshold(0, num_nodes),
+            stake_weights: Vec::new(),
         }
     }
 }
 
 impl TestMetadata {
+    /// The stake of node `node_id`
+    #[must_use]
+    pub fn stake_of(&self, node_id: u64) -> u64 {
+        usize::try_from(node_id)
+            .ok()
+            .and_then(|index| self.stake_weights.get(index))
+            .copied()
+            .unwrap_or(1)
+    }
+
     /// turn a description of a test (e.g. a [`TestMetadata`]) into
     /// a [`TestLauncher`] that can be used to launch the test.
     /// # Panics
@@ -225,7 +243,11 @@ impl TestMetadata {
         let known_nodes_with_stake = (0..total_nodes)
             .map(|node_id_| {
                 let cur_validator_config: ValidatorConfig<TYPES::SignatureKey> =
-                    ValidatorConfig::generated_from_seed_indexed([0u8; 32], node_id_ as u64, 1);
+                    ValidatorConfig::generated_from_seed_indexed(
+                        [0u8; 32],
+                        node_id_ as u64,
+                        self.stake_of(node_id_ as u64),
+                    );
 
                 cur_validator_config
                     .public_key
@@ -233,25 +255,32 @@ impl TestMetadata {
             })
             .collect();
         // But now to test validator's config, we input the info of my_own_validator from config file when node_id == 0.
-        let mut my_own_validator_config =
-            ValidatorConfig::generated_from_seed_indexed([0u8; 32], node_id, 1);
+        let mut my_own_validator_config = ValidatorConfig::generated_from_seed_indexed(
+            [0u8; 32],
+            node_id,
+            self.stake_of(node_id),
+        );
         if node_id == 0 {
             my_own_validator_config = ValidatorConfig::from(ValidatorConfigFile::from_file(
                 "config/ValidatorConfigFile.toml",
             ));
+            my_own_validator_config.stake_value = self.stake_of(0);
         }
         // let da_committee_nodes = known_nodes[0..da_committee_size].to_vec();
         let config = HotShotConfig {
+            chain_id: 0,
             // TODO this doesn't exist anymore
             execution_type: ExecutionType::Incremental,
             total_nodes: NonZeroUsize::new(total_nodes).unwrap(),
             num_bootstrap: num_bootstrap_nodes,
             min_transactions,
             max_transactions: NonZeroUsize::new(99999).unwrap(),
+            system_transactions_per_block: DEFAULT_SYSTEM_TRANSACTIONS_PER_BLOCK,
             known_nodes_with_stake,
             my_own_validator_config,
             da_committee_size,
             next_view_timeout: 500,
+            proposal_timeout: 250,
             timeout_ratio: (11, 10),
             round_start_delay: 1,
             start_delay: 1,
@@ -262,6 +291,23 @@ impl TestMetadata {
             election_config: Some(TYPES::Membership::default_election_config(
                 total_nodes as u64,
             )),
+            payload_spill: PayloadSpillConfig::default(),
+            legacy_signing: false,
+            stale_view_grace: DEFAULT_STALE_VIEW_GRACE_VIEWS,
+            vote_batch_delay_ms: 0,
+            vote_progress_steps: None,
+            parallel_state_application: false,
+            availability_samples: 0,
+            da_unicast_payload: false,
+            da_compact_proposals: false,
+            da_fallback_after_failures: None,
+            da_fallback_views: DEFAULT_DA_FALLBACK_VIEWS,
+            stake_table_retention_views: None,
+            view_artifact_retention_views: None,
+            epoch_length: None,
+            rpc: RpcConfig::default(),
+            stall_timeout: None,
+            storage_sync: StorageSyncConfig::default(),
         };
         let TimingData {
             next_view_timeout,
@@ -275,6 +321,7 @@ impl TestMetadata {
             // TODO this should really be using the timing config struct
             |a: &mut HotShotConfig<TYPES::SignatureKey, TYPES::ElectionConfigType>| {
                 a.next_view_timeout = next_view_timeout;
+                a.proposal_timeout = next_view_timeout / 2;
                 a.timeout_ratio = timeout_ratio;
                 a.round_start_delay = round_start_delay;
                 a.start_delay = start_delay;
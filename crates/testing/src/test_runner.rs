@@ -23,7 +23,7 @@ use hotshot_task::task::{Task, TaskRegistry, TestTask};
 use hotshot_types::{
     consensus::ConsensusMetricsValue,
     traits::{
-        election::Membership,
+        election::{ElectionConfig, Membership},
         node_implementation::{ConsensusTime, NodeType},
     },
     HotShotConfig, ValidatorConfig,
@@ -313,8 +313,11 @@ where
                 HotShotInitializer::<TYPES>::from_genesis(&TestInstanceState {}).unwrap();
             let networks = (self.launcher.resource_generator.channel_generator)(node_id);
             // We assign node's public key and stake value rather than read from config file since it's a test
-            let validator_config =
-                ValidatorConfig::generated_from_seed_indexed([0u8; 32], node_id, 1);
+            let validator_config = ValidatorConfig::generated_from_seed_indexed(
+                [0u8; 32],
+                node_id,
+                self.launcher.metadata.stake_of(node_id),
+            );
             let hotshot = self
                 .add_node_with_config(
                     networks.clone(),
@@ -365,7 +368,8 @@ where
         let quorum_election_config = config.election_config.clone().unwrap_or_else(|| {
             TYPES::Membership::default_election_config(config.total_nodes.get() as u64)
         });
-        let committee_election_config = I::committee_election_config_generator();
+        let committee_election_config =
+            quorum_election_config.da_config(config.da_committee_size as u64);
         let network_bundle = hotshot::Networks {
             quorum_network: networks.0.clone(),
             da_network: networks.1.clone(),
@@ -379,7 +383,7 @@ where
             ),
             da_membership: <TYPES as NodeType>::Membership::create_election(
                 known_nodes_with_stake.clone(),
-                committee_election_config(config.da_committee_size as u64),
+                committee_election_config,
             ),
             vid_membership: <TYPES as NodeType>::Membership::create_election(
                 known_nodes_with_stake.clone(),
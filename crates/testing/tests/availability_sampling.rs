@@ -0,0 +1,148 @@
+use hotshot::{
+    types::{BLSPubKey, SignatureKey},
+    HotShotConsensusApi,
+};
+use hotshot_example_types::{
+    block_types::TestTransaction,
+    node_types::{MemoryImpl, TestTypes},
+};
+use hotshot_task_impls::{
+    availability_sampling::AvailabilitySamplingTaskState, events::HotShotEvent,
+};
+use hotshot_testing::task_helpers::{build_system_handle, vid_init};
+use hotshot_types::{
+    data::{VidDisperse, VidSample, VidSchemeTrait, ViewNumber},
+    message::Proposal,
+    signing::{self, SigningDomain},
+    traits::{
+        block_contents::vid_commitment,
+        consensus_api::ConsensusApi,
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+    },
+};
+use std::{collections::HashMap, marker::PhantomData};
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_availability_sampling_serves_shares() {
+    use hotshot_task_impls::harness::run_harness;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // Node 2 is a DA member, and the leader of view 2.
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let pub_key = *api.public_key();
+    let quorum_membership = handle.hotshot.inner.memberships.quorum_membership.clone();
+
+    let vid = vid_init::<TestTypes>(&quorum_membership, ViewNumber::new(2));
+    let encoded_transactions =
+        TestTransaction::encode(vec![TestTransaction::new(vec![0])]).unwrap();
+    let vid_disperse = vid.disperse(&encoded_transactions).unwrap();
+    let payload_commitment = vid_disperse.commit;
+    let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::VidDisperse,
+        payload_commitment.as_ref(),
+    )
+    .expect("Failed to sign the payload commitment!");
+    let vid_disperse = VidDisperse::from_membership(
+        ViewNumber::new(2),
+        vid_disperse,
+        &quorum_membership.clone().into(),
+    );
+    let vid_proposal = Proposal {
+        data: vid_disperse.clone(),
+        signature,
+        _pd: PhantomData,
+    };
+
+    let owner = BLSPubKey::generated_from_seed_indexed([0u8; 32], 3).0;
+    let (requester, requester_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 7);
+    let sample = VidSample::from_disperse(&vid_disperse, &owner).unwrap();
+    assert!(matches!(
+        vid.verify_share(&sample.share, &sample.common, &payload_commitment),
+        Ok(Ok(()))
+    ));
+
+    let other_payload_commitment = vid_commitment(&[1, 2, 3], quorum_membership.total_nodes());
+    // a request for the share of `owner`, as `requester` signs it with `key`
+    let request = |payload_commitment, view, requester, key| {
+        let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+            key,
+            SigningDomain::VidShareRequest,
+            &VidSample::<TestTypes>::request_bytes(&payload_commitment, view, &owner, &pub_key),
+        )
+        .unwrap();
+        HotShotEvent::AvailabilitySampleRequestRecv(
+            payload_commitment,
+            view,
+            owner,
+            requester,
+            signature,
+        )
+    };
+    let (unstaked, unstaked_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 20);
+
+    let input = vec![
+        HotShotEvent::ViewChange(ViewNumber::new(1)),
+        HotShotEvent::VidDisperseRecv(vid_proposal, pub_key),
+        request(
+            payload_commitment,
+            ViewNumber::new(2),
+            requester,
+            &requester_key,
+        ),
+        // neither a payload of another view nor another payload of the view is served
+        request(
+            payload_commitment,
+            ViewNumber::new(3),
+            requester,
+            &requester_key,
+        ),
+        request(
+            other_payload_commitment,
+            ViewNumber::new(2),
+            requester,
+            &requester_key,
+        ),
+        // nor a request naming a requester which did not sign it, or which has no stake
+        request(
+            payload_commitment,
+            ViewNumber::new(2),
+            requester,
+            &unstaked_key,
+        ),
+        request(
+            payload_commitment,
+            ViewNumber::new(2),
+            unstaked,
+            &unstaked_key,
+        ),
+        HotShotEvent::Shutdown,
+    ];
+    let mut output = HashMap::new();
+    output.insert(
+        HotShotEvent::AvailabilitySampleResponseSend(sample, requester, pub_key),
+        1,
+    );
+
+    let state = AvailabilitySamplingTaskState::new(
+        handle.hotshot.get_consensus(),
+        api.inner.memberships.da_membership.clone().into(),
+        quorum_membership.into(),
+        pub_key,
+        api.private_key().clone(),
+        4,
+        handle.hotshot.inner.output_event_stream.0.clone(),
+        handle.hotshot.inner.id,
+    );
+    run_harness(input, output, state, false).await;
+}
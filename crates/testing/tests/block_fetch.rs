@@ -0,0 +1,208 @@
+use hotshot::HotShotConsensusApi;
+use hotshot_example_types::{
+    block_types::TestTransaction,
+    node_types::{MemoryImpl, TestTypes},
+};
+use hotshot_task_impls::{
+    block_fetch::{BlockFetchTaskState, FetchBlockRequest},
+    events::HotShotEvent,
+};
+use hotshot_types::{
+    data::ViewNumber,
+    traits::{
+        block_contents::vid_commitment, consensus_api::ConsensusApi, election::Membership,
+        node_implementation::ConsensusTime,
+    },
+};
+use std::collections::HashMap;
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn held_payloads_are_checked_against_their_commitment() {
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let total_nodes = api.inner.memberships.quorum_membership.total_nodes();
+    let held = TestTransaction::encode(vec![TestTransaction::new(vec![1])]).unwrap();
+    let held_commitment = vid_commitment(&held, total_nodes);
+    let other = TestTransaction::encode(vec![TestTransaction::new(vec![2])]).unwrap();
+    let other_commitment = vid_commitment(&other, total_nodes);
+
+    // the payload of one proposal of views 2 and 3 is held
+    {
+        let consensus = handle.hotshot.get_consensus();
+        let mut consensus = consensus.write().await;
+        for view in [2, 3] {
+            let stored = consensus
+                .payload_store
+                .intern(held_commitment, held.clone().into());
+            consensus
+                .saved_payloads
+                .insert(ViewNumber::new(view), stored);
+        }
+    }
+
+    let block_fetch_state = BlockFetchTaskState::new(
+        handle.hotshot.get_consensus(),
+        api.inner.memberships.da_membership.clone().into(),
+        api.inner.memberships.quorum_membership.clone().into(),
+        *api.public_key(),
+        None,
+        handle.hotshot.inner.id,
+    );
+    // not served, as the payload held for view 2 is another
+    assert!(block_fetch_state
+        .serve(FetchBlockRequest {
+            payload_commitment: other_commitment,
+            view: ViewNumber::new(2),
+        })
+        .await
+        .is_err());
+    assert_eq!(
+        block_fetch_state
+            .serve(FetchBlockRequest {
+                payload_commitment: held_commitment,
+                view: ViewNumber::new(3),
+            })
+            .await,
+        Ok(held.clone())
+    );
+
+    let input = vec![
+        // fetched from the DA committee rather than taken from view 2
+        HotShotEvent::BlockFetchNeeded(other_commitment, ViewNumber::new(2)),
+        // the payload held for view 3 is the one asked for
+        HotShotEvent::BlockFetchNeeded(held_commitment, ViewNumber::new(3)),
+        HotShotEvent::Shutdown,
+    ];
+    let mut output = HashMap::new();
+    output.insert(
+        HotShotEvent::BlockFetched(held_commitment, ViewNumber::new(3), held.into()),
+        1,
+    );
+    run_harness(input, output, block_fetch_state, false).await;
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn fetched_payloads_are_checked_against_their_commitment() {
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let total_nodes = api.inner.memberships.quorum_membership.total_nodes();
+    let payload = TestTransaction::encode(vec![TestTransaction::new(vec![1])]).unwrap();
+    let payload_commitment = vid_commitment(&payload, total_nodes);
+    let forged = TestTransaction::encode(vec![TestTransaction::new(vec![2])]).unwrap();
+
+    let input = vec![
+        HotShotEvent::BlockFetchNeeded(payload_commitment, ViewNumber::new(2)),
+        // a response which is not the payload is dropped, and the fetch goes on
+        HotShotEvent::BlockFetchResponseRecv(payload_commitment, ViewNumber::new(2), forged.into()),
+        HotShotEvent::BlockFetchResponseRecv(
+            payload_commitment,
+            ViewNumber::new(2),
+            payload.clone().into(),
+        ),
+        HotShotEvent::Shutdown,
+    ];
+    let mut output = HashMap::new();
+    output.insert(
+        HotShotEvent::BlockFetched(payload_commitment, ViewNumber::new(2), payload.into()),
+        1,
+    );
+
+    let block_fetch_state = BlockFetchTaskState::new(
+        handle.hotshot.get_consensus(),
+        api.inner.memberships.da_membership.clone().into(),
+        api.inner.memberships.quorum_membership.clone().into(),
+        *api.public_key(),
+        None,
+        handle.hotshot.inner.id,
+    );
+    run_harness(input, output, block_fetch_state, false).await;
+}
+
+/// The budget of the view is checked before any payload is looked up, and the payloads of the
+/// payload store are served as the commitment they are stored under says
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn requests_over_the_view_budget_are_refused_first() {
+    use hotshot_constants::BLOCK_FETCH_MAX_BYTES_PER_VIEW;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let total_nodes = api.inner.memberships.quorum_membership.total_nodes();
+    let small = TestTransaction::encode(vec![TestTransaction::new(vec![1])]).unwrap();
+    let commitment = vid_commitment(&small, total_nodes);
+    // half of the budget, stored under a commitment which is not recomputed
+    let half = vec![0u8; BLOCK_FETCH_MAX_BYTES_PER_VIEW / 2];
+    let _stored = handle
+        .hotshot
+        .get_consensus()
+        .write()
+        .await
+        .payload_store
+        .intern(commitment, half.clone().into());
+
+    let block_fetch_state = BlockFetchTaskState::new(
+        handle.hotshot.get_consensus(),
+        api.inner.memberships.da_membership.clone().into(),
+        api.inner.memberships.quorum_membership.clone().into(),
+        *api.public_key(),
+        None,
+        handle.hotshot.inner.id,
+    );
+    let request = FetchBlockRequest {
+        payload_commitment: commitment,
+        view: ViewNumber::new(2),
+    };
+    for _ in 0..2 {
+        assert_eq!(
+            block_fetch_state.serve(request.clone()).await,
+            Ok(half.clone())
+        );
+    }
+    // the budget is spent, so even a payload this node does not hold is refused for it
+    let other = TestTransaction::encode(vec![TestTransaction::new(vec![2])]).unwrap();
+    assert_eq!(
+        block_fetch_state
+            .serve(FetchBlockRequest {
+                payload_commitment: vid_commitment(&other, total_nodes),
+                view: ViewNumber::new(2),
+            })
+            .await,
+        Err("bandwidth limit reached".to_string())
+    );
+}
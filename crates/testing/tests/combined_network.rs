@@ -21,8 +21,8 @@ use hotshot_example_types::block_types::TestTransaction;
 #[cfg_attr(async_executor_impl = "async-std", async_std::test)]
 #[instrument]
 async fn test_hash_calculation() {
-    let message1 = TestTransaction(vec![0; 32]);
-    let message2 = TestTransaction(vec![1; 32]);
+    let message1 = TestTransaction::new(vec![0; 32]);
+    let message2 = TestTransaction::new(vec![1; 32]);
 
     assert_eq!(calculate_hash_of(&message1), calculate_hash_of(&message1));
     assert_ne!(calculate_hash_of(&message1), calculate_hash_of(&message2));
@@ -36,8 +36,8 @@ async fn test_hash_calculation() {
 #[cfg_attr(async_executor_impl = "async-std", async_std::test)]
 #[instrument]
 async fn test_cache_integrity() {
-    let message1 = TestTransaction(vec![0; 32]);
-    let message2 = TestTransaction(vec![1; 32]);
+    let message1 = TestTransaction::new(vec![0; 32]);
+    let message2 = TestTransaction::new(vec![1; 32]);
 
     let mut cache = Cache::new(3);
 
@@ -59,6 +59,7 @@ async fn build_vote(
         block_header: proposal.block_header,
         block_payload: None,
         proposer_id: membership.get_leader(view),
+        governance: proposal.governance,
     };
     let vote = QuorumVote::<TestTypes>::create_signed_vote(
         QuorumData {
@@ -180,7 +181,6 @@ async fn test_consensus_vote() {
 #[ignore]
 async fn test_consensus_with_vid() {
     use hotshot::traits::BlockPayload;
-    use hotshot::types::SignatureKey;
     use hotshot_example_types::block_types::TestBlockPayload;
     use hotshot_example_types::block_types::TestTransaction;
     use hotshot_task_impls::harness::run_harness;
@@ -193,7 +193,10 @@ async fn test_consensus_with_vid() {
     use hotshot_types::simple_vote::DAVote;
     use hotshot_types::traits::block_contents::{vid_commitment, TestableBlock};
     use hotshot_types::{
-        data::VidDisperse, message::Proposal, traits::node_implementation::NodeType,
+        data::VidDisperse,
+        message::Proposal,
+        signing::{self, SigningDomain},
+        traits::node_implementation::NodeType,
     };
     use std::marker::PhantomData;
 
@@ -212,14 +215,17 @@ async fn test_consensus_with_vid() {
     let pub_key = *api.public_key();
     let quorum_membership = handle.hotshot.inner.memberships.quorum_membership.clone();
     let vid = vid_init::<TestTypes>(&quorum_membership, ViewNumber::new(2));
-    let transactions = vec![TestTransaction(vec![0])];
+    let transactions = vec![TestTransaction::new(vec![0])];
     let encoded_transactions = TestTransaction::encode(transactions.clone()).unwrap();
     let vid_disperse = vid.disperse(&encoded_transactions).unwrap();
     let payload_commitment = vid_disperse.commit;
 
-    let vid_signature =
-        <TestTypes as NodeType>::SignatureKey::sign(api.private_key(), payload_commitment.as_ref())
-            .expect("Failed to sign payload commitment");
+    let vid_signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::VidDisperse,
+        payload_commitment.as_ref(),
+    )
+    .expect("Failed to sign payload commitment");
     let vid_disperse = vid.disperse(&encoded_transactions).unwrap();
     let vid_disperse_inner = VidDisperse::from_membership(
         ViewNumber::new(2),
@@ -245,11 +251,12 @@ async fn test_consensus_with_vid() {
     let proposal_view2 = build_quorum_proposal(&handle, &private_key_view2, 2).await;
     let block = <TestBlockPayload as TestableBlock>::genesis();
     let da_payload_commitment = vid_commitment(
-        &block.encode().unwrap().collect(),
+        &block.encode().unwrap().collect::<Vec<u8>>(),
         quorum_membership.total_nodes(),
     );
     let da_data = DAData {
         payload_commit: da_payload_commitment,
+        governance: Vec::new(),
     };
     let created_dac_view2 =
         build_cert::<TestTypes, DAData, DAVote<TestTypes>, DACertificate<TestTypes>>(
@@ -286,3 +293,204 @@ async fn test_consensus_with_vid() {
 
     run_harness(input, output, consensus_state, false).await;
 }
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn forged_proposals_do_not_penalize_the_leader() {
+    use hotshot::tasks::create_consensus_state;
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let (_, leader) = key_pair_for_id(1);
+    let (forger_key, _) = key_pair_for_id(3);
+
+    // a proposal of view 1 with an invalid justify QC, claiming to be from its leader but
+    // signed by another node
+    let mut forged = build_quorum_proposal(&handle, &forger_key, 1).await;
+    forged.data.justify_qc.is_genesis = false;
+    forged.data.justify_qc.signatures = None;
+
+    let input = vec![
+        HotShotEvent::QuorumProposalRecv(forged, leader),
+        HotShotEvent::Shutdown,
+    ];
+    let consensus_state =
+        create_consensus_state(handle.hotshot.inner.output_event_stream.0.clone(), &handle).await;
+    run_harness(input, HashMap::new(), consensus_state, false).await;
+
+    let consensus = handle.hotshot.get_consensus();
+    let invalid_proposals = consensus
+        .read()
+        .await
+        .leader_scores
+        .get(&leader)
+        .map_or(0, |score| score.invalid_proposals);
+    assert_eq!(invalid_proposals, 0);
+}
+
+/// Receive the events sent by the consensus task until `until` matches one, which is returned
+async fn recv_until(
+    events: &mut async_broadcast::Receiver<HotShotEvent<TestTypes>>,
+    until: impl Fn(&HotShotEvent<TestTypes>) -> bool,
+) -> HotShotEvent<TestTypes> {
+    async_compatibility_layer::art::async_timeout(std::time::Duration::from_secs(2), async {
+        loop {
+            let event = events.recv().await.expect("The event stream closed");
+            if until(&event) {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("The consensus task did not send the expected event")
+}
+
+/// With parallel state application the replica votes once the state of the proposal is applied
+/// on a worker task, and an application whose outcome is lost is given up on a later timeout
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_consensus_vote_with_parallel_state_application() {
+    use hotshot::tasks::create_consensus_state;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let (private_key, public_key) = key_pair_for_id(1);
+    let proposal = build_quorum_proposal(&handle, &private_key, 1).await;
+    let GeneralConsensusMessage::Vote(vote) = build_vote(&handle, proposal.data.clone()).await
+    else {
+        panic!("Failed to build the vote");
+    };
+
+    let mut consensus_state =
+        create_consensus_state(handle.hotshot.inner.output_event_stream.0.clone(), &handle).await;
+    consensus_state.parallel_state_application = true;
+    let (tx, mut rx) = async_broadcast::broadcast(1024);
+
+    consensus_state
+        .handle(
+            HotShotEvent::QuorumProposalRecv(proposal.clone(), public_key),
+            tx.clone(),
+        )
+        .await;
+    let applied = recv_until(&mut rx, |event| {
+        matches!(event, HotShotEvent::StateApplied(..))
+    })
+    .await;
+    assert!(matches!(
+        applied,
+        HotShotEvent::StateApplied(view, Some(_)) if view == ViewNumber::new(1)
+    ));
+    assert_eq!(consensus_state.state_pipeline.len(), 1);
+
+    consensus_state.handle(applied, tx.clone()).await;
+    let sent = recv_until(&mut rx, |event| {
+        matches!(event, HotShotEvent::QuorumVoteSend(_))
+    })
+    .await;
+    assert_eq!(sent, HotShotEvent::QuorumVoteSend(vote));
+    assert!(consensus_state.state_pipeline.is_empty());
+
+    // the outcome of this application is never handled, so it is pruned when view 2 times out
+    let mut consensus_state =
+        create_consensus_state(handle.hotshot.inner.output_event_stream.0.clone(), &handle).await;
+    consensus_state.parallel_state_application = true;
+    consensus_state
+        .handle(
+            HotShotEvent::QuorumProposalRecv(proposal, public_key),
+            tx.clone(),
+        )
+        .await;
+    assert_eq!(consensus_state.state_pipeline.len(), 1);
+    consensus_state
+        .handle(HotShotEvent::Timeout(ViewNumber::new(2)), tx)
+        .await;
+    assert!(consensus_state.state_pipeline.is_empty());
+}
+
+/// Only proposals signed by their leader are held until the view they extend, so that forged
+/// ones cannot fill the buffer of future-view messages
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn forged_future_proposals_are_not_held() {
+    use hotshot::tasks::create_consensus_state;
+    use hotshot_testing::task_helpers::build_system_handle;
+    use hotshot_types::{
+        signature_key::BLSPubKey,
+        signing::{self, SigningDomain},
+    };
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let (_, first_leader) = key_pair_for_id(1);
+    let (leader_key, leader) = key_pair_for_id(5);
+    let (forger_key, _) = key_pair_for_id(3);
+
+    // a proposal of view 5 extending a leaf of view 4 the node has not seen
+    let mut proposal = build_quorum_proposal(&handle, &leader_key, 1).await;
+    let instance_state = handle.get_consensus().read().await.instance_state.clone();
+    let unseen_parent = Leaf {
+        view_number: ViewNumber::new(4),
+        justify_qc: proposal.data.justify_qc.clone(),
+        parent_commitment: Leaf::genesis(&instance_state).commit(),
+        block_header: proposal.data.block_header.clone(),
+        block_payload: None,
+        proposer_id: first_leader,
+        governance: Vec::new(),
+    };
+    proposal.data.view_number = ViewNumber::new(5);
+    proposal.data.justify_qc.is_genesis = false;
+    proposal.data.justify_qc.view_number = ViewNumber::new(4);
+    proposal.data.justify_qc.data.leaf_commit = unseen_parent.commit();
+    proposal.data.proposer_id = leader;
+    let leaf_commitment = Leaf {
+        view_number: ViewNumber::new(5),
+        justify_qc: proposal.data.justify_qc.clone(),
+        parent_commitment: unseen_parent.commit(),
+        block_header: proposal.data.block_header.clone(),
+        block_payload: None,
+        proposer_id: leader,
+        governance: proposal.data.governance.clone(),
+    }
+    .commit();
+    let sign = |key: &_| {
+        signing::sign::<BLSPubKey>(key, SigningDomain::QuorumProposal, leaf_commitment.as_ref())
+            .unwrap()
+    };
+
+    let (tx, _rx) = async_broadcast::broadcast(1024);
+    let mut consensus_state =
+        create_consensus_state(handle.hotshot.inner.output_event_stream.0.clone(), &handle).await;
+    let mut forged = proposal.clone();
+    forged.signature = sign(&forger_key);
+    consensus_state
+        .handle(HotShotEvent::QuorumProposalRecv(forged, leader), tx.clone())
+        .await;
+    assert!(consensus_state.future_events.is_empty());
+
+    proposal.signature = sign(&leader_key);
+    consensus_state
+        .handle(HotShotEvent::QuorumProposalRecv(proposal, leader), tx)
+        .await;
+    assert_eq!(consensus_state.future_events.len(), 1);
+}
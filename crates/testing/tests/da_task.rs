@@ -1,11 +1,24 @@
-use hotshot::{types::SignatureKey, HotShotConsensusApi};
+use async_lock::RwLock;
+use commit::Committable;
+use hotshot::{
+    types::{BLSPubKey, SignatureKey},
+    HotShotConsensusApi,
+};
 use hotshot_example_types::{
     block_types::TestTransaction,
     node_types::{MemoryImpl, TestTypes},
 };
-use hotshot_task_impls::{da::DATaskState, events::HotShotEvent};
+use hotshot_task_impls::{
+    bus::EventBus,
+    compact_block::AnnouncedTransactions,
+    da::{DAFallbackState, DATaskState},
+    events::HotShotEvent,
+};
 use hotshot_types::{
-    data::{DAProposal, ViewNumber},
+    data::{CompactDAProposal, CompactTransaction, DAProposal, DAProposalCommitment, ViewNumber},
+    message::Proposal,
+    receipt::TransactionIndex,
+    signing::{self, SigningDomain},
     simple_vote::{DAData, DAVote},
     traits::{
         block_contents::vid_commitment,
@@ -15,7 +28,47 @@ use hotshot_types::{
     },
 };
 use sha2::{Digest, Sha256};
-use std::{collections::HashMap, marker::PhantomData};
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+    sync::Arc,
+};
+
+/// `commitment`, signed by the holder of `private_key`
+fn signed_commitment(
+    commitment: DAProposalCommitment<TestTypes>,
+    private_key: &<BLSPubKey as SignatureKey>::PrivateKey,
+) -> Proposal<TestTypes, DAProposalCommitment<TestTypes>> {
+    let signature = signing::sign::<BLSPubKey>(
+        private_key,
+        SigningDomain::DAProposalCommitment,
+        &commitment.signed_bytes(),
+    )
+    .expect("Failed to sign DA proposal commitment");
+    Proposal {
+        data: commitment,
+        signature,
+        _pd: PhantomData,
+    }
+}
+
+/// `compact`, signed by the holder of `private_key`
+fn signed_compact(
+    compact: CompactDAProposal<TestTypes>,
+    private_key: &<BLSPubKey as SignatureKey>::PrivateKey,
+) -> Proposal<TestTypes, CompactDAProposal<TestTypes>> {
+    let signature = signing::sign::<BLSPubKey>(
+        private_key,
+        SigningDomain::CompactDAProposal,
+        &compact.signed_bytes(),
+    )
+    .expect("Failed to sign compact DA proposal");
+    Proposal {
+        data: compact,
+        signature,
+        _pd: PhantomData,
+    }
+}
 
 #[cfg_attr(
     async_executor_impl = "tokio",
@@ -25,7 +78,6 @@ use std::{collections::HashMap, marker::PhantomData};
 async fn test_da_task() {
     use hotshot_task_impls::harness::run_harness;
     use hotshot_testing::task_helpers::build_system_handle;
-    use hotshot_types::message::Proposal;
 
     async_compatibility_layer::logging::setup_logging();
     async_compatibility_layer::logging::setup_backtrace();
@@ -36,7 +88,7 @@ async fn test_da_task() {
         inner: handle.hotshot.inner.clone(),
     };
     let pub_key = *api.public_key();
-    let transactions = vec![TestTransaction(vec![0])];
+    let transactions = vec![TestTransaction::new(vec![0])];
     let encoded_transactions = TestTransaction::encode(transactions.clone()).unwrap();
     let payload_commitment = vid_commitment(
         &encoded_transactions,
@@ -49,11 +101,14 @@ async fn test_da_task() {
     );
     let encoded_transactions_hash = Sha256::digest(&encoded_transactions);
 
-    let signature =
-        <TestTypes as NodeType>::SignatureKey::sign(api.private_key(), &encoded_transactions_hash)
-            .expect("Failed to sign block payload");
+    let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::DAProposal,
+        &encoded_transactions_hash,
+    )
+    .expect("Failed to sign block payload");
     let proposal = DAProposal {
-        encoded_transactions: encoded_transactions.clone(),
+        encoded_transactions: encoded_transactions.clone().into(),
         metadata: (),
         view_number: ViewNumber::new(2),
     };
@@ -74,7 +129,7 @@ async fn test_da_task() {
     input.push(HotShotEvent::ViewChange(ViewNumber::new(1)));
     input.push(HotShotEvent::ViewChange(ViewNumber::new(2)));
     input.push(HotShotEvent::TransactionsSequenced(
-        encoded_transactions.clone(),
+        encoded_transactions.clone().into(),
         (),
         ViewNumber::new(2),
     ));
@@ -86,6 +141,7 @@ async fn test_da_task() {
     let da_vote = DAVote::create_signed_vote(
         DAData {
             payload_commit: payload_commitment,
+            governance: Vec::new(),
         },
         ViewNumber::new(2),
         api.public_key(),
@@ -104,6 +160,476 @@ async fn test_da_task() {
         vote_collector: None.into(),
         public_key: *api.public_key(),
         private_key: api.private_key().clone(),
+        unicast_payload: false,
+        compact_proposals: false,
+        announced: AnnouncedTransactions::new(0),
+        rebuilds: BTreeMap::new(),
+        mempool: handle.hotshot.inner.mempool.clone(),
+        event_bus: EventBus::default(),
+        fallback: DAFallbackState::new(None, 0),
+        tx_index: Arc::new(RwLock::new(TransactionIndex::new(0))),
+        id: handle.hotshot.inner.id,
+    };
+    run_harness(input, output, da_state, false).await;
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_da_task_unicast_payload() {
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // Build the API for node 2.
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let pub_key = *api.public_key();
+    let total_nodes = api.inner.memberships.quorum_membership.total_nodes();
+    let encoded_transactions =
+        TestTransaction::encode(vec![TestTransaction::new(vec![0])]).unwrap();
+    let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::DAProposal,
+        &Sha256::digest(&encoded_transactions),
+    )
+    .expect("Failed to sign block payload");
+    let message = Proposal {
+        data: DAProposal {
+            encoded_transactions: encoded_transactions.clone().into(),
+            metadata: (),
+            view_number: ViewNumber::new(2),
+        },
+        signature,
+        _pd: PhantomData,
+    };
+    let commitment = signed_commitment(
+        DAProposalCommitment::new(&message.data, total_nodes),
+        api.private_key(),
+    );
+
+    let mut input = Vec::new();
+    let mut output = HashMap::new();
+
+    // In view 1, node 2 is the next leader.
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(1)));
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(2)));
+    input.push(HotShotEvent::TransactionsSequenced(
+        encoded_transactions.into(),
+        (),
+        ViewNumber::new(2),
+    ));
+    input.push(HotShotEvent::Shutdown);
+
+    // the payload goes to the committee only, the leader handling its own proposal at once,
+    // and its commitment to everyone
+    output.insert(
+        HotShotEvent::DAProposalCommitteeSend(message.clone(), pub_key),
+        1,
+    );
+    output.insert(
+        HotShotEvent::DAProposalCommitmentSend(commitment, pub_key),
+        1,
+    );
+    output.insert(HotShotEvent::DAProposalRecv(message, pub_key), 1);
+
+    let da_state = DATaskState {
+        api: api.clone(),
+        consensus: handle.hotshot.get_consensus(),
+        da_membership: api.inner.memberships.da_membership.clone().into(),
+        da_network: api.inner.networks.da_network.clone(),
+        quorum_membership: api.inner.memberships.quorum_membership.clone().into(),
+        cur_view: ViewNumber::new(0),
+        vote_collector: None.into(),
+        public_key: pub_key,
+        private_key: api.private_key().clone(),
+        unicast_payload: true,
+        compact_proposals: false,
+        announced: AnnouncedTransactions::new(0),
+        rebuilds: BTreeMap::new(),
+        mempool: handle.hotshot.inner.mempool.clone(),
+        event_bus: EventBus::default(),
+        fallback: DAFallbackState::new(None, 0),
+        tx_index: Arc::new(RwLock::new(TransactionIndex::new(0))),
+        id: handle.hotshot.inner.id,
+    };
+    run_harness(input, output, da_state, false).await;
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_da_task_receives_commitments() {
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::{build_system_handle, key_pair_for_id};
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // Build the API for node 2, outside a DA committee of nodes 5 to 9.
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let committee: Vec<_> = (5..10).map(key_pair_for_id).collect();
+    let da_membership = <TestTypes as NodeType>::Membership::create_election(
+        committee
+            .iter()
+            .map(|(_, public_key)| public_key.get_stake_table_entry(1))
+            .collect(),
+        <TestTypes as NodeType>::Membership::default_election_config(5),
+    );
+    let total_nodes = api.inner.memberships.quorum_membership.total_nodes();
+    let commitment = |view, transaction| {
+        DAProposalCommitment::new(
+            &DAProposal::<TestTypes> {
+                encoded_transactions: TestTransaction::encode(vec![TestTransaction::new(vec![
+                    transaction,
+                ])])
+                .unwrap()
+                .into(),
+                metadata: (),
+                view_number: ViewNumber::new(view),
+            },
+            total_nodes,
+        )
+    };
+    let leader_key = |view| {
+        let leader = da_membership.get_leader(ViewNumber::new(view));
+        committee
+            .iter()
+            .find(|(_, public_key)| *public_key == leader)
+            .map(|(private_key, _)| private_key.clone())
+            .unwrap()
+    };
+    let leader = |view| da_membership.get_leader(ViewNumber::new(view));
+
+    // the signature of the leader of view 2 over one commitment, replayed with another
+    let signed = signed_commitment(commitment(2, 0), &leader_key(2));
+    let replayed = Proposal {
+        data: commitment(2, 1),
+        signature: signed.signature.clone(),
+        _pd: PhantomData,
+    };
+    let genuine = signed_commitment(commitment(3, 0), &leader_key(3));
+    let genuine_commitment = genuine.data.payload_commitment;
+    let input = vec![
+        HotShotEvent::DAProposalCommitmentRecv(replayed, leader(2)),
+        HotShotEvent::DAProposalCommitmentRecv(genuine, leader(3)),
+        HotShotEvent::Shutdown,
+    ];
+
+    let da_state = DATaskState {
+        api: api.clone(),
+        consensus: handle.hotshot.get_consensus(),
+        da_membership: da_membership.clone().into(),
+        da_network: api.inner.networks.da_network.clone(),
+        quorum_membership: api.inner.memberships.quorum_membership.clone().into(),
+        cur_view: ViewNumber::new(0),
+        vote_collector: None.into(),
+        public_key: *api.public_key(),
+        private_key: api.private_key().clone(),
+        unicast_payload: true,
+        compact_proposals: false,
+        announced: AnnouncedTransactions::new(0),
+        rebuilds: BTreeMap::new(),
+        mempool: handle.hotshot.inner.mempool.clone(),
+        event_bus: EventBus::default(),
+        fallback: DAFallbackState::new(None, 0),
+        tx_index: Arc::new(RwLock::new(TransactionIndex::new(0))),
+        id: handle.hotshot.inner.id,
+    };
+    run_harness(input, HashMap::new(), da_state, false).await;
+
+    // only the commitment its leader signed was recorded
+    let consensus = handle.hotshot.get_consensus();
+    let consensus = consensus.read().await;
+    assert!(!consensus
+        .validated_state_map
+        .contains_key(&ViewNumber::new(2)));
+    let recorded = consensus
+        .validated_state_map
+        .get(&ViewNumber::new(3))
+        .and_then(|view| view.get_payload_commitment());
+    assert_eq!(recorded, Some(genuine_commitment));
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_da_task_fallback() {
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // Build the API for node 2.
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let pub_key = *api.public_key();
+    let encoded_transactions =
+        TestTransaction::encode(vec![TestTransaction::new(vec![0])]).unwrap();
+    let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::DAProposal,
+        &Sha256::digest(&encoded_transactions),
+    )
+    .expect("Failed to sign block payload");
+    let message = Proposal {
+        data: DAProposal {
+            encoded_transactions: encoded_transactions.clone().into(),
+            metadata: (),
+            view_number: ViewNumber::new(2),
+        },
+        signature,
+        _pd: PhantomData,
+    };
+
+    let mut input = Vec::new();
+    let mut output = HashMap::new();
+
+    // view 1 ends without a DA certificate, which is enough to fall back
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(1)));
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(2)));
+    input.push(HotShotEvent::TransactionsSequenced(
+        encoded_transactions.into(),
+        (),
+        ViewNumber::new(2),
+    ));
+    input.push(HotShotEvent::Shutdown);
+
+    // the proposal goes to the whole quorum, the leader voting on its own proposal too
+    output.insert(
+        HotShotEvent::DAFallbackProposalSend(message.clone(), pub_key),
+        1,
+    );
+    output.insert(HotShotEvent::DAFallbackProposalRecv(message, pub_key), 1);
+
+    let da_state = DATaskState {
+        api: api.clone(),
+        consensus: handle.hotshot.get_consensus(),
+        da_membership: api.inner.memberships.da_membership.clone().into(),
+        da_network: api.inner.networks.da_network.clone(),
+        quorum_membership: api.inner.memberships.quorum_membership.clone().into(),
+        cur_view: ViewNumber::new(0),
+        vote_collector: None.into(),
+        public_key: pub_key,
+        private_key: api.private_key().clone(),
+        unicast_payload: false,
+        compact_proposals: false,
+        announced: AnnouncedTransactions::new(0),
+        rebuilds: BTreeMap::new(),
+        mempool: handle.hotshot.inner.mempool.clone(),
+        event_bus: EventBus::default(),
+        fallback: DAFallbackState::new(Some(1), 5),
+        tx_index: Arc::new(RwLock::new(TransactionIndex::new(0))),
+        id: handle.hotshot.inner.id,
+    };
+    run_harness(input, output, da_state, false).await;
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_da_task_compact_proposal() {
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // Build the API for node 2.
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let pub_key = *api.public_key();
+    // most of the DA committee of 10 announces the transaction
+    let announcers: Vec<_> = (3..9)
+        .map(|id| BLSPubKey::generated_from_seed_indexed([0u8; 32], id).0)
+        .collect();
+    let total_nodes = api.inner.memberships.quorum_membership.total_nodes();
+    let announced = TestTransaction::new(vec![0]);
+    let fresh = TestTransaction::new(vec![1]);
+    let encoded_transactions =
+        TestTransaction::encode(vec![announced.clone(), fresh.clone()]).unwrap();
+    let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::DAProposal,
+        &Sha256::digest(&encoded_transactions),
+    )
+    .expect("Failed to sign block payload");
+    let message = Proposal {
+        data: DAProposal {
+            encoded_transactions: encoded_transactions.clone().into(),
+            metadata: (),
+            view_number: ViewNumber::new(2),
+        },
+        signature: signature.clone(),
+        _pd: PhantomData,
+    };
+    let commitment = signed_commitment(
+        DAProposalCommitment::new(&message.data, total_nodes),
+        api.private_key(),
+    );
+    // the transaction most members announced is left out, the other one sent in full
+    let compact = signed_compact(
+        CompactDAProposal {
+            transactions: vec![
+                CompactTransaction::Held(announced.commit()),
+                CompactTransaction::Full(fresh),
+            ],
+            metadata: (),
+            view_number: ViewNumber::new(2),
+            payload_signature: signature,
+        },
+        api.private_key(),
+    );
+
+    let mut input = Vec::new();
+    let mut output = HashMap::new();
+
+    // In view 1, node 2 is the next leader.
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(1)));
+    for announcer in announcers {
+        input.push(HotShotEvent::MempoolInventoryRecv(
+            vec![announced.commit()],
+            announcer,
+        ));
+    }
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(2)));
+    input.push(HotShotEvent::TransactionsSequenced(
+        encoded_transactions.into(),
+        (),
+        ViewNumber::new(2),
+    ));
+    input.push(HotShotEvent::Shutdown);
+
+    output.insert(HotShotEvent::CompactDAProposalSend(compact, pub_key), 1);
+    output.insert(
+        HotShotEvent::DAProposalCommitmentSend(commitment, pub_key),
+        1,
+    );
+    output.insert(HotShotEvent::DAProposalRecv(message, pub_key), 1);
+
+    let da_state = DATaskState {
+        api: api.clone(),
+        consensus: handle.hotshot.get_consensus(),
+        da_membership: api.inner.memberships.da_membership.clone().into(),
+        da_network: api.inner.networks.da_network.clone(),
+        quorum_membership: api.inner.memberships.quorum_membership.clone().into(),
+        cur_view: ViewNumber::new(0),
+        vote_collector: None.into(),
+        public_key: pub_key,
+        private_key: api.private_key().clone(),
+        unicast_payload: false,
+        compact_proposals: true,
+        announced: AnnouncedTransactions::new(10),
+        rebuilds: BTreeMap::new(),
+        mempool: handle.hotshot.inner.mempool.clone(),
+        event_bus: EventBus::default(),
+        fallback: DAFallbackState::new(None, 0),
+        tx_index: Arc::new(RwLock::new(TransactionIndex::new(0))),
+        id: handle.hotshot.inner.id,
+    };
+    run_harness(input, output, da_state, false).await;
+}
+
+/// DA committee members only rebuild the compact proposals their leader signed, so a proposal
+/// claiming to be from the leader does not take the place of the real one
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_da_task_ignores_forged_compact_proposals() {
+    use hotshot_task_impls::harness::run_harness;
+    use hotshot_testing::task_helpers::build_system_handle;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // Build the API for node 2, a member of the DA committee led in view 3 by node 3.
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let (leader, leader_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 3);
+    let forger_key = BLSPubKey::generated_from_seed_indexed([0u8; 32], 4).1;
+    let total_nodes = api.inner.memberships.quorum_membership.total_nodes();
+
+    let compact_of = |transaction: TestTransaction| {
+        let encoded_transactions = TestTransaction::encode(vec![transaction.clone()]).unwrap();
+        let payload_signature = signing::sign::<BLSPubKey>(
+            &leader_key,
+            SigningDomain::DAProposal,
+            &Sha256::digest(&encoded_transactions),
+        )
+        .expect("Failed to sign block payload");
+        let compact = CompactDAProposal {
+            transactions: vec![CompactTransaction::Full(transaction)],
+            metadata: (),
+            view_number: ViewNumber::new(3),
+            payload_signature,
+        };
+        (compact, encoded_transactions)
+    };
+    // the forger replays a payload signature of the leader, but cannot sign the compact form
+    let (forged, _) = compact_of(TestTransaction::new(vec![0]));
+    let (real, encoded_transactions) = compact_of(TestTransaction::new(vec![1]));
+
+    let input = vec![
+        HotShotEvent::ViewChange(ViewNumber::new(1)),
+        HotShotEvent::ViewChange(ViewNumber::new(2)),
+        HotShotEvent::CompactDAProposalRecv(signed_compact(forged, &forger_key), leader),
+        HotShotEvent::CompactDAProposalRecv(signed_compact(real, &leader_key), leader),
+        HotShotEvent::Shutdown,
+    ];
+    let vote = DAVote::create_signed_vote(
+        DAData {
+            payload_commit: vid_commitment(&encoded_transactions, total_nodes),
+            governance: Vec::new(),
+        },
+        ViewNumber::new(3),
+        api.public_key(),
+        api.private_key(),
+    )
+    .expect("Failed to sign DAData");
+    let output = HashMap::from([(HotShotEvent::DAVoteSend(vote), 1)]);
+
+    let da_state = DATaskState {
+        api: api.clone(),
+        consensus: handle.hotshot.get_consensus(),
+        da_membership: api.inner.memberships.da_membership.clone().into(),
+        da_network: api.inner.networks.da_network.clone(),
+        quorum_membership: api.inner.memberships.quorum_membership.clone().into(),
+        cur_view: ViewNumber::new(0),
+        vote_collector: None.into(),
+        public_key: *api.public_key(),
+        private_key: api.private_key().clone(),
+        unicast_payload: false,
+        compact_proposals: false,
+        announced: AnnouncedTransactions::new(0),
+        rebuilds: BTreeMap::new(),
+        mempool: handle.hotshot.inner.mempool.clone(),
+        event_bus: EventBus::default(),
+        fallback: DAFallbackState::new(None, 0),
+        tx_index: Arc::new(RwLock::new(TransactionIndex::new(0))),
         id: handle.hotshot.inner.id,
     };
     run_harness(input, output, da_state, false).await;
@@ -0,0 +1,151 @@
+use hotshot::HotShotConsensusApi;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes};
+use hotshot_task_impls::{epoch::EpochTaskState, events::HotShotEvent, harness::run_harness};
+use hotshot_testing::task_helpers::build_system_handle;
+use hotshot_types::{
+    data::ViewNumber,
+    epoch::{stake_table_commitment, Epoch, EpochParameters, EpochSchedule},
+    simple_vote::{EpochChangeData, EpochChangeVote},
+    traits::{
+        consensus_api::ConsensusApi,
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+    },
+};
+use std::collections::HashMap;
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_epoch_task_votes_at_boundary() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // Build the API for node 2.
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let quorum_membership = api.inner.memberships.quorum_membership.clone();
+    let schedule = EpochSchedule::new(4);
+    let parameters = EpochParameters {
+        epoch_length: 4,
+        da_committee_size: api.inner.config.da_committee_size as u64,
+    };
+
+    // view 3 is the last view of epoch 0, in which epoch 1 is voted on, for its first view
+    let data = EpochChangeData::<TestTypes> {
+        epoch: Epoch::new(1),
+        first_view: ViewNumber::new(4),
+        stake_table_commitment: stake_table_commitment::<<TestTypes as NodeType>::SignatureKey>(
+            &quorum_membership.get_committee_qc_stake_table(),
+        ),
+        parameters,
+    };
+    let vote = EpochChangeVote::create_signed_vote(
+        data,
+        ViewNumber::new(4),
+        api.public_key(),
+        api.private_key(),
+    )
+    .expect("Failed to sign EpochChangeVote");
+
+    let mut input = Vec::new();
+    let mut output = HashMap::new();
+
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(1)));
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(2)));
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(3)));
+    // a view change the node already saw does not make it vote again
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(3)));
+    input.push(HotShotEvent::ViewChange(ViewNumber::new(4)));
+    input.push(HotShotEvent::Shutdown);
+
+    output.insert(HotShotEvent::EpochChangeVoteSend(vote), 1);
+
+    let epoch_state = EpochTaskState {
+        api: api.clone(),
+        cur_view: ViewNumber::new(0),
+        schedule,
+        parameters,
+        consensus: handle.hotshot.get_consensus(),
+        quorum_membership: quorum_membership.into(),
+        da_membership: api.inner.memberships.da_membership.clone().into(),
+        quorum_network: api.inner.networks.quorum_network.clone(),
+        vote_collector: None.into(),
+        voted_epoch: None,
+        certified_epoch: None,
+        public_key: *api.public_key(),
+        private_key: api.private_key().clone(),
+        id: handle.hotshot.inner.id,
+    };
+    run_harness(input, output, epoch_state, false).await;
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_epoch_task_votes_after_skipping_the_boundary() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let quorum_membership = api.inner.memberships.quorum_membership.clone();
+    let schedule = EpochSchedule::new(4);
+    let parameters = EpochParameters {
+        epoch_length: 4,
+        da_committee_size: api.inner.config.da_committee_size as u64,
+    };
+
+    let data = EpochChangeData::<TestTypes> {
+        epoch: Epoch::new(1),
+        first_view: ViewNumber::new(4),
+        stake_table_commitment: stake_table_commitment::<<TestTypes as NodeType>::SignatureKey>(
+            &quorum_membership.get_committee_qc_stake_table(),
+        ),
+        parameters,
+    };
+    let vote = EpochChangeVote::create_signed_vote(
+        data,
+        ViewNumber::new(4),
+        api.public_key(),
+        api.private_key(),
+    )
+    .expect("Failed to sign EpochChangeVote");
+
+    // the node jumps from epoch 0 into epoch 1 without seeing the last view of epoch 0, and
+    // votes on epoch 1 once in it
+    let input = vec![
+        HotShotEvent::ViewChange(ViewNumber::new(2)),
+        HotShotEvent::ViewChange(ViewNumber::new(5)),
+        HotShotEvent::ViewChange(ViewNumber::new(6)),
+        HotShotEvent::Shutdown,
+    ];
+    let mut output = HashMap::new();
+    output.insert(HotShotEvent::EpochChangeVoteSend(vote), 1);
+
+    let epoch_state = EpochTaskState {
+        api: api.clone(),
+        cur_view: ViewNumber::new(0),
+        schedule,
+        parameters,
+        consensus: handle.hotshot.get_consensus(),
+        quorum_membership: quorum_membership.into(),
+        da_membership: api.inner.memberships.da_membership.clone().into(),
+        quorum_network: api.inner.networks.quorum_network.clone(),
+        vote_collector: None.into(),
+        voted_epoch: None,
+        certified_epoch: None,
+        public_key: *api.public_key(),
+        private_key: api.private_key().clone(),
+        id: handle.hotshot.inner.id,
+    };
+    run_harness(input, output, epoch_state, false).await;
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+    num::NonZeroUsize,
+    sync::Arc,
+};
+
+use async_lock::RwLock;
+use hotshot::{
+    traits::implementations::MemoryStorage, types::SystemContextHandle, HotShotConsensusApi,
+};
+use hotshot_example_types::{
+    block_types::TestTransaction,
+    node_types::{MemoryImpl, TestTypes},
+};
+use hotshot_task_impls::{
+    bus::EventBus,
+    compact_block::AnnouncedTransactions,
+    da::{DAFallbackState, DATaskState},
+    events::HotShotEvent,
+    harness::run_harness,
+};
+use hotshot_testing::task_helpers::{build_system_handle, system_context_builder};
+use hotshot_types::{
+    data::{DAProposal, ViewNumber},
+    epoch::{Epoch, EpochSchedule},
+    governance::{ConsensusParameters, GovernanceTransaction, ParameterChange, ParameterSchedule},
+    message::Proposal,
+    receipt::TransactionIndex,
+    signing::{self, SigningDomain},
+    simple_vote::{DAData, DAVote},
+    traits::{
+        block_contents::vid_commitment,
+        consensus_api::ConsensusApi,
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        storage::Storage,
+    },
+};
+use sha2::{Digest, Sha256};
+
+/// The DA task of the node of `handle`
+fn da_state(
+    handle: &SystemContextHandle<TestTypes, MemoryImpl>,
+) -> DATaskState<TestTypes, MemoryImpl, HotShotConsensusApi<TestTypes, MemoryImpl>> {
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    DATaskState {
+        api: api.clone(),
+        consensus: handle.hotshot.get_consensus(),
+        da_membership: api.inner.memberships.da_membership.clone().into(),
+        da_network: api.inner.networks.da_network.clone(),
+        quorum_membership: api.inner.memberships.quorum_membership.clone().into(),
+        cur_view: ViewNumber::new(0),
+        vote_collector: None.into(),
+        public_key: *api.public_key(),
+        private_key: api.private_key().clone(),
+        unicast_payload: false,
+        compact_proposals: false,
+        announced: AnnouncedTransactions::new(0),
+        rebuilds: BTreeMap::new(),
+        mempool: api.inner.mempool.clone(),
+        event_bus: EventBus::default(),
+        fallback: DAFallbackState::new(None, 0),
+        tx_index: Arc::new(RwLock::new(TransactionIndex::new(0))),
+        id: api.inner.id,
+    }
+}
+
+/// The DA proposal of view 2 of `transactions`, signed by the holder of `api`'s key
+fn da_proposal(
+    api: &HotShotConsensusApi<TestTypes, MemoryImpl>,
+    transactions: Vec<TestTransaction>,
+) -> Proposal<TestTypes, DAProposal<TestTypes>> {
+    let encoded_transactions = TestTransaction::encode(transactions).unwrap();
+    let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::DAProposal,
+        &Sha256::digest(&encoded_transactions),
+    )
+    .expect("Failed to sign block payload");
+    Proposal {
+        data: DAProposal {
+            encoded_transactions: encoded_transactions.into(),
+            metadata: (),
+            view_number: ViewNumber::new(2),
+        },
+        signature,
+        _pd: PhantomData,
+    }
+}
+
+/// A change of the timeout from epoch 3 on
+fn timeout_change() -> GovernanceTransaction {
+    GovernanceTransaction::new(
+        Epoch::new(3),
+        ParameterChange {
+            next_view_timeout: Some(5_000),
+            ..ParameterChange::default()
+        },
+    )
+}
+
+/// The DA committee votes on the governance transactions of the block along with its payload,
+/// so that its certificate tells replicas without the payload which ones the block holds
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn da_votes_certify_the_governance_transactions_of_the_block() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let governance = timeout_change();
+    let proposal = da_proposal(
+        &api,
+        vec![
+            TestTransaction::new(vec![0]),
+            TestTransaction::from_governance(&governance),
+        ],
+    );
+    let payload_commit = vid_commitment(
+        &proposal.data.encoded_transactions,
+        api.inner.memberships.quorum_membership.total_nodes(),
+    );
+
+    let input = vec![
+        HotShotEvent::ViewChange(ViewNumber::new(1)),
+        HotShotEvent::ViewChange(ViewNumber::new(2)),
+        HotShotEvent::DAProposalRecv(proposal, *api.public_key()),
+        HotShotEvent::Shutdown,
+    ];
+    let vote = DAVote::create_signed_vote(
+        DAData {
+            payload_commit,
+            governance: vec![governance],
+        },
+        ViewNumber::new(2),
+        api.public_key(),
+        api.private_key(),
+    )
+    .expect("Failed to sign DAData");
+    let output = HashMap::from([(HotShotEvent::DAVoteSend(vote), 1)]);
+    run_harness(input, output, da_state(&handle), false).await;
+}
+
+/// DA members do not vote for blocks holding more transactions than governance allows, so no
+/// replica votes for them either
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn da_members_do_not_vote_for_blocks_over_the_transaction_limit() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    handle
+        .hotshot
+        .get_consensus()
+        .write()
+        .await
+        .parameter_schedule = ParameterSchedule::new(
+        ConsensusParameters {
+            next_view_timeout: 10_000,
+            max_transactions: NonZeroUsize::new(1).unwrap(),
+            da_committee_size: 10,
+        },
+        None,
+        10,
+    );
+    let proposal = da_proposal(
+        &api,
+        vec![TestTransaction::new(vec![0]), TestTransaction::new(vec![1])],
+    );
+
+    let input = vec![
+        HotShotEvent::ViewChange(ViewNumber::new(1)),
+        HotShotEvent::ViewChange(ViewNumber::new(2)),
+        HotShotEvent::DAProposalRecv(proposal, *api.public_key()),
+        HotShotEvent::Shutdown,
+    ];
+    run_harness(input, HashMap::new(), da_state(&handle), false).await;
+
+    // nor did it promise to make the payload available
+    assert!(!handle
+        .hotshot
+        .get_consensus()
+        .read()
+        .await
+        .saved_payloads
+        .contains_key(&ViewNumber::new(2)));
+}
+
+/// A restarted node runs with the parameter schedule it persisted, DA committee included
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn restarted_nodes_run_with_the_persisted_parameter_schedule() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let mut schedule = ParameterSchedule::new(
+        ConsensusParameters {
+            next_view_timeout: 10_000,
+            max_transactions: NonZeroUsize::new(100).unwrap(),
+            da_committee_size: 6,
+        },
+        Some(EpochSchedule::new(10)),
+        10,
+    );
+    schedule
+        .schedule(ViewNumber::new(5), &timeout_change())
+        .unwrap();
+    schedule.certify(Epoch::new(3));
+    let storage = MemoryStorage::<TestTypes>::empty();
+    storage
+        .append_parameter_schedule(schedule.clone())
+        .await
+        .unwrap();
+
+    let handle = system_context_builder(2)
+        .storage(storage)
+        .build()
+        .await
+        .expect("Could not init hotshot")
+        .0;
+    assert_eq!(
+        handle
+            .hotshot
+            .get_consensus()
+            .read()
+            .await
+            .parameter_schedule,
+        schedule
+    );
+    assert_eq!(
+        handle.hotshot.inner.memberships.da_membership.total_nodes(),
+        6
+    );
+}
@@ -0,0 +1,32 @@
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_harness_with_faulty_nodes() {
+    use std::time::Duration;
+
+    use hotshot_example_types::node_types::{MemoryImpl, TestTypes};
+    use hotshot_testing::{
+        harness::TestHarness, overall_safety_task::OverallSafetyPropertiesDescription,
+    };
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    TestHarness::<TestTypes, MemoryImpl>::new()
+        .nodes(10)
+        // keep the faulty nodes out of the DA committee
+        .da_committee_size(8)
+        .faulty_nodes([8, 9], 5)
+        .success_criteria(OverallSafetyPropertiesDescription {
+            num_failed_views: 4,
+            check_leaf: true,
+            ..Default::default()
+        })
+        .rounds(15)
+        .timeout(Duration::from_secs(120))
+        .run()
+        .await;
+}
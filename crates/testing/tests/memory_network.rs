@@ -1,11 +1,13 @@
 #![allow(clippy::panic)]
 use std::collections::BTreeSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_compatibility_layer::logging::setup_logging;
 use hotshot::traits::election::static_committee::{GeneralStaticCommittee, StaticElectionConfig};
 use hotshot::traits::implementations::{
-    MasterMap, MemoryNetwork, MemoryStorage, NetworkingMetricsValue,
+    MasterMap, MemoryNetwork, MemoryStorage, NetworkingMetricsValue, PlaybackNetwork,
+    RecordingNetwork, TraceEntry, TraceEvent,
 };
 use hotshot::traits::NodeImplementation;
 use hotshot::types::SignatureKey;
@@ -107,9 +109,10 @@ fn gen_messages(num_messages: u64, seed: u64, pk: BLSPubKey) -> Vec<Message<Test
 
         let message = Message {
             version: VERSION_0_1,
+            chain_id: 0,
             sender: pk,
             kind: MessageKind::Data(DataMessage::SubmitTransaction(
-                TestTransaction(bytes.to_vec()),
+                TestTransaction::new(bytes.to_vec()),
                 <ViewNumber as ConsensusTime>::new(0),
             )),
         };
@@ -353,3 +356,134 @@ async fn memory_network_test_in_flight_message_count() {
     assert_eq!(network1.in_flight_message_count(), Some(0));
     assert_eq!(network2.in_flight_message_count(), Some(0));
 }
+
+// Messages recorded by a `RecordingNetwork` are replayed by a `PlaybackNetwork`
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_network_trace_playback() {
+    setup_logging();
+    let group: Arc<MasterMap<Message<Test>, <Test as NodeType>::SignatureKey>> = MasterMap::new();
+    trace!(?group);
+    let pub_key_1 = get_pubkey();
+    let network1 = MemoryNetwork::new(
+        pub_key_1,
+        NetworkingMetricsValue::default(),
+        group.clone(),
+        Option::None,
+    );
+    let pub_key_2 = get_pubkey();
+    let trace_path = std::env::temp_dir().join(format!(
+        "hotshot-trace-{}-{}.bin",
+        std::process::id(),
+        rand::random::<u64>()
+    ));
+    let network2 = RecordingNetwork::new(
+        MemoryNetwork::new(
+            pub_key_2,
+            NetworkingMetricsValue::default(),
+            group,
+            Option::None,
+        ),
+        &trace_path,
+    )
+    .expect("Failed to create the trace");
+
+    let messages: Vec<Message<Test>> = gen_messages(3, 100, pub_key_1);
+    for sent_message in &messages {
+        network1
+            .direct_message(sent_message.clone(), pub_key_2)
+            .await
+            .expect("Failed to message node");
+        let recv_messages = network2
+            .recv_msgs(TransmitType::Direct)
+            .await
+            .expect("Failed to receive message");
+        assert_eq!(recv_messages.len(), 1);
+    }
+    let reply = gen_messages(1, 200, pub_key_2).pop().unwrap();
+    network2
+        .broadcast_message(reply, BTreeSet::from([pub_key_1]))
+        .await
+        .expect("Failed to message node");
+
+    let playback: PlaybackNetwork<Message<Test>, BLSPubKey> =
+        PlaybackNetwork::from_file(&trace_path, false).expect("Failed to read the trace");
+    std::fs::remove_file(&trace_path).unwrap();
+    assert_eq!(playback.remaining(), messages.len());
+    assert!(matches!(
+        playback.recorded_sends(),
+        [entry] if matches!(&entry.event, TraceEvent::Broadcast { recipients, .. }
+            if *recipients == BTreeSet::from([pub_key_1]))
+    ));
+
+    let replayed = playback
+        .recv_msgs(TransmitType::Direct)
+        .await
+        .expect("Failed to replay messages");
+    assert_eq!(replayed.len(), messages.len());
+    for (sent_message, replayed_message) in messages.into_iter().zip(replayed) {
+        fake_message_eq(sent_message, replayed_message);
+    }
+    assert_eq!(playback.remaining(), 0);
+
+    // what the node under test sends is kept rather than delivered
+    let reply = gen_messages(1, 300, pub_key_2).pop().unwrap();
+    playback
+        .direct_message(reply, pub_key_1)
+        .await
+        .expect("Failed to message node");
+    assert_eq!(playback.sent().len(), 1);
+}
+
+// A `PlaybackNetwork` replays messages received directly and by broadcast in the order they were
+// received, not all those of one transmit type first
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_network_trace_playback_keeps_order() {
+    setup_logging();
+    let pub_key = get_pubkey();
+    let received = [
+        (TransmitType::Broadcast, 1),
+        (TransmitType::Direct, 2),
+        (TransmitType::Broadcast, 3),
+    ];
+    let entries = received
+        .iter()
+        .map(|(transmit_type, seed)| TraceEntry {
+            elapsed: Duration::ZERO,
+            event: TraceEvent::Received {
+                transmit_type: *transmit_type,
+                message: gen_messages(1, *seed, pub_key).pop().unwrap(),
+            },
+        })
+        .collect();
+    let playback: PlaybackNetwork<Message<Test>, BLSPubKey> = PlaybackNetwork::new(entries, false);
+
+    let first = playback.recv_msgs(TransmitType::Broadcast).await.unwrap();
+    assert_eq!(first.len(), 1);
+    fake_message_eq(first[0].clone(), gen_messages(1, 1, pub_key).pop().unwrap());
+    // the direct message received in between is delivered before the next broadcast
+    assert!(playback
+        .recv_msgs(TransmitType::Broadcast)
+        .await
+        .unwrap()
+        .is_empty());
+    let second = playback.recv_msgs(TransmitType::Direct).await.unwrap();
+    assert_eq!(second.len(), 1);
+    fake_message_eq(
+        second[0].clone(),
+        gen_messages(1, 2, pub_key).pop().unwrap(),
+    );
+    let third = playback.recv_msgs(TransmitType::Broadcast).await.unwrap();
+    assert_eq!(third.len(), 1);
+    fake_message_eq(third[0].clone(), gen_messages(1, 3, pub_key).pop().unwrap());
+    assert_eq!(playback.remaining(), 0);
+}
@@ -0,0 +1,72 @@
+use hotshot_example_types::{block_types::TestTransaction, node_types::TestTypes};
+use hotshot_task_impls::mempool::{ShardedMempool, MIN_PARALLEL_BATCH};
+use hotshot_types::{
+    consensus::ConsensusMetricsValue, traits::transaction_validator::TransactionValidator,
+};
+use std::sync::Arc;
+
+/// Accepts transactions which do not start with a zero byte
+struct NoLeadingZero;
+
+impl TransactionValidator<TestTypes> for NoLeadingZero {
+    fn max_size(&self) -> usize {
+        16
+    }
+    fn check_well_formed(&self, transaction: &TestTransaction) -> Result<(), String> {
+        match transaction.bytes.first() {
+            Some(0) => Err("leading zero byte".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `count` distinct transactions, every other one of which starts with a zero byte
+#[allow(clippy::cast_possible_truncation)]
+fn transactions(count: usize) -> Vec<TestTransaction> {
+    (0..count)
+        .map(|i| TestTransaction::new(vec![(i % 2) as u8, (i >> 8) as u8, i as u8]))
+        .collect()
+}
+
+/// Validate `count` transactions, returning the first bytes of those which pass
+async fn accepted(count: usize) -> Vec<u8> {
+    let mempool = ShardedMempool::<TestTypes>::new(&ConsensusMetricsValue::default())
+        .with_validator(Arc::new(NoLeadingZero));
+    mempool
+        .validate(transactions(count))
+        .await
+        .into_iter()
+        .map(|txn| txn.transaction.bytes[0])
+        .collect()
+}
+
+/// Batches too small for the workers are checked inline, by the validator of the mempool
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn invalid_transactions_are_rejected_inline() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let count = MIN_PARALLEL_BATCH - 1;
+    assert_eq!(accepted(count).await, vec![1; count / 2]);
+}
+
+/// Batches large enough are spread over the workers, which run the same checks
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn invalid_transactions_are_rejected_by_the_workers() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    for count in [MIN_PARALLEL_BATCH, 4 * MIN_PARALLEL_BATCH + 1] {
+        assert_eq!(accepted(count).await, vec![1; count / 2]);
+    }
+}
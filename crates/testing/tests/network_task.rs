@@ -1,13 +1,53 @@
-use hotshot::{types::SignatureKey, HotShotConsensusApi};
-use hotshot_example_types::node_types::{MemoryImpl, TestTypes};
-use hotshot_task_impls::events::HotShotEvent;
+use hotshot::{
+    types::{BLSPubKey, SignatureKey},
+    HotShotConsensusApi,
+};
+use hotshot_example_types::{
+    block_types::genesis_transactions_root,
+    node_types::{MemoryImpl, TestTypes},
+};
+use hotshot_task_impls::{
+    bus::EventBus,
+    events::HotShotEvent,
+    network::{NetworkMessageTaskState, StaleViewFilter},
+};
 use hotshot_testing::task_helpers::{build_quorum_proposal, vid_init};
 use hotshot_types::{
     data::{DAProposal, VidSchemeTrait, ViewNumber},
-    traits::{consensus_api::ConsensusApi, node_implementation::ConsensusTime},
+    signing::{self, SigningDomain},
+    traits::{
+        consensus_api::ConsensusApi,
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        transaction_validator::TransactionValidator,
+    },
 };
 use sha2::{Digest, Sha256};
-use std::{collections::HashMap, marker::PhantomData};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+/// The state of a network message task of a node of a single node stake table, keyed by
+/// `generated_from_seed_indexed([0u8; 32], 0)`
+fn message_task_state(
+    event_stream: async_broadcast::Sender<HotShotEvent<TestTypes>>,
+    chain_id: u64,
+    transaction_validator: Arc<dyn TransactionValidator<TestTypes>>,
+    stale_view_filter: StaleViewFilter,
+) -> NetworkMessageTaskState<TestTypes> {
+    let key = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    NetworkMessageTaskState {
+        event_stream,
+        chain_id,
+        metrics: Arc::default(),
+        transaction_validator,
+        stale_view_filter,
+        event_bus: EventBus::default(),
+        rpc: None,
+        quorum_membership: Arc::new(<TestTypes as NodeType>::Membership::create_election(
+            vec![key.get_stake_table_entry(1)],
+            <TestTypes as NodeType>::Membership::default_election_config(1),
+        )),
+    }
+}
 
 #[cfg(test)]
 #[cfg_attr(
@@ -34,27 +74,27 @@ async fn test_network_task() {
     // quorum membership for VID share distribution
     let quorum_membership = handle.hotshot.inner.memberships.quorum_membership.clone();
 
-    let encoded_transactions = Vec::new();
+    let encoded_transactions: Vec<u8> = Vec::new();
     let encoded_transactions_hash = Sha256::digest(&encoded_transactions);
-    let da_signature =
-        <TestTypes as hotshot_types::traits::node_implementation::NodeType>::SignatureKey::sign(
-            api.private_key(),
-            &encoded_transactions_hash,
-        )
-        .expect("Failed to sign block payload");
+    let da_signature = signing::sign::<BLSPubKey>(
+        api.private_key(),
+        SigningDomain::DAProposal,
+        &encoded_transactions_hash,
+    )
+    .expect("Failed to sign block payload");
     let vid = vid_init::<TestTypes>(&quorum_membership, ViewNumber::new(2));
     let vid_disperse = vid.disperse(&encoded_transactions).unwrap();
     let payload_commitment = vid_disperse.commit;
-    let vid_signature =
-        <TestTypes as hotshot_types::traits::node_implementation::NodeType>::SignatureKey::sign(
-            api.private_key(),
-            payload_commitment.as_ref(),
-        )
-        .expect("Failed to sign block commitment");
+    let vid_signature = signing::sign::<BLSPubKey>(
+        api.private_key(),
+        SigningDomain::VidDisperse,
+        payload_commitment.as_ref(),
+    )
+    .expect("Failed to sign block commitment");
 
     let da_proposal = Proposal {
         data: DAProposal {
-            encoded_transactions: encoded_transactions.clone(),
+            encoded_transactions: encoded_transactions.clone().into(),
             metadata: (),
             view_number: ViewNumber::new(2),
         },
@@ -84,7 +124,7 @@ async fn test_network_task() {
     input.push(HotShotEvent::ViewChange(ViewNumber::new(1)));
     input.push(HotShotEvent::ViewChange(ViewNumber::new(2)));
     input.push(HotShotEvent::TransactionsSequenced(
-        encoded_transactions.clone(),
+        encoded_transactions.clone().into(),
         (),
         ViewNumber::new(2),
     ));
@@ -104,7 +144,7 @@ async fn test_network_task() {
 
     output.insert(HotShotEvent::ViewChange(ViewNumber::new(1)), 2);
     output.insert(
-        HotShotEvent::TransactionsSequenced(encoded_transactions, (), ViewNumber::new(2)),
+        HotShotEvent::TransactionsSequenced(encoded_transactions.into(), (), ViewNumber::new(2)),
         2, // 2 occurrences: 1 from `input`, 1 from the transactions task
     );
     output.insert(
@@ -130,7 +170,12 @@ async fn test_network_task() {
     );
     output.insert(HotShotEvent::ViewChange(ViewNumber::new(2)), 2);
     output.insert(
-        HotShotEvent::SendPayloadCommitmentAndMetadata(payload_commitment, (), ViewNumber::new(2)),
+        HotShotEvent::SendPayloadCommitmentAndMetadata(
+            payload_commitment,
+            genesis_transactions_root(),
+            (),
+            ViewNumber::new(2),
+        ),
         2, // 2 occurrences: both from the VID task
     );
     output.insert(
@@ -147,3 +192,326 @@ async fn test_network_task() {
     // `allow_extra_output` to `true` for deterministic test result.
     // run_harness(input, output, Some(event_stream), build_fn, true).await;
 }
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_network_task_drops_other_chains() {
+    use hotshot_constants::VERSION_0_1;
+    use hotshot_example_types::block_types::TestTransaction;
+    use hotshot_types::{
+        message::{DataMessage, Message, MessageKind},
+        signature_key::BLSPubKey,
+        traits::transaction_validator::MaxSizeValidator,
+    };
+
+    let (tx, mut rx) = async_broadcast::broadcast(16);
+    let mut state = message_task_state(
+        tx,
+        7,
+        Arc::new(MaxSizeValidator::default()),
+        StaleViewFilter::new(0),
+    );
+    let sender = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let message = |chain_id, byte| Message {
+        version: VERSION_0_1,
+        chain_id,
+        sender,
+        kind: MessageKind::Data(DataMessage::SubmitTransaction(
+            TestTransaction::new(vec![byte]),
+            ViewNumber::new(0),
+        )),
+    };
+
+    state
+        .handle_messages(vec![message(7, 1), message(8, 2), message(7, 3)])
+        .await;
+
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        HotShotEvent::TransactionsRecv(vec![
+            TestTransaction::new(vec![1]),
+            TestTransaction::new(vec![3])
+        ])
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_network_task_drops_invalid_transactions() {
+    use hotshot_constants::VERSION_0_1;
+    use hotshot_example_types::block_types::TestTransaction;
+    use hotshot_types::{
+        message::{DataMessage, Message, MessageKind},
+        signature_key::BLSPubKey,
+        traits::transaction_validator::{MaxSizeValidator, TransactionRejection},
+    };
+
+    /// Accepts transactions of at most 16 bytes which do not start with a zero byte
+    struct NoLeadingZero;
+    impl TransactionValidator<TestTypes> for NoLeadingZero {
+        fn max_size(&self) -> usize {
+            16
+        }
+        fn check_well_formed(&self, transaction: &TestTransaction) -> Result<(), String> {
+            match transaction.bytes.first() {
+                Some(0) => Err("leading zero byte".to_string()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    assert!(matches!(
+        NoLeadingZero.validate(&TestTransaction::new(vec![0])),
+        Err(TransactionRejection::Malformed { .. })
+    ));
+    assert!(matches!(
+        TransactionValidator::<TestTypes>::validate(
+            &MaxSizeValidator { max_size: 4 },
+            &TestTransaction::new(vec![1; 4])
+        ),
+        Err(TransactionRejection::TooLarge { .. })
+    ));
+
+    let (tx, mut rx) = async_broadcast::broadcast(16);
+    let mut state = message_task_state(tx, 0, Arc::new(NoLeadingZero), StaleViewFilter::new(0));
+    let sender = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let message = |bytes| Message {
+        version: VERSION_0_1,
+        chain_id: 0,
+        sender,
+        kind: MessageKind::Data(DataMessage::SubmitTransaction(
+            TestTransaction::new(bytes),
+            ViewNumber::new(0),
+        )),
+    };
+
+    state
+        .handle_messages(vec![
+            message(vec![1]),
+            message(vec![0, 1]),
+            message(vec![2; 64]),
+        ])
+        .await;
+
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        HotShotEvent::TransactionsRecv(vec![TestTransaction::new(vec![1])])
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_network_task_drops_stale_views() {
+    use hotshot_constants::VERSION_0_1;
+    use hotshot_example_types::block_types::TestTransaction;
+    use hotshot_types::{
+        message::{
+            CommitteeConsensusMessage, DataMessage, Message, MessageKind, SequencingMessage,
+        },
+        simple_vote::{DAData, DAVote},
+        traits::{block_contents::vid_commitment, transaction_validator::MaxSizeValidator},
+    };
+
+    let stale_view_filter = StaleViewFilter::new(2);
+    stale_view_filter.enter_view(10);
+    assert!(stale_view_filter.is_stale(7));
+    assert!(!stale_view_filter.is_stale(8));
+    // views never go back
+    stale_view_filter.enter_view(3);
+    assert!(stale_view_filter.is_stale(7));
+
+    let (tx, mut rx) = async_broadcast::broadcast(16);
+    let mut state = message_task_state(
+        tx,
+        0,
+        Arc::new(MaxSizeValidator::default()),
+        stale_view_filter,
+    );
+    let (sender, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let vote = |view| {
+        DAVote::<TestTypes>::create_signed_vote(
+            DAData {
+                payload_commit: vid_commitment(&[], 1),
+                governance: Vec::new(),
+            },
+            ViewNumber::new(view),
+            &sender,
+            &private_key,
+        )
+        .unwrap()
+    };
+    let message = |kind| Message {
+        version: VERSION_0_1,
+        chain_id: 0,
+        sender,
+        kind,
+    };
+    let consensus = |view| {
+        message(MessageKind::from_consensus_message(SequencingMessage(
+            either::Right(CommitteeConsensusMessage::DAVote(vote(view))),
+        )))
+    };
+
+    state
+        .handle_messages(vec![
+            consensus(7),
+            consensus(8),
+            // data messages of passed views are still handled
+            message(MessageKind::Data(DataMessage::SubmitTransaction(
+                TestTransaction::new(vec![1]),
+                ViewNumber::new(1),
+            ))),
+        ])
+        .await;
+
+    assert_eq!(rx.try_recv().unwrap(), HotShotEvent::DAVoteRecv(vote(8)));
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        HotShotEvent::TransactionsRecv(vec![TestTransaction::new(vec![1])])
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_network_task_unpacks_vote_batches() {
+    use hotshot_constants::VERSION_0_1;
+    use hotshot_types::{
+        message::{GeneralConsensusMessage, Message, MessageKind, SequencingMessage, VoteBatch},
+        simple_vote::{TimeoutData, TimeoutVote},
+        traits::transaction_validator::MaxSizeValidator,
+    };
+
+    let stale_view_filter = StaleViewFilter::new(2);
+    stale_view_filter.enter_view(10);
+    let (tx, mut rx) = async_broadcast::broadcast(16);
+    let mut state = message_task_state(
+        tx,
+        0,
+        Arc::new(MaxSizeValidator::default()),
+        stale_view_filter,
+    );
+    let (sender, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let vote = |view| {
+        TimeoutVote::<TestTypes>::create_signed_vote(
+            TimeoutData {
+                view: ViewNumber::new(view),
+            },
+            ViewNumber::new(view),
+            &sender,
+            &private_key,
+        )
+        .unwrap()
+    };
+
+    // a batch of one vote is sent as the vote alone
+    let single = VoteBatch::<TestTypes> {
+        quorum_votes: Vec::new(),
+        timeout_votes: vec![vote(9)],
+    };
+    assert_eq!(
+        single.into_message(),
+        Some(GeneralConsensusMessage::TimeoutVote(vote(9)))
+    );
+
+    let batch = VoteBatch::<TestTypes> {
+        quorum_votes: Vec::new(),
+        timeout_votes: vec![vote(7), vote(9), vote(11)],
+    };
+    assert_eq!(batch.latest_view(), ViewNumber::new(11));
+    let message = Message {
+        version: VERSION_0_1,
+        chain_id: 0,
+        sender,
+        kind: MessageKind::from_consensus_message(SequencingMessage(either::Left(
+            batch.into_message().unwrap(),
+        ))),
+    };
+    state.handle_messages(vec![message]).await;
+
+    // the vote of the view which has passed is dropped
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        HotShotEvent::TimeoutVoteRecv(vote(9))
+    );
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        HotShotEvent::TimeoutVoteRecv(vote(11))
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_network_task_meters_accepted_messages_by_staked_sender() {
+    use hotshot_constants::VERSION_0_1;
+    use hotshot_example_types::block_types::TestTransaction;
+    use hotshot_types::{
+        bandwidth::UNSTAKED_PEER,
+        message::{DataMessage, Message, MessageKind},
+        traits::transaction_validator::MaxSizeValidator,
+    };
+
+    let (tx, _rx) = async_broadcast::broadcast(16);
+    let mut state = message_task_state(
+        tx,
+        7,
+        Arc::new(MaxSizeValidator::default()),
+        StaleViewFilter::new(0),
+    );
+    let staked = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let unstaked = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1).0;
+    let message = |chain_id, sender| Message::<TestTypes> {
+        version: VERSION_0_1,
+        chain_id,
+        sender,
+        kind: MessageKind::Data(DataMessage::SubmitTransaction(
+            TestTransaction::new(vec![1]),
+            ViewNumber::new(0),
+        )),
+    };
+
+    // messages of another chain are dropped before they are metered, whoever claims to send them
+    state
+        .handle_messages(vec![
+            message(8, staked),
+            message(7, staked),
+            message(7, unstaked),
+            message(7, unstaked),
+        ])
+        .await;
+
+    let usage = state.metrics.bandwidth.usage();
+    let received = |peer: &str| {
+        usage
+            .iter()
+            .filter(|usage| usage.peer == peer)
+            .map(|usage| usage.received_bytes)
+            .sum::<u64>()
+    };
+    assert!(received(&staked.to_string()) > 0);
+    assert_eq!(received(UNSTAKED_PEER), 2 * received(&staked.to_string()));
+    assert_eq!(usage.len(), 2);
+}
@@ -0,0 +1,51 @@
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+
+use hotshot::{
+    traits::election::static_committee::StaticElectionConfig,
+    types::{BLSPubKey, SignatureKey},
+};
+use hotshot_orchestrator::{client::OrchestratorClient, run_campaign};
+use tide_disco::Url;
+
+/// The url of a local port nothing listens on
+fn unused_url() -> Url {
+    let listener = TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))
+        .expect("could not bind a tcp port");
+    let addr = listener.local_addr().expect("the listener has no address");
+    drop(listener);
+    Url::parse(&format!("http://{addr}")).expect("invalid url")
+}
+
+/// A validator whose orchestrator cannot be reached gets an error it can report, not a panic
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn unreachable_orchestrators_are_errors() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let client = OrchestratorClient::connect(unused_url(), "127.0.0.1".to_string());
+    let public_key = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let result = client
+        .post_and_wait_all_public_keys::<BLSPubKey, StaticElectionConfig>(0, public_key)
+        .await;
+    assert!(result.is_err());
+}
+
+/// An orchestrator with nothing to run fails to start instead of panicking
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn campaigns_without_runs_are_errors() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let result = run_campaign::<BLSPubKey, StaticElectionConfig>(Vec::new(), unused_url()).await;
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
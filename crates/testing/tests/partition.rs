@@ -0,0 +1,67 @@
+/// Consensus stalls while the network is split in two halves neither of which holds a quorum, and
+/// resumes once the partition heals
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn consensus_resumes_after_a_partition_heals() {
+    use std::time::Duration;
+
+    use hotshot_example_types::node_types::{MemoryImpl, TestTypes};
+    use hotshot_testing::{
+        completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+        overall_safety_task::OverallSafetyPropertiesDescription,
+        spinning_task::{ChangeNode, SpinningTaskDescription, UpDown},
+        test_builder::{TestMetadata, TimingData},
+    };
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+    let mut metadata = TestMetadata {
+        total_nodes: 10,
+        start_nodes: 10,
+        timing_data: TimingData {
+            next_view_timeout: 1000,
+            ..Default::default()
+        },
+        ..TestMetadata::default()
+    };
+    let partition = (0..10)
+        .map(|idx| ChangeNode {
+            idx,
+            updown: UpDown::Partition {
+                reachable: if idx < 5 {
+                    (0..5).collect()
+                } else {
+                    (5..10).collect()
+                },
+                heal_after: Duration::from_secs(5),
+            },
+        })
+        .collect();
+    metadata.spinning_properties = SpinningTaskDescription {
+        node_changes: vec![(5, partition)],
+    };
+    metadata.view_sync_properties =
+        hotshot_testing::view_sync_task::ViewSyncTaskDescription::Threshold(0, 10);
+    metadata.completion_task_description =
+        CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+            TimeBasedCompletionTaskDescription {
+                duration: Duration::from_secs(60),
+            },
+        );
+    metadata.overall_safety_properties = OverallSafetyPropertiesDescription {
+        // views keep being decided after the partition heals
+        num_successful_views: 15,
+        num_failed_views: 10,
+        check_leaf: true,
+        ..Default::default()
+    };
+
+    metadata
+        .gen_launcher::<TestTypes, MemoryImpl>(0)
+        .launch()
+        .run_test()
+        .await;
+}
@@ -0,0 +1,106 @@
+use std::num::NonZeroUsize;
+
+use commit::Committable;
+use hotshot_example_types::block_types::{TestBlockPayload, TestTransaction};
+use hotshot_testing::task_helpers::build_system_handle;
+use hotshot_types::{
+    data::ViewNumber,
+    governance::{ConsensusParameters, ParameterSchedule},
+    payload_validation::PayloadRejection,
+    traits::{
+        block_contents::{vid_commitment, BlockHeader},
+        election::Membership,
+        node_implementation::ConsensusTime,
+        BlockPayload,
+    },
+};
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn payloads_are_validated_without_voting() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let (payload, metadata) =
+        TestBlockPayload::from_transactions(vec![TestTransaction::new(vec![1, 2, 3])]).unwrap();
+
+    // a payload proposed on top of the genesis leaf, which holds the high QC
+    let acceptance = handle
+        .validate_payload(payload.clone(), metadata.clone(), ViewNumber::new(1))
+        .await
+        .unwrap();
+    let encoded: Vec<u8> = payload.encode().unwrap().collect();
+    let total_nodes = handle
+        .hotshot
+        .inner
+        .memberships
+        .quorum_membership
+        .total_nodes();
+    assert_eq!(
+        acceptance.payload_commitment,
+        vid_commitment(&encoded, total_nodes)
+    );
+    assert_eq!(
+        acceptance.block_header.payload_commitment(),
+        acceptance.payload_commitment
+    );
+    assert_eq!(
+        acceptance.builder_commitment,
+        payload.builder_commitment(&metadata)
+    );
+    assert_eq!(acceptance.parent, handle.get_decided_leaf().await.commit());
+    assert_eq!(acceptance.parent_view, ViewNumber::genesis());
+    assert!(acceptance.governance.is_empty());
+
+    // replicas drop the proposals of the views which are over
+    handle.get_consensus().write().await.cur_view = ViewNumber::new(3);
+    assert_eq!(
+        handle
+            .validate_payload(payload.clone(), metadata.clone(), ViewNumber::new(2))
+            .await,
+        Err(PayloadRejection::StaleView {
+            view: ViewNumber::new(2),
+            current_view: ViewNumber::new(3),
+        })
+    );
+}
+
+/// Payloads are held to the limits governance sets, as the proposals replicas receive are
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn payloads_over_the_transaction_limit_are_rejected() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    handle.get_consensus().write().await.parameter_schedule = ParameterSchedule::new(
+        ConsensusParameters {
+            next_view_timeout: 10_000,
+            max_transactions: NonZeroUsize::new(1).unwrap(),
+            da_committee_size: 10,
+        },
+        None,
+        10,
+    );
+    let (payload, metadata) = TestBlockPayload::from_transactions(vec![
+        TestTransaction::new(vec![1]),
+        TestTransaction::new(vec![2]),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        handle
+            .validate_payload(payload.clone(), metadata.clone(), ViewNumber::new(1))
+            .await,
+        Err(PayloadRejection::TooManyTransactions { count: 2, max: 1 })
+    );
+}
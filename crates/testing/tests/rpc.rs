@@ -0,0 +1,259 @@
+use std::{sync::Arc, time::Duration};
+
+use async_compatibility_layer::art::async_timeout;
+use bincode::Options;
+
+use hotshot::traits::implementations::{MasterMap, MemoryNetwork, NetworkingMetricsValue};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    message::{DataMessage, Message, MessageKind},
+    rpc::{NetworkRpc, RpcConfig, RpcEndpoint, RpcError, RpcMethod, RpcRequest, RpcResponse},
+    signature_key::BLSPubKey,
+    signing::{self, SigningDomain},
+    traits::{
+        network::{ConnectedNetwork, TransmitType},
+        signature_key::SignatureKey,
+    },
+};
+use hotshot_utils::bincode::bincode_opts;
+
+/// The network the nodes of these tests call each other over
+type Network = MemoryNetwork<Message<TestTypes>, BLSPubKey>;
+
+/// Asks for a number to be doubled
+struct Double;
+
+impl RpcMethod for Double {
+    const NAME: &'static str = "double";
+    type Request = u64;
+    type Response = u64;
+}
+
+/// A node calling its peers over a memory network
+struct Node {
+    /// the key of the node
+    key: BLSPubKey,
+    /// the key the node signs with
+    private_key: <BLSPubKey as SignatureKey>::PrivateKey,
+    /// the network of the node
+    network: Arc<Network>,
+    /// the calls of the node
+    rpc: NetworkRpc<TestTypes, Network>,
+}
+
+/// Node `i` of the memory network `group`, calling with `config`
+fn node(i: u64, group: &Arc<MasterMap<Message<TestTypes>, BLSPubKey>>, config: RpcConfig) -> Node {
+    let (key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], i);
+    let network = Arc::new(MemoryNetwork::new(
+        key,
+        NetworkingMetricsValue::default(),
+        group.clone(),
+        None,
+    ));
+    let rpc = NetworkRpc::new(network.clone(), 0, key, private_key.clone(), config);
+    Node {
+        key,
+        private_key,
+        network,
+        rpc,
+    }
+}
+
+/// Hand the next direct message `node` receives to its calls, as the network message task does
+async fn deliver(node: &Node) {
+    let messages = node
+        .network
+        .recv_msgs(TransmitType::Direct)
+        .await
+        .expect("Failed to receive message");
+    for message in messages {
+        match message.kind {
+            MessageKind::Data(DataMessage::RpcRequest(request)) => {
+                node.rpc.handle_request(message.sender, request);
+            }
+            MessageKind::Data(DataMessage::RpcResponse(response)) => {
+                node.rpc.handle_response(message.sender, response);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn requests_are_answered_by_the_handler_of_their_method() {
+    async_compatibility_layer::logging::setup_logging();
+    let group = MasterMap::new();
+    let caller = node(0, &group, RpcConfig::default());
+    let callee = node(1, &group, RpcConfig::default());
+    callee
+        .rpc
+        .register::<Double, _, _>(|_, n| async move { Ok(n * 2) });
+
+    let request = caller.rpc.request::<Double>(callee.key, &21);
+    let relay = async {
+        deliver(&callee).await;
+        deliver(&caller).await;
+    };
+    let (response, ()) = futures::join!(request, relay);
+    assert_eq!(response.expect("request failed"), 42);
+    assert_eq!(caller.rpc.pending_requests(), 0);
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn requests_of_unknown_methods_fail() {
+    async_compatibility_layer::logging::setup_logging();
+    let group = MasterMap::new();
+    let caller = node(0, &group, RpcConfig::default());
+    let callee = node(1, &group, RpcConfig::default());
+
+    let request = caller.rpc.request::<Double>(callee.key, &21);
+    let relay = async {
+        deliver(&callee).await;
+        deliver(&caller).await;
+    };
+    let (response, ()) = futures::join!(request, relay);
+    assert!(matches!(response, Err(RpcError::Remote { .. })));
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn unanswered_requests_time_out() {
+    async_compatibility_layer::logging::setup_logging();
+    let group = MasterMap::new();
+    let config = RpcConfig {
+        timeout_ms: 100,
+        ..RpcConfig::default()
+    };
+    let caller = node(0, &group, config);
+    let callee = node(1, &group, RpcConfig::default());
+
+    // the callee never reads its messages
+    let response = caller.rpc.request::<Double>(callee.key, &21).await;
+    assert!(matches!(
+        response,
+        Err(RpcError::Timeout { method: "double" })
+    ));
+    assert_eq!(caller.rpc.pending_requests(), 0);
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn responses_are_only_taken_from_the_recipient() {
+    async_compatibility_layer::logging::setup_logging();
+    let group = MasterMap::new();
+    let config = RpcConfig {
+        timeout_ms: 100,
+        ..RpcConfig::default()
+    };
+    let caller = node(0, &group, config);
+    let callee = node(1, &group, RpcConfig::default());
+    let impostor = node(2, &group, RpcConfig::default());
+
+    let request = caller.rpc.request::<Double>(callee.key, &21);
+    let forge = async {
+        // the first request of the caller has correlation id 0, and the impostor claims to be
+        // the callee, but can only sign as itself
+        let result = Ok(vec![0]);
+        let signature = signing::sign::<BLSPubKey>(
+            &impostor.private_key,
+            SigningDomain::RpcResponse,
+            &RpcResponse::signed_bytes(0, &result, &caller.key),
+        )
+        .unwrap();
+        caller.rpc.handle_response(
+            callee.key,
+            RpcResponse {
+                id: 0,
+                result,
+                signature,
+            },
+        );
+    };
+    let (response, ()) = futures::join!(request, forge);
+    assert!(matches!(response, Err(RpcError::Timeout { .. })));
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn requests_not_signed_by_their_sender_are_not_answered() {
+    async_compatibility_layer::logging::setup_logging();
+    let group = MasterMap::new();
+    let attacker = node(0, &group, RpcConfig::default());
+    let callee = node(1, &group, RpcConfig::default());
+    let victim = node(2, &group, RpcConfig::default());
+    callee
+        .rpc
+        .register::<Double, _, _>(|_, n| async move { Ok(n * 2) });
+
+    // the attacker has the callee answer the victim, which never asked
+    let body = bincode_opts().serialize(&21u64).unwrap();
+    let signature = signing::sign::<BLSPubKey>(
+        &attacker.private_key,
+        SigningDomain::RpcRequest,
+        &RpcRequest::signed_bytes(0, Double::NAME, &body, &callee.key),
+    )
+    .unwrap();
+    callee.rpc.handle_request(
+        victim.key,
+        RpcRequest {
+            id: 0,
+            method: Double::NAME.to_string(),
+            body,
+            signature,
+        },
+    );
+    let received = async_timeout(
+        Duration::from_millis(200),
+        victim.network.recv_msgs(TransmitType::Direct),
+    )
+    .await;
+    assert!(received.is_err(), "the victim received a response");
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn requests_over_the_limit_are_dropped_even_for_unknown_methods() {
+    async_compatibility_layer::logging::setup_logging();
+    let group = MasterMap::new();
+    let caller = node(
+        0,
+        &group,
+        RpcConfig {
+            timeout_ms: 200,
+            ..RpcConfig::default()
+        },
+    );
+    let callee = node(
+        1,
+        &group,
+        RpcConfig {
+            max_incoming_requests: 0,
+            ..RpcConfig::default()
+        },
+    );
+
+    // the callee has no handler, and no slot to answer with an error either
+    let request = caller.rpc.request::<Double>(callee.key, &21);
+    let (response, ()) = futures::join!(request, deliver(&callee));
+    assert!(matches!(response, Err(RpcError::Timeout { .. })));
+}
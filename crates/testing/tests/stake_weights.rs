@@ -0,0 +1,76 @@
+#[cfg(test)]
+#[test]
+fn test_skewed_stake_thresholds() {
+    use hotshot_example_types::node_types::TestTypes;
+    use hotshot_types::traits::{
+        election::Membership, node_implementation::NodeType, signature_key::SignatureKey,
+    };
+
+    type Key = <TestTypes as NodeType>::SignatureKey;
+    type TestMembership = <TestTypes as NodeType>::Membership;
+
+    let weights = [10u64, 1, 1, 1, 1, 1];
+    let entries = weights
+        .iter()
+        .zip(0u64..)
+        .map(|(stake, i)| {
+            Key::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .get_stake_table_entry(*stake)
+        })
+        .collect();
+    let membership = TestMembership::create_election(
+        entries,
+        TestMembership::default_election_config(weights.len() as u64),
+    );
+
+    // thresholds are fractions of the total stake of 15, not of the 6 nodes
+    assert_eq!(membership.success_threshold().get(), 11);
+    assert_eq!(membership.failure_threshold().get(), 6);
+
+    let heavy = Key::generated_from_seed_indexed([0u8; 32], 0).0;
+    assert_eq!(
+        membership.get_stake(&heavy),
+        Some(heavy.get_stake_table_entry(10))
+    );
+    let outsider = Key::generated_from_seed_indexed([0u8; 32], 6).0;
+    assert!(!membership.has_stake(&outsider));
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_skewed_stake_survives_half_the_nodes_failing() {
+    use std::time::Duration;
+
+    use hotshot_example_types::node_types::{MemoryImpl, TestTypes};
+    use hotshot_testing::{
+        harness::TestHarness, overall_safety_task::OverallSafetyPropertiesDescription,
+        test_builder::TimingData,
+    };
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // the remaining 12 of 15 stake still meet the threshold of 11, although with unit
+    // weights losing 3 of 6 nodes would stall consensus
+    TestHarness::<TestTypes, MemoryImpl>::new()
+        .nodes(6)
+        .stake_weights([10, 1, 1, 1, 1, 1])
+        .faulty_nodes([3, 4, 5], 5)
+        .timing(TimingData {
+            next_view_timeout: 1000,
+            ..Default::default()
+        })
+        .success_criteria(OverallSafetyPropertiesDescription {
+            num_failed_views: 15,
+            ..Default::default()
+        })
+        .rounds(10)
+        .timeout(Duration::from_secs(120))
+        .run()
+        .await;
+}
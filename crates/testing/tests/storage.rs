@@ -2,16 +2,20 @@ use commit::Committable;
 use hotshot::traits::implementations::MemoryStorage;
 use hotshot::traits::Storage;
 use hotshot_example_types::{
-    block_types::{genesis_vid_commitment, TestBlockHeader, TestBlockPayload},
+    block_types::{
+        genesis_transactions_root, genesis_vid_commitment, TestBlockHeader, TestBlockPayload,
+    },
     node_types::TestTypes,
 };
 use hotshot_types::{
     data::{fake_commitment, Leaf},
+    payload_store::PayloadStore,
     simple_certificate::QuorumCertificate,
     traits::{
+        block_contents::vid_commitment,
         node_implementation::{ConsensusTime, NodeType},
         signature_key::SignatureKey,
-        storage::{StoredView, TestableStorage},
+        storage::{IntegrityViolation, StoredView, TestableStorage, ViewArtifacts},
     },
 };
 use std::marker::PhantomData;
@@ -22,6 +26,9 @@ fn random_stored_view(view_number: <TestTypes as NodeType>::Time) -> StoredView<
     let header = TestBlockHeader {
         block_number: 0,
         payload_commitment: genesis_vid_commitment(),
+        transactions_root: genesis_transactions_root(),
+        parent_commitment: None,
+        extensions: Vec::new(),
     };
     let dummy_leaf_commit = fake_commitment::<Leaf<TestTypes>>();
     let data = hotshot_types::simple_vote::QuorumData {
@@ -70,3 +77,455 @@ async fn memory_storage() {
         .unwrap();
     assert!(storage.get_anchored_view().await.is_err());
 }
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_storage_keeps_view_artifacts() {
+    let storage = MemoryStorage::construct_tmp_storage().unwrap();
+    let view_number = <TestTypes as NodeType>::Time::new(3);
+    let artifacts = ViewArtifacts::<TestTypes> {
+        view_number,
+        proposal: None,
+        da_certificate: None,
+        quorum_certificate: Some(random_stored_view(view_number).justify_qc),
+        signers: Vec::new(),
+    };
+    storage.append_view_artifacts(artifacts).await.unwrap();
+
+    // artifacts outlive the cleanup of stored views
+    storage
+        .cleanup_storage_up_to_view(view_number + 10)
+        .await
+        .unwrap();
+    let stored = storage
+        .get_view_artifacts(view_number)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.view_number, view_number);
+    assert_eq!(
+        stored.quorum_certificate.map(|qc| qc.view_number),
+        Some(view_number)
+    );
+    assert!(storage
+        .get_view_artifacts(view_number + 1)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_storage_prunes_view_artifacts_past_retention() {
+    let storage = MemoryStorage::<TestTypes>::construct_tmp_storage().unwrap();
+    let view = <TestTypes as NodeType>::Time::new;
+    for view_number in 1..=5 {
+        storage
+            .append_view_artifacts(ViewArtifacts::<TestTypes> {
+                view_number: view(view_number),
+                proposal: None,
+                da_certificate: None,
+                quorum_certificate: None,
+                signers: Vec::new(),
+            })
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        storage
+            .cleanup_view_artifacts_before_view(view(3))
+            .await
+            .unwrap(),
+        2
+    );
+    for view_number in 1..=5 {
+        assert_eq!(
+            storage
+                .get_view_artifacts(view(view_number))
+                .await
+                .unwrap()
+                .is_some(),
+            view_number >= 3
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_storage_keeps_stake_table_history() {
+    let storage = MemoryStorage::<TestTypes>::construct_tmp_storage().unwrap();
+    let view = <TestTypes as NodeType>::Time::new;
+    let stake_table = |stake: u64| {
+        vec![
+            <TestTypes as NodeType>::SignatureKey::generated_from_seed_indexed([0u8; 32], 0)
+                .0
+                .get_stake_table_entry(stake),
+        ]
+    };
+    assert!(storage.get_stake_table_at(view(0)).await.unwrap().is_none());
+    for (start, stake) in [(0, 1), (10, 2), (20, 3)] {
+        storage
+            .append_stake_table(view(start), stake_table(stake))
+            .await
+            .unwrap();
+    }
+    for (at, stake) in [(0, 1), (9, 1), (10, 2), (19, 2), (25, 3)] {
+        assert_eq!(
+            storage.get_stake_table_at(view(at)).await.unwrap(),
+            Some(stake_table(stake))
+        );
+    }
+
+    // the table in effect at the horizon is kept
+    assert_eq!(
+        storage
+            .cleanup_stake_tables_before_view(view(15))
+            .await
+            .unwrap(),
+        1
+    );
+    assert!(storage.get_stake_table_at(view(9)).await.unwrap().is_none());
+    assert_eq!(
+        storage.get_stake_table_at(view(15)).await.unwrap(),
+        Some(stake_table(2))
+    );
+    assert_eq!(
+        storage.get_stake_table_at(view(20)).await.unwrap(),
+        Some(stake_table(3))
+    );
+}
+
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_storage_shares_payloads_with_consensus() {
+    let storage = MemoryStorage::<TestTypes>::construct_tmp_storage().unwrap();
+    let store = PayloadStore::default();
+    let view = <TestTypes as NodeType>::Time::new;
+    let bytes = vec![9u8; 64];
+    let commitment = vid_commitment(&bytes, 4);
+
+    // the payload of two views, held by consensus and the storage
+    let payload = store.intern(commitment, bytes.clone().into());
+    for v in [1, 2] {
+        storage
+            .append_payload(view(v), commitment, payload.clone())
+            .await
+            .unwrap();
+    }
+    assert_eq!(store.references(&commitment), 2);
+    assert_eq!(store.bytes_in_memory(), 64);
+    drop(payload);
+
+    // the payload stays while a view keeps it
+    for v in [1, 2] {
+        storage
+            .append_single_view(random_stored_view(view(v)))
+            .await
+            .unwrap();
+    }
+    storage.cleanup_storage_up_to_view(view(2)).await.unwrap();
+    assert_eq!(
+        storage.get_payload(&commitment).await.unwrap().as_deref(),
+        Some(&bytes[..])
+    );
+
+    storage.cleanup_storage_up_to_view(view(3)).await.unwrap();
+    assert!(storage.get_payload(&commitment).await.unwrap().is_none());
+    assert_eq!(store.bytes_in_memory(), 0);
+    assert_eq!(store.collect_garbage(), 1);
+}
+
+/// A view extending `parent`, certified by a QC for `parent`
+fn child_stored_view(
+    parent: &StoredView<TestTypes>,
+    view_number: <TestTypes as NodeType>::Time,
+) -> StoredView<TestTypes> {
+    let parent_commit = Leaf::from(parent.clone()).commit();
+    let data = hotshot_types::simple_vote::QuorumData {
+        leaf_commit: parent_commit,
+    };
+    let commit = data.commit();
+    StoredView {
+        view_number,
+        parent: parent_commit,
+        justify_qc: QuorumCertificate {
+            is_genesis: false,
+            data,
+            vote_commitment: commit,
+            signatures: None,
+            view_number: parent.view_number,
+            _pd: PhantomData,
+        },
+        ..parent.clone()
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_storage_verifies_and_compacts() {
+    let storage = MemoryStorage::construct_tmp_storage().unwrap();
+    let genesis = random_stored_view(<TestTypes as NodeType>::Time::genesis());
+    let first = child_stored_view(&genesis, <TestTypes as NodeType>::Time::new(1));
+    let second = child_stored_view(&first, <TestTypes as NodeType>::Time::new(2));
+    for view in [genesis, first, second.clone()] {
+        storage.append_single_view(view).await.unwrap();
+    }
+    let report = storage.verify_integrity().await.unwrap();
+    assert!(report.is_ok(), "{:?}", report.violations);
+    assert_eq!(report.views_checked, 3);
+
+    // a view whose parent is unknown and which its QC does not certify
+    let mut orphan = child_stored_view(&second, <TestTypes as NodeType>::Time::new(3));
+    orphan.parent = fake_commitment::<Leaf<TestTypes>>();
+    let broken = MemoryStorage::construct_tmp_storage().unwrap();
+    broken.append_single_view(second.clone()).await.unwrap();
+    broken.append_single_view(orphan).await.unwrap();
+    assert_eq!(
+        broken.verify_integrity().await.unwrap().violations,
+        vec![
+            IntegrityViolation::QcLinkMismatch {
+                view: <TestTypes as NodeType>::Time::new(3)
+            },
+            IntegrityViolation::MissingParent {
+                view: <TestTypes as NodeType>::Time::new(3)
+            },
+        ]
+    );
+
+    // compacting keeps only the anchored view, which is still consistent on its own
+    assert_eq!(storage.compact().await.unwrap(), 2);
+    assert_eq!(storage.get_anchored_view().await.unwrap(), second);
+    assert!(storage.verify_integrity().await.unwrap().is_ok());
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn memory_storage_serves_reads_of_views() {
+    let view = <TestTypes as NodeType>::Time::new;
+    let storage = MemoryStorage::construct_tmp_storage().unwrap();
+    assert!(storage.latest_anchor().await.unwrap().is_none());
+    assert_eq!(storage.iter_views(..).await.unwrap().count(), 0);
+
+    let genesis = random_stored_view(<TestTypes as NodeType>::Time::genesis());
+    let first = child_stored_view(&genesis, view(1));
+    let second = child_stored_view(&first, view(2));
+    for stored in [genesis.clone(), first.clone(), second.clone()] {
+        storage.append_single_view(stored).await.unwrap();
+    }
+
+    let views: Vec<_> = storage.iter_views(view(1)..).await.unwrap().collect();
+    assert_eq!(views, vec![first.clone(), second.clone()]);
+    let views: Vec<_> = storage.iter_views(..view(1)).await.unwrap().collect();
+    assert_eq!(views, vec![genesis]);
+    assert_eq!(storage.latest_anchor().await.unwrap(), Some(second.clone()));
+
+    // the QC of a view is the justify QC of its child, and the newest view has none yet
+    assert_eq!(
+        storage.qc_for_view(view(1)).await.unwrap(),
+        Some(second.justify_qc)
+    );
+    assert!(storage.qc_for_view(view(2)).await.unwrap().is_none());
+
+    // once its child is cleaned up, the QC of a view is read from its artifacts
+    storage
+        .append_view_artifacts(ViewArtifacts {
+            view_number: view(0),
+            proposal: None,
+            da_certificate: None,
+            quorum_certificate: Some(first.justify_qc.clone()),
+            signers: Vec::new(),
+        })
+        .await
+        .unwrap();
+    storage.cleanup_storage_up_to_view(view(2)).await.unwrap();
+    assert_eq!(
+        storage.qc_for_view(view(0)).await.unwrap(),
+        Some(first.justify_qc)
+    );
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn uncommitted_leaves_are_recovered_from_the_write_ahead_log() {
+    use hotshot_types::storage_sync::{
+        encode_wal_record, recover_storage, StorageSyncConfig, WriteAheadLog,
+    };
+
+    let config = StorageSyncConfig {
+        wal_directory: Some(
+            std::env::temp_dir().join(format!("hotshot-recovery-{}", std::process::id())),
+        ),
+        ..StorageSyncConfig::default()
+    };
+    let path = config.wal_path(0).unwrap();
+    let genesis = random_stored_view(<TestTypes as NodeType>::Time::genesis());
+    let latest = random_stored_view(<TestTypes as NodeType>::Time::new(2));
+    let wal = WriteAheadLog::open(&path).unwrap();
+    wal.append(&[encode_wal_record(&genesis).unwrap()]).unwrap();
+    wal.append(&[encode_wal_record(&latest).unwrap()]).unwrap();
+
+    // the node crashed before committing them, and restarts with empty storage
+    let storage = MemoryStorage::<TestTypes>::construct_tmp_storage().unwrap();
+    assert_eq!(recover_storage(&storage, &config, 0).await.unwrap(), 2);
+    assert_eq!(storage.get_anchored_view().await.unwrap(), latest);
+
+    // the log is emptied once its leaves are committed
+    assert!(WriteAheadLog::read(&path).unwrap().is_empty());
+    assert_eq!(recover_storage(&storage, &config, 0).await.unwrap(), 0);
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn only_verified_votes_are_persisted() {
+    use hotshot::{types::BLSPubKey, HotShotConsensusApi};
+    use hotshot_example_types::node_types::MemoryImpl;
+    use hotshot_task_impls::{
+        events::HotShotEvent,
+        vote::{handle_vote, AccumulatorInfo},
+    };
+    use hotshot_testing::task_helpers::build_system_handle;
+    use hotshot_types::{
+        data::ViewNumber,
+        simple_vote::{QuorumData, QuorumVote},
+        traits::{consensus_api::ConsensusApi, election::Membership},
+        vote::Vote,
+    };
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let membership = api.inner.memberships.quorum_membership.clone();
+    // a view whose quorum votes this node collects
+    let view = (1..10)
+        .map(ViewNumber::new)
+        .find(|view| membership.get_leader(*view + 1) == *api.public_key())
+        .unwrap();
+    let data = QuorumData {
+        leaf_commit: fake_commitment::<Leaf<TestTypes>>(),
+    };
+    let valid = QuorumVote::<TestTypes>::create_signed_vote(
+        data.clone(),
+        view,
+        api.public_key(),
+        api.private_key(),
+    )
+    .unwrap();
+    // a vote claiming to be of the other node, signed with this node's key
+    let other = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1).0;
+    let other = if other == *api.public_key() {
+        BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0
+    } else {
+        other
+    };
+    let forged =
+        QuorumVote::<TestTypes>::create_signed_vote(data, view, &other, api.private_key()).unwrap();
+
+    let info = AccumulatorInfo {
+        public_key: *api.public_key(),
+        membership: membership.into(),
+        view,
+        id: 0,
+    };
+    let (sender, _receiver) = async_broadcast::broadcast(16);
+    let mut collector = None;
+    for vote in [&forged, &valid, &valid] {
+        handle_vote::<_, MemoryImpl, _, _, QuorumCertificate<TestTypes>>(
+            &mut collector,
+            vote,
+            HotShotEvent::QuorumVoteRecv(vote.clone()),
+            &info,
+            &api,
+            &sender,
+        )
+        .await;
+    }
+
+    // the forged vote is dropped, and the valid one is persisted once
+    let stored = api
+        .get_stored_votes(view, valid.get_data_commitment().as_ref())
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 1);
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+#[instrument]
+async fn stake_tables_of_unrecorded_views_are_not_guessed() {
+    use hotshot_testing::task_helpers::build_system_handle;
+    use hotshot_types::{error::HotShotError, traits::election::Membership};
+
+    let handle = build_system_handle(2).await.0;
+    let view = <TestTypes as NodeType>::Time::new;
+    let current = handle
+        .hotshot
+        .inner
+        .memberships
+        .quorum_membership
+        .get_committee_qc_stake_table();
+    // the stake table of the committee is recorded from the anchor on
+    assert_eq!(handle.get_stake_table_at(view(3)).await.unwrap(), current);
+
+    let next = current[..1].to_vec();
+    handle
+        .storage()
+        .append_stake_table(view(10), next.clone())
+        .await
+        .unwrap();
+    handle
+        .storage()
+        .cleanup_stake_tables_before_view(view(15))
+        .await
+        .unwrap();
+    assert_eq!(handle.get_stake_table_at(view(20)).await.unwrap(), next);
+    // the current stake table is not taken for views whose table was pruned
+    assert!(matches!(
+        handle.get_stake_table_at(view(5)).await,
+        Err(HotShotError::StakeTableNotFound { .. })
+    ));
+}
@@ -0,0 +1,56 @@
+#![allow(clippy::panic)]
+use hotshot::SystemContext;
+use hotshot_example_types::node_types::{MemoryImpl, TestTypes};
+use hotshot_testing::{task_helpers::system_context_builder, test_builder::TestMetadata};
+use hotshot_types::error::{ErrorKind, HotShotError};
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn builder_requires_every_part() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let launcher = TestMetadata::default_multiple_rounds().gen_launcher::<TestTypes, MemoryImpl>(0);
+    let config = launcher.resource_generator.config.clone();
+    let storage = (launcher.resource_generator.storage)(0);
+
+    let result = SystemContext::<TestTypes, MemoryImpl>::builder(
+        config.my_own_validator_config.public_key,
+        config.my_own_validator_config.private_key.clone(),
+        0,
+        config,
+    )
+    .storage(storage)
+    .build_context()
+    .await;
+    let Err(error @ HotShotError::InvalidConfig { .. }) = result else {
+        panic!("a node without networks nor initializer was built");
+    };
+    assert_eq!(error.kind(), ErrorKind::Config);
+    let message = error.to_string();
+    assert!(message.contains("memberships, networks, initializer"));
+    assert!(!message.contains("storage"));
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn builder_builds_observers() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let (mut handle, _tx, _rx) = system_context_builder(1)
+        .observer(true)
+        .build()
+        .await
+        .expect("Could not build an observer");
+    assert!(handle.is_observer());
+    handle.shut_down().await;
+}
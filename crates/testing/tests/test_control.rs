@@ -0,0 +1,52 @@
+#![allow(clippy::panic)]
+use std::time::Duration;
+
+use async_broadcast::Receiver;
+use async_compatibility_layer::art::async_timeout;
+use hotshot::{control::ControlledTask, plugin::BusEvent};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_testing::task_helpers::build_system_handle;
+use hotshot_types::{data::ViewNumber, traits::node_implementation::ConsensusTime};
+
+/// Wait for the consensus task to enter `view`
+async fn view_started(events: &mut Receiver<BusEvent<TestTypes>>, view: u64) {
+    loop {
+        match events.recv().await {
+            Ok(BusEvent::ViewStarted { view: started }) if *started == view => return,
+            Ok(_) => {}
+            Err(e) => panic!("the event bus closed: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn paused_tasks_handle_forced_views_once_resumed() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let (mut handle, _tx, _rx) = build_system_handle(1).await;
+    let control = handle.hotshot.test_control();
+    let mut events = handle.subscribe_event_bus();
+
+    control.pause(ControlledTask::Consensus);
+    assert!(control.is_paused(ControlledTask::Consensus));
+    assert!(!control.is_paused(ControlledTask::DA));
+    control.force_view(ViewNumber::new(5)).await;
+    assert!(
+        async_timeout(Duration::from_millis(200), view_started(&mut events, 5))
+            .await
+            .is_err(),
+        "a paused consensus task entered the forced view"
+    );
+
+    control.resume(ControlledTask::Consensus);
+    async_timeout(Duration::from_secs(2), view_started(&mut events, 5))
+        .await
+        .expect("the resumed consensus task did not enter the forced view");
+    handle.shut_down().await;
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use async_compatibility_layer::art::{async_sleep, async_timeout};
+use commit::Committable;
+use hotshot_example_types::block_types::TestTransaction;
+use hotshot_task_impls::events::HotShotEvent;
+use hotshot_testing::task_helpers::build_system_handle;
+use hotshot_types::receipt::TxStatus;
+
+/// The handle reports transactions it never saw as unknown, and those in its mempool as pending
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn the_handle_reports_what_became_of_a_transaction() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let (handle, sender, _receiver) = build_system_handle(2).await;
+    let tx = TestTransaction::new(vec![1, 2, 3]);
+    assert!(matches!(
+        handle.tx_status(&tx.commit()).await,
+        TxStatus::Unknown
+    ));
+
+    sender
+        .broadcast(HotShotEvent::TransactionsRecv(vec![tx.clone()]))
+        .await
+        .unwrap();
+    async_timeout(Duration::from_secs(5), async {
+        while !matches!(handle.tx_status(&tx.commit()).await, TxStatus::Pending) {
+            async_sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the transaction never entered the mempool");
+
+    // a transaction the node never received stays unknown
+    let unseen = TestTransaction::new(vec![4, 5, 6]);
+    assert!(matches!(
+        handle.tx_status(&unseen.commit()).await,
+        TxStatus::Unknown
+    ));
+}
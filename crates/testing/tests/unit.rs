@@ -1,4 +1,48 @@
 mod unit {
+    mod accumulator;
+    mod anchor;
+    mod bandwidth;
+    mod batch_verify;
+    mod campaign;
+    mod clock;
+    mod compact_block;
+    mod config_schema;
+    mod control;
+    mod da_fallback;
+    mod epoch;
+    mod error;
+    mod event_bus;
+    mod future_view_buffer;
+    mod fuzz;
+    mod governance;
+    mod header_chain;
+    mod header_extension;
+    mod inclusion_proof;
+    mod leaf_dag;
+    mod load_generator;
+    mod log_filter;
+    mod log_throttle;
+    mod mempool;
+    mod mempool_sync;
     mod message;
+    mod node_override;
+    mod orchestrator;
+    mod partition;
+    mod preflight;
+    mod qc_chain;
+    mod ramp_up;
+    mod receipt;
+    mod reputation;
+    mod run_config;
+    mod runtime;
+    mod safety_monitor;
+    mod soak;
+    mod stake_table_cache;
+    mod stall;
+    mod state_pipeline;
+    mod storage_sync;
     mod version;
+    mod view_latency;
+    mod view_window;
+    mod vote_progress;
 }
@@ -0,0 +1,113 @@
+use either::Either;
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    signature_key::BLSPubKey,
+    simple_certificate::{TimeoutCertificate, ViewSyncPreCommitCertificate2},
+    simple_vote::{TimeoutData, TimeoutVote, ViewSyncPreCommitData, ViewSyncPreCommitVote},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+    vote::{
+        AggregatedSignaturePolicy, Certificate, CertificatePolicy, SimpleCountPolicy,
+        StakeWeightedPolicy, VoteAccumulator,
+    },
+};
+
+type TestMembership = <TestTypes as NodeType>::Membership;
+type ViewNumber = <TestTypes as NodeType>::Time;
+
+/// A committee whose node `i` has a stake of `weights[i]`
+fn membership(weights: &[u64]) -> TestMembership {
+    let entries = weights
+        .iter()
+        .zip(0u64..)
+        .map(|(stake, i)| {
+            BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .get_stake_table_entry(*stake)
+        })
+        .collect();
+    TestMembership::create_election(
+        entries,
+        TestMembership::default_election_config(weights.len() as u64),
+    )
+}
+
+/// Timeout vote of node `i` for view 1
+fn timeout_vote(i: u64) -> TimeoutVote<TestTypes> {
+    let (public_key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], i);
+    let view = ViewNumber::new(1);
+    TimeoutVote::create_signed_vote(TimeoutData { view }, view, &public_key, &private_key).unwrap()
+}
+
+/// Number of votes from nodes `0..` needed before a timeout certificate forms
+fn votes_needed<POLICY: CertificatePolicy<TestTypes>>(weights: &[u64]) -> Option<u64> {
+    let membership = membership(weights);
+    let mut accumulator =
+        VoteAccumulator::<TestTypes, _, TimeoutCertificate<TestTypes>, POLICY>::new();
+    for i in 0..weights.len() as u64 {
+        if let Either::Right(cert) = accumulator.accumulate(&timeout_vote(i), &membership) {
+            assert!(cert.is_valid_cert(&membership));
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+#[test]
+fn accumulator_policies() {
+    let unit = [1, 1, 1, 1];
+    assert_eq!(votes_needed::<SimpleCountPolicy>(&unit), Some(3));
+    assert_eq!(votes_needed::<StakeWeightedPolicy>(&unit), Some(3));
+    assert_eq!(votes_needed::<AggregatedSignaturePolicy>(&unit), Some(3));
+
+    // a threshold of 5 of the total stake of 6
+    let skewed = [3, 1, 1, 1];
+    assert_eq!(votes_needed::<StakeWeightedPolicy>(&skewed), Some(3));
+    assert_eq!(votes_needed::<AggregatedSignaturePolicy>(&skewed), Some(3));
+    assert_eq!(votes_needed::<SimpleCountPolicy>(&skewed), None);
+}
+
+#[test]
+fn accumulator_ignores_duplicate_votes() {
+    let membership = membership(&[1, 1, 1, 1]);
+    let mut accumulator = VoteAccumulator::<TestTypes, _, TimeoutCertificate<TestTypes>>::new();
+    for _ in 0..4 {
+        assert!(accumulator
+            .accumulate(&timeout_vote(0), &membership)
+            .is_left());
+    }
+
+    // the next two distinct votes complete the certificate
+    assert!(accumulator
+        .accumulate(&timeout_vote(1), &membership)
+        .is_left());
+    assert!(accumulator
+        .accumulate(&timeout_vote(2), &membership)
+        .is_right());
+}
+
+#[test]
+fn accumulator_uses_the_certificate_threshold() {
+    let membership = membership(&[1, 1, 1, 1, 1, 1]);
+    let mut accumulator =
+        VoteAccumulator::<TestTypes, _, ViewSyncPreCommitCertificate2<TestTypes>>::new();
+    let round = ViewNumber::new(1);
+    let vote = |i| {
+        let (public_key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], i);
+        ViewSyncPreCommitVote::<TestTypes>::create_signed_vote(
+            ViewSyncPreCommitData { relay: 0, round },
+            round,
+            &public_key,
+            &private_key,
+        )
+        .unwrap()
+    };
+
+    // f + 1 of 6 is 3, fewer than the 5 a timeout certificate needs
+    assert!(accumulator.accumulate(&vote(0), &membership).is_left());
+    assert!(accumulator.accumulate(&vote(1), &membership).is_left());
+    assert!(accumulator.accumulate(&vote(2), &membership).is_right());
+}
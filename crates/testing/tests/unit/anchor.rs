@@ -0,0 +1,108 @@
+use commit::Committable;
+use hotshot_constants::ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS;
+use hotshot_example_types::{node_types::TestTypes, state_types::TestInstanceState};
+use hotshot_testing::task_helpers::{build_cert, key_pair_for_id};
+use hotshot_types::{
+    anchor::{is_announcement_due, AnchorAnnouncement, AnchorHint, LatestAnchor},
+    data::Leaf,
+    simple_certificate::QuorumCertificate,
+    simple_vote::{QuorumData, QuorumVote},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+};
+
+type TestMembership = <TestTypes as NodeType>::Membership;
+type ViewNumber = <TestTypes as NodeType>::Time;
+
+/// A committee of the `size` nodes starting at node `first`, with a stake of 1 each
+fn membership(first: u64, size: u64) -> TestMembership {
+    let entries = (first..first + size)
+        .map(|i| key_pair_for_id(i).1.get_stake_table_entry(1))
+        .collect();
+    TestMembership::create_election(entries, TestMembership::default_election_config(size))
+}
+
+/// a hint of the anchor at `view`
+fn hint(view: u64) -> AnchorHint {
+    let mut leaf = Leaf::<TestTypes>::genesis(&TestInstanceState {});
+    leaf.view_number = ViewNumber::new(view);
+    AnchorHint::new(&leaf, &QuorumCertificate::genesis())
+}
+
+#[test]
+fn hints_name_the_anchor() {
+    let genesis = Leaf::<TestTypes>::genesis(&TestInstanceState {});
+    let hint = AnchorHint::new(&genesis, &QuorumCertificate::genesis());
+    assert_eq!(hint.view, 0);
+    assert_eq!(hint.leaf_commitment, genesis.commit().to_string());
+    assert_eq!(
+        hint.qc_hash,
+        AnchorHint::new(&genesis, &QuorumCertificate::genesis()).qc_hash
+    );
+}
+
+#[test]
+fn announcements_are_due_every_interval() {
+    let interval = ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS;
+    assert!(!is_announcement_due(0, interval - 1));
+    assert!(is_announcement_due(interval - 1, interval));
+    assert!(!is_announcement_due(interval, interval + 1));
+    // an anchor jumping over several intervals is announced once
+    assert!(is_announcement_due(1, 3 * interval + 1));
+}
+
+#[test]
+fn latest_anchor_keeps_the_highest_view() {
+    let latest = LatestAnchor::default();
+    assert_eq!(latest.get(), None);
+
+    assert!(latest.record(hint(20)));
+    assert!(!latest.record(hint(10)));
+    assert!(!latest.record(hint(20)));
+    assert_eq!(latest.get().map(|hint| hint.view), Some(20));
+
+    // clones share the latest anchor
+    assert!(latest.clone().record(hint(30)));
+    assert_eq!(latest.get(), Some(hint(30)));
+}
+
+#[test]
+fn only_certified_announcements_give_hints() {
+    let committee = membership(0, 4);
+    let mut leaf = Leaf::<TestTypes>::genesis(&TestInstanceState {});
+    leaf.view_number = ViewNumber::new(20);
+    let (private_key, public_key) = key_pair_for_id(0);
+    let qc = build_cert::<
+        TestTypes,
+        QuorumData<TestTypes>,
+        QuorumVote<TestTypes>,
+        QuorumCertificate<TestTypes>,
+    >(
+        QuorumData {
+            leaf_commit: leaf.commit(),
+        },
+        &committee,
+        leaf.view_number,
+        &public_key,
+        &private_key,
+    );
+    let announcement = AnchorAnnouncement::new(&leaf, &qc);
+    assert_eq!(
+        announcement.verify(&committee),
+        Some(AnchorHint::new(&leaf, &qc))
+    );
+
+    // an anchor moved to a later view is not the leaf the quorum certified
+    let mut inflated = announcement.clone();
+    inflated.leaf.view_number = ViewNumber::new(u64::MAX);
+    assert_eq!(inflated.verify(&committee), None);
+    inflated.qc.view_number = ViewNumber::new(u64::MAX);
+    assert_eq!(inflated.verify(&committee), None);
+    // nor does the genesis certificate certify a leaf, nor the certificate of another committee
+    let uncertified = AnchorAnnouncement::new(&leaf, &QuorumCertificate::genesis());
+    assert_eq!(uncertified.verify(&committee), None);
+    assert_eq!(announcement.verify(&membership(4, 4)), None);
+}
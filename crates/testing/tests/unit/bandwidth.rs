@@ -0,0 +1,62 @@
+use hotshot_types::{
+    bandwidth::{
+        BandwidthMeter, BandwidthUsage, Direction, BROADCAST_PEER, MAX_METERED_PEERS, OTHER_PEERS,
+    },
+    message::MessagePurpose,
+};
+
+#[test]
+fn usage_is_totaled_by_peer_and_class() {
+    let meter = BandwidthMeter::default();
+    meter.record("alice", MessagePurpose::Vote, Direction::Sent, 10);
+    meter.record("alice", MessagePurpose::Vote, Direction::Sent, 5);
+    meter.record("alice", MessagePurpose::Vote, Direction::Received, 7);
+    meter.record("alice", MessagePurpose::Proposal, Direction::Received, 100);
+    meter.record(
+        BROADCAST_PEER,
+        MessagePurpose::Proposal,
+        Direction::Sent,
+        200,
+    );
+
+    let usage = |peer: &str, class: &str, sent_bytes, received_bytes| BandwidthUsage {
+        peer: peer.to_string(),
+        class: class.to_string(),
+        sent_bytes,
+        received_bytes,
+    };
+    assert_eq!(
+        meter.usage(),
+        vec![
+            usage("alice", "proposal", 0, 100),
+            usage("alice", "vote", 15, 7),
+            usage(BROADCAST_PEER, "proposal", 200, 0),
+        ]
+    );
+}
+
+#[test]
+fn metered_peers_are_bounded() {
+    let meter = BandwidthMeter::default();
+    for peer in 0..MAX_METERED_PEERS + 10 {
+        meter.record(
+            &peer.to_string(),
+            MessagePurpose::Vote,
+            Direction::Received,
+            1,
+        );
+    }
+    // peers metered already keep their own totals
+    meter.record("0", MessagePurpose::Vote, Direction::Received, 1);
+
+    let usage = meter.usage();
+    assert_eq!(usage.len(), MAX_METERED_PEERS + 1);
+    let received = |peer: &str| {
+        usage
+            .iter()
+            .find(|usage| usage.peer == peer)
+            .map(|usage| usage.received_bytes)
+    };
+    assert_eq!(received("0"), Some(2));
+    assert_eq!(received(OTHER_PEERS), Some(10));
+}
@@ -0,0 +1,117 @@
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    signature_key::BLSPubKey,
+    signing::{self, SigningDomain},
+    simple_certificate::TimeoutCertificate,
+    simple_vote::{TimeoutData, TimeoutVote},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+    vote::{Certificate, VoteAccumulator},
+};
+
+type TestMembership = <TestTypes as NodeType>::Membership;
+type ViewNumber = <TestTypes as NodeType>::Time;
+type Signature = <BLSPubKey as SignatureKey>::PureAssembledSignatureType;
+
+/// The key pair of node `i`
+fn key_pair(i: u64) -> (BLSPubKey, <BLSPubKey as SignatureKey>::PrivateKey) {
+    BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+}
+
+/// Node `i`'s key, the encoding of `message` and node `signer`'s signature of it
+fn signed(i: u64, signer: u64, message: &[u8]) -> (BLSPubKey, Vec<u8>, Signature) {
+    let signature =
+        signing::sign::<BLSPubKey>(&key_pair(signer).1, SigningDomain::Vote, message).unwrap();
+    (
+        key_pair(i).0,
+        signing::encode(SigningDomain::Vote, message),
+        signature,
+    )
+}
+
+/// Whether `batch` verifies at once
+fn batch_verifies(batch: &[(BLSPubKey, Vec<u8>, Signature)]) -> bool {
+    let items: Vec<_> = batch
+        .iter()
+        .map(|(key, data, signature)| (key, data.as_slice(), signature))
+        .collect();
+    BLSPubKey::batch_verify(&items)
+}
+
+/// A committee of `size` nodes with a stake of 1 each
+fn membership(size: u64) -> TestMembership {
+    let entries = (0..size)
+        .map(|i| key_pair(i).0.get_stake_table_entry(1))
+        .collect();
+    TestMembership::create_election(entries, TestMembership::default_election_config(size))
+}
+
+/// Timeout vote for view 1 in the name of node `i`, signed by node `signer`
+fn timeout_vote(i: u64, signer: u64) -> TimeoutVote<TestTypes> {
+    let view = ViewNumber::new(1);
+    TimeoutVote::create_signed_vote(
+        TimeoutData { view },
+        view,
+        &key_pair(i).0,
+        &key_pair(signer).1,
+    )
+    .unwrap()
+}
+
+#[test]
+fn batches_verify_only_if_every_signature_does() {
+    assert!(batch_verifies(&[]));
+
+    let same_message: Vec<_> = (0..4).map(|i| signed(i, i, b"vote")).collect();
+    assert!(batch_verifies(&same_message));
+
+    let mixed_messages: Vec<_> = (0..4)
+        .map(|i| signed(i, i, format!("vote {i}").as_bytes()))
+        .collect();
+    assert!(batch_verifies(&mixed_messages));
+
+    let mut forged = same_message.clone();
+    forged[2] = signed(2, 3, b"vote");
+    assert!(!batch_verifies(&forged));
+
+    let mut wrong_message = mixed_messages;
+    wrong_message[1].1 = signing::encode(SigningDomain::Vote, b"vote 0");
+    assert!(!batch_verifies(&wrong_message));
+}
+
+#[test]
+fn valid_batches_form_certificates() {
+    let membership = membership(4);
+    let mut accumulator = VoteAccumulator::<TestTypes, _, TimeoutCertificate<TestTypes>>::new();
+    let votes: Vec<_> = (0..3).map(|i| timeout_vote(i, i)).collect();
+
+    let cert = accumulator
+        .accumulate_batch(&votes, &membership)
+        .right()
+        .expect("three of four votes did not form a certificate");
+    assert!(cert.is_valid_cert(&membership));
+}
+
+#[test]
+fn invalid_votes_of_a_batch_are_ignored() {
+    let membership = membership(4);
+    let mut accumulator = VoteAccumulator::<TestTypes, _, TimeoutCertificate<TestTypes>>::new();
+    // node 2's vote is forged, and node 1 votes twice
+    let votes = [
+        timeout_vote(0, 0),
+        timeout_vote(1, 1),
+        timeout_vote(2, 3),
+        timeout_vote(1, 1),
+    ];
+    assert!(accumulator.accumulate_batch(&votes, &membership).is_left());
+
+    // node 3's vote completes the certificate
+    let cert = accumulator
+        .accumulate_batch(&[timeout_vote(3, 3)], &membership)
+        .right()
+        .expect("three valid votes did not form a certificate");
+    assert!(cert.is_valid_cert(&membership));
+}
@@ -0,0 +1,89 @@
+use std::{num::NonZeroUsize, time::Duration};
+
+use hotshot::traits::election::static_committee::StaticElectionConfig;
+use hotshot_orchestrator::{
+    campaign::{CampaignConfig, CampaignRunResults, RunResults},
+    config::NetworkConfig,
+};
+use hotshot_types::signature_key::BLSPubKey;
+
+/// The results of a node which committed `committed` transactions in `seconds`
+fn results(node_index: u64, committed: u64, seconds: u64) -> RunResults {
+    RunResults {
+        node_index,
+        total_transactions_committed: committed,
+        total_time_elapsed: Duration::from_secs(seconds),
+        ..RunResults::default()
+    }
+}
+
+/// A run of `node_results`
+fn run(node_results: Vec<RunResults>) -> CampaignRunResults {
+    CampaignRunResults {
+        run_index: 0,
+        total_nodes: node_results.len(),
+        transaction_size: 100,
+        transactions_per_round: 10,
+        node_results,
+    }
+}
+
+#[test]
+fn campaigns_expand_to_every_combination_in_order() {
+    let base = NetworkConfig::<BLSPubKey, StaticElectionConfig>::default();
+    let nodes = |n| NonZeroUsize::new(n).unwrap();
+    let campaign = CampaignConfig {
+        total_nodes: vec![nodes(4), nodes(20)],
+        transaction_sizes: vec![100, 200],
+        transactions_per_round: Vec::new(),
+    };
+
+    let configs = campaign.expand(&base);
+    let points: Vec<_> = configs
+        .iter()
+        .map(|config| {
+            (
+                config.config.total_nodes.get(),
+                config.transaction_size,
+                config.transactions_per_round,
+            )
+        })
+        .collect();
+    // an empty axis keeps the value of the base config
+    let per_round = base.transactions_per_round;
+    assert_eq!(
+        points,
+        vec![
+            (4, 100, per_round),
+            (4, 200, per_round),
+            (20, 100, per_round),
+            (20, 200, per_round),
+        ]
+    );
+    for config in &configs {
+        let total_nodes = config.config.total_nodes.get();
+        // the stake table and the DA committee follow the number of nodes
+        assert_eq!(config.config.known_nodes_with_stake.len(), total_nodes);
+        assert_eq!(
+            config.config.da_committee_size,
+            base.config.da_committee_size.min(total_nodes)
+        );
+    }
+
+    // a campaign without axes runs the base config once
+    let configs = CampaignConfig::default().expand(&base);
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[0].config.total_nodes, base.config.total_nodes);
+}
+
+#[test]
+fn mean_throughput_averages_the_nodes_which_ran() {
+    let throughput = run(vec![results(0, 100, 10), results(1, 400, 20)]).mean_throughput();
+    assert!((throughput - 15.0).abs() < f64::EPSILON);
+
+    // a node which reports no time taken does not count
+    let throughput = run(vec![results(0, 100, 10), results(1, 50, 0)]).mean_throughput();
+    assert!((throughput - 10.0).abs() < f64::EPSILON);
+
+    assert!(run(Vec::new()).mean_throughput().abs() < f64::EPSILON);
+}
@@ -0,0 +1,33 @@
+use futures::FutureExt;
+use hotshot_types::traits::clock::{Clock, MockClock};
+use std::time::Duration;
+
+#[test]
+fn mock_clock_only_moves_when_advanced() {
+    let clock = MockClock::new();
+    let start = clock.now();
+
+    let mut short = clock.sleep(Duration::from_secs(5));
+    let mut long = clock.sleep(Duration::from_secs(10));
+    assert!((&mut short).now_or_never().is_none());
+    assert!((&mut long).now_or_never().is_none());
+    assert_eq!(clock.pending_sleeps(), 2);
+
+    clock.advance(Duration::from_secs(4));
+    assert_eq!(clock.now(), start + Duration::from_secs(4));
+    assert!((&mut short).now_or_never().is_none());
+
+    assert_eq!(
+        clock.advance_to_next_deadline(),
+        Some(Duration::from_secs(1))
+    );
+    assert!((&mut short).now_or_never().is_some());
+    assert!((&mut long).now_or_never().is_none());
+    assert_eq!(clock.pending_sleeps(), 1);
+
+    // a cancelled sleep no longer holds a deadline
+    drop(long);
+    assert_eq!(clock.pending_sleeps(), 0);
+    assert_eq!(clock.advance_to_next_deadline(), None);
+    assert_eq!(clock.now(), start + Duration::from_secs(5));
+}
@@ -0,0 +1,86 @@
+use commit::Committable;
+use hotshot_example_types::{block_types::TestTransaction, node_types::TestTypes};
+use hotshot_task_impls::compact_block::{AnnouncedTransactions, CompactRebuild};
+use hotshot_types::{
+    data::{CompactTransaction, ViewNumber},
+    traits::node_implementation::ConsensusTime,
+};
+
+#[test]
+fn announcements_are_forgotten_after_the_retention() {
+    let first = TestTransaction::new(vec![1]).commit();
+    let second = TestTransaction::new(vec![2]).commit();
+    let mut announced = AnnouncedTransactions::new(2);
+    announced.announced(ViewNumber::new(1), &0u64, [first]);
+    announced.announced(ViewNumber::new(2), &0u64, [second]);
+    assert!(announced.is_believed_held(&first, 1));
+    assert!(announced.is_believed_held(&second, 1));
+
+    announced.prune(ViewNumber::new(3));
+    assert!(!announced.is_believed_held(&first, 1));
+    assert!(announced.is_believed_held(&second, 1));
+
+    // announcing again renews the belief
+    announced.announced(ViewNumber::new(3), &0u64, [second]);
+    announced.prune(ViewNumber::new(4));
+    assert!(announced.is_believed_held(&second, 1));
+}
+
+#[test]
+fn transactions_are_believed_held_once_enough_distinct_nodes_announce_them() {
+    let transaction = TestTransaction::new(vec![1]).commit();
+    let mut announced = AnnouncedTransactions::new(10);
+    announced.announced(ViewNumber::new(1), &0u64, [transaction]);
+    // announcing again does not count twice
+    announced.announced(ViewNumber::new(2), &0u64, [transaction]);
+    assert!(!announced.is_believed_held(&transaction, 2));
+
+    announced.announced(ViewNumber::new(2), &1u64, [transaction]);
+    assert!(announced.is_believed_held(&transaction, 2));
+}
+
+#[test]
+fn rebuilds_complete_once_every_held_transaction_is_found() {
+    let held = TestTransaction::new(vec![1]);
+    let repeated = TestTransaction::new(vec![2]);
+    let full = TestTransaction::new(vec![3]);
+    let mut rebuild = CompactRebuild::<TestTypes>::new(&[
+        CompactTransaction::Held(repeated.commit()),
+        CompactTransaction::Held(held.commit()),
+        CompactTransaction::Full(full.clone()),
+        CompactTransaction::Held(repeated.commit()),
+    ]);
+    assert!(!rebuild.is_complete());
+    assert_eq!(rebuild.missing().len(), 2);
+
+    // transactions the proposal does not hold are ignored
+    assert_eq!(
+        rebuild.fill([repeated.clone(), TestTransaction::new(vec![4])]),
+        1
+    );
+    assert_eq!(rebuild.missing(), vec![held.commit()]);
+    assert_eq!(rebuild.fill([held.clone()]), 1);
+    assert!(rebuild.is_complete());
+    assert_eq!(
+        rebuild.into_transactions(),
+        Some(vec![repeated.clone(), held, full, repeated])
+    );
+}
+
+#[test]
+fn incomplete_rebuilds_have_no_transactions() {
+    let rebuild = CompactRebuild::<TestTypes>::new(&[CompactTransaction::Held(
+        TestTransaction::new(vec![1]).commit(),
+    )]);
+    assert_eq!(rebuild.into_transactions(), None);
+}
+
+#[test]
+fn missing_transactions_are_requested_a_bounded_number_of_times() {
+    let mut rebuild = CompactRebuild::<TestTypes>::new(&[CompactTransaction::Held(
+        TestTransaction::new(vec![1]).commit(),
+    )]);
+    assert!(rebuild.request(2));
+    assert!(rebuild.request(2));
+    assert!(!rebuild.request(2));
+}
@@ -0,0 +1,57 @@
+use hotshot::types::BLSPubKey;
+use hotshot_orchestrator::schema::{network_config_schema, validate_config, validate_config_file};
+
+/// The run configuration shipped with the orchestrator
+const RUN_CONFIG: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../orchestrator/run-config.toml"
+);
+
+#[test]
+fn shipped_run_config_matches_the_schema() {
+    let schema = network_config_schema::<BLSPubKey>();
+    assert_eq!(schema["title"], "NetworkConfigFile");
+    assert!(schema["properties"]["config"].is_object());
+    // keys are never part of a configuration file
+    assert!(schema["definitions"]["HotShotConfigFile"]["properties"]
+        .get("my_own_validator_config")
+        .is_none());
+
+    validate_config_file::<BLSPubKey>(RUN_CONFIG).unwrap();
+}
+
+#[test]
+fn violations_are_reported_by_field() {
+    let contents = std::fs::read_to_string(RUN_CONFIG)
+        .unwrap()
+        .replacen(
+            "rounds = 100",
+            "rounds = \"many\"\ntransaction_sise = 10",
+            1,
+        )
+        .replacen("total_nodes = 10", "total_nodes = 0", 1);
+    let violations = validate_config::<BLSPubKey>(&contents).unwrap_err();
+
+    assert!(violations.iter().any(|v| v.path == "rounds"));
+    assert!(violations.iter().any(|v| v.path == "config.total_nodes"));
+    assert!(violations
+        .iter()
+        .any(|v| v.path.is_empty() && v.message.contains("transaction_sise")));
+    assert_eq!(violations.len(), 3);
+
+    let syntax_error = validate_config::<BLSPubKey>("rounds = = 3").unwrap_err();
+    assert_eq!(syntax_error.len(), 1);
+    assert!(syntax_error[0].path.is_empty());
+}
+
+#[test]
+fn invalid_mnemonics_are_reported() {
+    let contents = std::fs::read_to_string(RUN_CONFIG).unwrap().replacen(
+        "rounds = 100",
+        "rounds = 100\nmnemonic = \"not a mnemonic\"",
+        1,
+    );
+    let violations = validate_config::<BLSPubKey>(&contents).unwrap_err();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].path, "mnemonic");
+}
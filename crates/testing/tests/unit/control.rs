@@ -0,0 +1,42 @@
+use hotshot::traits::election::static_committee::StaticElectionConfig;
+use hotshot_orchestrator::{
+    config::NetworkConfig,
+    control::{NodeControl, REDACTED},
+};
+use hotshot_types::{signature_key::BLSPubKey, ValidatorConfig};
+
+#[test]
+fn reported_config_has_no_key_material() {
+    let mut config = NetworkConfig::<BLSPubKey, StaticElectionConfig>::default();
+    config.seed = [7; 32];
+    config.libp2p_seed = [8; 32];
+    config.config.my_own_validator_config =
+        ValidatorConfig::generated_from_seed_indexed([7; 32], 3, 1);
+
+    let mut control = NodeControl::default();
+    control.set_config(&config).unwrap();
+    let reported = control.config();
+    let validator = &reported["config"]["my_own_validator_config"];
+    assert_eq!(validator["private_key"], REDACTED);
+    assert_eq!(validator["state_key_pair"], REDACTED);
+    assert_eq!(reported["seed"], REDACTED);
+    assert_eq!(reported["libp2p_seed"], REDACTED);
+    // the rest is reported as is
+    assert_eq!(reported["node_index"], 0);
+    assert_ne!(validator["public_key"], REDACTED);
+}
+
+#[test]
+fn control_requests_need_the_token() {
+    let mut control = NodeControl::default();
+    // without a token, nothing is authorized
+    assert!(!control.authorizes(None));
+    assert!(!control.authorizes(Some("Bearer ")));
+
+    control.set_token("s3cret".to_string());
+    assert!(control.authorizes(Some("Bearer s3cret")));
+    assert!(!control.authorizes(None));
+    assert!(!control.authorizes(Some("s3cret")));
+    assert!(!control.authorizes(Some("Bearer s3cre")));
+    assert!(!control.authorizes(Some("Bearer s3cret!")));
+}
@@ -0,0 +1,60 @@
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_task_impls::da::DAFallbackState;
+use hotshot_types::{data::ViewNumber, traits::node_implementation::ConsensusTime};
+
+/// view `n`
+fn view(n: u64) -> ViewNumber {
+    ViewNumber::new(n)
+}
+
+#[test]
+fn falls_back_after_consecutive_failures_then_retries_the_committee() {
+    let mut fallback = DAFallbackState::<TestTypes>::new(Some(3), 5);
+    for n in 1..=3 {
+        fallback.certified(view(n));
+        assert_eq!(fallback.enter_view(view(n + 1)), None);
+    }
+
+    // views 4, 5 and 6 end without a certificate
+    assert_eq!(fallback.enter_view(view(5)), None);
+    assert_eq!(fallback.enter_view(view(6)), None);
+    assert_eq!(fallback.enter_view(view(7)), Some(3));
+    assert_eq!(fallback.until(), Some(view(12)));
+    assert!(fallback.is_active(view(8)));
+    assert!(fallback.is_active(view(12)));
+    assert!(!fallback.is_active(view(13)));
+
+    // certificates of the quorum do not end the fallback early
+    fallback.certified(view(8));
+    assert_eq!(fallback.enter_view(view(9)), None);
+    assert!(fallback.is_active(view(9)));
+
+    // once it has ended, the committee gets three views again
+    for n in 13..=15 {
+        assert_eq!(fallback.enter_view(view(n)), None);
+        assert!(!fallback.is_active(view(n)));
+    }
+    assert_eq!(fallback.enter_view(view(16)), Some(3));
+}
+
+#[test]
+fn never_falls_back_unless_configured() {
+    let mut fallback = DAFallbackState::<TestTypes>::new(None, 5);
+    for n in 1..100 {
+        assert_eq!(fallback.enter_view(view(n)), None);
+    }
+    assert!(!fallback.is_active(view(100)));
+}
+
+#[test]
+fn remembers_recent_proposals_to_the_quorum() {
+    let mut fallback = DAFallbackState::<TestTypes>::new(Some(1), 5);
+    fallback.record_proposal(view(4));
+    assert!(fallback.was_proposed(view(4)));
+    assert!(!fallback.was_proposed(view(5)));
+
+    let _ = fallback.enter_view(view(5));
+    assert!(fallback.was_proposed(view(4)));
+    let _ = fallback.enter_view(view(6));
+    assert!(!fallback.was_proposed(view(4)));
+}
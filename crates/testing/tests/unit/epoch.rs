@@ -0,0 +1,114 @@
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    data::ViewNumber,
+    epoch::{stake_table_commitment, Epoch, EpochParameters, EpochSchedule},
+    signature_key::BLSPubKey,
+    simple_certificate::EpochChangeCertificate,
+    simple_vote::{EpochChangeData, EpochChangeVote},
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+    },
+    vote::{Certificate, VoteAccumulator},
+};
+
+type TestMembership = <TestTypes as NodeType>::Membership;
+
+/// The key pair of node `i`
+fn key_pair(i: u64) -> (BLSPubKey, <BLSPubKey as SignatureKey>::PrivateKey) {
+    BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+}
+
+/// A committee of `size` nodes with a stake of 1 each
+fn membership(size: u64) -> TestMembership {
+    let entries = (0..size)
+        .map(|i| key_pair(i).0.get_stake_table_entry(1))
+        .collect();
+    TestMembership::create_election(entries, TestMembership::default_election_config(size))
+}
+
+/// What the certificate of `epoch` of `schedule` commits to, for the stake table of `membership`
+fn epoch_change_data(
+    schedule: EpochSchedule,
+    epoch: Epoch,
+    membership: &TestMembership,
+) -> EpochChangeData<TestTypes> {
+    EpochChangeData {
+        epoch,
+        first_view: schedule.first_view(epoch),
+        stake_table_commitment: stake_table_commitment::<BLSPubKey>(
+            &membership.get_committee_qc_stake_table(),
+        ),
+        parameters: EpochParameters {
+            epoch_length: schedule.length(),
+            da_committee_size: 4,
+        },
+    }
+}
+
+#[test]
+fn views_are_divided_into_epochs() {
+    let schedule = EpochSchedule::new(10);
+    assert_eq!(schedule.epoch_of(ViewNumber::genesis()), Epoch::new(0));
+    assert_eq!(schedule.epoch_of(ViewNumber::new(9)), Epoch::new(0));
+    assert_eq!(schedule.epoch_of(ViewNumber::new(10)), Epoch::new(1));
+    assert_eq!(
+        schedule.first_view::<ViewNumber>(Epoch::new(2)),
+        ViewNumber::new(20)
+    );
+    assert_eq!(
+        schedule.last_view::<ViewNumber>(Epoch::new(2)),
+        ViewNumber::new(29)
+    );
+    assert!(schedule.is_last_view(ViewNumber::new(29)));
+    assert!(!schedule.is_last_view(ViewNumber::new(30)));
+    assert_eq!(Epoch::new(2).next(), Epoch::new(3));
+
+    // every view of one-view epochs is the last view of its epoch
+    let schedule = EpochSchedule::new(0);
+    assert_eq!(schedule.length(), 1);
+    assert!(schedule.is_last_view(ViewNumber::new(5)));
+    assert_eq!(schedule.epoch_of(ViewNumber::new(5)), Epoch::new(5));
+}
+
+#[test]
+fn stake_tables_commit_to_their_entries() {
+    let four = membership(4).get_committee_qc_stake_table();
+    let five = membership(5).get_committee_qc_stake_table();
+    assert_eq!(
+        stake_table_commitment::<BLSPubKey>(&four),
+        stake_table_commitment::<BLSPubKey>(&four.clone())
+    );
+    assert_ne!(
+        stake_table_commitment::<BLSPubKey>(&four),
+        stake_table_commitment::<BLSPubKey>(&five)
+    );
+}
+
+#[test]
+fn epoch_change_votes_form_certificates() {
+    let membership = membership(4);
+    let schedule = EpochSchedule::new(10);
+    let data = epoch_change_data(schedule, Epoch::new(1), &membership);
+    let votes: Vec<_> = (0..3)
+        .map(|i| {
+            let (public_key, private_key) = key_pair(i);
+            EpochChangeVote::create_signed_vote(
+                data.clone(),
+                data.first_view,
+                &public_key,
+                &private_key,
+            )
+            .expect("failed to sign an epoch change vote")
+        })
+        .collect();
+
+    let mut accumulator = VoteAccumulator::<TestTypes, _, EpochChangeCertificate<TestTypes>>::new();
+    let cert = accumulator
+        .accumulate_batch(&votes, &membership)
+        .right()
+        .expect("three of four votes did not form a certificate");
+    assert!(cert.is_valid_cert(&membership));
+    assert_eq!(cert.data, data);
+}
@@ -0,0 +1,37 @@
+use hotshot::types::{BLSPubKey, SignatureKey};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    error::{ErrorKind, HotShotError},
+    traits::node_implementation::{ConsensusTime, NodeType},
+};
+
+type ViewNumber = <TestTypes as NodeType>::Time;
+
+#[test]
+fn errors_have_stable_kinds() {
+    let (leader, _) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let invalid_proposal = HotShotError::<TestTypes>::InvalidProposal {
+        view_number: ViewNumber::new(3),
+        leader,
+        reason: "invalid signature".to_string(),
+    };
+    assert_eq!(invalid_proposal.kind(), ErrorKind::InvalidProposal);
+
+    let partition = HotShotError::<TestTypes>::PartitionSuspected {
+        view_number: ViewNumber::new(12),
+        views_without_decide: 10,
+    };
+    assert_eq!(partition.kind(), ErrorKind::PartitionSuspected);
+    assert_eq!(
+        HotShotError::<TestTypes>::LeafNotFound {}.kind(),
+        ErrorKind::Storage
+    );
+
+    // applications match on these codes, they must never change
+    assert_eq!(ErrorKind::Network.code(), 1);
+    assert_eq!(ErrorKind::Storage.code(), 2);
+    assert_eq!(ErrorKind::InvalidProposal.code(), 3);
+    assert_eq!(ErrorKind::PartitionSuspected.code(), 4);
+    assert_eq!(ErrorKind::SafetyViolation.code(), 5);
+    assert_eq!(ErrorKind::Other.code(), 12);
+}
@@ -0,0 +1,59 @@
+use async_broadcast::TryRecvError;
+use hotshot_constants::EVENT_BUS_CAPACITY;
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_task_impls::bus::{BusEvent, EventBus};
+use hotshot_types::{
+    data::ViewNumber, message::MessagePurpose, traits::node_implementation::ConsensusTime,
+};
+
+/// the event of entering `view`
+fn view_started(view: u64) -> BusEvent<TestTypes> {
+    BusEvent::ViewStarted {
+        view: ViewNumber::new(view),
+    }
+}
+
+#[test]
+fn subscribers_receive_what_is_published_after_they_subscribe() {
+    let bus = EventBus::<TestTypes>::default();
+    // no one listens yet, so the event is dropped
+    bus.publish(view_started(1));
+
+    let mut first = bus.subscribe();
+    bus.publish(view_started(2));
+    let mut second = bus.clone().subscribe();
+    bus.publish(BusEvent::MessageSent {
+        purpose: MessagePurpose::Proposal,
+        broadcast: true,
+    });
+
+    assert_eq!(first.try_recv().unwrap(), view_started(2));
+    assert!(matches!(
+        first.try_recv().unwrap(),
+        BusEvent::MessageSent {
+            purpose: MessagePurpose::Proposal,
+            broadcast: true
+        }
+    ));
+    assert!(first.try_recv().is_err());
+    assert!(matches!(
+        second.try_recv().unwrap(),
+        BusEvent::MessageSent { .. }
+    ));
+    assert!(second.try_recv().is_err());
+}
+
+#[test]
+fn slow_subscribers_miss_the_oldest_events() {
+    let bus = EventBus::<TestTypes>::default();
+    let mut subscriber = bus.subscribe();
+    let published = u64::try_from(EVENT_BUS_CAPACITY).unwrap() + 5;
+    for view in 0..published {
+        bus.publish(view_started(view));
+    }
+
+    // the receiver first reports how many events it missed
+    assert_eq!(subscriber.try_recv(), Err(TryRecvError::Overflowed(5)));
+    assert_eq!(subscriber.try_recv().unwrap(), view_started(5));
+    assert_eq!(subscriber.len(), EVENT_BUS_CAPACITY - 1);
+}
@@ -0,0 +1,65 @@
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_task_impls::future_view_buffer::{BufferOutcome, FutureViewBuffer};
+use hotshot_types::traits::node_implementation::{ConsensusTime, NodeType};
+
+type ViewNumber = <TestTypes as NodeType>::Time;
+
+fn view(n: u64) -> ViewNumber {
+    ViewNumber::new(n)
+}
+
+#[test]
+fn future_messages_are_held_until_their_view() {
+    let mut buffer = FutureViewBuffer::new(8, 3);
+
+    // messages for the current view, or too far ahead, are not held
+    assert_eq!(
+        buffer.insert(view(5), view(5), "now"),
+        BufferOutcome::Ready("now")
+    );
+    assert_eq!(
+        buffer.insert(view(5), view(9), "far"),
+        BufferOutcome::Ready("far")
+    );
+
+    assert_eq!(
+        buffer.insert(view(5), view(7), "seven"),
+        BufferOutcome::Buffered
+    );
+    assert_eq!(
+        buffer.insert(view(5), view(6), "six"),
+        BufferOutcome::Buffered
+    );
+    assert_eq!(buffer.len(), 2);
+
+    assert_eq!(buffer.take_ready(view(6)), vec!["six"]);
+    assert_eq!(buffer.take_ready(view(8)), vec!["seven"]);
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn full_future_view_buffer_evicts_the_furthest_message() {
+    let mut buffer = FutureViewBuffer::new(2, 3);
+    assert_eq!(
+        buffer.insert(view(1), view(3), "three"),
+        BufferOutcome::Buffered
+    );
+    assert_eq!(
+        buffer.insert(view(1), view(4), "four"),
+        BufferOutcome::Buffered
+    );
+
+    // nothing buffered is released later than this message, so it is not held
+    assert_eq!(
+        buffer.insert(view(1), view(4), "late"),
+        BufferOutcome::Ready("late")
+    );
+    // this one is released sooner, so it takes the place of the furthest message
+    assert_eq!(
+        buffer.insert(view(1), view(2), "two"),
+        BufferOutcome::Evicted("four")
+    );
+
+    assert_eq!(buffer.len(), 2);
+    assert_eq!(buffer.take_ready(view(4)), vec!["two", "three"]);
+}
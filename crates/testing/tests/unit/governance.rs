@@ -0,0 +1,230 @@
+use std::num::NonZeroUsize;
+
+use commit::Committable;
+use hotshot_example_types::{
+    block_types::{TestBlockPayload, TestTransaction},
+    node_types::TestTypes,
+};
+use hotshot_types::{
+    data::ViewNumber,
+    epoch::{Epoch, EpochSchedule},
+    governance::{
+        ConsensusParameters, GovernanceError, GovernancePolicy, GovernanceTransaction,
+        NoGovernance, ParameterChange, ParameterSchedule, SignedByAuthority,
+    },
+    signature_key::BLSPubKey,
+    simple_vote::DAData,
+    traits::{
+        block_contents::{vid_commitment, Transaction, TransactionLane},
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+        BlockPayload,
+    },
+};
+
+type TestMembership = <TestTypes as NodeType>::Membership;
+
+/// The parameters the nodes start with
+fn initial() -> ConsensusParameters {
+    ConsensusParameters {
+        next_view_timeout: 10_000,
+        max_transactions: NonZeroUsize::new(100).unwrap(),
+        da_committee_size: 5,
+    }
+}
+
+/// A schedule of 10 nodes, with epochs of 10 views
+fn schedule() -> ParameterSchedule {
+    ParameterSchedule::new(initial(), Some(EpochSchedule::new(10)), 10)
+}
+
+/// A transaction changing the timeout to `next_view_timeout` from `epoch` on
+fn timeout_change(epoch: u64, next_view_timeout: u64) -> GovernanceTransaction {
+    GovernanceTransaction::new(
+        Epoch::new(epoch),
+        ParameterChange {
+            next_view_timeout: Some(next_view_timeout),
+            ..ParameterChange::default()
+        },
+    )
+}
+
+#[test]
+fn governance_transactions_are_recognized_in_payloads() {
+    let governance = timeout_change(3, 5_000);
+    let transactions = vec![
+        TestTransaction::new(vec![1, 2, 3]),
+        TestTransaction::from_governance(&governance),
+    ];
+    assert_eq!(transactions[0].governance(), None);
+    assert_eq!(transactions[1].governance(), Some(governance.clone()));
+    assert_eq!(transactions[1].lane(), TransactionLane::System);
+
+    let (payload, metadata) = TestBlockPayload::from_transactions(transactions).unwrap();
+    assert_eq!(payload.governance_transactions(&metadata), vec![governance]);
+}
+
+#[test]
+fn changes_take_effect_at_their_activation_epoch() {
+    let mut schedule = schedule();
+    // decided in epoch 1, so epoch 3 is the earliest it may take effect at
+    schedule
+        .schedule(ViewNumber::new(15), &timeout_change(3, 5_000))
+        .unwrap();
+
+    assert_eq!(schedule.parameters_at(Epoch::new(2)), initial());
+    assert_eq!(schedule.parameters_at_view(ViewNumber::new(29)), initial());
+    // the change waits for the certificate of its epoch
+    assert_eq!(schedule.parameters_at_view(ViewNumber::new(30)), initial());
+    schedule.certify(Epoch::new(3));
+    let changed = schedule.parameters_at_view(ViewNumber::new(30));
+    assert_eq!(changed.next_view_timeout, 5_000);
+    assert_eq!(changed.max_transactions, initial().max_transactions);
+    assert_eq!(schedule.parameters_at(Epoch::new(7)), changed);
+}
+
+#[test]
+fn later_changes_override_earlier_ones() {
+    let mut schedule = schedule();
+    let committee_change = GovernanceTransaction::new(
+        Epoch::new(3),
+        ParameterChange {
+            da_committee_size: Some(7),
+            ..ParameterChange::default()
+        },
+    );
+    schedule
+        .schedule(ViewNumber::new(5), &timeout_change(3, 5_000))
+        .unwrap();
+    schedule
+        .schedule(ViewNumber::new(6), &committee_change)
+        .unwrap();
+    schedule
+        .schedule(ViewNumber::new(7), &timeout_change(3, 6_000))
+        .unwrap();
+    schedule
+        .schedule(ViewNumber::new(8), &timeout_change(5, 8_000))
+        .unwrap();
+
+    let at_three = schedule.parameters_at(Epoch::new(3));
+    assert_eq!(at_three.next_view_timeout, 6_000);
+    assert_eq!(at_three.da_committee_size, 7);
+    let at_five = schedule.parameters_at(Epoch::new(5));
+    assert_eq!(at_five.next_view_timeout, 8_000);
+    assert_eq!(at_five.da_committee_size, 7);
+}
+
+#[test]
+fn invalid_changes_are_rejected() {
+    let mut schedule = schedule();
+    assert_eq!(
+        schedule.schedule(ViewNumber::new(15), &timeout_change(2, 5_000)),
+        Err(GovernanceError::TooLate {
+            activation_epoch: Epoch::new(2),
+            earliest: Epoch::new(3),
+        })
+    );
+    assert_eq!(
+        schedule.schedule(
+            ViewNumber::new(15),
+            &GovernanceTransaction::new(Epoch::new(4), ParameterChange::default())
+        ),
+        Err(GovernanceError::EmptyChange)
+    );
+    assert!(matches!(
+        schedule.schedule(
+            ViewNumber::new(15),
+            &GovernanceTransaction::new(
+                Epoch::new(4),
+                ParameterChange {
+                    da_committee_size: Some(11),
+                    ..ParameterChange::default()
+                },
+            )
+        ),
+        Err(GovernanceError::InvalidParameter { .. })
+    ));
+    assert_eq!(schedule.parameters_at(Epoch::new(8)), initial());
+
+    let mut without_epochs = ParameterSchedule::new(initial(), None, 10);
+    assert_eq!(
+        without_epochs.schedule(ViewNumber::new(15), &timeout_change(4, 5_000)),
+        Err(GovernanceError::EpochsDisabled)
+    );
+}
+
+#[test]
+fn only_transactions_signed_by_an_authority_are_authorized() {
+    let (authority, authority_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let (_, other_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1);
+    let policy = SignedByAuthority::new(vec![authority]);
+
+    let unsigned = timeout_change(3, 5_000);
+    let signed = unsigned
+        .clone()
+        .signed::<BLSPubKey>(&authority_key)
+        .unwrap();
+    assert!(policy.authorizes(&signed));
+    assert!(!policy.authorizes(&unsigned));
+    assert!(!policy.authorizes(&unsigned.clone().signed::<BLSPubKey>(&other_key).unwrap()));
+    // the signature covers the change
+    let mut tampered = signed.clone();
+    tampered.change.next_view_timeout = Some(1);
+    assert!(!policy.authorizes(&tampered));
+
+    // nodes ignore every transaction unless configured with a policy
+    assert!(!NoGovernance.authorizes(&signed));
+}
+
+#[test]
+fn da_votes_commit_to_the_governance_transactions_of_the_block() {
+    let payload_commit = vid_commitment(&[], 1);
+    let without = DAData {
+        payload_commit,
+        governance: Vec::new(),
+    };
+    let with = DAData {
+        payload_commit,
+        governance: vec![timeout_change(3, 5_000)],
+    };
+    let forged = DAData {
+        payload_commit,
+        governance: vec![timeout_change(3, 1)],
+    };
+    assert_ne!(without.commit(), with.commit());
+    assert_ne!(with.commit(), forged.commit());
+}
+
+#[test]
+fn resizing_a_committee_resizes_its_clones() {
+    let entries = (0..10)
+        .map(|i| {
+            BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                .0
+                .get_stake_table_entry(1)
+        })
+        .collect();
+    let membership =
+        TestMembership::create_election(entries, TestMembership::default_election_config(5));
+    let clone = membership.clone();
+    assert_eq!(clone.total_nodes(), 5);
+
+    membership.resize_committee(7);
+    assert_eq!(clone.total_nodes(), 7);
+    assert_eq!(clone.get_committee(ViewNumber::new(1)).len(), 7);
+    assert_eq!(clone.get_committee_qc_stake_table().len(), 7);
+}
+
+#[test]
+fn schedules_survive_serialization() {
+    let mut schedule = schedule();
+    schedule
+        .schedule(ViewNumber::new(15), &timeout_change(3, 5_000))
+        .unwrap();
+    schedule.certify(Epoch::new(3));
+    let bytes = bincode::serialize(&schedule).unwrap();
+    let restored: ParameterSchedule = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(restored, schedule);
+    assert_eq!(restored.certified(), Epoch::new(3));
+}
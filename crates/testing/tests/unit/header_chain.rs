@@ -0,0 +1,64 @@
+use commit::Committable;
+use hotshot_example_types::{
+    block_types::{genesis_transactions_root, genesis_vid_commitment, TestBlockHeader},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_types::traits::block_contents::{validate_header_chain, BlockHeader, HeaderChainError};
+
+/// a chain of `len` headers from genesis on
+fn header_chain(len: usize) -> Vec<TestBlockHeader> {
+    let (genesis, _, _) = TestBlockHeader::genesis(&TestInstanceState {});
+    let mut headers = vec![genesis];
+    while headers.len() < len {
+        let parent = headers.last().unwrap();
+        let header = TestBlockHeader::new(
+            &TestValidatedState::default(),
+            &TestInstanceState {},
+            parent,
+            genesis_vid_commitment(),
+            genesis_transactions_root(),
+            (),
+            Vec::new(),
+        );
+        headers.push(header);
+    }
+    headers
+}
+
+#[test]
+fn headers_extend_their_parent() {
+    let headers = header_chain(3);
+    assert_eq!(headers[0].parent_commitment(), None);
+    assert!(headers[1].extends(&headers[0]));
+    assert!(headers[2].extends(&headers[1]));
+    assert!(!headers[2].extends(&headers[0]));
+    assert!(!headers[0].extends(&headers[0]));
+    assert_eq!(validate_header_chain(&headers), Ok(()));
+    assert_eq!(validate_header_chain::<TestBlockHeader>(&[]), Ok(()));
+}
+
+#[test]
+fn broken_chains_are_rejected() {
+    let mut skipped = header_chain(4);
+    skipped.remove(1);
+    assert_eq!(
+        validate_header_chain(&skipped),
+        Err(HeaderChainError::ParentMismatch { index: 1 })
+    );
+
+    let mut renumbered = header_chain(3);
+    renumbered[2].block_number = 5;
+    assert_eq!(
+        validate_header_chain(&renumbered),
+        Err(HeaderChainError::BlockNumberGap { index: 2 })
+    );
+
+    // the commitment of a header covers its parent, so it cannot be re-linked unnoticed
+    let mut forged = header_chain(3);
+    forged[1].parent_commitment = None;
+    assert_eq!(
+        validate_header_chain(&forged),
+        Err(HeaderChainError::ParentMismatch { index: 1 })
+    );
+    assert_ne!(forged[1].commit(), header_chain(2)[1].commit());
+}
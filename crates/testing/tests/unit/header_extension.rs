@@ -0,0 +1,117 @@
+use commit::Committable;
+use hotshot_constants::MAX_HEADER_EXTENSIONS_SIZE;
+use hotshot_example_types::{
+    block_types::{genesis_transactions_root, genesis_vid_commitment, TestBlockHeader},
+    node_types::TestTypes,
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_types::{
+    data::ViewNumber,
+    traits::{
+        block_contents::BlockHeader,
+        header_extension::{validate_extensions, ExtensionRejection, HeaderExtender, NoExtensions},
+        node_implementation::ConsensusTime,
+    },
+};
+
+/// the header with `extensions` on the genesis header
+fn child_of_genesis(extensions: Vec<u8>) -> (TestBlockHeader, TestBlockHeader) {
+    let (genesis, _, _) = TestBlockHeader::genesis(&TestInstanceState {});
+    let header = TestBlockHeader::new(
+        &TestValidatedState::default(),
+        &TestInstanceState {},
+        &genesis,
+        genesis_vid_commitment(),
+        genesis_transactions_root(),
+        (),
+        extensions,
+    );
+    (genesis, header)
+}
+
+/// accepts only extensions naming the view they are proposed for
+struct ViewTag;
+
+impl HeaderExtender<TestTypes> for ViewTag {
+    fn extensions(&self, view: ViewNumber, _parent: &TestBlockHeader) -> Vec<u8> {
+        view.to_le_bytes().to_vec()
+    }
+
+    fn check_extensions(
+        &self,
+        view: ViewNumber,
+        _parent: &TestBlockHeader,
+        extensions: &[u8],
+    ) -> Result<(), String> {
+        if extensions == view.to_le_bytes() {
+            Ok(())
+        } else {
+            Err("wrong view tag".to_string())
+        }
+    }
+}
+
+#[test]
+fn headers_commit_to_their_extensions() {
+    let (_, plain) = child_of_genesis(Vec::new());
+    let (_, tagged) = child_of_genesis(vec![1, 2, 3]);
+    let (_, retagged) = child_of_genesis(vec![1, 2, 4]);
+    assert_eq!(tagged.extensions(), &[1, 2, 3]);
+    assert!(plain.extensions().is_empty());
+    assert_ne!(plain.commit(), tagged.commit());
+    assert_ne!(tagged.commit(), retagged.commit());
+}
+
+#[test]
+fn extenders_check_the_extensions_of_proposed_headers() {
+    let view = ViewNumber::new(7);
+    let (genesis, _) = child_of_genesis(Vec::new());
+    let (_, tagged) = child_of_genesis(ViewTag.extensions(view, &genesis));
+    assert_eq!(
+        validate_extensions::<TestTypes>(&ViewTag, view, &genesis, &tagged),
+        Ok(())
+    );
+    assert!(matches!(
+        validate_extensions::<TestTypes>(&ViewTag, ViewNumber::new(8), &genesis, &tagged),
+        Err(ExtensionRejection::RejectedByApplication { .. })
+    ));
+
+    // without an application, any extensions within the size limit are accepted
+    let (_, untagged) = child_of_genesis(Vec::new());
+    assert_eq!(
+        validate_extensions::<TestTypes>(&NoExtensions, view, &genesis, &untagged),
+        Ok(())
+    );
+    assert!(HeaderExtender::<TestTypes>::extensions(&NoExtensions, view, &genesis).is_empty());
+    let (_, oversized) = child_of_genesis(vec![0; MAX_HEADER_EXTENSIONS_SIZE + 1]);
+    assert_eq!(
+        validate_extensions::<TestTypes>(&NoExtensions, view, &genesis, &oversized),
+        Err(ExtensionRejection::TooLarge {
+            size: MAX_HEADER_EXTENSIONS_SIZE + 1,
+            max_size: MAX_HEADER_EXTENSIONS_SIZE,
+        })
+    );
+}
+
+/// accepts any extensions
+struct AcceptAll;
+
+impl HeaderExtender<TestTypes> for AcceptAll {
+    fn check_extensions(
+        &self,
+        _view: ViewNumber,
+        _parent: &TestBlockHeader,
+        _extensions: &[u8],
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[test]
+fn extenders_cannot_lift_the_size_limit() {
+    let (genesis, oversized) = child_of_genesis(vec![0; MAX_HEADER_EXTENSIONS_SIZE + 1]);
+    assert!(matches!(
+        validate_extensions::<TestTypes>(&AcceptAll, ViewNumber::new(1), &genesis, &oversized),
+        Err(ExtensionRejection::TooLarge { .. })
+    ));
+}
@@ -0,0 +1,41 @@
+use commit::Committable;
+use hotshot_example_types::{
+    block_types::{genesis_vid_commitment, TestBlockHeader, TestBlockPayload, TestTransaction},
+    state_types::{TestInstanceState, TestValidatedState},
+};
+use hotshot_types::traits::{block_contents::BlockHeader, BlockPayload};
+
+/// the header of `payload` on the genesis header
+fn header_of(payload: &TestBlockPayload) -> TestBlockHeader {
+    let (genesis, _, _) = TestBlockHeader::genesis(&TestInstanceState {});
+    TestBlockHeader::new(
+        &TestValidatedState::default(),
+        &TestInstanceState {},
+        &genesis,
+        genesis_vid_commitment(),
+        payload.transactions_root(&()),
+        (),
+        Vec::new(),
+    )
+}
+
+#[test]
+fn inclusion_proofs_verify_against_the_header() {
+    let transactions: Vec<_> = (0..5).map(|i| TestTransaction::new(vec![i])).collect();
+    let (payload, ()) = TestBlockPayload::from_transactions(transactions.clone()).unwrap();
+    let header = header_of(&payload);
+    for transaction in &transactions {
+        let proof = payload.inclusion_proof(&(), &transaction.commit()).unwrap();
+        assert!(proof.verify(transaction.commit(), &header.transactions_root()));
+    }
+
+    // the header of another block does not prove the transactions of this one
+    let (other, ()) =
+        TestBlockPayload::from_transactions(vec![TestTransaction::new(vec![9])]).unwrap();
+    let other_header = header_of(&other);
+    let proof = payload
+        .inclusion_proof(&(), &transactions[0].commit())
+        .unwrap();
+    assert!(!proof.verify(transactions[0].commit(), &other_header.transactions_root()));
+    assert_ne!(header.commit(), other_header.commit());
+}
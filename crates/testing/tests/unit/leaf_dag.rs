@@ -0,0 +1,75 @@
+use hotshot_types::leaf_dag::{LeafDag, LeafDagNode, LeafStatus};
+
+/// a leaf `commitment` of `view` on `parent`
+fn node(commitment: &str, view: u64, parent: &str, status: LeafStatus) -> LeafDagNode {
+    LeafDagNode {
+        commitment: commitment.to_string(),
+        view,
+        parent: parent.to_string(),
+        justify_qc_view: view - 1,
+        status,
+    }
+}
+
+/// leaves of views 2 and 3 both extending that of view 1, where view 3 was decided and view 2
+/// orphaned, and an undecided leaf of view 4 on top
+fn forked() -> LeafDag {
+    LeafDag {
+        last_decided_view: 3,
+        locked_view: 3,
+        high_qc_view: 4,
+        nodes: vec![
+            node("one", 1, "genesis", LeafStatus::Decided),
+            node("two", 2, "one", LeafStatus::Orphaned),
+            node("three", 3, "one", LeafStatus::Decided),
+            node("four", 4, "three", LeafStatus::Undecided),
+        ],
+    }
+}
+
+#[test]
+fn forks_are_rendered_in_dot() {
+    let dot = forked().to_dot();
+    assert!(dot.starts_with("digraph leaves {\n"));
+    assert!(dot.ends_with("}\n"));
+    for line in [
+        r#"    "one" [label="view 1\njustify qc 0" color=green];"#,
+        r#"    "two" [label="view 2\njustify qc 1" color=red];"#,
+        r#"    "three" [label="view 3\njustify qc 2" color=green];"#,
+        r#"    "four" [label="view 4\njustify qc 3" color=black];"#,
+        // both branches of the fork point to the leaf they extend
+        r#"    "two" -> "one";"#,
+        r#"    "three" -> "one";"#,
+        r#"    "four" -> "three";"#,
+    ] {
+        assert!(dot.lines().any(|l| l == line), "{line} is not in\n{dot}");
+    }
+    // the parent of the oldest leaf is no longer held, so there is no edge to it
+    assert!(!dot.contains("genesis"));
+    assert_eq!(dot.matches("->").count(), 3);
+}
+
+#[test]
+fn dot_identifiers_are_escaped() {
+    let dag = LeafDag {
+        nodes: vec![
+            node(r#"a"b"#, 1, "genesis", LeafStatus::Decided),
+            node(r"c\d", 2, r#"a"b"#, LeafStatus::Undecided),
+        ],
+        ..LeafDag::default()
+    };
+    assert!(dag.to_dot().contains(r#"    "c\\d" -> "a\"b";"#));
+}
+
+#[test]
+fn forks_round_trip_through_json() {
+    let dag = forked();
+    let json = dag.to_json().unwrap();
+    assert_eq!(serde_json::from_str::<LeafDag>(&json).unwrap(), dag);
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["last_decided_view"], 3);
+    assert_eq!(value["nodes"][1]["parent"], "one");
+    assert_eq!(value["nodes"][1]["status"], "Orphaned");
+    assert_eq!(value["nodes"][2]["status"], "Decided");
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use hotshot_orchestrator::{
+    config::LoadGeneratorConfig,
+    load::{LoadController, LoadObservation},
+};
+
+/// a decide which committed `transactions` in a second, with a mempool of `mempool_depth`
+fn decide(mempool_depth: usize, transactions: u64) -> LoadObservation {
+    LoadObservation {
+        mempool_depth,
+        decide_latency: Some(Duration::from_millis(500)),
+        transactions_committed: transactions,
+        elapsed: Duration::from_secs(1),
+    }
+}
+
+#[test]
+fn rate_grows_until_consensus_falls_behind() {
+    let config = LoadGeneratorConfig {
+        initial_rate: 10,
+        min_rate: 5,
+        max_rate: 45,
+        increase_step: 10,
+        decrease_percent: 50,
+        target_mempool_depth: 100,
+        max_decide_latency_ms: 1000,
+    };
+    let mut load = LoadController::new(config);
+    assert_eq!(load.rate(), 10);
+
+    assert_eq!(load.observe(&decide(0, 10)), 20);
+    assert_eq!(load.observe(&decide(50, 20)), 30);
+    assert_eq!(load.observe(&decide(60, 30)), 40);
+    assert_eq!(load.observe(&decide(70, 40)), 45);
+    assert!(!load.is_saturated());
+    assert_eq!(load.sustainable_tps(), None);
+
+    // a deep mempool, or slow decides, cut the rate down to its minimum at most
+    assert_eq!(load.observe(&decide(101, 40)), 23);
+    let slow = LoadObservation {
+        decide_latency: Some(Duration::from_secs(2)),
+        ..decide(0, 20)
+    };
+    assert_eq!(load.observe(&slow), 12);
+    assert_eq!(load.observe(&slow), 6);
+    assert_eq!(load.observe(&slow), 5);
+    assert!(load.is_saturated());
+}
+
+#[test]
+fn throughput_is_measured_once_saturated() {
+    let mut load = LoadController::new(LoadGeneratorConfig::default());
+    load.observe(&decide(0, 1000));
+    load.observe(&decide(usize::MAX, 1000));
+    assert_eq!(load.sustainable_tps(), None);
+
+    load.observe(&decide(0, 30));
+    load.observe(&LoadObservation {
+        elapsed: Duration::from_secs(2),
+        ..decide(0, 90)
+    });
+    assert_eq!(load.sustainable_tps(), Some(40.0));
+}
@@ -0,0 +1,94 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hotshot_orchestrator::control::{LogCaptureStatus, LogControl, LogFilter};
+
+/// A log filter which only keeps its directives, rejecting ones which are empty
+#[derive(Clone, Debug, Default)]
+struct FakeFilter(Arc<Mutex<String>>);
+
+impl LogFilter for FakeFilter {
+    fn directives(&self) -> String {
+        self.0.lock().expect("poisoned").clone()
+    }
+
+    fn set_directives(&self, directives: &str) -> Result<(), String> {
+        if directives.is_empty() {
+            return Err("no directives".to_string());
+        }
+        *self.0.lock().expect("poisoned") = directives.to_string();
+        Ok(())
+    }
+}
+
+/// Control of a filter which starts at `info`, and the filter
+fn control() -> (LogControl, FakeFilter) {
+    let filter = FakeFilter::default();
+    filter.set_directives("info").expect("valid directives");
+    (LogControl::new(filter.clone()), filter)
+}
+
+#[test]
+fn capture_windows_revert_once_they_close() {
+    let (control, filter) = control();
+    let start = Instant::now();
+
+    control
+        .capture_at("debug", Duration::from_secs(60), start)
+        .expect("valid directives");
+    assert_eq!(filter.directives(), "debug");
+    let status = control.status_at(start + Duration::from_secs(20));
+    assert_eq!(status.directives, "debug");
+    assert_eq!(
+        status.capture,
+        Some(LogCaptureStatus {
+            reverts_to: "info".to_string(),
+            remaining_seconds: 40,
+        })
+    );
+
+    // a second window extends the first, and still reverts to the filter before either
+    control
+        .capture_at(
+            "trace",
+            Duration::from_secs(60),
+            start + Duration::from_secs(30),
+        )
+        .expect("valid directives");
+    assert_eq!(
+        control.close_expired_at(start + Duration::from_secs(60)),
+        None
+    );
+    assert_eq!(filter.directives(), "trace");
+    assert_eq!(
+        control.close_expired_at(start + Duration::from_secs(90)),
+        Some("info".to_string())
+    );
+    assert_eq!(filter.directives(), "info");
+    assert_eq!(control.status_at(start).capture, None);
+}
+
+#[test]
+fn setting_the_filter_closes_the_capture_window() {
+    let (control, filter) = control();
+    let start = Instant::now();
+
+    assert!(control.set("").is_err());
+    assert!(control
+        .capture_at("", Duration::from_secs(60), start)
+        .is_err());
+    assert_eq!(control.status_at(start).capture, None);
+
+    control
+        .capture_at("debug", Duration::from_secs(60), start)
+        .expect("valid directives");
+    control.set("warn").expect("valid directives");
+    assert_eq!(control.status_at(start).capture, None);
+    assert_eq!(
+        control.close_expired_at(start + Duration::from_secs(60)),
+        None
+    );
+    assert_eq!(filter.directives(), "warn");
+}
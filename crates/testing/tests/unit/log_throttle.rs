@@ -0,0 +1,38 @@
+use hotshot_utils::log_throttle::{LogThrottle, Summary};
+use std::time::{Duration, Instant};
+
+#[test]
+fn repeated_messages_are_coalesced() {
+    let throttle =
+        LogThrottle::new(Duration::from_secs(10)).with_window("proposal_missing", Duration::ZERO);
+    let start = Instant::now();
+
+    let first = throttle.check_at("view_timeout", start).unwrap();
+    assert_eq!(first.suppressed, 0);
+    assert_eq!(first.to_string(), "");
+    for second in 1..=37 {
+        assert!(throttle
+            .check_at("view_timeout", start + Duration::from_secs(second) / 4)
+            .is_none());
+    }
+    // other targets are throttled separately
+    assert!(throttle.check_at("liveness_check", start).is_some());
+
+    let summary = throttle
+        .check_at("view_timeout", start + Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(
+        summary,
+        Summary {
+            target: "view_timeout",
+            suppressed: 37,
+            elapsed: Duration::from_secs(10),
+        }
+    );
+    assert_eq!(summary.to_string(), " [view_timeout x37 in last 10s]");
+
+    // a zero window logs every message
+    for _ in 0..3 {
+        assert!(throttle.check_at("proposal_missing", start).is_some());
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+use commit::Committable;
+use futures::executor::block_on;
+use hotshot_example_types::{block_types::TestTransaction, node_types::TestTypes};
+use hotshot_task_impls::mempool::ShardedMempool;
+use hotshot_types::{
+    consensus::ConsensusMetricsValue,
+    epoch::Epoch,
+    governance::{GovernanceTransaction, ParameterChange},
+    traits::{block_contents::ViewWindow, node_implementation::ConsensusTime},
+};
+
+/// A system lane transaction, distinct for every `epoch`
+fn system_transaction(epoch: u64) -> TestTransaction {
+    TestTransaction::from_governance(&GovernanceTransaction::new(
+        Epoch::new(epoch),
+        ParameterChange {
+            next_view_timeout: Some(1000),
+            ..ParameterChange::default()
+        },
+    ))
+}
+
+/// Validate and insert `transactions`, returning how many were inserted
+fn insert(mempool: &ShardedMempool<TestTypes>, transactions: Vec<TestTransaction>) -> usize {
+    block_on(async {
+        let validated = mempool.validate(transactions).await;
+        mempool.insert(validated).await.len()
+    })
+}
+
+#[test]
+fn system_lane_is_bounded() {
+    let mempool =
+        ShardedMempool::<TestTypes>::new(&ConsensusMetricsValue::default()).with_system_capacity(3);
+    assert_eq!(
+        insert(&mempool, (0..5).map(system_transaction).collect()),
+        3
+    );
+    // the lane keeps the oldest transactions, and user transactions are not limited by it
+    assert_eq!(
+        block_on(mempool.system_transactions()),
+        (0..3).map(system_transaction).collect::<Vec<_>>()
+    );
+    assert_eq!(insert(&mempool, vec![TestTransaction::new(vec![1])]), 1);
+    assert_eq!(block_on(mempool.len()), 4);
+
+    // room is made as system transactions are decided
+    let decided = HashSet::from([system_transaction(0).commit()]);
+    assert_eq!(block_on(mempool.remove_all(&decided)).len(), 1);
+    assert_eq!(insert(&mempool, vec![system_transaction(4)]), 1);
+}
+
+#[test]
+fn blocks_reserve_capacity_for_the_system_lane() {
+    let mempool = ShardedMempool::<TestTypes>::new(&ConsensusMetricsValue::default());
+    let user: Vec<_> = (0..10u8).map(|i| TestTransaction::new(vec![i])).collect();
+    insert(&mempool, user.clone());
+    insert(&mempool, (0..4).map(system_transaction).collect());
+    let later = system_transaction(9).with_window(ViewWindow::starting_at(5));
+    insert(&mempool, vec![later.clone()]);
+
+    // system transactions come first, up to the reserved capacity, and in the order received
+    let block = block_on(mempool.block_transactions(1, 2, 6));
+    assert_eq!(block.len(), 6);
+    assert_eq!(block[..2], [system_transaction(0), system_transaction(1)]);
+    assert!(block[2..].iter().all(|txn| user.contains(txn)));
+
+    // unused reserved capacity goes to user transactions, and windows are respected
+    let block = block_on(mempool.block_transactions(1, 8, 12));
+    assert_eq!(block.len(), 12);
+    assert_eq!(
+        block[..4],
+        (0..4).map(system_transaction).collect::<Vec<_>>()[..]
+    );
+    assert!(!block.contains(&later));
+    assert!(block_on(mempool.block_transactions(5, 8, 20)).contains(&later));
+
+    // the reservation never exceeds the block
+    assert_eq!(
+        block_on(mempool.block_transactions(1, 8, 3)),
+        (0..3).map(system_transaction).collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,43 @@
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_task_impls::mempool_sync::MempoolSync;
+use hotshot_types::traits::node_implementation::{ConsensusTime, NodeType};
+
+type ViewNumber = <TestTypes as NodeType>::Time;
+
+fn view(n: u64) -> ViewNumber {
+    ViewNumber::new(n)
+}
+
+#[test]
+fn announcements_are_periodic() {
+    let mut sync = MempoolSync::<ViewNumber, u64>::new(5, 4, 20);
+
+    assert!(sync.should_announce(view(1)));
+    for n in 2..6 {
+        assert!(!sync.should_announce(view(n)));
+    }
+    assert!(sync.should_announce(view(6)));
+    // skipped views still announce
+    assert!(sync.should_announce(view(30)));
+}
+
+#[test]
+fn only_missing_undecided_transactions_are_requested() {
+    let mut sync = MempoolSync::<ViewNumber, u64>::new(5, 4, 20);
+    sync.decided(view(1), [1]);
+
+    // decided transactions are not requested, and requests are capped
+    assert_eq!(sync.request_missing(view(2), 1..8), vec![2, 3, 4, 5]);
+    // neither are transactions requested recently
+    assert_eq!(sync.request_missing(view(3), [2, 3, 6]), vec![6]);
+    sync.received(&2);
+    assert_eq!(sync.request_missing(view(3), [2]), vec![2]);
+
+    // requests may be repeated after an interval, decided transactions are remembered longer
+    assert!(sync.should_announce(view(8)));
+    assert_eq!(sync.request_missing(view(8), [1, 3]), vec![3]);
+    assert!(sync.is_decided(&1));
+    assert!(sync.should_announce(view(21)));
+    assert!(!sync.is_decided(&1));
+    assert_eq!(sync.request_missing(view(21), [1]), vec![1]);
+}
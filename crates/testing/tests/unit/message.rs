@@ -47,6 +47,7 @@ fn version_number_at_start_of_serialization() {
     };
     let message = Message {
         version,
+        chain_id: 7,
         sender,
         kind: MessageKind::Consensus(SequencingMessage(Left(
             GeneralConsensusMessage::ViewSyncCommitCertificate(simple_certificate),
@@ -59,4 +60,93 @@ fn version_number_at_start_of_serialization() {
 
     assert_eq!(version.major, major_version_read);
     assert_eq!(version.minor, minor_version_read);
+    // The chain id follows the version
+    let chain_id_read = u64::from_le_bytes(serialized_message[4..12].try_into().unwrap());
+    assert_eq!(chain_id_read, 7);
+}
+
+#[test]
+// Checks that consensus messages are handed out before transactions, keeping the order of
+// arrival within each class.
+fn consensus_messages_are_prioritized() {
+    use hotshot_constants::VERSION_0_1;
+    use hotshot_example_types::block_types::TestTransaction;
+    use hotshot_types::{
+        message::DataMessage,
+        traits::network::{prioritize, MessagePriority, NetworkMsg},
+    };
+
+    let sender = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let view_number = ConsensusTime::new(3);
+    let data: ViewSyncCommitData<TestTypes> = ViewSyncCommitData {
+        relay: 0,
+        round: view_number,
+    };
+    let certificate = Message::<TestTypes> {
+        version: VERSION_0_1,
+        chain_id: 0,
+        sender,
+        kind: MessageKind::Consensus(SequencingMessage(Left(
+            GeneralConsensusMessage::ViewSyncCommitCertificate(SimpleCertificate {
+                data: data.clone(),
+                vote_commitment: data.commit(),
+                view_number,
+                signatures: None,
+                is_genesis: false,
+                _pd: PhantomData,
+            }),
+        ))),
+    };
+    let transaction = |byte| Message::<TestTypes> {
+        version: VERSION_0_1,
+        chain_id: 0,
+        sender,
+        kind: MessageKind::Data(DataMessage::SubmitTransaction(
+            TestTransaction::new(vec![byte]),
+            view_number,
+        )),
+    };
+    assert_eq!(certificate.priority(), MessagePriority::Consensus);
+    assert_eq!(transaction(0).priority(), MessagePriority::Transactions);
+
+    let mut messages = vec![transaction(1), certificate.clone(), transaction(2)];
+    prioritize(&mut messages);
+    assert_eq!(messages, vec![certificate, transaction(1), transaction(2)]);
+}
+
+#[test]
+// Checks that timeout and view sync messages are told apart from other messages, so combined
+// networks can relay them over the web server.
+fn view_change_messages_are_told_apart() {
+    use hotshot_example_types::block_types::TestTransaction;
+    use hotshot_types::message::{DataMessage, VoteBatch};
+
+    let view_number = ConsensusTime::new(3);
+    let data: ViewSyncCommitData<TestTypes> = ViewSyncCommitData {
+        relay: 0,
+        round: view_number,
+    };
+    let certificate = MessageKind::<TestTypes>::Consensus(SequencingMessage(Left(
+        GeneralConsensusMessage::ViewSyncCommitCertificate(SimpleCertificate {
+            data: data.clone(),
+            vote_commitment: data.commit(),
+            view_number,
+            signatures: None,
+            is_genesis: false,
+            _pd: PhantomData,
+        }),
+    )));
+    assert!(certificate.is_view_change());
+
+    // a batch only of quorum votes changes no views
+    let batch = MessageKind::<TestTypes>::Consensus(SequencingMessage(Left(
+        GeneralConsensusMessage::VoteBatch(VoteBatch::default()),
+    )));
+    assert!(!batch.is_view_change());
+
+    let transaction = MessageKind::<TestTypes>::Data(DataMessage::SubmitTransaction(
+        TestTransaction::new(vec![0]),
+        view_number,
+    ));
+    assert!(!transaction.is_view_change());
 }
@@ -0,0 +1,98 @@
+use std::num::NonZeroUsize;
+
+use hotshot::traits::election::static_committee::StaticElectionConfig;
+use hotshot_orchestrator::config::{NetworkConfig, NodeOverride, NodeRole};
+use hotshot_types::signature_key::BLSPubKey;
+
+/// A run of 4 nodes, the first 2 of which form the DA committee, with `node_overrides`
+fn config(node_overrides: Vec<NodeOverride>) -> NetworkConfig<BLSPubKey, StaticElectionConfig> {
+    let mut config = NetworkConfig::default();
+    config.config.total_nodes = NonZeroUsize::new(4).unwrap();
+    config.config.da_committee_size = 2;
+    config.transaction_size = 100;
+    config.node_overrides = node_overrides;
+    config
+}
+
+#[test]
+fn overrides_apply_to_their_nodes_and_role() {
+    let everyone = NodeOverride::default();
+    let listed = NodeOverride {
+        nodes: vec![1, 3],
+        ..NodeOverride::default()
+    };
+    let da = NodeOverride {
+        role: Some(NodeRole::Da),
+        ..NodeOverride::default()
+    };
+    let listed_non_da = NodeOverride {
+        nodes: vec![1, 3],
+        role: Some(NodeRole::NonDa),
+        ..NodeOverride::default()
+    };
+    for node in 0..4 {
+        assert!(everyone.applies_to(node, 2));
+    }
+    assert!(!listed.applies_to(0, 2));
+    assert!(listed.applies_to(1, 2));
+    assert!(da.applies_to(1, 2));
+    assert!(!da.applies_to(2, 2));
+    // both the nodes and the role must match
+    assert!(!listed_non_da.applies_to(1, 2));
+    assert!(listed_non_da.applies_to(3, 2));
+    assert!(!listed_non_da.applies_to(2, 2));
+}
+
+#[test]
+fn later_overrides_take_precedence() {
+    let config = config(vec![
+        NodeOverride {
+            transactions_per_round: Some(10),
+            transaction_size: Some(512),
+            ..NodeOverride::default()
+        },
+        NodeOverride {
+            role: Some(NodeRole::NonDa),
+            transactions_per_round: Some(20),
+            ..NodeOverride::default()
+        },
+    ]);
+
+    let da_node = config.for_node(0);
+    assert_eq!(da_node.node_transactions_per_round, Some(10));
+    assert_eq!(da_node.transaction_size, 512);
+    // the later override only replaces what it sets
+    let other_node = config.for_node(3);
+    assert_eq!(other_node.node_transactions_per_round, Some(20));
+    assert_eq!(other_node.transaction_size, 512);
+    // the run itself is left as is
+    assert_eq!(config.node_transactions_per_round, None);
+    assert_eq!(config.transaction_size, 100);
+}
+
+#[test]
+fn run_overrides_replace_stakes_and_failure_domains() {
+    let mut config = config(vec![
+        NodeOverride {
+            nodes: vec![2],
+            stake: Some(5),
+            ..NodeOverride::default()
+        },
+        NodeOverride {
+            role: Some(NodeRole::Da),
+            failure_domain: Some("eu-west".to_string()),
+            ..NodeOverride::default()
+        },
+    ]);
+    config.stake_weights = vec![3];
+    config.apply_run_overrides();
+
+    // nodes without an entry keep the default stake of 1
+    assert_eq!(config.stake_weights, vec![3, 1, 5]);
+    assert_eq!(config.stake_of(2), 5);
+    assert_eq!(config.stake_of(3), 1);
+    assert_eq!(
+        config.failure_domains,
+        vec!["eu-west".to_string(), "eu-west".to_string()]
+    );
+}
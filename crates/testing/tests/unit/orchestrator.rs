@@ -0,0 +1,125 @@
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    num::NonZeroUsize,
+};
+
+use hotshot::traits::election::static_committee::StaticElectionConfig;
+use hotshot_orchestrator::{
+    campaign::RunResults,
+    config::{unix_time_ms, NetworkConfig},
+    OrchestratorApi, OrchestratorState,
+};
+use hotshot_types::{signature_key::BLSPubKey, traits::signature_key::SignatureKey};
+
+type State = OrchestratorState<BLSPubKey, StaticElectionConfig>;
+
+/// A run of 3 nodes which starts once 2 of them are ready
+fn state() -> State {
+    let mut config = NetworkConfig::default();
+    config.config.total_nodes = NonZeroUsize::new(3).unwrap();
+    config.config.known_nodes_with_stake.truncate(3);
+    config.start_threshold = Some(2);
+    OrchestratorState::new(config)
+}
+
+/// the public key of node `i`
+fn key(i: u64) -> BLSPubKey {
+    BLSPubKey::generated_from_seed_indexed([1u8; 32], i).0
+}
+
+/// Register a node as the client does: identity, then public key. Returns its index.
+fn register(state: &mut State, key: &BLSPubKey) -> u64 {
+    let node_index = u64::from(
+        state
+            .post_identity(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)])
+            .unwrap(),
+    );
+    // the client's body carries the length of the key first
+    let mut body = vec![0u8; 8];
+    body.extend(key.to_bytes());
+    state.register_public_key(node_index, &mut body).unwrap();
+    node_index
+}
+
+#[test]
+fn nodes_registering_after_the_start_are_newcomers() {
+    let mut state = state();
+    assert_eq!(register(&mut state, &key(0)), 0);
+    assert_eq!(register(&mut state, &key(1)), 1);
+    state.post_ready().unwrap();
+    state.post_ready().unwrap();
+    assert!(state.get_start().unwrap());
+    assert!(state.get_newcomers(0).unwrap().is_empty());
+
+    // the late joiner starts from the snapshot, which holds the keys of the running nodes
+    let snapshot = state.get_config_snapshot().unwrap();
+    assert_eq!(
+        BLSPubKey::get_public_key(&snapshot.config.known_nodes_with_stake[1]),
+        key(1)
+    );
+    assert_eq!(register(&mut state, &key(2)), 2);
+    assert_eq!(state.get_newcomers(0).unwrap(), vec![2]);
+    assert!(state.get_newcomers(1).unwrap().is_empty());
+    let stake_table = state.get_stake_table().unwrap();
+    assert_eq!(BLSPubKey::get_public_key(&stake_table[2]), key(2));
+}
+
+#[test]
+fn indices_of_nodes_which_leave_are_handed_out_again() {
+    let mut state = state();
+    for i in 0..3 {
+        assert_eq!(register(&mut state, &key(i)), i);
+    }
+    // the run is full
+    assert!(state
+        .post_identity(vec![IpAddr::V4(Ipv4Addr::LOCALHOST)])
+        .is_err());
+
+    state.post_leave(1).unwrap();
+    // a node which is not in the run cannot leave it
+    assert!(state.post_leave(1).is_err());
+    state.post_ready().unwrap();
+    state.post_ready().unwrap();
+
+    // the index of the node which left goes to the next node, which joins late
+    assert_eq!(register(&mut state, &key(3)), 1);
+    assert_eq!(state.get_newcomers(0).unwrap(), vec![1]);
+    let stake_table = state.get_stake_table().unwrap();
+    assert_eq!(BLSPubKey::get_public_key(&stake_table[1]), key(3));
+}
+
+#[test]
+fn runs_time_out_once_the_first_node_posted_its_results() {
+    let mut config = NetworkConfig::default();
+    config.config.total_nodes = NonZeroUsize::new(3).unwrap();
+    config.config.known_nodes_with_stake.truncate(3);
+    config.results_timeout_seconds = 10;
+    let mut state: State = OrchestratorState::new_campaign(vec![config.clone(), config]);
+    for i in 0..3 {
+        register(&mut state, &key(i));
+    }
+    assert_eq!(state.get_runs_after(0).unwrap(), 1);
+
+    // nothing times out before a node posted its results
+    assert!(!state.expire_run(unix_time_ms() + 60_000));
+    state
+        .post_run_results(RunResults {
+            node_index: 0,
+            ..RunResults::default()
+        })
+        .unwrap();
+    let posted_ms = unix_time_ms();
+    assert!(!state.expire_run(posted_ms + 5_000));
+    assert_eq!(state.get_run_index().unwrap(), Some(0));
+
+    // the nodes which did not post by the deadline are not waited for
+    assert!(state.expire_run(posted_ms + 11_000));
+    assert_eq!(state.get_run_index().unwrap(), Some(1));
+    assert_eq!(
+        state.get_campaign_results().unwrap()[0].node_results.len(),
+        1
+    );
+    // the nodes of the first run still learn there is a run after theirs
+    assert_eq!(state.get_runs_after(0).unwrap(), 1);
+    assert_eq!(state.get_runs_after(1).unwrap(), 0);
+}
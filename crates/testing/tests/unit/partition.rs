@@ -0,0 +1,94 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use hotshot_orchestrator::{
+    campaign::{CampaignRunResults, PartitionOutcome, RunResults},
+    config::{PartitionOrder, PartitionTracker, ScheduledPartition},
+};
+use hotshot_types::{
+    signature_key::BLSPubKey,
+    traits::{network::NetworkPartition, signature_key::SignatureKey},
+};
+
+/// the public key of node `i`
+fn key(i: u64) -> BLSPubKey {
+    BLSPubKey::generated_from_seed_indexed([0u8; 32], i).0
+}
+
+#[test]
+fn groups_reach_their_own_members() {
+    let partition = ScheduledPartition {
+        view: 10,
+        duration_seconds: 5,
+        groups: vec![vec![0, 1], vec![2, 9]],
+    };
+    assert_eq!(partition.reachable(1, 6), BTreeSet::from([0, 1]));
+    // nodes past the end of the network are ignored
+    assert_eq!(partition.reachable(2, 6), BTreeSet::from([2]));
+    // the nodes in no group are together
+    assert_eq!(partition.reachable(4, 6), BTreeSet::from([3, 4, 5]));
+}
+
+#[test]
+fn every_node_is_ordered_into_a_partition_once_one_reaches_its_view() {
+    let mut tracker = PartitionTracker::new(
+        vec![ScheduledPartition {
+            view: 10,
+            duration_seconds: 5,
+            groups: vec![vec![0, 1]],
+        }],
+        4,
+    );
+    assert_eq!(tracker.order(0, 9, 1_000), None);
+    let order = |reachable: &[u64]| PartitionOrder {
+        index: 0,
+        reachable: reachable.iter().copied().collect(),
+    };
+    assert_eq!(tracker.order(0, 10, 2_000), Some(order(&[0, 1])));
+    // a node behind is ordered into the partition too, on the other side of it
+    assert_eq!(tracker.order(3, 4, 3_000), Some(order(&[2, 3])));
+    // until it heals, without ever starting again
+    assert_eq!(tracker.order(1, 12, 7_000), None);
+    assert_eq!(tracker.order(1, 20, 8_000), None);
+}
+
+#[test]
+fn partitions_drop_peers_until_healed() {
+    let partition = NetworkPartition::<BLSPubKey>::default();
+    assert!(!partition.is_partitioned());
+    assert!(partition.is_reachable(&key(3)));
+
+    // clones share the partition
+    partition.clone().isolate([key(0), key(1)]);
+    assert!(partition.is_partitioned());
+    assert!(partition.is_reachable(&key(1)));
+    assert!(!partition.is_reachable(&key(3)));
+
+    partition.heal();
+    assert!(!partition.is_partitioned());
+    assert!(partition.is_reachable(&key(3)));
+}
+
+#[test]
+fn nodes_recover_once_every_partition_is_followed_by_a_decide() {
+    let outcome = |recovered_after| PartitionOutcome {
+        view: 10,
+        duration: Duration::from_secs(5),
+        recovered_after,
+    };
+    let node = |partitions| RunResults {
+        partitions,
+        ..RunResults::default()
+    };
+    let results = CampaignRunResults {
+        run_index: 0,
+        total_nodes: 3,
+        transaction_size: 100,
+        transactions_per_round: 10,
+        node_results: vec![
+            node(vec![outcome(Some(Duration::from_secs(1)))]),
+            node(vec![outcome(Some(Duration::from_secs(2))), outcome(None)]),
+            node(Vec::new()),
+        ],
+    };
+    assert_eq!(results.recovered_nodes(), (1, 2));
+}
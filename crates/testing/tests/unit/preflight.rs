@@ -0,0 +1,76 @@
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
+
+use hotshot_orchestrator::preflight::{
+    check_clock_skew, check_disk, check_udp_port, check_web_server, CheckResult, CheckStatus,
+    PreflightReport, CLOCK_SKEW_FAIL_MS,
+};
+use tide_disco::Url;
+
+/// an address on the loopback interface with a port the system picks
+fn any_local_port() -> SocketAddr {
+    SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)
+}
+
+#[test]
+fn clock_skew_passes_warns_then_fails() {
+    assert_eq!(check_clock_skew(Some(20)).status, CheckStatus::Pass);
+    assert_eq!(check_clock_skew(Some(-800)).status, CheckStatus::Warn);
+    assert_eq!(check_clock_skew(None).status, CheckStatus::Warn);
+    let skewed = check_clock_skew(Some(CLOCK_SKEW_FAIL_MS + 1));
+    assert_eq!(skewed.status, CheckStatus::Fail);
+    assert!(skewed.remedy.is_some());
+}
+
+#[test]
+fn only_failures_are_hard() {
+    let mut report = PreflightReport {
+        checks: vec![
+            CheckResult::pass("a", "fine"),
+            CheckResult::warn("b", "slow", "speed it up"),
+        ],
+    };
+    assert!(!report.has_hard_failures());
+
+    report
+        .checks
+        .push(CheckResult::fail("c", "broken", "repair it"));
+    assert!(report.has_hard_failures());
+    assert_eq!(report.count(CheckStatus::Warn), 1);
+    let printed = report.to_string();
+    assert!(printed.contains("1 passed, 1 warned, 1 failed"));
+    assert!(printed.contains("[FAIL] c: broken"));
+    assert!(printed.contains("fix: repair it"));
+}
+
+#[test]
+fn ports_in_use_fail() {
+    let socket = UdpSocket::bind(any_local_port()).expect("could not bind a udp port");
+    let taken = socket.local_addr().expect("the socket has no address");
+    assert_eq!(check_udp_port(taken).status, CheckStatus::Fail);
+    drop(socket);
+    assert_eq!(check_udp_port(taken).status, CheckStatus::Pass);
+}
+
+#[test]
+fn web_servers_must_listen() {
+    let listener = TcpListener::bind(any_local_port()).expect("could not bind a tcp port");
+    let addr = listener.local_addr().expect("the listener has no address");
+    let url = Url::parse(&format!("http://{addr}")).expect("invalid url");
+    assert_eq!(check_web_server(&url).status, CheckStatus::Pass);
+    drop(listener);
+    assert_eq!(check_web_server(&url).status, CheckStatus::Fail);
+}
+
+#[test]
+fn disks_are_written_to() {
+    let directory = std::env::temp_dir();
+    let checks = check_disk(&directory, 0);
+    assert_eq!(checks.len(), 2);
+    assert!(checks.iter().all(|check| check.status != CheckStatus::Fail));
+
+    let missing = directory.join("hotshot-preflight-missing").join("nested");
+    let checks = check_disk(&missing, 0);
+    assert!(checks
+        .iter()
+        .any(|check| check.name == "disk write" && check.status == CheckStatus::Fail));
+}
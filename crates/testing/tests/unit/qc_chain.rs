@@ -0,0 +1,155 @@
+use bitvec::bitvec;
+use commit::Committable;
+use ethereum_types::U256;
+use hotshot::types::{BLSPubKey, SignatureKey};
+use hotshot_example_types::{node_types::TestTypes, state_types::TestInstanceState};
+use hotshot_testing::task_helpers::key_pair_for_id;
+use hotshot_types::{
+    data::Leaf,
+    qc_chain::{verify_qc_chain, QcChainError, QcChainVerifier, StakeTable},
+    simple_certificate::QuorumCertificate,
+    simple_vote::{QuorumData, SimpleVote},
+    traits::node_implementation::{ConsensusTime, NodeType},
+    vote::{Certificate, Vote},
+};
+
+type ViewNumber = <TestTypes as NodeType>::Time;
+
+/// the stake table of nodes `ids`, each with a stake of one
+fn stake_table(ids: std::ops::Range<u64>) -> StakeTable<TestTypes> {
+    ids.map(|id| key_pair_for_id(id).1.get_stake_table_entry(1))
+        .collect()
+}
+
+/// a certificate for `leaf` signed by the nodes `signers` of the stake table of nodes `ids`
+fn certificate(
+    leaf: &Leaf<TestTypes>,
+    ids: std::ops::Range<u64>,
+    signers: usize,
+) -> QuorumCertificate<TestTypes> {
+    let data = QuorumData {
+        leaf_commit: leaf.commit(),
+    };
+    let mut signed = bitvec![0; ids.clone().count()];
+    let mut signatures = Vec::new();
+    for (index, id) in ids.clone().enumerate().take(signers) {
+        let (private_key, public_key) = key_pair_for_id(id);
+        let vote = SimpleVote::<TestTypes, QuorumData<TestTypes>>::create_signed_vote(
+            data.clone(),
+            leaf.view_number,
+            &public_key,
+            &private_key,
+        )
+        .unwrap();
+        signed.set(index, true);
+        signatures.push(vote.get_signature());
+    }
+    // assemble without a threshold, so that signatures of too little stake can be built
+    let params = BLSPubKey::get_public_parameter(stake_table(ids), U256::zero());
+    let signature = BLSPubKey::assemble(&params, signed.as_bitslice(), &signatures);
+    QuorumCertificate::create_signed_certificate(data.commit(), data, signature, leaf.view_number)
+}
+
+fn leaf(view: u64, parent: &Leaf<TestTypes>) -> Leaf<TestTypes> {
+    let mut leaf = Leaf::genesis(&TestInstanceState {});
+    leaf.view_number = ViewNumber::new(view);
+    leaf.parent_commitment = parent.commit();
+    leaf
+}
+
+#[test]
+fn certified_chains_are_verified() {
+    let genesis = Leaf::genesis(&TestInstanceState {});
+    let one = leaf(1, &genesis);
+    let two = leaf(2, &one);
+    let chain = vec![
+        (one.clone(), certificate(&one, 0..4, 3)),
+        (two.clone(), certificate(&two, 0..4, 4)),
+    ];
+
+    let tip = verify_qc_chain(stake_table(0..4), chain).unwrap();
+    assert_eq!(tip.leaf, two);
+    assert_eq!(tip.stake_table, stake_table(0..4));
+
+    assert_eq!(
+        verify_qc_chain(stake_table(0..4), Vec::new()).unwrap_err(),
+        QcChainError::EmptyChain
+    );
+}
+
+#[test]
+fn invalid_chains_are_rejected() {
+    let genesis = Leaf::genesis(&TestInstanceState {});
+    let one = leaf(1, &genesis);
+    let two = leaf(2, &one);
+
+    // two of four nodes are not a quorum
+    assert_eq!(
+        verify_qc_chain(
+            stake_table(0..4),
+            vec![(one.clone(), certificate(&one, 0..4, 2))]
+        )
+        .unwrap_err(),
+        QcChainError::InvalidSignature { view: 1 }
+    );
+
+    // a certificate for another leaf
+    assert_eq!(
+        verify_qc_chain(
+            stake_table(0..4),
+            vec![(two.clone(), certificate(&one, 0..4, 4))]
+        )
+        .unwrap_err(),
+        QcChainError::CertificateMismatch {
+            view: 2,
+            qc_view: 1
+        }
+    );
+
+    // a leaf which does not extend the previous one
+    let fork = leaf(3, &one);
+    assert_eq!(
+        verify_qc_chain(
+            stake_table(0..4),
+            vec![
+                (one.clone(), certificate(&one, 0..4, 4)),
+                (two.clone(), certificate(&two, 0..4, 4)),
+                (fork.clone(), certificate(&fork, 0..4, 4)),
+            ]
+        )
+        .unwrap_err(),
+        QcChainError::BrokenParentLink {
+            view: 3,
+            parent_view: 2
+        }
+    );
+}
+
+#[test]
+fn stake_table_transitions_are_followed() {
+    let genesis = Leaf::genesis(&TestInstanceState {});
+    let one = leaf(1, &genesis);
+    let two = leaf(2, &one);
+    // the leaf of view 1 hands over to nodes 4 to 7
+    let verifier = QcChainVerifier::<TestTypes>::new(stake_table(0..4))
+        .with_transitions(|leaf| (*leaf.view_number == 1).then(|| stake_table(4..8)));
+
+    let tip = verifier
+        .verify(vec![
+            (one.clone(), certificate(&one, 0..4, 4)),
+            (two.clone(), certificate(&two, 4..8, 3)),
+        ])
+        .unwrap();
+    assert_eq!(tip.stake_table, stake_table(4..8));
+
+    // the old stake table no longer certifies leaves
+    assert_eq!(
+        verifier
+            .verify(vec![
+                (one.clone(), certificate(&one, 0..4, 4)),
+                (two.clone(), certificate(&two, 0..4, 4)),
+            ])
+            .unwrap_err(),
+        QcChainError::InvalidSignature { view: 2 }
+    );
+}
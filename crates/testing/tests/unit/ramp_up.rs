@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use hotshot_orchestrator::config::RampUpSchedule;
+
+#[test]
+fn nodes_start_in_waves_by_index() {
+    let schedule = RampUpSchedule {
+        wave_size: 3,
+        wave_interval_seconds: 5,
+    };
+    assert_eq!(schedule.wave_of(0), 0);
+    assert_eq!(schedule.wave_of(2), 0);
+    assert_eq!(schedule.wave_of(3), 1);
+    assert_eq!(schedule.start_offset(2), Duration::ZERO);
+    assert_eq!(schedule.start_offset(7), Duration::from_secs(10));
+    // the last of 10 nodes is alone in the fourth wave
+    assert_eq!(schedule.wave_of(9), 3);
+    assert_eq!(schedule.start_offset(9), Duration::from_secs(15));
+}
+
+#[test]
+fn empty_waves_hold_one_node() {
+    let schedule = RampUpSchedule {
+        wave_size: 0,
+        wave_interval_seconds: 2,
+    };
+    assert_eq!(schedule.wave_of(4), 4);
+    assert_eq!(schedule.start_offset(0), Duration::ZERO);
+}
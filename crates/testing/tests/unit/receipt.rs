@@ -0,0 +1,54 @@
+use commit::{Commitment, Committable};
+use hotshot_example_types::{block_types::TestTransaction, node_types::TestTypes};
+use hotshot_types::{
+    data::ViewNumber,
+    receipt::{DropReason, TransactionIndex, TxStatus},
+    traits::node_implementation::ConsensusTime,
+};
+
+/// The commitment of transaction `i`
+fn tx(i: u8) -> Commitment<TestTransaction> {
+    TestTransaction::new(vec![i]).commit()
+}
+
+/// view `n`
+fn view(n: u64) -> ViewNumber {
+    ViewNumber::new(n)
+}
+
+#[test]
+fn transactions_go_from_pending_to_decided() {
+    let mut index = TransactionIndex::<TestTypes>::new(10);
+    assert_eq!(index.status(&tx(1), true), TxStatus::Pending);
+
+    index.record_included(view(3), [tx(1), tx(2)]);
+    assert_eq!(index.status(&tx(1), true), TxStatus::Included(view(3)));
+
+    // the proposal for view 3 fails, and view 5 takes the first transaction instead
+    index.record_included(view(5), [tx(1)]);
+    index.record_decided(view(5), [tx(1)]);
+    index.prune(view(5));
+    assert_eq!(index.status(&tx(1), false), TxStatus::Decided(view(5)));
+    assert_eq!(index.status(&tx(2), true), TxStatus::Pending);
+
+    // decided transactions are forgotten once they are older than the retention
+    index.prune(view(16));
+    assert_eq!(index.status(&tx(1), false), TxStatus::Unknown);
+}
+
+#[test]
+fn dropped_transactions_report_why() {
+    let mut index = TransactionIndex::<TestTypes>::new(10);
+    index.record_included(view(2), [tx(1)]);
+    index.record_dropped(view(4), DropReason::Expired, [tx(1), tx(2)]);
+    assert_eq!(
+        index.status(&tx(1), false),
+        TxStatus::Dropped(DropReason::Expired)
+    );
+    assert_eq!(index.status(&tx(3), false), TxStatus::Unknown);
+
+    // a transaction is never dropped once it is decided
+    index.record_decided(view(6), [tx(2)]);
+    index.record_dropped(view(7), DropReason::Expired, [tx(2)]);
+    assert_eq!(index.status(&tx(2), false), TxStatus::Decided(view(6)));
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use hotshot_types::{
+    reputation::{LeaderReputation, LeaderScore, LeaderScores, ReputationRules},
+    signature_key::BLSPubKey,
+    traits::signature_key::SignatureKey,
+};
+
+#[test]
+fn late_proposals_are_not_missed() {
+    let first = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0).0;
+    let second = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1).0;
+    let mut scores = LeaderScores::default();
+
+    scores.record_missed(3, first);
+    scores.record_proposal(3, first);
+    scores.record_proposal(4, first);
+    scores.record_missed(5, second);
+    // a proposal timing out twice is missed once
+    scores.record_missed(5, second);
+    scores.record_invalid(second);
+
+    assert_eq!(
+        scores.get(&first),
+        Some(LeaderScore {
+            proposals: 1,
+            late_proposals: 1,
+            missed_proposals: 0,
+            invalid_proposals: 0,
+        })
+    );
+    assert_eq!(
+        scores.get(&second),
+        Some(LeaderScore {
+            proposals: 0,
+            late_proposals: 0,
+            missed_proposals: 1,
+            invalid_proposals: 1,
+        })
+    );
+    assert_eq!(scores.report().len(), 2);
+}
+
+#[test]
+fn failing_leaders_are_down_weighted_from_the_next_epoch() {
+    let rules = ReputationRules {
+        epoch_views: 8,
+        window: 2,
+        max_missed: 1,
+        penalty: 2,
+    };
+    assert!(rules.validate().is_ok());
+    let num_leaders = 4;
+    let reputation = LeaderReputation::default();
+    let shared = reputation.clone();
+
+    // the leader of views 1 and 5 never gets its leaf decided
+    shared.record_decided(&rules, &[2, 3, 4, 6, 7, 8], num_leaders, |view| {
+        usize::try_from(view).unwrap() % num_leaders
+    });
+
+    // the epoch after next is the first the leader is down-weighted in
+    assert_eq!(reputation.leader(&rules, 13, 1, num_leaders), 1);
+    assert_eq!(reputation.down_weighted(&rules, 16), BTreeSet::from([1]));
+    // it keeps one in `penalty` of its turns, and passes the others on
+    assert_eq!(reputation.leader(&rules, 17, 1, num_leaders), 1);
+    assert_eq!(reputation.leader(&rules, 21, 1, num_leaders), 2);
+    assert_eq!(reputation.leader(&rules, 22, 2, num_leaders), 2);
+}
+
+#[test]
+fn reputation_rules_are_validated() {
+    assert!(ReputationRules::default().validate().is_ok());
+    for rules in [
+        ReputationRules {
+            epoch_views: 0,
+            ..ReputationRules::default()
+        },
+        ReputationRules {
+            max_missed: 10,
+            ..ReputationRules::default()
+        },
+        ReputationRules {
+            penalty: 0,
+            ..ReputationRules::default()
+        },
+    ] {
+        assert!(rules.validate().is_err());
+    }
+}
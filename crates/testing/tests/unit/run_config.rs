@@ -0,0 +1,56 @@
+use hotshot::{traits::election::static_committee::StaticElectionConfig, types::BLSPubKey};
+use hotshot_orchestrator::config::{NetworkConfig, NetworkConfigError};
+
+/// The run configuration shipped with the orchestrator
+const RUN_CONFIG: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../orchestrator/run-config.toml"
+);
+
+/// Load the run configuration file at `path`
+fn load(path: &str) -> Result<NetworkConfig<BLSPubKey, StaticElectionConfig>, NetworkConfigError> {
+    NetworkConfig::from_run_config_file(path)
+}
+
+/// Write `contents` to a run configuration file named after `name`, and load it
+fn load_contents(
+    name: &str,
+    contents: &str,
+) -> Result<NetworkConfig<BLSPubKey, StaticElectionConfig>, NetworkConfigError> {
+    let path = std::env::temp_dir().join(format!(
+        "hotshot-run-config-{name}-{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(&path, contents).unwrap();
+    let loaded = load(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    loaded
+}
+
+#[test]
+fn shipped_run_config_loads() {
+    let config = load(RUN_CONFIG).unwrap();
+    assert_eq!(config.rounds, 100);
+}
+
+#[test]
+fn run_configs_which_cannot_be_loaded_are_errors() {
+    assert!(matches!(
+        load("/nonexistent/run-config.toml"),
+        Err(NetworkConfigError::ReadFromFileError(_))
+    ));
+    assert!(matches!(
+        load_contents("invalid", "rounds = \"many\""),
+        Err(NetworkConfigError::RunConfigDeserializeError(_))
+    ));
+
+    let contents = std::fs::read_to_string(RUN_CONFIG).unwrap().replacen(
+        "rounds = 100",
+        "rounds = 100\nmnemonic = \"not a mnemonic\"",
+        1,
+    );
+    assert!(matches!(
+        load_contents("mnemonic", &contents),
+        Err(NetworkConfigError::KeyDerivationError(_))
+    ));
+}
@@ -0,0 +1,62 @@
+use futures::executor::block_on;
+use hotshot_types::{
+    runtime::{self, BlockingPool, RuntimeConfig},
+    signature_key::BLSPubKey,
+    signing::{self, SigningDomain},
+    traits::signature_key::SignatureKey,
+};
+
+/// The name of the thread running the caller
+fn thread_name() -> Option<String> {
+    std::thread::current().name().map(ToString::to_string)
+}
+
+#[test]
+fn blocking_pools_run_work_on_their_threads() {
+    let inline = BlockingPool::new("inline", 0).unwrap();
+    assert_eq!(inline.threads(), 0);
+    assert_eq!(block_on(inline.run(thread_name)), Some(thread_name()));
+
+    let pool = BlockingPool::new("test-pool", 2).unwrap();
+    assert_eq!(pool.threads(), 2);
+    let name = block_on(pool.run(thread_name)).flatten().unwrap();
+    assert!(name.starts_with("test-pool-"));
+
+    // a job which panics loses its result, but not its thread
+    assert_eq!(block_on(pool.run(|| -> u32 { unreachable!() })), None);
+    assert_eq!(block_on(pool.run(|| 7)), Some(7));
+}
+
+#[test]
+fn offloaded_validation_matches_inline_validation() {
+    runtime::configure(&RuntimeConfig {
+        verification_threads: 2,
+        ..RuntimeConfig::default()
+    })
+    .unwrap();
+
+    let (key, private_key) = BLSPubKey::generated_from_seed_indexed([0u8; 32], 0);
+    let other_key = BLSPubKey::generated_from_seed_indexed([0u8; 32], 1).0;
+    let message = [3u8; 32];
+    let signature =
+        signing::sign::<BLSPubKey>(&private_key, SigningDomain::Vote, &message).unwrap();
+
+    assert!(block_on(signing::validate_offloaded(
+        key,
+        signature.clone(),
+        SigningDomain::Vote,
+        &message
+    )));
+    assert!(!block_on(signing::validate_offloaded(
+        other_key,
+        signature.clone(),
+        SigningDomain::Vote,
+        &message
+    )));
+    assert!(!block_on(signing::validate_offloaded(
+        key,
+        signature,
+        SigningDomain::QuorumProposal,
+        &message
+    )));
+}
@@ -0,0 +1,88 @@
+use commit::Committable;
+use hotshot_example_types::{node_types::TestTypes, state_types::TestInstanceState};
+use hotshot_types::{
+    data::{random_commitment, Leaf},
+    safety::{SafetyEvidence, SafetyMonitor},
+    simple_certificate::QuorumCertificate,
+    simple_vote::QuorumData,
+    traits::node_implementation::{ConsensusTime, NodeType},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::marker::PhantomData;
+
+type ViewNumber = <TestTypes as NodeType>::Time;
+
+fn certificate(view: u64, leaf: &Leaf<TestTypes>) -> QuorumCertificate<TestTypes> {
+    let data = QuorumData {
+        leaf_commit: leaf.commit(),
+    };
+    QuorumCertificate {
+        vote_commitment: data.commit(),
+        data,
+        view_number: ViewNumber::new(view),
+        signatures: None,
+        is_genesis: false,
+        _pd: PhantomData,
+    }
+}
+
+fn leaf(view: u64, parent: &Leaf<TestTypes>) -> Leaf<TestTypes> {
+    let mut leaf = Leaf::genesis(&TestInstanceState {});
+    leaf.view_number = ViewNumber::new(view);
+    leaf.parent_commitment = parent.commit();
+    leaf
+}
+
+#[test]
+fn conflicting_certificates_are_detected() {
+    let genesis = Leaf::genesis(&TestInstanceState {});
+    let first = leaf(1, &genesis);
+    let mut other = leaf(1, &genesis);
+    other.parent_commitment = random_commitment(&mut StdRng::seed_from_u64(0));
+
+    let mut monitor = SafetyMonitor::new(10);
+    assert!(monitor
+        .observe_certificate(&certificate(1, &first))
+        .is_none());
+    // seeing the same certificate twice is fine
+    assert!(monitor
+        .observe_certificate(&certificate(1, &first))
+        .is_none());
+    assert_eq!(
+        monitor.observe_certificate(&certificate(1, &other)),
+        Some(SafetyEvidence::ConflictingCertificates {
+            first: certificate(1, &first),
+            second: certificate(1, &other),
+        })
+    );
+}
+
+#[test]
+fn reorganizations_are_detected() {
+    let genesis = Leaf::genesis(&TestInstanceState {});
+    let one = leaf(1, &genesis);
+    let two = leaf(2, &one);
+    let three = leaf(3, &two);
+
+    let mut monitor = SafetyMonitor::new(10);
+    assert!(monitor
+        .observe_decide(&[two.clone(), one.clone()], true)
+        .is_none());
+    assert!(monitor.observe_decide(&[three], true).is_none());
+
+    // a leaf of view 4 which does not extend the leaf decided in view 3
+    let fork = leaf(4, &two);
+    assert!(matches!(
+        monitor.observe_decide(&[fork.clone()], true),
+        Some(SafetyEvidence::Reorganization { leaf, .. }) if leaf == fork
+    ));
+
+    // a different leaf decided in view 2
+    let mut other_two = leaf(2, &one);
+    other_two.parent_commitment = random_commitment(&mut StdRng::seed_from_u64(0));
+    assert!(matches!(
+        monitor.observe_decide(&[other_two], false),
+        Some(SafetyEvidence::ConflictingDecides { view_number, .. })
+            if view_number == ViewNumber::new(2)
+    ));
+}
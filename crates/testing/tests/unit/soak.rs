@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use hotshot_orchestrator::{
+    config::SoakConfig,
+    soak::{ProcessUsage, ResourceMetric, ResourceSample, SoakMonitor},
+};
+
+/// a sample taken `minutes` into the run
+fn sample(minutes: u64, rss_mb: u64, open_fds: u64, tasks: u64) -> ResourceSample {
+    ResourceSample {
+        elapsed: Duration::from_secs(minutes * 60),
+        rss_bytes: Some(rss_mb << 20),
+        open_fds: Some(open_fds),
+        threads: Some(8),
+        tasks: Some(tasks),
+        channel_depth: Some(minutes % 3 * 100),
+    }
+}
+
+/// thresholds, with the first two samples taken as the warm-up
+fn config() -> SoakConfig {
+    SoakConfig {
+        warmup_samples: 2,
+        max_rss_growth_percent: 20,
+        max_fd_growth: 10,
+        max_task_growth: 4,
+        max_channel_depth_growth: 500,
+        ..SoakConfig::default()
+    }
+}
+
+#[test]
+fn steady_usage_does_not_leak() {
+    let mut soak = SoakMonitor::new(config());
+    for minute in 0..60 {
+        // memory grows during the warm-up, then spikes now and then
+        let rss = match minute {
+            0 => 50,
+            m if m % 10 == 0 => 150,
+            _ => 100,
+        };
+        soak.record(sample(minute, rss, 40 + minute % 2, 20));
+    }
+    assert!(soak.leaks().is_empty());
+}
+
+#[test]
+fn metrics_trending_upward_leak() {
+    let mut soak = SoakMonitor::new(config());
+    // memory grows by 1MB, and a file descriptor is opened, every minute
+    let leaking = |minute| sample(minute, 100 + minute, 40 + minute, 20);
+    for minute in 0..3 {
+        soak.record(leaking(minute));
+    }
+    // a single sample after the warm-up has no trend
+    assert!(soak.leaks().is_empty());
+
+    for minute in 3..60 {
+        soak.record(leaking(minute));
+    }
+    let leaks = soak.leaks();
+    let metrics: Vec<_> = leaks.iter().map(|leak| leak.metric).collect();
+    assert_eq!(metrics, [ResourceMetric::Rss, ResourceMetric::OpenFds]);
+    // 57MB over the 102MB after the warm-up
+    assert!((leaks[0].growth - 57.0 / 102.0 * 100.0).abs() < 1e-6);
+    assert!((leaks[1].growth - 57.0).abs() < 1e-6);
+    assert_eq!(leaks[1].threshold, 10);
+}
+
+#[test]
+fn process_usage_is_read_where_the_platform_reports_it() {
+    let usage = ProcessUsage::current();
+    if cfg!(target_os = "linux") {
+        assert!(usage.rss_bytes.is_some_and(|rss| rss > 0));
+        assert!(usage.open_fds.is_some_and(|fds| fds > 0));
+        assert!(usage.threads.is_some_and(|threads| threads > 0));
+    }
+}
@@ -0,0 +1,73 @@
+use hotshot::types::{BLSPubKey, SignatureKey};
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_testing::task_helpers::key_pair_for_id;
+use hotshot_types::{
+    stake_table_cache::{stake_table_epoch, StakeTableCache, StakeTableCacheStats},
+    traits::{election::Membership, node_implementation::NodeType},
+};
+
+type TestMembership = <TestTypes as NodeType>::Membership;
+
+/// the stake table of nodes `ids`, each with a stake of one
+fn stake_table(ids: std::ops::Range<u64>) -> Vec<<BLSPubKey as SignatureKey>::StakeTableEntry> {
+    ids.map(|id| key_pair_for_id(id).1.get_stake_table_entry(1))
+        .collect()
+}
+
+#[test]
+fn lookups_are_cached_per_epoch() {
+    let cache = StakeTableCache::<BLSPubKey>::default();
+    let table = stake_table(0..4);
+    let epoch = stake_table_epoch::<BLSPubKey>(&table);
+
+    assert_eq!(
+        cache.index_of(epoch, &table, &key_pair_for_id(2).1),
+        Some(2)
+    );
+    assert_eq!(
+        cache.index_of(epoch, &table, &key_pair_for_id(3).1),
+        Some(3)
+    );
+    assert_eq!(cache.index_of(epoch, &table, &key_pair_for_id(9).1), None);
+    let _ = cache.params(epoch, &table, 3);
+    let _ = cache.params(epoch, &table, 3);
+    assert_eq!(cache.stats(), StakeTableCacheStats { hits: 3, misses: 2 });
+    assert_eq!(cache.stats().hit_rate_percent(), Some(60));
+
+    // another stake table is of another epoch, which starts the cache over
+    let reordered: Vec<_> = table.iter().rev().cloned().collect();
+    let new_epoch = stake_table_epoch::<BLSPubKey>(&reordered);
+    assert_ne!(new_epoch, epoch);
+    assert_eq!(
+        cache.index_of(new_epoch, &reordered, &key_pair_for_id(2).1),
+        Some(1)
+    );
+    assert_eq!(cache.clone().stats().misses, 3);
+}
+
+#[test]
+fn static_committees_cache_their_stake_table() {
+    let membership = TestMembership::create_election(
+        stake_table(0..5),
+        TestMembership::default_election_config(4),
+    );
+    assert_eq!(
+        membership.stake_table_cache_stats(),
+        Some(StakeTableCacheStats::default())
+    );
+
+    // only the committee is indexed
+    assert_eq!(
+        membership.get_committee_index(&key_pair_for_id(1).1),
+        Some(1)
+    );
+    assert!(membership.has_stake(&key_pair_for_id(3).1));
+    assert!(!membership.has_stake(&key_pair_for_id(4).1));
+    let _ = membership.get_committee_qc_params(membership.success_threshold().get());
+
+    let stats = membership.stake_table_cache_stats().unwrap();
+    assert_eq!(stats.misses, 2);
+    assert_eq!(stats.hits, 2);
+    // clones share the cache
+    assert_eq!(membership.clone().stake_table_cache_stats(), Some(stats));
+}
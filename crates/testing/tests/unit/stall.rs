@@ -0,0 +1,79 @@
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+use hotshot_example_types::node_types::TestTypes;
+use hotshot_types::{
+    data::ViewNumber,
+    signature_key::BLSPubKey,
+    stall::{StallTracker, TimerStates, VoteCounts, VoteKind},
+    traits::{node_implementation::ConsensusTime, signature_key::SignatureKey},
+};
+
+/// The key of node `i`
+fn key(i: u64) -> BLSPubKey {
+    BLSPubKey::generated_from_seed_indexed([0u8; 32], i).0
+}
+
+/// view `n`
+fn view(n: u64) -> ViewNumber {
+    ViewNumber::new(n)
+}
+
+#[test]
+fn diagnoses_what_was_seen_of_the_current_view() {
+    let start = Instant::now();
+    let committee: BTreeSet<_> = (0..4).map(key).collect();
+    let mut tracker = StallTracker::<TestTypes>::new(view(0), start);
+    tracker.record_proposal(view(0), key(1));
+    assert!(tracker.enter_view(view(1), start));
+
+    tracker.record_vote(VoteKind::Quorum, view(1), key(1));
+    tracker.record_vote(VoteKind::Timeout, view(2), key(2));
+    // votes of views before the current one only count for the peers heard from
+    tracker.record_vote(VoteKind::Quorum, view(0), key(2));
+    tracker.timers_mut().view_timeouts += 1;
+
+    let later = start + Duration::from_secs(30);
+    let diagnostics = tracker.diagnostics(later, &key(0), key(2), &committee, 3);
+    assert_eq!(diagnostics.view, view(1));
+    assert_eq!(diagnostics.stalled_for, Duration::from_secs(30));
+    assert_eq!(diagnostics.leader, key(2));
+    assert_eq!(diagnostics.last_proposal, Some((view(0), key(1))));
+    assert_eq!(
+        diagnostics.votes,
+        VoteCounts {
+            quorum: 1,
+            timeout: 1,
+            ..VoteCounts::default()
+        }
+    );
+    assert_eq!(diagnostics.vote_threshold, 3);
+    assert_eq!(diagnostics.peers.committee_size, 4);
+    assert_eq!(diagnostics.peers.heard_from, 2);
+    assert_eq!(diagnostics.peers.silent, vec![key(3)]);
+    assert_eq!(diagnostics.timers.view_timeouts, 1);
+}
+
+#[test]
+fn forgets_the_views_it_left() {
+    let start = Instant::now();
+    let committee: BTreeSet<_> = (0..2).map(key).collect();
+    let mut tracker = StallTracker::<TestTypes>::new(view(3), start);
+    tracker.record_vote(VoteKind::DA, view(3), key(1));
+    tracker.timers_mut().view_sync_triggered = true;
+
+    // older views do not move the tracker back
+    assert!(!tracker.enter_view(view(2), start));
+    assert_eq!(tracker.view(), view(3));
+
+    let later = start + Duration::from_secs(5);
+    assert!(tracker.enter_view(view(4), later));
+    let diagnostics = tracker.diagnostics(later, &key(0), key(1), &committee, 2);
+    assert_eq!(diagnostics.stalled_for, Duration::ZERO);
+    assert_eq!(diagnostics.votes, VoteCounts::default());
+    assert_eq!(diagnostics.timers, TimerStates::default());
+    assert_eq!(diagnostics.peers.silent, vec![key(1)]);
+    assert!(diagnostics.last_proposal.is_none());
+}
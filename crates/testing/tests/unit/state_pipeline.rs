@@ -0,0 +1,106 @@
+use hotshot_task_impls::state_pipeline::{CommittedApplication, StatePipeline, MAX_HELD_MESSAGES};
+use hotshot_types::{data::ViewNumber, traits::node_implementation::ConsensusTime};
+
+/// A pipeline of applications identified by their view, for leaves identified by a number
+type Pipeline = StatePipeline<ViewNumber, u64, &'static str, bool, &'static str>;
+
+#[test]
+fn applications_are_committed_in_view_order() {
+    let mut pipeline = Pipeline::default();
+    assert!(pipeline.start(ViewNumber::new(1), 1, "first"));
+    assert!(pipeline.start(ViewNumber::new(2), 2, "second"));
+    assert!(pipeline.start(ViewNumber::new(3), 3, "third"));
+    // only one application runs for a view
+    assert!(!pipeline.start(ViewNumber::new(3), 4, "other third"));
+
+    // the later applications wait for the first one
+    assert!(pipeline.finish(ViewNumber::new(2), true).is_empty());
+    assert!(pipeline.finish(ViewNumber::new(3), false).is_empty());
+    assert_eq!(pipeline.len(), 3);
+
+    let committed = pipeline.finish(ViewNumber::new(1), true);
+    let order: Vec<_> = committed
+        .iter()
+        .map(|committed| (committed.view, committed.job, committed.outcome))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            (ViewNumber::new(1), "first", true),
+            (ViewNumber::new(2), "second", true),
+            (ViewNumber::new(3), "third", false),
+        ]
+    );
+    assert!(pipeline.is_empty());
+}
+
+#[test]
+fn messages_are_held_until_their_parent_is_committed() {
+    let mut pipeline = Pipeline::default();
+    assert!(pipeline.start(ViewNumber::new(1), 1, "first"));
+    assert!(pipeline.is_applying(&1));
+    assert!(!pipeline.is_applying(&2));
+    assert!(pipeline.hold(1, "child of the first"));
+
+    assert_eq!(
+        pipeline.finish(ViewNumber::new(1), true),
+        vec![CommittedApplication {
+            view: ViewNumber::new(1),
+            job: "first",
+            outcome: true,
+            released: vec!["child of the first"],
+        }]
+    );
+    assert!(!pipeline.is_applying(&1));
+}
+
+#[test]
+fn outcomes_of_unknown_views_are_ignored() {
+    let mut pipeline = Pipeline::default();
+    assert!(pipeline.finish(ViewNumber::new(5), true).is_empty());
+    assert!(pipeline.is_empty());
+}
+
+#[test]
+fn lost_applications_are_pruned() {
+    let mut pipeline = Pipeline::default();
+    assert!(pipeline.start(ViewNumber::new(1), 1, "lost"));
+    assert!(pipeline.start(ViewNumber::new(2), 2, "second"));
+    assert!(pipeline.start(ViewNumber::new(3), 3, "third"));
+    assert!(pipeline.hold(1, "child of the lost"));
+    assert!(pipeline.finish(ViewNumber::new(2), true).is_empty());
+
+    // the second one no longer waits for the first one, the third one is still running
+    let committed = pipeline.prune(ViewNumber::new(3));
+    assert_eq!(
+        committed,
+        vec![CommittedApplication {
+            view: ViewNumber::new(2),
+            job: "second",
+            outcome: true,
+            released: vec![],
+        }]
+    );
+    assert!(!pipeline.is_applying(&1));
+    assert!(pipeline.is_applying(&3));
+    assert_eq!(pipeline.held(), 0);
+
+    // nor is the outcome of the first one committed if it arrives after all
+    assert!(pipeline.finish(ViewNumber::new(1), true).is_empty());
+    assert_eq!(pipeline.len(), 1);
+}
+
+#[test]
+fn held_messages_are_bounded() {
+    let mut pipeline = Pipeline::default();
+    assert!(pipeline.start(ViewNumber::new(1), 1, "first"));
+    for _ in 0..MAX_HELD_MESSAGES {
+        assert!(pipeline.hold(1, "child of the first"));
+    }
+    assert!(!pipeline.hold(1, "one too many"));
+    assert_eq!(pipeline.held(), MAX_HELD_MESSAGES);
+
+    let committed = pipeline.finish(ViewNumber::new(1), true);
+    assert_eq!(committed[0].released.len(), MAX_HELD_MESSAGES);
+    assert_eq!(pipeline.held(), 0);
+}
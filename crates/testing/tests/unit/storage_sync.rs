@@ -0,0 +1,160 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use hotshot_types::storage_sync::{CommitAction, GroupCommit, StorageSyncConfig, WriteAheadLog};
+
+/// batches of up to 3 views, committed 100ms after their first write at the latest
+fn batched() -> StorageSyncConfig {
+    StorageSyncConfig {
+        max_views_per_commit: 3,
+        max_commit_delay_ms: 100,
+        wal_directory: None,
+    }
+}
+
+/// a write-ahead log file of its own for test `name`
+fn wal_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir()
+        .join(format!("hotshot-wal-{}", std::process::id()))
+        .join(format!("{name}.wal"));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn every_write_is_committed_by_default() {
+    let mut batch = GroupCommit::new(&StorageSyncConfig::default());
+    let now = Instant::now();
+    assert!(!batch.is_pending());
+    assert_eq!(batch.record(1, now), CommitAction::Commit);
+    batch.committed();
+    // even writes counting no view of their own
+    assert_eq!(batch.record(0, now), CommitAction::Commit);
+}
+
+#[test]
+fn batches_are_committed_after_enough_views_or_time() {
+    let mut batch = GroupCommit::new(&batched());
+    let start = Instant::now();
+    assert_eq!(
+        batch.record(1, start),
+        CommitAction::CommitAfter(Duration::from_millis(100))
+    );
+    assert_eq!(batch.record(0, start), CommitAction::Wait);
+    assert_eq!(batch.record(1, start), CommitAction::Wait);
+    assert_eq!(batch.record(1, start), CommitAction::Commit);
+    let first = batch.batch();
+    batch.committed();
+    assert_ne!(batch.batch(), first);
+    assert!(!batch.is_pending());
+
+    // the delay runs from the first write of the batch
+    assert!(matches!(
+        batch.record(1, start),
+        CommitAction::CommitAfter(_)
+    ));
+    assert!(!batch.is_due(start + Duration::from_millis(99)));
+    assert!(batch.is_due(start + Duration::from_millis(100)));
+    assert_eq!(
+        batch.record(1, start + Duration::from_millis(150)),
+        CommitAction::Commit
+    );
+}
+
+#[test]
+fn write_ahead_logs_keep_their_records_until_cleared() {
+    let path = wal_path("records");
+    assert!(WriteAheadLog::read(&path)
+        .expect("a missing log reads as empty")
+        .is_empty());
+
+    let wal = WriteAheadLog::open(&path).expect("cannot open the log");
+    // records appended together are synced together
+    wal.append(&[b"first".as_slice(), b""])
+        .expect("cannot append");
+    wal.append(&[b"third"]).expect("cannot append");
+    let records = WriteAheadLog::read(&path).expect("cannot read the log");
+    assert_eq!(
+        records,
+        vec![b"first".to_vec(), Vec::new(), b"third".to_vec()]
+    );
+
+    wal.clear().expect("cannot clear the log");
+    assert!(WriteAheadLog::read(&path)
+        .expect("cannot read the log")
+        .is_empty());
+    wal.append(&[b"again"]).expect("cannot append");
+    assert_eq!(
+        WriteAheadLog::read(&path).expect("cannot read the log"),
+        vec![b"again".to_vec()]
+    );
+}
+
+#[test]
+fn records_cut_short_are_dropped() {
+    let path = wal_path("torn");
+    let wal = WriteAheadLog::open(&path).expect("cannot open the log");
+    wal.append(&[b"whole"]).expect("cannot append");
+    // a crash in the middle of appending the next record
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .expect("cannot open the log file");
+    file.write_all(&10u64.to_le_bytes())
+        .expect("cannot write the length");
+    file.write_all(b"half").expect("cannot write the record");
+    assert_eq!(
+        WriteAheadLog::read(&path).expect("cannot read the log"),
+        vec![b"whole".to_vec()]
+    );
+}
+
+#[test]
+fn the_log_ends_at_the_first_corrupt_record() {
+    let path = wal_path("corrupt");
+    let wal = WriteAheadLog::open(&path).expect("cannot open the log");
+    wal.append(&[b"whole"]).expect("cannot append");
+    // the file grew before a crash, but the record never made it into the new extent
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .expect("cannot open the log file");
+    file.write_all(&[0u8; 64]).expect("cannot grow the log");
+    assert_eq!(
+        WriteAheadLog::read(&path).expect("cannot read the log"),
+        vec![b"whole".to_vec()]
+    );
+
+    // a record whose contents were damaged ends the log, with everything after it
+    wal.clear().expect("cannot clear the log");
+    wal.append(&[b"first".as_slice(), b"second", b"third"])
+        .expect("cannot append");
+    let mut bytes = std::fs::read(&path).expect("cannot read the log file");
+    let second = bytes
+        .windows(6)
+        .position(|window| window == b"second")
+        .expect("the record is in the log");
+    bytes[second] ^= 0xff;
+    std::fs::write(&path, bytes).expect("cannot write the log file");
+    assert_eq!(
+        WriteAheadLog::read(&path).expect("cannot read the log"),
+        vec![b"first".to_vec()]
+    );
+}
+
+#[test]
+fn nodes_have_logs_of_their_own() {
+    let config = StorageSyncConfig {
+        wal_directory: Some(PathBuf::from("wal")),
+        ..batched()
+    };
+    assert_eq!(
+        config.wal_path(3),
+        Some(PathBuf::from("wal").join("hotshot-3.wal"))
+    );
+    assert_eq!(batched().wal_path(3), None);
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use hotshot_types::view_latency::{ViewLatencyEstimator, VIEWS_TO_DECIDE};
+
+#[test]
+fn average_follows_recent_views() {
+    let mut estimator = ViewLatencyEstimator::new(0.5, 4);
+    assert_eq!(estimator.average(), None);
+    assert_eq!(estimator.estimated_finality_latency(), None);
+
+    estimator.record(Duration::from_secs(1));
+    assert_eq!(estimator.average(), Some(Duration::from_secs(1)));
+    estimator.record(Duration::from_secs(3));
+    assert_eq!(estimator.average(), Some(Duration::from_secs(2)));
+    assert_eq!(
+        estimator.estimated_finality_latency(),
+        Some(Duration::from_secs(2) * VIEWS_TO_DECIDE)
+    );
+}
+
+#[test]
+fn percentiles_cover_the_window() {
+    let mut estimator = ViewLatencyEstimator::new(0.2, 4);
+    assert_eq!(estimator.percentile(50.0), None);
+    for millis in [1000, 40, 10, 30, 20] {
+        estimator.record(Duration::from_millis(millis));
+    }
+    // the oldest duration left the window
+    assert_eq!(estimator.percentile(0.0), Some(Duration::from_millis(10)));
+    assert_eq!(estimator.percentile(50.0), Some(Duration::from_millis(20)));
+    assert_eq!(estimator.percentile(75.0), Some(Duration::from_millis(30)));
+    assert_eq!(estimator.percentile(100.0), Some(Duration::from_millis(40)));
+}
@@ -0,0 +1,45 @@
+use hotshot_example_types::block_types::{TestBlockPayload, TestTransaction};
+use hotshot_types::traits::{block_contents::ViewWindow, BlockPayload};
+
+#[test]
+fn windows_bound_inclusion() {
+    let unbounded = ViewWindow::default();
+    assert!(unbounded.includes(0));
+    assert!(!unbounded.has_expired(u64::MAX));
+
+    let scheduled = ViewWindow::starting_at(5);
+    assert!(!scheduled.includes(4));
+    assert!(scheduled.includes(5));
+
+    let expiring = ViewWindow::expiring_at(5);
+    assert!(expiring.includes(5));
+    assert!(!expiring.has_expired(5));
+    assert!(expiring.has_expired(6));
+    assert!(!expiring.includes(6));
+
+    let window = ViewWindow {
+        earliest: Some(3),
+        expiry: Some(4),
+    };
+    assert_eq!(
+        (0..7)
+            .filter(|view| window.includes(*view))
+            .collect::<Vec<_>>(),
+        vec![3, 4]
+    );
+}
+
+#[test]
+fn windows_survive_block_encoding() {
+    let transactions = vec![
+        TestTransaction::new(vec![1, 2, 3]),
+        TestTransaction::new(vec![4]).with_window(ViewWindow::starting_at(7)),
+        TestTransaction::new(vec![]).with_window(ViewWindow {
+            earliest: Some(2),
+            expiry: Some(9),
+        }),
+    ];
+    let (payload, metadata) = TestBlockPayload::from_transactions(transactions.clone()).unwrap();
+    let decoded = TestBlockPayload::from_bytes(payload.encode().unwrap(), &metadata);
+    assert_eq!(decoded.transactions, transactions);
+}
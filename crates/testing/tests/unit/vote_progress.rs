@@ -0,0 +1,30 @@
+use ethereum_types::U256;
+use hotshot_types::vote::VoteProgress;
+
+/// `collected_weight` of the way to `threshold`
+fn progress(collected_weight: u64, threshold: u64) -> VoteProgress {
+    VoteProgress {
+        collected_weight: U256::from(collected_weight),
+        threshold: U256::from(threshold),
+    }
+}
+
+#[test]
+fn steps_count_the_whole_steps_reached() {
+    assert_eq!(progress(0, 7).step(4), 0);
+    assert_eq!(progress(1, 7).step(4), 0);
+    assert_eq!(progress(2, 7).step(4), 1);
+    assert_eq!(progress(4, 7).step(4), 2);
+    assert_eq!(progress(6, 7).step(4), 3);
+    assert_eq!(progress(7, 7).step(4), 4);
+}
+
+#[test]
+fn steps_stop_at_the_threshold() {
+    // late votes, and stake tables weighing more than the threshold
+    assert_eq!(progress(10, 7).step(4), 4);
+    assert_eq!(progress(u64::MAX, 1).step(u64::MAX), u64::MAX);
+    // nothing is needed for a certificate of no weight
+    assert_eq!(progress(0, 0).step(4), 4);
+    assert_eq!(progress(3, 7).step(0), 0);
+}
@@ -83,6 +83,69 @@ async fn test_memory_network_sync() {
         .await;
 }
 
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn test_memory_network_latency_matrix() {
+    use hotshot_example_types::node_types::{MemoryImpl, TestTypes};
+    use hotshot_testing::{
+        completion_task::{CompletionTaskDescription, TimeBasedCompletionTaskDescription},
+        test_builder::TestMetadata,
+    };
+    use hotshot_types::traits::network::LatencyMatrix;
+    use std::time::Duration;
+
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+    let latency_matrix = LatencyMatrix::from_toml(
+        r#"
+        regions = ["us-east", "eu-west", "ap-south"]
+
+        [local]
+        delay_low_ms = 1
+        delay_high_ms = 3
+
+        [[links]]
+        from = "us-east"
+        to = "eu-west"
+        delay_low_ms = 35
+        delay_high_ms = 45
+        bandwidth_bytes_per_sec = 12500000
+
+        [[links]]
+        from = "us-east"
+        to = "ap-south"
+        delay_low_ms = 90
+        delay_high_ms = 110
+
+        [[links]]
+        from = "eu-west"
+        to = "ap-south"
+        delay_low_ms = 60
+        delay_high_ms = 70
+        "#,
+    )
+    .unwrap();
+    let metadata = TestMetadata {
+        // allow more time to pass in CI
+        completion_task_description: CompletionTaskDescription::TimeBasedCompletionTaskBuilder(
+            TimeBasedCompletionTaskDescription {
+                duration: Duration::from_secs(240),
+            },
+        ),
+        unreliable_network: Some(Box::new(latency_matrix)),
+        ..TestMetadata::default()
+    };
+    metadata
+        .gen_launcher::<TestTypes, MemoryImpl>(0)
+        .launch()
+        .run_test()
+        .await;
+}
+
 #[cfg_attr(
     async_executor_impl = "tokio",
     tokio::test(flavor = "multi_thread", worker_threads = 2)
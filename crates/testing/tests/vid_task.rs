@@ -1,6 +1,6 @@
-use hotshot::{types::SignatureKey, HotShotConsensusApi};
+use hotshot::HotShotConsensusApi;
 use hotshot_example_types::{
-    block_types::TestTransaction,
+    block_types::{TestBlockPayload, TestTransaction},
     node_types::{MemoryImpl, TestTypes},
 };
 use hotshot_task_impls::{events::HotShotEvent, vid::VIDTaskState};
@@ -8,7 +8,8 @@ use hotshot_testing::task_helpers::{build_system_handle, vid_init};
 use hotshot_types::traits::node_implementation::{ConsensusTime, NodeType};
 use hotshot_types::{
     data::{DAProposal, VidDisperse, VidSchemeTrait, ViewNumber},
-    traits::consensus_api::ConsensusApi,
+    signing::{self, SigningDomain},
+    traits::{consensus_api::ConsensusApi, BlockPayload},
 };
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -36,16 +37,23 @@ async fn test_vid_task() {
     let quorum_membership = handle.hotshot.inner.memberships.quorum_membership.clone();
 
     let vid = vid_init::<TestTypes>(&quorum_membership, ViewNumber::new(0));
-    let transactions = vec![TestTransaction(vec![0])];
+    let transactions = vec![TestTransaction::new(vec![0])];
     let encoded_transactions = TestTransaction::encode(transactions.clone()).unwrap();
     let vid_disperse = vid.disperse(&encoded_transactions).unwrap();
     let payload_commitment = vid_disperse.commit;
+    let transactions_root = TestBlockPayload::from_transactions(transactions.clone())
+        .unwrap()
+        .0
+        .transactions_root(&());
 
-    let signature =
-        <TestTypes as NodeType>::SignatureKey::sign(api.private_key(), payload_commitment.as_ref())
-            .expect("Failed to sign block payload!");
+    let signature = signing::sign::<<TestTypes as NodeType>::SignatureKey>(
+        api.private_key(),
+        SigningDomain::VidDisperse,
+        payload_commitment.as_ref(),
+    )
+    .expect("Failed to sign block payload!");
     let proposal: DAProposal<TestTypes> = DAProposal {
-        encoded_transactions: encoded_transactions.clone(),
+        encoded_transactions: encoded_transactions.clone().into(),
         metadata: (),
         view_number: ViewNumber::new(2),
     };
@@ -74,7 +82,7 @@ async fn test_vid_task() {
     input.push(HotShotEvent::ViewChange(ViewNumber::new(1)));
     input.push(HotShotEvent::ViewChange(ViewNumber::new(2)));
     input.push(HotShotEvent::TransactionsSequenced(
-        encoded_transactions.clone(),
+        encoded_transactions.clone().into(),
         (),
         ViewNumber::new(2),
     ));
@@ -92,7 +100,12 @@ async fn test_vid_task() {
     );
 
     output.insert(
-        HotShotEvent::SendPayloadCommitmentAndMetadata(payload_commitment, (), ViewNumber::new(2)),
+        HotShotEvent::SendPayloadCommitmentAndMetadata(
+            payload_commitment,
+            transactions_root,
+            (),
+            ViewNumber::new(2),
+        ),
         1,
     );
     output.insert(
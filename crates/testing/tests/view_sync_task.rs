@@ -11,6 +11,7 @@ use std::collections::HashMap;
 )]
 #[cfg_attr(async_executor_impl = "async-std", async_std::test)]
 async fn test_view_sync_task() {
+    use hotshot_task_impls::bus::EventBus;
     use hotshot_task_impls::harness::run_harness;
     use hotshot_task_impls::view_sync::ViewSyncTaskState;
     use hotshot_testing::task_helpers::build_system_handle;
@@ -66,6 +67,8 @@ async fn test_view_sync_task() {
         commit_relay_map: HashMap::default().into(),
         finalize_relay_map: HashMap::default().into(),
         view_sync_timeout: Duration::new(10, 0),
+        clock: handle.hotshot.clock(),
+        event_bus: EventBus::default(),
         id: handle.hotshot.inner.id,
         last_garbage_collected_view: ViewNumber::new(0),
     };
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use async_broadcast::broadcast;
+use commit::Committable;
+use either::Either::Left;
+use hotshot::HotShotConsensusApi;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes},
+    state_types::TestInstanceState,
+};
+use hotshot_task_impls::{
+    events::{HotShotEvent, HotShotTaskCompleted},
+    vote::{handle_vote, AccumulatorInfo},
+};
+use hotshot_testing::task_helpers::{build_system_handle, key_pair_for_id};
+use hotshot_types::{
+    data::{Leaf, ViewNumber},
+    simple_vote::{QuorumData, QuorumVote},
+    traits::{consensus_api::ConsensusApi, node_implementation::ConsensusTime},
+};
+
+/// A batch of votes with a forged signature in it is verified again vote by vote: the valid
+/// votes still count towards the certificate, and the real vote of the forged voter still forms
+/// it
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn batch_with_a_forged_vote_falls_back_and_forms_the_certificate() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // node 2 leads view 2, and collects the quorum votes of view 1
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let info = AccumulatorInfo {
+        public_key: *api.public_key(),
+        membership: Arc::new(api.inner.memberships.quorum_membership.clone()),
+        view: ViewNumber::new(1),
+        id: 2,
+    };
+    let (tx, mut rx) = broadcast(16);
+    let data = QuorumData {
+        leaf_commit: Leaf::<TestTypes>::genesis(&TestInstanceState {}).commit(),
+    };
+    let vote_of = |voter| {
+        let (private_key, public_key) = key_pair_for_id(voter);
+        QuorumVote::<TestTypes>::create_signed_vote(
+            data.clone(),
+            ViewNumber::new(1),
+            &public_key,
+            &private_key,
+        )
+        .unwrap()
+    };
+
+    // the vote of node 4 carries the signature of node 5
+    let mut forged = vote_of(4);
+    forged.signature.1 = vote_of(5).signature.1;
+
+    let mut collector = None;
+    // the seventh vote meets the threshold of seven, but only if the forged one counts
+    for vote in [0, 1, 3, 5, 6, 7].into_iter().map(vote_of).chain([forged]) {
+        let result = handle_vote(
+            &mut collector,
+            &vote,
+            HotShotEvent::QuorumVoteRecv(vote.clone()),
+            &info,
+            &api,
+            &tx,
+        )
+        .await;
+        assert_eq!(result, None);
+    }
+    assert!(
+        rx.try_recv().is_err(),
+        "a certificate formed without seven valid votes"
+    );
+
+    // the six valid votes were kept, so the real vote of node 4 forms the certificate
+    let vote = vote_of(4);
+    let result = handle_vote(
+        &mut collector,
+        &vote,
+        HotShotEvent::QuorumVoteRecv(vote.clone()),
+        &info,
+        &api,
+        &tx,
+    )
+    .await;
+    assert_eq!(result, Some(HotShotTaskCompleted));
+    let Ok(HotShotEvent::QCFormed(Left(certificate))) = rx.try_recv() else {
+        panic!("No quorum certificate was formed");
+    };
+    assert_eq!(certificate.view_number, ViewNumber::new(1));
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use async_broadcast::broadcast;
+use commit::Committable;
+use either::Either::Left;
+use hotshot::HotShotConsensusApi;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes},
+    state_types::TestInstanceState,
+};
+use hotshot_task_impls::{
+    events::{HotShotEvent, HotShotTaskCompleted},
+    vote::{handle_vote, AccumulatorInfo, VoteCollectionTaskState},
+};
+use hotshot_testing::task_helpers::{build_system_handle, key_pair_for_id};
+use hotshot_types::{
+    data::{Leaf, ViewNumber},
+    simple_certificate::QuorumCertificate,
+    simple_vote::{QuorumData, QuorumVote},
+    traits::{consensus_api::ConsensusApi, node_implementation::ConsensusTime},
+};
+
+/// The collector of the quorum votes node 2 gathers
+type Collector =
+    Option<VoteCollectionTaskState<TestTypes, QuorumVote<TestTypes>, QuorumCertificate<TestTypes>>>;
+
+/// The quorum vote of `voter` for the genesis leaf in `view`
+fn vote_of(voter: u64, view: u64) -> QuorumVote<TestTypes> {
+    let (private_key, public_key) = key_pair_for_id(voter);
+    QuorumVote::<TestTypes>::create_signed_vote(
+        QuorumData {
+            leaf_commit: Leaf::<TestTypes>::genesis(&TestInstanceState {}).commit(),
+        },
+        ViewNumber::new(view),
+        &public_key,
+        &private_key,
+    )
+    .unwrap()
+}
+
+/// Feed the votes of `voters` in `view` to `collector` as node 2, returning what the last one
+/// did
+async fn collect(
+    collector: &mut Collector,
+    voters: impl IntoIterator<Item = u64>,
+    view: u64,
+    api: &HotShotConsensusApi<TestTypes, MemoryImpl>,
+    tx: &async_broadcast::Sender<HotShotEvent<TestTypes>>,
+) -> Option<HotShotTaskCompleted> {
+    let info = AccumulatorInfo {
+        public_key: *api.public_key(),
+        membership: Arc::new(api.inner.memberships.quorum_membership.clone()),
+        view: ViewNumber::new(view),
+        id: 2,
+    };
+    let mut result = None;
+    for voter in voters {
+        let vote = vote_of(voter, view);
+        result = handle_vote(
+            collector,
+            &vote,
+            HotShotEvent::QuorumVoteRecv(vote.clone()),
+            &info,
+            api,
+            tx,
+        )
+        .await;
+    }
+    result
+}
+
+/// Once its certificate is formed, a collector ignores the late votes of its view, and is
+/// re-armed for the next view it leads rather than replaced, forming the certificate of that
+/// view too
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn completed_collectors_ignore_late_votes_and_are_rearmed() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // of the 10 nodes, node 2 leads views 2 and 12, and collects the votes of views 1 and 11
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let (tx, mut rx) = broadcast(16);
+    let mut collector: Collector = None;
+
+    assert_eq!(
+        collect(&mut collector, 0..7, 1, &api, &tx).await,
+        Some(HotShotTaskCompleted)
+    );
+    let Ok(HotShotEvent::QCFormed(Left(certificate))) = rx.try_recv() else {
+        panic!("No quorum certificate was formed for view 1");
+    };
+    assert_eq!(certificate.view_number, ViewNumber::new(1));
+
+    // the votes that come after the certificate change nothing
+    assert_eq!(collect(&mut collector, 7..10, 1, &api, &tx).await, None);
+    assert!(rx.try_recv().is_err(), "a late vote formed a certificate");
+    let state = collector.as_ref().expect("the collector is kept");
+    assert!(state.completed);
+    assert!(state.pending.is_empty() && state.unpersisted.is_empty());
+
+    // a vote of view 11 re-arms the same collector, which starts the view over
+    assert_eq!(collect(&mut collector, [0], 11, &api, &tx).await, None);
+    let state = collector.as_ref().expect("the collector is kept");
+    assert_eq!(state.view, ViewNumber::new(11));
+    assert!(!state.completed);
+
+    assert_eq!(
+        collect(&mut collector, 1..7, 11, &api, &tx).await,
+        Some(HotShotTaskCompleted)
+    );
+    let Ok(HotShotEvent::QCFormed(Left(certificate))) = rx.try_recv() else {
+        panic!("No quorum certificate was formed for view 11");
+    };
+    assert_eq!(certificate.view_number, ViewNumber::new(11));
+
+    // votes of an older view do not take the collector back
+    assert_eq!(collect(&mut collector, [9], 1, &api, &tx).await, None);
+    assert_eq!(
+        collector.as_ref().map(|state| state.view),
+        Some(ViewNumber::new(11))
+    );
+    assert!(rx.try_recv().is_err());
+}
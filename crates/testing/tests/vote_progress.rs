@@ -0,0 +1,105 @@
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use async_broadcast::broadcast;
+use async_compatibility_layer::art::async_timeout;
+use commit::Committable;
+use hotshot::HotShotConsensusApi;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes},
+    state_types::TestInstanceState,
+};
+use hotshot_task_impls::{
+    events::HotShotEvent,
+    vote::{handle_vote, AccumulatorInfo},
+};
+use hotshot_testing::task_helpers::{key_pair_for_id, system_context_builder_with_config};
+use hotshot_types::{
+    data::{Leaf, ViewNumber},
+    event::EventType,
+    simple_vote::{QuorumData, QuorumVote},
+    traits::{
+        consensus_api::ConsensusApi, election::Membership, node_implementation::ConsensusTime,
+    },
+};
+
+/// The vote progress events of a leader collecting votes name the voters so far and the members
+/// of the committee yet to vote
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn vote_progress_events_name_the_signers_and_missing_voters() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    // node 2 leads view 2, and collects the quorum votes of view 1
+    let handle = system_context_builder_with_config(2, |config| {
+        config.vote_progress_steps = Some(10);
+    })
+    .build()
+    .await
+    .expect("Could not init hotshot")
+    .0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let membership = Arc::new(api.inner.memberships.quorum_membership.clone());
+    let info = AccumulatorInfo {
+        public_key: *api.public_key(),
+        membership: membership.clone(),
+        view: ViewNumber::new(1),
+        id: 2,
+    };
+    let committee = membership.get_committee(ViewNumber::new(1));
+    let mut events = handle.get_event_stream_known_impl();
+    let (tx, _rx) = broadcast(16);
+    let data = QuorumData {
+        leaf_commit: Leaf::<TestTypes>::genesis(&TestInstanceState {}).commit(),
+    };
+
+    let mut collector = None;
+    let mut signers = BTreeSet::new();
+    for voter in [1, 3] {
+        let (private_key, public_key) = key_pair_for_id(voter);
+        let vote = QuorumVote::create_signed_vote(
+            data.clone(),
+            ViewNumber::new(1),
+            &public_key,
+            &private_key,
+        )
+        .unwrap();
+        handle_vote(
+            &mut collector,
+            &vote,
+            HotShotEvent::QuorumVoteRecv(vote.clone()),
+            &info,
+            &api,
+            &tx,
+        )
+        .await;
+        signers.insert(public_key);
+
+        // every vote is another step of the ten to the threshold of seven votes
+        let (reported_signers, missing) = async_timeout(Duration::from_secs(2), async {
+            loop {
+                let event = events.recv().await.expect("The event stream closed");
+                if let EventType::VoteProgress {
+                    signers, missing, ..
+                } = event.event
+                {
+                    return (signers, missing);
+                }
+            }
+        })
+        .await
+        .expect("No vote progress was reported");
+        assert_eq!(
+            reported_signers.into_iter().collect::<BTreeSet<_>>(),
+            signers
+        );
+        let missing: BTreeSet<_> = missing.into_iter().collect();
+        assert_eq!(missing, committee.difference(&signers).copied().collect());
+    }
+}
@@ -0,0 +1,81 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use async_broadcast::broadcast;
+use commit::Committable;
+use hotshot::HotShotConsensusApi;
+use hotshot_example_types::{
+    node_types::{MemoryImpl, TestTypes},
+    state_types::TestInstanceState,
+};
+use hotshot_task_impls::{events::HotShotEvent, watchdog::WatchdogTaskState};
+use hotshot_testing::task_helpers::{build_system_handle, key_pair_for_id};
+use hotshot_types::{
+    data::{Leaf, ViewNumber},
+    simple_vote::{QuorumData, QuorumVote},
+    stall::StallTracker,
+    traits::{
+        clock::SystemClock, consensus_api::ConsensusApi, election::Membership,
+        node_implementation::ConsensusTime,
+    },
+};
+
+#[cfg(test)]
+#[cfg_attr(
+    async_executor_impl = "tokio",
+    tokio::test(flavor = "multi_thread", worker_threads = 2)
+)]
+#[cfg_attr(async_executor_impl = "async-std", async_std::test)]
+async fn only_votes_signed_by_their_voter_count_towards_the_peers_heard_from() {
+    async_compatibility_layer::logging::setup_logging();
+    async_compatibility_layer::logging::setup_backtrace();
+
+    let handle = build_system_handle(2).await.0;
+    let api: HotShotConsensusApi<TestTypes, MemoryImpl> = HotShotConsensusApi {
+        inner: handle.hotshot.inner.clone(),
+    };
+    let quorum_membership = Arc::new(api.inner.memberships.quorum_membership.clone());
+    let mut state = WatchdogTaskState {
+        api: api.clone(),
+        clock: Arc::new(SystemClock),
+        stall_timeout: std::time::Duration::from_secs(60),
+        quorum_membership: quorum_membership.clone(),
+        da_membership: Arc::new(api.inner.memberships.da_membership.clone()),
+        tracker: StallTracker::new(ViewNumber::genesis(), std::time::Instant::now()),
+        timer_task: None,
+        public_key: *api.public_key(),
+        id: handle.hotshot.inner.id,
+        _pd: PhantomData,
+    };
+    let (tx, _rx) = broadcast(16);
+
+    let data = QuorumData {
+        leaf_commit: Leaf::<TestTypes>::genesis(&TestInstanceState {}).commit(),
+    };
+    let (private_key, public_key) = key_pair_for_id(1);
+    let signed =
+        QuorumVote::create_signed_vote(data.clone(), ViewNumber::new(1), &public_key, &private_key)
+            .unwrap();
+    // node 3 claims the vote, but it is signed by node 1
+    let forged = QuorumVote::create_signed_vote(
+        data,
+        ViewNumber::new(1),
+        &key_pair_for_id(3).1,
+        &private_key,
+    )
+    .unwrap();
+    state
+        .handle(HotShotEvent::QuorumVoteRecv(signed), tx.clone())
+        .await;
+    state.handle(HotShotEvent::QuorumVoteRecv(forged), tx).await;
+
+    let diagnostics = state.tracker.diagnostics(
+        std::time::Instant::now(),
+        api.public_key(),
+        quorum_membership.get_leader(ViewNumber::new(1)),
+        &quorum_membership.get_committee(ViewNumber::genesis()),
+        quorum_membership.success_threshold().get(),
+    );
+    assert_eq!(diagnostics.votes.quorum, 1);
+    assert_eq!(diagnostics.peers.heard_from, 1);
+    assert!(diagnostics.peers.silent.contains(&key_pair_for_id(3).1));
+}
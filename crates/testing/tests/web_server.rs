@@ -46,3 +46,50 @@ async fn web_server_network() {
         .await;
     shutdown_logging();
 }
+
+/// Keys are spread over the shards, and adding a shard only moves keys onto it
+#[test]
+fn web_server_shard_ring() {
+    use hotshot::traits::implementations::ShardRing;
+    use hotshot_types::{signature_key::BLSPubKey, traits::signature_key::SignatureKey};
+
+    let keys: Vec<_> = (0..200)
+        .map(|i| BLSPubKey::generated_from_seed_indexed([0u8; 32], i).0)
+        .collect();
+    let four = ShardRing::new(4);
+    let five = ShardRing::new(5);
+
+    let mut per_shard = [0; 4];
+    for key in &keys {
+        per_shard[four.shard_of(key)] += 1;
+        assert_eq!(four.shard_of(key), ShardRing::new(4).shard_of(key));
+        let moved_to = five.shard_of(key);
+        assert!(moved_to == four.shard_of(key) || moved_to == 4);
+    }
+    assert!(per_shard.iter().all(|count| *count > 0));
+    assert!(keys.iter().all(|key| ShardRing::new(1).shard_of(key) == 0));
+}
+
+/// Each view's broadcasts go to a single shard, and transactions to the home shard
+#[test]
+fn web_server_broadcasts_go_to_the_shard_of_their_view() {
+    use hotshot::traits::implementations::ShardRing;
+    use hotshot_types::message::MessagePurpose;
+
+    let ring = ShardRing::new(4);
+    let mut per_shard = [0; 4];
+    for view in 0..200 {
+        let shard = ring.shard_of_broadcast(MessagePurpose::Proposal, view);
+        per_shard[shard] += 1;
+        for purpose in [
+            MessagePurpose::DAC,
+            MessagePurpose::VidDisperse,
+            MessagePurpose::ViewSyncCertificate,
+            MessagePurpose::Upgrade,
+        ] {
+            assert_eq!(ring.shard_of_broadcast(purpose, view), shard);
+        }
+        assert_eq!(ring.shard_of_broadcast(MessagePurpose::Data, view), 0);
+    }
+    assert!(per_shard.iter().all(|count| *count > 0));
+}
@@ -0,0 +1,137 @@
+//! Announcements of the latest anchor, the newest decided leaf
+//!
+//! Nodes joining a running network, observers in particular, do not know how far consensus has
+//! got. The leader of a view in which the anchor passes a multiple of
+//! [`ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS`] gossips an [`AnchorAnnouncement`]: the anchor leaf with
+//! its quorum certificate. Networks keep the compact [`AnchorHint`] of the latest announcement
+//! whose certificate verifies, see [`ConnectedNetwork::latest_anchor_hint`], so that such a node
+//! can target its catchup at the right view right away.
+//!
+//! An announcement is sent by a single node, but a hint is only kept once a quorum certified its
+//! leaf, so no node can make others look for an anchor past the one consensus reached.
+//!
+//! [`ConnectedNetwork::latest_anchor_hint`]: crate::traits::network::ConnectedNetwork::latest_anchor_hint
+
+use std::sync::{Arc, RwLock};
+
+use commit::Committable;
+use hotshot_constants::ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::Leaf,
+    simple_certificate::QuorumCertificate,
+    traits::{
+        election::Membership,
+        node_implementation::{ConsensusTime, NodeType},
+    },
+    vote::Certificate,
+};
+
+/// The anchor a node announced: what it last decided
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnchorHint {
+    /// the view of the anchor
+    pub view: u64,
+    /// the commitment of the anchor leaf
+    pub leaf_commitment: String,
+    /// the hash of the quorum certificate of the anchor leaf
+    pub qc_hash: [u8; 32],
+}
+
+impl AnchorHint {
+    /// The hint of the anchor `leaf`, certified by `qc`
+    #[must_use]
+    pub fn new<TYPES: NodeType>(leaf: &Leaf<TYPES>, qc: &QuorumCertificate<TYPES>) -> Self {
+        let qc_bytes = bincode::serialize(qc).unwrap_or_default();
+        Self {
+            view: leaf.get_view_number().get_u64(),
+            leaf_commitment: leaf.commit().to_string(),
+            qc_hash: *blake3::hash(&qc_bytes).as_bytes(),
+        }
+    }
+}
+
+/// The anchor a node announced, with the certificate of a quorum which voted for it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct AnchorAnnouncement<TYPES: NodeType> {
+    /// the anchor leaf, without its payload
+    pub leaf: Leaf<TYPES>,
+    /// the quorum certificate of the anchor leaf
+    pub qc: QuorumCertificate<TYPES>,
+}
+
+impl<TYPES: NodeType> AnchorAnnouncement<TYPES> {
+    /// The announcement of the anchor `leaf`, certified by `qc`
+    #[must_use]
+    pub fn new(leaf: &Leaf<TYPES>, qc: &QuorumCertificate<TYPES>) -> Self {
+        let mut leaf = leaf.clone();
+        leaf.block_payload = None;
+        Self {
+            leaf,
+            qc: qc.clone(),
+        }
+    }
+
+    /// The hint of the announced anchor, if a quorum of `membership` certified its leaf
+    #[must_use]
+    pub fn verify<MEMBERSHIP: Membership<TYPES>>(
+        &self,
+        membership: &MEMBERSHIP,
+    ) -> Option<AnchorHint> {
+        // the genesis leaf is never announced, and a certificate without signatures cannot be
+        // checked, nor can the commitment of a leaf whose justify QC has none
+        if self.qc.is_genesis
+            || self.qc.signatures.is_none()
+            || (!self.leaf.justify_qc.is_genesis && self.leaf.justify_qc.signatures.is_none())
+        {
+            return None;
+        }
+        // the signatures cover the vote commitment, which must be that of the leaf announced,
+        // whose commitment covers its view
+        let certifies_leaf = self.qc.vote_commitment == self.qc.data.commit()
+            && self.qc.data.leaf_commit == self.leaf.commit()
+            && self.qc.view_number == self.leaf.view_number;
+        (certifies_leaf && self.qc.is_valid_cert(membership))
+            .then(|| AnchorHint::new(&self.leaf, &self.qc))
+    }
+}
+
+/// Whether the anchor moving from view `old_anchor` to view `new_anchor` passed a multiple of
+/// [`ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS`], so that it is due to be announced
+#[must_use]
+pub fn is_announcement_due(old_anchor: u64, new_anchor: u64) -> bool {
+    old_anchor / ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS
+        < new_anchor / ANCHOR_ANNOUNCEMENT_INTERVAL_VIEWS
+}
+
+/// The announced anchor of the highest view a network received, shared by its clones
+#[derive(Clone, Debug, Default)]
+pub struct LatestAnchor(Arc<RwLock<Option<AnchorHint>>>);
+
+impl LatestAnchor {
+    /// Record the announced anchor `hint`, if it is newer than the latest one. Returns whether it
+    /// was.
+    /// # Panics
+    /// If the lock on the latest anchor is poisoned
+    pub fn record(&self, hint: AnchorHint) -> bool {
+        let mut latest = self.0.write().unwrap();
+        if latest
+            .as_ref()
+            .is_some_and(|latest| latest.view >= hint.view)
+        {
+            return false;
+        }
+        *latest = Some(hint);
+        true
+    }
+
+    /// The announced anchor of the highest view, if any was received
+    /// # Panics
+    /// If the lock on the latest anchor is poisoned
+    #[must_use]
+    pub fn get(&self) -> Option<AnchorHint> {
+        self.0.read().unwrap().clone()
+    }
+}
@@ -0,0 +1,167 @@
+//! Accounting of the bytes exchanged with each peer, by message class
+//!
+//! The network tasks record the serialized size of every message they hand to, or receive from,
+//! the network implementation in use, so the accounting is the same for all of them. Broadcasts
+//! are recorded once, against [`BROADCAST_PEER`], as the implementation decides how many peers
+//! they actually reach. Totals are exported as counters named `<peer>.<class>_sent_bytes` and
+//! `<peer>.<class>_received_bytes` and can be listed with [`BandwidthMeter::usage`].
+//!
+//! Received messages are only recorded once they passed the checks of the network task, against
+//! their sender if it is in the stake table and [`UNSTAKED_PEER`] otherwise. The meter keeps at
+//! most [`MAX_METERED_PEERS`] peers apart, and records any further peer against [`OTHER_PEERS`].
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    message::MessagePurpose,
+    traits::metrics::{Counter, Metrics, NoMetrics},
+};
+
+/// The peer broadcasts are recorded against
+pub const BROADCAST_PEER: &str = "broadcast";
+
+/// The peer messages from senders outside the stake table are recorded against
+pub const UNSTAKED_PEER: &str = "unstaked";
+
+/// The peer the bytes of peers past [`MAX_METERED_PEERS`] are recorded against
+pub const OTHER_PEERS: &str = "other";
+
+/// The most peers a meter keeps totals of, besides [`OTHER_PEERS`]
+pub const MAX_METERED_PEERS: usize = 1024;
+
+/// Whether bytes were sent or received
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// sent to the peer
+    Sent,
+    /// received from the peer
+    Received,
+}
+
+/// The bytes exchanged with a peer in messages of a class
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BandwidthUsage {
+    /// the peer, or [`BROADCAST_PEER`]
+    pub peer: String,
+    /// the class of the messages
+    pub class: String,
+    /// bytes sent to the peer
+    pub sent_bytes: u64,
+    /// bytes received from the peer
+    pub received_bytes: u64,
+}
+
+/// The totals of a peer and class, with the counters exporting them
+struct Totals {
+    /// bytes sent so far
+    sent_bytes: u64,
+    /// bytes received so far
+    received_bytes: u64,
+    /// exports `sent_bytes`
+    sent: Box<dyn Counter>,
+    /// exports `received_bytes`
+    received: Box<dyn Counter>,
+}
+
+/// Counts the bytes exchanged with each peer, by message class
+pub struct BandwidthMeter {
+    /// where the counters of new peers and classes are created
+    metrics: Box<dyn Metrics>,
+    /// the totals so far, by peer then class
+    totals: Mutex<BTreeMap<String, BTreeMap<&'static str, Totals>>>,
+}
+
+impl Debug for BandwidthMeter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BandwidthMeter")
+            .field("usage", &self.usage())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for BandwidthMeter {
+    fn default() -> Self {
+        Self::new(&*NoMetrics::boxed())
+    }
+}
+
+impl BandwidthMeter {
+    /// Create a meter exporting its counters in a `bandwidth` subgroup of `metrics`
+    #[must_use]
+    pub fn new(metrics: &dyn Metrics) -> Self {
+        Self {
+            metrics: metrics.subgroup(String::from("bandwidth")),
+            totals: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record `bytes` exchanged with `peer` in a message of `class`, against [`OTHER_PEERS`] if
+    /// `peer` is new and the meter already keeps [`MAX_METERED_PEERS`] peers
+    /// # Panics
+    /// If the lock on the totals is poisoned
+    pub fn record(&self, peer: &str, class: MessagePurpose, direction: Direction, bytes: usize) {
+        let mut totals = self.totals.lock().unwrap();
+        let peer = if totals.contains_key(peer)
+            || totals.len() - usize::from(totals.contains_key(OTHER_PEERS)) < MAX_METERED_PEERS
+        {
+            peer
+        } else {
+            OTHER_PEERS
+        };
+        let totals = totals
+            .entry(peer.to_string())
+            .or_default()
+            .entry(class.name())
+            .or_insert_with(|| {
+                let group = self.metrics.subgroup(peer.to_string());
+                Totals {
+                    sent_bytes: 0,
+                    received_bytes: 0,
+                    sent: group.create_counter(
+                        format!("{}_sent_bytes", class.name()),
+                        Some(String::from("bytes")),
+                    ),
+                    received: group.create_counter(
+                        format!("{}_received_bytes", class.name()),
+                        Some(String::from("bytes")),
+                    ),
+                }
+            });
+        match direction {
+            Direction::Sent => {
+                totals.sent_bytes += bytes as u64;
+                totals.sent.add(bytes);
+            }
+            Direction::Received => {
+                totals.received_bytes += bytes as u64;
+                totals.received.add(bytes);
+            }
+        }
+    }
+
+    /// The bytes exchanged so far with every peer, by class, ordered by peer then class
+    /// # Panics
+    /// If the lock on the totals is poisoned
+    #[must_use]
+    pub fn usage(&self) -> Vec<BandwidthUsage> {
+        self.totals
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|(peer, classes)| {
+                classes.iter().map(|(class, totals)| BandwidthUsage {
+                    peer: peer.clone(),
+                    class: (*class).to_string(),
+                    sent_bytes: totals.sent_bytes,
+                    received_bytes: totals.received_bytes,
+                })
+            })
+            .collect()
+    }
+}
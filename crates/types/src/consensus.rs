@@ -4,16 +4,25 @@ pub use crate::utils::{View, ViewInner};
 use displaydoc::Display;
 
 use crate::{
-    data::Leaf,
+    bandwidth::BandwidthMeter,
+    data::{Leaf, VidCommitment},
     error::HotShotError,
+    governance::ParameterSchedule,
+    leaf_dag::LeafDag,
+    payload_store::{PayloadStore, StoredPayload},
+    reputation::LeaderScores,
     simple_certificate::{DACertificate, QuorumCertificate},
+    stall::VoteKind,
     traits::{
         metrics::{Counter, Gauge, Histogram, Label, Metrics, NoMetrics},
         node_implementation::NodeType,
     },
     utils::Terminator,
+    view_latency::ViewLatencyEstimator,
 };
+use bytes::Bytes;
 use commit::Commitment;
+use hotshot_constants::MEMPOOL_SHARDS;
 
 use std::{
     collections::{BTreeMap, HashMap},
@@ -21,6 +30,16 @@ use std::{
 };
 use tracing::error;
 
+/// The vote collection tasks, by the kind of votes they collect, and the names of their metrics
+pub const VOTE_TASKS: [(VoteKind, &str); 6] = [
+    (VoteKind::Quorum, "quorum"),
+    (VoteKind::DA, "da"),
+    (VoteKind::Timeout, "timeout"),
+    (VoteKind::ViewSync, "view_sync"),
+    (VoteKind::Upgrade, "upgrade"),
+    (VoteKind::EpochChange, "epoch_change"),
+];
+
 /// A type alias for `HashMap<Commitment<T>, T>`
 type CommitmentMap<T> = HashMap<Commitment<T>, T>;
 
@@ -53,7 +72,10 @@ pub struct Consensus<TYPES: NodeType> {
     /// Saved payloads.
     ///
     /// Encoded transactions for every view if we got a payload for that view.
-    pub saved_payloads: BTreeMap<TYPES::Time, Vec<u8>>,
+    pub saved_payloads: BTreeMap<TYPES::Time, StoredPayload>,
+
+    /// Decides which of the saved payloads are held in memory and which are written to disk
+    pub payload_store: PayloadStore,
 
     /// The `locked_qc` view number
     pub locked_view: TYPES::Time,
@@ -63,6 +85,16 @@ pub struct Consensus<TYPES: NodeType> {
 
     /// A reference to the metrics trait
     pub metrics: Arc<ConsensusMetricsValue>,
+
+    /// How long recent views lasted, maintained by the consensus task
+    pub view_latency: ViewLatencyEstimator,
+
+    /// How the leaders performed as this node observed them, maintained by the consensus task
+    pub leader_scores: LeaderScores<TYPES::SignatureKey>,
+
+    /// The consensus parameters of every epoch, changed by the governance transactions of the
+    /// decided leaves, maintained by the consensus task
+    pub parameter_schedule: ParameterSchedule,
 }
 
 /// Contains several `ConsensusMetrics` that we're interested in from the consensus interfaces
@@ -88,6 +120,61 @@ pub struct ConsensusMetricsValue {
     pub outstanding_transactions_memory_size: Box<dyn Gauge>,
     /// Number of views that timed out
     pub number_of_timeouts: Box<dyn Counter>,
+    /// Number of views whose proposal did not arrive within the proposal timeout
+    pub number_of_missing_proposals: Box<dyn Counter>,
+    /// Number of VID shares sampled from the DA committee which were not returned, or were invalid
+    pub availability_samples_failed: Box<dyn Counter>,
+    /// Number of certified payloads whose availability sampling failed
+    pub availability_alarms: Box<dyn Counter>,
+    /// Number of received messages dropped because they carried another chain id
+    pub chain_id_mismatches: Box<dyn Counter>,
+    /// Number of received proposals dropped because their view had passed
+    pub stale_proposals_dropped: Box<dyn Counter>,
+    /// Number of received votes dropped because their view had passed
+    pub stale_votes_dropped: Box<dyn Counter>,
+    /// Number of received view sync votes and certificates dropped because their view had passed
+    pub stale_view_sync_messages_dropped: Box<dyn Counter>,
+    /// Number of received DA certificates dropped because their view had passed
+    pub stale_certificates_dropped: Box<dyn Counter>,
+    /// Number of received VID dispersals dropped because their view had passed
+    pub stale_vid_disperses_dropped: Box<dyn Counter>,
+    /// Number of received upgrade proposals and votes dropped because their view had passed
+    pub stale_upgrade_messages_dropped: Box<dyn Counter>,
+    /// Number of transactions rejected by the transaction validator
+    pub transactions_rejected: Box<dyn Counter>,
+    /// Number of transactions dropped from the mempool because they expired before inclusion
+    pub transactions_expired: Box<dyn Counter>,
+    /// Number of times a vote collector was re-armed for a new view instead of being recreated
+    pub vote_collectors_rearmed: Box<dyn Counter>,
+    /// Number of messages held until the node reaches their view
+    pub future_view_messages_buffered: Box<dyn Gauge>,
+    /// Number of held messages released once the node reached their view
+    pub future_view_messages_released: Box<dyn Counter>,
+    /// Number of held messages handled early to make room in the future view buffer
+    pub future_view_messages_evicted: Box<dyn Counter>,
+    /// Time in seconds the vote collection tasks spent processing a single vote
+    pub vote_collection_duration: Box<dyn Histogram>,
+    /// Time in seconds each vote collection task spent processing a single vote, one for each
+    /// kind of votes in the order of [`VOTE_TASKS`], see [`Self::vote_task_duration`]
+    pub vote_task_durations: Vec<Box<dyn Histogram>>,
+    /// Number of transactions held by each shard of the mempool
+    pub mempool_shard_transactions: Vec<Box<dyn Gauge>>,
+    /// Number of announced transactions requested from other nodes by the mempool sync protocol
+    pub mempool_sync_transactions_requested: Box<dyn Counter>,
+    /// Bytes exchanged with each peer, by message class
+    pub bandwidth: Arc<BandwidthMeter>,
+    /// How long views lasted, from entering them to entering the next view
+    pub view_duration: Box<dyn Histogram>,
+    /// Moving average of view durations, in milliseconds
+    pub view_duration_average: Box<dyn Gauge>,
+    /// How long a transaction submitted now is expected to take to be decided, in milliseconds
+    pub estimated_finality_latency: Box<dyn Gauge>,
+    /// Number of quorum stake table lookups served from the stake table cache
+    pub stake_table_cache_hits: Box<dyn Gauge>,
+    /// Number of quorum stake table lookups computed from the stake table
+    pub stake_table_cache_misses: Box<dyn Gauge>,
+    /// Share of quorum stake table lookups served from the stake table cache, in percent
+    pub stake_table_cache_hit_rate: Box<dyn Gauge>,
 }
 
 /// The wrapper with a string name for the networking metrics
@@ -224,8 +311,86 @@ impl ConsensusMetricsValue {
             outstanding_transactions_memory_size: metrics
                 .create_gauge(String::from("outstanding_transactions_memory_size"), None),
             number_of_timeouts: metrics.create_counter(String::from("number_of_timeouts"), None),
+            number_of_missing_proposals: metrics
+                .create_counter(String::from("number_of_missing_proposals"), None),
+            availability_samples_failed: metrics
+                .create_counter(String::from("availability_samples_failed"), None),
+            availability_alarms: metrics.create_counter(String::from("availability_alarms"), None),
+            chain_id_mismatches: metrics.create_counter(String::from("chain_id_mismatches"), None),
+            stale_proposals_dropped: metrics
+                .create_counter(String::from("stale_proposals_dropped"), None),
+            stale_votes_dropped: metrics.create_counter(String::from("stale_votes_dropped"), None),
+            stale_view_sync_messages_dropped: metrics
+                .create_counter(String::from("stale_view_sync_messages_dropped"), None),
+            stale_certificates_dropped: metrics
+                .create_counter(String::from("stale_certificates_dropped"), None),
+            stale_vid_disperses_dropped: metrics
+                .create_counter(String::from("stale_vid_disperses_dropped"), None),
+            stale_upgrade_messages_dropped: metrics
+                .create_counter(String::from("stale_upgrade_messages_dropped"), None),
+            transactions_rejected: metrics
+                .create_counter(String::from("transactions_rejected"), None),
+            transactions_expired: metrics
+                .create_counter(String::from("transactions_expired"), None),
+            vote_collectors_rearmed: metrics
+                .create_counter(String::from("vote_collectors_rearmed"), None),
+            future_view_messages_buffered: metrics
+                .create_gauge(String::from("future_view_messages_buffered"), None),
+            future_view_messages_released: metrics
+                .create_counter(String::from("future_view_messages_released"), None),
+            future_view_messages_evicted: metrics
+                .create_counter(String::from("future_view_messages_evicted"), None),
+            vote_collection_duration: metrics.create_histogram(
+                String::from("vote_collection_duration"),
+                Some(String::from("seconds")),
+            ),
+            vote_task_durations: VOTE_TASKS
+                .iter()
+                .map(|(_, task)| {
+                    metrics.create_histogram(
+                        format!("{task}_vote_task_duration"),
+                        Some(String::from("seconds")),
+                    )
+                })
+                .collect(),
+            mempool_shard_transactions: (0..MEMPOOL_SHARDS)
+                .map(|shard| {
+                    metrics.create_gauge(format!("mempool_shard_{shard}_transactions"), None)
+                })
+                .collect(),
+            mempool_sync_transactions_requested: metrics
+                .create_counter(String::from("mempool_sync_transactions_requested"), None),
+            bandwidth: Arc::new(BandwidthMeter::new(metrics)),
+            view_duration: metrics
+                .create_histogram(String::from("view_duration"), Some(String::from("seconds"))),
+            view_duration_average: metrics.create_gauge(
+                String::from("view_duration_average"),
+                Some(String::from("milliseconds")),
+            ),
+            estimated_finality_latency: metrics.create_gauge(
+                String::from("estimated_finality_latency"),
+                Some(String::from("milliseconds")),
+            ),
+            stake_table_cache_hits: metrics
+                .create_gauge(String::from("stake_table_cache_hits"), None),
+            stake_table_cache_misses: metrics
+                .create_gauge(String::from("stake_table_cache_misses"), None),
+            stake_table_cache_hit_rate: metrics.create_gauge(
+                String::from("stake_table_cache_hit_rate"),
+                Some(String::from("percent")),
+            ),
         }
     }
+
+    /// The time in seconds the vote collection task for votes of `kind` spent on each vote
+    #[must_use]
+    pub fn vote_task_duration(&self, kind: VoteKind) -> &dyn Histogram {
+        let task = VOTE_TASKS
+            .iter()
+            .position(|(task_kind, _)| *task_kind == kind)
+            .unwrap_or_default();
+        &*self.vote_task_durations[task]
+    }
 }
 
 impl Default for ConsensusMetricsValue {
@@ -325,6 +490,22 @@ impl<TYPES: NodeType> Consensus<TYPES> {
             });
         self.validated_state_map = self.validated_state_map.split_off(&new_anchor_view);
         self.saved_payloads = self.saved_payloads.split_off(&new_anchor_view);
+        self.payload_store.collect_garbage();
+    }
+
+    /// Save the encoded transactions of the payload of `view`, whose commitment is
+    /// `payload_commitment`, replacing any saved before. A payload the store already holds,
+    /// e.g. for another view or task, is shared rather than stored again.
+    pub fn save_payload(
+        &mut self,
+        view: TYPES::Time,
+        payload_commitment: VidCommitment,
+        encoded_transactions: Bytes,
+    ) {
+        let payload = self
+            .payload_store
+            .intern(payload_commitment, encoded_transactions);
+        self.saved_payloads.insert(view, payload);
     }
 
     /// Gets the last decided leaf.
@@ -342,6 +523,12 @@ impl<TYPES: NodeType> Consensus<TYPES> {
         self.saved_leaves.get(&leaf).unwrap().clone()
     }
 
+    /// Snapshot the in-memory leaf DAG, for visualizing forks
+    #[must_use]
+    pub fn leaf_dag(&self) -> LeafDag {
+        LeafDag::from_consensus(self)
+    }
+
     /// Gets the validated state with the given view number, if in the state map.
     #[must_use]
     pub fn get_state(&self, view_number: TYPES::Time) -> Option<&Arc<TYPES::ValidatedState>> {
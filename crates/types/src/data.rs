@@ -4,6 +4,7 @@
 //! `HotShot`'s version of a block, and proposals, messages upon which to reach the consensus.
 
 use crate::{
+    governance::{commit_governance, GovernanceTransaction},
     simple_certificate::{QuorumCertificate, TimeoutCertificate, UpgradeCertificate},
     simple_vote::UpgradeProposalData,
     traits::{
@@ -20,6 +21,7 @@ use crate::{
 use ark_bls12_381::Bls12_381;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use bincode::Options;
+use bytes::Bytes;
 use commit::{Commitment, Committable, RawCommitmentBuilder};
 use derivative::Derivative;
 use hotshot_utils::bincode::bincode_opts;
@@ -29,6 +31,7 @@ use jf_primitives::{
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use snafu::Snafu;
 use std::{
     collections::BTreeMap,
@@ -115,11 +118,105 @@ pub type TxnCommitment<STATE> = Commitment<Transaction<STATE>>;
 #[derive(custom_debug::Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
 pub struct DAProposal<TYPES: NodeType> {
     /// Encoded transactions in the block to be applied.
-    pub encoded_transactions: Vec<u8>,
+    ///
+    /// Reference counted so that the payload is shared, rather than copied, between the DA
+    /// task, the network and storage.
+    pub encoded_transactions: Bytes,
+    /// Metadata of the block to be applied.
+    pub metadata: <TYPES::BlockPayload as BlockPayload>::Metadata,
+    /// View this proposal applies to
+    pub view_number: TYPES::Time,
+}
+
+/// The commitment of a [`DAProposal`], which its leader broadcasts to the nodes outside the DA
+/// committee when it sends the payload itself to the committee members only. Its signature
+/// covers its view, payload commitment and transactions hash, as given by
+/// [`DAProposalCommitment::signed_bytes`], so it cannot be replayed with another commitment.
+#[derive(custom_debug::Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+pub struct DAProposalCommitment<TYPES: NodeType> {
+    /// VID commitment to the payload of the proposal
+    pub payload_commitment: VidCommitment,
+    /// SHA-256 hash of the encoded transactions
+    pub encoded_transactions_hash: Vec<u8>,
+    /// Metadata of the block to be applied.
+    pub metadata: <TYPES::BlockPayload as BlockPayload>::Metadata,
+    /// View the proposal applies to
+    pub view_number: TYPES::Time,
+}
+
+impl<TYPES: NodeType> DAProposalCommitment<TYPES> {
+    /// The commitment of `proposal`, for a network of `num_storage_nodes` nodes
+    #[must_use]
+    pub fn new(proposal: &DAProposal<TYPES>, num_storage_nodes: usize) -> Self {
+        Self {
+            payload_commitment: vid_commitment(&proposal.encoded_transactions, num_storage_nodes),
+            encoded_transactions_hash: Sha256::digest(&proposal.encoded_transactions).to_vec(),
+            metadata: proposal.metadata.clone(),
+            view_number: proposal.view_number,
+        }
+    }
+
+    /// The bytes its leader signs, binding the payload commitment and the transactions hash to
+    /// the view
+    ///
+    /// # Panics
+    ///
+    /// If they cannot be serialized, which they always can
+    #[must_use]
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        bincode_opts()
+            .serialize(&(
+                self.view_number,
+                &self.payload_commitment,
+                &self.encoded_transactions_hash,
+            ))
+            .expect("DA proposal commitments serialize")
+    }
+}
+
+/// A transaction of a [`CompactDAProposal`]
+#[derive(custom_debug::Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub enum CompactTransaction<TYPES: NodeType> {
+    /// A transaction the DA committee members are believed to hold in their mempools, by its
+    /// commitment
+    Held(Commitment<TYPES::Transaction>),
+    /// A transaction believed missing from their mempools
+    Full(TYPES::Transaction),
+}
+
+/// A [`DAProposal`] whose transactions the DA committee members are believed to hold are
+/// replaced by their commitments. The members rebuild the payload from their mempools, fetching
+/// the transactions they miss from the leader, and check the signature of the DA proposal, over
+/// the hash of the encoded transactions, against the rebuilt payload. The leader also signs
+/// [`CompactDAProposal::signed_bytes`], so that members only start rebuilding proposals of the
+/// leader.
+#[derive(custom_debug::Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct CompactDAProposal<TYPES: NodeType> {
+    /// The transactions of the block, in order
+    pub transactions: Vec<CompactTransaction<TYPES>>,
     /// Metadata of the block to be applied.
     pub metadata: <TYPES::BlockPayload as BlockPayload>::Metadata,
     /// View this proposal applies to
     pub view_number: TYPES::Time,
+    /// The signature of the full DA proposal, over the hash of its encoded transactions
+    pub payload_signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+}
+
+impl<TYPES: NodeType> CompactDAProposal<TYPES> {
+    /// The bytes its leader signs: the hash of the whole compact proposal
+    ///
+    /// # Panics
+    ///
+    /// If it cannot be serialized, which it always can
+    #[must_use]
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let encoded = bincode_opts()
+            .serialize(self)
+            .expect("compact DA proposals serialize");
+        Sha256::digest(encoded).to_vec()
+    }
 }
 
 /// A proposal to upgrade the network
@@ -180,6 +277,57 @@ impl<TYPES: NodeType> VidDisperse<TYPES> {
     }
 }
 
+/// A single VID share, with the data needed to check it against the payload commitment, sent to
+/// a node sampling the availability of a payload
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+pub struct VidSample<TYPES: NodeType> {
+    /// The view the payload was proposed in
+    pub view_number: TYPES::Time,
+    /// Block payload commitment
+    pub payload_commitment: VidCommitment,
+    /// The storage node the share was dispersed to
+    pub owner: TYPES::SignatureKey,
+    /// The share
+    pub share: <VidScheme as VidSchemeTrait>::Share,
+    /// VID common data sent to all storage nodes
+    pub common: <VidScheme as VidSchemeTrait>::Common,
+}
+
+impl<TYPES: NodeType> VidSample<TYPES> {
+    /// The share of `owner` in `disperse`, if it has one
+    #[must_use]
+    pub fn from_disperse(
+        disperse: &VidDisperse<TYPES>,
+        owner: &TYPES::SignatureKey,
+    ) -> Option<Self> {
+        Some(Self {
+            view_number: disperse.view_number,
+            payload_commitment: disperse.payload_commitment,
+            owner: owner.clone(),
+            share: disperse.shares.get(owner)?.clone(),
+            common: disperse.common.clone(),
+        })
+    }
+
+    /// The bytes a node signs to ask `da_member` for the share dispersed to `owner` of the
+    /// payload with `payload_commitment`, proposed in `view`
+    ///
+    /// # Panics
+    ///
+    /// If they cannot be serialized, which they always can
+    #[must_use]
+    pub fn request_bytes(
+        payload_commitment: &VidCommitment,
+        view: TYPES::Time,
+        owner: &TYPES::SignatureKey,
+        da_member: &TYPES::SignatureKey,
+    ) -> Vec<u8> {
+        bincode_opts()
+            .serialize(&(payload_commitment, view, owner, da_member))
+            .expect("VID share requests serialize")
+    }
+}
+
 /// Trusted KZG setup for VID.
 ///
 /// TESTING ONLY: don't use this in production
@@ -220,6 +368,9 @@ pub struct QuorumProposal<TYPES: NodeType> {
 
     /// the propser id
     pub proposer_id: TYPES::SignatureKey,
+
+    /// The governance transactions of the block, which the leaf commits to
+    pub governance: Vec<GovernanceTransaction>,
 }
 
 impl<TYPES: NodeType> HasViewNumber<TYPES> for DAProposal<TYPES> {
@@ -228,12 +379,30 @@ impl<TYPES: NodeType> HasViewNumber<TYPES> for DAProposal<TYPES> {
     }
 }
 
+impl<TYPES: NodeType> HasViewNumber<TYPES> for DAProposalCommitment<TYPES> {
+    fn get_view_number(&self) -> TYPES::Time {
+        self.view_number
+    }
+}
+
+impl<TYPES: NodeType> HasViewNumber<TYPES> for CompactDAProposal<TYPES> {
+    fn get_view_number(&self) -> TYPES::Time {
+        self.view_number
+    }
+}
+
 impl<TYPES: NodeType> HasViewNumber<TYPES> for VidDisperse<TYPES> {
     fn get_view_number(&self) -> TYPES::Time {
         self.view_number
     }
 }
 
+impl<TYPES: NodeType> HasViewNumber<TYPES> for VidSample<TYPES> {
+    fn get_view_number(&self) -> TYPES::Time {
+        self.view_number
+    }
+}
+
 impl<TYPES: NodeType> HasViewNumber<TYPES> for QuorumProposal<TYPES> {
     fn get_view_number(&self) -> TYPES::Time {
         self.view_number
@@ -296,6 +465,11 @@ pub struct Leaf<TYPES: NodeType> {
 
     /// the proposer id of the leaf
     pub proposer_id: TYPES::SignatureKey,
+
+    /// The governance transactions of the block, so that nodes without the payload can schedule
+    /// the parameter changes it decides
+    #[serde(default)]
+    pub governance: Vec<GovernanceTransaction>,
 }
 
 impl<TYPES: NodeType> PartialEq for Leaf<TYPES> {
@@ -304,6 +478,7 @@ impl<TYPES: NodeType> PartialEq for Leaf<TYPES> {
             && self.justify_qc == other.justify_qc
             && self.parent_commitment == other.parent_commitment
             && self.block_header == other.block_header
+            && self.governance == other.governance
     }
 }
 
@@ -313,6 +488,7 @@ impl<TYPES: NodeType> Hash for Leaf<TYPES> {
         self.justify_qc.hash(state);
         self.parent_commitment.hash(state);
         self.block_header.hash(state);
+        self.governance.hash(state);
     }
 }
 
@@ -340,6 +516,7 @@ impl<TYPES: NodeType> Leaf<TYPES> {
             block_header: block_header.clone(),
             block_payload: Some(block_payload),
             proposer_id: <<TYPES as NodeType>::SignatureKey as SignatureKey>::genesis_proposer_pk(),
+            governance: Vec::new(),
         }
     }
 
@@ -376,7 +553,7 @@ impl<TYPES: NodeType> Leaf<TYPES> {
         block_payload: TYPES::BlockPayload,
         num_storage_nodes: usize,
     ) -> Result<(), BlockError> {
-        let encoded_txns = match block_payload.encode() {
+        let encoded_txns: Vec<u8> = match block_payload.encode() {
             // TODO (Keyao) [VALIDATED_STATE] - Avoid collect/copy on the encoded transaction bytes.
             // <https://github.com/EspressoSystems/HotShot/issues/2115>
             Ok(encoded) => encoded.into_iter().collect(),
@@ -411,6 +588,11 @@ impl<TYPES: NodeType> Leaf<TYPES> {
         self.proposer_id.clone()
     }
 
+    /// The governance transactions of the block of this leaf.
+    pub fn get_governance(&self) -> &[GovernanceTransaction] {
+        &self.governance
+    }
+
     /// Create a leaf from information stored about a view.
     pub fn from_stored_view(stored_view: StoredView<TYPES>) -> Self {
         Self {
@@ -420,6 +602,7 @@ impl<TYPES: NodeType> Leaf<TYPES> {
             block_header: stored_view.block_header,
             block_payload: stored_view.block_payload,
             proposer_id: stored_view.proposer_id,
+            governance: stored_view.governance,
         }
     }
 }
@@ -489,7 +672,7 @@ impl<TYPES: NodeType> Committable for Leaf<TYPES> {
         };
 
         // Skip the transaction commitments, so that the repliacs can reconstruct the leaf.
-        RawCommitmentBuilder::new("leaf commitment")
+        let builder = RawCommitmentBuilder::new("leaf commitment")
             .u64_field("view number", *self.view_number)
             .u64_field("block number", self.get_height())
             .field("parent Leaf commitment", self.parent_commitment)
@@ -502,8 +685,40 @@ impl<TYPES: NodeType> Committable for Leaf<TYPES> {
                 self.justify_qc.get_data().leaf_commit,
             )
             .constant_str("justify_qc signatures")
-            .var_size_bytes(&signatures_bytes)
-            .finalize()
+            .var_size_bytes(&signatures_bytes);
+        // the proposal signature and the votes cover the header extensions; leaves without any
+        // keep the commitments they had before headers carried extensions
+        let extensions = self.block_header.extensions();
+        let builder = if extensions.is_empty() {
+            builder
+        } else {
+            builder
+                .constant_str("block header extensions")
+                .var_size_bytes(extensions)
+        };
+        // likewise for the governance transactions, which nodes schedule once the leaf is decided
+        if self.governance.is_empty() {
+            builder.finalize()
+        } else {
+            commit_governance(builder, &self.governance).finalize()
+        }
+    }
+}
+
+impl<TYPES> From<StoredView<TYPES>> for Leaf<TYPES>
+where
+    TYPES: NodeType,
+{
+    fn from(view: StoredView<TYPES>) -> Self {
+        Leaf {
+            view_number: view.view_number,
+            justify_qc: view.justify_qc,
+            parent_commitment: view.parent,
+            block_header: view.block_header,
+            block_payload: view.block_payload,
+            proposer_id: view.proposer_id,
+            governance: view.governance,
+        }
     }
 }
 
@@ -519,6 +734,7 @@ where
             block_header: leaf.get_block_header().clone(),
             block_payload: leaf.get_block_payload(),
             proposer_id: leaf.get_proposer_id(),
+            governance: leaf.get_governance().to_vec(),
         }
     }
 }
@@ -0,0 +1,129 @@
+//! Epochs: runs of a fixed number of consecutive views over which the stake table and parameters
+//! of the network do not change
+//!
+//! With epochs enabled, see [`HotShotConfig::epoch_length`](crate::HotShotConfig), the nodes vote
+//! in the last view of every epoch on the stake table and parameters of the next one, or once in
+//! the next one if they skipped that view, and the leader of the first view of the next epoch
+//! aggregates the votes into an
+//! [`EpochChangeCertificate`](crate::simple_certificate::EpochChangeCertificate). The certificate
+//! is where reconfiguration, checkpointing and pruning hook in: it says from which view on which
+//! stake table signs certificates, which is what a node needs to validate the chain past it, and
+//! the parameters of an epoch only take effect once it is seen.
+
+use std::fmt::{self, Display};
+
+use bincode::Options;
+use hotshot_utils::bincode::bincode_opts;
+use serde::{Deserialize, Serialize};
+
+use crate::traits::{node_implementation::ConsensusTime, signature_key::SignatureKey};
+
+/// The number of an epoch. Epoch 0 starts at the genesis view.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct Epoch(u64);
+
+impl Epoch {
+    /// Epoch number `n`
+    #[must_use]
+    pub const fn new(n: u64) -> Self {
+        Self(n)
+    }
+
+    /// The epoch after this one
+    #[must_use]
+    pub const fn next(self) -> Self {
+        Self(self.0.saturating_add(1))
+    }
+}
+
+impl std::ops::Deref for Epoch {
+    type Target = u64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Display for Epoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How views are divided into epochs: every epoch is `length` consecutive views long
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EpochSchedule {
+    /// the number of views of every epoch, at least 1
+    length: u64,
+}
+
+impl EpochSchedule {
+    /// Epochs of `length` views. An epoch of 0 views is taken to be one view long.
+    #[must_use]
+    pub fn new(length: u64) -> Self {
+        Self {
+            length: length.max(1),
+        }
+    }
+
+    /// The number of views of every epoch
+    #[must_use]
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// The epoch `view` is in
+    #[must_use]
+    pub fn epoch_of<TIME: ConsensusTime>(&self, view: TIME) -> Epoch {
+        Epoch(*view / self.length)
+    }
+
+    /// The first view of `epoch`
+    #[must_use]
+    pub fn first_view<TIME: ConsensusTime>(&self, epoch: Epoch) -> TIME {
+        TIME::new(epoch.0.saturating_mul(self.length))
+    }
+
+    /// The last view of `epoch`
+    #[must_use]
+    pub fn last_view<TIME: ConsensusTime>(&self, epoch: Epoch) -> TIME {
+        TIME::new(
+            epoch
+                .0
+                .saturating_mul(self.length)
+                .saturating_add(self.length - 1),
+        )
+    }
+
+    /// Whether `view` is the last view of its epoch, the one the next epoch is voted on in
+    #[must_use]
+    pub fn is_last_view<TIME: ConsensusTime>(&self, view: TIME) -> bool {
+        *view % self.length == self.length - 1
+    }
+}
+
+/// The parameters of the network an epoch change certificate commits the next epoch to, besides
+/// its stake table
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EpochParameters {
+    /// the number of views of the epoch
+    pub epoch_length: u64,
+    /// the size of the DA committee
+    pub da_committee_size: u64,
+}
+
+/// The commitment to `stake_table` an epoch change certificate signs: the hash of its entries,
+/// in order
+///
+/// # Panics
+///
+/// If a stake table entry cannot be serialized, which the entries of every key type can
+#[must_use]
+pub fn stake_table_commitment<KEY: SignatureKey>(stake_table: &[KEY::StakeTableEntry]) -> [u8; 32] {
+    let bytes = bincode_opts()
+        .serialize(stake_table)
+        .expect("stake table entries serialize");
+    *blake3::hash(&bytes).as_bytes()
+}
@@ -1,10 +1,13 @@
 //! Error type for `HotShot`
 //!
 //! This module provides [`HotShotError`], which is an enum representing possible faults that can
-//! occur while interacting with this crate.
+//! occur while interacting with this crate. Each error belongs to an [`ErrorKind`], whose numeric
+//! code is stable across releases, so that applications can react to errors without matching on
+//! their messages.
 
 use crate::traits::{
-    block_contents::BlockPayload, node_implementation::NodeType, storage::StorageError,
+    block_contents::BlockPayload, election::ElectionError, node_implementation::NodeType,
+    storage::StorageError, transaction_validator::TransactionRejection,
 };
 use snafu::Snafu;
 use std::num::NonZeroU64;
@@ -45,13 +48,69 @@ pub enum HotShotError<TYPES: NodeType> {
         /// Underlying network fault
         source: crate::traits::network::NetworkError,
     },
+    /// A submitted transaction did not pass validation
+    #[snafu(display("Transaction rejected: {source}"))]
+    TransactionRejected {
+        /// Why the transaction was rejected
+        source: TransactionRejection,
+    },
     /// Item was not present in storage
     LeafNotFound {/* TODO we should create a way to to_string */},
+    /// No stake table was recorded in effect at a view, e.g. one before the anchor or pruned
+    #[snafu(display("No stake table recorded for view {view_number:?}"))]
+    StakeTableNotFound {
+        /// the view the stake table was asked for
+        view_number: TYPES::Time,
+    },
     /// Error accesing storage
+    #[snafu(display("Storage failure: {source}"))]
     StorageError {
         /// Underlying error
         source: StorageError,
     },
+    /// The leader of a view sent a proposal which does not pass validation
+    #[snafu(display(
+        "Invalid proposal for view {view_number:?} from leader {leader:?}: {reason}"
+    ))]
+    InvalidProposal {
+        /// view of the proposal
+        view_number: TYPES::Time,
+        /// the leader which sent the proposal
+        leader: TYPES::SignatureKey,
+        /// why the proposal was rejected
+        reason: String,
+    },
+    /// No view has been decided for long enough that the node is likely cut off from a quorum
+    #[snafu(display(
+        "Network partition suspected: nothing decided for {views_without_decide} views, until view \
+         {view_number:?}"
+    ))]
+    PartitionSuspected {
+        /// the view the node timed out in
+        view_number: TYPES::Time,
+        /// number of views since the last decided view
+        views_without_decide: u64,
+    },
+    /// Conflicting certificates were observed, so the chain may have forked
+    #[snafu(display("Safety violation in view {view_number:?}: {context}"))]
+    SafetyViolation {
+        /// view in which the violation was observed
+        view_number: TYPES::Time,
+        /// what conflicts
+        context: String,
+    },
+    /// The election configuration is unsafe
+    #[snafu(display("Invalid election configuration: {source}"))]
+    InvalidElectionConfig {
+        /// Underlying error
+        source: ElectionError,
+    },
+    /// A node was configured inconsistently, or incompletely
+    #[snafu(display("Invalid configuration: {context}"))]
+    InvalidConfig {
+        /// what is wrong with the configuration
+        context: String,
+    },
     /// Invalid state machine state
     #[snafu(display("Invalid state machine state: {}", context))]
     InvalidState {
@@ -89,6 +148,71 @@ pub enum HotShotError<TYPES: NodeType> {
     Continue,
 }
 
+/// The category of a [`HotShotError`]
+///
+/// The discriminants are stable: new kinds get new codes, and codes are never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u16)]
+pub enum ErrorKind {
+    /// the network could not deliver a message
+    Network = 1,
+    /// storage could not be read or written
+    Storage = 2,
+    /// a leader proposed something invalid
+    InvalidProposal = 3,
+    /// the node is likely cut off from a quorum
+    PartitionSuspected = 4,
+    /// conflicting certificates were observed
+    SafetyViolation = 5,
+    /// a block could not be built or verified
+    Block = 6,
+    /// a submitted transaction was rejected
+    TransactionRejected = 7,
+    /// the configuration is invalid
+    Config = 8,
+    /// a view or an operation timed out
+    Timeout = 9,
+    /// not enough valid signatures were collected
+    InsufficientSignatures = 10,
+    /// internal state is inconsistent
+    InvalidState = 11,
+    /// anything else
+    Other = 12,
+}
+
+impl ErrorKind {
+    /// The stable numeric code of this kind
+    #[must_use]
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+}
+
+impl<TYPES: NodeType> HotShotError<TYPES> {
+    /// The category of this error
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::FailedToMessageLeader { .. }
+            | Self::FailedToBroadcast { .. }
+            | Self::NetworkFault { .. } => ErrorKind::Network,
+            Self::LeafNotFound {} | Self::StakeTableNotFound { .. } | Self::StorageError { .. } => {
+                ErrorKind::Storage
+            }
+            Self::InvalidProposal { .. } => ErrorKind::InvalidProposal,
+            Self::PartitionSuspected { .. } => ErrorKind::PartitionSuspected,
+            Self::SafetyViolation { .. } => ErrorKind::SafetyViolation,
+            Self::BlockError { .. } => ErrorKind::Block,
+            Self::TransactionRejected { .. } => ErrorKind::TransactionRejected,
+            Self::InvalidElectionConfig { .. } | Self::InvalidConfig { .. } => ErrorKind::Config,
+            Self::TimeoutError { .. } | Self::ViewTimeoutError { .. } => ErrorKind::Timeout,
+            Self::InsufficientValidSignatures { .. } => ErrorKind::InsufficientSignatures,
+            Self::InvalidState { .. } => ErrorKind::InvalidState,
+            Self::Misc { .. } | Self::Continue => ErrorKind::Other,
+        }
+    }
+}
+
 /// Contains information about what the state of the hotshot-consensus was when a round timed out
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -1,13 +1,18 @@
 //! Events that a `HotShot` instance can emit
 
 use crate::{
-    data::{DAProposal, Leaf, QuorumProposal, UpgradeProposal, VidDisperse},
+    data::{DAProposal, Leaf, QuorumProposal, UpgradeProposal, VidCommitment, VidDisperse},
+    epoch::Epoch,
     error::HotShotError,
+    governance::ConsensusParameters,
     message::Proposal,
-    simple_certificate::QuorumCertificate,
+    safety::SafetyEvidence,
+    simple_certificate::{EpochChangeCertificate, QuorumCertificate},
+    stall::{StallDiagnostics, VoteKind},
     traits::node_implementation::NodeType,
 };
 
+use ethereum_types::U256;
 use std::sync::Arc;
 /// A status event emitted by a `HotShot` instance
 ///
@@ -53,11 +58,47 @@ pub enum EventType<TYPES: NodeType> {
         /// Optional information of the number of transactions in the block, for logging purposes.
         block_size: Option<u64>,
     },
+    /// Conflicting certificates or decides were observed, so the chain may have forked. The node
+    /// stops voting and proposing; it must be investigated before it is restarted.
+    SafetyViolation {
+        /// What conflicts
+        evidence: Arc<SafetyEvidence<TYPES>>,
+    },
     /// A replica task was canceled by a timeout interrupt
     ReplicaViewTimeout {
         /// The view that timed out
         view_number: TYPES::Time,
     },
+    /// No proposal for a view arrived within the proposal timeout, so its leader has likely
+    /// failed. Unlike [`EventType::ReplicaViewTimeout`], the view itself is still running.
+    ProposalMissing {
+        /// The view whose proposal is missing
+        view: TYPES::Time,
+        /// The leader of that view
+        leader: TYPES::SignatureKey,
+    },
+    /// Sampling the VID shares of a payload certified by the DA committee failed: some of the
+    /// DA members asked did not return a valid share in time, so the payload may not be
+    /// available despite its certificate
+    AvailabilityAlarm {
+        /// The view the payload was proposed in
+        view: TYPES::Time,
+        /// The commitment of the payload
+        payload_commitment: VidCommitment,
+        /// The number of shares sampled
+        samples: usize,
+        /// The number of sampled shares not returned, or not matching the commitment
+        failed_samples: usize,
+    },
+    /// Several views in a row ended without a DA certificate, likely as too many DA committee
+    /// members are down, so this node disseminates its DA proposals to the whole quorum, every
+    /// node of which votes on them, until `until_view`
+    DAFallback {
+        /// The number of views which ended without a DA certificate
+        failed_views: u64,
+        /// The last view whose DA proposal goes to the whole quorum
+        until_view: TYPES::Time,
+    },
     /// A next leader task was canceled by a timeout interrupt
     NextLeaderViewTimeout {
         /// The view that timed out
@@ -98,4 +139,40 @@ pub enum EventType<TYPES: NodeType> {
         /// Public key of the leader submitting the proposal
         sender: TYPES::SignatureKey,
     },
+    /// The quorum certified the stake table and parameters of the next epoch, which is where
+    /// reconfiguration, checkpointing and pruning hook in
+    EpochChange {
+        /// The certificate, which names the epoch and its first view
+        certificate: EpochChangeCertificate<TYPES>,
+    },
+    /// A decided governance transaction changed the consensus parameters from an epoch on
+    ParameterChangeScheduled {
+        /// The epoch from whose first view on the parameters change
+        epoch: Epoch,
+        /// The parameters of the epoch, with the change
+        parameters: ConsensusParameters,
+    },
+    /// The votes this node collects as a leader covered another step of the way to the
+    /// threshold of their certificate, see
+    /// [`HotShotConfig::vote_progress_steps`](crate::HotShotConfig)
+    VoteProgress {
+        /// The view the votes are for
+        view: TYPES::Time,
+        /// The kind of the votes
+        kind: VoteKind,
+        /// The weight of the votes collected for the commitment voted for most
+        collected_weight: U256,
+        /// The weight the certificate needs
+        threshold: U256,
+        /// The voters of the commitment voted for most
+        signers: Vec<TYPES::SignatureKey>,
+        /// The members of the committee of the view who did not vote for it
+        missing: Vec<TYPES::SignatureKey>,
+    },
+    /// The view did not change for the stall timeout, so the node is likely stuck. Reported
+    /// again every time another stall timeout passes without a view change.
+    Stall {
+        /// What the node saw of the view it is stuck in
+        diagnostics: Arc<StallDiagnostics<TYPES>>,
+    },
 }
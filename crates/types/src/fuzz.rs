@@ -0,0 +1,473 @@
+//! Arbitrary instances of proposals, votes, certificates and messages, for fuzzing
+//!
+//! Fuzz targets turn the bytes of the fuzzer into messages with [`Arbitrary`], then feed them,
+//! or their [`mutate`]d serializations, to the deserialization and validation code of the
+//! network layer. Instances are well formed but not necessarily valid: they are signed by the
+//! keys of the first [`FUZZ_KEYS`] test nodes, over the data they carry or over something else,
+//! so that validation is exercised past its first check.
+//!
+//! Application types are not known here, so the instances of messages carrying block headers,
+//! block metadata or transactions require implementations of [`Arbitrary`] for those.
+//! VID dispersals are never generated, as their shares are only meaningful when computed from a
+//! payload.
+
+pub mod mutate;
+
+use std::marker::PhantomData;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use bitvec::{order::Lsb0, vec::BitVec};
+use bytes::Bytes;
+use commit::{Commitment, Committable, RawCommitmentBuilder};
+use either::Either::{Left, Right};
+use ethereum_types::U256;
+use hotshot_constants::Version;
+
+use crate::{
+    data::{
+        DAProposal, DAProposalCommitment, QuorumProposal, UpgradeProposal, VidCommitment, VidSample,
+    },
+    message::{
+        CommitteeConsensusMessage, DataMessage, GeneralConsensusMessage, Message, MessageKind,
+        Proposal, SequencingMessage, VoteBatch,
+    },
+    signing::{self, SigningDomain},
+    simple_certificate::{SimpleCertificate, Threshold},
+    simple_vote::{
+        DAData, QuorumData, SimpleVote, TimeoutData, UpgradeProposalData, VIDData,
+        ViewSyncCommitData, ViewSyncFinalizeData, ViewSyncPreCommitData, Voteable,
+    },
+    traits::{
+        block_contents::vid_commitment,
+        node_implementation::{ConsensusTime, NodeType},
+        signature_key::SignatureKey,
+        BlockPayload,
+    },
+    vote::HasViewNumber,
+};
+
+/// Number of test nodes whose keys sign arbitrary votes, certificates and proposals
+pub const FUZZ_KEYS: u64 = 10;
+
+/// An arbitrary view number
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_view<TYPES: NodeType>(u: &mut Unstructured<'_>) -> Result<TYPES::Time> {
+    Ok(TYPES::Time::new(u.arbitrary()?))
+}
+
+/// The key pair of an arbitrary one of the first [`FUZZ_KEYS`] test nodes
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_key_pair<TYPES: NodeType>(
+    u: &mut Unstructured<'_>,
+) -> Result<(
+    TYPES::SignatureKey,
+    <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+)> {
+    let index = u.int_in_range(0..=FUZZ_KEYS - 1)?;
+    Ok(TYPES::SignatureKey::generated_from_seed_indexed(
+        [0u8; 32], index,
+    ))
+}
+
+/// An arbitrary signer, and its signature in `domain` of either `message` or arbitrary bytes
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_signature<TYPES: NodeType>(
+    u: &mut Unstructured<'_>,
+    domain: SigningDomain,
+    message: &[u8],
+) -> Result<(
+    TYPES::SignatureKey,
+    <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+)> {
+    let (key, private_key) = arbitrary_key_pair::<TYPES>(u)?;
+    let signature = if u.arbitrary()? {
+        signing::sign::<TYPES::SignatureKey>(&private_key, domain, message)
+    } else {
+        signing::sign::<TYPES::SignatureKey>(&private_key, domain, &u.arbitrary::<Vec<u8>>()?)
+    };
+    let signature = signature.map_err(|_| arbitrary::Error::IncorrectFormat)?;
+    Ok((key, signature))
+}
+
+/// An assembled vote signature of `message`, by an arbitrary non-empty subset of a stake table of
+/// test nodes of arbitrary size
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_assembled_signature<TYPES: NodeType>(
+    u: &mut Unstructured<'_>,
+    message: &[u8],
+) -> Result<<TYPES::SignatureKey as SignatureKey>::QCType> {
+    let total = u.int_in_range(1..=FUZZ_KEYS)?;
+    let mut signers = BitVec::<usize, Lsb0>::new();
+    let mut signatures = Vec::new();
+    let mut stake_table = Vec::new();
+    for index in 0..total {
+        let (key, private_key) = TYPES::SignatureKey::generated_from_seed_indexed([0u8; 32], index);
+        stake_table.push(key.get_stake_table_entry(1));
+        // the first node always signs, as an aggregate needs a signature
+        let signs = index == 0 || u.arbitrary()?;
+        signers.push(signs);
+        if signs {
+            let signature =
+                signing::sign::<TYPES::SignatureKey>(&private_key, SigningDomain::Vote, message)
+                    .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+            signatures.push(signature);
+        }
+    }
+    // no threshold, so that signatures of too little stake are generated too
+    let params = TYPES::SignatureKey::get_public_parameter(stake_table, U256::zero());
+    Ok(TYPES::SignatureKey::assemble(
+        &params,
+        signers.as_bitslice(),
+        &signatures,
+    ))
+}
+
+/// An arbitrary commitment
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_commitment<T: Committable>(u: &mut Unstructured<'_>) -> Result<Commitment<T>> {
+    Ok(RawCommitmentBuilder::new("Arbitrary Commitment")
+        .fixed_size_bytes(&u.arbitrary::<[u8; 32]>()?)
+        .finalize())
+}
+
+/// Arbitrarily many arbitrary commitments
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_commitments<T: Committable>(
+    u: &mut Unstructured<'_>,
+) -> Result<Vec<Commitment<T>>> {
+    let len = u.arbitrary_len::<[u8; 32]>()?;
+    (0..len).map(|_| arbitrary_commitment(u)).collect()
+}
+
+/// The VID commitment of an arbitrary short payload
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_vid_commitment(u: &mut Unstructured<'_>) -> Result<VidCommitment> {
+    let len = u.int_in_range(0..=64)?.min(u.len());
+    Ok(vid_commitment(u.bytes(len)?, 1))
+}
+
+/// An arbitrary protocol version
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn arbitrary_version(u: &mut Unstructured<'_>) -> Result<Version> {
+    Ok(Version {
+        major: u.arbitrary()?,
+        minor: u.arbitrary()?,
+    })
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for QuorumData<TYPES> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            leaf_commit: arbitrary_commitment(u)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for DAData {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            payload_commit: arbitrary_vid_commitment(u)?,
+            governance: Vec::new(),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for VIDData {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            payload_commit: arbitrary_vid_commitment(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for TimeoutData<TYPES> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            view: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for ViewSyncPreCommitData<TYPES> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            relay: u.arbitrary()?,
+            round: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for ViewSyncCommitData<TYPES> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            relay: u.arbitrary()?,
+            round: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for ViewSyncFinalizeData<TYPES> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            relay: u.arbitrary()?,
+            round: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for UpgradeProposalData<TYPES> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            old_version: arbitrary_version(u)?,
+            new_version: arbitrary_version(u)?,
+            new_version_hash: u.arbitrary()?,
+            old_version_last_block: arbitrary_view::<TYPES>(u)?,
+            new_version_first_block: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType, DATA: Voteable + Arbitrary<'a>> Arbitrary<'a>
+    for SimpleVote<TYPES, DATA>
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let data: DATA = u.arbitrary()?;
+        let signature =
+            arbitrary_signature::<TYPES>(u, SigningDomain::Vote, data.commit().as_ref())?;
+        Ok(Self {
+            signature,
+            data,
+            view_number: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType, VOTEABLE: Voteable + Arbitrary<'a>, THRESHOLD: Threshold<TYPES>>
+    Arbitrary<'a> for SimpleCertificate<TYPES, VOTEABLE, THRESHOLD>
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let data: VOTEABLE = u.arbitrary()?;
+        let vote_commitment = if u.arbitrary()? {
+            data.commit()
+        } else {
+            arbitrary_commitment(u)?
+        };
+        let signatures = if u.ratio(1, 16)? {
+            None
+        } else {
+            Some(arbitrary_assembled_signature::<TYPES>(
+                u,
+                vote_commitment.as_ref(),
+            )?)
+        };
+        Ok(Self {
+            data,
+            vote_commitment,
+            view_number: arbitrary_view::<TYPES>(u)?,
+            signatures,
+            is_genesis: u.ratio(1, 16)?,
+            _pd: PhantomData,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for QuorumProposal<TYPES>
+where
+    TYPES::BlockHeader: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            block_header: u.arbitrary()?,
+            view_number: arbitrary_view::<TYPES>(u)?,
+            justify_qc: u.arbitrary()?,
+            timeout_certificate: u.arbitrary()?,
+            upgrade_certificate: u.arbitrary()?,
+            proposer_id: arbitrary_key_pair::<TYPES>(u)?.0,
+            governance: Vec::new(),
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for DAProposal<TYPES>
+where
+    <TYPES::BlockPayload as BlockPayload>::Metadata: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            encoded_transactions: Bytes::from(u.arbitrary::<Vec<u8>>()?),
+            metadata: u.arbitrary()?,
+            view_number: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for DAProposalCommitment<TYPES>
+where
+    <TYPES::BlockPayload as BlockPayload>::Metadata: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            payload_commitment: arbitrary_vid_commitment(u)?,
+            encoded_transactions_hash: u.arbitrary::<[u8; 32]>()?.to_vec(),
+            metadata: u.arbitrary()?,
+            view_number: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for UpgradeProposal<TYPES> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            upgrade_proposal: u.arbitrary()?,
+            view_number: arbitrary_view::<TYPES>(u)?,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType, PROPOSAL> Arbitrary<'a> for Proposal<TYPES, PROPOSAL>
+where
+    PROPOSAL: HasViewNumber<TYPES> + serde::de::DeserializeOwned + Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let data = u.arbitrary()?;
+        // what a proposal signs depends on its kind, so sign arbitrary bytes in an arbitrary
+        // domain
+        let domain = *u.choose(&[
+            SigningDomain::QuorumProposal,
+            SigningDomain::DAProposal,
+            SigningDomain::DAProposalCommitment,
+            SigningDomain::VidDisperse,
+        ])?;
+        let message: [u8; 32] = u.arbitrary()?;
+        Ok(Self {
+            data,
+            signature: arbitrary_signature::<TYPES>(u, domain, &message)?.1,
+            _pd: PhantomData,
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for GeneralConsensusMessage<TYPES>
+where
+    TYPES::BlockHeader: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=11)? {
+            0 => Self::Proposal(u.arbitrary()?),
+            1 => Self::Vote(u.arbitrary()?),
+            2 => Self::ViewSyncPreCommitVote(u.arbitrary()?),
+            3 => Self::ViewSyncCommitVote(u.arbitrary()?),
+            4 => Self::ViewSyncFinalizeVote(u.arbitrary()?),
+            5 => Self::ViewSyncPreCommitCertificate(u.arbitrary()?),
+            6 => Self::ViewSyncCommitCertificate(u.arbitrary()?),
+            7 => Self::ViewSyncFinalizeCertificate(u.arbitrary()?),
+            8 => Self::TimeoutVote(u.arbitrary()?),
+            9 => Self::UpgradeProposal(u.arbitrary()?),
+            10 => Self::UpgradeVote(u.arbitrary()?),
+            _ => Self::VoteBatch(VoteBatch {
+                quorum_votes: u.arbitrary()?,
+                timeout_votes: u.arbitrary()?,
+            }),
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for CommitteeConsensusMessage<TYPES>
+where
+    <TYPES::BlockPayload as BlockPayload>::Metadata: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=3)? {
+            0 => Self::DAProposal(u.arbitrary()?),
+            1 => Self::DAVote(u.arbitrary()?),
+            2 => Self::DAProposalCommitment(u.arbitrary()?),
+            _ => Self::DACertificate(u.arbitrary()?),
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for SequencingMessage<TYPES>
+where
+    TYPES::BlockHeader: Arbitrary<'a>,
+    <TYPES::BlockPayload as BlockPayload>::Metadata: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self(if u.arbitrary()? {
+            Left(u.arbitrary()?)
+        } else {
+            Right(u.arbitrary()?)
+        }))
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for DataMessage<TYPES>
+where
+    TYPES::Transaction: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // VID shares cannot be made up, so VID share responses are left out, like VID dispersals,
+        // and so are anchor announcements, whose leaves cannot be made up either
+        Ok(match u.int_in_range(0..=4)? {
+            0 => Self::SubmitTransaction(u.arbitrary()?, arbitrary_view::<TYPES>(u)?),
+            1 => Self::MempoolInventory(arbitrary_commitments(u)?, arbitrary_view::<TYPES>(u)?),
+            2 => Self::RequestTransactions(arbitrary_commitments(u)?, arbitrary_view::<TYPES>(u)?),
+            3 => Self::TransactionsResponse(u.arbitrary()?, arbitrary_view::<TYPES>(u)?),
+            _ => {
+                let payload_commitment = arbitrary_vid_commitment(u)?;
+                let view = arbitrary_view::<TYPES>(u)?;
+                let owner = arbitrary_key_pair::<TYPES>(u)?.0;
+                let da_member = arbitrary_key_pair::<TYPES>(u)?.0;
+                let (_, signature) = arbitrary_signature::<TYPES>(
+                    u,
+                    SigningDomain::VidShareRequest,
+                    &VidSample::<TYPES>::request_bytes(
+                        &payload_commitment,
+                        view,
+                        &owner,
+                        &da_member,
+                    ),
+                )?;
+                Self::RequestVidShare(payload_commitment, view, owner, signature)
+            }
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for MessageKind<TYPES>
+where
+    TYPES::BlockHeader: Arbitrary<'a>,
+    TYPES::Transaction: Arbitrary<'a>,
+    <TYPES::BlockPayload as BlockPayload>::Metadata: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            Self::Consensus(u.arbitrary()?)
+        } else {
+            Self::Data(u.arbitrary()?)
+        })
+    }
+}
+
+impl<'a, TYPES: NodeType> Arbitrary<'a> for Message<TYPES>
+where
+    TYPES::BlockHeader: Arbitrary<'a>,
+    TYPES::Transaction: Arbitrary<'a>,
+    <TYPES::BlockPayload as BlockPayload>::Metadata: Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            version: arbitrary_version(u)?,
+            chain_id: u.arbitrary()?,
+            sender: arbitrary_key_pair::<TYPES>(u)?.0,
+            kind: u.arbitrary()?,
+        })
+    }
+}
@@ -0,0 +1,179 @@
+//! Mutation of messages, structured and on their serialization
+//!
+//! Structured mutations change one part of a well formed message and leave the rest, its
+//! signatures in particular, as they were: a vote moved to another view, or a proposal
+//! attributed to another sender, must be rejected by validation rather than by deserialization.
+//! Byte mutations change the serialization of a message, to exercise deserialization.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use bincode::Options;
+use either::Either::{Left, Right};
+use hotshot_utils::bincode::bincode_opts;
+
+use super::{arbitrary_key_pair, arbitrary_version, arbitrary_view};
+use crate::{
+    message::{
+        CommitteeConsensusMessage, DataMessage, GeneralConsensusMessage, Message, MessageKind,
+    },
+    traits::{node_implementation::NodeType, BlockPayload},
+};
+
+/// A structured change to a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    /// Replace the protocol version
+    Version,
+    /// Replace the chain id
+    ChainId,
+    /// Attribute the message to another test node
+    Sender,
+    /// Move what the message carries to another view
+    View,
+    /// Replace what the message carries with an arbitrary proposal, vote, certificate or data
+    Kind,
+}
+
+/// A change to the serialization of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteMutation {
+    /// Flip a single bit
+    FlipBit,
+    /// Drop everything after some byte
+    Truncate,
+    /// Insert an arbitrary byte
+    Insert,
+    /// Remove a single byte
+    Remove,
+    /// Repeat a range of bytes
+    Duplicate,
+}
+
+/// Apply an arbitrary [`Mutation`] to `message`, returning which
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn mutate_message<'a, TYPES: NodeType>(
+    message: &mut Message<TYPES>,
+    u: &mut Unstructured<'a>,
+) -> Result<Mutation>
+where
+    TYPES::BlockHeader: Arbitrary<'a>,
+    TYPES::Transaction: Arbitrary<'a>,
+    <TYPES::BlockPayload as BlockPayload>::Metadata: Arbitrary<'a>,
+{
+    let mutation = *u.choose(&[
+        Mutation::Version,
+        Mutation::ChainId,
+        Mutation::Sender,
+        Mutation::View,
+        Mutation::Kind,
+    ])?;
+    match mutation {
+        Mutation::Version => message.version = arbitrary_version(u)?,
+        Mutation::ChainId => message.chain_id = u.arbitrary()?,
+        Mutation::Sender => message.sender = arbitrary_key_pair::<TYPES>(u)?.0,
+        Mutation::View => set_view_number(&mut message.kind, arbitrary_view::<TYPES>(u)?),
+        Mutation::Kind => message.kind = u.arbitrary()?,
+    }
+    Ok(mutation)
+}
+
+/// Apply an arbitrary [`ByteMutation`] to `bytes`, returning which
+/// # Errors
+/// If the fuzzer data is malformed
+pub fn mutate_bytes(bytes: &mut Vec<u8>, u: &mut Unstructured<'_>) -> Result<ByteMutation> {
+    let mutation = *u.choose(&[
+        ByteMutation::FlipBit,
+        ByteMutation::Truncate,
+        ByteMutation::Insert,
+        ByteMutation::Remove,
+        ByteMutation::Duplicate,
+    ])?;
+    if bytes.is_empty() {
+        bytes.push(u.arbitrary()?);
+        return Ok(ByteMutation::Insert);
+    }
+    let index = u.choose_index(bytes.len())?;
+    match mutation {
+        ByteMutation::FlipBit => bytes[index] ^= 1 << u.int_in_range(0..=7)?,
+        ByteMutation::Truncate => bytes.truncate(index),
+        ByteMutation::Insert => bytes.insert(index, u.arbitrary()?),
+        ByteMutation::Remove => {
+            bytes.remove(index);
+        }
+        ByteMutation::Duplicate => {
+            let end = u.int_in_range(index..=bytes.len())?;
+            let range = bytes[index..end].to_vec();
+            bytes.splice(index..index, range);
+        }
+    }
+    Ok(mutation)
+}
+
+/// Serialize `message` as the network does, then apply arbitrarily many [`ByteMutation`]s
+/// # Errors
+/// If the fuzzer data is malformed, or the message cannot be serialized
+pub fn mutate_serialized<TYPES: NodeType>(
+    message: &Message<TYPES>,
+    u: &mut Unstructured<'_>,
+) -> Result<Vec<u8>> {
+    let mut bytes = bincode_opts()
+        .serialize(message)
+        .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+    for _ in 0..u.int_in_range(1..=4)? {
+        mutate_bytes(&mut bytes, u)?;
+    }
+    Ok(bytes)
+}
+
+/// Move what `kind` carries to `view`, leaving everything else, signatures included, unchanged
+pub fn set_view_number<TYPES: NodeType>(kind: &mut MessageKind<TYPES>, view: TYPES::Time) {
+    match kind {
+        MessageKind::Consensus(message) => match &mut message.0 {
+            Left(general) => match general {
+                GeneralConsensusMessage::Proposal(p) => p.data.view_number = view,
+                GeneralConsensusMessage::Vote(v) => v.view_number = view,
+                GeneralConsensusMessage::ViewSyncPreCommitVote(v) => v.view_number = view,
+                GeneralConsensusMessage::ViewSyncCommitVote(v) => v.view_number = view,
+                GeneralConsensusMessage::ViewSyncFinalizeVote(v) => v.view_number = view,
+                GeneralConsensusMessage::ViewSyncPreCommitCertificate(c) => c.view_number = view,
+                GeneralConsensusMessage::ViewSyncCommitCertificate(c) => c.view_number = view,
+                GeneralConsensusMessage::ViewSyncFinalizeCertificate(c) => c.view_number = view,
+                GeneralConsensusMessage::TimeoutVote(v) => v.view_number = view,
+                GeneralConsensusMessage::UpgradeProposal(p) => p.data.view_number = view,
+                GeneralConsensusMessage::UpgradeVote(v) => v.view_number = view,
+                GeneralConsensusMessage::EpochChangeVote(v) => v.view_number = view,
+                GeneralConsensusMessage::EpochChangeCertificate(c) => c.view_number = view,
+                GeneralConsensusMessage::VoteBatch(batch) => {
+                    for v in &mut batch.quorum_votes {
+                        v.view_number = view;
+                    }
+                    for v in &mut batch.timeout_votes {
+                        v.view_number = view;
+                    }
+                }
+            },
+            Right(committee) => match committee {
+                CommitteeConsensusMessage::DAProposal(p) => p.data.view_number = view,
+                CommitteeConsensusMessage::DAVote(v) => v.view_number = view,
+                CommitteeConsensusMessage::DACertificate(c) => c.view_number = view,
+                CommitteeConsensusMessage::VidDisperseMsg(p) => p.data.view_number = view,
+                CommitteeConsensusMessage::DAProposalCommitment(p) => p.data.view_number = view,
+                CommitteeConsensusMessage::DAFallbackProposal(p) => p.data.view_number = view,
+                CommitteeConsensusMessage::CompactDAProposal(p) => p.data.view_number = view,
+            },
+        },
+        MessageKind::Data(
+            DataMessage::SubmitTransaction(_, v)
+            | DataMessage::MempoolInventory(_, v)
+            | DataMessage::RequestTransactions(_, v)
+            | DataMessage::TransactionsResponse(_, v)
+            | DataMessage::RequestVidShare(_, v, _, _),
+        ) => *v = view,
+        MessageKind::Data(DataMessage::VidShareResponse(sample)) => sample.view_number = view,
+        MessageKind::Data(DataMessage::AnchorAnnouncement(announcement)) => {
+            announcement.leaf.view_number = view;
+        }
+        // RPC messages are not of any view
+        MessageKind::Data(DataMessage::RpcRequest(_) | DataMessage::RpcResponse(_)) => {}
+    }
+}
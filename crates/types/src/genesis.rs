@@ -0,0 +1,146 @@
+//! The genesis file, which records the initial stake table and parameters of a network
+//!
+//! A network is started from a `genesis.json` generated once, ahead of time, and distributed to
+//! every node. Nodes check their genesis state against the commitment it records, so that a
+//! node started with a different configuration or state is caught before it joins.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+use crate::traits::signature_key::SignatureKey;
+
+/// Errors reading, writing or checking a genesis file
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum GenesisError {
+    /// The genesis file could not be read or written
+    #[snafu(display("Failed to access the genesis file: {source}"))]
+    GenesisIo {
+        /// The underlying IO error
+        source: std::io::Error,
+    },
+    /// The genesis file, or something hashed into it, could not be (de)serialized
+    #[snafu(display("Failed to (de)serialize genesis data: {source}"))]
+    GenesisJson {
+        /// The underlying serialization error
+        source: serde_json::Error,
+    },
+    /// The local genesis state does not match the genesis file
+    #[snafu(display(
+        "Genesis state commitment {actual} does not match {expected} in the genesis file"
+    ))]
+    GenesisStateMismatch {
+        /// The commitment in the genesis file
+        expected: String,
+        /// The commitment of the local genesis state
+        actual: String,
+    },
+}
+
+/// The contents of a `genesis.json`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = ""))]
+pub struct GenesisFile<KEY: SignatureKey> {
+    /// Identifies the network, so that nodes of different networks do not talk to each other
+    pub chain_id: u64,
+    /// The initial stake table, in node index order
+    pub stake_table: Vec<KEY::StakeTableEntry>,
+    /// blake3 hash, in hex, of the configuration the network was generated from
+    pub config_hash: String,
+    /// blake3 hash, in hex, of the genesis validated state
+    pub genesis_state_commitment: String,
+}
+
+impl<KEY: SignatureKey> GenesisFile<KEY> {
+    /// Build the genesis of a network from its configuration and genesis state
+    /// # Errors
+    /// If `config` or `genesis_state` cannot be serialized
+    pub fn new(
+        chain_id: u64,
+        stake_table: Vec<KEY::StakeTableEntry>,
+        config: &impl Serialize,
+        genesis_state: &impl Serialize,
+    ) -> Result<Self, GenesisError> {
+        Ok(Self {
+            chain_id,
+            stake_table,
+            config_hash: hash_of(config)?,
+            genesis_state_commitment: hash_of(genesis_state)?,
+        })
+    }
+
+    /// Read a genesis file
+    /// # Errors
+    /// If the file cannot be read or is not a valid genesis file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, GenesisError> {
+        let contents = std::fs::read_to_string(path).context(GenesisIoSnafu)?;
+        serde_json::from_str(&contents).context(GenesisJsonSnafu)
+    }
+
+    /// Write this genesis file to `path`
+    /// # Errors
+    /// If the file cannot be written
+    pub fn to_file(&self, path: impl AsRef<Path>) -> Result<(), GenesisError> {
+        let contents = serde_json::to_string_pretty(self).context(GenesisJsonSnafu)?;
+        std::fs::write(path, contents).context(GenesisIoSnafu)
+    }
+
+    /// Check that `genesis_state` is the state this genesis file commits to
+    /// # Errors
+    /// If the commitments differ, or the state cannot be serialized
+    pub fn verify_state(&self, genesis_state: &impl Serialize) -> Result<(), GenesisError> {
+        let actual = hash_of(genesis_state)?;
+        if actual != self.genesis_state_commitment {
+            return Err(GenesisError::GenesisStateMismatch {
+                expected: self.genesis_state_commitment.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the network was generated from `config`
+    /// # Errors
+    /// If `config` cannot be serialized
+    pub fn matches_config(&self, config: &impl Serialize) -> Result<bool, GenesisError> {
+        Ok(hash_of(config)? == self.config_hash)
+    }
+}
+
+/// blake3 hash, in hex, of the JSON serialization of `value`
+fn hash_of(value: &impl Serialize) -> Result<String, GenesisError> {
+    let bytes = serde_json::to_vec(value).context(GenesisJsonSnafu)?;
+    Ok(blake3::hash(&bytes).to_hex().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GenesisError, GenesisFile};
+    use crate::{signature_key::BLSPubKey, traits::signature_key::SignatureKey};
+
+    #[test]
+    fn genesis_state_is_checked() {
+        let stake_table = (0..4)
+            .map(|i| {
+                BLSPubKey::generated_from_seed_indexed([0u8; 32], i)
+                    .0
+                    .get_stake_table_entry(1)
+            })
+            .collect();
+        let genesis = GenesisFile::<BLSPubKey>::new(7, stake_table, &"config", &42u64).unwrap();
+
+        let json = serde_json::to_string(&genesis).unwrap();
+        let parsed: GenesisFile<BLSPubKey> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, genesis);
+
+        assert!(genesis.verify_state(&42u64).is_ok());
+        assert!(matches!(
+            genesis.verify_state(&43u64),
+            Err(GenesisError::GenesisStateMismatch { .. })
+        ));
+        assert!(genesis.matches_config(&"config").unwrap());
+        assert!(!genesis.matches_config(&"other config").unwrap());
+    }
+}
@@ -0,0 +1,388 @@
+//! Changes of consensus parameters through governance transactions, so that every node switches
+//! to them at the same view rather than each operator editing its own configuration
+//!
+//! A [`GovernanceTransaction`] is an ordinary transaction of the application, which its
+//! [`Transaction::governance`](crate::traits::block_contents::Transaction::governance) recognizes.
+//! The DA committee certifies the governance transactions of every block along with its payload,
+//! so that replicas without the payload check those the leader copied into its proposal. Once the
+//! leaf including a transaction is decided, the consensus task adds its [`ParameterChange`] to
+//! the [`ParameterSchedule`] if the [`GovernancePolicy`] of the node authorizes it. The change
+//! takes effect at the first view of its activation epoch once the epoch change certificate of
+//! that epoch commits to it. The activation epoch must be at least [`MIN_ACTIVATION_EPOCHS`]
+//! after the epoch of the leaf, so that every node learns of the change before the epoch before
+//! it certifies the parameters it runs with. Changes need epochs, see
+//! [`HotShotConfig::epoch_length`](crate::HotShotConfig).
+//!
+//! Nodes run with [`NoGovernance`] unless configured otherwise, which authorizes no change;
+//! networks whose parameters are governed by a set of keys run with [`SignedByAuthority`].
+
+use std::{collections::BTreeMap, num::NonZeroUsize};
+
+use bincode::Options;
+use commit::{Committable, RawCommitmentBuilder};
+use hotshot_utils::bincode::bincode_opts;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+use crate::{
+    epoch::{Epoch, EpochSchedule},
+    signing::{self, SigningDomain},
+    traits::{node_implementation::ConsensusTime, signature_key::SignatureKey},
+};
+
+/// The bytes every encoded governance transaction starts with
+pub const GOVERNANCE_TRANSACTION_TAG: &[u8] = b"HOTSHOT_GOVERNANCE";
+
+/// The number of epochs after the epoch of the leaf deciding a change that it may take effect
+/// at, at the earliest
+pub const MIN_ACTIVATION_EPOCHS: u64 = 2;
+
+/// The consensus parameters governance transactions change
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConsensusParameters {
+    /// base duration of the next-view timeout, in milliseconds
+    pub next_view_timeout: u64,
+    /// maximum number of transactions per block
+    pub max_transactions: NonZeroUsize,
+    /// size of the DA committee
+    pub da_committee_size: u64,
+}
+
+impl ConsensusParameters {
+    /// These parameters, with the fields set by `change` replaced
+    #[must_use]
+    pub fn with_change(mut self, change: &ParameterChange) -> Self {
+        if let Some(next_view_timeout) = change.next_view_timeout {
+            self.next_view_timeout = next_view_timeout;
+        }
+        if let Some(max_transactions) = change.max_transactions {
+            self.max_transactions = max_transactions;
+        }
+        if let Some(da_committee_size) = change.da_committee_size {
+            self.da_committee_size = da_committee_size;
+        }
+        self
+    }
+}
+
+/// A change of some of the consensus parameters. The fields left unset keep their value.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ParameterChange {
+    /// the new base duration of the next-view timeout, in milliseconds
+    pub next_view_timeout: Option<u64>,
+    /// the new maximum number of transactions per block
+    pub max_transactions: Option<NonZeroUsize>,
+    /// the new size of the DA committee
+    pub da_committee_size: Option<u64>,
+}
+
+impl ParameterChange {
+    /// This change, with the fields set by `later` replaced
+    #[must_use]
+    pub fn merged_with(self, later: &Self) -> Self {
+        Self {
+            next_view_timeout: later.next_view_timeout.or(self.next_view_timeout),
+            max_transactions: later.max_transactions.or(self.max_transactions),
+            da_committee_size: later.da_committee_size.or(self.da_committee_size),
+        }
+    }
+}
+
+/// A transaction scheduling a change of the consensus parameters from an epoch on
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GovernanceTransaction {
+    /// the epoch from whose first view the change takes effect
+    pub activation_epoch: Epoch,
+    /// the change
+    pub change: ParameterChange,
+    /// what the [`GovernancePolicy`] of the nodes authorizes the change by, e.g. the signature
+    /// of [`GovernanceTransaction::signed_bytes`] [`SignedByAuthority`] checks
+    pub authorization: Vec<u8>,
+}
+
+impl GovernanceTransaction {
+    /// A transaction scheduling `change` from `activation_epoch` on, without authorization
+    #[must_use]
+    pub fn new(activation_epoch: Epoch, change: ParameterChange) -> Self {
+        Self {
+            activation_epoch,
+            change,
+            authorization: Vec::new(),
+        }
+    }
+
+    /// This transaction, authorized by the signature of `private_key` which
+    /// [`SignedByAuthority`] checks
+    ///
+    /// # Errors
+    ///
+    /// If signing fails
+    pub fn signed<KEY: SignatureKey>(
+        mut self,
+        private_key: &KEY::PrivateKey,
+    ) -> Result<Self, KEY::SignError> {
+        let signature =
+            signing::sign::<KEY>(private_key, SigningDomain::Governance, &self.signed_bytes())?;
+        self.authorization = bincode_opts().serialize(&signature).unwrap_or_default();
+        Ok(self)
+    }
+
+    /// The bytes an authority signs: the activation epoch and the change, without the
+    /// authorization
+    #[must_use]
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let change = &self.change;
+        let mut bytes = self.activation_epoch.to_le_bytes().to_vec();
+        for field in [
+            change.next_view_timeout,
+            change.max_transactions.map(|max| max.get() as u64),
+            change.da_committee_size,
+        ] {
+            bytes.push(u8::from(field.is_some()));
+            bytes.extend(field.unwrap_or_default().to_le_bytes());
+        }
+        bytes
+    }
+
+    /// The bytes of this transaction, [`GOVERNANCE_TRANSACTION_TAG`] followed by its fields
+    ///
+    /// # Panics
+    ///
+    /// If the transaction cannot be serialized, which it always can
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = GOVERNANCE_TRANSACTION_TAG.to_vec();
+        bytes.extend(
+            bincode_opts()
+                .serialize(self)
+                .expect("governance transactions serialize"),
+        );
+        bytes
+    }
+
+    /// The governance transaction `bytes` encode, if they encode one
+    #[must_use]
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let fields = bytes.strip_prefix(GOVERNANCE_TRANSACTION_TAG)?;
+        bincode_opts().deserialize(fields).ok()
+    }
+}
+
+/// Add `transactions` to `builder` field by field, for the commitments of leaves and DA votes
+/// which cover the governance transactions of a block
+#[must_use]
+pub fn commit_governance<T: Committable>(
+    builder: RawCommitmentBuilder<T>,
+    transactions: &[GovernanceTransaction],
+) -> RawCommitmentBuilder<T> {
+    transactions.iter().fold(
+        builder.u64_field("governance transactions", transactions.len() as u64),
+        |builder, transaction| {
+            builder
+                .var_size_field("governance transaction", &transaction.signed_bytes())
+                .var_size_field("governance authorization", &transaction.authorization)
+        },
+    )
+}
+
+/// Decides which decided governance transactions change the consensus parameters. Every node
+/// of a network must run with the same policy, or they schedule different changes.
+pub trait GovernancePolicy: Send + Sync + 'static {
+    /// Whether `transaction` may change the consensus parameters
+    fn authorizes(&self, transaction: &GovernanceTransaction) -> bool;
+}
+
+/// The policy of networks whose parameters only change with their configuration: no
+/// transaction is authorized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoGovernance;
+
+impl GovernancePolicy for NoGovernance {
+    fn authorizes(&self, _transaction: &GovernanceTransaction) -> bool {
+        false
+    }
+}
+
+/// The policy of networks governed by a set of keys: a transaction is authorized if any of the
+/// authorities signed it, see [`GovernanceTransaction::signed`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedByAuthority<KEY: SignatureKey> {
+    /// the keys which may change the parameters
+    authorities: Vec<KEY>,
+}
+
+impl<KEY: SignatureKey> SignedByAuthority<KEY> {
+    /// A policy authorizing the transactions signed by any of `authorities`
+    #[must_use]
+    pub fn new(authorities: Vec<KEY>) -> Self {
+        Self { authorities }
+    }
+}
+
+impl<KEY: SignatureKey + 'static> GovernancePolicy for SignedByAuthority<KEY> {
+    fn authorizes(&self, transaction: &GovernanceTransaction) -> bool {
+        let Ok(signature) = bincode_opts()
+            .deserialize::<KEY::PureAssembledSignatureType>(&transaction.authorization)
+        else {
+            return false;
+        };
+        let message = transaction.signed_bytes();
+        self.authorities.iter().any(|authority| {
+            signing::validate(authority, &signature, SigningDomain::Governance, &message)
+        })
+    }
+}
+
+/// Why a governance transaction was not scheduled
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[snafu(visibility(pub))]
+pub enum GovernanceError {
+    /// The network runs without epochs, so changes have no boundary to take effect at
+    #[snafu(display("Parameter changes need epochs"))]
+    EpochsDisabled,
+    /// The activation epoch is too close to the epoch of the leaf deciding the change
+    #[snafu(display("Change activates at epoch {activation_epoch}, before epoch {earliest}"))]
+    TooLate {
+        /// The epoch the change was to take effect at
+        activation_epoch: Epoch,
+        /// The earliest epoch it could take effect at
+        earliest: Epoch,
+    },
+    /// The change sets none of the parameters
+    #[snafu(display("Parameter change sets no parameter"))]
+    EmptyChange,
+    /// The change sets a parameter to a value it may not take
+    #[snafu(display("Invalid parameter change: {reason}"))]
+    InvalidParameter {
+        /// What is wrong with the value
+        reason: String,
+    },
+}
+
+/// The consensus parameters of every epoch: those a node started with, and the changes of the
+/// governance transactions decided since. Nodes persist it, see
+/// [`Storage::append_parameter_schedule`](crate::traits::storage::Storage), so that a restarted
+/// node runs with the changes decided before it stopped.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ParameterSchedule {
+    /// the parameters before any change
+    initial: ConsensusParameters,
+    /// how views are divided into epochs, if they are
+    epochs: Option<EpochSchedule>,
+    /// the number of nodes, which the DA committee may not be larger than
+    total_nodes: u64,
+    /// the changes, by the epoch they take effect at
+    changes: BTreeMap<Epoch, ParameterChange>,
+    /// the last epoch whose epoch change certificate was seen, the genesis one needing none
+    certified: Epoch,
+}
+
+impl ParameterSchedule {
+    /// A schedule of the `initial` parameters of a network of `total_nodes` nodes, whose views
+    /// are divided into `epochs` if they are
+    #[must_use]
+    pub fn new(
+        initial: ConsensusParameters,
+        epochs: Option<EpochSchedule>,
+        total_nodes: u64,
+    ) -> Self {
+        Self {
+            initial,
+            epochs,
+            total_nodes,
+            changes: BTreeMap::new(),
+            certified: Epoch::default(),
+        }
+    }
+
+    /// Schedule `transaction`, decided in the leaf of `decided_view`. A change for an epoch
+    /// which already has one replaces the parameters both set, the later one winning.
+    ///
+    /// # Errors
+    ///
+    /// If the network runs without epochs, the activation epoch is fewer than
+    /// [`MIN_ACTIVATION_EPOCHS`] after the epoch of `decided_view`, or the change is empty or
+    /// invalid
+    pub fn schedule<TIME: ConsensusTime>(
+        &mut self,
+        decided_view: TIME,
+        transaction: &GovernanceTransaction,
+    ) -> Result<(), GovernanceError> {
+        let Some(epochs) = self.epochs else {
+            return Err(GovernanceError::EpochsDisabled);
+        };
+        let earliest = Epoch::new(
+            epochs
+                .epoch_of(decided_view)
+                .saturating_add(MIN_ACTIVATION_EPOCHS),
+        );
+        if transaction.activation_epoch < earliest {
+            return Err(GovernanceError::TooLate {
+                activation_epoch: transaction.activation_epoch,
+                earliest,
+            });
+        }
+        self.validate(&transaction.change)?;
+        let change = self
+            .changes
+            .entry(transaction.activation_epoch)
+            .or_default();
+        *change = change.merged_with(&transaction.change);
+        Ok(())
+    }
+
+    /// Check that `change` sets some parameter, and every one it sets to a value it may take
+    fn validate(&self, change: &ParameterChange) -> Result<(), GovernanceError> {
+        if *change == ParameterChange::default() {
+            return Err(GovernanceError::EmptyChange);
+        }
+        if change.next_view_timeout == Some(0) {
+            return Err(GovernanceError::InvalidParameter {
+                reason: "the next-view timeout must be positive".to_string(),
+            });
+        }
+        if let Some(size) = change.da_committee_size {
+            if size == 0 || size > self.total_nodes {
+                return Err(GovernanceError::InvalidParameter {
+                    reason: format!(
+                        "the DA committee size must be between 1 and {}, not {size}",
+                        self.total_nodes
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The parameters `epoch` runs with
+    #[must_use]
+    pub fn parameters_at(&self, epoch: Epoch) -> ConsensusParameters {
+        self.changes
+            .range(..=epoch)
+            .fold(self.initial, |parameters, (_, change)| {
+                parameters.with_change(change)
+            })
+    }
+
+    /// Record that the epoch change certificate of `epoch` was seen, so its parameters are in
+    /// effect from its first view on
+    pub fn certify(&mut self, epoch: Epoch) {
+        self.certified = self.certified.max(epoch);
+    }
+
+    /// The last epoch whose epoch change certificate was seen
+    #[must_use]
+    pub fn certified(&self) -> Epoch {
+        self.certified
+    }
+
+    /// The parameters `view` runs with: those of its epoch if the epoch was certified, and
+    /// otherwise those of the last certified epoch, until its certificate is seen
+    #[must_use]
+    pub fn parameters_at_view<TIME: ConsensusTime>(&self, view: TIME) -> ConsensusParameters {
+        match self.epochs {
+            Some(epochs) => self.parameters_at(epochs.epoch_of(view).min(self.certified)),
+            None => self.initial,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Merkle inclusion proofs of transactions within a block payload
+//!
+//! The tree is built over the transaction commitments of a payload, in order. Leaves and inner
+//! nodes are hashed with distinct prefixes, and a node without a sibling is promoted to the next
+//! level unchanged rather than paired with a copy of itself.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A SHA-256 digest
+type Sha256Digest = [u8; 32];
+
+/// Prefix of a hashed leaf
+const LEAF_PREFIX: u8 = 0;
+
+/// Prefix of a hashed inner node
+const NODE_PREFIX: u8 = 1;
+
+/// Merkle root over the transaction commitments of a block payload
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TransactionsRoot(Sha256Digest);
+
+impl TransactionsRoot {
+    /// Compute the root over `commitments`, in order
+    #[must_use]
+    pub fn from_commitments<C: AsRef<[u8]>>(commitments: &[C]) -> Self {
+        let mut level: Vec<_> = commitments.iter().map(hash_leaf).collect();
+        if level.is_empty() {
+            return Self(Sha256::digest(b"").into());
+        }
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!("chunks of two"),
+                })
+                .collect();
+        }
+        Self(level[0])
+    }
+}
+
+impl AsRef<Sha256Digest> for TransactionsRoot {
+    fn as_ref(&self) -> &Sha256Digest {
+        &self.0
+    }
+}
+
+/// Proof that a transaction commitment is included, at a given position, in the tree with some
+/// [`TransactionsRoot`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TransactionInclusionProof {
+    /// Position of the transaction within the payload
+    pub index: u64,
+    /// Number of transactions in the payload
+    pub num_transactions: u64,
+    /// Sibling hashes from the leaf up to the root, skipping levels where the path has none
+    pub siblings: Vec<Sha256Digest>,
+}
+
+impl TransactionInclusionProof {
+    /// Build the proof for the commitment at `index`, or `None` if it is out of range
+    #[must_use]
+    pub fn new<C: AsRef<[u8]>>(commitments: &[C], index: usize) -> Option<Self> {
+        if index >= commitments.len() {
+            return None;
+        }
+        let mut level: Vec<_> = commitments.iter().map(hash_leaf).collect();
+        let mut position = index;
+        let mut siblings = Vec::new();
+        while level.len() > 1 {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!("chunks of two"),
+                })
+                .collect();
+            position /= 2;
+        }
+        Some(Self {
+            index: index as u64,
+            num_transactions: commitments.len() as u64,
+            siblings,
+        })
+    }
+
+    /// Check that `commitment` is included at this proof's position in the tree with `root`
+    #[must_use]
+    pub fn verify(&self, commitment: impl AsRef<[u8]>, root: &TransactionsRoot) -> bool {
+        if self.index >= self.num_transactions {
+            return false;
+        }
+        let mut hash = hash_leaf(commitment);
+        let mut position = self.index;
+        let mut width = self.num_transactions;
+        let mut siblings = self.siblings.iter();
+        while width > 1 {
+            let sibling = position ^ 1;
+            if sibling < width {
+                let Some(sibling_hash) = siblings.next() else {
+                    return false;
+                };
+                hash = if position % 2 == 0 {
+                    hash_node(&hash, sibling_hash)
+                } else {
+                    hash_node(sibling_hash, &hash)
+                };
+            }
+            position /= 2;
+            width = (width + 1) / 2;
+        }
+        siblings.next().is_none() && hash == root.0
+    }
+}
+
+/// Hash a transaction commitment into a leaf
+fn hash_leaf(commitment: impl AsRef<[u8]>) -> Sha256Digest {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(commitment.as_ref());
+    hasher.finalize().into()
+}
+
+/// Hash two children into their parent
+fn hash_node(left: &Sha256Digest, right: &Sha256Digest) -> Sha256Digest {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TransactionInclusionProof, TransactionsRoot};
+
+    #[test]
+    fn proofs_verify_for_every_position() {
+        for len in 1..=9u8 {
+            let commitments: Vec<[u8; 4]> = (0..len).map(|i| [i; 4]).collect();
+            let root = TransactionsRoot::from_commitments(&commitments);
+            for (index, commitment) in commitments.iter().enumerate() {
+                let proof = TransactionInclusionProof::new(&commitments, index).unwrap();
+                assert!(proof.verify(commitment, &root));
+                assert!(!proof.verify([len; 4], &root));
+            }
+            assert!(TransactionInclusionProof::new(&commitments, commitments.len()).is_none());
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Derivation of validator identities from a BIP-39 mnemonic
+//!
+//! A validator's consensus key and libp2p identity can both be recovered from a single seed
+//! phrase instead of being generated from raw seeds. The phrase is turned into a BIP-39 seed,
+//! from which a separate seed is derived for each kind of key, so that knowing one key says
+//! nothing about the other. Keys are then generated from these seeds and the validator's
+//! account index, the same way they are from raw seeds.
+
+use snafu::Snafu;
+
+/// Context of the seed consensus keys are generated from
+const CONSENSUS_SEED_CONTEXT: &str = "HotShot 2024 consensus key seed";
+/// Context of the seed libp2p identities are generated from
+const LIBP2P_SEED_CONTEXT: &str = "HotShot 2024 libp2p identity seed";
+
+/// Errors deriving keys from a mnemonic
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum KeyDerivationError {
+    /// The phrase is not a valid BIP-39 mnemonic
+    #[snafu(display("Invalid BIP-39 mnemonic: {reason}"))]
+    InvalidMnemonic {
+        /// Why the phrase was rejected
+        reason: String,
+    },
+}
+
+/// The seeds a validator's keys are generated from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DerivedSeeds {
+    /// seed of the consensus signature key and the state key pair
+    pub consensus: [u8; 32],
+    /// seed of the libp2p identity
+    pub libp2p: [u8; 32],
+}
+
+impl DerivedSeeds {
+    /// Use a raw seed for every key, as configs without a mnemonic do
+    #[must_use]
+    pub fn from_raw(seed: [u8; 32]) -> Self {
+        Self {
+            consensus: seed,
+            libp2p: seed,
+        }
+    }
+
+    /// Derive the seeds from a BIP-39 mnemonic, without a passphrase
+    /// # Errors
+    /// If `phrase` is not a valid BIP-39 mnemonic
+    pub fn from_mnemonic(phrase: &str) -> Result<Self, KeyDerivationError> {
+        let mnemonic = bip39::Mnemonic::parse_normalized(phrase).map_err(|e| {
+            KeyDerivationError::InvalidMnemonic {
+                reason: e.to_string(),
+            }
+        })?;
+        let seed = mnemonic.to_seed_normalized("");
+        Ok(Self {
+            consensus: blake3::derive_key(CONSENSUS_SEED_CONTEXT, &seed),
+            libp2p: blake3::derive_key(LIBP2P_SEED_CONTEXT, &seed),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DerivedSeeds, KeyDerivationError};
+    use crate::{signature_key::BLSPubKey, ValidatorConfig};
+
+    /// The mnemonic of the BIP-39 test vectors
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                          abandon abandon abandon about";
+
+    #[test]
+    fn keys_are_derived_from_mnemonic() {
+        let seeds = DerivedSeeds::from_mnemonic(PHRASE).unwrap();
+        assert_eq!(seeds, DerivedSeeds::from_mnemonic(PHRASE).unwrap());
+        assert_ne!(seeds.consensus, seeds.libp2p);
+
+        let first = ValidatorConfig::<BLSPubKey>::from_mnemonic(PHRASE, 0, 1).unwrap();
+        let again = ValidatorConfig::<BLSPubKey>::from_mnemonic(PHRASE, 0, 1).unwrap();
+        let second = ValidatorConfig::<BLSPubKey>::from_mnemonic(PHRASE, 1, 1).unwrap();
+        assert_eq!(first.public_key, again.public_key);
+        assert_ne!(first.public_key, second.public_key);
+
+        assert!(matches!(
+            DerivedSeeds::from_mnemonic("abandon abandon abandon"),
+            Err(KeyDerivationError::InvalidMnemonic { .. })
+        ));
+    }
+}
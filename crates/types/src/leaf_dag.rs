@@ -0,0 +1,138 @@
+//! Export of the in-memory leaf DAG, for visualizing forks and orphaned proposals
+
+use std::{collections::HashSet, fmt::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{consensus::Consensus, traits::node_implementation::NodeType};
+
+/// Where a leaf stands relative to the decided chain
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeafStatus {
+    /// The leaf is the last decided leaf or one of its ancestors
+    Decided,
+    /// The leaf is at or before the last decided view but not on the decided chain
+    Orphaned,
+    /// The leaf is after the last decided view
+    Undecided,
+}
+
+/// A single proposal in the leaf DAG
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LeafDagNode {
+    /// commitment of the leaf
+    pub commitment: String,
+    /// view the leaf was proposed in
+    pub view: u64,
+    /// commitment of the parent leaf
+    pub parent: String,
+    /// view of the QC the leaf extends
+    pub justify_qc_view: u64,
+    /// where the leaf stands relative to the decided chain
+    pub status: LeafStatus,
+}
+
+/// A snapshot of every leaf consensus currently holds in memory, linked by parent commitment
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeafDag {
+    /// the last decided view
+    pub last_decided_view: u64,
+    /// the locked view
+    pub locked_view: u64,
+    /// view of the high QC
+    pub high_qc_view: u64,
+    /// the leaves, ordered by view
+    pub nodes: Vec<LeafDagNode>,
+}
+
+impl LeafDag {
+    /// Snapshot the leaves held by `consensus`
+    #[must_use]
+    pub fn from_consensus<TYPES: NodeType>(consensus: &Consensus<TYPES>) -> Self {
+        let mut decided = HashSet::new();
+        if let Some(mut commitment) = consensus
+            .validated_state_map
+            .get(&consensus.last_decided_view)
+            .and_then(|view| view.get_leaf_commitment())
+        {
+            while let Some(leaf) = consensus.saved_leaves.get(&commitment) {
+                if !decided.insert(commitment) {
+                    break;
+                }
+                commitment = leaf.parent_commitment;
+            }
+        }
+
+        let mut nodes: Vec<_> = consensus
+            .saved_leaves
+            .iter()
+            .map(|(commitment, leaf)| {
+                let status = if decided.contains(commitment) {
+                    LeafStatus::Decided
+                } else if leaf.view_number <= consensus.last_decided_view {
+                    LeafStatus::Orphaned
+                } else {
+                    LeafStatus::Undecided
+                };
+                LeafDagNode {
+                    commitment: format!("{commitment:?}"),
+                    view: *leaf.view_number,
+                    parent: format!("{:?}", leaf.parent_commitment),
+                    justify_qc_view: *leaf.justify_qc.view_number,
+                    status,
+                }
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.view);
+
+        Self {
+            last_decided_view: *consensus.last_decided_view,
+            locked_view: *consensus.locked_view,
+            high_qc_view: *consensus.high_qc.view_number,
+            nodes,
+        }
+    }
+
+    /// Render the DAG in graphviz DOT format. Decided leaves are green, orphaned ones red.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph leaves {\n    rankdir=LR;\n");
+        let known: HashSet<&str> = self.nodes.iter().map(|n| n.commitment.as_str()).collect();
+        for node in &self.nodes {
+            let color = match node.status {
+                LeafStatus::Decided => "green",
+                LeafStatus::Orphaned => "red",
+                LeafStatus::Undecided => "black",
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"view {}\\njustify qc {}\" color={color}];",
+                escape(&node.commitment),
+                node.view,
+                node.justify_qc_view
+            );
+            if known.contains(node.parent.as_str()) {
+                let _ = writeln!(
+                    dot,
+                    "    \"{}\" -> \"{}\";",
+                    escape(&node.commitment),
+                    escape(&node.parent)
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the DAG as JSON
+    /// # Errors
+    /// If serialization fails
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Escape a string for use as a quoted DOT identifier
+fn escape(id: &str) -> String {
+    id.replace('\\', "\\\\").replace('"', "\\\"")
+}
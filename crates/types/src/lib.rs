@@ -1,20 +1,45 @@
 //! Types and Traits for the `HotShot` consensus module
 use displaydoc::Display;
+use payload_store::PayloadSpillConfig;
 use std::{future::Future, num::NonZeroUsize, pin::Pin, time::Duration};
 use traits::{election::ElectionConfig, signature_key::SignatureKey};
+pub mod anchor;
+pub mod bandwidth;
 pub mod consensus;
 pub mod data;
+pub mod epoch;
 pub mod error;
 pub mod event;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod genesis;
+pub mod governance;
+pub mod inclusion_proof;
+pub mod key_derivation;
+pub mod leaf_dag;
 pub mod light_client;
 pub mod message;
+pub mod payload_store;
+pub mod payload_validation;
 pub mod qc;
+pub mod qc_chain;
+pub mod receipt;
+pub mod reputation;
+pub mod rpc;
+pub mod runtime;
+pub mod safety;
 pub mod signature_key;
+pub mod signing;
 pub mod simple_certificate;
 pub mod simple_vote;
 pub mod stake_table;
+pub mod stake_table_cache;
+pub mod stall;
+pub mod statsd;
+pub mod storage_sync;
 pub mod traits;
 pub mod utils;
+pub mod view_latency;
 pub mod vote;
 
 /// Pinned future that is Send and Sync
@@ -74,6 +99,22 @@ impl<KEY: SignatureKey> ValidatorConfig<KEY> {
             state_key_pair: state_key_pairs,
         }
     }
+
+    /// generate validator config from a BIP-39 mnemonic, account index and stake value
+    /// # Errors
+    /// If `phrase` is not a valid BIP-39 mnemonic
+    pub fn from_mnemonic(
+        phrase: &str,
+        account_index: u64,
+        stake_value: u64,
+    ) -> Result<Self, key_derivation::KeyDerivationError> {
+        let seeds = key_derivation::DerivedSeeds::from_mnemonic(phrase)?;
+        Ok(Self::generated_from_seed_indexed(
+            seeds.consensus,
+            account_index,
+            stake_value,
+        ))
+    }
 }
 
 impl<KEY: SignatureKey> Default for ValidatorConfig<KEY> {
@@ -86,6 +127,9 @@ impl<KEY: SignatureKey> Default for ValidatorConfig<KEY> {
 #[derive(Clone, custom_debug::Debug, serde::Serialize, serde::Deserialize)]
 #[serde(bound(deserialize = ""))]
 pub struct HotShotConfig<KEY: SignatureKey, ELECTIONCONFIG: ElectionConfig> {
+    /// The chain id of the network, from its genesis file. Messages carrying another chain id
+    /// are dropped.
+    pub chain_id: u64,
     /// Whether to run one view or continuous views
     pub execution_type: ExecutionType,
     /// Total number of nodes in the network
@@ -94,6 +138,9 @@ pub struct HotShotConfig<KEY: SignatureKey, ELECTIONCONFIG: ElectionConfig> {
     pub min_transactions: usize,
     /// Maximum transactions per block
     pub max_transactions: NonZeroUsize,
+    /// Transactions per block reserved for the system lane, out of `max_transactions`. Capacity
+    /// the system lane leaves unused goes to user transactions.
+    pub system_transactions_per_block: usize,
     /// List of known node's public keys and stake value for certificate aggregation, serving as public parameter
     pub known_nodes_with_stake: Vec<KEY::StakeTableEntry>,
     /// My own validator config, including my public key, private key, stake value, serving as private parameter
@@ -102,6 +149,9 @@ pub struct HotShotConfig<KEY: SignatureKey, ELECTIONCONFIG: ElectionConfig> {
     pub da_committee_size: usize,
     /// Base duration for next-view timeout, in milliseconds
     pub next_view_timeout: u64,
+    /// How long a replica waits for the leader's proposal before reporting it missing, in
+    /// milliseconds. This does not end the view, which still runs until `next_view_timeout`.
+    pub proposal_timeout: u64,
     /// The exponential backoff ration for the next-view timeout
     pub timeout_ratio: (u64, u64),
     /// The delay a leader inserts before starting pre-commit, in milliseconds
@@ -116,4 +166,60 @@ pub struct HotShotConfig<KEY: SignatureKey, ELECTIONCONFIG: ElectionConfig> {
     pub propose_max_round_time: Duration,
     /// the election configuration
     pub election_config: Option<ELECTIONCONFIG>,
+    /// When block payloads are written to disk rather than held in memory
+    pub payload_spill: PayloadSpillConfig,
+    /// Number of views before the current view whose consensus messages are still handled.
+    /// Older ones are dropped as soon as they are received.
+    pub stale_view_grace: u64,
+    /// Milliseconds votes wait to be sent with the next votes for the same leader, in one
+    /// message. 0 sends every vote at once, in a message of its own.
+    pub vote_batch_delay_ms: u64,
+    /// Number of steps of the way to the threshold of a certificate at which a leader reports
+    /// the weight of the votes it collected, in vote progress events: 4 reports a quarter, half,
+    /// three quarters and all of the threshold. `None` reports no progress.
+    pub vote_progress_steps: Option<u64>,
+    /// Apply the block headers of proposals to the state of their parent on worker tasks, so
+    /// the consensus task goes on with other events meanwhile, committing the new states in
+    /// view order. `false` applies them on the consensus task, before anything else is handled.
+    pub parallel_state_application: bool,
+    /// Number of VID shares nodes outside the DA committee sample from it for every certified
+    /// payload, raising an alarm if any is not returned. 0 trusts the DA certificate alone.
+    pub availability_samples: usize,
+    /// Send the payload of DA proposals to each DA committee member in a direct message, and
+    /// broadcast just its commitment to the other nodes, rather than broadcasting the payload.
+    /// Needs a network which delivers direct messages, so not the web server network.
+    pub da_unicast_payload: bool,
+    /// Send DA proposals compact: the transactions other nodes announced holding through
+    /// mempool sync are replaced by their commitments, which DA committee members rebuild the
+    /// payload from, fetching the transactions they miss from the leader. Compact proposals go
+    /// to the committee members in direct messages, as with `da_unicast_payload`.
+    pub da_compact_proposals: bool,
+    /// Number of consecutive views without a DA certificate after which DA leaders disseminate
+    /// their proposals to the whole quorum, every node of which votes on them, rather than to
+    /// the DA committee. `None` never falls back. Needs a network which delivers direct
+    /// messages, so not the web server network.
+    pub da_fallback_after_failures: Option<u64>,
+    /// Number of views DA proposals are disseminated to the whole quorum for once fallen back,
+    /// before the DA committee is tried again
+    pub da_fallback_views: u64,
+    /// Sign messages without domain separation, as nodes predating it do. Only for networks
+    /// with such nodes, until they are upgraded.
+    pub legacy_signing: bool,
+    /// Number of views before the anchor whose stake tables are kept in storage, for their
+    /// certificates to be validated. `None` keeps every stake table, as light clients need.
+    pub stake_table_retention_views: Option<u64>,
+    /// Number of views before the anchor whose artifacts are kept in storage, for auditors to
+    /// re-verify them. `None` keeps the artifacts of every decided view.
+    pub view_artifact_retention_views: Option<u64>,
+    /// Number of views of every epoch. The quorum certifies the stake table and parameters of
+    /// every epoch in the last view of the one before, see [`epoch`]. `None` runs without epochs.
+    pub epoch_length: Option<u64>,
+    /// Timeouts and limits of request/response calls between nodes, see [`rpc`]
+    pub rpc: rpc::RpcConfig,
+    /// Milliseconds without a view change after which the node reports itself stalled, with
+    /// diagnostics of the view it is stuck in, see [`stall`]. `None` runs no watchdog.
+    pub stall_timeout: Option<u64>,
+    /// When the writes to storage are committed, and the write-ahead log of the leaves yet to
+    /// be committed, see [`storage_sync`]
+    pub storage_sync: storage_sync::StorageSyncConfig,
 }
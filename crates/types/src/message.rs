@@ -3,26 +3,31 @@
 //! This module contains types used to represent the various types of messages that
 //! `HotShot` nodes can send among themselves.
 
+use crate::anchor::AnchorAnnouncement;
 use crate::data::{QuorumProposal, UpgradeProposal};
+use crate::rpc::{RpcRequest, RpcResponse};
 use crate::simple_certificate::{
-    DACertificate, ViewSyncCommitCertificate2, ViewSyncFinalizeCertificate2,
-    ViewSyncPreCommitCertificate2,
+    DACertificate, EpochChangeCertificate, ViewSyncCommitCertificate2,
+    ViewSyncFinalizeCertificate2, ViewSyncPreCommitCertificate2,
 };
 use crate::simple_vote::{
-    DAVote, TimeoutVote, UpgradeVote, ViewSyncCommitVote, ViewSyncFinalizeVote,
+    DAVote, EpochChangeVote, TimeoutVote, UpgradeVote, ViewSyncCommitVote, ViewSyncFinalizeVote,
     ViewSyncPreCommitVote,
 };
 use crate::traits::signature_key::SignatureKey;
 use crate::vote::HasViewNumber;
 use crate::{
-    data::{DAProposal, VidDisperse},
+    data::{
+        CompactDAProposal, DAProposal, DAProposalCommitment, VidCommitment, VidDisperse, VidSample,
+    },
     simple_vote::QuorumVote,
     traits::{
-        network::{NetworkMsg, ViewMessage},
-        node_implementation::NodeType,
+        network::{MessagePriority, NetworkMsg, ViewMessage},
+        node_implementation::{ConsensusTime, NodeType},
     },
 };
 
+use commit::Commitment;
 use derivative::Derivative;
 use either::Either::{self, Left, Right};
 use hotshot_constants::Version;
@@ -37,6 +42,10 @@ pub struct Message<TYPES: NodeType> {
     /// The version of the protocol in use for this message
     pub version: Version,
 
+    /// The chain id of the network the message was sent on. Follows the version, so that the
+    /// version number stays at the start of the serialization.
+    pub chain_id: u64,
+
     /// The sender of this message
     pub sender: TYPES::SignatureKey,
 
@@ -44,7 +53,27 @@ pub struct Message<TYPES: NodeType> {
     pub kind: MessageKind<TYPES>,
 }
 
-impl<TYPES: NodeType> NetworkMsg for Message<TYPES> {}
+impl<TYPES: NodeType> NetworkMsg for Message<TYPES> {
+    fn priority(&self) -> MessagePriority {
+        match &self.kind {
+            MessageKind::Consensus(SequencingMessage(Left(_)))
+            | MessageKind::Data(DataMessage::AnchorAnnouncement(_)) => MessagePriority::Consensus,
+            MessageKind::Consensus(SequencingMessage(Right(_)))
+            | MessageKind::Data(
+                DataMessage::RequestVidShare(..)
+                | DataMessage::VidShareResponse(..)
+                | DataMessage::RpcRequest(..)
+                | DataMessage::RpcResponse(..),
+            ) => MessagePriority::DA,
+            MessageKind::Data(
+                DataMessage::SubmitTransaction(..)
+                | DataMessage::MempoolInventory(..)
+                | DataMessage::RequestTransactions(..)
+                | DataMessage::TransactionsResponse(..),
+            ) => MessagePriority::Transactions,
+        }
+    }
+}
 
 impl<TYPES: NodeType> ViewMessage<TYPES> for Message<TYPES> {
     /// get the view number out of a message
@@ -61,7 +90,7 @@ impl<TYPES: NodeType> ViewMessage<TYPES> for Message<TYPES> {
 pub struct Messages<TYPES: NodeType>(pub Vec<Message<TYPES>>);
 
 /// A message type agnostic description of a message's purpose
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum MessagePurpose {
     /// Message with a [quorum/DA] proposal.
     Proposal,
@@ -85,6 +114,41 @@ pub enum MessagePurpose {
     VidDisperse,
     /// Message with an upgrade proposal.
     Upgrade,
+    /// Announcement, request or transfer of mempool transactions
+    MempoolSync,
+    /// Request for, or response with, a VID share sampled to check a payload is available
+    AvailabilitySampling,
+    /// Announcement of the latest anchor
+    AnchorAnnouncement,
+    /// Message with an epoch change vote or certificate
+    EpochChange,
+    /// Request or response of a request/response call between two nodes
+    Rpc,
+}
+
+impl MessagePurpose {
+    /// The name of the class of messages with this purpose, as used in metrics
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Proposal => "proposal",
+            Self::LatestProposal => "latest_proposal",
+            Self::LatestViewSyncCertificate => "latest_view_sync_certificate",
+            Self::Vote => "vote",
+            Self::ViewSyncVote => "view_sync_vote",
+            Self::ViewSyncCertificate => "view_sync_certificate",
+            Self::DAC => "da_certificate",
+            Self::Internal => "internal",
+            Self::Data => "data",
+            Self::VidDisperse => "vid_disperse",
+            Self::Upgrade => "upgrade",
+            Self::MempoolSync => "mempool_sync",
+            Self::AvailabilitySampling => "availability_sampling",
+            Self::AnchorAnnouncement => "anchor_announcement",
+            Self::EpochChange => "epoch_change",
+            Self::Rpc => "rpc",
+        }
+    }
 }
 
 // TODO (da) make it more customized to the consensus layer, maybe separating the specific message
@@ -106,6 +170,31 @@ impl<TYPES: NodeType> MessageKind<TYPES> {
     pub fn from_consensus_message(m: SequencingMessage<TYPES>) -> Self {
         Self::Consensus(m)
     }
+
+    /// Whether this is a message nodes change views with when consensus fails to: a timeout
+    /// vote, possibly in a batch, or a view sync vote or certificate
+    #[must_use]
+    pub fn is_view_change(&self) -> bool {
+        match self {
+            MessageKind::Consensus(SequencingMessage(Left(message))) => match message {
+                GeneralConsensusMessage::TimeoutVote(_)
+                | GeneralConsensusMessage::ViewSyncPreCommitVote(_)
+                | GeneralConsensusMessage::ViewSyncCommitVote(_)
+                | GeneralConsensusMessage::ViewSyncFinalizeVote(_)
+                | GeneralConsensusMessage::ViewSyncPreCommitCertificate(_)
+                | GeneralConsensusMessage::ViewSyncCommitCertificate(_)
+                | GeneralConsensusMessage::ViewSyncFinalizeCertificate(_) => true,
+                GeneralConsensusMessage::VoteBatch(batch) => !batch.timeout_votes.is_empty(),
+                GeneralConsensusMessage::Proposal(_)
+                | GeneralConsensusMessage::Vote(_)
+                | GeneralConsensusMessage::UpgradeProposal(_)
+                | GeneralConsensusMessage::UpgradeVote(_)
+                | GeneralConsensusMessage::EpochChangeVote(_)
+                | GeneralConsensusMessage::EpochChangeCertificate(_) => false,
+            },
+            MessageKind::Consensus(SequencingMessage(Right(_))) | MessageKind::Data(_) => false,
+        }
+    }
 }
 
 impl<TYPES: NodeType> From<DataMessage<TYPES>> for MessageKind<TYPES> {
@@ -119,6 +208,20 @@ impl<TYPES: NodeType> ViewMessage<TYPES> for MessageKind<TYPES> {
         match &self {
             MessageKind::Consensus(message) => message.view_number(),
             MessageKind::Data(DataMessage::SubmitTransaction(_, v)) => *v,
+            MessageKind::Data(
+                DataMessage::MempoolInventory(_, v)
+                | DataMessage::RequestTransactions(_, v)
+                | DataMessage::TransactionsResponse(_, v),
+            ) => *v,
+            MessageKind::Data(DataMessage::RequestVidShare(_, v, _, _)) => *v,
+            MessageKind::Data(DataMessage::VidShareResponse(sample)) => sample.view_number,
+            MessageKind::Data(DataMessage::AnchorAnnouncement(announcement)) => {
+                announcement.leaf.view_number
+            }
+            // RPC messages are not of any view
+            MessageKind::Data(DataMessage::RpcRequest(_) | DataMessage::RpcResponse(_)) => {
+                TYPES::Time::genesis()
+            }
         }
     }
 
@@ -127,6 +230,14 @@ impl<TYPES: NodeType> ViewMessage<TYPES> for MessageKind<TYPES> {
             MessageKind::Consensus(message) => message.purpose(),
             MessageKind::Data(message) => match message {
                 DataMessage::SubmitTransaction(_, _) => MessagePurpose::Data,
+                DataMessage::MempoolInventory(_, _)
+                | DataMessage::RequestTransactions(_, _)
+                | DataMessage::TransactionsResponse(_, _) => MessagePurpose::MempoolSync,
+                DataMessage::RequestVidShare(_, _, _, _) | DataMessage::VidShareResponse(_) => {
+                    MessagePurpose::AvailabilitySampling
+                }
+                DataMessage::AnchorAnnouncement(_) => MessagePurpose::AnchorAnnouncement,
+                DataMessage::RpcRequest(_) | DataMessage::RpcResponse(_) => MessagePurpose::Rpc,
             },
         }
     }
@@ -175,6 +286,9 @@ impl<TYPES: NodeType> ProcessedGeneralConsensusMessage<TYPES> {
             GeneralConsensusMessage::ViewSyncFinalizeCertificate(_) => unimplemented!(),
             GeneralConsensusMessage::UpgradeProposal(_) => unimplemented!(),
             GeneralConsensusMessage::UpgradeVote(_) => unimplemented!(),
+            GeneralConsensusMessage::EpochChangeVote(_) => unimplemented!(),
+            GeneralConsensusMessage::EpochChangeCertificate(_) => unimplemented!(),
+            GeneralConsensusMessage::VoteBatch(_) => unimplemented!(),
         }
     }
 }
@@ -191,6 +305,18 @@ pub enum ProcessedCommitteeConsensusMessage<TYPES: NodeType> {
     DACertificate(DACertificate<TYPES>, TYPES::SignatureKey),
     /// VID dispersal data. Like [`DAProposal`]
     VidDisperseMsg(Proposal<TYPES, VidDisperse<TYPES>>, TYPES::SignatureKey),
+    /// Commitment of a DA proposal whose payload was sent to the DA committee only.
+    DAProposalCommitment(
+        Proposal<TYPES, DAProposalCommitment<TYPES>>,
+        TYPES::SignatureKey,
+    ),
+    /// DA proposal disseminated to the whole quorum.
+    DAFallbackProposal(Proposal<TYPES, DAProposal<TYPES>>, TYPES::SignatureKey),
+    /// DA proposal carrying the commitments of the transactions the committee is believed to hold.
+    CompactDAProposal(
+        Proposal<TYPES, CompactDAProposal<TYPES>>,
+        TYPES::SignatureKey,
+    ),
 }
 
 impl<TYPES: NodeType> From<ProcessedCommitteeConsensusMessage<TYPES>>
@@ -210,6 +336,15 @@ impl<TYPES: NodeType> From<ProcessedCommitteeConsensusMessage<TYPES>>
             ProcessedCommitteeConsensusMessage::VidDisperseMsg(disperse, _) => {
                 CommitteeConsensusMessage::VidDisperseMsg(disperse)
             }
+            ProcessedCommitteeConsensusMessage::DAProposalCommitment(p, _) => {
+                CommitteeConsensusMessage::DAProposalCommitment(p)
+            }
+            ProcessedCommitteeConsensusMessage::DAFallbackProposal(p, _) => {
+                CommitteeConsensusMessage::DAFallbackProposal(p)
+            }
+            ProcessedCommitteeConsensusMessage::CompactDAProposal(p, _) => {
+                CommitteeConsensusMessage::CompactDAProposal(p)
+            }
         }
     }
 }
@@ -230,6 +365,15 @@ impl<TYPES: NodeType> ProcessedCommitteeConsensusMessage<TYPES> {
             CommitteeConsensusMessage::VidDisperseMsg(disperse) => {
                 ProcessedCommitteeConsensusMessage::VidDisperseMsg(disperse, sender)
             }
+            CommitteeConsensusMessage::DAProposalCommitment(p) => {
+                ProcessedCommitteeConsensusMessage::DAProposalCommitment(p, sender)
+            }
+            CommitteeConsensusMessage::DAFallbackProposal(p) => {
+                ProcessedCommitteeConsensusMessage::DAFallbackProposal(p, sender)
+            }
+            CommitteeConsensusMessage::CompactDAProposal(p) => {
+                ProcessedCommitteeConsensusMessage::CompactDAProposal(p, sender)
+            }
         }
     }
 }
@@ -291,6 +435,78 @@ pub enum GeneralConsensusMessage<TYPES: NodeType> {
 
     /// Message with an upgrade vote
     UpgradeVote(UpgradeVote<TYPES>),
+
+    /// Message with a vote on the stake table and parameters of the next epoch
+    EpochChangeVote(EpochChangeVote<TYPES>),
+
+    /// Message with the certificate of the stake table and parameters of the next epoch
+    EpochChangeCertificate(EpochChangeCertificate<TYPES>),
+
+    /// Message with the votes of a node for several views, sent to one leader at once
+    VoteBatch(VoteBatch<TYPES>),
+}
+
+/// Quorum and timeout votes of a node for several views, sent to their leader in one message,
+/// e.g. when the node catches up and votes for many views in a row
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
+#[serde(bound(deserialize = "", serialize = ""))]
+pub struct VoteBatch<TYPES: NodeType> {
+    /// the quorum votes, in the order they were cast
+    pub quorum_votes: Vec<QuorumVote<TYPES>>,
+    /// the timeout votes, in the order they were cast
+    pub timeout_votes: Vec<TimeoutVote<TYPES>>,
+}
+
+impl<TYPES: NodeType> Default for VoteBatch<TYPES> {
+    fn default() -> Self {
+        Self {
+            quorum_votes: Vec::new(),
+            timeout_votes: Vec::new(),
+        }
+    }
+}
+
+impl<TYPES: NodeType> VoteBatch<TYPES> {
+    /// The number of votes in the batch
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.quorum_votes.len() + self.timeout_votes.len()
+    }
+
+    /// Whether the batch has no votes
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The latest view the batch has a vote for, or genesis if it has none
+    #[must_use]
+    pub fn latest_view(&self) -> TYPES::Time {
+        self.quorum_votes
+            .iter()
+            .map(HasViewNumber::get_view_number)
+            .chain(
+                self.timeout_votes
+                    .iter()
+                    .map(HasViewNumber::get_view_number),
+            )
+            .max()
+            .unwrap_or_else(TYPES::Time::genesis)
+    }
+
+    /// The batch as a message, or a message with its only vote. [`None`] if it has no votes.
+    #[must_use]
+    pub fn into_message(mut self) -> Option<GeneralConsensusMessage<TYPES>> {
+        match (self.quorum_votes.len(), self.timeout_votes.len()) {
+            (0, 0) => None,
+            (1, 0) => self.quorum_votes.pop().map(GeneralConsensusMessage::Vote),
+            (0, 1) => self
+                .timeout_votes
+                .pop()
+                .map(GeneralConsensusMessage::TimeoutVote),
+            _ => Some(GeneralConsensusMessage::VoteBatch(self)),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Hash, Eq)]
@@ -311,6 +527,18 @@ pub enum CommitteeConsensusMessage<TYPES: NodeType> {
     /// Like [`DAProposal`]. Use `Msg` suffix to distinguish from [`VidDisperse`].
     /// TODO this variant should not be a [`CommitteeConsensusMessage`] because <https://github.com/EspressoSystems/HotShot/issues/1696>
     VidDisperseMsg(Proposal<TYPES, VidDisperse<TYPES>>),
+
+    /// Commitment of a DA proposal, for the nodes outside the DA committee when the payload
+    /// itself is sent to the committee members only
+    DAProposalCommitment(Proposal<TYPES, DAProposalCommitment<TYPES>>),
+
+    /// DA proposal disseminated to every node of the quorum, each of which votes on it, after
+    /// the DA committee failed to certify several payloads in a row
+    DAFallbackProposal(Proposal<TYPES, DAProposal<TYPES>>),
+
+    /// DA proposal whose transactions the committee members are believed to hold are replaced by
+    /// their commitments, sent to the committee members only
+    CompactDAProposal(Proposal<TYPES, CompactDAProposal<TYPES>>),
 }
 
 /// Messages for sequencing consensus.
@@ -357,6 +585,11 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
                         message.data.get_view_number()
                     }
                     GeneralConsensusMessage::UpgradeVote(message) => message.get_view_number(),
+                    GeneralConsensusMessage::EpochChangeVote(message) => message.get_view_number(),
+                    GeneralConsensusMessage::EpochChangeCertificate(message) => {
+                        message.get_view_number()
+                    }
+                    GeneralConsensusMessage::VoteBatch(batch) => batch.latest_view(),
                 }
             }
             Right(committee_message) => {
@@ -373,6 +606,9 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
                     CommitteeConsensusMessage::VidDisperseMsg(disperse) => {
                         disperse.data.get_view_number()
                     }
+                    CommitteeConsensusMessage::DAProposalCommitment(p) => p.data.get_view_number(),
+                    CommitteeConsensusMessage::DAFallbackProposal(p) => p.data.get_view_number(),
+                    CommitteeConsensusMessage::CompactDAProposal(p) => p.data.get_view_number(),
                 }
             }
         }
@@ -385,9 +621,9 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
         match &self.0 {
             Left(general_message) => match general_message {
                 GeneralConsensusMessage::Proposal(_) => MessagePurpose::Proposal,
-                GeneralConsensusMessage::Vote(_) | GeneralConsensusMessage::TimeoutVote(_) => {
-                    MessagePurpose::Vote
-                }
+                GeneralConsensusMessage::Vote(_)
+                | GeneralConsensusMessage::TimeoutVote(_)
+                | GeneralConsensusMessage::VoteBatch(_) => MessagePurpose::Vote,
                 GeneralConsensusMessage::ViewSyncPreCommitVote(_)
                 | GeneralConsensusMessage::ViewSyncCommitVote(_)
                 | GeneralConsensusMessage::ViewSyncFinalizeVote(_) => MessagePurpose::ViewSyncVote,
@@ -400,9 +636,15 @@ impl<TYPES: NodeType> SequencingMessage<TYPES> {
 
                 GeneralConsensusMessage::UpgradeProposal(_)
                 | GeneralConsensusMessage::UpgradeVote(_) => MessagePurpose::Upgrade,
+
+                GeneralConsensusMessage::EpochChangeVote(_)
+                | GeneralConsensusMessage::EpochChangeCertificate(_) => MessagePurpose::EpochChange,
             },
             Right(committee_message) => match committee_message {
-                CommitteeConsensusMessage::DAProposal(_) => MessagePurpose::Proposal,
+                CommitteeConsensusMessage::DAProposal(_)
+                | CommitteeConsensusMessage::DAProposalCommitment(_)
+                | CommitteeConsensusMessage::DAFallbackProposal(_)
+                | CommitteeConsensusMessage::CompactDAProposal(_) => MessagePurpose::Proposal,
                 CommitteeConsensusMessage::DAVote(_) => MessagePurpose::Vote,
                 CommitteeConsensusMessage::DACertificate(_) => MessagePurpose::DAC,
                 CommitteeConsensusMessage::VidDisperseMsg(_) => MessagePurpose::VidDisperse,
@@ -419,6 +661,30 @@ pub enum DataMessage<TYPES: NodeType> {
     /// TODO rethink this when we start to send these messages
     /// we only need the view number for broadcast
     SubmitTransaction(TYPES::Transaction, TYPES::Time),
+    /// The commitments of the transactions in the sender's mempool, as of the given view
+    MempoolInventory(Vec<Commitment<TYPES::Transaction>>, TYPES::Time),
+    /// Request the transactions with the given commitments, in response to a
+    /// [`DataMessage::MempoolInventory`]
+    RequestTransactions(Vec<Commitment<TYPES::Transaction>>, TYPES::Time),
+    /// Transactions, in response to a [`DataMessage::RequestTransactions`]
+    TransactionsResponse(Vec<TYPES::Transaction>, TYPES::Time),
+    /// Request the VID share dispersed to the given node for the payload with the given
+    /// commitment, proposed in the given view, from a DA member, signed by the requester over
+    /// [`VidSample::request_bytes`]
+    RequestVidShare(
+        VidCommitment,
+        TYPES::Time,
+        TYPES::SignatureKey,
+        <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType,
+    ),
+    /// A VID share, in response to a [`DataMessage::RequestVidShare`]
+    VidShareResponse(VidSample<TYPES>),
+    /// The latest anchor of the sender
+    AnchorAnnouncement(AnchorAnnouncement<TYPES>),
+    /// A request of a request/response call, see [`crate::rpc`]
+    RpcRequest(RpcRequest<TYPES::SignatureKey>),
+    /// The response to a [`DataMessage::RpcRequest`]
+    RpcResponse(RpcResponse<TYPES::SignatureKey>),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
@@ -0,0 +1,336 @@
+//! Storage of block payloads within a memory budget
+//!
+//! Payloads held for DA are kept until their view is garbage collected, so a node following a
+//! chain of multi-megabyte blocks may hold many of them at once. The [`PayloadStore`] keeps
+//! small payloads in memory, and writes large ones, and any which would take the payloads in
+//! memory over budget, to anonymous temporary files mapped back into memory. The operating
+//! system then pages them in on access rather than the node holding them on the heap.
+//!
+//! The same payload is also held for several views and by several tasks: the VID task of the
+//! leader, the DA task, the block fetch task and the storage all refer to it. Payloads stored
+//! with [`PayloadStore::intern`] are addressed by their commitment, so however many of them
+//! refer to a payload its bytes are held once, and are freed as soon as the last reference is
+//! dropped, which for the payloads of consensus is when their view falls behind the anchor.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Write},
+    ops::Deref,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError, Weak,
+    },
+};
+
+use bytes::Bytes;
+use hotshot_constants::{PAYLOAD_MEMORY_BUDGET, PAYLOAD_SPILL_THRESHOLD};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::data::VidCommitment;
+
+/// When payloads are written to disk rather than held in memory
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PayloadSpillConfig {
+    /// payloads of at least this many bytes are always written to disk
+    #[serde(default = "default_spill_threshold")]
+    pub threshold: usize,
+    /// maximum number of payload bytes held in memory, beyond which payloads are written to disk
+    #[serde(default = "default_memory_budget")]
+    pub memory_budget: usize,
+    /// directory of the temporary files, the system temporary directory if unset
+    #[serde(default)]
+    pub directory: Option<PathBuf>,
+}
+
+/// the default of [`PayloadSpillConfig::threshold`]
+fn default_spill_threshold() -> usize {
+    PAYLOAD_SPILL_THRESHOLD
+}
+
+/// the default of [`PayloadSpillConfig::memory_budget`]
+fn default_memory_budget() -> usize {
+    PAYLOAD_MEMORY_BUDGET
+}
+
+impl Default for PayloadSpillConfig {
+    fn default() -> Self {
+        Self {
+            threshold: PAYLOAD_SPILL_THRESHOLD,
+            memory_budget: PAYLOAD_MEMORY_BUDGET,
+            directory: None,
+        }
+    }
+}
+
+/// The share of the memory budget held by a payload, given back when it is dropped
+#[derive(Debug)]
+struct Reservation {
+    /// number of bytes reserved
+    len: usize,
+    /// the bytes held in memory by every payload of the store
+    in_memory: Arc<AtomicUsize>,
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.in_memory.fetch_sub(self.len, Ordering::Relaxed);
+    }
+}
+
+/// A payload written to a temporary file
+#[derive(Debug)]
+struct Spilled {
+    /// the mapping of the file
+    map: Mmap,
+    /// the file, removed from the file system as soon as it was created
+    _file: File,
+}
+
+/// Where the bytes of a [`StoredPayload`] live
+#[derive(Debug)]
+enum Location {
+    /// on the heap, counted against the memory budget
+    Memory(Bytes, Reservation),
+    /// in a memory mapped temporary file
+    Disk(Spilled),
+}
+
+/// A block payload held by a [`PayloadStore`]; cheap to clone, and dereferences to its bytes.
+/// Clones share the bytes, which are freed when the last clone is dropped.
+#[derive(Clone, Debug)]
+pub struct StoredPayload(Arc<Location>);
+
+impl StoredPayload {
+    /// Whether the payload was written to disk
+    #[must_use]
+    pub fn is_spilled(&self) -> bool {
+        matches!(*self.0, Location::Disk(_))
+    }
+
+    /// The payload as [`Bytes`], e.g. to send it. Copies the payload if it was written to disk.
+    #[must_use]
+    pub fn to_bytes(&self) -> Bytes {
+        match &*self.0 {
+            Location::Memory(bytes, _) => bytes.clone(),
+            Location::Disk(spilled) => Bytes::copy_from_slice(&spilled.map),
+        }
+    }
+}
+
+impl Deref for StoredPayload {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &*self.0 {
+            Location::Memory(bytes, _) => bytes,
+            Location::Disk(spilled) => &spilled.map,
+        }
+    }
+}
+
+impl PartialEq for StoredPayload {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for StoredPayload {}
+
+/// Decides which payloads are held in memory and which are written to disk, and holds each
+/// payload stored with [`PayloadStore::intern`] once. Clones share the same payloads.
+#[derive(Clone, Debug)]
+pub struct PayloadStore {
+    /// when payloads are written to disk
+    config: PayloadSpillConfig,
+    /// the bytes held in memory by every payload of the store
+    in_memory: Arc<AtomicUsize>,
+    /// the interned payloads by commitment; an entry outlives its payload until
+    /// [`PayloadStore::collect_garbage`] or another payload with the commitment is interned
+    interned: Arc<Mutex<HashMap<VidCommitment, Weak<Location>>>>,
+}
+
+impl PayloadStore {
+    /// Create a store writing payloads to disk as `config` says
+    #[must_use]
+    pub fn new(config: PayloadSpillConfig) -> Self {
+        Self {
+            config,
+            in_memory: Arc::new(AtomicUsize::new(0)),
+            interned: Arc::default(),
+        }
+    }
+
+    /// Number of payload bytes currently held in memory
+    #[must_use]
+    pub fn bytes_in_memory(&self) -> usize {
+        self.in_memory.load(Ordering::Relaxed)
+    }
+
+    /// Store `payload`, in memory or on disk. Should writing it to disk fail, it is kept in
+    /// memory, over budget if need be.
+    pub fn store(&self, payload: Bytes) -> StoredPayload {
+        let len = payload.len();
+        let in_memory = self.in_memory.fetch_add(len, Ordering::Relaxed);
+        let reservation = Reservation {
+            len,
+            in_memory: Arc::clone(&self.in_memory),
+        };
+        // an empty file cannot be mapped
+        if len == 0 || (len < self.config.threshold && in_memory + len <= self.config.memory_budget)
+        {
+            return StoredPayload(Arc::new(Location::Memory(payload, reservation)));
+        }
+        match self.spill(&payload) {
+            Ok(spilled) => {
+                drop(reservation);
+                StoredPayload(Arc::new(Location::Disk(spilled)))
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to write a payload of {len} bytes to disk, keeping it in memory: {e}"
+                );
+                StoredPayload(Arc::new(Location::Memory(payload, reservation)))
+            }
+        }
+    }
+
+    /// Store `payload`, whose commitment is `payload_commitment`, unless the store already
+    /// holds it, in which case the payload already held is returned and `payload` is dropped.
+    pub fn intern(&self, payload_commitment: VidCommitment, payload: Bytes) -> StoredPayload {
+        if let Some(stored) = self.get(&payload_commitment) {
+            return stored;
+        }
+        // stored without the lock, as it may write to disk
+        let stored = self.store(payload);
+        let mut interned = self.interned.lock().unwrap_or_else(PoisonError::into_inner);
+        // another caller may have interned the payload meanwhile
+        if let Some(existing) = interned.get(&payload_commitment).and_then(Weak::upgrade) {
+            return StoredPayload(existing);
+        }
+        interned.insert(payload_commitment, Arc::downgrade(&stored.0));
+        stored
+    }
+
+    /// The interned payload with this commitment, if anything still refers to it
+    #[must_use]
+    pub fn get(&self, payload_commitment: &VidCommitment) -> Option<StoredPayload> {
+        self.interned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(payload_commitment)
+            .and_then(Weak::upgrade)
+            .map(StoredPayload)
+    }
+
+    /// Number of references to the interned payload with this commitment, 0 if it was freed
+    #[must_use]
+    pub fn references(&self, payload_commitment: &VidCommitment) -> usize {
+        self.interned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(payload_commitment)
+            .map_or(0, Weak::strong_count)
+    }
+
+    /// Number of interned payloads which are still referred to
+    #[must_use]
+    pub fn interned_payloads(&self) -> usize {
+        self.interned
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .filter(|payload| payload.strong_count() > 0)
+            .count()
+    }
+
+    /// Forget the interned payloads nothing refers to any more, which consensus calls as the
+    /// anchor moves on. Returns the number of payloads forgotten.
+    pub fn collect_garbage(&self) -> usize {
+        let mut interned = self.interned.lock().unwrap_or_else(PoisonError::into_inner);
+        let before = interned.len();
+        interned.retain(|_, payload| payload.strong_count() > 0);
+        before - interned.len()
+    }
+
+    /// Write `payload` to a new temporary file and map it
+    fn spill(&self, payload: &[u8]) -> io::Result<Spilled> {
+        let mut file = match &self.config.directory {
+            Some(directory) => tempfile::tempfile_in(directory)?,
+            None => tempfile::tempfile()?,
+        };
+        file.write_all(payload)?;
+        file.flush()?;
+        // SAFETY: the file was unlinked on creation, so no other process can modify it, and
+        // this process only writes to it before mapping it
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(Spilled { map, _file: file })
+    }
+}
+
+impl Default for PayloadStore {
+    fn default() -> Self {
+        Self::new(PayloadSpillConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::traits::block_contents::vid_commitment;
+
+    #[test]
+    fn large_payloads_and_payloads_over_budget_are_spilled() {
+        let store = PayloadStore::new(PayloadSpillConfig {
+            threshold: 64,
+            memory_budget: 100,
+            directory: None,
+        });
+
+        let small = store.store(Bytes::from(vec![1u8; 60]));
+        assert!(!small.is_spilled());
+        assert_eq!(store.bytes_in_memory(), 60);
+
+        let large = store.store(Bytes::from(vec![2u8; 64]));
+        assert!(large.is_spilled());
+        assert_eq!(&*large, &[2u8; 64][..]);
+        assert_eq!(large.to_bytes(), Bytes::from(vec![2u8; 64]));
+
+        // a small payload which would take the store over budget
+        let over_budget = store.store(Bytes::from(vec![3u8; 50]));
+        assert!(over_budget.is_spilled());
+        assert_eq!(store.bytes_in_memory(), 60);
+
+        drop(small);
+        assert_eq!(store.bytes_in_memory(), 0);
+        assert!(!store.store(Bytes::from(vec![4u8; 50])).is_spilled());
+    }
+
+    #[test]
+    fn interned_payloads_are_held_once_until_unreferenced() {
+        let store = PayloadStore::default();
+        let bytes = Bytes::from(vec![5u8; 32]);
+        let commitment = vid_commitment(&bytes, 4);
+
+        let first = store.intern(commitment, bytes.clone());
+        // a copy of the same payload, e.g. received again from the network
+        let second = store.intern(commitment, Bytes::from(bytes.to_vec()));
+        assert_eq!(store.bytes_in_memory(), 32);
+        assert_eq!(store.references(&commitment), 2);
+        assert_eq!(store.get(&commitment).as_deref(), Some(&bytes[..]));
+
+        drop(first);
+        assert_eq!(store.references(&commitment), 1);
+        assert_eq!(store.collect_garbage(), 0);
+
+        drop(second);
+        assert_eq!(store.bytes_in_memory(), 0);
+        assert!(store.get(&commitment).is_none());
+        assert_eq!(store.interned_payloads(), 0);
+        assert_eq!(store.collect_garbage(), 1);
+    }
+}
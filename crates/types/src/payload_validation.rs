@@ -0,0 +1,299 @@
+//! Dry runs of the checks replicas run on a proposed block payload
+//!
+//! A block builder bidding a payload for a view wants to know beforehand whether the replicas
+//! would vote for a proposal of it. [`validate_payload`] runs the checks a replica runs on such a
+//! proposal, without voting, on the block header the leader of the view builds for the payload
+//! on the leaf of the high QC: that the view still takes proposals, that the header and its
+//! extensions are valid on that leaf ([`validate_header`]), that the block is within the limits
+//! governance set and the proposal carries its governance transactions
+//! ([`validate_block_contents`]), and that the header applies to the state of that leaf. The
+//! consensus task runs the same checks on the proposals it receives. The signature and
+//! certificates of the proposal are those of the leader, and not checked.
+//!
+//! A dry run computes the VID commitment of the payload and applies its header, which takes
+//! long for large payloads. [`PayloadDryRun::prepare`] takes what the checks need from
+//! [`Consensus`], so that the lock on it is released before [`PayloadDryRun::run`] does the work.
+
+use std::sync::Arc;
+
+use commit::Commitment;
+use hotshot_constants::MAX_HEADER_EXTENSIONS_SIZE;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+
+use crate::{
+    consensus::Consensus,
+    data::{Leaf, VidCommitment},
+    governance::{ConsensusParameters, GovernanceTransaction},
+    traits::{
+        block_contents::{vid_commitment, BlockHeader},
+        header_extension::{validate_extensions, ExtensionRejection, HeaderExtender},
+        node_implementation::NodeType,
+        states::ValidatedState,
+        BlockPayload,
+    },
+    utils::BuilderCommitment,
+    vote::HasViewNumber,
+};
+
+/// What replicas would accept a proposal of a payload as
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct PayloadAcceptance<TYPES: NodeType> {
+    /// the view of the proposal
+    pub view: TYPES::Time,
+    /// the leaf the proposal extends, that of the high QC
+    pub parent: Commitment<Leaf<TYPES>>,
+    /// the view of the leaf the proposal extends
+    pub parent_view: TYPES::Time,
+    /// the commitment to the payload the DA committee votes for
+    pub payload_commitment: VidCommitment,
+    /// the commitment of the builder to the payload
+    pub builder_commitment: BuilderCommitment,
+    /// the block header of the payload, with the extensions of this node's application
+    pub block_header: TYPES::BlockHeader,
+    /// the governance transactions of the block, which the proposal carries
+    pub governance: Vec<GovernanceTransaction>,
+}
+
+/// Why replicas would reject a proposal of a payload
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+#[snafu(visibility(pub))]
+pub enum PayloadRejection<TYPES: NodeType> {
+    /// The view of the proposal is over
+    #[snafu(display("View {view:?} is before the current view {current_view:?}"))]
+    StaleView {
+        /// the view of the proposal
+        view: TYPES::Time,
+        /// the view the node is in
+        current_view: TYPES::Time,
+    },
+    /// The node does not know the state the proposal would extend yet
+    #[snafu(display("The state of the leaf of the high QC, of view {parent_view:?}, is unknown"))]
+    UnknownParent {
+        /// the view of the high QC
+        parent_view: TYPES::Time,
+    },
+    /// The payload cannot be encoded for the DA committee
+    #[snafu(display("The payload cannot be encoded: {reason}"))]
+    Unencodable {
+        /// the error of the payload
+        reason: String,
+    },
+    /// The block header does not commit to the header of the leaf it extends
+    #[snafu(display("The block header does not commit to the header of its parent"))]
+    UnrelatedParent,
+    /// The extensions of the block header are rejected
+    #[snafu(display("The block header extensions are rejected: {source}"))]
+    Extensions {
+        /// why they are rejected
+        source: ExtensionRejection,
+    },
+    /// The block header does not commit to the transactions of the block
+    #[snafu(display("The transactions root does not match the block"))]
+    TransactionsRoot,
+    /// The block holds more transactions than governance allows in the view
+    #[snafu(display("The block has {count} transactions, more than the maximum of {max}"))]
+    TooManyTransactions {
+        /// the number of transactions of the block
+        count: usize,
+        /// the maximum number of transactions of a block in the view
+        max: usize,
+    },
+    /// The governance transactions of the proposal are not those of the block
+    #[snafu(display("The governance transactions do not match the block"))]
+    Governance,
+    /// The block header of the payload does not apply to the state of its parent
+    #[snafu(display("The block header does not apply to the state of its parent: {reason}"))]
+    InvalidHeader {
+        /// the error of the state
+        reason: String,
+    },
+    /// The dry run did not finish
+    #[snafu(display("The dry run of the payload did not finish: {reason}"))]
+    Aborted {
+        /// why it did not finish
+        reason: String,
+    },
+}
+
+/// Check `header`, proposed in `view` on the leaf whose header is `parent`: that it commits to
+/// `parent`, and that its extensions are within the size limit and accepted by `extender`
+///
+/// # Errors
+/// The first check the header fails
+pub fn validate_header<TYPES: NodeType>(
+    extender: &dyn HeaderExtender<TYPES>,
+    view: TYPES::Time,
+    parent: &TYPES::BlockHeader,
+    header: &TYPES::BlockHeader,
+) -> Result<(), PayloadRejection<TYPES>> {
+    if !header.extends(parent) {
+        return Err(PayloadRejection::UnrelatedParent);
+    }
+    validate_extensions(extender, view, parent, header)
+        .map_err(|source| PayloadRejection::Extensions { source })
+}
+
+/// Check `payload`, the block of the proposal of `header` carrying `governance`: that the header
+/// commits to its transactions, that it holds no more transactions than `parameters`, those
+/// governance set for the view of the proposal, allow, and that `governance` are its governance
+/// transactions
+///
+/// # Errors
+/// The first check the block fails
+pub fn validate_block_contents<TYPES: NodeType>(
+    parameters: &ConsensusParameters,
+    header: &TYPES::BlockHeader,
+    governance: &[GovernanceTransaction],
+    payload: &TYPES::BlockPayload,
+) -> Result<(), PayloadRejection<TYPES>> {
+    let metadata = header.metadata();
+    if payload.transactions_root(metadata) != header.transactions_root() {
+        return Err(PayloadRejection::TransactionsRoot);
+    }
+    let max = parameters.max_transactions.get();
+    let count = payload.transaction_commitments(metadata).len();
+    if count > max {
+        return Err(PayloadRejection::TooManyTransactions { count, max });
+    }
+    if payload.governance_transactions(metadata) != governance {
+        return Err(PayloadRejection::Governance);
+    }
+    Ok(())
+}
+
+/// What a dry run of a proposal takes from [`Consensus`]: the view of the proposal, the leaf
+/// of the high QC it extends, and the state of that leaf
+#[derive(Clone, Debug)]
+pub struct PayloadDryRun<TYPES: NodeType> {
+    /// the view of the proposal
+    view: TYPES::Time,
+    /// the commitment of the leaf the proposal extends
+    parent: Commitment<Leaf<TYPES>>,
+    /// the view of the leaf the proposal extends
+    parent_view: TYPES::Time,
+    /// the leaf the proposal extends
+    parent_leaf: Leaf<TYPES>,
+    /// the state of the leaf the proposal extends
+    parent_state: Arc<TYPES::ValidatedState>,
+    /// the instance state of the node
+    instance_state: TYPES::InstanceState,
+    /// the consensus parameters governance set for the view
+    parameters: ConsensusParameters,
+}
+
+impl<TYPES: NodeType> PayloadDryRun<TYPES> {
+    /// Take what a dry run of a proposal in `view` needs from `consensus`, without the payload
+    ///
+    /// # Errors
+    /// If the view of the proposal is over, or the state of the leaf it extends is unknown
+    pub fn prepare(
+        consensus: &Consensus<TYPES>,
+        view: TYPES::Time,
+    ) -> Result<Self, PayloadRejection<TYPES>> {
+        // replicas drop proposals from older views
+        if view < consensus.cur_view {
+            return Err(PayloadRejection::StaleView {
+                view,
+                current_view: consensus.cur_view,
+            });
+        }
+
+        let parent_view = consensus.high_qc.get_view_number();
+        let unknown_parent = PayloadRejection::UnknownParent { parent_view };
+        let Some((parent, parent_state)) = consensus
+            .validated_state_map
+            .get(&parent_view)
+            .and_then(|parent| parent.view_inner.get_leaf())
+        else {
+            return Err(unknown_parent);
+        };
+        let Some(parent_leaf) = consensus.saved_leaves.get(&parent) else {
+            return Err(unknown_parent);
+        };
+        Ok(Self {
+            view,
+            parent,
+            parent_view,
+            parent_leaf: parent_leaf.clone(),
+            parent_state: Arc::clone(parent_state),
+            instance_state: consensus.instance_state.clone(),
+            parameters: consensus.parameter_schedule.parameters_at_view(view),
+        })
+    }
+
+    /// Check a proposal of `payload`, with `metadata`, as a replica with the header extensions
+    /// of `extender` would, in a network of `total_nodes`, without voting
+    ///
+    /// # Errors
+    /// Why the replicas would reject the proposal
+    pub fn run(
+        &self,
+        extender: &dyn HeaderExtender<TYPES>,
+        total_nodes: usize,
+        payload: &TYPES::BlockPayload,
+        metadata: &<TYPES::BlockPayload as BlockPayload>::Metadata,
+    ) -> Result<PayloadAcceptance<TYPES>, PayloadRejection<TYPES>> {
+        let view = self.view;
+        let parent_header = &self.parent_leaf.block_header;
+        let encoded_transactions: Vec<u8> = payload
+            .encode()
+            .map_err(|e| PayloadRejection::Unencodable {
+                reason: e.to_string(),
+            })?
+            .collect();
+        let payload_commitment = vid_commitment(&encoded_transactions, total_nodes);
+        // the leader proposes without extensions over the size limit
+        let mut extensions = extender.extensions(view, parent_header);
+        if extensions.len() > MAX_HEADER_EXTENSIONS_SIZE {
+            extensions = Vec::new();
+        }
+        let block_header = TYPES::BlockHeader::new(
+            &self.parent_state,
+            &self.instance_state,
+            parent_header,
+            payload_commitment,
+            payload.transactions_root(metadata),
+            metadata.clone(),
+            extensions,
+        );
+        // the leader copies the governance transactions of the block into the proposal
+        let governance = payload.governance_transactions(metadata);
+
+        validate_header(extender, view, parent_header, &block_header)?;
+        validate_block_contents(&self.parameters, &block_header, &governance, payload)?;
+        self.parent_state
+            .validate_and_apply_header(&self.instance_state, parent_header, &block_header)
+            .map_err(|e| PayloadRejection::InvalidHeader {
+                reason: e.to_string(),
+            })?;
+
+        Ok(PayloadAcceptance {
+            view,
+            parent: self.parent,
+            parent_view: self.parent_view,
+            payload_commitment,
+            builder_commitment: payload.builder_commitment(metadata),
+            block_header,
+            governance,
+        })
+    }
+}
+
+/// Check a proposal of `payload`, with `metadata`, in `view` as a replica with `consensus` and
+/// the header extensions of `extender` would, in a network of `total_nodes`, without voting.
+/// Holds `consensus` for the whole check, see [`PayloadDryRun`] to release it sooner.
+///
+/// # Errors
+/// Why the replicas would reject the proposal
+pub fn validate_payload<TYPES: NodeType>(
+    consensus: &Consensus<TYPES>,
+    extender: &dyn HeaderExtender<TYPES>,
+    total_nodes: usize,
+    payload: &TYPES::BlockPayload,
+    metadata: &<TYPES::BlockPayload as BlockPayload>::Metadata,
+    view: TYPES::Time,
+) -> Result<PayloadAcceptance<TYPES>, PayloadRejection<TYPES>> {
+    PayloadDryRun::prepare(consensus, view)?.run(extender, total_nodes, payload, metadata)
+}
@@ -0,0 +1,228 @@
+//! Verification of chains of quorum certificates
+//!
+//! A follower which does not run consensus can still trust a leaf without trusting whoever served
+//! it: given the stake table at genesis, a chain of leaves, each with the certificate of a quorum
+//! of that stake voting for it, proves the leaves were certified by the network. The leaves only
+//! need their headers, not their payloads. [`verify_qc_chain`] checks such a chain; stake table
+//! changes are followed with a [`QcChainVerifier`] told where the chain records them. Signatures
+//! are checked in the signing context of the process, which must be
+//! [configured](crate::signing::configure) with the chain id of the network.
+
+use std::fmt::{self, Debug};
+
+use commit::Committable;
+use ethereum_types::U256;
+use snafu::Snafu;
+
+use crate::{
+    data::Leaf,
+    signing::{self, SigningDomain},
+    simple_certificate::QuorumCertificate,
+    traits::{
+        election::QuorumRules,
+        node_implementation::NodeType,
+        signature_key::{SignatureKey, StakeTableEntryType},
+    },
+};
+
+/// The stake table entries of the signature key of `TYPES`
+pub type StakeTable<TYPES> =
+    Vec<<<TYPES as NodeType>::SignatureKey as SignatureKey>::StakeTableEntry>;
+
+/// Why a chain of quorum certificates was rejected
+#[derive(Debug, Snafu, PartialEq, Eq)]
+#[snafu(visibility(pub))]
+pub enum QcChainError {
+    /// The chain has no leaves
+    #[snafu(display("The chain has no leaves"))]
+    EmptyChain,
+    /// The stake table certifying a leaf has no stake
+    #[snafu(display("The stake table certifying the leaf of view {view} has no stake"))]
+    EmptyStakeTable {
+        /// view of the leaf
+        view: u64,
+    },
+    /// The leaf is not the one its certificate is for
+    #[snafu(display("The certificate of view {qc_view} is not for the leaf of view {view}"))]
+    CertificateMismatch {
+        /// view of the leaf
+        view: u64,
+        /// view of the certificate
+        qc_view: u64,
+    },
+    /// The certificate is not signed by enough of the stake table
+    #[snafu(display("The certificate of the leaf of view {view} is not validly signed"))]
+    InvalidSignature {
+        /// view of the leaf
+        view: u64,
+    },
+    /// The leaf does not extend the previous leaf of the chain
+    #[snafu(display("The leaf of view {view} does not extend the leaf of view {parent_view}"))]
+    BrokenParentLink {
+        /// view of the leaf
+        view: u64,
+        /// view of the previous leaf of the chain
+        parent_view: u64,
+    },
+}
+
+/// The last leaf of a verified chain
+#[derive(Clone, Debug)]
+pub struct VerifiedTip<TYPES: NodeType> {
+    /// the leaf
+    pub leaf: Leaf<TYPES>,
+    /// the certificate of the leaf
+    pub qc: QuorumCertificate<TYPES>,
+    /// the stake table certifying the leaves after it
+    pub stake_table: StakeTable<TYPES>,
+}
+
+/// What a leaf changes the stake table to, for the leaves after it
+type Transition<TYPES> = Box<dyn Fn(&Leaf<TYPES>) -> Option<StakeTable<TYPES>> + Send + Sync>;
+
+/// Verifies chains of quorum certificates starting from a trusted stake table
+pub struct QcChainVerifier<TYPES: NodeType> {
+    /// the stake table certifying the first leaf of a chain
+    stake_table: StakeTable<TYPES>,
+    /// the stake certificates need
+    rules: QuorumRules,
+    /// where the chain records stake table changes
+    transition: Option<Transition<TYPES>>,
+}
+
+impl<TYPES: NodeType> Debug for QcChainVerifier<TYPES> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QcChainVerifier")
+            .field("stake_table", &self.stake_table)
+            .field("rules", &self.rules)
+            .field("follows_transitions", &self.transition.is_some())
+            .finish()
+    }
+}
+
+impl<TYPES: NodeType> QcChainVerifier<TYPES> {
+    /// Create a verifier trusting `stake_table` to certify the first leaf of a chain, with
+    /// default quorum rules and a stake table which never changes
+    #[must_use]
+    pub fn new(stake_table: StakeTable<TYPES>) -> Self {
+        Self {
+            stake_table,
+            rules: QuorumRules::default(),
+            transition: None,
+        }
+    }
+
+    /// Require the stake of `rules` rather than the default quorum rules
+    #[must_use]
+    pub fn with_rules(mut self, rules: QuorumRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Follow stake table changes: `transition` returns the stake table a leaf changes to, which
+    /// certifies the leaves after it once that leaf is itself certified
+    #[must_use]
+    pub fn with_transitions(
+        mut self,
+        transition: impl Fn(&Leaf<TYPES>) -> Option<StakeTable<TYPES>> + Send + Sync + 'static,
+    ) -> Self {
+        self.transition = Some(Box::new(transition));
+        self
+    }
+
+    /// Verify `chain`, oldest leaf first, each leaf with its certificate: every certificate must
+    /// be for its leaf and signed by enough of the current stake table, and every leaf must
+    /// extend the one before it.
+    /// # Errors
+    /// If any of the checks fails
+    pub fn verify(
+        &self,
+        chain: Vec<(Leaf<TYPES>, QuorumCertificate<TYPES>)>,
+    ) -> Result<VerifiedTip<TYPES>, QcChainError> {
+        let mut stake_table = self.stake_table.clone();
+        let mut tip: Option<(Leaf<TYPES>, QuorumCertificate<TYPES>)> = None;
+        for (leaf, qc) in chain {
+            let view = *leaf.view_number;
+            if let Some((parent, _)) = &tip {
+                if leaf.view_number <= parent.view_number
+                    || leaf.parent_commitment != parent.commit()
+                {
+                    return Err(QcChainError::BrokenParentLink {
+                        view,
+                        parent_view: *parent.view_number,
+                    });
+                }
+            }
+            self.verify_certificate(&stake_table, &leaf, &qc)?;
+            if let Some(next) = self.transition.as_ref().and_then(|t| t(&leaf)) {
+                stake_table = next;
+            }
+            tip = Some((leaf, qc));
+        }
+        let (leaf, qc) = tip.ok_or(QcChainError::EmptyChain)?;
+        Ok(VerifiedTip {
+            leaf,
+            qc,
+            stake_table,
+        })
+    }
+
+    /// Check `qc` is a certificate for `leaf` signed by enough of `stake_table`
+    fn verify_certificate(
+        &self,
+        stake_table: &StakeTable<TYPES>,
+        leaf: &Leaf<TYPES>,
+        qc: &QuorumCertificate<TYPES>,
+    ) -> Result<(), QcChainError> {
+        let view = *leaf.view_number;
+        let mismatch = QcChainError::CertificateMismatch {
+            view,
+            qc_view: *qc.view_number,
+        };
+        // the commitment of a leaf covers the signatures of its justification
+        if leaf.justify_qc.signatures.is_none() && !leaf.justify_qc.is_genesis {
+            return Err(mismatch);
+        }
+        if qc.view_number != leaf.view_number
+            || qc.data.leaf_commit != leaf.commit()
+            || qc.vote_commitment != qc.data.commit()
+        {
+            return Err(mismatch);
+        }
+        let Some(signatures) = qc.signatures.as_ref() else {
+            return Err(QcChainError::InvalidSignature { view });
+        };
+        let total_stake = stake_table
+            .iter()
+            .map(|entry| u64::try_from(entry.get_stake()).unwrap_or(u64::MAX))
+            .fold(0, u64::saturating_add);
+        if total_stake == 0 {
+            return Err(QcChainError::EmptyStakeTable { view });
+        }
+        let threshold = self.rules.success.threshold(total_stake);
+        let params = <TYPES::SignatureKey as SignatureKey>::get_public_parameter(
+            stake_table.clone(),
+            U256::from(threshold.get()),
+        );
+        if !<TYPES::SignatureKey as SignatureKey>::check(
+            &params,
+            &signing::encode(SigningDomain::Vote, qc.vote_commitment.as_ref()),
+            signatures,
+        ) {
+            return Err(QcChainError::InvalidSignature { view });
+        }
+        Ok(())
+    }
+}
+
+/// Verify `chain`, oldest leaf first, each leaf with its certificate, against the stake table at
+/// genesis, which must certify every leaf. Returns the last leaf of the chain.
+/// # Errors
+/// If a certificate is not for its leaf or not signed by enough stake, or a leaf does not extend
+/// the one before it
+pub fn verify_qc_chain<TYPES: NodeType>(
+    genesis_stake_table: StakeTable<TYPES>,
+    chain: Vec<(Leaf<TYPES>, QuorumCertificate<TYPES>)>,
+) -> Result<VerifiedTip<TYPES>, QcChainError> {
+    QcChainVerifier::new(genesis_stake_table).verify(chain)
+}
@@ -0,0 +1,149 @@
+//! Receipts of submitted transactions, and what became of the transactions since
+//!
+//! Submitting a transaction returns a [`TxReceipt`], whose commitment the node can then be asked
+//! the [`TxStatus`] of. The status comes from the mempool of the node, for transactions still
+//! waiting for a block, and from a [`TransactionIndex`] of the transactions it saw proposed,
+//! decided or dropped since.
+
+use std::{collections::HashMap, time::SystemTime};
+
+use commit::Commitment;
+use serde::{Deserialize, Serialize};
+
+use crate::traits::node_implementation::NodeType;
+
+/// What a node returns for a transaction submitted to it
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct TxReceipt<TYPES: NodeType> {
+    /// the commitment of the transaction, to ask for its status with
+    pub commitment: Commitment<TYPES::Transaction>,
+    /// when the node accepted the transaction
+    pub submitted_at: SystemTime,
+    /// the view the node was in when it accepted the transaction
+    pub node_view: TYPES::Time,
+}
+
+/// Why a transaction is no longer waiting for a block
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DropReason {
+    /// its view window expired before it was decided
+    Expired,
+}
+
+/// What became of a transaction, as far as a node knows
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub enum TxStatus<TYPES: NodeType> {
+    /// it waits in the mempool for a block
+    Pending,
+    /// it is in the block of an undecided proposal for this view. Only nodes which see the
+    /// block before it is decided, the DA committee and the leader, report this.
+    Included(TYPES::Time),
+    /// it is in the block of the leaf decided for this view
+    Decided(TYPES::Time),
+    /// it will not be decided
+    Dropped(DropReason),
+    /// the node holds no record of it: the transaction never reached the node, or the node
+    /// forgot it since it was decided or dropped
+    Unknown,
+}
+
+/// The transactions a node saw proposed, decided or dropped, remembered for a number of views
+#[derive(Clone, Debug)]
+pub struct TransactionIndex<TYPES: NodeType> {
+    /// the transactions in the blocks of undecided proposals, by the latest view proposing them
+    included: HashMap<Commitment<TYPES::Transaction>, TYPES::Time>,
+    /// the decided transactions, by the view of the leaf deciding them
+    decided: HashMap<Commitment<TYPES::Transaction>, TYPES::Time>,
+    /// the dropped transactions, with why and the view they were dropped in
+    dropped: HashMap<Commitment<TYPES::Transaction>, (DropReason, TYPES::Time)>,
+    /// number of views decided and dropped transactions are remembered for
+    retention: u64,
+}
+
+impl<TYPES: NodeType> TransactionIndex<TYPES> {
+    /// An empty index, remembering decided and dropped transactions for `retention` views
+    #[must_use]
+    pub fn new(retention: u64) -> Self {
+        Self {
+            included: HashMap::new(),
+            decided: HashMap::new(),
+            dropped: HashMap::new(),
+            retention,
+        }
+    }
+
+    /// Record that the block proposed for `view` holds the transactions `commitments`
+    pub fn record_included(
+        &mut self,
+        view: TYPES::Time,
+        commitments: impl IntoIterator<Item = Commitment<TYPES::Transaction>>,
+    ) {
+        for commitment in commitments {
+            if !self.decided.contains_key(&commitment) {
+                self.included.insert(commitment, view);
+            }
+        }
+    }
+
+    /// Record that the leaf decided for `view` holds the transactions `commitments`
+    pub fn record_decided(
+        &mut self,
+        view: TYPES::Time,
+        commitments: impl IntoIterator<Item = Commitment<TYPES::Transaction>>,
+    ) {
+        for commitment in commitments {
+            self.included.remove(&commitment);
+            self.dropped.remove(&commitment);
+            self.decided.insert(commitment, view);
+        }
+    }
+
+    /// Record that the transactions `commitments` were dropped for `reason`, in `view`
+    pub fn record_dropped(
+        &mut self,
+        view: TYPES::Time,
+        reason: DropReason,
+        commitments: impl IntoIterator<Item = Commitment<TYPES::Transaction>>,
+    ) {
+        for commitment in commitments {
+            if !self.decided.contains_key(&commitment) {
+                self.included.remove(&commitment);
+                self.dropped.insert(commitment, (reason, view));
+            }
+        }
+    }
+
+    /// Forget what no longer matters once `view` is decided: the proposals for views up to it,
+    /// which lost to the decided ones, and the transactions decided or dropped more than the
+    /// retention before it
+    pub fn prune(&mut self, view: TYPES::Time) {
+        self.included.retain(|_, proposed| *proposed > view);
+        let horizon = view.saturating_sub(self.retention);
+        self.decided.retain(|_, decided| **decided >= horizon);
+        self.dropped.retain(|_, (_, dropped)| **dropped >= horizon);
+    }
+
+    /// The status of the transaction `commitment`, given whether the mempool holds it
+    #[must_use]
+    pub fn status(
+        &self,
+        commitment: &Commitment<TYPES::Transaction>,
+        in_mempool: bool,
+    ) -> TxStatus<TYPES> {
+        if let Some(view) = self.decided.get(commitment) {
+            return TxStatus::Decided(*view);
+        }
+        if let Some(view) = self.included.get(commitment) {
+            return TxStatus::Included(*view);
+        }
+        if in_mempool {
+            return TxStatus::Pending;
+        }
+        match self.dropped.get(commitment) {
+            Some((reason, _)) => TxStatus::Dropped(*reason),
+            None => TxStatus::Unknown,
+        }
+    }
+}
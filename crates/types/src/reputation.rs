@@ -0,0 +1,309 @@
+//! Scores of how leaders perform, and a leader rotation weighted by them
+//!
+//! [`LeaderScores`] counts, as this node observed them, the proposals each leader sent in time,
+//! late or invalid, and those which never arrived. Nodes observe them differently, so they are
+//! only reported, through the status API.
+//!
+//! The leader of a view must be the same for every node, so a rotation can only be weighted by
+//! what all nodes agree on: the decided chain. [`LeaderReputation`] records, for every view up to
+//! the latest decided leaf, whether the leaf of the view is in the chain, and down-weights the
+//! leaders which missed more than [`ReputationRules::max_missed`] of their latest
+//! [`ReputationRules::window`] turns. Views are grouped in epochs of
+//! [`ReputationRules::epoch_views`] views, and the leaders down-weighted in an epoch are fixed
+//! when the epoch before it starts, from the views settled by then. Nodes therefore agree on the
+//! rotation as long as they decide leaves less than an epoch after they are proposed. The
+//! reputation is kept in memory only: a restarted node agrees again once every leader had
+//! [`ReputationRules::window`] turns since its restart.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::traits::{election::ElectionError, signature_key::SignatureKey};
+
+/// The number of views a timed out proposal is remembered for, so that it counts as late rather
+/// than missed if it arrives after all
+pub const LATE_PROPOSAL_HORIZON_VIEWS: u64 = 100;
+
+/// How a leader performed, as observed by this node
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LeaderScore {
+    /// valid proposals which arrived before the proposal timeout
+    pub proposals: u64,
+    /// valid proposals which arrived after the proposal timeout
+    pub late_proposals: u64,
+    /// proposals which never arrived
+    pub missed_proposals: u64,
+    /// proposals which failed validation
+    pub invalid_proposals: u64,
+}
+
+/// The score of a leader, as reported through the status API
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeaderPerformance {
+    /// the leader
+    pub leader: String,
+    /// how it performed
+    pub score: LeaderScore,
+}
+
+/// How every leader performed so far, as observed by this node
+#[derive(Clone, Debug)]
+pub struct LeaderScores<KEY: SignatureKey> {
+    /// the score of each leader
+    scores: BTreeMap<KEY, LeaderScore>,
+    /// the recent views whose proposal timed out, with their leader
+    timed_out: BTreeMap<u64, KEY>,
+}
+
+impl<KEY: SignatureKey> Default for LeaderScores<KEY> {
+    fn default() -> Self {
+        Self {
+            scores: BTreeMap::new(),
+            timed_out: BTreeMap::new(),
+        }
+    }
+}
+
+impl<KEY: SignatureKey> LeaderScores<KEY> {
+    /// Record that the proposal of `leader` for `view` did not arrive in time
+    pub fn record_missed(&mut self, view: u64, leader: KEY) {
+        if self.timed_out.contains_key(&view) {
+            return;
+        }
+        self.scores
+            .entry(leader.clone())
+            .or_default()
+            .missed_proposals += 1;
+        self.timed_out.insert(view, leader);
+        self.timed_out = self
+            .timed_out
+            .split_off(&view.saturating_sub(LATE_PROPOSAL_HORIZON_VIEWS));
+    }
+
+    /// Record a valid proposal of `leader` for `view`, which is late if it timed out
+    pub fn record_proposal(&mut self, view: u64, leader: KEY) {
+        let score = self.scores.entry(leader.clone()).or_default();
+        if self.timed_out.get(&view) == Some(&leader) {
+            self.timed_out.remove(&view);
+            score.missed_proposals = score.missed_proposals.saturating_sub(1);
+            score.late_proposals += 1;
+        } else {
+            score.proposals += 1;
+        }
+    }
+
+    /// Record an invalid proposal of `leader`
+    pub fn record_invalid(&mut self, leader: KEY) {
+        self.scores.entry(leader).or_default().invalid_proposals += 1;
+    }
+
+    /// The score of `leader`, if it was the leader of a view this node saw
+    #[must_use]
+    pub fn get(&self, leader: &KEY) -> Option<LeaderScore> {
+        self.scores.get(leader).copied()
+    }
+
+    /// The scores of all leaders, as reported through the status API
+    #[must_use]
+    pub fn report(&self) -> Vec<LeaderPerformance> {
+        self.scores
+            .iter()
+            .map(|(leader, score)| LeaderPerformance {
+                leader: leader.to_string(),
+                score: *score,
+            })
+            .collect()
+    }
+}
+
+/// How leaders are down-weighted in the rotation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ReputationRules {
+    /// the number of views of an epoch, over which the down-weighted leaders are fixed
+    pub epoch_views: u64,
+    /// the number of latest turns of a leader its reputation is computed over
+    pub window: usize,
+    /// the number of those turns a leader may miss before it is down-weighted
+    pub max_missed: usize,
+    /// a down-weighted leader is kept for one in this many of its turns, the others are passed
+    /// on to the next leader in the rotation which is not down-weighted
+    pub penalty: u64,
+}
+
+impl Default for ReputationRules {
+    fn default() -> Self {
+        Self {
+            epoch_views: 100,
+            window: 10,
+            max_missed: 5,
+            penalty: 4,
+        }
+    }
+}
+
+impl ReputationRules {
+    /// Check that leaders can be scored and down-weighted under these rules
+    /// # Errors
+    /// If epochs or windows are empty, if leaders are down-weighted without missing a turn or
+    /// can miss every turn, or if the penalty is zero
+    pub fn validate(&self) -> Result<(), ElectionError> {
+        let invalid_rules = |reason: &str| {
+            Err(ElectionError::UnsafeQuorumRules {
+                reason: reason.to_string(),
+            })
+        };
+        if self.epoch_views == 0 || self.window == 0 {
+            return invalid_rules("reputation epochs and windows must not be empty");
+        }
+        if self.max_missed >= self.window {
+            return invalid_rules("leaders missing every turn of their window must be penalized");
+        }
+        if self.penalty == 0 {
+            return invalid_rules("down-weighted leaders must keep some of their turns");
+        }
+        Ok(())
+    }
+}
+
+/// What the decided chain says of the leaders so far
+#[derive(Default)]
+struct ReputationState {
+    /// the first view not settled by a decided leaf
+    next_view: u64,
+    /// the latest turns of each leader, by index, and whether the leaf of each was decided
+    turns: BTreeMap<usize, VecDeque<bool>>,
+    /// the leaders down-weighted in each epoch
+    down_weighted: BTreeMap<u64, BTreeSet<usize>>,
+}
+
+impl ReputationState {
+    /// The index of the leader of `view`, whose index in the plain rotation is `base`
+    fn leader(&self, rules: &ReputationRules, view: u64, base: usize, num_leaders: usize) -> usize {
+        let Some(down_weighted) = self.down_weighted.get(&(view / rules.epoch_views)) else {
+            return base;
+        };
+        let turn = view / u64::try_from(num_leaders.max(1)).unwrap_or(u64::MAX);
+        if !down_weighted.contains(&base)
+            || down_weighted.len() >= num_leaders
+            || turn % rules.penalty == 0
+        {
+            return base;
+        }
+        (1..num_leaders)
+            .map(|offset| (base + offset) % num_leaders)
+            .find(|index| !down_weighted.contains(index))
+            .unwrap_or(base)
+    }
+}
+
+/// The reputation of the leaders according to the decided chain, shared by the memberships of a
+/// node. Clones share the reputation, so all memberships weigh their rotation the same way.
+#[derive(Clone, Default)]
+pub struct LeaderReputation {
+    /// the reputation so far
+    state: Arc<RwLock<ReputationState>>,
+}
+
+impl Debug for LeaderReputation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeaderReputation").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for LeaderReputation {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl Eq for LeaderReputation {}
+
+impl Hash for LeaderReputation {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl LeaderReputation {
+    /// The index of the leader of `view` out of `num_leaders`, whose index in the plain rotation
+    /// is `base`
+    /// # Panics
+    /// If the lock on the reputation is poisoned
+    #[must_use]
+    pub fn leader(
+        &self,
+        rules: &ReputationRules,
+        view: u64,
+        base: usize,
+        num_leaders: usize,
+    ) -> usize {
+        self.state
+            .read()
+            .unwrap()
+            .leader(rules, view, base, num_leaders)
+    }
+
+    /// Record newly decided leaves, of `decided_views`: every view up to the latest of them is
+    /// settled, and its leader, whose index in the plain rotation is `base(view)`, missed its
+    /// turn unless the view is one of them
+    /// # Panics
+    /// If the lock on the reputation is poisoned
+    pub fn record_decided(
+        &self,
+        rules: &ReputationRules,
+        decided_views: &[u64],
+        num_leaders: usize,
+        base: impl Fn(u64) -> usize,
+    ) {
+        let Some(&latest) = decided_views.iter().max() else {
+            return;
+        };
+        let mut state = self.state.write().unwrap();
+        // the genesis view has no leader
+        for view in state.next_view.max(1)..=latest {
+            if view % rules.epoch_views == 0 {
+                let down_weighted = state
+                    .turns
+                    .iter()
+                    .filter(|(_, turns)| {
+                        turns.iter().filter(|decided| !**decided).count() > rules.max_missed
+                    })
+                    .map(|(index, _)| *index)
+                    .collect();
+                state
+                    .down_weighted
+                    .insert(view / rules.epoch_views + 1, down_weighted);
+            }
+            let leader = state.leader(rules, view, base(view), num_leaders);
+            let turns = state.turns.entry(leader).or_default();
+            turns.push_back(decided_views.contains(&view));
+            if turns.len() > rules.window {
+                turns.pop_front();
+            }
+        }
+        state.next_view = latest + 1;
+        // the previous epoch is kept for proposals of its last views arriving late
+        let previous_epoch = (latest / rules.epoch_views).saturating_sub(1);
+        state.down_weighted = state.down_weighted.split_off(&previous_epoch);
+    }
+
+    /// The indices of the leaders down-weighted in the epoch of `view`
+    /// # Panics
+    /// If the lock on the reputation is poisoned
+    #[must_use]
+    pub fn down_weighted(&self, rules: &ReputationRules, view: u64) -> BTreeSet<usize> {
+        self.state
+            .read()
+            .unwrap()
+            .down_weighted
+            .get(&(view / rules.epoch_views))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
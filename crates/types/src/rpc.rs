@@ -0,0 +1,588 @@
+//! Request/response calls between nodes, over the fire-and-forget messages of a
+//! [`ConnectedNetwork`]
+//!
+//! Catchup, block fetch and peer status queries ask one peer for something and wait for its
+//! answer. [`NetworkRpc`] sends such a request in a direct message carrying a correlation id,
+//! and hands the response, which carries the same id, to the caller waiting on it. What is asked
+//! and answered is typed by an [`RpcMethod`], whose handler a node registers to answer the
+//! requests of its peers. Tasks which are not generic over the network make and answer calls
+//! through the [`RpcClient`] trait instead, with [`call`] and [`serve`].
+//!
+//! The sender of a message is whoever it claims to be, so requests and responses are signed.
+//! A request is only answered if it is signed by the node the response goes to, for this node,
+//! and a response is only taken if it is signed by the node the request went to, for the node
+//! which sent it. Unsigned messages would let any node have its peers send their responses to
+//! a victim, or answer the requests of others.
+//!
+//! A request which is not answered within [`RpcConfig::timeout_ms`] fails. A node handles at
+//! most [`RpcConfig::max_incoming_requests`] requests of its peers at once, answers included:
+//! the requests beyond are dropped, and time out at their callers, rather than answered with an
+//! error, so that no request costs a reply the limit does not count. A node waits for one of
+//! its requests to be answered before it sends more than
+//! [`RpcConfig::max_outgoing_requests`] at once.
+//!
+//! [`NetworkRpc`] works over every network, as it only sends direct messages, and the network
+//! message task hands it the requests and responses received, see [`RpcEndpoint`]. The web
+//! server network does not deliver direct messages of this kind, so requests over it fail to be
+//! sent.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    future::Future,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use async_compatibility_layer::art::{async_spawn, async_timeout};
+use async_lock::{Semaphore, SemaphoreGuardArc};
+use bincode::Options;
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use hotshot_constants::{
+    RPC_MAX_INCOMING_REQUESTS, RPC_MAX_OUTGOING_REQUESTS, RPC_TIMEOUT_MS, VERSION_0_1,
+};
+use hotshot_utils::bincode::bincode_opts;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use tracing::{debug, warn};
+
+use crate::{
+    message::{DataMessage, Message, MessageKind},
+    signing::{self, SigningDomain},
+    traits::{
+        network::{ConnectedNetwork, NetworkError},
+        node_implementation::NodeType,
+        signature_key::SignatureKey,
+    },
+};
+
+/// A kind of request a node can ask its peers, and the response they answer it with
+pub trait RpcMethod: Send + Sync + 'static {
+    /// The name requests of this method are sent under, unique among the methods of a node
+    const NAME: &'static str;
+    /// What is asked
+    type Request: Serialize + DeserializeOwned + Send + Sync + 'static;
+    /// What is answered
+    type Response: Serialize + DeserializeOwned + Send + 'static;
+}
+
+/// A request of an [`RpcMethod`], as sent over the network
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct RpcRequest<K: SignatureKey> {
+    /// the correlation id the response carries too, unique among the requests of the sender
+    pub id: u64,
+    /// the [`RpcMethod::NAME`] of the method
+    pub method: String,
+    /// the serialized [`RpcMethod::Request`]
+    pub body: Vec<u8>,
+    /// the signature of the sender over [`RpcRequest::signed_bytes`]
+    pub signature: K::PureAssembledSignatureType,
+}
+
+impl<K: SignatureKey> RpcRequest<K> {
+    /// What the sender of the request `id` of `method` with `body` to `recipient` signs
+    ///
+    /// # Panics
+    ///
+    /// If they cannot be serialized, which they always can
+    #[must_use]
+    pub fn signed_bytes(id: u64, method: &str, body: &[u8], recipient: &K) -> Vec<u8> {
+        bincode_opts()
+            .serialize(&(id, method, body, recipient))
+            .expect("RPC requests serialize")
+    }
+}
+
+/// The response to an [`RpcRequest`], as sent over the network
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(bound(deserialize = ""))]
+pub struct RpcResponse<K: SignatureKey> {
+    /// the correlation id of the request
+    pub id: u64,
+    /// the serialized [`RpcMethod::Response`], or why the request could not be answered
+    pub result: Result<Vec<u8>, String>,
+    /// the signature of the sender over [`RpcResponse::signed_bytes`]
+    pub signature: K::PureAssembledSignatureType,
+}
+
+impl<K: SignatureKey> RpcResponse<K> {
+    /// What the sender of `result`, the response to the request `id` of `requester`, signs
+    ///
+    /// # Panics
+    ///
+    /// If they cannot be serialized, which they always can
+    #[must_use]
+    pub fn signed_bytes(id: u64, result: &Result<Vec<u8>, String>, requester: &K) -> Vec<u8> {
+        bincode_opts()
+            .serialize(&(id, result, requester))
+            .expect("RPC responses serialize")
+    }
+}
+
+/// How long requests wait for their responses, and how many are handled at once
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RpcConfig {
+    /// milliseconds a request waits for its response, including for a slot among the
+    /// outgoing requests, before it fails
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// maximum number of requests waiting for their responses at once
+    #[serde(default = "default_max_outgoing_requests")]
+    pub max_outgoing_requests: usize,
+    /// maximum number of requests of peers handled at once, beyond which requests are dropped
+    #[serde(default = "default_max_incoming_requests")]
+    pub max_incoming_requests: usize,
+}
+
+/// the default of [`RpcConfig::timeout_ms`]
+fn default_timeout_ms() -> u64 {
+    RPC_TIMEOUT_MS
+}
+
+/// the default of [`RpcConfig::max_outgoing_requests`]
+fn default_max_outgoing_requests() -> usize {
+    RPC_MAX_OUTGOING_REQUESTS
+}
+
+/// the default of [`RpcConfig::max_incoming_requests`]
+fn default_max_incoming_requests() -> usize {
+    RPC_MAX_INCOMING_REQUESTS
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: RPC_TIMEOUT_MS,
+            max_outgoing_requests: RPC_MAX_OUTGOING_REQUESTS,
+            max_incoming_requests: RPC_MAX_INCOMING_REQUESTS,
+        }
+    }
+}
+
+/// Why a request failed
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub))]
+pub enum RpcError {
+    /// The request could not be signed
+    Sign,
+    /// The request could not be sent
+    Send {
+        /// the error of the network
+        source: NetworkError,
+    },
+    /// No response came in time
+    Timeout {
+        /// the method of the request
+        method: &'static str,
+    },
+    /// The request or its response could not be serialized
+    Serialization {
+        /// the error of the serialization
+        source: bincode::Error,
+    },
+    /// The peer answered that it could not handle the request
+    Remote {
+        /// why the peer could not handle it
+        message: String,
+    },
+    /// The response was dropped before it came, as the node shut down
+    Cancelled,
+}
+
+/// Receives the RPC messages of a node; implemented by [`NetworkRpc`] for the network message
+/// task, which is not generic over the network
+pub trait RpcEndpoint<K: SignatureKey>: Send + Sync {
+    /// Answer `request` of `sender`, in the background, if `sender` signed it
+    fn handle_request(&self, sender: K, request: RpcRequest<K>);
+
+    /// Hand `response` from `sender` to the request waiting for it, if the recipient of the
+    /// request signed it
+    fn handle_response(&self, sender: K, response: RpcResponse<K>);
+}
+
+/// The handler of the requests of a method, on serialized requests and responses
+pub type RpcHandler<K> =
+    Arc<dyn Fn(K, Vec<u8>) -> BoxFuture<'static, Result<Vec<u8>, String>> + Send + Sync>;
+
+/// Makes and answers the calls of a node on serialized requests and responses; implemented by
+/// [`NetworkRpc`] for the tasks which are not generic over the network, which type their calls
+/// with [`call`] and [`serve`]
+pub trait RpcClient<K>: Send + Sync {
+    /// Ask `recipient` for `body`, a serialized request of `method`, and wait for its serialized
+    /// response
+    fn call_raw(
+        &self,
+        recipient: K,
+        method: &'static str,
+        body: Vec<u8>,
+    ) -> BoxFuture<'_, Result<Vec<u8>, RpcError>>;
+
+    /// Answer the requests of `method` with `handler`, replacing the handler registered before
+    fn serve_raw(&self, method: &'static str, handler: RpcHandler<K>);
+}
+
+/// Ask `recipient` for `request` through `client`, and wait for its response
+///
+/// # Errors
+///
+/// If the request cannot be sent, is not answered in time, or the recipient answers that it
+/// could not handle it
+pub async fn call<M: RpcMethod, K>(
+    client: &(impl RpcClient<K> + ?Sized),
+    recipient: K,
+    request: &M::Request,
+) -> Result<M::Response, RpcError> {
+    let body = bincode_opts()
+        .serialize(request)
+        .context(SerializationSnafu)?;
+    let response = client.call_raw(recipient, M::NAME, body).await?;
+    bincode_opts()
+        .deserialize(&response)
+        .context(SerializationSnafu)
+}
+
+/// Answer the requests of `M` through `client` with `handler`, replacing the handler registered
+/// before
+pub fn serve<M, K, F, FUT>(client: &(impl RpcClient<K> + ?Sized), handler: F)
+where
+    M: RpcMethod,
+    K: Send + 'static,
+    F: Fn(K, M::Request) -> FUT + Send + Sync + 'static,
+    FUT: Future<Output = Result<M::Response, String>> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+    let erased: RpcHandler<K> = Arc::new(move |sender, body| {
+        let handler = Arc::clone(&handler);
+        async move {
+            let request = bincode_opts()
+                .deserialize::<M::Request>(&body)
+                .map_err(|e| format!("malformed {} request: {e}", M::NAME))?;
+            let response = handler(sender, request).await?;
+            bincode_opts()
+                .serialize(&response)
+                .map_err(|e| format!("unserializable {} response: {e}", M::NAME))
+        }
+        .boxed()
+    });
+    client.serve_raw(M::NAME, erased);
+}
+
+/// A request waiting for its response
+struct PendingRequest<K> {
+    /// the peer the request went to, the only one whose response is taken
+    recipient: K,
+    /// where its response goes
+    response: oneshot::Sender<Result<Vec<u8>, String>>,
+}
+
+/// Request/response calls over `NET`, see the [module documentation](self)
+pub struct NetworkRpc<TYPES: NodeType, NET> {
+    /// the network requests and responses are sent over
+    network: Arc<NET>,
+    /// the chain id messages are sent with
+    chain_id: u64,
+    /// the key of this node, which sends the requests and responses
+    public_key: TYPES::SignatureKey,
+    /// the key requests and responses are signed with
+    private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+    /// timeouts and limits
+    config: RpcConfig,
+    /// the correlation id of the next request
+    next_id: Arc<AtomicU64>,
+    /// the requests waiting for their responses, by correlation id
+    pending: Arc<Mutex<HashMap<u64, PendingRequest<TYPES::SignatureKey>>>>,
+    /// the handler of each method, by name
+    handlers: Arc<RwLock<HashMap<&'static str, RpcHandler<TYPES::SignatureKey>>>>,
+    /// slots for the requests waiting for their responses
+    outgoing: Arc<Semaphore>,
+    /// slots for the requests of peers being handled
+    incoming: Arc<Semaphore>,
+    /// phantom data for `TYPES`
+    _pd: PhantomData<TYPES>,
+}
+
+impl<TYPES: NodeType, NET> Clone for NetworkRpc<TYPES, NET> {
+    fn clone(&self) -> Self {
+        Self {
+            network: Arc::clone(&self.network),
+            chain_id: self.chain_id,
+            public_key: self.public_key.clone(),
+            private_key: self.private_key.clone(),
+            config: self.config,
+            next_id: Arc::clone(&self.next_id),
+            pending: Arc::clone(&self.pending),
+            handlers: Arc::clone(&self.handlers),
+            outgoing: Arc::clone(&self.outgoing),
+            incoming: Arc::clone(&self.incoming),
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<TYPES: NodeType, NET> Debug for NetworkRpc<TYPES, NET> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NetworkRpc")
+            .field("public_key", &self.public_key)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<TYPES: NodeType, NET: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>>
+    NetworkRpc<TYPES, NET>
+{
+    /// Calls from the node with `public_key`, signed with `private_key`, over `network`, on the
+    /// chain `chain_id`
+    #[must_use]
+    pub fn new(
+        network: Arc<NET>,
+        chain_id: u64,
+        public_key: TYPES::SignatureKey,
+        private_key: <TYPES::SignatureKey as SignatureKey>::PrivateKey,
+        config: RpcConfig,
+    ) -> Self {
+        Self {
+            network,
+            chain_id,
+            public_key,
+            private_key,
+            config,
+            next_id: Arc::new(AtomicU64::new(0)),
+            pending: Arc::default(),
+            handlers: Arc::default(),
+            outgoing: Arc::new(Semaphore::new(config.max_outgoing_requests.max(1))),
+            incoming: Arc::new(Semaphore::new(config.max_incoming_requests)),
+            _pd: PhantomData,
+        }
+    }
+
+    /// Answer the requests of `M` with `handler`, replacing the handler registered before
+    pub fn register<M, F, FUT>(&self, handler: F)
+    where
+        M: RpcMethod,
+        F: Fn(TYPES::SignatureKey, M::Request) -> FUT + Send + Sync + 'static,
+        FUT: Future<Output = Result<M::Response, String>> + Send + 'static,
+    {
+        serve::<M, _, _, _>(self, handler);
+    }
+
+    /// Ask `recipient` for `request`, and wait for its response
+    ///
+    /// # Errors
+    ///
+    /// If the request cannot be signed or sent, is not answered in time, or the recipient
+    /// answers that it could not handle it
+    pub async fn request<M: RpcMethod>(
+        &self,
+        recipient: TYPES::SignatureKey,
+        request: &M::Request,
+    ) -> Result<M::Response, RpcError> {
+        call::<M, _>(self, recipient, request).await
+    }
+
+    /// The number of requests waiting for their responses
+    ///
+    /// # Panics
+    ///
+    /// If the lock on the pending requests is poisoned
+    #[must_use]
+    pub fn pending_requests(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Send the request `id` to `recipient`, once there is a slot for it, and wait for its
+    /// serialized response
+    async fn send_request(
+        &self,
+        id: u64,
+        recipient: TYPES::SignatureKey,
+        method: &'static str,
+        body: Vec<u8>,
+    ) -> Result<Vec<u8>, RpcError> {
+        let _slot = self.outgoing.acquire_arc().await;
+        let signature = signing::sign::<TYPES::SignatureKey>(
+            &self.private_key,
+            SigningDomain::RpcRequest,
+            &RpcRequest::signed_bytes(id, method, &body, &recipient),
+        )
+        .map_err(|_| RpcError::Sign)?;
+        let (response, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(
+            id,
+            PendingRequest {
+                recipient: recipient.clone(),
+                response,
+            },
+        );
+        let request = RpcRequest {
+            id,
+            method: method.to_string(),
+            body,
+            signature,
+        };
+        self.network
+            .direct_message(self.message(DataMessage::RpcRequest(request)), recipient)
+            .await
+            .context(SendSnafu)?;
+        match receiver.await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(message)) => Err(RpcError::Remote { message }),
+            Err(oneshot::Canceled) => Err(RpcError::Cancelled),
+        }
+    }
+
+    /// A message of this node with `data`
+    fn message(&self, data: DataMessage<TYPES>) -> Message<TYPES> {
+        Message {
+            version: VERSION_0_1,
+            chain_id: self.chain_id,
+            sender: self.public_key.clone(),
+            kind: MessageKind::from(data),
+        }
+    }
+
+    /// Send `result` to `requester` as the response to its request `id`, in the background,
+    /// releasing the incoming `slot` the request holds once it is sent
+    fn respond(
+        &self,
+        requester: TYPES::SignatureKey,
+        id: u64,
+        result: Result<Vec<u8>, String>,
+        slot: SemaphoreGuardArc,
+    ) {
+        let Ok(signature) = signing::sign::<TYPES::SignatureKey>(
+            &self.private_key,
+            SigningDomain::RpcResponse,
+            &RpcResponse::signed_bytes(id, &result, &requester),
+        ) else {
+            warn!("Failed to sign an RPC response");
+            return;
+        };
+        let network = Arc::clone(&self.network);
+        let message = self.message(DataMessage::RpcResponse(RpcResponse {
+            id,
+            result,
+            signature,
+        }));
+        async_spawn(async move {
+            if let Err(e) = network.direct_message(message, requester).await {
+                warn!("Failed to send an RPC response: {:?}", e);
+            }
+            drop(slot);
+        });
+    }
+}
+
+impl<TYPES: NodeType, NET: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>>
+    RpcClient<TYPES::SignatureKey> for NetworkRpc<TYPES, NET>
+{
+    fn call_raw(
+        &self,
+        recipient: TYPES::SignatureKey,
+        method: &'static str,
+        body: Vec<u8>,
+    ) -> BoxFuture<'_, Result<Vec<u8>, RpcError>> {
+        async move {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let timeout = Duration::from_millis(self.config.timeout_ms);
+            let result =
+                async_timeout(timeout, self.send_request(id, recipient, method, body)).await;
+            // a request which timed out is still registered
+            self.pending.lock().unwrap().remove(&id);
+            result.map_err(|_| RpcError::Timeout { method })?
+        }
+        .boxed()
+    }
+
+    fn serve_raw(&self, method: &'static str, handler: RpcHandler<TYPES::SignatureKey>) {
+        self.handlers.write().unwrap().insert(method, handler);
+    }
+}
+
+impl<TYPES: NodeType, NET: ConnectedNetwork<Message<TYPES>, TYPES::SignatureKey>>
+    RpcEndpoint<TYPES::SignatureKey> for NetworkRpc<TYPES, NET>
+{
+    fn handle_request(
+        &self,
+        sender: TYPES::SignatureKey,
+        request: RpcRequest<TYPES::SignatureKey>,
+    ) {
+        let RpcRequest {
+            id,
+            method,
+            body,
+            signature,
+        } = request;
+        // the response goes to the sender, which must be the node which asked
+        if !signing::validate(
+            &sender,
+            &signature,
+            SigningDomain::RpcRequest,
+            &RpcRequest::signed_bytes(id, &method, &body, &self.public_key),
+        ) {
+            debug!(
+                "Dropping an RPC request not signed by its sender {:?}",
+                sender
+            );
+            return;
+        }
+        // every reply, errors included, takes a slot
+        let Some(slot) = self.incoming.try_acquire_arc() else {
+            debug!("Too many RPC requests to handle the one from {:?}", sender);
+            return;
+        };
+        let handler = self.handlers.read().unwrap().get(method.as_str()).cloned();
+        let Some(handler) = handler else {
+            debug!(
+                "RPC request from {:?} for unknown method {}",
+                sender, method
+            );
+            self.respond(sender, id, Err(format!("unknown method {method}")), slot);
+            return;
+        };
+        let rpc = self.clone();
+        async_spawn(async move {
+            let result = handler(sender.clone(), body).await;
+            rpc.respond(sender, id, result, slot);
+        });
+    }
+
+    fn handle_response(
+        &self,
+        sender: TYPES::SignatureKey,
+        response: RpcResponse<TYPES::SignatureKey>,
+    ) {
+        let mut pending = self.pending.lock().unwrap();
+        let Some(request) = pending.remove(&response.id) else {
+            debug!(
+                "Dropping the response of {:?} to no pending request",
+                sender
+            );
+            return;
+        };
+        // only the recipient of the request answers it, whoever the message claims to be from
+        if request.recipient != sender
+            || !signing::validate(
+                &request.recipient,
+                &response.signature,
+                SigningDomain::RpcResponse,
+                &RpcResponse::signed_bytes(response.id, &response.result, &self.public_key),
+            )
+        {
+            warn!(
+                "Dropping a response from {:?} not signed by {:?}, the recipient of the request",
+                sender, request.recipient
+            );
+            pending.insert(response.id, request);
+            return;
+        }
+        // the receiver is gone if the request was given up on
+        let _ = request.response.send(response.result);
+    }
+}
@@ -0,0 +1,136 @@
+//! Tuning of the threads consensus runs on
+//!
+//! By default all of consensus and networking runs on whatever runtime the caller set up. A
+//! [`RuntimeConfig`] moves the heaviest work to threads of its own: the verification of vote
+//! signatures to a pool of blocking threads, the receiving and the sending of messages to
+//! runtimes of their own, and the consensus task to a thread pinned to one core, so that a flood
+//! of messages cannot starve consensus.
+//!
+//! Like the signing context, the verification pool is shared by the whole process: it is set
+//! with [`configure`] when a node starts, and used by [`verify`].
+
+use std::{
+    io,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex, OnceLock, RwLock},
+    thread,
+};
+
+use futures::channel::oneshot;
+use serde::{Deserialize, Serialize};
+
+/// How the work of a node is spread over threads. The default runs everything on the runtime
+/// of the caller.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct RuntimeConfig {
+    /// threads verifying the signatures of votes, 0 to verify them on the task receiving them
+    pub verification_threads: usize,
+    /// worker threads of a runtime dedicated to receiving messages, 0 to receive them on the
+    /// runtime of the caller
+    pub inbound_threads: usize,
+    /// worker threads of a runtime dedicated to sending messages, 0 to send them on the runtime
+    /// of the caller
+    pub outbound_threads: usize,
+    /// the core the consensus task runs on, alone on a thread of its own, if any
+    pub consensus_core: Option<usize>,
+}
+
+/// Work sent to the threads of a [`BlockingPool`]
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A pool of threads dedicated to blocking work, such as verifying signatures
+#[derive(Clone, Default)]
+pub struct BlockingPool {
+    /// the queue of the threads, none if work runs inline
+    sender: Option<Arc<Mutex<mpsc::Sender<Job>>>>,
+    /// the number of threads
+    threads: usize,
+}
+
+impl BlockingPool {
+    /// A pool of `threads` threads, named after `name`, or running work inline if `threads` is 0.
+    /// The threads exit once every clone of the pool is dropped.
+    /// # Errors
+    /// If a thread cannot be started
+    pub fn new(name: &str, threads: usize) -> io::Result<Self> {
+        if threads == 0 {
+            return Ok(Self::default());
+        }
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("{name}-{index}"))
+                .spawn(move || loop {
+                    let Ok(queue) = receiver.lock() else {
+                        return;
+                    };
+                    let Ok(job) = queue.recv() else {
+                        return;
+                    };
+                    drop(queue);
+                    // a job which panics loses its result, but not its thread
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                })?;
+        }
+        Ok(Self {
+            sender: Some(Arc::new(Mutex::new(sender))),
+            threads,
+        })
+    }
+
+    /// The number of threads of the pool, 0 if work runs inline
+    #[must_use]
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Run `job` on the pool, and wait for its result. `None` if the job panicked.
+    pub async fn run<T: Send + 'static>(
+        &self,
+        job: impl FnOnce() -> T + Send + 'static,
+    ) -> Option<T> {
+        let Some(sender) = &self.sender else {
+            return Some(job());
+        };
+        let (result_sender, result) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            // the receiver is gone if the caller stopped waiting
+            let _ = result_sender.send(job());
+        });
+        sender.lock().ok()?.send(job).ok()?;
+        result.await.ok()
+    }
+}
+
+/// The verification pool shared by the whole process
+fn verification_pool() -> &'static RwLock<BlockingPool> {
+    static GLOBAL: OnceLock<RwLock<BlockingPool>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(BlockingPool::default()))
+}
+
+/// Verify signatures, process wide, on the threads `config` asks for
+/// # Errors
+/// If the verification threads cannot be started
+/// # Panics
+/// If the lock on the verification pool is poisoned
+pub fn configure(config: &RuntimeConfig) -> io::Result<()> {
+    if verification_pool().read().unwrap().threads() == config.verification_threads {
+        return Ok(());
+    }
+    let pool = BlockingPool::new("hotshot-verify", config.verification_threads)?;
+    *verification_pool().write().unwrap() = pool;
+    Ok(())
+}
+
+/// Run the signature verification `job` on the verification pool of the process. A job which
+/// panicked verified nothing.
+/// # Panics
+/// If the lock on the verification pool is poisoned
+pub async fn verify(job: impl FnOnce() -> bool + Send + 'static) -> bool {
+    let pool = verification_pool().read().unwrap().clone();
+    pool.run(job).await.unwrap_or(false)
+}
@@ -0,0 +1,164 @@
+//! Detection of safety violations
+//!
+//! Two valid quorum certificates for different leaves of the same view, or a decided leaf which
+//! does not extend the previously decided one, mean that more than a third of the stake is
+//! faulty and the chain may have forked. The [`SafetyMonitor`] watches the certificates and
+//! decides of a node and produces the [`SafetyEvidence`] of such a violation, upon which the node
+//! stops voting: halting is preferable to extending a forked chain.
+
+use std::collections::BTreeMap;
+
+use commit::{Commitment, Committable};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::Leaf,
+    simple_certificate::QuorumCertificate,
+    traits::node_implementation::{ConsensusTime, NodeType},
+    vote::HasViewNumber,
+};
+
+/// Proof that the chain may have forked
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(bound(deserialize = ""))]
+pub enum SafetyEvidence<TYPES: NodeType> {
+    /// Two valid quorum certificates of the same view, for different leaves
+    ConflictingCertificates {
+        /// the certificate seen first
+        first: QuorumCertificate<TYPES>,
+        /// the conflicting certificate
+        second: QuorumCertificate<TYPES>,
+    },
+    /// A leaf was decided in a view in which a different leaf had already been decided
+    ConflictingDecides {
+        /// the view both leaves were decided in
+        view_number: TYPES::Time,
+        /// the leaf decided first
+        decided: Commitment<Leaf<TYPES>>,
+        /// the conflicting leaf
+        conflicting: Commitment<Leaf<TYPES>>,
+    },
+    /// A decided leaf does not extend the previously decided leaf
+    Reorganization {
+        /// view of the previously decided leaf
+        decided_view: TYPES::Time,
+        /// the previously decided leaf
+        decided: Commitment<Leaf<TYPES>>,
+        /// the new decided leaf extending something else
+        leaf: Leaf<TYPES>,
+    },
+}
+
+impl<TYPES: NodeType> SafetyEvidence<TYPES> {
+    /// The view in which the violation was observed
+    #[must_use]
+    pub fn view_number(&self) -> TYPES::Time {
+        match self {
+            Self::ConflictingCertificates { second, .. } => second.get_view_number(),
+            Self::ConflictingDecides { view_number, .. } => *view_number,
+            Self::Reorganization { leaf, .. } => leaf.view_number,
+        }
+    }
+}
+
+/// Watches the certificates and decides of a node for conflicts
+#[derive(Debug)]
+pub struct SafetyMonitor<TYPES: NodeType> {
+    /// the first valid quorum certificate seen for each view
+    certificates: BTreeMap<TYPES::Time, QuorumCertificate<TYPES>>,
+    /// the leaf decided in each view
+    decided: BTreeMap<TYPES::Time, Commitment<Leaf<TYPES>>>,
+    /// how many views before the last decided view are remembered
+    history: u64,
+}
+
+impl<TYPES: NodeType> SafetyMonitor<TYPES> {
+    /// Create a monitor remembering `history` views before the last decided view
+    #[must_use]
+    pub fn new(history: u64) -> Self {
+        Self {
+            certificates: BTreeMap::new(),
+            decided: BTreeMap::new(),
+            history,
+        }
+    }
+
+    /// Record the valid certificate `qc`. Returns the evidence of a violation if another leaf
+    /// was certified in the same view.
+    pub fn observe_certificate(
+        &mut self,
+        qc: &QuorumCertificate<TYPES>,
+    ) -> Option<SafetyEvidence<TYPES>> {
+        // the genesis certificate is not signed, and shared by every node
+        if qc.is_genesis {
+            return None;
+        }
+        match self.certificates.get(&qc.view_number) {
+            Some(first) if first.data.leaf_commit != qc.data.leaf_commit => {
+                Some(SafetyEvidence::ConflictingCertificates {
+                    first: first.clone(),
+                    second: qc.clone(),
+                })
+            }
+            Some(_) => None,
+            None => {
+                self.certificates.insert(qc.view_number, qc.clone());
+                None
+            }
+        }
+    }
+
+    /// Record the leaves of a decide, newest first, as in
+    /// [`EventType::Decide`](crate::event::EventType::Decide). `complete` is whether the chain
+    /// reaches back to the previously decided leaf; only then is it checked to extend it.
+    /// Returns the evidence of a violation if the decide conflicts with an earlier one.
+    pub fn observe_decide(
+        &mut self,
+        leaf_chain: &[Leaf<TYPES>],
+        complete: bool,
+    ) -> Option<SafetyEvidence<TYPES>> {
+        let last_decided = self
+            .decided
+            .last_key_value()
+            .map(|(view, commitment)| (*view, *commitment));
+        for leaf in leaf_chain.iter().rev() {
+            let commitment = leaf.commit();
+            match self.decided.get(&leaf.view_number) {
+                Some(decided) if *decided != commitment => {
+                    return Some(SafetyEvidence::ConflictingDecides {
+                        view_number: leaf.view_number,
+                        decided: *decided,
+                        conflicting: commitment,
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    self.decided.insert(leaf.view_number, commitment);
+                }
+            }
+        }
+        if let (Some((decided_view, decided)), Some(oldest), true) =
+            (last_decided, leaf_chain.last(), complete)
+        {
+            if oldest.view_number > decided_view && oldest.parent_commitment != decided {
+                return Some(SafetyEvidence::Reorganization {
+                    decided_view,
+                    decided,
+                    leaf: oldest.clone(),
+                });
+            }
+        }
+        self.prune();
+        None
+    }
+
+    /// Forget the views long before the last decided view
+    fn prune(&mut self) {
+        let Some(last_decided) = self.decided.keys().next_back().copied() else {
+            return;
+        };
+        let keep_from = TYPES::Time::new((*last_decided).saturating_sub(self.history));
+        self.certificates = self.certificates.split_off(&keep_from);
+        self.decided = self.decided.split_off(&keep_from);
+    }
+}
@@ -12,7 +12,7 @@ use jf_primitives::{
     errors::PrimitivesError,
     signatures::{
         bls_over_bn254::{BLSOverBN254CurveSignatureScheme, KeyPair, SignKey, VerKey},
-        SignatureScheme,
+        AggregateableSignatureSchemes, SignatureScheme,
     },
 };
 use rand::SeedableRng;
@@ -44,6 +44,32 @@ impl SignatureKey for BLSPubKey {
         BLSOverBN254CurveSignatureScheme::verify(&(), self, generic_msg, signature).is_ok()
     }
 
+    /// Verifies the aggregate of the signatures, in two pairings when the batch signs one message
+    /// and one more pairing per signature otherwise, instead of two per signature. Signatures
+    /// which only cancel each other out pass, as would the certificate they are assembled into.
+    fn batch_verify(batch: &[(&Self, &[u8], &Self::PureAssembledSignatureType)]) -> bool {
+        let Some((_, first, _)) = batch.first() else {
+            return true;
+        };
+        let keys: Vec<Self> = batch.iter().map(|(key, _, _)| **key).collect();
+        let signatures: Vec<_> = batch
+            .iter()
+            .map(|(_, _, signature)| (*signature).clone())
+            .collect();
+        let Ok(aggregate) = BLSOverBN254CurveSignatureScheme::aggregate(&(), &keys, &signatures)
+        else {
+            return false;
+        };
+        if batch.iter().all(|(_, data, _)| data == first) {
+            BLSOverBN254CurveSignatureScheme::multi_sig_verify(&(), &keys, first, &aggregate)
+                .is_ok()
+        } else {
+            let messages: Vec<&[u8]> = batch.iter().map(|(_, data, _)| *data).collect();
+            BLSOverBN254CurveSignatureScheme::aggregate_verify(&(), &keys, &messages, &aggregate)
+                .is_ok()
+        }
+    }
+
     fn sign(
         sk: &Self::PrivateKey,
         data: &[u8],
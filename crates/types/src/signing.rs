@@ -0,0 +1,214 @@
+//! Domain separated encodings of what nodes sign
+//!
+//! Nodes sign votes, proposals, VID dispersals, contact records and decide notifications with
+//! the same key. Signing their raw bytes would let a signature made for one purpose, or on one
+//! network, be presented as valid for another. Instead every signed message is first encoded as
+//! a tagged hash of its purpose, the version of this encoding and the chain id of the network,
+//! with the message itself; the hash is also of the fixed size aggregate signatures require.
+//!
+//! The chain id and the encoding in use are set once for the whole process with [`configure`],
+//! when the node starts. Networks whose nodes predate this encoding run with
+//! [`SigningContext::legacy`] until all their nodes are upgraded.
+
+use std::sync::{OnceLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{runtime, traits::signature_key::SignatureKey};
+
+/// Version of the encoding of signed messages, part of every tag
+pub const SIGNING_ENCODING_VERSION: u8 = 1;
+
+/// What a signature is for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SigningDomain {
+    /// A vote, or the certificate assembled from votes. Votes of different kinds are already
+    /// told apart by the commitment they sign.
+    Vote,
+    /// A quorum proposal, which signs the commitment of its leaf
+    QuorumProposal,
+    /// A DA proposal, which signs the hash of its transactions
+    DAProposal,
+    /// The commitment of a DA proposal, which signs
+    /// [`crate::data::DAProposalCommitment::signed_bytes`]
+    DAProposalCommitment,
+    /// A compact DA proposal, which signs [`crate::data::CompactDAProposal::signed_bytes`]
+    CompactDAProposal,
+    /// A VID dispersal, which signs its payload commitment
+    VidDisperse,
+    /// A libp2p contact record binding a consensus key to a peer id
+    ContactRecord,
+    /// A decide notification sent to a webhook
+    DecideNotification,
+    /// A request for a sampled VID share, which signs [`crate::data::VidSample::request_bytes`]
+    VidShareRequest,
+    /// An RPC request, which signs [`crate::rpc::RpcRequest::signed_bytes`]
+    RpcRequest,
+    /// An RPC response, which signs [`crate::rpc::RpcResponse::signed_bytes`]
+    RpcResponse,
+    /// A governance transaction, which signs
+    /// [`crate::governance::GovernanceTransaction::signed_bytes`]
+    Governance,
+}
+
+impl SigningDomain {
+    /// The tag of the domain, hashed with the message
+    #[must_use]
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::Vote => "vote",
+            Self::QuorumProposal => "quorum proposal",
+            Self::DAProposal => "da proposal",
+            Self::DAProposalCommitment => "da proposal commitment",
+            Self::CompactDAProposal => "compact da proposal",
+            Self::VidDisperse => "vid disperse",
+            Self::ContactRecord => "contact record",
+            Self::DecideNotification => "decide notification",
+            Self::VidShareRequest => "vid share request",
+            Self::RpcRequest => "rpc request",
+            Self::RpcResponse => "rpc response",
+            Self::Governance => "governance",
+        }
+    }
+}
+
+/// How the messages signed by the nodes of a network are encoded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigningContext {
+    /// the chain id of the network
+    pub chain_id: u64,
+    /// sign messages as they are, as nodes predating domain separation do
+    pub legacy: bool,
+}
+
+impl SigningContext {
+    /// The bytes to sign for `message` in `domain`
+    #[must_use]
+    pub fn encode(&self, domain: SigningDomain, message: &[u8]) -> Vec<u8> {
+        if self.legacy {
+            return message.to_vec();
+        }
+        let context = format!(
+            "HotShot v{SIGNING_ENCODING_VERSION} {} signature",
+            domain.tag()
+        );
+        let mut hasher = blake3::Hasher::new_derive_key(&context);
+        hasher.update(&self.chain_id.to_le_bytes());
+        hasher.update(message);
+        hasher.finalize().as_bytes().to_vec()
+    }
+}
+
+/// The signing context shared by the whole process
+fn global() -> &'static RwLock<SigningContext> {
+    static GLOBAL: OnceLock<RwLock<SigningContext>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(SigningContext::default()))
+}
+
+/// Replace the signing context shared by the whole process
+/// # Panics
+/// If the lock on the context is poisoned
+pub fn configure(context: SigningContext) {
+    *global().write().unwrap() = context;
+}
+
+/// The signing context shared by the whole process
+/// # Panics
+/// If the lock on the context is poisoned
+#[must_use]
+pub fn context() -> SigningContext {
+    *global().read().unwrap()
+}
+
+/// The bytes to sign for `message` in `domain`, according to the signing context of the process
+#[must_use]
+pub fn encode(domain: SigningDomain, message: &[u8]) -> Vec<u8> {
+    context().encode(domain, message)
+}
+
+/// Sign `message` in `domain`
+/// # Errors
+/// If the signature fails
+pub fn sign<KEY: SignatureKey>(
+    private_key: &KEY::PrivateKey,
+    domain: SigningDomain,
+    message: &[u8],
+) -> Result<KEY::PureAssembledSignatureType, KEY::SignError> {
+    KEY::sign(private_key, &encode(domain, message))
+}
+
+/// Whether `signature` is `key`'s signature of `message` in `domain`
+#[must_use]
+pub fn validate<KEY: SignatureKey>(
+    key: &KEY,
+    signature: &KEY::PureAssembledSignatureType,
+    domain: SigningDomain,
+    message: &[u8],
+) -> bool {
+    key.validate(signature, &encode(domain, message))
+}
+
+/// Whether `signature` is `key`'s signature of `message` in `domain`, verified on the
+/// verification threads of the process, see [`runtime::configure`]
+pub async fn validate_offloaded<KEY: SignatureKey + 'static>(
+    key: KEY,
+    signature: KEY::PureAssembledSignatureType,
+    domain: SigningDomain,
+    message: &[u8],
+) -> bool {
+    let encoded = encode(domain, message);
+    runtime::verify(move || key.validate(&signature, &encoded)).await
+}
+
+/// Whether every `(key, signature, message)` of `batch` is `key`'s signature of `message` in
+/// `domain`, verified at once, see [`SignatureKey::batch_verify`]
+#[must_use]
+pub fn validate_batch<KEY: SignatureKey, M: AsRef<[u8]>>(
+    batch: &[(KEY, KEY::PureAssembledSignatureType, M)],
+    domain: SigningDomain,
+) -> bool {
+    let encoded: Vec<_> = batch
+        .iter()
+        .map(|(_, _, message)| encode(domain, message.as_ref()))
+        .collect();
+    let items: Vec<_> = batch
+        .iter()
+        .zip(&encoded)
+        .map(|((key, signature, _), data)| (key, data.as_slice(), signature))
+        .collect();
+    KEY::batch_verify(&items)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encodings_are_separated_by_domain_and_chain() {
+        let message = [7u8; 32];
+        let context = SigningContext {
+            chain_id: 1,
+            legacy: false,
+        };
+        let vote = context.encode(SigningDomain::Vote, &message);
+        assert_eq!(vote.len(), 32);
+        assert_ne!(
+            vote,
+            context.encode(SigningDomain::QuorumProposal, &message)
+        );
+        assert_ne!(
+            vote,
+            SigningContext {
+                chain_id: 2,
+                legacy: false
+            }
+            .encode(SigningDomain::Vote, &message)
+        );
+
+        let legacy = SigningContext {
+            chain_id: 1,
+            legacy: true,
+        };
+        assert_eq!(legacy.encode(SigningDomain::Vote, &message), message);
+    }
+}
@@ -7,12 +7,12 @@ use std::{
 };
 
 use commit::{Commitment, CommitmentBoundsArkless, Committable};
-use ethereum_types::U256;
 
 use crate::{
     data::Leaf,
+    signing::{self, SigningDomain},
     simple_vote::{
-        DAData, QuorumData, TimeoutData, UpgradeProposalData, ViewSyncCommitData,
+        DAData, EpochChangeData, QuorumData, TimeoutData, UpgradeProposalData, ViewSyncCommitData,
         ViewSyncFinalizeData, ViewSyncPreCommitData, Voteable,
     },
     traits::{
@@ -102,13 +102,10 @@ impl<TYPES: NodeType, VOTEABLE: Voteable + 'static, THRESHOLD: Threshold<TYPES>>
         if self.is_genesis && self.view_number == TYPES::Time::genesis() {
             return true;
         }
-        let real_qc_pp = <TYPES::SignatureKey as SignatureKey>::get_public_parameter(
-            membership.get_committee_qc_stake_table(),
-            U256::from(Self::threshold(membership)),
-        );
+        let real_qc_pp = membership.get_committee_qc_params(Self::threshold(membership));
         <TYPES::SignatureKey as SignatureKey>::check(
             &real_qc_pp,
-            self.vote_commitment.as_ref(),
+            &signing::encode(SigningDomain::Vote, self.vote_commitment.as_ref()),
             self.signatures.as_ref().unwrap(),
         )
     }
@@ -159,6 +156,20 @@ impl<TYPES: NodeType> QuorumCertificate<TYPES> {
     }
 }
 
+impl<TYPES: NodeType> DACertificate<TYPES> {
+    /// Whether the certificate is valid, signed either by the DA committee, or by the whole
+    /// quorum when the payload was disseminated to every node because the committee was
+    /// unresponsive. Either attests that enough nodes hold the payload for it to be available.
+    #[must_use]
+    pub fn is_valid_da_cert<MEMBERSHIP: Membership<TYPES>>(
+        &self,
+        da_membership: &MEMBERSHIP,
+        quorum_membership: &MEMBERSHIP,
+    ) -> bool {
+        self.is_valid_cert(da_membership) || self.is_valid_cert(quorum_membership)
+    }
+}
+
 /// Type alias for a `QuorumCertificate`, which is a `SimpleCertificate` of `QuorumVotes`
 pub type QuorumCertificate<TYPES> = SimpleCertificate<TYPES, QuorumData<TYPES>, SuccessThreshold>;
 /// Type alias for a DA certificate over `DAData`
@@ -177,3 +188,7 @@ pub type ViewSyncFinalizeCertificate2<TYPES> =
 /// Type alias for a `UpgradeCertificate`, which is a `SimpleCertificate` of `UpgradeProposalData`
 pub type UpgradeCertificate<TYPES> =
     SimpleCertificate<TYPES, UpgradeProposalData<TYPES>, UpgradeThreshold>;
+/// Type alias for an `EpochChangeCertificate`, which is a `SimpleCertificate` of
+/// `EpochChangeData`
+pub type EpochChangeCertificate<TYPES> =
+    SimpleCertificate<TYPES, EpochChangeData<TYPES>, SuccessThreshold>;
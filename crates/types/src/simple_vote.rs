@@ -10,6 +10,9 @@ use hotshot_constants::Version;
 
 use crate::{
     data::{Leaf, VidCommitment},
+    epoch::{Epoch, EpochParameters},
+    governance::{commit_governance, GovernanceTransaction},
+    signing::{self, SigningDomain},
     traits::{node_implementation::NodeType, signature_key::SignatureKey},
     vote::{HasViewNumber, Vote},
 };
@@ -26,6 +29,9 @@ pub struct QuorumData<TYPES: NodeType> {
 pub struct DAData {
     /// Commitment to a block payload
     pub payload_commit: VidCommitment,
+    /// The governance transactions of the block, which replicas without the payload check the
+    /// quorum proposal against
+    pub governance: Vec<GovernanceTransaction>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
 /// Data used for a timeout vote.
@@ -77,6 +83,19 @@ pub struct UpgradeProposalData<TYPES: NodeType + DeserializeOwned> {
     /// The first block for which the new version will be in effect.
     pub new_version_first_block: TYPES::Time,
 }
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash, Eq)]
+/// Data used for an epoch change vote, on what the next epoch runs with.
+pub struct EpochChangeData<TYPES: NodeType> {
+    /// The epoch which starts
+    pub epoch: Epoch,
+    /// The first view of the epoch
+    pub first_view: TYPES::Time,
+    /// Commitment to the stake table of the epoch, see
+    /// [`stake_table_commitment`](crate::epoch::stake_table_commitment)
+    pub stake_table_commitment: [u8; 32],
+    /// The parameters of the network for the epoch
+    pub parameters: EpochParameters,
+}
 
 /// Marker trait for data or commitments that can be voted on.
 /// Only structs in this file can implement voteable.  This is enforced with the `Sealed` trait
@@ -149,7 +168,11 @@ impl<TYPES: NodeType, DATA: Voteable + 'static> SimpleVote<TYPES, DATA> {
         pub_key: &TYPES::SignatureKey,
         private_key: &<TYPES::SignatureKey as SignatureKey>::PrivateKey,
     ) -> Result<Self, <TYPES::SignatureKey as SignatureKey>::SignError> {
-        match TYPES::SignatureKey::sign(private_key, data.commit().as_ref()) {
+        match signing::sign::<TYPES::SignatureKey>(
+            private_key,
+            SigningDomain::Vote,
+            data.commit().as_ref(),
+        ) {
             Ok(signature) => Ok(Self {
                 signature: (pub_key.clone(), signature),
                 data,
@@ -178,9 +201,14 @@ impl<TYPES: NodeType> Committable for TimeoutData<TYPES> {
 
 impl Committable for DAData {
     fn commit(&self) -> Commitment<Self> {
-        commit::RawCommitmentBuilder::new("DA Vote")
-            .var_size_bytes(self.payload_commit.as_ref())
-            .finalize()
+        let builder = commit::RawCommitmentBuilder::new("DA Vote")
+            .var_size_bytes(self.payload_commit.as_ref());
+        // votes on blocks without governance transactions keep the commitment they had before
+        if self.governance.is_empty() {
+            builder.finalize()
+        } else {
+            commit_governance(builder, &self.governance).finalize()
+        }
     }
 }
 
@@ -207,6 +235,18 @@ impl<TYPES: NodeType> Committable for UpgradeProposalData<TYPES> {
     }
 }
 
+impl<TYPES: NodeType> Committable for EpochChangeData<TYPES> {
+    fn commit(&self) -> Commitment<Self> {
+        commit::RawCommitmentBuilder::new("Epoch Change Vote")
+            .u64(*self.epoch)
+            .u64(*self.first_view)
+            .fixed_size_bytes(&self.stake_table_commitment)
+            .u64(self.parameters.epoch_length)
+            .u64(self.parameters.da_committee_size)
+            .finalize()
+    }
+}
+
 /// This implements commit for all the types which contain a view and relay public key.
 fn view_and_relay_commit<TYPES: NodeType, T: Committable>(
     view: TYPES::Time,
@@ -256,3 +296,5 @@ pub type ViewSyncPreCommitVote<TYPES> = SimpleVote<TYPES, ViewSyncPreCommitData<
 pub type ViewSyncFinalizeVote<TYPES> = SimpleVote<TYPES, ViewSyncFinalizeData<TYPES>>;
 /// Upgrade proposal vote
 pub type UpgradeVote<TYPES> = SimpleVote<TYPES, UpgradeProposalData<TYPES>>;
+/// Epoch change vote type alias
+pub type EpochChangeVote<TYPES> = SimpleVote<TYPES, EpochChangeData<TYPES>>;
@@ -0,0 +1,199 @@
+//! Caching of what vote and certificate checks derive from the stake table of a committee
+//!
+//! Every vote is placed in the signer bitvec of its certificate by the index of its signer in
+//! the stake table, and every certificate is checked against public parameters assembled from
+//! the whole stake table. Both used to be computed again for every vote and every certificate,
+//! although the stake table of a static membership never changes. A [`StakeTableCache`] keeps
+//! them for the current epoch of the stake table instead, and starts over once the stake table
+//! it is asked about is of another epoch, because the membership changed.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use ethereum_types::U256;
+
+use crate::traits::signature_key::SignatureKey;
+
+/// The epoch of `stake_table`: a fingerprint which changes whenever the stake table does
+#[must_use]
+pub fn stake_table_epoch<KEY: SignatureKey>(stake_table: &[KEY::StakeTableEntry]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    stake_table.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// How often a [`StakeTableCache`] was asked for something it held
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeTableCacheStats {
+    /// lookups served from the cache
+    pub hits: u64,
+    /// lookups which had to be computed from the stake table
+    pub misses: u64,
+}
+
+impl StakeTableCacheStats {
+    /// The share of lookups served from the cache, in percent, if there were any
+    #[must_use]
+    pub fn hit_rate_percent(&self) -> Option<u64> {
+        let lookups = self.hits.saturating_add(self.misses);
+        (lookups > 0).then(|| self.hits.saturating_mul(100) / lookups)
+    }
+}
+
+/// What was derived from the stake table of one epoch
+struct CachedEpoch<KEY: SignatureKey> {
+    /// the epoch of the stake table
+    epoch: u64,
+    /// the index of each key in the stake table, once a key was looked up
+    indices: Option<HashMap<KEY, usize>>,
+    /// the public parameters of certificates, by stake threshold
+    params: HashMap<u64, KEY::QCParams>,
+}
+
+/// The indices and certificate parameters derived from the stake table of a committee, for its
+/// current epoch. Clones share the cache, so that all the clones of a membership derive them once.
+pub struct StakeTableCache<KEY: SignatureKey> {
+    /// what was derived from the stake table of the latest epoch asked about
+    state: Arc<RwLock<Option<CachedEpoch<KEY>>>>,
+    /// lookups served from the cache
+    hits: Arc<AtomicU64>,
+    /// lookups computed from the stake table
+    misses: Arc<AtomicU64>,
+}
+
+impl<KEY: SignatureKey> Clone for StakeTableCache<KEY> {
+    fn clone(&self) -> Self {
+        Self {
+            state: Arc::clone(&self.state),
+            hits: Arc::clone(&self.hits),
+            misses: Arc::clone(&self.misses),
+        }
+    }
+}
+
+impl<KEY: SignatureKey> Default for StakeTableCache<KEY> {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            hits: Arc::default(),
+            misses: Arc::default(),
+        }
+    }
+}
+
+impl<KEY: SignatureKey> Debug for StakeTableCache<KEY> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StakeTableCache")
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+// a cache only holds what can be derived again, so it does not tell memberships apart
+impl<KEY: SignatureKey> PartialEq for StakeTableCache<KEY> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<KEY: SignatureKey> Eq for StakeTableCache<KEY> {}
+
+impl<KEY: SignatureKey> Hash for StakeTableCache<KEY> {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl<KEY: SignatureKey> StakeTableCache<KEY> {
+    /// The index of `key` in `stake_table`, of epoch `epoch`, if it is in it
+    /// # Panics
+    /// If the lock on the cache is poisoned
+    pub fn index_of(
+        &self,
+        epoch: u64,
+        stake_table: &[KEY::StakeTableEntry],
+        key: &KEY,
+    ) -> Option<usize> {
+        {
+            let state = self.state.read().unwrap();
+            if let Some(indices) = state
+                .as_ref()
+                .filter(|cached| cached.epoch == epoch)
+                .and_then(|cached| cached.indices.as_ref())
+            {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return indices.get(key).copied();
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let mut state = self.state.write().unwrap();
+        Self::epoch_mut(&mut state, epoch)
+            .indices
+            .get_or_insert_with(|| {
+                stake_table
+                    .iter()
+                    .enumerate()
+                    .map(|(index, entry)| (KEY::get_public_key(entry), index))
+                    .collect()
+            })
+            .get(key)
+            .copied()
+    }
+
+    /// The public parameters certificates of `threshold` stake out of `stake_table`, of epoch
+    /// `epoch`, are checked against
+    /// # Panics
+    /// If the lock on the cache is poisoned
+    pub fn params(
+        &self,
+        epoch: u64,
+        stake_table: &[KEY::StakeTableEntry],
+        threshold: u64,
+    ) -> KEY::QCParams {
+        {
+            let state = self.state.read().unwrap();
+            if let Some(params) = state
+                .as_ref()
+                .filter(|cached| cached.epoch == epoch)
+                .and_then(|cached| cached.params.get(&threshold))
+            {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return params.clone();
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let params = KEY::get_public_parameter(stake_table.to_vec(), U256::from(threshold));
+        let mut state = self.state.write().unwrap();
+        Self::epoch_mut(&mut state, epoch)
+            .params
+            .insert(threshold, params.clone());
+        params
+    }
+
+    /// How often the cache was asked for something it held so far
+    #[must_use]
+    pub fn stats(&self) -> StakeTableCacheStats {
+        StakeTableCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// What was derived from the stake table of `epoch`, dropping what was derived for another
+    /// epoch
+    fn epoch_mut(state: &mut Option<CachedEpoch<KEY>>, epoch: u64) -> &mut CachedEpoch<KEY> {
+        if state.as_ref().is_some_and(|cached| cached.epoch != epoch) {
+            *state = None;
+        }
+        state.get_or_insert_with(|| CachedEpoch {
+            epoch,
+            indices: None,
+            params: HashMap::new(),
+        })
+    }
+}
@@ -0,0 +1,210 @@
+//! Diagnostics of a node whose view stopped advancing
+//!
+//! With a stall timeout configured, see [`HotShotConfig::stall_timeout`](crate::HotShotConfig),
+//! a watchdog reports the node stalled once its view has not changed for that long, and again
+//! every time as long again passes. The report is a [`StallDiagnostics`]: what the node saw of
+//! the view it is stuck in, which is what tells a missing leader from a partitioned node or a
+//! quorum short of votes.
+
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+use crate::traits::node_implementation::NodeType;
+
+/// The kinds of votes, those the watchdog counts and those of vote progress events
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VoteKind {
+    /// a vote on a quorum proposal
+    Quorum,
+    /// a vote on a DA proposal
+    DA,
+    /// a vote to time the view out
+    Timeout,
+    /// a vote of any phase of view sync
+    ViewSync,
+    /// a vote on an upgrade proposal, which the watchdog does not count
+    Upgrade,
+    /// a vote certifying the next epoch, which the watchdog does not count
+    EpochChange,
+}
+
+/// The votes received for the current view or later ones since the view started, which are
+/// yet to form certificates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VoteCounts {
+    /// votes on quorum proposals
+    pub quorum: u64,
+    /// votes on DA proposals
+    pub da: u64,
+    /// votes to time the view out
+    pub timeout: u64,
+    /// votes of view sync
+    pub view_sync: u64,
+}
+
+/// The timers which ran out since the current view started
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TimerStates {
+    /// view timeouts, each of which should have moved the node to the next view
+    pub view_timeouts: u64,
+    /// proposal timeouts, each of which reported a proposal missing
+    pub proposal_timeouts: u64,
+    /// view sync timeouts, each of which moved view sync on to its next relay
+    pub view_sync_timeouts: u64,
+    /// whether view sync was triggered
+    pub view_sync_triggered: bool,
+}
+
+/// Which members of the quorum the node heard from since the current view started
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PeerSummary<KEY> {
+    /// the number of members of the quorum
+    pub committee_size: usize,
+    /// the number of other members whose proposals or votes reached the node
+    pub heard_from: usize,
+    /// the other members nothing reached the node from
+    pub silent: Vec<KEY>,
+}
+
+/// What a node saw of the view it is stuck in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StallDiagnostics<TYPES: NodeType> {
+    /// the view the node is stuck in
+    pub view: TYPES::Time,
+    /// how long ago the view started
+    pub stalled_for: Duration,
+    /// the leader of the next view, whose proposal would move the node on
+    pub leader: TYPES::SignatureKey,
+    /// the view and sender of the last quorum proposal the node received, if any
+    pub last_proposal: Option<(TYPES::Time, TYPES::SignatureKey)>,
+    /// the votes received, which are yet to form certificates
+    pub votes: VoteCounts,
+    /// the stake votes need to form a certificate
+    pub vote_threshold: u64,
+    /// which members of the quorum the node heard from
+    pub peers: PeerSummary<TYPES::SignatureKey>,
+    /// the timers which ran out
+    pub timers: TimerStates,
+}
+
+/// What the watchdog records of the current view, to diagnose the node if it stalls in it
+#[derive(Clone, Debug)]
+pub struct StallTracker<TYPES: NodeType> {
+    /// the current view
+    view: TYPES::Time,
+    /// when the current view started
+    view_start: Instant,
+    /// the view and sender of the last quorum proposal received, over every view
+    last_proposal: Option<(TYPES::Time, TYPES::SignatureKey)>,
+    /// the votes received since the view started
+    votes: VoteCounts,
+    /// the timers which ran out since the view started
+    timers: TimerStates,
+    /// the nodes heard from since the view started
+    heard_from: BTreeSet<TYPES::SignatureKey>,
+}
+
+impl<TYPES: NodeType> StallTracker<TYPES> {
+    /// A tracker of `view`, which started at `now`
+    #[must_use]
+    pub fn new(view: TYPES::Time, now: Instant) -> Self {
+        Self {
+            view,
+            view_start: now,
+            last_proposal: None,
+            votes: VoteCounts::default(),
+            timers: TimerStates::default(),
+            heard_from: BTreeSet::new(),
+        }
+    }
+
+    /// The current view
+    #[must_use]
+    pub fn view(&self) -> TYPES::Time {
+        self.view
+    }
+
+    /// Move on to `view`, which started at `now`, forgetting what was seen of the views before.
+    /// Returns whether `view` is newer than the current view; older ones are ignored.
+    pub fn enter_view(&mut self, view: TYPES::Time, now: Instant) -> bool {
+        if view <= self.view {
+            return false;
+        }
+        self.view = view;
+        self.view_start = now;
+        self.votes = VoteCounts::default();
+        self.timers = TimerStates::default();
+        self.heard_from.clear();
+        true
+    }
+
+    /// Record a quorum proposal for `view` from `sender`
+    pub fn record_proposal(&mut self, view: TYPES::Time, sender: TYPES::SignatureKey) {
+        self.heard_from.insert(sender.clone());
+        self.last_proposal = Some((view, sender));
+    }
+
+    /// Record that a message from `sender` reached the node
+    pub fn record_peer(&mut self, sender: TYPES::SignatureKey) {
+        self.heard_from.insert(sender);
+    }
+
+    /// Record a vote of `kind` for `view` from `voter`. Votes for views before the current one
+    /// count for the peers heard from, but not among the votes.
+    pub fn record_vote(&mut self, kind: VoteKind, view: TYPES::Time, voter: TYPES::SignatureKey) {
+        self.heard_from.insert(voter);
+        if view < self.view {
+            return;
+        }
+        let count = match kind {
+            VoteKind::Quorum => &mut self.votes.quorum,
+            VoteKind::DA => &mut self.votes.da,
+            VoteKind::Timeout => &mut self.votes.timeout,
+            VoteKind::ViewSync => &mut self.votes.view_sync,
+            VoteKind::Upgrade | VoteKind::EpochChange => return,
+        };
+        *count += 1;
+    }
+
+    /// The timers which ran out since the view started, to record another one in
+    pub fn timers_mut(&mut self) -> &mut TimerStates {
+        &mut self.timers
+    }
+
+    /// The diagnostics of the node at `now`, if it is stuck in the current view.
+    ///
+    /// `own_key` is left out of the peers, `leader` leads the next view, `committee` is the
+    /// quorum and `vote_threshold` the stake a certificate needs.
+    #[must_use]
+    pub fn diagnostics(
+        &self,
+        now: Instant,
+        own_key: &TYPES::SignatureKey,
+        leader: TYPES::SignatureKey,
+        committee: &BTreeSet<TYPES::SignatureKey>,
+        vote_threshold: u64,
+    ) -> StallDiagnostics<TYPES> {
+        let others = committee.iter().filter(|member| *member != own_key);
+        let silent: Vec<_> = others
+            .clone()
+            .filter(|member| !self.heard_from.contains(*member))
+            .cloned()
+            .collect();
+        StallDiagnostics {
+            view: self.view,
+            stalled_for: now.saturating_duration_since(self.view_start),
+            leader,
+            last_proposal: self.last_proposal.clone(),
+            votes: self.votes,
+            vote_threshold,
+            peers: PeerSummary {
+                committee_size: committee.len(),
+                heard_from: others.count() - silent.len(),
+                silent,
+            },
+            timers: self.timers,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! A [`Metrics`] backend pushing every update to a statsd sink over UDP
+//!
+//! Counters, gauges and histograms are sent as they are updated, one datagram each, so nothing
+//! is aggregated on the node. With `dogstatsd` set, the configured tags are appended to every
+//! metric and histograms use the dogstatsd `h` type; plain statsd sinks get timers instead.
+//! Labels have no statsd equivalent and are dropped.
+
+use std::{io, net::UdpSocket, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::traits::metrics::{Counter, Gauge, Histogram, Label, Metrics};
+
+/// Where and how to push metrics to statsd
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StatsdConfig {
+    /// address of the sink, e.g. `127.0.0.1:8125`
+    pub address: String,
+    /// prefix of every metric name, joined to it with a `.`
+    #[serde(default)]
+    pub prefix: String,
+    /// tags sent with every metric, e.g. `node:3`; only sent to dogstatsd sinks
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// whether the sink understands the dogstatsd extensions
+    #[serde(default)]
+    pub dogstatsd: bool,
+}
+
+/// The socket metrics are sent through, shared by every metric of a [`StatsdMetrics`]
+#[derive(Debug)]
+struct Sink {
+    /// the connected socket
+    socket: UdpSocket,
+    /// the `|#...` suffix carrying the tags, empty if there are none
+    tags: String,
+    /// whether the sink understands the dogstatsd extensions
+    dogstatsd: bool,
+}
+
+impl Sink {
+    /// Send `value` of metric `name`, of statsd type `kind`
+    fn send(&self, name: &str, value: &str, kind: &str) {
+        let line = format!("{name}:{value}|{kind}{}", self.tags);
+        // metrics are best effort, a full or absent sink must not disturb consensus
+        if let Err(e) = self.socket.send(line.as_bytes()) {
+            debug!("Failed to send metric {name} to statsd: {e}");
+        }
+    }
+}
+
+/// A [`Metrics`] implementation sending every update to a statsd sink
+#[derive(Clone, Debug)]
+pub struct StatsdMetrics {
+    /// where the metrics are sent
+    sink: Arc<Sink>,
+    /// prefix of the names of the metrics created from this group
+    prefix: String,
+}
+
+impl StatsdMetrics {
+    /// Connect to the sink described by `config`
+    /// # Errors
+    /// If no local socket can be bound, or `config.address` cannot be resolved
+    pub fn new(config: &StatsdConfig) -> io::Result<Self> {
+        let socket = UdpSocket::bind(if config.address.starts_with('[') {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        })?;
+        socket.connect(&config.address)?;
+        socket.set_nonblocking(true)?;
+        let tags = if config.dogstatsd && !config.tags.is_empty() {
+            format!("|#{}", config.tags.join(","))
+        } else {
+            String::new()
+        };
+        Ok(Self {
+            sink: Arc::new(Sink {
+                socket,
+                tags,
+                dogstatsd: config.dogstatsd,
+            }),
+            prefix: config.prefix.clone(),
+        })
+    }
+
+    /// Connect to the sink described by `config`, as a `Box<dyn Metrics>`
+    /// # Errors
+    /// If no local socket can be bound, or `config.address` cannot be resolved
+    pub fn boxed(config: &StatsdConfig) -> io::Result<Box<dyn Metrics>> {
+        Ok(Box::new(Self::new(config)?))
+    }
+
+    /// The full name of the metric `name` of this group
+    fn name(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{name}", self.prefix)
+        }
+    }
+
+    /// A metric of this group
+    fn metric(&self, name: &str) -> StatsdMetric {
+        StatsdMetric {
+            sink: Arc::clone(&self.sink),
+            name: self.name(name),
+        }
+    }
+}
+
+impl Metrics for StatsdMetrics {
+    fn create_counter(&self, label: String, _unit_label: Option<String>) -> Box<dyn Counter> {
+        Box::new(self.metric(&label))
+    }
+
+    fn create_gauge(&self, label: String, _unit_label: Option<String>) -> Box<dyn Gauge> {
+        Box::new(self.metric(&label))
+    }
+
+    fn create_histogram(&self, label: String, _unit_label: Option<String>) -> Box<dyn Histogram> {
+        Box::new(self.metric(&label))
+    }
+
+    fn create_label(&self, label: String) -> Box<dyn Label> {
+        Box::new(self.metric(&label))
+    }
+
+    fn subgroup(&self, subgroup_name: String) -> Box<dyn Metrics> {
+        Box::new(Self {
+            sink: Arc::clone(&self.sink),
+            prefix: self.name(&subgroup_name),
+        })
+    }
+}
+
+/// A single counter, gauge or histogram of a [`StatsdMetrics`]
+#[derive(Clone, Debug)]
+struct StatsdMetric {
+    /// where updates are sent
+    sink: Arc<Sink>,
+    /// the full name of the metric
+    name: String,
+}
+
+impl Counter for StatsdMetric {
+    fn add(&self, amount: usize) {
+        self.sink.send(&self.name, &amount.to_string(), "c");
+    }
+}
+
+impl Gauge for StatsdMetric {
+    fn set(&self, amount: usize) {
+        self.sink.send(&self.name, &amount.to_string(), "g");
+    }
+
+    fn update(&self, delta: i64) {
+        // a signed value is a relative update of a statsd gauge
+        self.sink.send(&self.name, &format!("{delta:+}"), "g");
+    }
+}
+
+impl Histogram for StatsdMetric {
+    fn add_point(&self, point: f64) {
+        let kind = if self.sink.dogstatsd { "h" } else { "ms" };
+        self.sink.send(&self.name, &point.to_string(), kind);
+    }
+}
+
+impl Label for StatsdMetric {
+    fn set(&self, _value: String) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sends_statsd_lines() {
+        let sink = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let metrics = StatsdMetrics::new(&StatsdConfig {
+            address: sink.local_addr().unwrap().to_string(),
+            prefix: "hotshot".to_string(),
+            tags: vec!["node:3".to_string()],
+            dogstatsd: true,
+        })
+        .unwrap();
+        let consensus = metrics.subgroup("consensus".to_string());
+
+        let mut buf = [0u8; 256];
+        let mut recv = || {
+            let len = sink.recv(&mut buf).unwrap();
+            String::from_utf8(buf[..len].to_vec()).unwrap()
+        };
+        consensus.create_counter("views".to_string(), None).add(2);
+        assert_eq!(recv(), "hotshot.consensus.views:2|c|#node:3");
+        let gauge = consensus.create_gauge("peers".to_string(), None);
+        gauge.set(5);
+        assert_eq!(recv(), "hotshot.consensus.peers:5|g|#node:3");
+        gauge.update(-1);
+        assert_eq!(recv(), "hotshot.consensus.peers:-1|g|#node:3");
+        consensus
+            .create_histogram("latency".to_string(), None)
+            .add_point(1.5);
+        assert_eq!(recv(), "hotshot.consensus.latency:1.5|h|#node:3");
+    }
+}
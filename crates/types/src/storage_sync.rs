@@ -0,0 +1,312 @@
+//! Group commit of storage writes
+//!
+//! Committing storage after the writes of every view makes a node on a real disk fsync-bound.
+//! Following a [`StorageSyncConfig`], the node instead commits the writes of up to
+//! `max_views_per_commit` views at once, or the writes pending for `max_commit_delay_ms`,
+//! whichever comes first, as tracked by a [`GroupCommit`].
+//!
+//! The leaves written since the last commit are also appended to a [`WriteAheadLog`], which is
+//! synced with each of them. A node which crashes between two commits finds them in the log on
+//! restart, see [`recover_storage`], so its storage never falls behind what it had decided.
+//!
+//! Only leaves are logged. The payloads and artifacts of decided views, and the cleanups of old
+//! views, are committed with the batch of their leaves but not logged: after a crash, the node
+//! fetches payloads from its peers again, goes without the artifacts of the lost views, and
+//! repeats the cleanups when it next decides.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bincode::Options;
+use hotshot_utils::bincode::bincode_opts;
+use serde::{Deserialize, Serialize};
+
+use crate::traits::{
+    node_implementation::NodeType,
+    storage::{Storage, StorageError, StoredView, ViewEntry},
+};
+
+/// When the writes to storage are committed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct StorageSyncConfig {
+    /// most views whose writes are committed at once
+    #[serde(default = "default_max_views_per_commit")]
+    pub max_views_per_commit: u64,
+    /// most milliseconds writes wait for their commit; 0 commits every write at once, whatever
+    /// `max_views_per_commit` is
+    #[serde(default)]
+    pub max_commit_delay_ms: u64,
+    /// directory of the write-ahead logs of the leaves written since the last commit, one file
+    /// per node. If unset, a crash loses them, and the node catches up on them from its peers.
+    #[serde(default)]
+    pub wal_directory: Option<PathBuf>,
+}
+
+/// the default of [`StorageSyncConfig::max_views_per_commit`]
+fn default_max_views_per_commit() -> u64 {
+    1
+}
+
+impl Default for StorageSyncConfig {
+    fn default() -> Self {
+        Self {
+            max_views_per_commit: default_max_views_per_commit(),
+            max_commit_delay_ms: 0,
+            wal_directory: None,
+        }
+    }
+}
+
+impl StorageSyncConfig {
+    /// The file of the write-ahead log of node `node_id`, if there is a log
+    #[must_use]
+    pub fn wal_path(&self, node_id: u64) -> Option<PathBuf> {
+        self.wal_directory
+            .as_ref()
+            .map(|directory| directory.join(format!("hotshot-{node_id}.wal")))
+    }
+}
+
+/// What to do after a write to storage
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitAction {
+    /// commit the batch now
+    Commit,
+    /// the write started a new batch: commit it after this long, unless it is committed before
+    CommitAfter(Duration),
+    /// leave the write for the commit of its batch
+    Wait,
+}
+
+/// The writes to storage since its last commit
+#[derive(Clone, Debug)]
+pub struct GroupCommit {
+    /// most views whose writes are committed at once
+    max_views: u64,
+    /// most time writes wait for their commit
+    max_delay: Duration,
+    /// views written since the last commit
+    views: u64,
+    /// when the first write since the last commit happened, `None` if no write is pending
+    first_write: Option<Instant>,
+    /// commits so far, which number the batches
+    commits: u64,
+}
+
+impl GroupCommit {
+    /// No writes yet, to be committed following `config`
+    #[must_use]
+    pub fn new(config: &StorageSyncConfig) -> Self {
+        Self {
+            max_views: config.max_views_per_commit.max(1),
+            max_delay: Duration::from_millis(config.max_commit_delay_ms),
+            views: 0,
+            first_write: None,
+            commits: 0,
+        }
+    }
+
+    /// Record writes at `now` for `views` more views, which is 0 for writes belonging to views
+    /// already counted, e.g. their payloads
+    pub fn record(&mut self, views: u64, now: Instant) -> CommitAction {
+        let started_batch = self.first_write.is_none();
+        self.first_write.get_or_insert(now);
+        self.views += views;
+        if self.is_due(now) {
+            CommitAction::Commit
+        } else if started_batch {
+            CommitAction::CommitAfter(self.max_delay)
+        } else {
+            CommitAction::Wait
+        }
+    }
+
+    /// Whether writes are pending, and either enough views or enough time went by for them to
+    /// be committed at `now`
+    #[must_use]
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.first_write.is_some_and(|first_write| {
+            self.views >= self.max_views
+                || now.saturating_duration_since(first_write) >= self.max_delay
+        })
+    }
+
+    /// Whether writes are pending
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.first_write.is_some()
+    }
+
+    /// The number of the current batch, which tells a timer armed for it whether it was
+    /// committed since
+    #[must_use]
+    pub fn batch(&self) -> u64 {
+        self.commits
+    }
+
+    /// Record that the pending writes were committed
+    pub fn committed(&mut self) {
+        self.views = 0;
+        self.first_write = None;
+        self.commits += 1;
+    }
+}
+
+/// An append-only file of records, synced to disk as they are appended
+///
+/// Every record is preceded by its length and a CRC-32 of the length and the record, so that one
+/// cut short or left as garbage by a crash, e.g. a zero-filled extent after the file grew, is
+/// recognized when the log is read, and the log cut there. Appending and clearing block on the disk, so async callers run
+/// them on a blocking thread; clones share the file for that.
+#[derive(Clone, Debug)]
+pub struct WriteAheadLog {
+    /// the file of the log, opened to append to
+    file: Arc<File>,
+}
+
+impl WriteAheadLog {
+    /// Open the log at `path` to append to, creating it and its directory if there are none
+    /// # Errors
+    /// If the file cannot be opened
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Arc::new(file),
+        })
+    }
+
+    /// Append `records` to the log, returning once they are on disk, with a single sync
+    /// # Errors
+    /// If the records cannot be written or synced
+    pub fn append<R: AsRef<[u8]>>(&self, records: &[R]) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        for record in records {
+            let record = record.as_ref();
+            let len = (record.len() as u64).to_le_bytes();
+            bytes.extend_from_slice(&len);
+            bytes.extend_from_slice(&crc32(&[&len, record]).to_le_bytes());
+            bytes.extend_from_slice(record);
+        }
+        (&*self.file).write_all(&bytes)?;
+        self.file.sync_data()
+    }
+
+    /// Empty the log, once its records are committed
+    /// # Errors
+    /// If the file cannot be truncated or synced
+    pub fn clear(&self) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.file.sync_data()
+    }
+
+    /// The records of the log at `path`, in the order they were appended, up to the first one
+    /// cut short or corrupted by a crash, which ends the log. None if there is no log.
+    /// # Errors
+    /// If the file cannot be read
+    pub fn read(path: &Path) -> io::Result<Vec<Vec<u8>>> {
+        let mut bytes = Vec::new();
+        match File::open(path) {
+            Ok(mut file) => file.read_to_end(&mut bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut records = Vec::new();
+        let mut rest = bytes.as_slice();
+        while rest.len() >= WAL_HEADER_LEN {
+            let (header, tail) = rest.split_at(WAL_HEADER_LEN);
+            let mut len = [0u8; 8];
+            len.copy_from_slice(&header[..8]);
+            let mut checksum = [0u8; 4];
+            checksum.copy_from_slice(&header[8..]);
+            let Some(record) = usize::try_from(u64::from_le_bytes(len))
+                .ok()
+                .and_then(|record_len| tail.get(..record_len))
+                .filter(|record| crc32(&[&len, record]) == u32::from_le_bytes(checksum))
+            else {
+                break;
+            };
+            records.push(record.to_vec());
+            rest = &tail[record.len()..];
+        }
+        Ok(records)
+    }
+}
+
+/// The length and checksum preceding every record of a write-ahead log
+const WAL_HEADER_LEN: usize = 12;
+
+/// The CRC-32 (IEEE) of `parts`, one after the other
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for byte in parts.iter().flat_map(|part| part.iter()) {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Encode `view` as a record of the write-ahead log
+/// # Errors
+/// If the view cannot be serialized
+pub fn encode_wal_record<TYPES: NodeType>(
+    view: &StoredView<TYPES>,
+) -> Result<Vec<u8>, StorageError> {
+    bincode_opts()
+        .serialize(view)
+        .map_err(|e| StorageError::WriteAheadLog {
+            reason: format!("cannot serialize view: {e}"),
+        })
+}
+
+/// Commit to `storage` the leaves a crash of node `node_id` left in its write-ahead log before
+/// they were committed, then empty the log. Run on restart, before the anchor is read from
+/// `storage`, e.g. by `HotShotInitializer::from_storage`, which would otherwise resume from an
+/// older anchor. Returns the number of leaves recovered.
+/// # Errors
+/// If the log cannot be read, decoded or emptied, or `storage` fails to take the leaves
+pub async fn recover_storage<TYPES: NodeType>(
+    storage: &impl Storage<TYPES>,
+    config: &StorageSyncConfig,
+    node_id: u64,
+) -> Result<usize, StorageError> {
+    let Some(path) = config.wal_path(node_id) else {
+        return Ok(0);
+    };
+    let path = path.as_path();
+    let wal_error = |e: io::Error| StorageError::WriteAheadLog {
+        reason: format!("{}: {e}", path.display()),
+    };
+    let views = WriteAheadLog::read(path)
+        .map_err(wal_error)?
+        .iter()
+        .map(|record| {
+            bincode_opts()
+                .deserialize::<StoredView<TYPES>>(record)
+                .map_err(|e| StorageError::WriteAheadLog {
+                    reason: format!("cannot deserialize view: {e}"),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let recovered = views.len();
+    if recovered > 0 {
+        storage
+            .append(views.into_iter().map(ViewEntry::from).collect())
+            .await?;
+        storage.commit().await?;
+    }
+    WriteAheadLog::open(path)
+        .and_then(|wal| wal.clear())
+        .map_err(wal_error)?;
+    Ok(recovered)
+}
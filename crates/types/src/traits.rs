@@ -1,7 +1,9 @@
 //! Common traits for the `HotShot` protocol
 pub mod block_contents;
+pub mod clock;
 pub mod consensus_api;
 pub mod election;
+pub mod header_extension;
 pub mod metrics;
 pub mod network;
 pub mod node_implementation;
@@ -10,6 +12,7 @@ pub mod signature_key;
 pub mod stake_table;
 pub mod states;
 pub mod storage;
+pub mod transaction_validator;
 
 pub use block_contents::BlockPayload;
 pub use states::ValidatedState;
@@ -5,11 +5,14 @@
 
 use crate::{
     data::{test_srs, VidCommitment, VidScheme, VidSchemeTrait},
+    governance::GovernanceTransaction,
+    inclusion_proof::{TransactionInclusionProof, TransactionsRoot},
     traits::ValidatedState,
     utils::BuilderCommitment,
 };
 use commit::{Commitment, Committable};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::Snafu;
 
 use std::{
     error::Error,
@@ -21,6 +24,76 @@ use std::{
 pub trait Transaction:
     Clone + Serialize + DeserializeOwned + Debug + PartialEq + Eq + Sync + Send + Committable + Hash
 {
+    /// The views whose blocks may include this transaction. The block builder leaves the
+    /// transaction in the mempool until its earliest view, and drops it once it has expired.
+    /// Defaults to every view.
+    fn view_window(&self) -> ViewWindow {
+        ViewWindow::default()
+    }
+
+    /// The lane of the mempool the transaction waits in for a block. Defaults to the user lane.
+    fn lane(&self) -> TransactionLane {
+        TransactionLane::User
+    }
+
+    /// The change of consensus parameters this transaction schedules, if it is a governance
+    /// transaction. Defaults to none.
+    fn governance(&self) -> Option<GovernanceTransaction> {
+        None
+    }
+}
+
+/// The lanes of the mempool, which share the capacity of a block
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransactionLane {
+    /// Transactions of the protocol itself, such as key rotations and stake updates. Every block
+    /// reserves capacity for them, and includes them before any user transaction, in the order
+    /// they were received.
+    System,
+    /// Every other transaction
+    #[default]
+    User,
+}
+
+/// The views whose blocks may include a transaction
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ViewWindow {
+    /// the first view whose block may include the transaction, if not the first view
+    pub earliest: Option<u64>,
+    /// the last view whose block may include the transaction, if any
+    pub expiry: Option<u64>,
+}
+
+impl ViewWindow {
+    /// A window of the views from `view` on
+    #[must_use]
+    pub fn starting_at(view: u64) -> Self {
+        Self {
+            earliest: Some(view),
+            expiry: None,
+        }
+    }
+
+    /// A window of the views up to `view`, included
+    #[must_use]
+    pub fn expiring_at(view: u64) -> Self {
+        Self {
+            earliest: None,
+            expiry: Some(view),
+        }
+    }
+
+    /// Whether the block of `view` may include the transaction
+    #[must_use]
+    pub fn includes(&self, view: u64) -> bool {
+        !self.earliest.is_some_and(|earliest| view < earliest) && !self.has_expired(view)
+    }
+
+    /// Whether the blocks of `view` and every later view may not include the transaction
+    #[must_use]
+    pub fn has_expired(&self, view: u64) -> bool {
+        self.expiry.is_some_and(|expiry| view > expiry)
+    }
 }
 
 /// Abstraction over the full contents of a block
@@ -81,6 +154,39 @@ pub trait BlockPayload:
 
     /// Generate commitment that builders use to sign block options.
     fn builder_commitment(&self, metadata: &Self::Metadata) -> BuilderCommitment;
+
+    /// Merkle root over the commitments of this payload's transactions, in order.
+    fn transactions_root(&self, metadata: &Self::Metadata) -> TransactionsRoot {
+        TransactionsRoot::from_commitments(&self.transaction_commitments(metadata))
+    }
+
+    /// Produce a proof that the transaction with `txn_commitment` is included in this payload,
+    /// verifiable against [`BlockPayload::transactions_root`].
+    ///
+    /// Returns [`None`] if the payload does not contain the transaction.
+    fn inclusion_proof(
+        &self,
+        metadata: &Self::Metadata,
+        txn_commitment: &Commitment<Self::Transaction>,
+    ) -> Option<TransactionInclusionProof> {
+        let commitments = self.transaction_commitments(metadata);
+        let index = commitments
+            .iter()
+            .position(|commitment| commitment == txn_commitment)?;
+        TransactionInclusionProof::new(&commitments, index)
+    }
+
+    /// The transactions of this payload, in order, if the payload can list them. Defaults to
+    /// none, which leaves the DA proposals of the payload uncompacted.
+    fn transactions(&self, _metadata: &Self::Metadata) -> Option<Vec<Self::Transaction>> {
+        None
+    }
+
+    /// The governance transactions of this payload, in order. Defaults to none, for payloads
+    /// which cannot list their transactions.
+    fn governance_transactions(&self, _metadata: &Self::Metadata) -> Vec<GovernanceTransaction> {
+        Vec::new()
+    }
 }
 
 /// extra functions required on block to be usable by hotshot-testing
@@ -92,12 +198,24 @@ pub trait TestableBlock: BlockPayload + Debug {
     fn txn_count(&self) -> u64;
 }
 
+/// A transaction the benchmarking tooling can generate, so that real payload types can be
+/// benchmarked with the same orchestrator setup as the example types.
+pub trait BenchmarkableTransaction: Transaction {
+    /// Generate a random transaction with a payload of roughly `size` bytes, which only the
+    /// blocks of the views of `window` may include.
+    fn create_random_transaction(
+        rng: &mut dyn rand::RngCore,
+        size: usize,
+        window: ViewWindow,
+    ) -> Self;
+}
+
 /// Compute the VID payload commitment.
 /// # Panics
 /// If the VID computation fails.
 #[must_use]
 pub fn vid_commitment(
-    encoded_transactions: &Vec<u8>,
+    encoded_transactions: &[u8],
     num_storage_nodes: usize,
 ) -> <VidScheme as VidSchemeTrait>::Commit {
     let num_chunks = 1 << num_storage_nodes.ilog2();
@@ -119,14 +237,17 @@ pub trait BlockHeader:
     /// Validated state.
     type State: ValidatedState<BlockHeader = Self>;
 
-    /// Build a header with the payload commitment, metadata, instance-level state, parent header,
-    /// and parent state.
+    /// Build a header with the payload commitment, the root of the transactions of the payload,
+    /// metadata, instance-level state, parent header, parent state, and the application-defined
+    /// extensions, which the header commits to.
     fn new(
         parent_state: &Self::State,
         instance_state: &<Self::State as ValidatedState>::Instance,
         parent_header: &Self,
         payload_commitment: VidCommitment,
+        transactions_root: TransactionsRoot,
         metadata: <Self::Payload as BlockPayload>::Metadata,
+        extensions: Vec<u8>,
     ) -> Self;
 
     /// Build the genesis header, payload, and metadata.
@@ -141,9 +262,63 @@ pub trait BlockHeader:
     /// Get the block number.
     fn block_number(&self) -> u64;
 
+    /// Get the commitment to the header of the parent block, or [`None`] for the genesis header.
+    fn parent_commitment(&self) -> Option<Commitment<Self>>;
+
+    /// Whether this header is the child of `parent`: it commits to `parent` and follows it in
+    /// block number.
+    fn extends(&self, parent: &Self) -> bool {
+        self.parent_commitment() == Some(parent.commit())
+            && parent.block_number().checked_add(1) == Some(self.block_number())
+    }
+
     /// Get the payload commitment.
     fn payload_commitment(&self) -> VidCommitment;
 
+    /// Get the root of the transactions of the payload, see
+    /// [`BlockPayload::transactions_root`], which inclusion proofs are verified against.
+    fn transactions_root(&self) -> TransactionsRoot;
+
     /// Get the metadata.
     fn metadata(&self) -> &<Self::Payload as BlockPayload>::Metadata;
+
+    /// Get the application-defined extensions, see
+    /// [`HeaderExtender`](crate::traits::header_extension::HeaderExtender).
+    fn extensions(&self) -> &[u8];
+}
+
+/// Why a chain of block headers is not linked
+#[derive(Snafu, Debug, Clone, PartialEq, Eq)]
+pub enum HeaderChainError {
+    /// The header at `index` does not commit to the header before it.
+    #[snafu(display("header {index} does not commit to the header before it"))]
+    ParentMismatch {
+        /// the index of the header in the chain
+        index: usize,
+    },
+    /// The header at `index` does not follow the header before it in block number.
+    #[snafu(display("header {index} does not follow the header before it in block number"))]
+    BlockNumberGap {
+        /// the index of the header in the chain
+        index: usize,
+    },
+}
+
+/// Check that each header of `headers` extends the one before it, so that a chain of headers can
+/// be verified on its own, without the leaves carrying them.
+///
+/// # Errors
+/// If a header does not commit to the header before it, or does not follow it in block number.
+pub fn validate_header_chain<H: BlockHeader>(headers: &[H]) -> Result<(), HeaderChainError> {
+    for (index, pair) in headers.windows(2).enumerate() {
+        let (parent, child) = (&pair[0], &pair[1]);
+        let index = index + 1;
+        if child.parent_commitment() != Some(parent.commit()) {
+            return Err(HeaderChainError::ParentMismatch { index });
+        }
+        if parent.block_number().checked_add(1) != Some(child.block_number()) {
+            return Err(HeaderChainError::BlockNumberGap { index });
+        }
+    }
+    Ok(())
 }
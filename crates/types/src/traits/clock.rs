@@ -0,0 +1,180 @@
+//! The time source of a node
+//!
+//! Consensus and network tasks never sleep on the wall clock directly; they ask the [`Clock`]
+//! injected into their `SystemContext`. Production nodes use the [`SystemClock`], while tests
+//! and deterministic simulations use a [`MockClock`] which only moves when told to, so that
+//! timeouts fire exactly when the test decides.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use async_compatibility_layer::art::async_sleep;
+
+/// A future completing once a [`Clock`] has moved past a deadline
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// A source of time for timeouts
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// The current time
+    fn now(&self) -> Instant;
+
+    /// A future completing once `duration` has elapsed on this clock
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// The wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(async_sleep(duration))
+    }
+}
+
+/// The state of a [`MockClock`], shared by its clones and the sleeps it created
+#[derive(Debug)]
+struct MockClockState {
+    /// the current time of the clock
+    now: Instant,
+    /// the pending sleeps by id, with their deadline and the waker to wake once the clock moves
+    sleepers: BTreeMap<u64, (Instant, Waker)>,
+    /// the id of the next sleep
+    next_id: u64,
+}
+
+/// A clock which only moves when advanced; clones share the same time
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    /// the shared state
+    state: Arc<Mutex<MockClockState>>,
+}
+
+impl MockClock {
+    /// Create a clock starting at the current wall clock time
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockClockState {
+                now: Instant::now(),
+                sleepers: BTreeMap::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Move the clock forward by `duration`, completing the sleeps which are due
+    /// # Panics
+    /// If the lock on the clock is poisoned
+    pub fn advance(&self, duration: Duration) {
+        let due: Vec<Waker> = {
+            let mut state = self.state.lock().unwrap();
+            state.now += duration;
+            let now = state.now;
+            state
+                .sleepers
+                .values()
+                .filter(|(deadline, _)| *deadline <= now)
+                .map(|(_, waker)| waker.clone())
+                .collect()
+        };
+        for waker in due {
+            waker.wake();
+        }
+    }
+
+    /// Move the clock forward to the earliest deadline of a pending sleep, if any. Returns how
+    /// far the clock moved.
+    /// # Panics
+    /// If the lock on the clock is poisoned
+    pub fn advance_to_next_deadline(&self) -> Option<Duration> {
+        let step = {
+            let state = self.state.lock().unwrap();
+            let next = state
+                .sleepers
+                .values()
+                .map(|(deadline, _)| *deadline)
+                .min()?;
+            next.saturating_duration_since(state.now)
+        };
+        self.advance(step);
+        Some(step)
+    }
+
+    /// Number of sleeps waiting for the clock to move
+    /// # Panics
+    /// If the lock on the clock is poisoned
+    #[must_use]
+    pub fn pending_sleeps(&self) -> usize {
+        self.state.lock().unwrap().sleepers.len()
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.state.lock().unwrap().now
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        Box::pin(MockSleep {
+            id,
+            deadline: state.now + duration,
+            state: Arc::clone(&self.state),
+        })
+    }
+}
+
+/// A sleep on a [`MockClock`]
+struct MockSleep {
+    /// identifies the sleep among the pending sleeps of the clock
+    id: u64,
+    /// when the sleep completes
+    deadline: Instant,
+    /// the state of the clock
+    state: Arc<Mutex<MockClockState>>,
+}
+
+impl Future for MockSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.now >= self.deadline {
+            state.sleepers.remove(&self.id);
+            return Poll::Ready(());
+        }
+        state
+            .sleepers
+            .insert(self.id, (self.deadline, cx.waker().clone()));
+        Poll::Pending
+    }
+}
+
+impl Drop for MockSleep {
+    fn drop(&mut self) {
+        // a cancelled sleep no longer holds the clock back
+        if let Ok(mut state) = self.state.lock() {
+            state.sleepers.remove(&self.id);
+        }
+    }
+}
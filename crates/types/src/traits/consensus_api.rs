@@ -1,12 +1,17 @@
 //! Contains the [`ConsensusApi`] trait.
 
 use crate::{
-    data::Leaf,
+    consensus::ConsensusMetricsValue,
+    data::{Leaf, VidCommitment},
     event::Event,
+    governance::ParameterSchedule,
+    payload_store::StoredPayload,
+    qc_chain::StakeTable,
+    safety::SafetyEvidence,
     traits::{
         node_implementation::{NodeImplementation, NodeType},
         signature_key::SignatureKey,
-        storage::StorageError,
+        storage::{StorageError, ViewArtifacts},
     },
 };
 use async_trait::async_trait;
@@ -33,12 +38,22 @@ pub trait ConsensusApi<TYPES: NodeType, I: NodeImplementation<TYPES>>: Send + Sy
     /// Returns the minimum transactions that must be in a block
     fn min_transactions(&self) -> usize;
 
+    /// Returns the transactions of a block reserved for system transactions
+    fn system_transactions_per_block(&self) -> usize;
+
+    /// Returns the number of steps of the way to a certificate threshold at which vote progress
+    /// is reported, if it is
+    fn vote_progress_steps(&self) -> Option<u64>;
+
     /// Get a reference to the public key.
     fn public_key(&self) -> &TYPES::SignatureKey;
 
     /// Get a reference to the private key.
     fn private_key(&self) -> &<TYPES::SignatureKey as SignatureKey>::PrivateKey;
 
+    /// Get a reference to the consensus metrics.
+    fn metrics(&self) -> &ConsensusMetricsValue;
+
     /// Notify the system of an event within `hotshot-consensus`.
     async fn send_event(&self, event: Event<TYPES>);
 
@@ -48,4 +63,50 @@ pub trait ConsensusApi<TYPES: NodeType, I: NodeImplementation<TYPES>>: Send + Sy
         old_anchor_view: TYPES::Time,
         leaf: Leaf<TYPES>,
     ) -> Result<(), StorageError>;
+
+    /// Persist the artifacts of decided views, for auditors
+    async fn store_view_artifacts(
+        &self,
+        artifacts: Vec<ViewArtifacts<TYPES>>,
+    ) -> Result<(), StorageError>;
+
+    /// Persist the payloads of decided views, with their views and commitments
+    async fn store_payloads(
+        &self,
+        payloads: Vec<(TYPES::Time, VidCommitment, StoredPayload)>,
+    ) -> Result<(), StorageError>;
+
+    /// Persist the evidence of a safety violation
+    async fn store_safety_evidence(
+        &self,
+        evidence: SafetyEvidence<TYPES>,
+    ) -> Result<(), StorageError>;
+
+    /// Persist `stake_table` as the stake table in effect from `view` on
+    async fn store_stake_table(
+        &self,
+        view: TYPES::Time,
+        stake_table: StakeTable<TYPES>,
+    ) -> Result<(), StorageError>;
+
+    /// Persist `schedule` as the consensus parameters of every epoch, replacing the previous one
+    async fn store_parameter_schedule(
+        &self,
+        schedule: ParameterSchedule,
+    ) -> Result<(), StorageError>;
+
+    /// Persist a vote towards the certificate for `commitment` in `view`
+    async fn store_vote(
+        &self,
+        view: TYPES::Time,
+        commitment: Vec<u8>,
+        vote: Vec<u8>,
+    ) -> Result<(), StorageError>;
+
+    /// The votes persisted towards the certificate for `commitment` in `view`
+    async fn get_stored_votes(
+        &self,
+        view: TYPES::Time,
+        commitment: &[u8],
+    ) -> Result<Vec<Vec<u8>>, StorageError>;
 }
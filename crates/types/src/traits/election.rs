@@ -5,10 +5,21 @@
 
 use super::node_implementation::NodeType;
 
-use crate::traits::signature_key::SignatureKey;
+use crate::{
+    reputation::ReputationRules, stake_table_cache::StakeTableCacheStats,
+    traits::signature_key::SignatureKey,
+};
 
+use ethereum_types::U256;
+
+use serde::{Deserialize, Serialize};
 use snafu::Snafu;
-use std::{collections::BTreeSet, fmt::Debug, hash::Hash, num::NonZeroU64};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    num::NonZeroU64,
+};
 
 /// Error for election problems
 #[derive(Snafu, Debug)]
@@ -19,6 +30,182 @@ pub enum ElectionError {
     /// NOTE: it would be better to make Election polymorphic over
     /// the election error and then have specific math errors
     MathError,
+    /// The configured quorum rules would not keep consensus safe
+    #[snafu(display("Unsafe quorum rules: {reason}"))]
+    UnsafeQuorumRules {
+        /// Which bound the rules violate
+        reason: String,
+    },
+}
+
+/// A fraction of a committee's stake. A certificate under it needs strictly more stake than the
+/// fraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StakeFraction {
+    /// numerator of the fraction
+    pub numerator: u64,
+    /// denominator of the fraction
+    pub denominator: u64,
+}
+
+impl StakeFraction {
+    /// f + 1, enough to include at least one honest node
+    pub const ONE_THIRD: Self = Self::new(1, 3);
+    /// A simple majority
+    pub const HALF: Self = Self::new(1, 2);
+    /// 2f + 1, the BFT quorum
+    pub const TWO_THIRDS: Self = Self::new(2, 3);
+    /// The supermajority needed to upgrade the network protocol
+    pub const NINE_TENTHS: Self = Self::new(9, 10);
+
+    /// The fraction `numerator / denominator`
+    #[must_use]
+    pub const fn new(numerator: u64, denominator: u64) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// The stake needed out of `total`, i.e. `total * numerator / denominator + 1`
+    #[must_use]
+    pub fn threshold(&self, total: u64) -> NonZeroU64 {
+        let share =
+            u128::from(total) * u128::from(self.numerator) / u128::from(self.denominator.max(1));
+        NonZeroU64::MIN.saturating_add(u64::try_from(share).unwrap_or(u64::MAX))
+    }
+
+    /// Whether this fraction is at least `other`
+    fn at_least(&self, other: &Self) -> bool {
+        u128::from(self.numerator) * u128::from(other.denominator)
+            >= u128::from(other.numerator) * u128::from(self.denominator)
+    }
+}
+
+/// The stake each kind of certificate needs from a committee
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuorumRules {
+    /// Stake needed for a certificate that something succeeded, e.g. a QC or DAC
+    pub success: StakeFraction,
+    /// Stake needed to show at least one honest node took part, e.g. for view sync
+    pub failure: StakeFraction,
+    /// Stake needed to upgrade the network protocol
+    pub upgrade: StakeFraction,
+    /// Assume an honest majority rather than fewer than a third of the stake being faulty, which
+    /// allows a success threshold down to a half. Only safe for the DA committee.
+    pub honest_majority: bool,
+}
+
+impl Default for QuorumRules {
+    fn default() -> Self {
+        Self {
+            success: StakeFraction::TWO_THIRDS,
+            failure: StakeFraction::ONE_THIRD,
+            upgrade: StakeFraction::NINE_TENTHS,
+            honest_majority: false,
+        }
+    }
+}
+
+impl QuorumRules {
+    /// Rules for a committee with an honest majority, needing just over half the stake
+    #[must_use]
+    pub fn honest_majority() -> Self {
+        Self {
+            success: StakeFraction::HALF,
+            failure: StakeFraction::HALF,
+            upgrade: StakeFraction::NINE_TENTHS,
+            honest_majority: true,
+        }
+    }
+
+    /// Check that certificates under these rules can be formed and keep consensus safe
+    /// # Errors
+    /// If a fraction is not below one, if the success fraction is below two thirds (a half in
+    /// honest majority mode), if the success and failure fractions add up to less than one, or
+    /// if the upgrade fraction is below the success fraction
+    pub fn validate(&self) -> Result<(), ElectionError> {
+        let unsafe_rules = |reason: &str| {
+            Err(ElectionError::UnsafeQuorumRules {
+                reason: reason.to_string(),
+            })
+        };
+        for fraction in [self.success, self.failure, self.upgrade] {
+            if fraction.denominator == 0 || fraction.numerator >= fraction.denominator {
+                return unsafe_rules("fractions must be below one, or certificates cannot form");
+            }
+        }
+        let min_success = if self.honest_majority {
+            StakeFraction::HALF
+        } else {
+            StakeFraction::TWO_THIRDS
+        };
+        if !self.success.at_least(&min_success) {
+            return unsafe_rules("the success fraction is too low for two quorums to intersect");
+        }
+        // the faulty stake tolerated is one minus the success fraction, which the failure
+        // fraction must exceed to include an honest node
+        let complement = StakeFraction::new(
+            self.success.denominator - self.success.numerator,
+            self.success.denominator,
+        );
+        if !self.failure.at_least(&complement) {
+            return unsafe_rules("the failure fraction cannot guarantee an honest node");
+        }
+        if !self.upgrade.at_least(&self.success) {
+            return unsafe_rules("the upgrade fraction is below the success fraction");
+        }
+        Ok(())
+    }
+}
+
+/// Failure domains (e.g. regions or providers) of the nodes in a stake table, and how many
+/// nodes of one domain a committee sampled from the table may hold
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FailureDomains {
+    /// The failure domain of each node, by index in the stake table. Nodes without a label are
+    /// not constrained.
+    pub labels: Vec<Option<String>>,
+    /// The maximum number of committee members from a single failure domain
+    pub max_per_domain: usize,
+}
+
+impl FailureDomains {
+    /// Check that committees can be sampled under these constraints
+    /// # Errors
+    /// If no node of a labelled domain may be on a committee
+    pub fn validate(&self) -> Result<(), ElectionError> {
+        if self.max_per_domain == 0 && self.labels.iter().any(Option::is_some) {
+            return Err(ElectionError::UnsafeQuorumRules {
+                reason: "failure domains must allow at least one committee member each".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The stake table indices of a committee of up to `size` out of `num_nodes` nodes, taken in
+    /// stake table order and skipping nodes whose failure domain is full. The committee is
+    /// smaller than `size` if the constraints leave too few nodes.
+    #[must_use]
+    pub fn sample_committee(&self, num_nodes: usize, size: usize) -> Vec<usize> {
+        let mut members_per_domain = HashMap::<&str, usize>::new();
+        let mut committee = Vec::with_capacity(size);
+        for index in 0..num_nodes {
+            if committee.len() == size {
+                break;
+            }
+            if let Some(Some(domain)) = self.labels.get(index) {
+                let members = members_per_domain.entry(domain.as_str()).or_default();
+                if *members >= self.max_per_domain {
+                    continue;
+                }
+                *members += 1;
+            }
+            committee.push(index);
+        }
+        committee
+    }
 }
 
 /// election config
@@ -31,6 +218,33 @@ pub trait ElectionConfig:
     + Send
     + core::fmt::Debug
 {
+    /// Check the configuration is safe to run consensus with. Called on the quorum's
+    /// configuration at startup.
+    /// # Errors
+    /// If the configuration is unsafe
+    fn validate(&self) -> Result<(), ElectionError> {
+        Ok(())
+    }
+
+    /// The configuration of a DA committee of `num_nodes` nodes, derived from this one, which is
+    /// the quorum's configuration
+    #[must_use]
+    fn da_config(&self, num_nodes: u64) -> Self;
+
+    /// Sample the DA committee derived from this configuration under `domains`. Elections
+    /// without committee sampling ignore this.
+    #[must_use]
+    fn with_da_failure_domains(self, _domains: FailureDomains) -> Self {
+        self
+    }
+
+    /// Down-weight leaders which fail to get their proposals decided under `rules`, in the
+    /// rotations of all committees derived from this configuration. Elections without a leader
+    /// rotation ignore this.
+    #[must_use]
+    fn with_leader_reputation(self, _rules: ReputationRules) -> Self {
+        self
+    }
 }
 
 /// A protocol for determining membership in and participating in a committee.
@@ -79,4 +293,121 @@ pub trait Membership<TYPES: NodeType>:
 
     /// Returns the threshold required to upgrade the network protocol
     fn upgrade_threshold(&self) -> NonZeroU64;
+
+    /// Record the views of newly decided leaves, for rotations weighted by how often the
+    /// proposals of leaders are decided. Committees whose rotation is not weighted ignore this.
+    fn record_decided_views(&self, _views: &[TYPES::Time]) {}
+
+    /// Resize the committee to `size` members, as governance changes the size of the DA
+    /// committee. Clones of the membership are resized with it. Committees whose size is not
+    /// theirs to choose ignore this.
+    fn resize_committee(&self, _size: u64) {}
+
+    /// The index of `pub_key` in the stake table of the committee, which is where its vote goes
+    /// in the signers of a certificate, or `None` if the key is not in the table
+    fn get_committee_index(&self, pub_key: &TYPES::SignatureKey) -> Option<usize> {
+        self.get_committee_qc_stake_table()
+            .iter()
+            .position(|entry| TYPES::SignatureKey::get_public_key(entry) == *pub_key)
+    }
+
+    /// The public parameters the certificates of the committee needing `threshold` stake are
+    /// checked against
+    fn get_committee_qc_params(
+        &self,
+        threshold: u64,
+    ) -> <TYPES::SignatureKey as SignatureKey>::QCParams {
+        TYPES::SignatureKey::get_public_parameter(
+            self.get_committee_qc_stake_table(),
+            U256::from(threshold),
+        )
+    }
+
+    /// The stake table of the committee in effect at `view`, or `None` if the committee does
+    /// not know it. The default knows none, as the current stake table need not be the one of
+    /// `view`; the stake tables of past views are persisted in
+    /// [`Storage`](crate::traits::storage::Storage) instead.
+    fn get_stake_table_at(
+        &self,
+        _view: TYPES::Time,
+    ) -> Option<Vec<<TYPES::SignatureKey as SignatureKey>::StakeTableEntry>> {
+        None
+    }
+
+    /// How often the stake table lookups of the committee were served from a
+    /// [`StakeTableCache`](crate::stake_table_cache::StakeTableCache), if the committee
+    /// caches them
+    fn stake_table_cache_stats(&self) -> Option<StakeTableCacheStats> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FailureDomains, QuorumRules, StakeFraction};
+
+    #[test]
+    fn quorum_rules_validation() {
+        assert!(QuorumRules::default().validate().is_ok());
+        assert!(QuorumRules::honest_majority().validate().is_ok());
+        assert!(QuorumRules {
+            success: StakeFraction::new(3, 4),
+            failure: StakeFraction::new(1, 4),
+            ..QuorumRules::default()
+        }
+        .validate()
+        .is_ok());
+
+        // two quorums of just over half need not intersect in an honest node
+        assert!(QuorumRules {
+            honest_majority: false,
+            ..QuorumRules::honest_majority()
+        }
+        .validate()
+        .is_err());
+        assert!(QuorumRules {
+            success: StakeFraction::new(3, 4),
+            ..QuorumRules::default()
+        }
+        .validate()
+        .is_err());
+        assert!(QuorumRules {
+            upgrade: StakeFraction::new(1, 1),
+            ..QuorumRules::default()
+        }
+        .validate()
+        .is_err());
+
+        assert_eq!(StakeFraction::TWO_THIRDS.threshold(10).get(), 7);
+        assert_eq!(StakeFraction::ONE_THIRD.threshold(10).get(), 4);
+    }
+
+    #[test]
+    fn committee_sampling_respects_failure_domains() {
+        let label = |domain: &str| Some(domain.to_string());
+        let domains = FailureDomains {
+            labels: vec![
+                label("a"),
+                label("a"),
+                label("a"),
+                label("b"),
+                None,
+                label("b"),
+            ],
+            max_per_domain: 2,
+        };
+        assert!(domains.validate().is_ok());
+        assert_eq!(domains.sample_committee(6, 4), vec![0, 1, 3, 4]);
+        // the third node of `a` is skipped even when the committee cannot be filled
+        assert_eq!(domains.sample_committee(6, 6), vec![0, 1, 3, 4, 5]);
+        // nodes past the end of the labels are not constrained
+        assert_eq!(domains.sample_committee(8, 7), vec![0, 1, 3, 4, 5, 6, 7]);
+
+        assert!(FailureDomains {
+            max_per_domain: 0,
+            ..domains
+        }
+        .validate()
+        .is_err());
+    }
 }
@@ -0,0 +1,84 @@
+//! Application-defined metadata in block headers
+//!
+//! Every block header carries opaque extensions, e.g. the L1 origin of a rollup block. The leader
+//! fills them in with its [`HeaderExtender`] when it builds the header, and every replica checks
+//! them with its own before voting for the proposal. The header commits to its extensions, so they
+//! are decided with the block.
+
+use hotshot_constants::MAX_HEADER_EXTENSIONS_SIZE;
+use snafu::Snafu;
+
+use crate::traits::{block_contents::BlockHeader, node_implementation::NodeType};
+
+/// Why the extensions of a proposed header were rejected
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[snafu(visibility(pub))]
+pub enum ExtensionRejection {
+    /// The extensions are larger than a header may carry
+    #[snafu(display("Header extensions of {size} bytes exceed the maximum of {max_size} bytes"))]
+    TooLarge {
+        /// Size of the extensions
+        size: usize,
+        /// Maximum size of the extensions of a header
+        max_size: usize,
+    },
+    /// The application rejected the extensions
+    #[snafu(display("Header extensions rejected by the application: {reason}"))]
+    RejectedByApplication {
+        /// Why the application rejected the extensions
+        reason: String,
+    },
+}
+
+/// Fills in the extensions of the headers a leader builds, and checks those of the headers it is
+/// proposed
+///
+/// Both hooks default to no extensions: headers are built without any, and accepted whatever they
+/// carry. Replicas check the extensions with [`validate_extensions`], which enforces
+/// [`MAX_HEADER_EXTENSIONS_SIZE`] whatever the extender.
+pub trait HeaderExtender<TYPES: NodeType>: Send + Sync + 'static {
+    /// The extensions of the header the node builds on `parent` for `view`
+    fn extensions(&self, _view: TYPES::Time, _parent: &TYPES::BlockHeader) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Application specific check of `extensions`, of the header proposed on `parent` for `view`
+    /// # Errors
+    /// Why the application rejects the extensions
+    fn check_extensions(
+        &self,
+        _view: TYPES::Time,
+        _parent: &TYPES::BlockHeader,
+        _extensions: &[u8],
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Check the extensions of `header`, proposed on `parent` for `view`: first their size, then
+/// with the application's `extender`
+/// # Errors
+/// The first check the extensions fail
+pub fn validate_extensions<TYPES: NodeType>(
+    extender: &dyn HeaderExtender<TYPES>,
+    view: TYPES::Time,
+    parent: &TYPES::BlockHeader,
+    header: &TYPES::BlockHeader,
+) -> Result<(), ExtensionRejection> {
+    let extensions = header.extensions();
+    if extensions.len() > MAX_HEADER_EXTENSIONS_SIZE {
+        return Err(ExtensionRejection::TooLarge {
+            size: extensions.len(),
+            max_size: MAX_HEADER_EXTENSIONS_SIZE,
+        });
+    }
+    extender
+        .check_extensions(view, parent, extensions)
+        .map_err(|reason| ExtensionRejection::RejectedByApplication { reason })
+}
+
+/// An extender for applications without header metadata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoExtensions;
+
+impl<TYPES: NodeType> HeaderExtender<TYPES> for NoExtensions {}
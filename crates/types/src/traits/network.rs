@@ -12,16 +12,22 @@ use tokio::time::error::Elapsed as TimeoutError;
 #[cfg(not(any(async_executor_impl = "async-std", async_executor_impl = "tokio")))]
 compile_error! {"Either config option \"async-std\" or \"tokio\" must be enabled for this crate."}
 use super::{node_implementation::NodeType, signature_key::SignatureKey};
-use crate::{data::ViewNumber, message::MessagePurpose, BoxSyncFuture};
+use crate::{anchor::AnchorHint, data::ViewNumber, message::MessagePurpose, BoxSyncFuture};
 use async_compatibility_layer::channel::UnboundedSendError;
 use async_trait::async_trait;
+use bytes::Bytes;
 use rand::{
     distributions::{Bernoulli, Uniform},
     prelude::Distribution,
 };
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
-use std::{collections::BTreeSet, fmt::Debug, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
 
 impl From<NetworkNodeHandleError> for NetworkError {
     fn from(error: NetworkNodeHandleError) -> Self {
@@ -45,6 +51,23 @@ impl From<NetworkNodeHandleError> for NetworkError {
 pub enum MemoryNetworkError {
     /// stub
     Stub,
+    /// The latency matrix file could not be read
+    LatencyMatrixRead {
+        /// The underlying IO error
+        source: std::io::Error,
+    },
+    /// The latency matrix is not valid TOML
+    LatencyMatrixParse {
+        /// The underlying parse error
+        source: toml::de::Error,
+    },
+    /// The latency matrix has no regions
+    NoRegions,
+    /// A link of the latency matrix names a region which is not listed
+    UnknownRegion {
+        /// The unknown region
+        region: String,
+    },
 }
 
 /// Centralized server specific errors
@@ -70,7 +93,7 @@ pub enum WebServerNetworkError {
 }
 
 /// the type of transmission
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransmitType {
     /// directly transmit
     Direct,
@@ -119,6 +142,13 @@ pub enum NetworkError {
         /// originating bincode error
         source: bincode::Error,
     },
+    /// Failed to sign a network record
+    FailedToSign,
+    /// Failed to read or write a recorded network trace
+    Trace {
+        /// originating I/O error
+        source: std::io::Error,
+    },
     /// A timeout occurred
     Timeout {
         /// Source of error
@@ -205,10 +235,32 @@ impl<K: SignatureKey> ConsensusIntentEvent<K> {
     }
 }
 
+/// How urgently a received message has to be processed. Networks hand out received messages
+/// in this order, most urgent first, so that under congestion transactions are delayed before
+/// the messages views depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MessagePriority {
+    /// Proposals, votes, certificates and view sync messages
+    Consensus,
+    /// Data availability traffic: DA proposals, votes and certificates, VID shares and blocks
+    DA,
+    /// Transactions
+    Transactions,
+}
+
 /// common traits we would like our network messages to implement
 pub trait NetworkMsg:
     Serialize + for<'a> Deserialize<'a> + Clone + Sync + Send + Debug + 'static
 {
+    /// The priority class of this message
+    fn priority(&self) -> MessagePriority {
+        MessagePriority::Consensus
+    }
+}
+
+/// Order `messages` most urgent first, keeping the order of arrival within each priority class
+pub fn prioritize<M: NetworkMsg>(messages: &mut [M]) {
+    messages.sort_by_key(NetworkMsg::priority);
 }
 
 impl NetworkMsg for Vec<u8> {}
@@ -287,6 +339,29 @@ pub trait ConnectedNetwork<M: NetworkMsg, K: SignatureKey + 'static>:
     /// blocking
     /// Ideally we would pass in the `Time` type, but that requires making the entire trait generic over NodeType
     async fn inject_consensus_info(&self, _event: ConsensusIntentEvent<K>) {}
+
+    /// The keys of the stake table in effect from now on, once the stake table of a new epoch
+    /// is certified. Networks admitting only the peers of the stake table follow it; the
+    /// default ignores it.
+    async fn update_stake_table(&self, _keys: BTreeSet<K>) {}
+
+    /// The announced anchor of the highest view this network received, if any, so that a node
+    /// joining the network knows which view to catch up to
+    fn latest_anchor_hint(&self) -> Option<AnchorHint> {
+        None
+    }
+
+    /// Record an anchor announced to this network, see [`ConnectedNetwork::latest_anchor_hint`]
+    fn record_anchor_hint(&self, _hint: AnchorHint) {}
+
+    /// Cut this node off from every peer but `reachable`, or reconnect it to every peer if
+    /// `reachable` is `None`. The network drops the messages between the node and the peers
+    /// across the partition, as it authenticates them, in both directions.
+    /// # Errors
+    /// If the network cannot tell its peers apart, as a centralized server cannot
+    async fn set_partition(&self, _reachable: Option<BTreeSet<K>>) -> Result<(), NetworkError> {
+        Err(NetworkError::UnimplementedFeature)
+    }
 }
 
 /// Describes additional functionality needed by the test network implementation
@@ -341,7 +416,7 @@ pub trait NetworkReliability: Debug + Sync + std::marker::Send + DynClone + 'sta
     }
 
     /// scramble the packet
-    fn scramble(&self, msg: Vec<u8>) -> Vec<u8> {
+    fn scramble(&self, msg: Bytes) -> Bytes {
         msg
     }
 
@@ -361,11 +436,40 @@ pub trait NetworkReliability: Debug + Sync + std::marker::Send + DynClone + 'sta
     /// then return a future that does the sending and delaying
     fn chaos_send_msg(
         &self,
-        msg: Vec<u8>,
-        send_fn: Arc<dyn Send + Sync + 'static + Fn(Vec<u8>) -> BoxSyncFuture<'static, ()>>,
+        msg: Bytes,
+        send_fn: Arc<dyn Send + Sync + 'static + Fn(Bytes) -> BoxSyncFuture<'static, ()>>,
+    ) -> BoxSyncFuture<'static, ()> {
+        self.chaos_send_delayed_msg(msg, self.sample_delay(), send_fn)
+    }
+
+    /// sample the delay of a message of `size` bytes sent from the node with index `sender` to
+    /// the node with index `recipient`. Only networks whose links differ need to override this.
+    fn sample_link_delay(&self, _sender: u64, _recipient: u64, _size: usize) -> Duration {
+        self.sample_delay()
+    }
+
+    /// like [`Self::chaos_send_msg`], but with the delay of the link between the nodes with
+    /// indices `sender` and `recipient`
+    fn chaos_send_link_msg(
+        &self,
+        msg: Bytes,
+        sender: u64,
+        recipient: u64,
+        send_fn: Arc<dyn Send + Sync + 'static + Fn(Bytes) -> BoxSyncFuture<'static, ()>>,
+    ) -> BoxSyncFuture<'static, ()> {
+        let delay = self.sample_link_delay(sender, recipient, msg.len());
+        self.chaos_send_delayed_msg(msg, delay, send_fn)
+    }
+
+    /// decide whether to send the message, whether to send duplicates and whether to include
+    /// noise, then return a future sending it after `delay`
+    fn chaos_send_delayed_msg(
+        &self,
+        msg: Bytes,
+        delay: Duration,
+        send_fn: Arc<dyn Send + Sync + 'static + Fn(Bytes) -> BoxSyncFuture<'static, ()>>,
     ) -> BoxSyncFuture<'static, ()> {
         let sample_keep = self.sample_keep();
-        let delay = self.sample_delay();
         let repeats = self.sample_repeat();
         let mut msgs = Vec::new();
         for _idx in 0..repeats {
@@ -589,3 +693,215 @@ impl NetworkReliability for ChaosNetwork {
         Uniform::new_inclusive(self.repeat_low, self.repeat_high).sample(&mut rand::thread_rng())
     }
 }
+
+/// A partition of the network as seen from one node: while it is in place, the network of the
+/// node neither delivers its messages to nor takes messages from the peers on the other side,
+/// see [`ConnectedNetwork::set_partition`]. Unlike the reliability of a test network, a
+/// partition is set and healed at runtime. Clones share the partition.
+#[derive(Clone, Debug)]
+pub struct NetworkPartition<K: SignatureKey> {
+    /// the peers the node can reach, or `None` if the network is whole
+    reachable: Arc<Mutex<Option<BTreeSet<K>>>>,
+}
+
+impl<K: SignatureKey> Default for NetworkPartition<K> {
+    fn default() -> Self {
+        Self {
+            reachable: Arc::default(),
+        }
+    }
+}
+
+impl<K: SignatureKey> NetworkPartition<K> {
+    /// Cut the node off from every peer but `reachable`
+    pub fn isolate(&self, reachable: impl IntoIterator<Item = K>) {
+        *self.lock() = Some(reachable.into_iter().collect());
+    }
+
+    /// Reconnect the node to every peer
+    pub fn heal(&self) {
+        *self.lock() = None;
+    }
+
+    /// Whether a partition is in place
+    #[must_use]
+    pub fn is_partitioned(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// Whether the node may exchange messages with `peer`
+    #[must_use]
+    pub fn is_reachable(&self, peer: &K) -> bool {
+        match &*self.lock() {
+            Some(reachable) => reachable.contains(peer),
+            None => true,
+        }
+    }
+
+    /// Lock the peers the node can reach. A poisoned lock is taken over, as the set is only ever
+    /// replaced whole.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<BTreeSet<K>>> {
+        self.reachable
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A link between nodes of a [`LatencyMatrix`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkConfig {
+    /// lowest value in milliseconds that a packet may be delayed
+    #[serde(default)]
+    pub delay_low_ms: u64,
+    /// highest value in milliseconds that a packet may be delayed
+    #[serde(default)]
+    pub delay_high_ms: u64,
+    /// bytes per second the link can carry, unlimited if unset
+    #[serde(default)]
+    pub bandwidth_bytes_per_sec: Option<u64>,
+}
+
+/// The link from one region of a [`LatencyMatrix`] to another
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RegionLinkConfig {
+    /// region of the sender
+    pub from: String,
+    /// region of the recipient
+    pub to: String,
+    /// the link between them
+    #[serde(flatten)]
+    pub link: LinkConfig,
+}
+
+/// The on-disk description of a [`LatencyMatrix`], e.g.
+///
+/// ```toml
+/// regions = ["us-east", "eu-west"]
+///
+/// [local]
+/// delay_low_ms = 1
+/// delay_high_ms = 2
+///
+/// [[links]]
+/// from = "us-east"
+/// to = "eu-west"
+/// delay_low_ms = 40
+/// delay_high_ms = 45
+/// bandwidth_bytes_per_sec = 12500000
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyMatrixConfig {
+    /// the regions nodes are placed in. Node `i` is in region `i % regions.len()`.
+    pub regions: Vec<String>,
+    /// the link between nodes of the same region
+    #[serde(default)]
+    pub local: LinkConfig,
+    /// the links between regions. A link applies in both directions unless the reverse
+    /// direction is listed as well. Unlisted pairs of regions use `local`.
+    #[serde(default)]
+    pub links: Vec<RegionLinkConfig>,
+}
+
+/// A network approximating a geo-distributed deployment: nodes are placed in regions, and
+/// every pair of regions has its own delay distribution and bandwidth cap. Packets are never
+/// dropped.
+#[derive(Clone, Debug)]
+pub struct LatencyMatrix {
+    /// the link from each region (outer index) to each region (inner index)
+    links: Vec<Vec<LinkConfig>>,
+    /// for each pair of nodes, when the link between them is done sending what it was given
+    busy_until: Arc<Mutex<HashMap<(u64, u64), Instant>>>,
+}
+
+impl LatencyMatrix {
+    /// Build the matrix described by `config`
+    /// # Errors
+    /// If `config` has no regions, or one of its links names an unlisted region
+    pub fn new(config: &LatencyMatrixConfig) -> Result<Self, MemoryNetworkError> {
+        if config.regions.is_empty() {
+            return Err(MemoryNetworkError::NoRegions);
+        }
+        let region_index = |region: &String| {
+            config
+                .regions
+                .iter()
+                .position(|r| r == region)
+                .ok_or_else(|| MemoryNetworkError::UnknownRegion {
+                    region: region.clone(),
+                })
+        };
+        let mut links = vec![vec![config.local; config.regions.len()]; config.regions.len()];
+        let mut explicit = BTreeSet::new();
+        for link in &config.links {
+            let (from, to) = (region_index(&link.from)?, region_index(&link.to)?);
+            links[from][to] = link.link;
+            explicit.insert((from, to));
+            if !explicit.contains(&(to, from)) {
+                links[to][from] = link.link;
+            }
+        }
+        Ok(Self {
+            links,
+            busy_until: Arc::default(),
+        })
+    }
+
+    /// Parse a matrix from a TOML [`LatencyMatrixConfig`]
+    /// # Errors
+    /// If the TOML is invalid, or does not describe a valid matrix
+    pub fn from_toml(toml: &str) -> Result<Self, MemoryNetworkError> {
+        let config: LatencyMatrixConfig = toml::from_str(toml)
+            .map_err(|source| MemoryNetworkError::LatencyMatrixParse { source })?;
+        Self::new(&config)
+    }
+
+    /// Load a matrix from a TOML [`LatencyMatrixConfig`] file
+    /// # Errors
+    /// If the file cannot be read, or does not describe a valid matrix
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, MemoryNetworkError> {
+        let toml = std::fs::read_to_string(path)
+            .map_err(|source| MemoryNetworkError::LatencyMatrixRead { source })?;
+        Self::from_toml(&toml)
+    }
+
+    /// The link between the nodes with indices `sender` and `recipient`
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn link(&self, sender: u64, recipient: u64) -> LinkConfig {
+        let regions = self.links.len() as u64;
+        self.links[(sender % regions) as usize][(recipient % regions) as usize]
+    }
+}
+
+impl NetworkReliability for LatencyMatrix {
+    fn sample_delay(&self) -> Duration {
+        let local = self.link(0, 0);
+        Duration::from_millis(
+            Uniform::new_inclusive(local.delay_low_ms, local.delay_high_ms)
+                .sample(&mut rand::thread_rng()),
+        )
+    }
+
+    fn sample_link_delay(&self, sender: u64, recipient: u64, size: usize) -> Duration {
+        let link = self.link(sender, recipient);
+        let propagation = Duration::from_millis(
+            Uniform::new_inclusive(link.delay_low_ms, link.delay_high_ms)
+                .sample(&mut rand::thread_rng()),
+        );
+        let Some(bandwidth) = link.bandwidth_bytes_per_sec.filter(|b| *b > 0) else {
+            return propagation;
+        };
+
+        // messages on a capped link queue up behind each other
+        #[allow(clippy::cast_precision_loss)]
+        let transmission = Duration::from_secs_f64(size as f64 / bandwidth as f64);
+        let now = Instant::now();
+        let mut busy_until = self
+            .busy_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let link_busy = busy_until.entry((sender, recipient)).or_insert(now);
+        *link_busy = (*link_busy).max(now) + transmission;
+        *link_busy - now + propagation
+    }
+}
@@ -87,6 +87,14 @@ pub trait SignatureKey:
     // of serialization, to avoid Cryptographic pitfalls
     /// Validate a signature
     fn validate(&self, signature: &Self::PureAssembledSignatureType, data: &[u8]) -> bool;
+    /// Validate every `(key, data, signature)` of `batch` at once, which schemes with aggregate
+    /// signatures do faster than one by one. An empty batch is valid. Only tells whether the
+    /// whole batch is valid: on failure, find the culprits with [`SignatureKey::validate`].
+    fn batch_verify(batch: &[(&Self, &[u8], &Self::PureAssembledSignatureType)]) -> bool {
+        batch
+            .iter()
+            .all(|(key, data, signature)| key.validate(signature, data))
+    }
     /// Produce a signature
     /// # Errors
     /// If unable to sign the data with the key
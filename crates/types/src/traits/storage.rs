@@ -1,23 +1,51 @@
 //! Abstraction over on-disk storage of node state
 
 use super::node_implementation::NodeType;
-use crate::{data::Leaf, simple_certificate::QuorumCertificate, vote::HasViewNumber};
+use crate::{
+    data::{Leaf, QuorumProposal, VidCommitment},
+    governance::{GovernanceTransaction, ParameterSchedule},
+    message::Proposal,
+    payload_store::StoredPayload,
+    qc_chain::StakeTable,
+    safety::SafetyEvidence,
+    simple_certificate::{DACertificate, QuorumCertificate},
+    vote::{Certificate, HasViewNumber},
+};
 use async_trait::async_trait;
-use commit::Commitment;
+use commit::{Commitment, Committable};
 use derivative::Derivative;
+use serde::{Deserialize, Serialize};
 use snafu::Snafu;
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::{Bound, RangeBounds},
+};
 /// Errors that can occur in the storage layer.
 #[derive(Clone, Debug, Snafu)]
 #[snafu(visibility(pub))]
 pub enum StorageError {
     /// No genesis view was inserted
     NoGenesisView,
+    /// The write-ahead log of the writes yet to be committed could not be read or written
+    #[snafu(display("write-ahead log failed: {reason}"))]
+    WriteAheadLog {
+        /// what went wrong
+        reason: String,
+    },
+    /// The storage does not implement this operation
+    #[snafu(display("storage does not support {operation}"))]
+    Unsupported {
+        /// the operation asked for
+        operation: &'static str,
+    },
 }
 
 /// Result for a storage type
 pub type Result<T = ()> = std::result::Result<T, StorageError>;
 
+/// Stored views in view order, as read with [`Storage::iter_views`]
+pub type StoredViews<TYPES> = Box<dyn Iterator<Item = StoredView<TYPES>> + Send>;
+
 /// Abstraction over on disk persistence of node state
 ///
 /// This should be a cloneable handle to an underlying storage, with each clone pointing to the same
@@ -45,6 +73,187 @@ where
     async fn append_single_view(&self, view: StoredView<TYPES>) -> Result {
         self.append(vec![ViewEntry::Success(view)]).await
     }
+
+    /// Persist a verified vote accumulated towards the certificate for `commitment` in `view`,
+    /// so a restarting leader can resume forming the certificate.
+    ///
+    /// # Errors
+    /// [`StorageError::Unsupported`] unless the storage keeps votes
+    async fn append_vote(
+        &self,
+        _view: TYPES::Time,
+        _commitment: Vec<u8>,
+        _vote: Vec<u8>,
+    ) -> Result {
+        Err(StorageError::Unsupported {
+            operation: "append_vote",
+        })
+    }
+
+    /// The votes persisted with [`Storage::append_vote`] for `commitment` in `view`, in the
+    /// order they were appended
+    ///
+    /// # Errors
+    /// [`StorageError::Unsupported`] unless the storage keeps votes
+    async fn get_votes(&self, _view: TYPES::Time, _commitment: &[u8]) -> Result<Vec<Vec<u8>>> {
+        Err(StorageError::Unsupported {
+            operation: "get_votes",
+        })
+    }
+
+    /// Drop the votes of every view before `view`. Returns the number of votes dropped.
+    ///
+    /// # Errors
+    /// [`StorageError::Unsupported`] unless the storage keeps votes
+    async fn cleanup_votes_before_view(&self, _view: TYPES::Time) -> Result<usize> {
+        Err(StorageError::Unsupported {
+            operation: "cleanup_votes_before_view",
+        })
+    }
+
+    /// Persist the artifacts of a decided view, for auditors to re-verify it. Unlike stored
+    /// views, these are not cleaned up as the anchor advances, but only past the retention of
+    /// [`Storage::cleanup_view_artifacts_before_view`]. Storage which does not keep history may
+    /// ignore this.
+    async fn append_view_artifacts(&self, _artifacts: ViewArtifacts<TYPES>) -> Result {
+        Ok(())
+    }
+
+    /// The artifacts persisted with [`Storage::append_view_artifacts`] for `view`, if any
+    async fn get_view_artifacts(&self, _view: TYPES::Time) -> Result<Option<ViewArtifacts<TYPES>>> {
+        Ok(None)
+    }
+
+    /// Drop the artifacts of every view before `view`. Returns the number of views whose
+    /// artifacts were dropped.
+    ///
+    /// # Errors
+    /// [`StorageError::Unsupported`] unless the storage keeps view artifacts
+    async fn cleanup_view_artifacts_before_view(&self, _view: TYPES::Time) -> Result<usize> {
+        Err(StorageError::Unsupported {
+            operation: "cleanup_view_artifacts_before_view",
+        })
+    }
+
+    /// Persist `payload`, whose commitment is `payload_commitment`, as the payload of decided
+    /// `view`. The payload is shared with consensus, so storage holding it in memory holds no
+    /// copy of it. It is cleaned up with its view. Storage which does not keep payloads may
+    /// ignore this.
+    async fn append_payload(
+        &self,
+        _view: TYPES::Time,
+        _payload_commitment: VidCommitment,
+        _payload: StoredPayload,
+    ) -> Result {
+        Ok(())
+    }
+
+    /// The payload persisted with [`Storage::append_payload`] with this commitment, if any
+    async fn get_payload(
+        &self,
+        _payload_commitment: &VidCommitment,
+    ) -> Result<Option<StoredPayload>> {
+        Ok(None)
+    }
+
+    /// Persist `stake_table` as the stake table in effect from `view` on, until the next one
+    /// appended, for certificates of past views to be validated against the stake which signed
+    /// them. Storage which does not keep history may ignore this.
+    async fn append_stake_table(
+        &self,
+        _view: TYPES::Time,
+        _stake_table: StakeTable<TYPES>,
+    ) -> Result {
+        Ok(())
+    }
+
+    /// The stake table persisted with [`Storage::append_stake_table`] in effect at `view`, i.e.
+    /// the last one appended for a view up to `view`, if any
+    async fn get_stake_table_at(&self, _view: TYPES::Time) -> Result<Option<StakeTable<TYPES>>> {
+        Ok(None)
+    }
+
+    /// Drop the stake tables superseded before `view`, keeping the one still in effect at it.
+    /// Returns the number of stake tables dropped.
+    async fn cleanup_stake_tables_before_view(&self, _view: TYPES::Time) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Persist `schedule`, replacing the one persisted before, for a restarted node to run with
+    /// the parameter changes governance decided. Storage which does not keep it may ignore
+    /// this, its node then restarting with the parameters of its configuration.
+    async fn append_parameter_schedule(&self, _schedule: ParameterSchedule) -> Result {
+        Ok(())
+    }
+
+    /// The schedule persisted last with [`Storage::append_parameter_schedule`], if any
+    async fn get_parameter_schedule(&self) -> Result<Option<ParameterSchedule>> {
+        Ok(None)
+    }
+
+    /// Persist the evidence of a safety violation, for it to be investigated. Unlike stored
+    /// views, evidence is never cleaned up.
+    async fn append_safety_evidence(&self, _evidence: SafetyEvidence<TYPES>) -> Result {
+        Ok(())
+    }
+
+    /// The evidence persisted with [`Storage::append_safety_evidence`], oldest first
+    async fn get_safety_evidence(&self) -> Result<Vec<SafetyEvidence<TYPES>>> {
+        Ok(Vec::new())
+    }
+
+    /// Check the stored views against each other, recomputing the commitment of every leaf and
+    /// checking it is the one its child and the child's justify QC point to. Storage which
+    /// cannot list its views checks nothing.
+    async fn verify_integrity(&self) -> Result<IntegrityReport<TYPES>> {
+        Ok(IntegrityReport::default())
+    }
+
+    /// Drop everything which is no longer needed to resume from the anchored view: the views
+    /// before it, and the failed views and votes of those views. Returns the number of entries
+    /// dropped.
+    async fn compact(&self) -> Result<usize> {
+        Ok(0)
+    }
+
+    // The reads below never change the storage, so that processes other than the node sharing
+    // it, e.g. an explorer reading the same database, can serve queries from it directly.
+
+    /// The stored views in `range`, in view order. The views are read when this is called, so
+    /// views appended later are not iterated.
+    ///
+    /// # Errors
+    /// [`StorageError::Unsupported`] unless the storage can list its views
+    async fn iter_views<R>(&self, _range: R) -> Result<StoredViews<TYPES>>
+    where
+        R: RangeBounds<TYPES::Time> + Send,
+    {
+        Err(StorageError::Unsupported {
+            operation: "iter_views",
+        })
+    }
+
+    /// The latest anchored view, or `None` if no view was stored yet
+    async fn latest_anchor(&self) -> Result<Option<StoredView<TYPES>>> {
+        match self.get_anchored_view().await {
+            Ok(view) => Ok(Some(view)),
+            Err(StorageError::NoGenesisView) => Ok(None),
+        }
+    }
+
+    /// The QC certifying the leaf of `view`: the justify QC of the stored view it is for, if
+    /// any
+    ///
+    /// # Errors
+    /// [`StorageError::Unsupported`] unless the storage can list its views, see
+    /// [`Storage::iter_views`]
+    async fn qc_for_view(&self, view: TYPES::Time) -> Result<Option<QuorumCertificate<TYPES>>> {
+        Ok(self
+            .iter_views((Bound::Excluded(view), Bound::Unbounded))
+            .await?
+            .find(|child| child.justify_qc.get_view_number() == view)
+            .map(|child| child.justify_qc))
+    }
     // future improvement:
     // async fn get_future_views(&self) -> Vec<FutureView>;
     //     async fn add_transaction(&self, transactions: Transaction) -> TransactionHash;
@@ -71,8 +280,9 @@ where
 
 /// An internal representation of the data stored in a [`Storage`].
 ///
-/// This should only be used for testing, never in production code.
-#[derive(Debug, PartialEq)]
+/// This should only be used for testing and offline tooling, never in production code.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
 pub struct StorageState<TYPES: NodeType> {
     /// The views that have been successful
     pub stored: BTreeMap<TYPES::Time, StoredView<TYPES>>,
@@ -113,8 +323,9 @@ where
 }
 
 /// A view stored in the [`Storage`]
-#[derive(Clone, Debug, Derivative)]
+#[derive(Clone, Debug, Derivative, Serialize, Deserialize)]
 #[derivative(PartialEq)]
+#[serde(bound(deserialize = ""))]
 pub struct StoredView<TYPES: NodeType> {
     /// The view number of this view
     pub view_number: TYPES::Time,
@@ -131,6 +342,9 @@ pub struct StoredView<TYPES: NodeType> {
     /// the proposer id
     #[derivative(PartialEq = "ignore")]
     pub proposer_id: TYPES::SignatureKey,
+    /// The governance transactions of the block
+    #[serde(default)]
+    pub governance: Vec<GovernanceTransaction>,
 }
 
 impl<TYPES> StoredView<TYPES>
@@ -155,6 +369,102 @@ where
             block_header,
             block_payload,
             proposer_id,
+            governance: Vec::new(),
+        }
+    }
+}
+
+/// An inconsistency between stored views
+#[derive(Clone, Debug, PartialEq, Eq, Snafu)]
+pub enum IntegrityViolation<TYPES: NodeType> {
+    /// No stored leaf has the commitment a view names as its parent
+    #[snafu(display("The parent of view {view:?} is not stored"))]
+    MissingParent {
+        /// the view whose parent is missing
+        view: TYPES::Time,
+    },
+    /// The justify QC of a view certifies another leaf than its parent
+    #[snafu(display("The justify QC of view {view:?} does not certify its parent"))]
+    QcLinkMismatch {
+        /// the view whose justify QC is wrong
+        view: TYPES::Time,
+    },
+    /// The justify QC of a view is not from before the view
+    #[snafu(display("The justify QC of view {view:?} is from view {qc_view:?}"))]
+    QcViewMismatch {
+        /// the view whose justify QC is wrong
+        view: TYPES::Time,
+        /// the view of the justify QC
+        qc_view: TYPES::Time,
+    },
+}
+
+/// The outcome of [`Storage::verify_integrity`]
+#[derive(Clone, Debug, PartialEq, Eq, Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct IntegrityReport<TYPES: NodeType> {
+    /// number of stored views which were checked
+    pub views_checked: usize,
+    /// the inconsistencies found, in view order
+    pub violations: Vec<IntegrityViolation<TYPES>>,
+}
+
+impl<TYPES: NodeType> IntegrityReport<TYPES> {
+    /// Whether no inconsistencies were found
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Check `stored` views against each other, as [`Storage::verify_integrity`] does. The oldest
+/// view is allowed to have no stored parent, since older views are cleaned up.
+#[must_use]
+pub fn verify_stored_views<TYPES: NodeType>(
+    stored: &BTreeMap<TYPES::Time, StoredView<TYPES>>,
+) -> IntegrityReport<TYPES> {
+    let commitments: HashMap<Commitment<Leaf<TYPES>>, TYPES::Time> = stored
+        .values()
+        .map(|view| (Leaf::from(view.clone()).commit(), view.view_number))
+        .collect();
+    let mut violations = Vec::new();
+    for (index, view) in stored.values().enumerate() {
+        let qc_view = view.justify_qc.get_view_number();
+        if qc_view >= view.view_number && !view.justify_qc.is_genesis {
+            violations.push(IntegrityViolation::QcViewMismatch {
+                view: view.view_number,
+                qc_view,
+            });
+        }
+        if !view.justify_qc.is_genesis && view.justify_qc.get_data().leaf_commit != view.parent {
+            violations.push(IntegrityViolation::QcLinkMismatch {
+                view: view.view_number,
+            });
+        }
+        if index > 0 && !commitments.contains_key(&view.parent) {
+            violations.push(IntegrityViolation::MissingParent {
+                view: view.view_number,
+            });
         }
     }
+    IntegrityReport {
+        views_checked: stored.len(),
+        violations,
+    }
+}
+
+/// Everything needed to independently re-verify a decided view
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(deserialize = ""))]
+pub struct ViewArtifacts<TYPES: NodeType> {
+    /// The decided view
+    pub view_number: TYPES::Time,
+    /// The leader's signed proposal, if this node received it
+    pub proposal: Option<Proposal<TYPES, QuorumProposal<TYPES>>>,
+    /// The DA certificate of the view's block, if this node saw it
+    pub da_certificate: Option<DACertificate<TYPES>>,
+    /// The QC certifying the view's leaf
+    pub quorum_certificate: Option<QuorumCertificate<TYPES>>,
+    /// The public keys whose signatures are aggregated in `quorum_certificate`
+    pub signers: Vec<TYPES::SignatureKey>,
 }
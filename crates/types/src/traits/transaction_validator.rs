@@ -0,0 +1,103 @@
+//! Validation of transactions as they enter a node
+//!
+//! Every transaction is checked by a [`TransactionValidator`] before it enters the mempool, both
+//! when submitted to the node and when received from other nodes, so that malformed or oversized
+//! data never takes up mempool or block space.
+
+use bincode::Options;
+use hotshot_constants::DEFAULT_MAX_TRANSACTION_SIZE;
+use hotshot_utils::bincode::bincode_opts;
+use snafu::Snafu;
+
+use crate::traits::node_implementation::NodeType;
+
+/// Why a transaction was rejected
+#[derive(Debug, Clone, PartialEq, Eq, Snafu)]
+#[snafu(visibility(pub))]
+pub enum TransactionRejection {
+    /// The transaction is larger than the validator allows
+    #[snafu(display("Transaction of {size} bytes exceeds the maximum of {max_size} bytes"))]
+    TooLarge {
+        /// Serialized size of the transaction
+        size: usize,
+        /// Maximum serialized size of a transaction
+        max_size: usize,
+    },
+    /// The transaction is not well formed
+    #[snafu(display("Malformed transaction: {reason}"))]
+    Malformed {
+        /// What is wrong with the transaction
+        reason: String,
+    },
+    /// The application rejected the transaction
+    #[snafu(display("Transaction rejected by the application: {reason}"))]
+    RejectedByApplication {
+        /// Why the application rejected the transaction
+        reason: String,
+    },
+}
+
+/// Checks transactions at every point they enter a node
+///
+/// Only [`TransactionValidator::max_size`] must be provided; the other checks accept every
+/// transaction unless overridden.
+pub trait TransactionValidator<TYPES: NodeType>: Send + Sync + 'static {
+    /// The maximum serialized size of a transaction, in bytes
+    fn max_size(&self) -> usize;
+
+    /// Check that `transaction` is well formed
+    /// # Errors
+    /// A description of what is wrong with the transaction
+    fn check_well_formed(&self, _transaction: &TYPES::Transaction) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Application specific check of `transaction`
+    /// # Errors
+    /// Why the application rejects the transaction
+    fn check_application(&self, _transaction: &TYPES::Transaction) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Run every check on `transaction`, from the cheapest to the most expensive
+    /// # Errors
+    /// The first check the transaction fails
+    fn validate(&self, transaction: &TYPES::Transaction) -> Result<(), TransactionRejection> {
+        let max_size = self.max_size();
+        let size = bincode_opts()
+            .serialized_size(transaction)
+            .ok()
+            .and_then(|size| usize::try_from(size).ok())
+            .ok_or_else(|| TransactionRejection::Malformed {
+                reason: "transaction cannot be serialized".to_string(),
+            })?;
+        if size > max_size {
+            return Err(TransactionRejection::TooLarge { size, max_size });
+        }
+        self.check_well_formed(transaction)
+            .map_err(|reason| TransactionRejection::Malformed { reason })?;
+        self.check_application(transaction)
+            .map_err(|reason| TransactionRejection::RejectedByApplication { reason })
+    }
+}
+
+/// A validator which only limits the size of transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxSizeValidator {
+    /// The maximum serialized size of a transaction, in bytes
+    pub max_size: usize,
+}
+
+impl Default for MaxSizeValidator {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_TRANSACTION_SIZE,
+        }
+    }
+}
+
+impl<TYPES: NodeType> TransactionValidator<TYPES> for MaxSizeValidator {
+    fn max_size(&self) -> usize {
+        self.max_size
+    }
+}
@@ -0,0 +1,99 @@
+//! Estimates of how long views last, and so how long transactions take to be decided
+//!
+//! The consensus task records how long each view lasted, from entering it to entering the next.
+//! A [`ViewLatencyEstimator`] keeps an exponential moving average of those durations, which
+//! follows changes of the network quickly, and the most recent ones, for percentiles. A leaf is
+//! decided [`VIEWS_TO_DECIDE`] views after its own, so that many views of average duration
+//! estimate how long a transaction submitted now takes to be final.
+
+use std::{collections::VecDeque, time::Duration};
+
+/// The weight of the latest view duration in the moving average
+pub const VIEW_LATENCY_EMA_WEIGHT: f64 = 0.2;
+
+/// The number of recent view durations percentiles are computed over
+pub const VIEW_LATENCY_WINDOW: usize = 100;
+
+/// The number of views after its own a leaf is decided in: the chain of three certified leaves
+/// deciding it is complete once the leader of the third view after it proposes
+pub const VIEWS_TO_DECIDE: u32 = 3;
+
+/// Moving average and percentiles of the durations of recent views
+#[derive(Clone, Debug)]
+pub struct ViewLatencyEstimator {
+    /// the weight of the latest duration in the average
+    weight: f64,
+    /// the number of durations kept for percentiles
+    window: usize,
+    /// the moving average, once a view completed
+    average: Option<Duration>,
+    /// the latest durations, oldest first
+    recent: VecDeque<Duration>,
+}
+
+impl Default for ViewLatencyEstimator {
+    fn default() -> Self {
+        Self::new(VIEW_LATENCY_EMA_WEIGHT, VIEW_LATENCY_WINDOW)
+    }
+}
+
+impl ViewLatencyEstimator {
+    /// Create an estimator giving the latest duration `weight` in the average and computing
+    /// percentiles over the latest `window` durations
+    /// # Panics
+    /// If `weight` is not in `(0, 1]` or `window` is zero
+    #[must_use]
+    pub fn new(weight: f64, window: usize) -> Self {
+        assert!(
+            weight > 0.0 && weight <= 1.0,
+            "the weight of the latest view duration must be in (0, 1]"
+        );
+        assert!(window > 0, "view durations must be kept for percentiles");
+        Self {
+            weight,
+            window,
+            average: None,
+            recent: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Record that a view lasted `duration`
+    pub fn record(&mut self, duration: Duration) {
+        self.average = Some(match self.average {
+            Some(average) => average.mul_f64(1.0 - self.weight) + duration.mul_f64(self.weight),
+            None => duration,
+        });
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(duration);
+    }
+
+    /// The moving average of view durations, once a view completed
+    #[must_use]
+    pub fn average(&self) -> Option<Duration> {
+        self.average
+    }
+
+    /// The `percentile`th percentile, from 0 to 100, of the recent view durations, by nearest
+    /// rank, once a view completed
+    #[must_use]
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut sorted: Vec<_> = self.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// How long a transaction submitted now is expected to take to be decided, once a view
+    /// completed
+    #[must_use]
+    pub fn estimated_finality_latency(&self) -> Option<Duration> {
+        self.average.map(|average| average * VIEWS_TO_DECIDE)
+    }
+}
@@ -1,7 +1,7 @@
 //! Vote, Accumulator, and Certificate Types
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     marker::PhantomData,
 };
 
@@ -12,6 +12,7 @@ use ethereum_types::U256;
 use tracing::error;
 
 use crate::{
+    signing::{self, SigningDomain},
     simple_certificate::Threshold,
     simple_vote::Voteable,
     traits::{
@@ -81,11 +82,109 @@ type SignersMap<COMMITMENT, KEY> = HashMap<
         Vec<<KEY as SignatureKey>::PureAssembledSignatureType>,
     ),
 >;
+/// Accumulates votes of type `VOTE` until a certificate of type `CERT` can be formed
+pub trait Accumulator<
+    TYPES: NodeType,
+    VOTE: Vote<TYPES>,
+    CERT: Certificate<TYPES, Voteable = VOTE::Commitment>,
+>
+{
+    /// Add a vote to those accumulated so far. Returns the certificate once the accumulated
+    /// votes meet its threshold.
+    fn accumulate(&mut self, vote: &VOTE, membership: &TYPES::Membership) -> Either<(), CERT>;
+
+    /// Drop all accumulated votes
+    fn clear(&mut self);
+}
+
+/// How an accumulator tallies votes against a certificate's threshold
+pub trait CertificatePolicy<TYPES: NodeType> {
+    /// The weight carried by the vote of the node with `entry` in the stake table
+    fn weight(entry: &<TYPES::SignatureKey as SignatureKey>::StakeTableEntry) -> U256;
+
+    /// Whether a certificate may be issued with the assembled signature `qc` of `signed`, the
+    /// signed encoding of the vote commitment
+    fn accept(
+        _qc_pp: &<TYPES::SignatureKey as SignatureKey>::QCParams,
+        _signed: &[u8],
+        _qc: &<TYPES::SignatureKey as SignatureKey>::QCType,
+    ) -> bool {
+        true
+    }
+}
+
+/// Every vote counts once, whatever the stake of its signer. Only suited to committees where
+/// each node has a stake of 1, as certificates are still checked against stake.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SimpleCountPolicy;
+
+impl<TYPES: NodeType> CertificatePolicy<TYPES> for SimpleCountPolicy {
+    fn weight(_entry: &<TYPES::SignatureKey as SignatureKey>::StakeTableEntry) -> U256 {
+        U256::one()
+    }
+}
+
+/// Every vote counts with the stake of its signer
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StakeWeightedPolicy;
+
+impl<TYPES: NodeType> CertificatePolicy<TYPES> for StakeWeightedPolicy {
+    fn weight(entry: &<TYPES::SignatureKey as SignatureKey>::StakeTableEntry) -> U256 {
+        entry.get_stake()
+    }
+}
+
+/// Votes count with the stake of their signer, and the assembled signature is verified before
+/// a certificate is issued
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AggregatedSignaturePolicy;
+
+impl<TYPES: NodeType> CertificatePolicy<TYPES> for AggregatedSignaturePolicy {
+    fn weight(entry: &<TYPES::SignatureKey as SignatureKey>::StakeTableEntry) -> U256 {
+        entry.get_stake()
+    }
+
+    fn accept(
+        qc_pp: &<TYPES::SignatureKey as SignatureKey>::QCParams,
+        signed: &[u8],
+        qc: &<TYPES::SignatureKey as SignatureKey>::QCType,
+    ) -> bool {
+        <TYPES::SignatureKey as SignatureKey>::check(qc_pp, signed, qc)
+    }
+}
+
+/// How far the votes for a commitment are from the threshold of its certificate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoteProgress {
+    /// the weight of the votes collected
+    pub collected_weight: U256,
+    /// the weight the certificate needs
+    pub threshold: U256,
+}
+
+impl VoteProgress {
+    /// The number of `steps` of the way to the threshold the collected weight covers, at most
+    /// `steps`
+    #[must_use]
+    pub fn step(&self, steps: u64) -> u64 {
+        if self.threshold.is_zero() {
+            return steps;
+        }
+        let step = self.collected_weight.saturating_mul(U256::from(steps)) / self.threshold;
+        if step >= U256::from(steps) {
+            steps
+        } else {
+            step.as_u64()
+        }
+    }
+}
+
 /// Accumulates votes until a certificate is formed.  This implementation works for all simple vote and certificate pairs
 pub struct VoteAccumulator<
     TYPES: NodeType,
     VOTE: Vote<TYPES>,
     CERT: Certificate<TYPES, Voteable = VOTE::Commitment>,
+    POLICY: CertificatePolicy<TYPES> = StakeWeightedPolicy,
 > {
     /// Map of all signatures accumlated so far
     pub vote_outcomes: VoteMap2<
@@ -97,34 +196,228 @@ pub struct VoteAccumulator<
     /// And a list of valid signatures for certificate aggregation
     pub signers: SignersMap<Commitment<VOTE::Commitment>, TYPES::SignatureKey>,
     /// Phantom data to specify the types this accumulator is for
-    pub phantom: PhantomData<(TYPES, VOTE, CERT)>,
+    pub phantom: PhantomData<(TYPES, VOTE, CERT, POLICY)>,
+}
+
+impl<
+        TYPES: NodeType,
+        VOTE: Vote<TYPES>,
+        CERT: Certificate<TYPES, Voteable = VOTE::Commitment>,
+        POLICY: CertificatePolicy<TYPES>,
+    > Default for VoteAccumulator<TYPES, VOTE, CERT, POLICY>
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<TYPES: NodeType, VOTE: Vote<TYPES>, CERT: Certificate<TYPES, Voteable = VOTE::Commitment>>
-    VoteAccumulator<TYPES, VOTE, CERT>
+impl<
+        TYPES: NodeType,
+        VOTE: Vote<TYPES>,
+        CERT: Certificate<TYPES, Voteable = VOTE::Commitment>,
+        POLICY: CertificatePolicy<TYPES>,
+    > VoteAccumulator<TYPES, VOTE, CERT, POLICY>
 {
+    /// Create an accumulator with no votes in it
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            vote_outcomes: HashMap::new(),
+            signers: HashMap::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Drop all accumulated votes.  The underlying maps keep their capacity so the
+    /// accumulator can be reused for the next view without reallocating.
+    pub fn clear(&mut self) {
+        self.vote_outcomes.clear();
+        self.signers.clear();
+    }
+
+    /// How far the votes recorded for `commitment` are from the threshold of its certificate
+    #[must_use]
+    pub fn progress(
+        &self,
+        commitment: &Commitment<VOTE::Commitment>,
+        membership: &TYPES::Membership,
+    ) -> VoteProgress {
+        VoteProgress {
+            collected_weight: self
+                .vote_outcomes
+                .get(commitment)
+                .map_or_else(U256::zero, |(weight, _)| *weight),
+            threshold: CERT::threshold(membership).into(),
+        }
+    }
+
+    /// How far the votes recorded for `commitment` would be from the threshold of its
+    /// certificate once the votes of `pending` for it are recorded as well, if their signatures
+    /// are valid. Votes from nodes not in the stake table, or from nodes which already voted, add
+    /// nothing.
+    #[must_use]
+    pub fn progress_with(
+        &self,
+        commitment: &Commitment<VOTE::Commitment>,
+        pending: &[VOTE],
+        membership: &TYPES::Membership,
+    ) -> VoteProgress {
+        let mut progress = self.progress(commitment, membership);
+        let mut counted = HashSet::new();
+        for vote in pending {
+            let key = vote.get_signing_key();
+            if vote.get_data_commitment() != *commitment
+                || self.has_recorded(&key, commitment)
+                || membership.get_committee_index(&key).is_none()
+            {
+                continue;
+            }
+            let Some(entry) = membership.get_stake(&key) else {
+                continue;
+            };
+            if counted.insert(key) {
+                progress.collected_weight += POLICY::weight(&entry);
+            }
+        }
+        progress
+    }
+
+    /// The keys of the voters whose votes for `commitment` were recorded
+    #[must_use]
+    pub fn voters(&self, commitment: &Commitment<VOTE::Commitment>) -> Vec<TYPES::SignatureKey> {
+        self.vote_outcomes
+            .get(commitment)
+            .map(|(_, votes)| votes.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether the vote of `key` for `commitment` was recorded, which it only is once its
+    /// signature was verified
+    #[must_use]
+    pub fn has_recorded(
+        &self,
+        key: &TYPES::SignatureKey,
+        commitment: &Commitment<VOTE::Commitment>,
+    ) -> bool {
+        self.vote_outcomes
+            .get(commitment)
+            .is_some_and(|(_, votes)| votes.contains_key(key))
+    }
+
     /// Add a vote to the total accumulated votes.  Returns the accumulator or the certificate if we
-    /// have accumulated enough votes to exceed the threshold for creating a certificate.
-    ///
-    /// # Panics
-    /// Panics if the vote comes from a node not in the stake table
+    /// have accumulated enough votes to exceed the threshold for creating a certificate. Votes
+    /// from nodes not in the stake table are ignored.
     pub fn accumulate(&mut self, vote: &VOTE, membership: &TYPES::Membership) -> Either<(), CERT> {
-        let key = vote.get_signing_key();
-
-        let vote_commitment = vote.get_data_commitment();
-        if !key.validate(&vote.get_signature(), vote_commitment.as_ref()) {
+        if !signing::validate(
+            &vote.get_signing_key(),
+            &vote.get_signature(),
+            SigningDomain::Vote,
+            vote.get_data_commitment().as_ref(),
+        ) {
             error!("Invalid vote! Vote Data {:?}", vote.get_data());
             return Either::Left(());
         }
+        self.accumulate_verified(vote, membership)
+    }
 
-        let Some(stake_table_entry) = membership.get_stake(&key) else {
+    /// Add a vote whose signature was already verified, e.g. on the verification threads, see
+    /// [`VoteAccumulator::accumulate`]
+    pub fn accumulate_verified(
+        &mut self,
+        vote: &VOTE,
+        membership: &TYPES::Membership,
+    ) -> Either<(), CERT> {
+        if !self.record(vote, membership) {
             return Either::Left(());
+        }
+        self.certify(vote, membership)
+    }
+
+    /// Add `votes` to the total accumulated votes, verifying their signatures in one batch per
+    /// commitment rather than one by one, see [`SignatureKey::batch_verify`]. Returns the
+    /// certificate if the votes accumulated so far meet its threshold. Votes from nodes not in the
+    /// stake table, or from nodes which already voted, are ignored, and so are invalid votes: a
+    /// batch which fails to verify is verified again one vote at a time.
+    ///
+    /// A batch only proves that the aggregate of its signatures is valid, which signatures
+    /// cancelling each other out pass as well. Every vote of a batch is therefore added before a
+    /// certificate is assembled, so that the certificate aggregates all of them and is valid.
+    pub fn accumulate_batch(
+        &mut self,
+        votes: &[VOTE],
+        membership: &TYPES::Membership,
+    ) -> Either<(), CERT> {
+        let mut batches: HashMap<Commitment<VOTE::Commitment>, Vec<&VOTE>> = HashMap::new();
+        let mut seen = HashSet::new();
+        for vote in votes {
+            let key = vote.get_signing_key();
+            let commitment = vote.get_data_commitment();
+            let voted = self
+                .vote_outcomes
+                .get(&commitment)
+                .is_some_and(|(_, voters)| voters.contains_key(&key));
+            if membership.get_stake(&key).is_none()
+                || membership.get_committee_index(&key).is_none()
+                || voted
+                || !seen.insert((commitment, key))
+            {
+                continue;
+            }
+            batches.entry(commitment).or_default().push(vote);
+        }
+
+        let mut result = Either::Left(());
+        for batch in batches.into_values() {
+            let signed: Vec<_> = batch
+                .iter()
+                .map(|vote| {
+                    (
+                        vote.get_signing_key(),
+                        vote.get_signature(),
+                        vote.get_data_commitment(),
+                    )
+                })
+                .collect();
+            let valid: Vec<&VOTE> = if signing::validate_batch(&signed, SigningDomain::Vote) {
+                batch
+            } else {
+                batch
+                    .into_iter()
+                    .filter(|vote| {
+                        signing::validate(
+                            &vote.get_signing_key(),
+                            &vote.get_signature(),
+                            SigningDomain::Vote,
+                            vote.get_data_commitment().as_ref(),
+                        )
+                    })
+                    .collect()
+            };
+            let recorded: Vec<&VOTE> = valid
+                .into_iter()
+                .filter(|vote| self.record(vote, membership))
+                .collect();
+            if let Some(vote) = recorded.first() {
+                if result.is_left() {
+                    result = self.certify(vote, membership);
+                }
+            }
+        }
+        result
+    }
+
+    /// Record the signature of `vote` towards a certificate for its commitment. Returns whether
+    /// it was recorded, which it is not if its signer is not in the stake table or already voted.
+    fn record(&mut self, vote: &VOTE, membership: &TYPES::Membership) -> bool {
+        let key = vote.get_signing_key();
+        let vote_commitment = vote.get_data_commitment();
+
+        let (Some(stake_table_entry), Some(vote_node_id)) = (
+            membership.get_stake(&key),
+            membership.get_committee_index(&key),
+        ) else {
+            return false;
         };
-        let stake_table = membership.get_committee_qc_stake_table();
-        let vote_node_id = stake_table
-            .iter()
-            .position(|x| *x == stake_table_entry.clone())
-            .unwrap();
 
         let original_signature: <TYPES::SignatureKey as SignatureKey>::PureAssembledSignatureType =
             vote.get_signature();
@@ -136,7 +429,7 @@ impl<TYPES: NodeType, VOTE: Vote<TYPES>, CERT: Certificate<TYPES, Voteable = VOT
 
         // Check for duplicate vote
         if total_vote_map.contains_key(&key) {
-            return Either::Left(());
+            return false;
         }
         let (signers, sig_list) = self
             .signers
@@ -144,22 +437,31 @@ impl<TYPES: NodeType, VOTE: Vote<TYPES>, CERT: Certificate<TYPES, Voteable = VOT
             .or_insert((bitvec![0; membership.total_nodes()], Vec::new()));
         if signers.get(vote_node_id).as_deref() == Some(&true) {
             error!("Node id is already in signers list");
-            return Either::Left(());
+            return false;
         }
         signers.set(vote_node_id, true);
         sig_list.push(original_signature);
 
-        // TODO: Get the stake from the stake table entry.
-        *total_stake_casted += stake_table_entry.get_stake();
+        *total_stake_casted += POLICY::weight(&stake_table_entry);
         total_vote_map.insert(key, (vote.get_signature(), vote.get_data_commitment()));
+        true
+    }
+
+    /// Assemble the certificate for the commitment of `vote` if the votes recorded for it meet
+    /// its threshold
+    fn certify(&self, vote: &VOTE, membership: &TYPES::Membership) -> Either<(), CERT> {
+        let vote_commitment = vote.get_data_commitment();
+        let (Some((total_stake_casted, _)), Some((signers, sig_list))) = (
+            self.vote_outcomes.get(&vote_commitment),
+            self.signers.get(&vote_commitment),
+        ) else {
+            return Either::Left(());
+        };
 
         if *total_stake_casted >= CERT::threshold(membership).into() {
             // Assemble QC
             let real_qc_pp: <<TYPES as NodeType>::SignatureKey as SignatureKey>::QCParams =
-                <TYPES::SignatureKey as SignatureKey>::get_public_parameter(
-                    stake_table,
-                    U256::from(CERT::threshold(membership)),
-                );
+                membership.get_committee_qc_params(CERT::threshold(membership));
 
             let real_qc_sig = <TYPES::SignatureKey as SignatureKey>::assemble(
                 &real_qc_pp,
@@ -167,6 +469,12 @@ impl<TYPES: NodeType, VOTE: Vote<TYPES>, CERT: Certificate<TYPES, Voteable = VOT
                 &sig_list[..],
             );
 
+            let signed = signing::encode(SigningDomain::Vote, vote_commitment.as_ref());
+            if !POLICY::accept(&real_qc_pp, &signed, &real_qc_sig) {
+                error!("Assembled signature was rejected for {:?}", vote.get_data());
+                return Either::Left(());
+            }
+
             let cert = CERT::create_signed_certificate(
                 vote.get_data_commitment(),
                 vote.get_data().clone(),
@@ -179,5 +487,21 @@ impl<TYPES: NodeType, VOTE: Vote<TYPES>, CERT: Certificate<TYPES, Voteable = VOT
     }
 }
 
+impl<
+        TYPES: NodeType,
+        VOTE: Vote<TYPES>,
+        CERT: Certificate<TYPES, Voteable = VOTE::Commitment>,
+        POLICY: CertificatePolicy<TYPES>,
+    > Accumulator<TYPES, VOTE, CERT> for VoteAccumulator<TYPES, VOTE, CERT, POLICY>
+{
+    fn accumulate(&mut self, vote: &VOTE, membership: &TYPES::Membership) -> Either<(), CERT> {
+        VoteAccumulator::accumulate(self, vote, membership)
+    }
+
+    fn clear(&mut self) {
+        VoteAccumulator::clear(self);
+    }
+}
+
 /// Mapping of commitments to vote tokens by key.
 type VoteMap2<COMMITMENT, PK, SIG> = HashMap<COMMITMENT, (U256, BTreeMap<PK, (SIG, COMMITMENT)>)>;
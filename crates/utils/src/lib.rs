@@ -3,5 +3,8 @@
 /// Provides bincode options
 pub mod bincode;
 
+/// Coalesces repeated log messages into periodic summaries
+pub mod log_throttle;
+
 /// Provides version utilities
 pub mod version;
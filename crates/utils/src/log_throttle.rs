@@ -0,0 +1,156 @@
+//! Coalescing of repeated log messages
+//!
+//! Under a partition the same warnings are logged for every view, by every task. Call sites
+//! name the kind of message they log, its target, and ask [`check`] whether to log it: the
+//! first occurrence of a target in a window is logged, later ones are counted, and the next
+//! occurrence after the window carries a [`Summary`] of how many were suppressed.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Mutex, OnceLock, RwLock},
+    time::{Duration, Instant},
+};
+
+use hotshot_constants::LOG_THROTTLE_DEFAULT_WINDOW_SEC;
+
+/// How often the messages of a target were seen in the current window
+#[derive(Debug, Clone, Copy)]
+struct Occurrences {
+    /// when the first message of the window was logged
+    window_start: Instant,
+    /// number of messages suppressed since
+    suppressed: u64,
+}
+
+/// Decides which of the repeated messages of each target are logged
+#[derive(Debug)]
+pub struct LogThrottle {
+    /// the window of targets without a specific one
+    default_window: Duration,
+    /// windows of specific targets
+    windows: HashMap<String, Duration>,
+    /// the occurrences of each target in its current window
+    occurrences: Mutex<HashMap<&'static str, Occurrences>>,
+}
+
+/// The messages of a target suppressed before the one being logged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    /// the target of the messages
+    pub target: &'static str,
+    /// number of messages suppressed
+    pub suppressed: u64,
+    /// time since the last message logged
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Summary {
+    /// Empty if nothing was suppressed, so it can be appended to every message
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.suppressed == 0 {
+            return Ok(());
+        }
+        write!(
+            f,
+            " [{} x{} in last {}s]",
+            self.target,
+            self.suppressed,
+            self.elapsed.as_secs()
+        )
+    }
+}
+
+impl LogThrottle {
+    /// Create a throttle logging each target at most once per `default_window`. A zero window
+    /// logs every message.
+    #[must_use]
+    pub fn new(default_window: Duration) -> Self {
+        Self {
+            default_window,
+            windows: HashMap::new(),
+            occurrences: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Log `target` at most once per `window` instead of the default window
+    #[must_use]
+    pub fn with_window(mut self, target: impl Into<String>, window: Duration) -> Self {
+        self.windows.insert(target.into(), window);
+        self
+    }
+
+    /// Whether to log a message of `target` now. If so, the summary of the messages suppressed
+    /// before it is returned.
+    pub fn check(&self, target: &'static str) -> Option<Summary> {
+        self.check_at(target, Instant::now())
+    }
+
+    /// Whether to log a message of `target` at `now`
+    /// # Panics
+    /// If the lock on the occurrences is poisoned
+    pub fn check_at(&self, target: &'static str, now: Instant) -> Option<Summary> {
+        let window = self
+            .windows
+            .get(target)
+            .copied()
+            .unwrap_or(self.default_window);
+        let mut occurrences = self.occurrences.lock().unwrap();
+        let Some(seen) = occurrences.get_mut(target) else {
+            occurrences.insert(
+                target,
+                Occurrences {
+                    window_start: now,
+                    suppressed: 0,
+                },
+            );
+            return Some(Summary {
+                target,
+                suppressed: 0,
+                elapsed: Duration::ZERO,
+            });
+        };
+        let elapsed = now.saturating_duration_since(seen.window_start);
+        if elapsed < window {
+            seen.suppressed += 1;
+            return None;
+        }
+        let summary = Summary {
+            target,
+            suppressed: seen.suppressed,
+            elapsed,
+        };
+        *seen = Occurrences {
+            window_start: now,
+            suppressed: 0,
+        };
+        Some(summary)
+    }
+}
+
+impl Default for LogThrottle {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(LOG_THROTTLE_DEFAULT_WINDOW_SEC))
+    }
+}
+
+/// The throttle shared by the whole process
+fn global() -> &'static RwLock<LogThrottle> {
+    static GLOBAL: OnceLock<RwLock<LogThrottle>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(LogThrottle::default()))
+}
+
+/// Replace the throttle shared by the whole process
+/// # Panics
+/// If the lock on the throttle is poisoned
+pub fn configure(throttle: LogThrottle) {
+    *global().write().unwrap() = throttle;
+}
+
+/// Whether to log a message of `target` now, according to the throttle shared by the whole
+/// process. If so, the summary of the messages suppressed before it is returned.
+/// # Panics
+/// If the lock on the throttle is poisoned
+pub fn check(target: &'static str) -> Option<Summary> {
+    global().read().unwrap().check(target)
+}
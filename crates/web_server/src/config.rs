@@ -11,6 +11,18 @@ pub const MAX_VIEWS: usize = 100;
 pub const MAX_TXNS: usize = 500;
 /// How many transactions to return at once
 pub const TX_BATCH_SIZE: u64 = 1;
+/// How many chains to keep messages of at once
+pub const MAX_CHAINS: usize = 16;
+
+/// `route` of the chain `chain_id`, for web servers shared by several chains. Route functions
+/// without it serve chain 0.
+#[must_use]
+pub fn chain_route(chain_id: u64, route: &str) -> String {
+    match route.strip_prefix("api/") {
+        Some(path) => format!("api/chain/{chain_id}/{path}"),
+        None => route.to_string(),
+    }
+}
 
 /// get proposal
 #[must_use]
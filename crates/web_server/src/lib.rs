@@ -3,7 +3,7 @@
 /// Configuration for the webserver
 pub mod config;
 
-use crate::config::{MAX_TXNS, MAX_VIEWS, TX_BATCH_SIZE};
+use crate::config::{MAX_CHAINS, MAX_TXNS, MAX_VIEWS, TX_BATCH_SIZE};
 use async_compatibility_layer::channel::OneShotReceiver;
 use async_lock::RwLock;
 use clap::Args;
@@ -12,7 +12,7 @@ use futures::FutureExt;
 use hotshot_types::traits::signature_key::SignatureKey;
 use rand::{distributions::Alphanumeric, rngs::StdRng, thread_rng, Rng, SeedableRng};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     io,
     path::PathBuf,
 };
@@ -20,7 +20,7 @@ use tide_disco::{
     api::ApiError,
     error::ServerError,
     method::{ReadState, WriteState},
-    Api, App, StatusCode, Url,
+    Api, App, RequestParams, StatusCode, Url,
 };
 use tracing::{debug, info};
 
@@ -30,10 +30,49 @@ type State<KEY> = RwLock<WebServerState<KEY>>;
 /// Convience alias for errors in this crate
 type Error = ServerError;
 
-/// State that tracks proposals and votes the server receives
+/// State that tracks the messages of each chain the server serves, apart from one another
+struct WebServerState<KEY> {
+    /// chain id -> the state of the chain
+    chains: HashMap<u64, ChainState<KEY>>,
+    /// the state read for chains nothing was posted to yet
+    empty: ChainState<KEY>,
+    /// the only chains posted to, or `None` to serve any chain
+    allowed_chains: Option<HashSet<u64>>,
+    /// number of posts so far, to tell which chain was posted to least recently
+    posts: u64,
+    /// shutdown signal
+    shutdown: Option<OneShotReceiver<()>>,
+}
+
+impl<KEY: SignatureKey + 'static> WebServerState<KEY> {
+    /// Create new web server state, serving the chains `chain_ids`, or any chain if empty
+    fn new(chain_ids: &[u64]) -> Self {
+        Self {
+            chains: HashMap::new(),
+            empty: ChainState::new(),
+            allowed_chains: (!chain_ids.is_empty()).then(|| chain_ids.iter().copied().collect()),
+            posts: 0,
+            shutdown: None,
+        }
+    }
+    /// Provide a shutdown signal to the server
+    /// # Panics
+    /// Panics if already shut down
+    #[allow(clippy::panic)]
+    pub fn with_shutdown_signal(mut self, shutdown_listener: Option<OneShotReceiver<()>>) -> Self {
+        assert!(
+            self.shutdown.is_none(),
+            "A shutdown signal is already registered and can not be registered twice"
+        );
+        self.shutdown = shutdown_listener;
+        self
+    }
+}
+
+/// State that tracks proposals and votes the server receives for one chain
 /// Data is stored as a `Vec<u8>` to not incur overhead from deserializing
 // TODO should the view numbers be generic over time?
-struct WebServerState<KEY> {
+struct ChainState<KEY> {
     /// view number -> (secret, proposal)
     proposals: BTreeMap<u64, (String, Vec<u8>)>,
     /// for view sync: view number -> (relay, certificate)
@@ -84,16 +123,16 @@ struct WebServerState<KEY> {
     /// highest transaction index
     num_txns: u64,
 
-    /// shutdown signal
-    shutdown: Option<OneShotReceiver<()>>,
     /// stake table with leader keys
     stake_table: Vec<KEY>,
+    /// the number of posts to the server when this chain was last posted to
+    last_post: u64,
     /// prng for generating endpoint
     _prng: StdRng,
 }
 
-impl<KEY: SignatureKey + 'static> WebServerState<KEY> {
-    /// Create new web server state
+impl<KEY> ChainState<KEY> {
+    /// Create new chain state
     fn new() -> Self {
         Self {
             proposals: BTreeMap::new(),
@@ -104,8 +143,8 @@ impl<KEY: SignatureKey + 'static> WebServerState<KEY> {
             latest_proposal: 0,
             latest_view_sync_certificate: 0,
             oldest_certificate: 0,
-            shutdown: None,
             stake_table: Vec::new(),
+            last_post: 0,
             vote_index: HashMap::new(),
             transactions: HashMap::new(),
             txn_lookup: HashMap::new(),
@@ -130,17 +169,59 @@ impl<KEY: SignatureKey + 'static> WebServerState<KEY> {
             view_sync_certificate_index: HashMap::new(),
         }
     }
-    /// Provide a shutdown signal to the server
-    /// # Panics
-    /// Panics if already shut down
-    #[allow(clippy::panic)]
-    pub fn with_shutdown_signal(mut self, shutdown_listener: Option<OneShotReceiver<()>>) -> Self {
-        assert!(
-            self.shutdown.is_none(),
-            "A shutdown signal is already registered and can not be registered twice"
-        );
-        self.shutdown = shutdown_listener;
-        self
+}
+
+/// Trait giving access to the state of each chain of the `WebServerState`
+pub trait WebServerChains<KEY> {
+    /// The state of one chain
+    type Chain: WebServerDataSource<KEY> + Send + Sync;
+
+    /// The state of the chain `chain_id`, empty if nothing was posted to it
+    fn chain(&self, chain_id: u64) -> &Self::Chain;
+
+    /// The state of the chain `chain_id`, to post to. Without an allowlist of chains, the chain
+    /// posted to least recently is dropped to make room for a new chain once the server serves
+    /// as many chains as it can.
+    /// # Errors
+    /// Error if the server does not serve the chain `chain_id`.
+    fn chain_mut(&mut self, chain_id: u64) -> Result<&mut Self::Chain, Error>;
+}
+
+impl<KEY: SignatureKey> WebServerChains<KEY> for WebServerState<KEY> {
+    type Chain = ChainState<KEY>;
+
+    fn chain(&self, chain_id: u64) -> &Self::Chain {
+        self.chains.get(&chain_id).unwrap_or(&self.empty)
+    }
+
+    fn chain_mut(&mut self, chain_id: u64) -> Result<&mut Self::Chain, Error> {
+        match &self.allowed_chains {
+            Some(allowed) if !allowed.contains(&chain_id) => {
+                return Err(ServerError {
+                    status: StatusCode::Forbidden,
+                    message: format!("Chain {chain_id} is not served by this web server"),
+                });
+            }
+            // the allowlist bounds the chains served
+            Some(_) => {}
+            None => {
+                if !self.chains.contains_key(&chain_id) && self.chains.len() >= MAX_CHAINS {
+                    let least_recent = self
+                        .chains
+                        .iter()
+                        .min_by_key(|(_, chain)| chain.last_post)
+                        .map(|(id, _)| *id);
+                    if let Some(evicted) = least_recent {
+                        info!("Dropping the messages of chain {evicted} to serve chain {chain_id}");
+                        self.chains.remove(&evicted);
+                    }
+                }
+            }
+        }
+        self.posts += 1;
+        let chain = self.chains.entry(chain_id).or_insert_with(ChainState::new);
+        chain.last_post = self.posts;
+        Ok(chain)
     }
 }
 
@@ -262,7 +343,7 @@ pub trait WebServerDataSource<KEY> {
     fn get_vid_certificate(&self, index: u64) -> Result<Option<Vec<Vec<u8>>>, Error>;
 }
 
-impl<KEY: SignatureKey> WebServerDataSource<KEY> for WebServerState<KEY> {
+impl<KEY: SignatureKey> WebServerDataSource<KEY> for ChainState<KEY> {
     fn proposal(&self, view_number: u64) -> Option<(String, Vec<u8>)> {
         self.proposals.get(&view_number).cloned()
     }
@@ -741,11 +822,24 @@ impl<KEY: SignatureKey> WebServerDataSource<KEY> for WebServerState<KEY> {
 }
 
 /// configurability options for the web server
-#[derive(Args, Default)]
+#[derive(Args, Debug, Default)]
 pub struct Options {
     #[arg(long = "web-server-api-path", env = "WEB_SERVER_API_PATH")]
     /// path to API
     pub api_path: Option<PathBuf>,
+    #[arg(
+        long = "web-server-chain-ids",
+        env = "WEB_SERVER_CHAIN_IDS",
+        value_delimiter = ','
+    )]
+    /// the only chains to accept posts for; any chain if empty, keeping the messages of at most
+    /// `MAX_CHAINS` chains
+    pub chain_ids: Vec<u64>,
+}
+
+/// The chain a request is for: the one in its `chain_id` parameter, or chain 0 without it
+fn chain_id(req: &RequestParams) -> Result<u64, Error> {
+    Ok(req.opt_integer_param("chain_id")?.unwrap_or(0))
 }
 
 /// Sets up all API routes
@@ -753,7 +847,7 @@ pub struct Options {
 fn define_api<State, KEY>(options: &Options) -> Result<Api<State, Error>, ApiError>
 where
     State: 'static + Send + Sync + ReadState + WriteState,
-    <State as ReadState>::State: Send + Sync + WebServerDataSource<KEY>,
+    <State as ReadState>::State: Send + Sync + WebServerChains<KEY>,
     KEY: SignatureKey,
 {
     let mut api = match &options.api_path {
@@ -770,35 +864,42 @@ where
     api.get("getproposal", |req, state| {
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
-            state.get_proposal(view_number)
+            state.chain(chain_id(&req)?).get_proposal(view_number)
         }
         .boxed()
     })?
     .get("getviddisperse", |req, state| {
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
-            state.get_vid_disperse(view_number)
+            state.chain(chain_id(&req)?).get_vid_disperse(view_number)
         }
         .boxed()
     })?
-    .get("get_latest_proposal", |_req, state| {
-        async move { state.get_latest_proposal() }.boxed()
+    .get("get_latest_proposal", |req, state| {
+        async move { state.chain(chain_id(&req)?).get_latest_proposal() }.boxed()
     })?
-    .get("get_latest_view_sync_certificate", |_req, state| {
-        async move { state.get_latest_view_sync_certificate() }.boxed()
+    .get("get_latest_view_sync_certificate", |req, state| {
+        async move {
+            state
+                .chain(chain_id(&req)?)
+                .get_latest_view_sync_certificate()
+        }
+        .boxed()
     })?
     .get("getviewsynccertificate", |req, state| {
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let index: u64 = req.integer_param("index")?;
-            state.get_view_sync_certificate(view_number, index)
+            state
+                .chain(chain_id(&req)?)
+                .get_view_sync_certificate(view_number, index)
         }
         .boxed()
     })?
     .get("getcertificate", |req, state| {
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
-            state.get_da_certificate(view_number)
+            state.chain(chain_id(&req)?).get_da_certificate(view_number)
         }
         .boxed()
     })?
@@ -806,7 +907,7 @@ where
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let index: u64 = req.integer_param("index")?;
-            state.get_votes(view_number, index)
+            state.chain(chain_id(&req)?).get_votes(view_number, index)
         }
         .boxed()
     })?
@@ -814,14 +915,16 @@ where
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let index: u64 = req.integer_param("index")?;
-            state.get_view_sync_votes(view_number, index)
+            state
+                .chain(chain_id(&req)?)
+                .get_view_sync_votes(view_number, index)
         }
         .boxed()
     })?
     .get("gettransactions", |req, state| {
         async move {
             let index: u64 = req.integer_param("index")?;
-            state.get_transactions(index)
+            state.chain(chain_id(&req)?).get_transactions(index)
         }
         .boxed()
     })?
@@ -830,7 +933,9 @@ where
             let view_number: u64 = req.integer_param("view_number")?;
             // Using body_bytes because we don't want to deserialize; body_auto or body_json deserializes automatically
             let vote = req.body_bytes();
-            state.post_vote(view_number, vote)
+            state
+                .chain_mut(chain_id(&req)?)?
+                .post_vote(view_number, vote)
         }
         .boxed()
     })?
@@ -839,7 +944,9 @@ where
             let view_number: u64 = req.integer_param("view_number")?;
             // Using body_bytes because we don't want to deserialize; body_auto or body_json deserializes automatically
             let vote = req.body_bytes();
-            state.post_view_sync_vote(view_number, vote)
+            state
+                .chain_mut(chain_id(&req)?)?
+                .post_view_sync_vote(view_number, vote)
         }
         .boxed()
     })?
@@ -847,7 +954,9 @@ where
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let proposal = req.body_bytes();
-            state.post_proposal(view_number, proposal)
+            state
+                .chain_mut(chain_id(&req)?)?
+                .post_proposal(view_number, proposal)
         }
         .boxed()
     })?
@@ -855,7 +964,9 @@ where
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let disperse = req.body_bytes();
-            state.post_vid_disperse(view_number, disperse)
+            state
+                .chain_mut(chain_id(&req)?)?
+                .post_vid_disperse(view_number, disperse)
         }
         .boxed()
     })?
@@ -863,7 +974,9 @@ where
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let proposal = req.body_bytes();
-            state.post_view_sync_certificate(view_number, proposal)
+            state
+                .chain_mut(chain_id(&req)?)?
+                .post_view_sync_certificate(view_number, proposal)
         }
         .boxed()
     })?
@@ -871,14 +984,16 @@ where
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let cert = req.body_bytes();
-            state.post_da_certificate(view_number, cert)
+            state
+                .chain_mut(chain_id(&req)?)?
+                .post_da_certificate(view_number, cert)
         }
         .boxed()
     })?
     .post("posttransaction", |req, state| {
         async move {
             let txns = req.body_bytes();
-            state.post_transaction(txns)
+            state.chain_mut(chain_id(&req)?)?.post_transaction(txns)
         }
         .boxed()
     })?
@@ -886,7 +1001,7 @@ where
         async move {
             //works one key at a time for now
             let key = req.body_bytes();
-            state.post_staketable(key)
+            state.chain_mut(chain_id(&req)?)?.post_staketable(key)
         }
         .boxed()
     })?
@@ -894,7 +1009,9 @@ where
         async move {
             //works one txn at a time for now
             let txn = req.body_bytes();
-            state.post_completed_transaction(txn)
+            state
+                .chain_mut(chain_id(&req)?)?
+                .post_completed_transaction(txn)
         }
         .boxed()
     })?
@@ -902,12 +1019,13 @@ where
         async move {
             let view_number: u64 = req.integer_param("view_number")?;
             let secret: &str = req.string_param("secret")?;
+            let chain = state.chain_mut(chain_id(&req)?)?;
             //if secret is correct and view_number->proposal is empty, proposal is valid
-            if let Some(prop) = state.proposal(view_number) {
+            if let Some(prop) = chain.proposal(view_number) {
                 if prop.1.is_empty() {
                     if prop.0 == secret {
                         let proposal = req.body_bytes();
-                        state.post_secret_proposal(view_number, proposal)
+                        chain.post_secret_proposal(view_number, proposal)
                     } else {
                         Err(ServerError {
                             status: StatusCode::BadRequest,
@@ -944,10 +1062,22 @@ pub async fn run_web_server<KEY: SignatureKey + 'static>(
     shutdown_listener: Option<OneShotReceiver<()>>,
     url: Url,
 ) -> io::Result<()> {
-    let options = Options::default();
+    run_web_server_with_options::<KEY>(shutdown_listener, url, Options::default()).await
+}
 
+/// run the web server with `options`
+/// # Errors
+/// If the server cannot be served at `url`
+/// # Panics
+/// on errors creating or registering the tide disco api
+pub async fn run_web_server_with_options<KEY: SignatureKey + 'static>(
+    shutdown_listener: Option<OneShotReceiver<()>>,
+    url: Url,
+    options: Options,
+) -> io::Result<()> {
     let web_api = define_api(&options).unwrap();
-    let state = State::new(WebServerState::new().with_shutdown_signal(shutdown_listener));
+    let state =
+        State::new(WebServerState::new(&options.chain_ids).with_shutdown_signal(shutdown_listener));
     let mut app = App::<State<KEY>, Error>::with_state(state);
 
     app.register_module("api", web_api).unwrap();
@@ -956,3 +1086,55 @@ pub async fn run_web_server<KEY: SignatureKey + 'static>(
 
     app_future.await
 }
+
+#[cfg(test)]
+mod test {
+    use super::{WebServerChains, WebServerDataSource, WebServerState};
+    use crate::config::MAX_CHAINS;
+    use hotshot_types::signature_key::BLSPubKey;
+    use tide_disco::StatusCode;
+
+    #[test]
+    fn chains_outside_the_allowlist_are_refused() {
+        let mut state = WebServerState::<BLSPubKey>::new(&[1, 2]);
+        state
+            .chain_mut(1)
+            .unwrap()
+            .post_transaction(vec![1])
+            .unwrap();
+        let refused = state.chain_mut(3).err().expect("chain 3 was served");
+        assert_eq!(refused.status, StatusCode::Forbidden);
+        assert!(state.chains.contains_key(&1));
+        assert!(!state.chains.contains_key(&3));
+    }
+
+    #[test]
+    fn the_chain_posted_to_least_recently_is_dropped_for_a_new_one() {
+        let mut state = WebServerState::<BLSPubKey>::new(&[]);
+        for chain_id in 0..MAX_CHAINS as u64 {
+            state
+                .chain_mut(chain_id)
+                .unwrap()
+                .post_transaction(vec![1])
+                .unwrap();
+        }
+        // chain 0 is still in use, so chain 1 is the least recent
+        state
+            .chain_mut(0)
+            .unwrap()
+            .post_transaction(vec![2])
+            .unwrap();
+        state.chain_mut(MAX_CHAINS as u64).unwrap();
+        assert_eq!(state.chains.len(), MAX_CHAINS);
+        assert!(state.chains.contains_key(&0));
+        assert!(!state.chains.contains_key(&1));
+        assert_eq!(
+            state
+                .chain(0)
+                .get_transactions(0)
+                .unwrap()
+                .map(|(_, txns)| txns),
+            Some(vec![vec![1]])
+        );
+    }
+}